@@ -0,0 +1,188 @@
+use anyhow::{Context, Result, bail};
+use petalsonic::audio_data::PetalSonicAudioData;
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// FFT size of the spectral comparison
+const FFT_SIZE: usize = 2048;
+/// Lowest edge of the octave bands in Hz
+const LOWEST_BAND_HZ: f32 = 31.25;
+/// Bands quieter than this in the reference are not compared (dB)
+const BAND_FLOOR_DB: f32 = -90.0;
+
+/// How far a render may drift from its reference
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    /// Largest per-channel RMS difference in dB
+    pub rms_db: f32,
+    /// Largest per-channel octave band level difference in dB
+    pub band_db: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            rms_db: 0.5,
+            band_db: 1.5,
+        }
+    }
+}
+
+/// Differences found between a render and its reference
+#[derive(Debug, Default)]
+pub struct Comparison {
+    /// Largest per-channel RMS difference in dB
+    pub rms_db: f32,
+    /// Largest per-channel octave band level difference in dB
+    pub band_db: f32,
+}
+
+impl Comparison {
+    pub fn passes(&self, tolerance: &Tolerance) -> bool {
+        self.rms_db <= tolerance.rms_db && self.band_db <= tolerance.band_db
+    }
+}
+
+/// Compare interleaved samples against a reference WAV file
+///
+/// Channel count, sample rate and length must match exactly; the levels are compared
+/// per channel, overall (RMS) and per octave band, so a swapped left/right image, a gain
+/// change or a resampling artifact shows up while dither-level noise does not.
+pub fn compare_with_reference(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    reference: &Path,
+) -> Result<Comparison> {
+    let reference_data = PetalSonicAudioData::from_path(&reference.to_string_lossy())
+        .with_context(|| format!("Failed to load reference {}", reference.display()))?;
+    if reference_data.channels() != channels || reference_data.sample_rate() != sample_rate {
+        bail!(
+            "Reference is {} channels at {} Hz, render is {} channels at {} Hz",
+            reference_data.channels(),
+            reference_data.sample_rate(),
+            channels,
+            sample_rate
+        );
+    }
+    if reference_data.len() != samples.len() {
+        bail!(
+            "Reference has {} frames, render has {}",
+            reference_data.total_frames(),
+            samples.len() / channels as usize
+        );
+    }
+
+    let mut comparison = Comparison::default();
+    for channel in 0..channels as usize {
+        let rendered: Vec<f32> = samples
+            .iter()
+            .skip(channel)
+            .step_by(channels as usize)
+            .copied()
+            .collect();
+        let expected = reference_data.channel_samples(channel)?;
+
+        let rms_diff = (to_db(rms(&rendered)) - to_db(rms(&expected))).abs();
+        comparison.rms_db = comparison.rms_db.max(rms_diff);
+
+        let rendered_bands = band_levels(&rendered, sample_rate);
+        let expected_bands = band_levels(&expected, sample_rate);
+        for (rendered, expected) in rendered_bands.iter().zip(&expected_bands) {
+            if *expected > BAND_FLOOR_DB {
+                comparison.band_db = comparison.band_db.max((rendered - expected).abs());
+            }
+        }
+    }
+    Ok(comparison)
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn to_db(value: f32) -> f32 {
+    20.0 * value.max(1e-9).log10()
+}
+
+/// Average level of each octave band in dB, from Hann windowed FFT frames
+fn band_levels(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let bin_hz = sample_rate as f32 / FFT_SIZE as f32;
+    let mut band_edges = vec![LOWEST_BAND_HZ];
+    while band_edges
+        .last()
+        .is_some_and(|edge| edge * 2.0 < sample_rate as f32 / 2.0)
+    {
+        band_edges.push(band_edges[band_edges.len() - 1] * 2.0);
+    }
+    let mut energies = vec![0.0f32; band_edges.len() - 1];
+
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+        .collect();
+    let mut frames = 0;
+    let mut start = 0;
+    while start < samples.len() {
+        let mut spectrum: Vec<(f32, f32)> = (0..FFT_SIZE)
+            .map(|i| {
+                (
+                    samples.get(start + i).copied().unwrap_or(0.0) * window[i],
+                    0.0,
+                )
+            })
+            .collect();
+        fft(&mut spectrum);
+        for (bin, (re, im)) in spectrum.iter().enumerate().take(FFT_SIZE / 2) {
+            let frequency = bin as f32 * bin_hz;
+            if let Some(band) = band_edges
+                .windows(2)
+                .position(|edge| frequency >= edge[0] && frequency < edge[1])
+            {
+                energies[band] += re * re + im * im;
+            }
+        }
+        frames += 1;
+        start += FFT_SIZE / 2;
+    }
+
+    energies
+        .iter()
+        .map(|energy| 10.0 * (energy / frames.max(1) as f32).max(1e-18).log10())
+        .collect()
+}
+
+/// In-place radix-2 FFT of (re, im) pairs; the length must be a power of two
+fn fft(data: &mut [(f32, f32)]) {
+    let n = data.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_re, w_im) = ((angle * k as f32).cos(), (angle * k as f32).sin());
+                let (a_re, a_im) = data[start + k];
+                let (b_re, b_im) = data[start + k + len / 2];
+                let (t_re, t_im) = (b_re * w_re - b_im * w_im, b_re * w_im + b_im * w_re);
+                data[start + k] = (a_re + t_re, a_im + t_im);
+                data[start + k + len / 2] = (a_re - t_re, a_im - t_im);
+            }
+        }
+        len <<= 1;
+    }
+}
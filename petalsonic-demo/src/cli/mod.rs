@@ -0,0 +1,453 @@
+mod compare;
+mod scenario;
+
+use anyhow::{Context, Result, bail};
+use petalsonic::{
+    PetalSonicEvent, SourceConfig,
+    audio_data::PetalSonicAudioData,
+    config::PetalSonicWorldDesc,
+    engine::PetalSonicEngine,
+    math::{Pose, Quat, Vec3},
+    playback::LoopMode,
+    world::{PetalSonicWorld, SourceId},
+};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use compare::{Tolerance, compare_with_reference};
+use scenario::{Action, Scenario, Side};
+
+const USAGE: &str = "Usage:
+  petalsonic-demo --scenario <file> [--device] [--output <wav>] [--reference <wav> [--bless]]
+  petalsonic-demo --suite <dir> [--bless]
+
+Runs a scenario file against the engine. Without --device the scenario is rendered
+offline, faster than real time; with --device it plays on the default output device.
+--output overrides the scenario's output WAV file. --reference compares the offline
+render against a reference render, which --bless (re)writes instead.
+
+--suite renders every *.scenario file of a directory offline and compares it against the
+reference render next to it (<name>.wav), failing if any render drifted. Scenarios without
+a reference are reported as skipped; render their references with --bless.";
+
+/// Options of a scenario run
+struct CliOptions {
+    scenario: Option<PathBuf>,
+    suite: Option<PathBuf>,
+    device: bool,
+    output: Option<PathBuf>,
+    reference: Option<PathBuf>,
+    bless: bool,
+}
+
+impl CliOptions {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Self {
+            scenario: None,
+            suite: None,
+            device: false,
+            output: None,
+            reference: None,
+            bless: false,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scenario" => options.scenario = args.next().map(PathBuf::from),
+                "--suite" => options.suite = args.next().map(PathBuf::from),
+                "--output" => options.output = args.next().map(PathBuf::from),
+                "--reference" => options.reference = args.next().map(PathBuf::from),
+                "--device" => options.device = true,
+                "--bless" => options.bless = true,
+                "--help" | "-h" => bail!("{}", USAGE),
+                other => bail!("Unknown argument '{}'\n\n{}", other, USAGE),
+            }
+        }
+        if options.scenario.is_none() == options.suite.is_none() {
+            bail!("Expected either --scenario or --suite\n\n{}", USAGE);
+        }
+        if options.device && (options.reference.is_some() || options.suite.is_some()) {
+            bail!(
+                "References are compared against offline renders only\n\n{}",
+                USAGE
+            );
+        }
+        Ok(options)
+    }
+}
+
+/// Run the scenario named by the command line arguments
+pub fn run_cli_tests(args: &[String]) -> Result<()> {
+    env_logger::Builder::from_default_env()
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let options = CliOptions::parse(args)?;
+    if let Some(dir) = &options.suite {
+        return run_suite(dir, options.bless);
+    }
+    let Some(scenario_path) = &options.scenario else {
+        return Ok(());
+    };
+    let scenario = Scenario::load(scenario_path)?;
+    let output = options.output.or_else(|| scenario.output.clone());
+
+    let (world, mut engine) = create_engine()?;
+    let mut runner = ScenarioRunner::new(&scenario, world)?;
+    if options.device {
+        return runner.run_on_device(&mut engine, output.as_deref());
+    }
+
+    let render = runner.run_offline(&mut engine)?;
+    if let Some(output) = &output {
+        render.write_wav(output)?;
+        log::info!("CLI: Wrote {}", output.display());
+    }
+    check_expectations(&render, &scenario)?;
+    if let Some(reference) = &options.reference
+        && check_reference(&render, reference, options.bless)? == ReferenceCheck::Missing
+    {
+        log::warn!(
+            "CLI: SKIP reference comparison, {} does not exist; render it with --bless",
+            reference.display()
+        );
+    }
+    Ok(())
+}
+
+/// Render every scenario of `dir` and compare it against its reference render
+fn run_suite(dir: &Path, bless: bool) -> Result<()> {
+    let mut scenarios: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "scenario"))
+        .collect();
+    scenarios.sort();
+    if scenarios.is_empty() {
+        bail!("No *.scenario files in {}", dir.display());
+    }
+
+    let mut failures = Vec::new();
+    let mut skipped = Vec::new();
+    for path in &scenarios {
+        let scenario = Scenario::load(path)?;
+        let (world, mut engine) = create_engine()?;
+        let render = ScenarioRunner::new(&scenario, world)?.run_offline(&mut engine)?;
+        let result = check_expectations(&render, &scenario)
+            .and_then(|()| check_reference(&render, &path.with_extension("wav"), bless));
+        match result {
+            Ok(ReferenceCheck::Missing) => {
+                log::warn!("CLI: SKIP {}: no reference render", path.display());
+                skipped.push(path.display().to_string());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("CLI: {}: {:#}", path.display(), e);
+                failures.push(path.display().to_string());
+            }
+        }
+    }
+
+    let passed = scenarios.len() - failures.len() - skipped.len();
+    log::info!(
+        "CLI: {} passed, {} failed, {} skipped of {} scenarios",
+        passed,
+        failures.len(),
+        skipped.len(),
+        scenarios.len()
+    );
+    if !skipped.is_empty() {
+        log::warn!(
+            "CLI: Skipped without a reference: {}; render them with --bless",
+            skipped.join(", ")
+        );
+    }
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} scenarios failed: {}",
+            failures.len(),
+            scenarios.len(),
+            failures.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Check the channel balance a scenario expects of its render
+fn check_expectations(render: &Render, scenario: &Scenario) -> Result<()> {
+    if scenario.expectations.is_empty() {
+        return Ok(());
+    }
+    if render.channels < 2 {
+        bail!("Expectations need a stereo render");
+    }
+
+    let channels = render.channels as usize;
+    let frame_at = |seconds: f64| (seconds * render.sample_rate as f64).round() as usize;
+    for expectation in &scenario.expectations {
+        let frames = render.samples.len() / channels;
+        let range = frame_at(expectation.from).min(frames)..frame_at(expectation.to).min(frames);
+        let energy = |channel: usize| -> f32 {
+            render.samples[range.start * channels..range.end * channels]
+                .chunks_exact(channels)
+                .map(|frame| frame[channel] * frame[channel])
+                .sum()
+        };
+        let (left, right) = (energy(0), energy(1));
+        let (louder, quieter) = match expectation.louder {
+            Side::Left => (left, right),
+            Side::Right => (right, left),
+        };
+        // At least 3 dB apart, so a centered image does not pass by chance
+        if louder <= quieter * 2.0 || louder == 0.0 {
+            bail!(
+                "Expected the {:?} channel louder from {} s to {} s, energy left {:.3}, right {:.3}",
+                expectation.louder,
+                expectation.from,
+                expectation.to,
+                left,
+                right
+            );
+        }
+    }
+    log::info!(
+        "CLI: {} channel balance expectations met",
+        scenario.expectations.len()
+    );
+    Ok(())
+}
+
+/// Outcome of comparing a render against its reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReferenceCheck {
+    /// The render is within tolerance of the reference
+    Matched,
+    /// The reference was (re)written from the render
+    Blessed,
+    /// There is no reference yet, so nothing was compared
+    Missing,
+}
+
+/// Compare a render against its reference, or replace the reference when blessing
+fn check_reference(render: &Render, reference: &Path, bless: bool) -> Result<ReferenceCheck> {
+    if bless {
+        render.write_wav(reference)?;
+        log::info!("CLI: Blessed {}", reference.display());
+        return Ok(ReferenceCheck::Blessed);
+    }
+    if !reference.exists() {
+        return Ok(ReferenceCheck::Missing);
+    }
+
+    let tolerance = Tolerance::default();
+    let comparison = compare_with_reference(
+        &render.samples,
+        render.channels,
+        render.sample_rate,
+        reference,
+    )?;
+    if !comparison.passes(&tolerance) {
+        bail!(
+            "Render drifted from {}: RMS {:.2} dB (max {:.2}), bands {:.2} dB (max {:.2})",
+            reference.display(),
+            comparison.rms_db,
+            tolerance.rms_db,
+            comparison.band_db,
+            tolerance.band_db
+        );
+    }
+    log::info!(
+        "CLI: Matches {} (RMS {:.2} dB, bands {:.2} dB)",
+        reference.display(),
+        comparison.rms_db,
+        comparison.band_db
+    );
+    Ok(ReferenceCheck::Matched)
+}
+
+/// Create a world and engine with the demo's configuration
+fn create_engine() -> Result<(Arc<PetalSonicWorld>, PetalSonicEngine)> {
+    let world_desc = PetalSonicWorldDesc {
+        sample_rate: 48000,
+        block_size: 1024,
+        hrtf_path: Some("petalsonic-demo/asset/hrtf/hrtf_b_nh172.sofa".to_string()),
+        ..Default::default()
+    };
+    let world = Arc::new(PetalSonicWorld::new(world_desc.clone())?);
+    world.set_listener_pose(Pose::new(Vec3::ZERO, Quat::IDENTITY));
+    let engine = PetalSonicEngine::new(world_desc, world.clone())?;
+    Ok((world, engine))
+}
+
+/// Interleaved output of an offline run
+struct Render {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Render {
+    fn write_wav(&self, path: &Path) -> Result<()> {
+        write_wav(path, &self.samples, self.channels, self.sample_rate)
+    }
+}
+
+/// Applies the events of a scenario to a world
+struct ScenarioRunner<'a> {
+    scenario: &'a Scenario,
+    world: Arc<PetalSonicWorld>,
+    source_ids: HashMap<String, SourceId>,
+}
+
+impl<'a> ScenarioRunner<'a> {
+    fn new(scenario: &'a Scenario, world: Arc<PetalSonicWorld>) -> Result<Self> {
+        let mut source_ids = HashMap::new();
+        for source in &scenario.sources {
+            let audio_data = PetalSonicAudioData::from_path(&source.path.to_string_lossy())
+                .with_context(|| format!("Failed to load {}", source.path.display()))?;
+            let source_id = world.register_audio(
+                audio_data,
+                SourceConfig::spatial_with_volume(source.position, 1.0),
+            )?;
+            log::info!("CLI: Registered '{}' as source {}", source.name, source_id);
+            source_ids.insert(source.name.clone(), source_id);
+        }
+        Ok(Self {
+            scenario,
+            world,
+            source_ids,
+        })
+    }
+
+    fn apply(&self, action: &Action) -> Result<()> {
+        match action {
+            Action::Play(name) => {
+                let looping = self.scenario.source(name).is_some_and(|s| s.looping);
+                let loop_mode = if looping {
+                    LoopMode::Infinite
+                } else {
+                    LoopMode::Once
+                };
+                self.world.play(self.source_ids[name], loop_mode)?;
+            }
+            Action::Stop(name) => self.world.stop(self.source_ids[name])?,
+            Action::Move(name, position) => self.world.update_source_config(
+                self.source_ids[name],
+                SourceConfig::spatial_with_volume(*position, 1.0),
+            )?,
+            Action::Listener(position, yaw) => self.world.set_listener_pose(Pose::new(
+                *position,
+                Quat::from_rotation_y(yaw.to_radians()),
+            )),
+        }
+        log::info!("CLI: {:?}", action);
+        Ok(())
+    }
+
+    /// Render the scenario without a device, faster than real time
+    fn run_offline(&mut self, engine: &mut PetalSonicEngine) -> Result<Render> {
+        let sample_rate = engine.config().sample_rate;
+        let frame_at = |seconds: f64| (seconds * sample_rate as f64).round() as usize;
+        let total_frames = frame_at(self.scenario.duration);
+
+        let started = Instant::now();
+        let mut samples = Vec::new();
+        let mut rendered = 0;
+        for event in &self.scenario.events {
+            let frame = frame_at(event.time).min(total_frames);
+            samples.extend(engine.render_offline(frame - rendered.min(frame))?);
+            rendered = rendered.max(frame);
+            self.apply(&event.action)?;
+        }
+        samples.extend(engine.render_offline(total_frames - rendered)?);
+        log_events(engine);
+
+        log::info!(
+            "CLI: Rendered {:.2} s offline in {:.2} s",
+            self.scenario.duration,
+            started.elapsed().as_secs_f64()
+        );
+        Ok(Render {
+            samples,
+            channels: engine.config().channels,
+            sample_rate,
+        })
+    }
+
+    /// Play the scenario on the default output device in real time
+    fn run_on_device(
+        &mut self,
+        engine: &mut PetalSonicEngine,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        engine.start()?;
+        // The master stem of a recording is the device mix; it is moved to `output` below
+        let recording_dir = output.map(|path| path.with_extension("stems"));
+        if let Some(dir) = &recording_dir {
+            engine.record_stems(&[], dir)?;
+        }
+
+        let started = Instant::now();
+        for event in &self.scenario.events {
+            sleep_until(started + Duration::from_secs_f64(event.time));
+            self.apply(&event.action)?;
+            log_events(engine);
+        }
+        sleep_until(started + Duration::from_secs_f64(self.scenario.duration));
+        log_events(engine);
+
+        engine.stop_stem_recording()?;
+        engine.stop()?;
+        if let (Some(dir), Some(output)) = (recording_dir, output) {
+            std::fs::rename(dir.join("master.wav"), output)?;
+            std::fs::remove_dir_all(&dir)?;
+            log::info!("CLI: Wrote {}", output.display());
+        }
+        Ok(())
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
+
+fn log_events(engine: &PetalSonicEngine) {
+    for event in engine.poll_events() {
+        if let PetalSonicEvent::SourceCompleted { source_id } = event {
+            log::info!("CLI: Source {} completed", source_id);
+        }
+    }
+}
+
+/// Write interleaved samples to a 32-bit float WAV file
+fn write_wav(path: &Path, samples: &[f32], channels: u16, sample_rate: u32) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let block_align = channels * 4;
+    let data_bytes = (samples.len() * 4) as u32;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_bytes).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&3u16.to_le_bytes())?; // IEEE float
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&32u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_bytes.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
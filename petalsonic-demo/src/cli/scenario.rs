@@ -0,0 +1,192 @@
+use anyhow::{Context, Result, anyhow, bail};
+use petalsonic::math::Vec3;
+use std::path::{Path, PathBuf};
+
+/// A source declared by a scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioSource {
+    pub name: String,
+    pub path: PathBuf,
+    pub position: Vec3,
+    pub looping: bool,
+}
+
+/// What happens at a scenario event
+#[derive(Debug, Clone)]
+pub enum Action {
+    Play(String),
+    Stop(String),
+    Move(String, Vec3),
+    /// Listener position and yaw in degrees, counterclockwise seen from above
+    Listener(Vec3, f32),
+}
+
+/// Output channel of a stereo render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A channel expected to be louder than the other over a time range, in seconds
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    pub from: f64,
+    pub to: f64,
+    pub louder: Side,
+}
+
+/// An action at a time in seconds from the start of the scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioEvent {
+    pub time: f64,
+    pub action: Action,
+}
+
+/// A scripted run of the engine, parsed from a scenario file
+///
+/// Scenario files are line based; `#` starts a comment. Times are in seconds, positions
+/// in world units, and file paths relative to the scenario file:
+///
+/// ```text
+/// duration 6
+/// output footsteps.wav
+/// source steps sound/footsteps.wav 4 0 -2 loop
+/// play 0 steps
+/// move 2 steps 0 0 -2
+/// listener 3 0 0 1 90
+/// stop 5.5 steps
+/// expect 0.5 1.5 right
+/// ```
+///
+/// `expect` checks an offline render: the named channel must be louder than the other one
+/// between the two times.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// Length of the run in seconds
+    pub duration: f64,
+    /// WAV file receiving the rendered output
+    pub output: Option<PathBuf>,
+    pub sources: Vec<ScenarioSource>,
+    /// Events sorted by time
+    pub events: Vec<ScenarioEvent>,
+    pub expectations: Vec<Expectation>,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::parse(&text, base).with_context(|| format!("Invalid scenario {}", path.display()))
+    }
+
+    fn parse(text: &str, base: &Path) -> Result<Self> {
+        let mut scenario = Scenario {
+            duration: 0.0,
+            output: None,
+            sources: Vec::new(),
+            events: Vec::new(),
+            expectations: Vec::new(),
+        };
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            scenario
+                .parse_line(line, base)
+                .with_context(|| format!("line {}: {}", index + 1, line))?;
+        }
+
+        if scenario.duration <= 0.0 {
+            scenario.duration = scenario.events.iter().map(|e| e.time).fold(0.0, f64::max);
+        }
+        for event in &scenario.events {
+            if let Action::Play(name) | Action::Stop(name) | Action::Move(name, _) = &event.action
+                && scenario.source(name).is_none()
+            {
+                bail!("Unknown source '{}'", name);
+            }
+        }
+        scenario.events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(scenario)
+    }
+
+    fn parse_line(&mut self, line: &str, base: &Path) -> Result<()> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["duration", seconds] => self.duration = parse_number(seconds)?,
+            ["output", path] => self.output = Some(base.join(path)),
+            ["source", name, path, x, y, z, rest @ ..] => {
+                let looping = match rest {
+                    [] => false,
+                    ["loop"] => true,
+                    _ => bail!("Expected 'loop' or nothing after the position"),
+                };
+                self.sources.push(ScenarioSource {
+                    name: name.to_string(),
+                    path: base.join(path),
+                    position: parse_position(x, y, z)?,
+                    looping,
+                });
+            }
+            ["play", time, name] => self.push(time, Action::Play(name.to_string()))?,
+            ["stop", time, name] => self.push(time, Action::Stop(name.to_string()))?,
+            ["move", time, name, x, y, z] => {
+                let position = parse_position(x, y, z)?;
+                self.push(time, Action::Move(name.to_string(), position))?;
+            }
+            ["listener", time, x, y, z, rest @ ..] => {
+                let position = parse_position(x, y, z)?;
+                let yaw = match rest {
+                    [] => 0.0,
+                    [yaw] => parse_number(yaw)? as f32,
+                    _ => bail!("Expected a yaw or nothing after the position"),
+                };
+                self.push(time, Action::Listener(position, yaw))?;
+            }
+            ["expect", from, to, side] => {
+                let louder = match *side {
+                    "left" => Side::Left,
+                    "right" => Side::Right,
+                    _ => bail!("Expected 'left' or 'right', found '{}'", side),
+                };
+                let (from, to) = (parse_number(from)?, parse_number(to)?);
+                if from >= to {
+                    bail!("Empty time range {} to {}", from, to);
+                }
+                self.expectations.push(Expectation { from, to, louder });
+            }
+            _ => bail!("Unrecognized directive"),
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, time: &str, action: Action) -> Result<()> {
+        let time = parse_number(time)?;
+        if time < 0.0 {
+            bail!("Negative time {}", time);
+        }
+        self.events.push(ScenarioEvent { time, action });
+        Ok(())
+    }
+
+    pub fn source(&self, name: &str) -> Option<&ScenarioSource> {
+        self.sources.iter().find(|source| source.name == name)
+    }
+}
+
+fn parse_number(word: &str) -> Result<f64> {
+    word.parse()
+        .map_err(|_| anyhow!("Expected a number, found '{}'", word))
+}
+
+fn parse_position(x: &str, y: &str, z: &str) -> Result<Vec3> {
+    Ok(Vec3::new(
+        parse_number(x)? as f32,
+        parse_number(y)? as f32,
+        parse_number(z)? as f32,
+    ))
+}
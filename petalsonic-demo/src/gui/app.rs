@@ -11,7 +11,7 @@ use petalsonic::{
 use std::collections::VecDeque;
 use std::sync::Arc;
 
-use super::profiling;
+use super::{inspector, profiling};
 
 #[derive(Clone)]
 struct AudioSource {
@@ -533,6 +533,12 @@ impl eframe::App for SpatialAudioDemo {
                             &self.timing_history,
                             self.max_frame_time_us,
                         );
+
+                        ui.add_space(20.0);
+                        ui.separator();
+
+                        // Render state reported by the engine
+                        inspector::draw_inspector_widget(ui, &self.engine.debug_snapshot());
                     });
             });
 
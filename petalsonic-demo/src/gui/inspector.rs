@@ -0,0 +1,110 @@
+use egui::Color32;
+use petalsonic::{DebugSnapshot, PlayState};
+
+/// Lowest level shown on the meters
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Map a linear peak to a 0-1 meter fill on a dB scale
+fn meter_fill(peak: f32) -> f32 {
+    let db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+    ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+/// Draw a horizontal level meter with the peak in dBFS
+fn draw_meter(ui: &mut egui::Ui, label: &str, peak: f32) {
+    let db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+    let color = if peak >= 1.0 {
+        Color32::RED
+    } else if db > -6.0 {
+        Color32::YELLOW
+    } else {
+        Color32::from_rgb(80, 200, 120)
+    };
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(
+            egui::ProgressBar::new(meter_fill(peak))
+                .desired_width(160.0)
+                .fill(color)
+                .text(if db <= METER_FLOOR_DB {
+                    "-inf dB".to_string()
+                } else {
+                    format!("{:.1} dB", db)
+                }),
+        );
+    });
+}
+
+/// Draw an inspector of the engine's render state
+///
+/// Everything shown comes from [`petalsonic::PetalSonicEngine::debug_snapshot`], so it
+/// reflects what the render thread actually did, including sources the demo did not start.
+///
+/// # Arguments
+/// * `ui` - The egui UI context
+/// * `snapshot` - Render state of the current frame
+pub fn draw_inspector_widget(ui: &mut egui::Ui, snapshot: &DebugSnapshot) {
+    ui.collapsing("Audio Inspector", |ui| {
+        ui.heading("Output");
+        for (channel, peak) in snapshot.output_peaks.iter().enumerate() {
+            draw_meter(ui, &format!("Ch {}", channel), *peak);
+        }
+        let mut buses: Vec<_> = snapshot.bus_levels.iter().collect();
+        buses.sort_by(|a, b| a.0.cmp(b.0));
+        for (bus, level) in buses {
+            draw_meter(ui, bus, *level);
+        }
+
+        ui.add_space(10.0);
+        ui.heading(format!("Instances ({})", snapshot.sources.len()));
+        if snapshot.sources.is_empty() {
+            ui.label("No active instances");
+            return;
+        }
+
+        let mut sources: Vec<_> = snapshot.sources.iter().collect();
+        sources.sort_by_key(|source| source.source_id.to_string());
+        egui::ScrollArea::vertical()
+            .id_salt("inspector_instances")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for source in sources {
+                    ui.group(|ui| {
+                        let state = match source.playback.play_state {
+                            PlayState::Playing => "playing",
+                            PlayState::Paused => "paused",
+                            PlayState::Stopped => "stopped",
+                        };
+                        ui.label(format!("{} ({})", source.source_id, state));
+                        ui.label(format!(
+                            "  Frame: {} / {} ({:.2} s / {:.2} s)",
+                            source.playback.current_frame,
+                            source.playback.total_frames,
+                            source.playback.current_time,
+                            source.playback.total_time
+                        ));
+                        if let Some(position) = source.position {
+                            ui.label(format!(
+                                "  Pos: ({:.1}, {:.1}, {:.1})",
+                                position.x, position.y, position.z
+                            ));
+                        }
+                        let attenuation = source
+                            .distance_attenuation
+                            .map_or("-".to_string(), |gain| format!("{:.3}", gain));
+                        let occlusion = source
+                            .occlusion
+                            .map_or("-".to_string(), |occlusion| format!("{:.2}", occlusion));
+                        ui.label(format!(
+                            "  Volume: {:.2}  Attenuation: {}  Occlusion: {}",
+                            source.volume, attenuation, occlusion
+                        ));
+                        if let Some(bus) = &source.bus {
+                            ui.label(format!("  Bus: {}", bus));
+                        }
+                        draw_meter(ui, "  Level", source.level);
+                    });
+                }
+            });
+    });
+}
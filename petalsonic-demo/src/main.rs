@@ -1,6 +1,12 @@
+mod cli;
 mod gui;
 
-fn main() -> Result<(), eframe::Error> {
-    // Run GUI demo
-    gui::run()
+fn main() -> anyhow::Result<()> {
+    // Run a scenario when arguments are given, the GUI demo otherwise
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run_cli_tests(&args);
+    }
+
+    gui::run().map_err(|e| anyhow::anyhow!("{}", e))
 }
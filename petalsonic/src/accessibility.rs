@@ -0,0 +1,187 @@
+//! Accessibility options of the output.
+//!
+//! Players with hearing in one ear only miss whatever the mix places on the other side,
+//! including spatial cues such as an enemy approaching from the left. With mono output
+//! enabled (see [`PetalSonicEngine::set_mono_output`](crate::PetalSonicEngine::set_mono_output)
+//! or [`PetalSonicWorldDesc::mono_output`](crate::PetalSonicWorldDesc::mono_output)), all
+//! output channels play the same downmix, so every sound is heard on either side:
+//!
+//! ```ignore
+//! engine.set_mono_output(true);
+//! ```
+//!
+//! The downmix is taken after spatial decoding and scaled by 1/√N for N channels (-3 dB for
+//! stereo), which keeps the loudness of typical, partly decorrelated content close to the
+//! original and keeps centered content from clipping as often as a plain sum would.
+//! Toggling crossfades over one block. Like the master volume, it applies to the device
+//! output only; the output tap, stems and loudness metering keep seeing the mix.
+//!
+//! Players with swapped headphone sides or uneven hearing can also swap the left and right
+//! channel and shift the balance between them:
+//!
+//! ```ignore
+//! engine.set_swap_channels(true);
+//! engine.set_balance(-0.3); // right side 30% quieter
+//! ```
+//!
+//! Both apply to the first two device channels in the audio callback, after the
+//! conversion to the device's channel layout, so they act on what the device plays
+//! whatever the world's layout. Balance attenuates the opposite side and keeps the
+//! favored side at full level. Changes are ramped over one callback.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Accessibility options shared between the engine (main thread) and the render thread
+pub(crate) struct SharedAccessibility {
+    mono: AtomicBool,
+    swap_channels: AtomicBool,
+    balance: AtomicU32,
+}
+
+impl SharedAccessibility {
+    pub fn new(mono: bool, swap_channels: bool, balance: f32) -> Self {
+        Self {
+            mono: AtomicBool::new(mono),
+            swap_channels: AtomicBool::new(swap_channels),
+            balance: AtomicU32::new(balance.clamp(-1.0, 1.0).to_bits()),
+        }
+    }
+
+    pub fn mono(&self) -> bool {
+        self.mono.load(Ordering::Relaxed)
+    }
+
+    pub fn set_mono(&self, enabled: bool) {
+        self.mono.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn swap_channels(&self) -> bool {
+        self.swap_channels.load(Ordering::Relaxed)
+    }
+
+    pub fn set_swap_channels(&self, enabled: bool) {
+        self.swap_channels.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn balance(&self) -> f32 {
+        f32::from_bits(self.balance.load(Ordering::Relaxed))
+    }
+
+    pub fn set_balance(&self, balance: f32) {
+        self.balance
+            .store(balance.clamp(-1.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Render-side downmix applying the accessibility options to interleaved audio
+pub(crate) struct AccessibilityMix {
+    /// Share of the mono downmix in the output at the end of the last block, from 0 to 1
+    mono: f32,
+}
+
+impl AccessibilityMix {
+    pub fn new(shared: &SharedAccessibility) -> Self {
+        Self {
+            mono: if shared.mono() { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Apply the options to interleaved samples in place, ramping changes over the block
+    pub fn process(&mut self, shared: &SharedAccessibility, samples: &mut [f32], channels: usize) {
+        let target = if shared.mono() { 1.0 } else { 0.0 };
+        if channels < 2 || (self.mono == 0.0 && target == 0.0) {
+            self.mono = target;
+            return;
+        }
+
+        let downmix_gain = 1.0 / (channels as f32).sqrt();
+        let frames = samples.len() / channels;
+        let step = (target - self.mono) / frames.max(1) as f32;
+        for frame in samples.chunks_exact_mut(channels) {
+            self.mono += step;
+            let downmix = frame.iter().sum::<f32>() * downmix_gain;
+            for sample in frame.iter_mut() {
+                *sample += (downmix - *sample) * self.mono;
+            }
+        }
+        self.mono = target;
+    }
+}
+
+/// Callback-side channel swap and balance of the first two device channels
+pub(crate) struct ChannelBalance {
+    /// Device frame to fill before calling [`Self::process`]
+    frame: Vec<f32>,
+    /// Share of the opposite channel played on each side, from 0 to 1 (swapped)
+    swap: f32,
+    swap_target: f32,
+    swap_step: f32,
+    /// Gains of the left and right channel
+    gains: [f32; 2],
+    gain_targets: [f32; 2],
+    gain_steps: [f32; 2],
+}
+
+impl ChannelBalance {
+    pub fn new(shared: &SharedAccessibility, device_channels: u16) -> Self {
+        let swap = if shared.swap_channels() { 1.0 } else { 0.0 };
+        let gains = Self::balance_gains(shared.balance());
+        Self {
+            frame: vec![0.0; device_channels as usize],
+            swap,
+            swap_target: swap,
+            swap_step: 0.0,
+            gains,
+            gain_targets: gains,
+            gain_steps: [0.0; 2],
+        }
+    }
+
+    /// Left and right gain for a balance from -1 (left only) to 1 (right only)
+    fn balance_gains(balance: f32) -> [f32; 2] {
+        [1.0 - balance.max(0.0), 1.0 + balance.min(0.0)]
+    }
+
+    /// Pick up the current options, ramping to them over the next `frames` frames
+    pub fn begin_callback(&mut self, shared: &SharedAccessibility, frames: usize) {
+        // The last ramp ends exactly on its target, even if the callback ran short
+        self.swap = self.swap_target;
+        self.gains = self.gain_targets;
+
+        let frames = frames.max(1) as f32;
+        self.swap_target = if shared.swap_channels() { 1.0 } else { 0.0 };
+        self.swap_step = (self.swap_target - self.swap) / frames;
+        self.gain_targets = Self::balance_gains(shared.balance());
+        for ((step, target), gain) in self
+            .gain_steps
+            .iter_mut()
+            .zip(self.gain_targets)
+            .zip(self.gains)
+        {
+            *step = (target - gain) / frames;
+        }
+    }
+
+    /// Device frame buffer to fill before calling [`Self::process`]
+    pub fn frame_mut(&mut self) -> &mut [f32] {
+        &mut self.frame
+    }
+
+    /// Swap and balance the frame buffer and return it
+    pub fn process(&mut self) -> &[f32] {
+        if self.frame.len() < 2 {
+            return &self.frame;
+        }
+        self.swap += self.swap_step;
+        self.gains[0] += self.gain_steps[0];
+        self.gains[1] += self.gain_steps[1];
+        if self.swap == 0.0 && self.gains == [1.0, 1.0] {
+            return &self.frame;
+        }
+
+        let (left, right) = (self.frame[0], self.frame[1]);
+        self.frame[0] = (left + (right - left) * self.swap) * self.gains[0];
+        self.frame[1] = (right + (left - right) * self.swap) * self.gains[1];
+        &self.frame
+    }
+}
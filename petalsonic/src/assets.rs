@@ -0,0 +1,134 @@
+//! Lookup of asset files (HRTF SOFA files) given by relative paths.
+//!
+//! A relative [`PetalSonicWorldDesc::hrtf_path`](crate::PetalSonicWorldDesc::hrtf_path)
+//! is looked up in the common install locations of the platform rather than only in the
+//! working directory, so a game started from a shortcut or a packaged app finds the files
+//! shipped next to it. The directories are tried in order:
+//!
+//! 1. `$PETALSONIC_ASSET_DIR`, if set
+//! 2. the working directory
+//! 3. the executable's directory and its `assets` subdirectory
+//! 4. the platform's data directories:
+//!    - macOS: the bundle's `Resources` directory
+//!    - Windows: `%LOCALAPPDATA%\petalsonic` and `%PROGRAMDATA%\petalsonic`
+//!    - other Unix: `$XDG_DATA_HOME/petalsonic` (`~/.local/share/petalsonic`), each
+//!      `$XDG_DATA_DIRS` entry's `petalsonic` subdirectory (`/usr/local/share`,
+//!      `/usr/share`) and `../share/petalsonic` next to the executable
+//!
+//! Absolute paths are used as they are. If the HRTF file is found nowhere, the engine
+//! falls back to Steam Audio's built-in HRTF and emits
+//! [`PetalSonicEvent::HrtfNotFound`](crate::PetalSonicEvent::HrtfNotFound) listing every
+//! path it tried.
+
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming a directory searched before all others
+pub const ASSET_DIR_ENV: &str = "PETALSONIC_ASSET_DIR";
+
+/// Result of looking up an asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetLookup {
+    /// The first candidate that exists
+    pub resolved: Option<PathBuf>,
+    /// Candidates in the order they were tried, up to and including the resolved one
+    pub attempted: Vec<PathBuf>,
+}
+
+/// Look up `path` in the asset search directories (see the [module docs](self))
+pub fn resolve_asset(path: impl AsRef<Path>) -> AssetLookup {
+    let path = path.as_ref();
+    let candidates = if path.is_absolute() {
+        vec![path.to_path_buf()]
+    } else {
+        asset_search_dirs()
+            .into_iter()
+            .map(|dir| dir.join(path))
+            .collect()
+    };
+
+    let mut attempted = Vec::new();
+    for candidate in candidates {
+        let found = candidate.is_file();
+        attempted.push(candidate);
+        if found {
+            return AssetLookup {
+                resolved: attempted.last().cloned(),
+                attempted,
+            };
+        }
+    }
+    AssetLookup {
+        resolved: None,
+        attempted,
+    }
+}
+
+/// Directories searched for relative asset paths, in order
+pub fn asset_search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os(ASSET_DIR_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Ok(dir) = std::env::current_dir() {
+        dirs.push(dir);
+    }
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    if let Some(exe_dir) = &exe_dir {
+        dirs.push(exe_dir.clone());
+        dirs.push(exe_dir.join("assets"));
+    }
+    dirs.extend(platform_data_dirs(exe_dir.as_deref()));
+
+    let mut unique = Vec::with_capacity(dirs.len());
+    for dir in dirs {
+        if !unique.contains(&dir) {
+            unique.push(dir);
+        }
+    }
+    unique
+}
+
+#[cfg(target_os = "macos")]
+fn platform_data_dirs(exe_dir: Option<&Path>) -> Vec<PathBuf> {
+    // Bundle layout: App.app/Contents/MacOS/<exe> and App.app/Contents/Resources
+    exe_dir
+        .and_then(Path::parent)
+        .map(|contents| vec![contents.join("Resources")])
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn platform_data_dirs(_exe_dir: Option<&Path>) -> Vec<PathBuf> {
+    ["LOCALAPPDATA", "PROGRAMDATA"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(|dir| PathBuf::from(dir).join("petalsonic"))
+        .collect()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn platform_data_dirs(exe_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    if let Some(data_home) = data_home {
+        dirs.push(data_home.join("petalsonic"));
+    }
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| PathBuf::from(dir).join("petalsonic")),
+    );
+    if let Some(exe_dir) = exe_dir {
+        dirs.push(exe_dir.join("../share/petalsonic"));
+    }
+    dirs
+}
@@ -1,3 +1,5 @@
+use super::streaming_resampler::ResamplerImpl;
+use crate::config::ResampleQuality;
 use crate::error::{PetalSonicError, Result};
 
 pub struct BatchResampler {
@@ -5,6 +7,7 @@ pub struct BatchResampler {
     target_sample_rate: u32,
     channels: u16,
     chunk_size: usize,
+    quality: ResampleQuality,
 }
 
 impl BatchResampler {
@@ -41,9 +44,16 @@ impl BatchResampler {
             target_sample_rate,
             channels,
             chunk_size: chunk_size.unwrap_or(1024),
+            quality: ResampleQuality::Fft,
         })
     }
 
+    /// Use the given converter instead of the default [`ResampleQuality::Fft`]
+    pub fn with_quality(mut self, quality: ResampleQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
     /// Resamples a single channel of audio data.
     ///
     /// # Data Format
@@ -60,16 +70,13 @@ impl BatchResampler {
             return Ok(channel_samples.to_vec());
         }
 
-        use rubato::{FftFixedIn, Resampler};
-
-        let mut resampler = FftFixedIn::new(
-            self.source_sample_rate as usize,
-            self.target_sample_rate as usize,
+        let mut resampler = ResamplerImpl::new(
+            self.quality,
+            self.source_sample_rate,
+            self.target_sample_rate,
             self.chunk_size,
-            2, // sub_chunks
             1, // single channel
-        )
-        .map_err(|e| PetalSonicError::AudioLoading(format!("Failed to create resampler: {}", e)))?;
+        )?;
 
         let mut output_buffer = Vec::new();
         let mut input_index = 0;
@@ -90,7 +97,7 @@ impl BatchResampler {
 
             let waves_in = vec![input_chunk];
             let waves_out = resampler
-                .process(&waves_in, None)
+                .process(&waves_in)
                 .map_err(|e| PetalSonicError::AudioLoading(format!("Resampling error: {}", e)))?;
 
             if let Some(first_channel) = waves_out.first() {
@@ -1,5 +1,9 @@
 use crate::{
-    audio_data::{AudioDataLoader, ConvertToMono, LoadOptions, PetalSonicAudioData},
+    audio_data::{
+        AudioDataLoader, AudioMetadata, ConvertToMono, LoadOptions, LoadWarning,
+        PetalSonicAudioData, read_wav_chunks,
+    },
+    channel_mix::{ChannelLayout, ChannelPosition},
     error::{PetalSonicError, Result},
 };
 use std::fs::File;
@@ -8,12 +12,28 @@ use std::sync::Arc;
 use std::time::Duration;
 use symphonia::{
     core::{
-        audio::SampleBuffer, codecs::DecoderOptions, errors::Error, formats::FormatOptions,
-        io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+        audio::{Channels, SampleBuffer},
+        codecs::DecoderOptions,
+        errors::Error,
+        formats::FormatOptions,
+        io::MediaSourceStream,
+        meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+        probe::Hint,
+        units::TimeBase,
     },
     default::{get_codecs, get_probe},
 };
 
+/// Range of sample rates in Hz real recordings use; rates outside it point at a broken header
+const PLAUSIBLE_SAMPLE_RATES: std::ops::RangeInclusive<u32> = 4_000..=768_000;
+
+/// Relative difference between the announced and the decoded length tolerated before
+/// warning, as encoder delay and padding make them differ slightly
+const LENGTH_TOLERANCE: f64 = 0.01;
+
+/// Frames the announced and decoded length may differ by regardless of the clip length
+const MIN_FRAME_TOLERANCE: u64 = 4096;
+
 /// Default audio loader implementation using the Symphonia decoder library.
 ///
 /// This loader supports various audio formats (MP3, WAV, FLAC, OGG, etc.) and decodes them
@@ -44,7 +64,7 @@ impl AudioDataLoader for DefaultAudioLoader {
         }
 
         let probe = get_probe();
-        let probed = probe
+        let mut probed = probe
             .format(
                 &hint,
                 mss,
@@ -55,23 +75,33 @@ impl AudioDataLoader for DefaultAudioLoader {
                 PetalSonicError::AudioLoading(format!("Failed to probe audio format: {:?}", e))
             })?;
 
+        let mut metadata = AudioMetadata::default();
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+            read_tags(revision, &mut metadata);
+        }
+
         let mut format = probed.format;
+        if let Some(revision) = format.metadata().current() {
+            read_tags(revision, &mut metadata);
+        }
 
         let track = format.default_track().ok_or_else(|| {
             PetalSonicError::AudioLoading("No default audio track found".to_string())
         })?;
 
-        let sample_rate = track
+        let header_rate = track
             .codec_params
             .sample_rate
             .ok_or_else(|| PetalSonicError::AudioLoading("Sample rate not found".to_string()))?
             as u32;
+        let declared_frames = track.codec_params.n_frames;
+        let time_base = track.codec_params.time_base;
 
-        let channels = track
-            .codec_params
-            .channels
-            .ok_or_else(|| PetalSonicError::AudioLoading("Channel count not found".to_string()))?
-            .count() as u16;
+        let layout =
+            channel_layout(track.codec_params.channels.ok_or_else(|| {
+                PetalSonicError::AudioLoading("Channel count not found".to_string())
+            })?);
+        let channels = layout.channels();
 
         let mut decoder = get_codecs()
             .make(&track.codec_params, &DecoderOptions::default())
@@ -80,6 +110,9 @@ impl AudioDataLoader for DefaultAudioLoader {
             })?;
 
         let mut samples: Vec<f32> = Vec::new();
+        // Rate of the first decoded buffer and end of the last packet, in `time_base` units
+        let mut stream_rate = None;
+        let mut end_timestamp = 0;
 
         loop {
             // Read the next packet from the container
@@ -94,6 +127,8 @@ impl AudioDataLoader for DefaultAudioLoader {
                 }
             };
 
+            end_timestamp = end_timestamp.max(packet.ts() + packet.dur());
+
             // Decode the packet into audio samples
             let decoded = match decoder.decode(&packet) {
                 Ok(decoded) => decoded,
@@ -110,6 +145,7 @@ impl AudioDataLoader for DefaultAudioLoader {
             // Convert the sample buffer into f32 samples using SampleBuffer
             let spec = *decoded.spec();
             let capacity = decoded.capacity();
+            stream_rate.get_or_insert(spec.rate);
 
             // Always convert to f32
             let mut tmp = SampleBuffer::<f32>::new(capacity as u64, spec);
@@ -118,42 +154,188 @@ impl AudioDataLoader for DefaultAudioLoader {
             samples.extend_from_slice(tmp.samples());
         }
 
-        // Apply mono conversion based on the option
-        let final_samples;
-        let final_channels;
-
-        match options.convert_to_mono {
-            ConvertToMono::Original => {
-                // Keep original channels
-                final_samples = samples;
-                final_channels = channels;
-            }
-            ConvertToMono::ForceMono => {
-                if channels == 1 {
-                    // Already mono, keep as is
-                    final_samples = samples;
-                    final_channels = 1;
-                } else {
-                    // Downmix all channels to mono using the most common technique (averaging)
-                    final_samples = samples
-                        .chunks(channels as usize)
-                        .map(|frame| {
-                            let sum: f32 = frame.iter().sum();
-                            sum / channels as f32
-                        })
-                        .collect();
-                    final_channels = 1;
-                }
-            }
+        let decoded_frames = (samples.len() / channels as usize) as u64;
+        let (sample_rate, warnings) = verify_sample_rate(
+            header_rate,
+            stream_rate,
+            options.force_sample_rate,
+            declared_frames,
+            decoded_frames,
+            time_base.map(|time_base| (time_base, end_timestamp)),
+        )?;
+        for warning in &warnings {
+            log::warn!("{}: {}", path, warning);
         }
+        metadata.load_warnings = warnings;
 
-        let duration = Duration::from_secs_f64(
-            final_samples.len() as f64 / (sample_rate * final_channels as u32) as f64,
-        );
+        let duration = Duration::from_secs_f64(decoded_frames as f64 / sample_rate as f64);
+
+        metadata.loop_points = metadata.loop_points_from_tags();
+        if let Some(chunks) = read_wav_chunks(path) {
+            metadata.loop_points = chunks.loop_points.or(metadata.loop_points);
+            metadata.timecode = chunks.timecode;
+            metadata.markers = chunks.markers;
+        }
 
         let audio_data =
-            PetalSonicAudioData::new(final_samples, sample_rate, final_channels, duration);
+            PetalSonicAudioData::new_with_layout(samples, sample_rate, layout, duration);
+        let total_frames = audio_data.total_frames();
+        let loop_region = metadata
+            .loop_points
+            .filter(|_| options.apply_loop_points)
+            .filter(|region| !region.is_empty() && region.end_frame <= total_frames);
+        if options.apply_loop_points && loop_region != metadata.loop_points {
+            log::warn!(
+                "Ignoring invalid loop points {:?} in {} ({} frames)",
+                metadata.loop_points,
+                path,
+                total_frames
+            );
+        }
+        let audio_data = audio_data.with_metadata(metadata, loop_region);
+
+        // Apply mono conversion based on the option
+        let audio_data = match options.convert_to_mono {
+            ConvertToMono::Original => audio_data,
+            // Downmix by speaker position, averaging all channels except LFE
+            ConvertToMono::ForceMono => audio_data.to_mono()?,
+        };
 
         Ok(Arc::new(audio_data))
     }
+
+    fn extensions(&self) -> &[&str] {
+        &[
+            "wav", "wave", "mp3", "flac", "ogg", "oga", "mka", "mkv", "webm",
+        ]
+    }
+
+    fn probe(&self, header: &[u8]) -> bool {
+        header.starts_with(b"RIFF")
+            || header.starts_with(b"fLaC")
+            || header.starts_with(b"OggS")
+            || header.starts_with(b"ID3")
+            || header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) // Matroska/WebM
+            || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0) // MPEG frame sync
+    }
+}
+
+/// Pick the sample rate of a decoded file and check it against the other timing
+/// information of the file: the rate of the decoded stream, the frame count announced by
+/// the header and the span of the container timestamps (`end` in `time_base` units)
+fn verify_sample_rate(
+    header_rate: u32,
+    stream_rate: Option<u32>,
+    forced_rate: Option<u32>,
+    declared_frames: Option<u64>,
+    decoded_frames: u64,
+    timestamps: Option<(TimeBase, u64)>,
+) -> Result<(u32, Vec<LoadWarning>)> {
+    let mut warnings = Vec::new();
+    let mut sample_rate = header_rate;
+    if let Some(stream) = stream_rate
+        && stream != header_rate
+    {
+        warnings.push(LoadWarning::SampleRateMismatch {
+            header: header_rate,
+            stream,
+        });
+        sample_rate = stream;
+    }
+    if let Some(forced) = forced_rate {
+        if forced == 0 {
+            return Err(PetalSonicError::Configuration(
+                "Forced sample rate must be greater than 0".to_string(),
+            ));
+        }
+        if forced != header_rate {
+            warnings.push(LoadWarning::SampleRateOverridden {
+                header: header_rate,
+                forced,
+            });
+        }
+        sample_rate = forced;
+    }
+    if !PLAUSIBLE_SAMPLE_RATES.contains(&sample_rate) {
+        warnings.push(LoadWarning::ImplausibleSampleRate { sample_rate });
+    }
+
+    let differs = |a: f64, b: f64, min_tolerance: f64| {
+        (a - b).abs() > (LENGTH_TOLERANCE * a.max(b)).max(min_tolerance)
+    };
+    if let Some(declared) = declared_frames
+        && differs(
+            declared as f64,
+            decoded_frames as f64,
+            MIN_FRAME_TOLERANCE as f64,
+        )
+    {
+        warnings.push(LoadWarning::FrameCountMismatch {
+            declared,
+            decoded: decoded_frames,
+        });
+    }
+
+    // Timestamps counting frames at the header's rate carry no independent timing
+    if let Some((time_base, end)) = timestamps
+        && time_base.numer as u64 * header_rate as u64 != time_base.denom as u64
+        && end > 0
+    {
+        let time = time_base.calc_time(end);
+        let container = Duration::from_secs_f64(time.seconds as f64 + time.frac);
+        let decoded = Duration::from_secs_f64(decoded_frames as f64 / sample_rate as f64);
+        if differs(
+            container.as_secs_f64(),
+            decoded.as_secs_f64(),
+            MIN_FRAME_TOLERANCE as f64 / sample_rate as f64,
+        ) {
+            warnings.push(LoadWarning::DurationMismatch { container, decoded });
+        }
+    }
+
+    Ok((sample_rate, warnings))
+}
+
+/// Append the tags of a metadata revision, filling in the well-known fields
+fn read_tags(revision: &MetadataRevision, metadata: &mut AudioMetadata) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+        let field = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => Some(&mut metadata.title),
+            Some(StandardTagKey::Artist) => Some(&mut metadata.artist),
+            Some(StandardTagKey::Album) => Some(&mut metadata.album),
+            _ => None,
+        };
+        if let Some(field) = field {
+            field.get_or_insert_with(|| value.clone());
+        }
+        metadata.tags.push((tag.key.clone(), value));
+    }
+}
+
+/// Convert a Symphonia channel mask (in WAVEFORMATEXTENSIBLE order) to a channel layout
+fn channel_layout(channels: Channels) -> ChannelLayout {
+    if channels.count() == 1 {
+        return ChannelLayout::mono();
+    }
+
+    let positions = channels
+        .iter()
+        .map(|channel| match channel {
+            Channels::FRONT_LEFT => ChannelPosition::FrontLeft,
+            Channels::FRONT_RIGHT => ChannelPosition::FrontRight,
+            Channels::FRONT_CENTRE => ChannelPosition::FrontCenter,
+            Channels::LFE1 | Channels::LFE2 => ChannelPosition::LowFrequency,
+            Channels::REAR_LEFT => ChannelPosition::BackLeft,
+            Channels::REAR_RIGHT => ChannelPosition::BackRight,
+            Channels::REAR_CENTRE => ChannelPosition::BackCenter,
+            Channels::SIDE_LEFT => ChannelPosition::SideLeft,
+            Channels::SIDE_RIGHT => ChannelPosition::SideRight,
+            Channels::FRONT_LEFT_CENTRE => ChannelPosition::FrontLeftOfCenter,
+            Channels::FRONT_RIGHT_CENTRE => ChannelPosition::FrontRightOfCenter,
+            _ => ChannelPosition::Unknown,
+        })
+        .collect();
+
+    ChannelLayout::new(positions)
 }
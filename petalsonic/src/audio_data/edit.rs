@@ -0,0 +1,157 @@
+//! Non-destructive editing utilities for [`PetalSonicAudioData`].
+//!
+//! Every operation returns a new `PetalSonicAudioData`; the source data is never modified,
+//! so clips that are already registered with a world can be edited safely.
+
+use super::PetalSonicAudioData;
+use crate::error::{PetalSonicError, Result};
+use std::ops::{Bound, RangeBounds};
+use std::time::Duration;
+
+impl PetalSonicAudioData {
+    /// Copy a range of frames into a new clip.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the range is reversed or extends past the
+    /// end of the audio.
+    pub fn sub_clip(&self, range: impl RangeBounds<usize>) -> Result<Self> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.total_frames(),
+        };
+
+        if start > end || end > self.total_frames() {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Frame range {}-{} out of bounds (max: {})",
+                start,
+                end,
+                self.total_frames()
+            )));
+        }
+
+        let channels = self.channels() as usize;
+        let samples = self.samples()[start * channels..end * channels].to_vec();
+        Ok(self.with_samples(samples))
+    }
+
+    /// Append `other` to the end of this clip.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the sample rates or channel layouts differ.
+    pub fn append(&self, other: &Self) -> Result<Self> {
+        Self::concat([self, other])
+    }
+
+    /// Concatenate clips in order, e.g. to stitch together number announcements.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if no clip is given, or if the clips do not all
+    /// share the same sample rate and channel layout.
+    pub fn concat<'a>(clips: impl IntoIterator<Item = &'a PetalSonicAudioData>) -> Result<Self> {
+        let mut clips = clips.into_iter();
+        let first = clips.next().ok_or_else(|| {
+            PetalSonicError::AudioFormat("Cannot concatenate zero clips".to_string())
+        })?;
+
+        let mut samples = first.samples().to_vec();
+        for clip in clips {
+            if clip.sample_rate() != first.sample_rate()
+                || clip.channel_layout() != first.channel_layout()
+            {
+                return Err(PetalSonicError::AudioFormat(format!(
+                    "Cannot concatenate {} Hz {:?} audio with {} Hz {:?} audio",
+                    first.sample_rate(),
+                    first.channel_layout().positions(),
+                    clip.sample_rate(),
+                    clip.channel_layout().positions()
+                )));
+            }
+            samples.extend_from_slice(clip.samples());
+        }
+
+        Ok(first.with_samples(samples))
+    }
+
+    /// Multiply every sample by a linear gain.
+    pub fn with_gain(&self, gain: f32) -> Self {
+        self.with_samples(self.samples().iter().map(|sample| sample * gain).collect())
+    }
+
+    /// Apply a linear fade in over the first `duration` of the clip.
+    pub fn fade_in(&self, duration: Duration) -> Self {
+        let fade_frames = self.duration_to_frames(duration);
+        self.with_envelope(|frame| {
+            if frame < fade_frames {
+                frame as f32 / fade_frames as f32
+            } else {
+                1.0
+            }
+        })
+    }
+
+    /// Apply a linear fade out over the last `duration` of the clip.
+    pub fn fade_out(&self, duration: Duration) -> Self {
+        let fade_frames = self.duration_to_frames(duration);
+        let total_frames = self.total_frames();
+        self.with_envelope(|frame| {
+            let remaining = total_frames - frame - 1;
+            if remaining < fade_frames {
+                remaining as f32 / fade_frames as f32
+            } else {
+                1.0
+            }
+        })
+    }
+
+    /// Number of frames covering `duration`, clamped to the clip length
+    fn duration_to_frames(&self, duration: Duration) -> usize {
+        ((duration.as_secs_f64() * self.sample_rate() as f64) as usize).min(self.total_frames())
+    }
+
+    /// Scale each frame by `envelope(frame_index)`
+    fn with_envelope(&self, envelope: impl Fn(usize) -> f32) -> Self {
+        let channels = self.channels() as usize;
+        let samples = self
+            .samples()
+            .chunks(channels)
+            .enumerate()
+            .flat_map(|(frame, samples)| {
+                let gain = envelope(frame);
+                samples.iter().map(move |sample| sample * gain)
+            })
+            .collect();
+        self.with_samples(samples)
+    }
+
+    /// New clip with the same format and metadata as `self` and the given interleaved
+    /// samples. Loop points and markers are dropped if the length changes.
+    fn with_samples(&self, samples: Vec<f32>) -> Self {
+        let duration = Duration::from_secs_f64(
+            samples.len() as f64 / (self.sample_rate() as f64 * self.channels() as f64),
+        );
+        let clip = Self::new_with_layout(
+            samples,
+            self.sample_rate(),
+            self.channel_layout().clone(),
+            duration,
+        );
+
+        if clip.total_frames() == self.total_frames() {
+            clip.with_metadata_of(self)
+        } else {
+            let mut metadata = self.metadata().clone();
+            metadata.loop_points = None;
+            metadata.markers.clear();
+            clip.with_metadata(metadata, None)
+        }
+    }
+}
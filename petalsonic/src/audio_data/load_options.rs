@@ -40,12 +40,30 @@ pub enum ConvertToMono {
 pub struct LoadOptions {
     /// How to handle mono conversion during audio loading.
     pub convert_to_mono: ConvertToMono,
+    /// Use loop points embedded in the file as the clip's loop region, so
+    /// `LoopMode::Infinite` playback repeats only that region.
+    pub apply_loop_points: bool,
+    /// Analyze onsets and beats on a background thread after loading (see
+    /// [`PetalSonicAudioData::rhythm`](crate::audio_data::PetalSonicAudioData::rhythm))
+    pub analyze_rhythm: bool,
+    /// Produce one mono clip per channel. Only [`PetalSonicAudioData::from_path_split`]
+    /// returns several clips; the other load functions ignore this option.
+    ///
+    /// [`PetalSonicAudioData::from_path_split`]: crate::audio_data::PetalSonicAudioData::from_path_split
+    pub split_channels: bool,
+    /// Sample rate the decoded samples are played at, overriding the one reported by the
+    /// file. For files whose header is known to be wrong; the samples are not resampled.
+    pub force_sample_rate: Option<u32>,
 }
 
 impl Default for LoadOptions {
     fn default() -> Self {
         Self {
             convert_to_mono: ConvertToMono::Original,
+            apply_loop_points: false,
+            analyze_rhythm: false,
+            split_channels: false,
+            force_sample_rate: None,
         }
     }
 }
@@ -79,4 +97,67 @@ impl LoadOptions {
         self.convert_to_mono = convert;
         self
     }
+
+    /// Sets whether embedded loop points become the clip's loop region.
+    ///
+    /// # Arguments
+    ///
+    /// * `apply` - If true, loop points found in the file (WAV `smpl` chunk or `LOOPSTART`
+    ///   comments) are used by `LoopMode::Infinite` playback
+    ///
+    /// # Returns
+    ///
+    /// Returns `self` to allow method chaining.
+    pub fn apply_loop_points(mut self, apply: bool) -> Self {
+        self.apply_loop_points = apply;
+        self
+    }
+
+    /// Sets whether onsets and beats are analyzed after loading.
+    ///
+    /// # Arguments
+    ///
+    /// * `analyze` - If true, the loaded clip is analyzed on a background thread; the
+    ///   result is available from `PetalSonicAudioData::cached_rhythm` once it finished
+    ///
+    /// # Returns
+    ///
+    /// Returns `self` to allow method chaining.
+    pub fn analyze_rhythm(mut self, analyze: bool) -> Self {
+        self.analyze_rhythm = analyze;
+        self
+    }
+
+    /// Sets whether files are split into one clip per channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `split` - If true, `PetalSonicAudioData::from_path_split` returns one mono clip per
+    ///   channel of the file. Combine with `ConvertToMono::Original`, as a clip forced to
+    ///   mono has a single channel to split.
+    ///
+    /// # Returns
+    ///
+    /// Returns `self` to allow method chaining.
+    pub fn split_channels(mut self, split: bool) -> Self {
+        self.split_channels = split;
+        self
+    }
+
+    /// Overrides the sample rate reported by the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - Rate in Hz the decoded samples are played at, e.g. when playback
+    ///   of a file sounds too fast or too slow. Recorded as a
+    ///   [`LoadWarning::SampleRateOverridden`](crate::audio_data::LoadWarning::SampleRateOverridden) if it differs
+    ///   from the header's.
+    ///
+    /// # Returns
+    ///
+    /// Returns `self` to allow method chaining.
+    pub fn force_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.force_sample_rate = Some(sample_rate);
+        self
+    }
 }
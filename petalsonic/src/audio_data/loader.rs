@@ -9,6 +9,9 @@ use std::sync::Arc;
 /// using Symphonia, but users can bring their own loaders for specialized formats
 /// or requirements.
 ///
+/// Loaders registered with a [`LoaderRegistry`](crate::audio_data::LoaderRegistry) are
+/// selected by [`extensions`](Self::extensions) and [`probe`](Self::probe).
+///
 /// # Example
 ///
 /// ```ignore
@@ -41,4 +44,20 @@ pub trait AudioDataLoader {
     ///
     /// Returns a `PetalSonicError` if the file cannot be loaded or decoded.
     fn load(&self, path: &str, options: &LoadOptions) -> Result<Arc<PetalSonicAudioData>>;
+
+    /// File extensions handled by this loader, lowercase and without the leading dot.
+    ///
+    /// Used by [`LoaderRegistry`](crate::audio_data::LoaderRegistry) to pick loaders.
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Returns true if this loader recognizes the file from its first bytes.
+    ///
+    /// Used by [`LoaderRegistry`](crate::audio_data::LoaderRegistry) for files whose
+    /// extension no loader claims. `header` holds up to 64 bytes.
+    fn probe(&self, header: &[u8]) -> bool {
+        let _ = header;
+        false
+    }
 }
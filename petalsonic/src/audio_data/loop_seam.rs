@@ -0,0 +1,226 @@
+//! Seamlessness check of a clip's loop points.
+//!
+//! A loop whose end does not flow into its start clicks, pumps or changes color every
+//! time it wraps. [`PetalSonicAudioData::analyze_loop_seam`] compares both sides of the
+//! seam so content tools and asset pipelines can catch such loops before shipping:
+//!
+//! ```ignore
+//! let ambience = PetalSonicAudioData::from_path_with_options(
+//!     "forest_loop.wav",
+//!     &LoadOptions::new().apply_loop_points(true),
+//! )?;
+//! let seam = ambience.analyze_loop_seam()?;
+//! if !seam.is_seamless() {
+//!     eprintln!(
+//!         "forest_loop.wav: score {:.2}, try a {:?} crossfade",
+//!         seam.score, seam.suggested_crossfade
+//!     );
+//! }
+//! ```
+//!
+//! Three mismatches are measured over 20 ms on each side of the seam:
+//!
+//! - the **discontinuity**: how far the first sample of the loop is from the waveform's
+//!   continuation of the last one, relative to how predictable the waveform is around the
+//!   seam (a click)
+//! - the **level mismatch**: the RMS difference between the two sides in dB (a jump in
+//!   loudness)
+//! - the **spectral mismatch**: how differently the energy of the two sides is spread over
+//!   three bands (below 200 Hz, 200 Hz to 2 kHz and above 2 kHz) (a jump in color)
+
+use super::{LoopRegion, PetalSonicAudioData};
+use crate::error::{PetalSonicError, Result};
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+/// Length of the windows compared on each side of the seam, in seconds
+const WINDOW_SECONDS: f64 = 0.02;
+/// Crossover frequencies of the spectral bands in Hz
+const BAND_CROSSOVERS_HZ: [f32; 2] = [200.0, 2000.0];
+/// Discontinuity below which the seam is as smooth as the waveform around it, and at which
+/// it counts as a certain click
+const CLICK_DISCONTINUITY: (f32, f32) = (2.0, 8.0);
+/// Level mismatch in dB below which it is inaudible, and at which it counts as a certain
+/// jump
+const AUDIBLE_LEVEL_DB: (f32, f32) = (1.0, 6.0);
+/// Spectral mismatch below which it is inaudible (or within the variation of noise), and at
+/// which it counts as a certain change of color
+const AUDIBLE_SPECTRAL: (f32, f32) = (0.1, 0.5);
+/// RMS in dBFS below which a side counts as silent
+const SILENCE_DB: f32 = -80.0;
+/// Score from which a loop counts as seamless
+const SEAMLESS_SCORE: f32 = 0.9;
+/// Crossfade suggested for a click alone
+const CLICK_CROSSFADE: Duration = Duration::from_millis(10);
+/// Crossfade suggested for the largest level or spectral mismatch
+const MAX_CROSSFADE: Duration = Duration::from_millis(250);
+
+/// How seamlessly a clip's loop region wraps around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopSeamAnalysis {
+    /// The region analyzed: the clip's loop region, or the whole clip without one
+    pub region: LoopRegion,
+    /// Error of the first loop sample against the continuation of the waveform, relative
+    /// to the typical error around the seam (about 1 for a seamless loop), over all channels
+    pub discontinuity: f32,
+    /// RMS difference of the two sides of the seam in dB
+    pub level_mismatch_db: f32,
+    /// Difference of the band energy distributions of the two sides, from 0 (same color)
+    /// to 1 (no band in common)
+    pub spectral_mismatch: f32,
+    /// Overall seamlessness, from 0 (clearly audible seam) to 1 (seamless)
+    pub score: f32,
+    /// Crossfade length that would hide the seam, zero if it is seamless
+    pub suggested_crossfade: Duration,
+}
+
+impl LoopSeamAnalysis {
+    /// Analyze the seam of `region` of a clip
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the region is empty or extends past the end
+    /// of the audio.
+    pub fn analyze(audio: &PetalSonicAudioData, region: LoopRegion) -> Result<Self> {
+        if region.is_empty() || region.end_frame > audio.total_frames() {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Loop region {}-{} out of bounds (max: {})",
+                region.start_frame,
+                region.end_frame,
+                audio.total_frames()
+            )));
+        }
+
+        let sample_rate = audio.sample_rate();
+        let channels = audio.channels() as usize;
+        let window = ((sample_rate as f64 * WINDOW_SECONDS) as usize)
+            .min(region.len() / 2)
+            .max(1);
+        let frame = |index: usize| &audio.samples()[index * channels..(index + 1) * channels];
+        let tail: Vec<&[f32]> = (region.end_frame - window..region.end_frame)
+            .map(frame)
+            .collect();
+        let head: Vec<&[f32]> = (region.start_frame..region.start_frame + window)
+            .map(frame)
+            .collect();
+
+        let discontinuity = (0..channels)
+            .map(|channel| {
+                let tail: Vec<f32> = tail.iter().map(|frame| frame[channel]).collect();
+                let head: Vec<f32> = head.iter().map(|frame| frame[channel]).collect();
+                channel_discontinuity(&tail, &head)
+            })
+            .fold(0.0f32, f32::max);
+
+        let tail_rms_db = rms_db(&tail);
+        let head_rms_db = rms_db(&head);
+        let (level_mismatch_db, spectral_mismatch) =
+            if tail_rms_db < SILENCE_DB && head_rms_db < SILENCE_DB {
+                (0.0, 0.0)
+            } else {
+                let tail_bands = band_distribution(&tail, sample_rate);
+                let head_bands = band_distribution(&head, sample_rate);
+                let spectral = 0.5
+                    * tail_bands
+                        .iter()
+                        .zip(head_bands)
+                        .map(|(tail, head)| (tail - head).abs())
+                        .sum::<f32>();
+                ((tail_rms_db - head_rms_db).abs(), spectral)
+            };
+
+        let audibility = |value: f32, (inaudible, certain): (f32, f32)| {
+            ((value - inaudible) / (certain - inaudible)).clamp(0.0, 1.0)
+        };
+        let click = audibility(discontinuity, CLICK_DISCONTINUITY);
+        let level = audibility(level_mismatch_db, AUDIBLE_LEVEL_DB);
+        let spectral = audibility(spectral_mismatch, AUDIBLE_SPECTRAL);
+        let score = (1.0 - click) * (1.0 - level) * (1.0 - spectral);
+
+        let suggested_crossfade = if score >= SEAMLESS_SCORE {
+            Duration::ZERO
+        } else {
+            let longest = Duration::from_secs_f64(region.len() as f64 / 2.0 / sample_rate as f64);
+            CLICK_CROSSFADE
+                .max(MAX_CROSSFADE.mul_f32(level.max(spectral)))
+                .min(longest)
+        };
+
+        Ok(Self {
+            region,
+            discontinuity,
+            level_mismatch_db,
+            spectral_mismatch,
+            score,
+            suggested_crossfade,
+        })
+    }
+
+    /// Whether the seam is unlikely to be audible
+    pub fn is_seamless(&self) -> bool {
+        self.score >= SEAMLESS_SCORE
+    }
+}
+
+/// Largest error of a linear extrapolation across the seam (into the first two samples of
+/// `head`, which catches both jumps and kinks), relative to the median error of the same
+/// extrapolation within both windows
+fn channel_discontinuity(tail: &[f32], head: &[f32]) -> f32 {
+    let prediction_error = |samples: &[f32]| (samples[2] - (2.0 * samples[1] - samples[0])).abs();
+    let mut errors: Vec<f32> = tail
+        .windows(3)
+        .chain(head.windows(3))
+        .map(prediction_error)
+        .collect();
+    let seam: Vec<f32> = tail[tail.len().saturating_sub(2)..]
+        .iter()
+        .chain(&head[..head.len().min(2)])
+        .copied()
+        .collect();
+    if errors.is_empty() || seam.len() < 3 {
+        return 1.0;
+    }
+    let seam_error = seam.windows(3).map(prediction_error).fold(0.0f32, f32::max);
+
+    errors.sort_by(f32::total_cmp);
+    let typical = errors[errors.len() / 2];
+    // Floor at the error of 16-bit quantization, so digital silence does not divide by 0
+    seam_error / typical.max(1.0 / 32_768.0)
+}
+
+/// RMS of the frames over all channels in dBFS
+fn rms_db(frames: &[&[f32]]) -> f32 {
+    let (sum, count) = frames
+        .iter()
+        .flat_map(|frame| frame.iter())
+        .fold((0.0f32, 0usize), |(sum, count), sample| {
+            (sum + sample * sample, count + 1)
+        });
+    let rms = (sum / count.max(1) as f32).sqrt();
+    20.0 * rms.max(1e-9).log10()
+}
+
+/// Share of the energy of the mono mix in each band
+fn band_distribution(frames: &[&[f32]], sample_rate: u32) -> [f32; 3] {
+    let coefficients =
+        BAND_CROSSOVERS_HZ.map(|cutoff| 1.0 - (-TAU * cutoff / sample_rate as f32).exp());
+    let mono = |frame: &[f32]| frame.iter().sum::<f32>() / frame.len() as f32;
+    let first = frames.first().map_or(0.0, |frame| mono(frame));
+    let mut lowpass = [first; 2];
+    let mut energy = [0.0f32; 3];
+    for frame in frames {
+        let sample = mono(frame);
+        for (state, coefficient) in lowpass.iter_mut().zip(coefficients) {
+            *state += coefficient * (sample - *state);
+        }
+        let bands = [lowpass[0], lowpass[1] - lowpass[0], sample - lowpass[1]];
+        for (energy, band) in energy.iter_mut().zip(bands) {
+            *energy += band * band;
+        }
+    }
+    let total: f32 = energy.iter().sum();
+    if total <= 0.0 {
+        return [0.0; 3];
+    }
+    energy.map(|band| band / total)
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+/// A region of an audio clip, in frames, played repeatedly when looping.
+///
+/// `end_frame` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopRegion {
+    /// First frame of the loop
+    pub start_frame: usize,
+    /// Frame after the last frame of the loop
+    pub end_frame: usize,
+}
+
+impl LoopRegion {
+    /// Create a loop region from a start frame and an exclusive end frame
+    pub fn new(start_frame: usize, end_frame: usize) -> Self {
+        Self {
+            start_frame,
+            end_frame,
+        }
+    }
+
+    /// Number of frames in the loop
+    pub fn len(&self) -> usize {
+        self.end_frame.saturating_sub(self.start_frame)
+    }
+
+    /// Returns true if the loop contains no frames
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scale the region to a different sample rate
+    pub(crate) fn resampled(&self, from_rate: u32, to_rate: u32) -> Self {
+        let ratio = to_rate as f64 / from_rate as f64;
+        Self {
+            start_frame: (self.start_frame as f64 * ratio).round() as usize,
+            end_frame: (self.end_frame as f64 * ratio).round() as usize,
+        }
+    }
+}
+
+/// A named position in an audio clip, reported with
+/// [`PetalSonicEvent::Marker`](crate::PetalSonicEvent::Marker) when playback crosses it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    /// Position in the clip, in frames
+    pub frame: usize,
+    /// Label of the marker
+    pub name: String,
+}
+
+impl Marker {
+    pub fn new(name: impl Into<String>, frame: usize) -> Self {
+        Self {
+            frame,
+            name: name.into(),
+        }
+    }
+}
+
+/// Inconsistency found in a file's header while loading it, in
+/// [`AudioMetadata::load_warnings`]
+///
+/// Most point at a header reporting the wrong sample rate, which would otherwise play the
+/// clip too fast or too slow. Load the file again with
+/// [`LoadOptions::force_sample_rate`](crate::audio_data::LoadOptions::force_sample_rate) if
+/// the rate used is wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadWarning {
+    /// The sample rate was set by `LoadOptions::force_sample_rate` instead of the header's
+    SampleRateOverridden { header: u32, forced: u32 },
+    /// The decoded stream runs at another rate than the header reports; the stream's rate
+    /// is used
+    SampleRateMismatch { header: u32, stream: u32 },
+    /// The sample rate used is outside the range of real recordings
+    ImplausibleSampleRate { sample_rate: u32 },
+    /// The header announces a different number of frames than were decoded
+    FrameCountMismatch { declared: u64, decoded: u64 },
+    /// The container's timestamps span a different duration than the decoded frames at the
+    /// sample rate used
+    DurationMismatch {
+        container: Duration,
+        decoded: Duration,
+    },
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SampleRateOverridden { header, forced } => write!(
+                f,
+                "sample rate forced to {} Hz (header reports {} Hz)",
+                forced, header
+            ),
+            Self::SampleRateMismatch { header, stream } => write!(
+                f,
+                "header reports {} Hz but the stream runs at {} Hz",
+                header, stream
+            ),
+            Self::ImplausibleSampleRate { sample_rate } => {
+                write!(f, "implausible sample rate of {} Hz", sample_rate)
+            }
+            Self::FrameCountMismatch { declared, decoded } => write!(
+                f,
+                "header announces {} frames but {} were decoded",
+                declared, decoded
+            ),
+            Self::DurationMismatch { container, decoded } => write!(
+                f,
+                "container timestamps span {:?} but the decoded frames last {:?}",
+                container, decoded
+            ),
+        }
+    }
+}
+
+/// Container metadata extracted when loading an audio file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioMetadata {
+    /// Track title
+    pub title: Option<String>,
+    /// Track artist
+    pub artist: Option<String>,
+    /// Album name
+    pub album: Option<String>,
+    /// All tags as (key, value) pairs, in the order found in the file
+    pub tags: Vec<(String, String)>,
+    /// Loop points embedded in the file (WAV `smpl` chunk or `LOOPSTART`/`LOOPLENGTH`/
+    /// `LOOPEND` comments)
+    pub loop_points: Option<LoopRegion>,
+    /// Broadcast Wave (`bext` chunk) time reference: the first sample's position in
+    /// samples since midnight
+    pub timecode: Option<u64>,
+    /// Markers sorted by frame: the WAV `cue ` chunk's cue points, named by their `labl`
+    /// labels, or set with `PetalSonicAudioData::with_markers`
+    pub markers: Vec<Marker>,
+    /// Inconsistencies between the header and the decoded audio found while loading
+    pub load_warnings: Vec<LoadWarning>,
+}
+
+impl AudioMetadata {
+    /// Value of the first tag with the given key (case-insensitive)
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Loop points from `LOOPSTART` with `LOOPLENGTH` or `LOOPEND` comments (as written by
+    /// RPG Maker, many game engines and trackers)
+    pub(crate) fn loop_points_from_tags(&self) -> Option<LoopRegion> {
+        let parse = |key| self.tag(key).and_then(|v| v.trim().parse::<usize>().ok());
+        let start = parse("LOOPSTART")?;
+        let end = parse("LOOPLENGTH")
+            .map(|length| start + length)
+            .or_else(|| parse("LOOPEND"))?;
+        Some(LoopRegion::new(start, end))
+    }
+}
+
+/// Chunks of a RIFF/WAVE file that Symphonia does not expose
+#[derive(Debug, Default)]
+pub(crate) struct WavChunks {
+    /// First loop of the `smpl` chunk
+    pub loop_points: Option<LoopRegion>,
+    /// `TimeReference` of the `bext` chunk
+    pub timecode: Option<u64>,
+    /// Cue points of the `cue ` chunk, sorted by frame
+    pub markers: Vec<Marker>,
+}
+
+/// Size of the fixed part of a `smpl` chunk, before the loop list
+const SMPL_HEADER_SIZE: usize = 36;
+/// Size of one `smpl` loop entry
+const SMPL_LOOP_SIZE: usize = 24;
+/// Offset of `TimeReferenceLow` in a `bext` chunk
+const BEXT_TIME_REFERENCE_OFFSET: usize = 338;
+/// Size of one `cue ` chunk entry
+const CUE_POINT_SIZE: usize = 24;
+
+/// Read the `smpl`, `bext`, `cue ` and `LIST`/`adtl` chunks of a WAV file
+///
+/// Returns `None` if the file is not a RIFF/WAVE file or cannot be read.
+pub(crate) fn read_wav_chunks(path: &str) -> Option<WavChunks> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header).ok()?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut chunks = WavChunks::default();
+    let mut cue_points = Vec::new();
+    let mut labels = HashMap::new();
+    let mut chunk_header = [0u8; 8];
+    while file.read_exact(&mut chunk_header).is_ok() {
+        let id = &chunk_header[0..4];
+        let size = u32::from_le_bytes(chunk_header[4..8].try_into().ok()?) as usize;
+        // Chunks are padded to an even size
+        let padded_size = size + (size & 1);
+
+        match id {
+            b"smpl" | b"bext" | b"cue " | b"LIST" => {
+                let mut data = vec![0u8; size];
+                file.read_exact(&mut data).ok()?;
+                if size != padded_size {
+                    file.seek(SeekFrom::Current(1)).ok()?;
+                }
+                match id {
+                    b"smpl" => chunks.loop_points = parse_smpl(&data),
+                    b"bext" => chunks.timecode = parse_bext(&data),
+                    b"cue " => cue_points = parse_cue(&data),
+                    _ => parse_labels(&data, &mut labels),
+                }
+            }
+            _ => {
+                file.seek(SeekFrom::Current(padded_size as i64)).ok()?;
+            }
+        }
+    }
+
+    chunks.markers = cue_points
+        .into_iter()
+        .map(|(id, frame)| {
+            let name = labels.remove(&id).unwrap_or_else(|| format!("Cue {}", id));
+            Marker::new(name, frame)
+        })
+        .collect();
+    chunks.markers.sort_by_key(|marker| marker.frame);
+    Some(chunks)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// First loop of a `smpl` chunk. Loop end frames are inclusive in the file.
+fn parse_smpl(data: &[u8]) -> Option<LoopRegion> {
+    let num_loops = read_u32(data, 28)?;
+    if num_loops == 0 {
+        return None;
+    }
+
+    let loop_offset = SMPL_HEADER_SIZE;
+    let start = read_u32(data, loop_offset + 8)? as usize;
+    let end = read_u32(data, loop_offset + 12)? as usize;
+    data.get(loop_offset..loop_offset + SMPL_LOOP_SIZE)?;
+    Some(LoopRegion::new(start, end + 1))
+}
+
+/// (ID, frame) of the cue points of a `cue ` chunk
+fn parse_cue(data: &[u8]) -> Vec<(u32, usize)> {
+    let count = read_u32(data, 0).unwrap_or(0) as usize;
+    (0..count)
+        .map_while(|index| {
+            let offset = 4 + index * CUE_POINT_SIZE;
+            let id = read_u32(data, offset)?;
+            let sample_offset = read_u32(data, offset + 20)?;
+            Some((id, sample_offset as usize))
+        })
+        .collect()
+}
+
+/// Collect the `labl` labels of a `LIST` chunk of type `adtl`, by cue point ID
+fn parse_labels(data: &[u8], labels: &mut HashMap<u32, String>) {
+    if data.get(0..4) != Some(b"adtl") {
+        return;
+    }
+
+    let mut offset = 4;
+    while let (Some(id), Some(size)) = (data.get(offset..offset + 4), read_u32(data, offset + 4)) {
+        let size = size as usize;
+        let start = offset + 8;
+        let Some(body) = data.get(start..start + size) else {
+            return;
+        };
+        if id == b"labl"
+            && let Some(cue_id) = read_u32(body, 0)
+        {
+            let text = &body[4..];
+            let text = &text[..text.iter().position(|b| *b == 0).unwrap_or(text.len())];
+            labels.insert(cue_id, String::from_utf8_lossy(text).into_owned());
+        }
+        offset = start + size + (size & 1);
+    }
+}
+
+fn parse_bext(data: &[u8]) -> Option<u64> {
+    let low = read_u32(data, BEXT_TIME_REFERENCE_OFFSET)? as u64;
+    let high = read_u32(data, BEXT_TIME_REFERENCE_OFFSET + 4)? as u64;
+    Some((high << 32) | low)
+}
@@ -2,10 +2,17 @@
 //!
 //! This module provides functionality for loading and processing audio files, including:
 //! - Loading audio from various formats (MP3, WAV, FLAC, OGG, etc.) via [`DefaultAudioLoader`]
-//! - Custom audio loaders through the [`AudioDataLoader`] trait
+//! - Custom audio loaders through the [`AudioDataLoader`] trait, selected by extension or
+//!   header in a [`LoaderRegistry`]
+//! - Building audio data from in-memory samples with [`PetalSonicAudioData::from_samples`] and
+//!   [`PetalSonicAudioData::from_planar`]
 //! - Audio data storage in [`PetalSonicAudioData`] with automatic reference counting
-//! - Batch and streaming resampling
-//! - Mono conversion options
+//! - Batch and streaming resampling, with a shared cache of resampled data
+//! - Mono conversion options and splitting files into one clip per channel
+//! - Container metadata (tags, loop points, BWF timecode) via [`AudioMetadata`], with
+//!   [`LoadWarning`]s for headers inconsistent with the decoded audio
+//! - Non-destructive editing: slicing, concatenation, gain and fades
+//! - Onset and beat analysis for rhythm-reactive gameplay via [`RhythmAnalysis`]
 //!
 //! # Examples
 //!
@@ -23,16 +30,32 @@
 
 mod batch_resampler;
 mod default_loader;
+mod edit;
 mod load_options;
 mod loader;
+mod loop_seam;
+mod metadata;
+mod registry;
+mod resample_cache;
+mod rhythm;
 mod streaming_resampler;
 
+use crate::channel_mix::{ChannelLayout, ChannelMixMatrix};
+use crate::config::ResampleQuality;
 use crate::error::{PetalSonicError, Result};
 pub use batch_resampler::BatchResampler;
 pub use default_loader::DefaultAudioLoader;
 pub use load_options::{ConvertToMono, LoadOptions};
 pub use loader::AudioDataLoader;
-use std::sync::Arc;
+pub use loop_seam::LoopSeamAnalysis;
+pub(crate) use metadata::read_wav_chunks;
+pub use metadata::{AudioMetadata, LoadWarning, LoopRegion, Marker};
+pub use registry::{DEFAULT_LOADER_PRIORITY, LoaderRegistry};
+pub(crate) use resample_cache::resample_shared;
+pub use resample_cache::{ResampleCacheStats, resample_cache_stats};
+pub use rhythm::RhythmAnalysis;
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
 use std::time::Duration;
 pub use streaming_resampler::{ResamplerType, StreamingResampler};
 
@@ -77,7 +100,7 @@ pub struct PetalSonicAudioData {
 /// - Some audio processing libraries prefer planar (e.g., FFmpeg, some VST plugins)
 ///
 /// **Note**: Functions like `channel_samples()` can extract planar data when needed.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AudioDataInner {
     /// Audio samples stored in **INTERLEAVED** format.
     ///
@@ -93,6 +116,12 @@ pub(crate) struct AudioDataInner {
     /// Number of audio channels (1 = mono, 2 = stereo, etc.)
     pub channels: u16,
 
+    /// Speaker position of each channel, in interleaved order
+    ///
+    /// Populated by the loader when the file declares a layout, otherwise the conventional
+    /// layout for the channel count (see [`ChannelLayout::default_for`]).
+    pub layout: ChannelLayout,
+
     /// Total duration of the audio
     pub duration: Duration,
 
@@ -100,6 +129,15 @@ pub(crate) struct AudioDataInner {
     ///
     /// Calculated as: `samples.len() / channels`
     pub total_frames: usize,
+
+    /// Container metadata extracted by the loader
+    pub metadata: AudioMetadata,
+
+    /// Region repeated by `LoopMode::Infinite` playback instead of the whole clip
+    pub loop_region: Option<LoopRegion>,
+
+    /// Onset and beat analysis, computed on first request (see [`rhythm`])
+    pub rhythm: OnceLock<Arc<RhythmAnalysis>>,
 }
 
 impl PetalSonicAudioData {
@@ -109,21 +147,131 @@ impl PetalSonicAudioData {
         channels: u16,
         duration: Duration,
     ) -> Self {
+        Self::new_with_layout(
+            samples,
+            sample_rate,
+            ChannelLayout::default_for(channels),
+            duration,
+        )
+    }
+
+    pub(crate) fn new_with_layout(
+        samples: Vec<f32>,
+        sample_rate: u32,
+        layout: ChannelLayout,
+        duration: Duration,
+    ) -> Self {
+        let channels = layout.channels();
         let total_frames = samples.len() / channels as usize;
         Self {
             inner: Arc::new(AudioDataInner {
                 samples,
                 sample_rate,
                 channels,
+                layout,
                 duration,
                 total_frames,
+                metadata: AudioMetadata::default(),
+                loop_region: None,
+                rhythm: OnceLock::new(),
             }),
         }
     }
 
-    /// Load audio data from a file path using the default loader.
+    /// Replace the metadata and loop region of a newly created instance
+    pub(crate) fn with_metadata(
+        mut self,
+        metadata: AudioMetadata,
+        loop_region: Option<LoopRegion>,
+    ) -> Self {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.metadata = metadata;
+        inner.loop_region = loop_region;
+        self
+    }
+
+    /// Copy the metadata and loop region of `source`, which must have the same frame count
+    /// and sample rate
+    pub(crate) fn with_metadata_of(self, source: &Self) -> Self {
+        self.with_metadata(source.inner.metadata.clone(), source.inner.loop_region)
+    }
+
+    /// Create audio data from interleaved samples.
+    ///
+    /// Use this to register synthesized or streamed audio (TTS output, procedural audio,
+    /// network audio) without going through a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Samples in **INTERLEAVED** format (`[L0, R0, L1, R1, ...]` for stereo)
+    /// * `sample_rate` - Sample rate in Hz
+    /// * `channels` - Number of channels
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if `sample_rate` or `channels` is zero, or if
+    /// the sample count is not a multiple of `channels`.
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Result<Arc<Self>> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Invalid audio format: {} Hz, {} channels",
+                sample_rate, channels
+            )));
+        }
+
+        if !samples.len().is_multiple_of(channels as usize) {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Sample count {} is not a multiple of the channel count {}",
+                samples.len(),
+                channels
+            )));
+        }
+
+        let total_frames = samples.len() / channels as usize;
+        let duration = Duration::from_secs_f64(total_frames as f64 / sample_rate as f64);
+        Ok(Arc::new(Self::new(
+            samples,
+            sample_rate,
+            channels,
+            duration,
+        )))
+    }
+
+    /// Create audio data from planar samples (one buffer per channel).
+    ///
+    /// The channel buffers are interleaved into the internal storage format.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if `channels` is empty, the channel buffers
+    /// have different lengths, or `sample_rate` is zero.
+    pub fn from_planar(channels: Vec<Vec<f32>>, sample_rate: u32) -> Result<Arc<Self>> {
+        let channel_count = u16::try_from(channels.len()).map_err(|_| {
+            PetalSonicError::AudioFormat(format!("Too many channels: {}", channels.len()))
+        })?;
+        let frames = channels.first().map_or(0, Vec::len);
+
+        if let Some(channel) = channels.iter().position(|channel| channel.len() != frames) {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Channel {} has {} samples, expected {}",
+                channel,
+                channels[channel].len(),
+                frames
+            )));
+        }
+
+        let mut samples = Vec::with_capacity(frames * channels.len());
+        for frame in 0..frames {
+            samples.extend(channels.iter().map(|channel| channel[frame]));
+        }
+
+        Self::from_samples(samples, sample_rate, channel_count)
+    }
+
+    /// Load audio data from a file path using the global loader registry.
     ///
-    /// This is a convenience method that uses the built-in Symphonia-based loader
+    /// This is a convenience method that picks a loader from
+    /// [`LoaderRegistry::global`] (by default the built-in Symphonia-based loader)
     /// with default loading options.
     ///
     /// # Arguments
@@ -138,14 +286,13 @@ impl PetalSonicAudioData {
     ///
     /// Returns a `PetalSonicError` if the file cannot be loaded or decoded.
     pub fn from_path(path: &str) -> Result<Arc<Self>> {
-        let loader = DefaultAudioLoader;
-        loader.load(path, &LoadOptions::default())
+        LoaderRegistry::load_global(path, &LoadOptions::default())
     }
 
     /// Load audio data from a file path with custom loading options.
     ///
-    /// This is a convenience method that uses the built-in Symphonia-based loader
-    /// with user-specified loading options.
+    /// This is a convenience method that picks a loader from
+    /// [`LoaderRegistry::global`] with user-specified loading options.
     ///
     /// # Arguments
     ///
@@ -160,8 +307,42 @@ impl PetalSonicAudioData {
     ///
     /// Returns a `PetalSonicError` if the file cannot be loaded or decoded.
     pub fn from_path_with_options(path: &str, options: &LoadOptions) -> Result<Arc<Self>> {
-        let loader = DefaultAudioLoader;
-        loader.load(path, options)
+        LoaderRegistry::load_global(path, options)
+    }
+
+    /// Load a file as one clip per channel if [`LoadOptions::split_channels`] is set,
+    /// otherwise as a single clip.
+    ///
+    /// Picks a loader from [`LoaderRegistry::global`] as [`Self::from_path_with_options`]
+    /// does, then splits the decoded clip with [`Self::split_channels`], so each channel
+    /// of a combined dialogue + effects file can be registered as its own source:
+    ///
+    /// ```no_run
+    /// # use petalsonic::audio_data::*;
+    /// let options = LoadOptions::new().split_channels(true);
+    /// let languages = PetalSonicAudioData::from_path_split("dialogue_en_fr.wav", &options)?;
+    /// # Ok::<(), petalsonic::error::PetalSonicError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PetalSonicError` if the file cannot be loaded or decoded.
+    pub fn from_path_split(path: &str, options: &LoadOptions) -> Result<Vec<Arc<Self>>> {
+        if !options.split_channels {
+            return Ok(vec![Self::from_path_with_options(path, options)?]);
+        }
+        // Only the split clips are analyzed
+        let combined = LoadOptions {
+            analyze_rhythm: false,
+            ..options.clone()
+        };
+        let channels = Self::from_path_with_options(path, &combined)?.split_channels();
+        if options.analyze_rhythm {
+            for channel in &channels {
+                channel.analyze_rhythm_in_background();
+            }
+        }
+        Ok(channels)
     }
 
     /// Load audio data from a file path using a custom loader.
@@ -187,7 +368,11 @@ impl PetalSonicAudioData {
         loader: &L,
         options: &LoadOptions,
     ) -> Result<Arc<Self>> {
-        loader.load(path, options)
+        let audio_data = loader.load(path, options)?;
+        if options.analyze_rhythm {
+            audio_data.analyze_rhythm_in_background();
+        }
+        Ok(audio_data)
     }
 
     pub fn sample_rate(&self) -> u32 {
@@ -198,6 +383,177 @@ impl PetalSonicAudioData {
         self.inner.channels
     }
 
+    /// Speaker position of each channel
+    pub fn channel_layout(&self) -> &ChannelLayout {
+        &self.inner.layout
+    }
+
+    /// Return a copy tagged with a different channel layout, without changing the samples.
+    ///
+    /// Use this when the channel order is known but was not declared by the source (e.g.
+    /// buffers created with [`Self::from_samples`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the layout's channel count differs.
+    pub fn with_channel_layout(&self, layout: ChannelLayout) -> Result<Self> {
+        if layout.channels() != self.inner.channels {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Layout has {} channels, audio has {}",
+                layout.channels(),
+                self.inner.channels
+            )));
+        }
+
+        Ok(Self::new_with_layout(
+            self.inner.samples.clone(),
+            self.inner.sample_rate,
+            layout,
+            self.inner.duration,
+        )
+        .with_metadata_of(self))
+    }
+
+    /// Onsets and beat grid of the clip, analyzed on the first call and kept with the data
+    /// (see [`RhythmAnalysis`]). Clones of this data share the analysis.
+    ///
+    /// The analysis reads the whole clip; call [`Self::analyze_rhythm_in_background`] to
+    /// keep it off the calling thread.
+    pub fn rhythm(&self) -> Arc<RhythmAnalysis> {
+        self.inner
+            .rhythm
+            .get_or_init(|| Arc::new(RhythmAnalysis::analyze(self)))
+            .clone()
+    }
+
+    /// The rhythm analysis if it was already computed
+    pub fn cached_rhythm(&self) -> Option<Arc<RhythmAnalysis>> {
+        self.inner.rhythm.get().cloned()
+    }
+
+    /// Analyze the rhythm of the clip on a background thread, after which
+    /// [`Self::cached_rhythm`] returns it
+    pub fn analyze_rhythm_in_background(self: &Arc<Self>) -> JoinHandle<Arc<RhythmAnalysis>> {
+        let audio = self.clone();
+        std::thread::spawn(move || audio.rhythm())
+    }
+
+    /// Container metadata extracted by the loader (empty for programmatic buffers)
+    pub fn metadata(&self) -> &AudioMetadata {
+        &self.inner.metadata
+    }
+
+    /// Region repeated by `LoopMode::Infinite` playback, if any
+    ///
+    /// Set from the embedded loop points when loading with
+    /// [`LoadOptions::apply_loop_points`], or explicitly with [`Self::with_loop_region`].
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.inner.loop_region
+    }
+
+    /// Return a copy that loops over `region` (or the whole clip for `None`) when played
+    /// with `LoopMode::Infinite`.
+    ///
+    /// Playback starts at the beginning of the clip and jumps back to the start of the
+    /// region each time the end of the region is reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the region is empty or extends past the end
+    /// of the audio.
+    pub fn with_loop_region(&self, region: Option<LoopRegion>) -> Result<Self> {
+        if let Some(region) = region
+            && (region.is_empty() || region.end_frame > self.inner.total_frames)
+        {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Loop region {}-{} out of bounds (max: {})",
+                region.start_frame, region.end_frame, self.inner.total_frames
+            )));
+        }
+
+        Ok(self
+            .clone()
+            .with_metadata(self.inner.metadata.clone(), region))
+    }
+
+    /// Check how seamlessly the loop region (or the whole clip without one) wraps around
+    /// (see [`LoopSeamAnalysis`])
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the clip is empty.
+    pub fn analyze_loop_seam(&self) -> Result<LoopSeamAnalysis> {
+        let region = self
+            .inner
+            .loop_region
+            .unwrap_or(LoopRegion::new(0, self.inner.total_frames));
+        LoopSeamAnalysis::analyze(self, region)
+    }
+
+    /// Markers of the clip, sorted by frame (see [`AudioMetadata::markers`])
+    pub fn markers(&self) -> &[Marker] {
+        &self.inner.metadata.markers
+    }
+
+    /// Return a copy with `markers` in place of the clip's markers, e.g. to add
+    /// programmatic markers to those loaded from the file:
+    ///
+    /// ```ignore
+    /// let mut markers = audio.markers().to_vec();
+    /// markers.push(Marker::new("footstep_left", 12_000));
+    /// let audio = world.register_audio(Arc::new(audio.with_markers(markers)?), config)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if a marker is past the end of the audio.
+    pub fn with_markers(&self, mut markers: Vec<Marker>) -> Result<Self> {
+        if let Some(marker) = markers
+            .iter()
+            .find(|marker| marker.frame >= self.inner.total_frames)
+        {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Marker '{}' at frame {} out of bounds (max: {})",
+                marker.name, marker.frame, self.inner.total_frames
+            )));
+        }
+
+        markers.sort_by_key(|marker| marker.frame);
+        let mut metadata = self.inner.metadata.clone();
+        metadata.markers = markers;
+        Ok(self.clone().with_metadata(metadata, self.inner.loop_region))
+    }
+
+    /// Convert to another channel layout by speaker position
+    ///
+    /// Channels are reordered, and channels missing from `target` are folded into the
+    /// nearest speakers (see [`ChannelMixMatrix::for_layouts`]).
+    pub fn remap_channels(&self, target: &ChannelLayout) -> Self {
+        if *target == self.inner.layout {
+            return self.clone();
+        }
+
+        let matrix = ChannelMixMatrix::for_layouts(&self.inner.layout, target);
+        let mut frame = vec![0.0; target.channels() as usize];
+        let mut samples = Vec::with_capacity(self.inner.total_frames * frame.len());
+        for input in self
+            .inner
+            .samples
+            .chunks_exact(self.inner.channels as usize)
+        {
+            matrix.apply(input, &mut frame);
+            samples.extend_from_slice(&frame);
+        }
+
+        Self::new_with_layout(
+            samples,
+            self.inner.sample_rate,
+            target.clone(),
+            self.inner.duration,
+        )
+        .with_metadata_of(self)
+    }
+
     pub fn duration(&self) -> Duration {
         self.inner.duration
     }
@@ -218,6 +574,16 @@ impl PetalSonicAudioData {
         self.inner.samples.len()
     }
 
+    /// Bytes of memory held by the sample data
+    pub fn memory_size(&self) -> usize {
+        self.inner.samples.capacity() * std::mem::size_of::<f32>()
+    }
+
+    /// Identity of the shared sample storage; clones of the same data share it
+    pub(crate) fn storage_id(&self) -> usize {
+        Arc::as_ptr(&self.inner) as usize
+    }
+
     /// Get samples for a specific channel (0-indexed)
     pub fn channel_samples(&self, channel: usize) -> Result<Vec<f32>> {
         if channel >= self.inner.channels as usize {
@@ -253,35 +619,49 @@ impl PetalSonicAudioData {
         Ok(self.inner.samples[start_sample..end_sample].to_vec())
     }
 
-    /// Convert to mono by downmixing all channels
+    /// Split into one mono clip per channel, in channel order, e.g. for multi-language
+    /// dialogue stems or M/S recordings stored in one file. Each clip keeps the metadata
+    /// and loop region of this one.
+    pub fn split_channels(&self) -> Vec<Arc<Self>> {
+        (0..self.inner.channels as usize)
+            .map(|channel| {
+                let samples = self
+                    .inner
+                    .samples
+                    .chunks(self.inner.channels as usize)
+                    .map(|frame| frame[channel])
+                    .collect();
+                let clip = Self::new_with_layout(
+                    samples,
+                    self.inner.sample_rate,
+                    ChannelLayout::mono(),
+                    self.inner.duration,
+                );
+                Arc::new(clip.with_metadata_of(self))
+            })
+            .collect()
+    }
+
+    /// Convert to mono by averaging all channels except LFE
     pub fn to_mono(&self) -> Result<Self> {
         if self.inner.channels == 1 {
             return Ok(self.clone());
         }
 
-        let mono_samples: Vec<f32> = self
-            .inner
-            .samples
-            .chunks(self.inner.channels as usize)
-            .map(|frame| {
-                let sum: f32 = frame.iter().sum();
-                sum / self.inner.channels as f32
-            })
-            .collect();
-
-        let mono_duration =
-            Duration::from_secs_f64(mono_samples.len() as f64 / self.inner.sample_rate as f64);
-
-        Ok(Self::new(
-            mono_samples,
-            self.inner.sample_rate,
-            1,
-            mono_duration,
-        ))
+        Ok(self.remap_channels(&ChannelLayout::mono()))
     }
 
     /// Resample to a different sample rate using rubato, returns a new `PetalSonicAudioData` instance
     pub fn resample(&self, target_sample_rate: u32) -> Result<Self> {
+        self.resample_with_quality(target_sample_rate, ResampleQuality::Fft)
+    }
+
+    /// Like [`Self::resample`], with the given converter
+    pub fn resample_with_quality(
+        &self,
+        target_sample_rate: u32,
+        quality: ResampleQuality,
+    ) -> Result<Self> {
         if target_sample_rate == self.inner.sample_rate {
             return Ok(self.clone());
         }
@@ -291,7 +671,8 @@ impl PetalSonicAudioData {
             target_sample_rate,
             self.inner.channels,
             Some(1024), // chunk_size
-        )?;
+        )?
+        .with_quality(quality);
 
         let resampled_samples = resampler.resample_interleaved(&self.inner.samples)?;
 
@@ -300,11 +681,29 @@ impl PetalSonicAudioData {
                 / (target_sample_rate * self.inner.channels as u32) as f64,
         );
 
-        Ok(Self::new(
+        let resample_region =
+            |region: LoopRegion| region.resampled(self.inner.sample_rate, target_sample_rate);
+        let mut metadata = self.inner.metadata.clone();
+        metadata.loop_points = metadata.loop_points.map(resample_region);
+        let ratio = target_sample_rate as f64 / self.inner.sample_rate as f64;
+        for marker in &mut metadata.markers {
+            marker.frame = (marker.frame as f64 * ratio).round() as usize;
+        }
+        let resampled = Self::new_with_layout(
             resampled_samples,
             target_sample_rate,
-            self.inner.channels,
+            self.inner.layout.clone(),
             new_duration,
-        ))
+        );
+        let loop_region = self
+            .inner
+            .loop_region
+            .map(resample_region)
+            .map(|region| LoopRegion {
+                end_frame: region.end_frame.min(resampled.total_frames()),
+                ..region
+            });
+
+        Ok(resampled.with_metadata(metadata, loop_region))
     }
 }
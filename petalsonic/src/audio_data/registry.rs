@@ -0,0 +1,197 @@
+use crate::audio_data::{AudioDataLoader, DefaultAudioLoader, LoadOptions, PetalSonicAudioData};
+use crate::error::{PetalSonicError, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Priority of the built-in [`DefaultAudioLoader`] in [`LoaderRegistry::new`]
+pub const DEFAULT_LOADER_PRIORITY: i32 = 0;
+
+/// Number of bytes read from the start of a file for [`AudioDataLoader::probe`]
+const PROBE_HEADER_SIZE: usize = 64;
+
+struct RegisteredLoader {
+    loader: Arc<dyn AudioDataLoader + Send + Sync>,
+    priority: i32,
+}
+
+/// A set of [`AudioDataLoader`]s selected by file extension and header probing.
+///
+/// When loading a file, the registry tries every loader whose
+/// [`extensions`](AudioDataLoader::extensions) contain the file's extension or whose
+/// [`probe`](AudioDataLoader::probe) accepts the file header, highest priority first, and
+/// returns the first successful result. If no loader matches, all loaders are tried in
+/// priority order. Loaders with equal priority are tried in registration order.
+///
+/// [`PetalSonicAudioData::from_path`] and
+/// [`PetalSonicAudioData::from_path_with_options`] use the global registry, so a custom
+/// format registered there works everywhere a path is loaded:
+///
+/// ```ignore
+/// use petalsonic_core::audio_data::LoaderRegistry;
+///
+/// // Higher priority than the default loader for the extensions MyPackLoader claims
+/// LoaderRegistry::register_global(MyPackLoader, 10);
+/// let audio = PetalSonicAudioData::from_path("sounds.pack")?;
+/// ```
+pub struct LoaderRegistry {
+    loaders: Vec<RegisteredLoader>,
+}
+
+impl Default for LoaderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoaderRegistry {
+    /// Create a registry containing the [`DefaultAudioLoader`] at
+    /// [`DEFAULT_LOADER_PRIORITY`]
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(DefaultAudioLoader, DEFAULT_LOADER_PRIORITY);
+        registry
+    }
+
+    /// Create a registry without any loader
+    pub fn empty() -> Self {
+        Self {
+            loaders: Vec::new(),
+        }
+    }
+
+    /// Register a loader. Higher priorities are tried first.
+    pub fn register<L>(&mut self, loader: L, priority: i32)
+    where
+        L: AudioDataLoader + Send + Sync + 'static,
+    {
+        // Insert after all loaders with the same or higher priority
+        let index = self
+            .loaders
+            .iter()
+            .position(|registered| registered.priority < priority)
+            .unwrap_or(self.loaders.len());
+        self.loaders.insert(
+            index,
+            RegisteredLoader {
+                loader: Arc::new(loader),
+                priority,
+            },
+        );
+    }
+
+    /// Number of registered loaders
+    pub fn len(&self) -> usize {
+        self.loaders.len()
+    }
+
+    /// Returns true if no loader is registered
+    pub fn is_empty(&self) -> bool {
+        self.loaders.is_empty()
+    }
+
+    /// The registry used by [`PetalSonicAudioData::from_path`], initially equal to
+    /// [`LoaderRegistry::new`]
+    pub fn global() -> &'static RwLock<LoaderRegistry> {
+        static GLOBAL: OnceLock<RwLock<LoaderRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| RwLock::new(LoaderRegistry::new()))
+    }
+
+    /// Register a loader with the global registry
+    pub fn register_global<L>(loader: L, priority: i32)
+    where
+        L: AudioDataLoader + Send + Sync + 'static,
+    {
+        Self::global()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .register(loader, priority);
+    }
+
+    /// Load a file with the global registry
+    pub(crate) fn load_global(
+        path: &str,
+        options: &LoadOptions,
+    ) -> Result<Arc<PetalSonicAudioData>> {
+        // Clone the candidates so the lock is not held while decoding
+        let candidates = Self::global()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .candidates(path)?;
+        let audio_data = Self::load_with(&candidates, path, options)?;
+        if options.analyze_rhythm {
+            audio_data.analyze_rhythm_in_background();
+        }
+        Ok(audio_data)
+    }
+
+    /// Loaders to try for `path`, in order
+    fn candidates(&self, path: &str) -> Result<Vec<Arc<dyn AudioDataLoader + Send + Sync>>> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let header = read_header(path)?;
+
+        let matching: Vec<_> = self
+            .loaders
+            .iter()
+            .filter(|registered| {
+                let loader = &registered.loader;
+                extension
+                    .as_deref()
+                    .is_some_and(|ext| loader.extensions().contains(&ext))
+                    || loader.probe(&header)
+            })
+            .map(|registered| registered.loader.clone())
+            .collect();
+
+        if !matching.is_empty() {
+            return Ok(matching);
+        }
+
+        Ok(self
+            .loaders
+            .iter()
+            .map(|registered| registered.loader.clone())
+            .collect())
+    }
+
+    fn load_with(
+        candidates: &[Arc<dyn AudioDataLoader + Send + Sync>],
+        path: &str,
+        options: &LoadOptions,
+    ) -> Result<Arc<PetalSonicAudioData>> {
+        let mut last_error = None;
+        for loader in candidates {
+            match loader.load(path, options) {
+                Ok(audio_data) => return Ok(audio_data),
+                Err(e) => {
+                    log::debug!("Loader failed for {}: {}", path, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            PetalSonicError::AudioLoading(format!("No audio loader registered for {}", path))
+        }))
+    }
+}
+
+impl AudioDataLoader for LoaderRegistry {
+    fn load(&self, path: &str, options: &LoadOptions) -> Result<Arc<PetalSonicAudioData>> {
+        Self::load_with(&self.candidates(path)?, path, options)
+    }
+}
+
+/// Read the first bytes of a file for probing
+fn read_header(path: &str) -> Result<Vec<u8>> {
+    let file = File::open(path)
+        .map_err(|e| PetalSonicError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, e)))?;
+    let mut header = Vec::with_capacity(PROBE_HEADER_SIZE);
+    file.take(PROBE_HEADER_SIZE as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
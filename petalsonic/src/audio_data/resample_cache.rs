@@ -0,0 +1,128 @@
+//! Process-wide cache of resampled audio data.
+//!
+//! Registering the same data in several worlds, or several times in one world, would
+//! otherwise resample it each time. The cache maps the identity of the source data
+//! (clones of a [`PetalSonicAudioData`] share it) and the target rate to the converted
+//! copy, which is reused only if it was made with the same converter quality. Entries hold weak references: the cache never keeps audio alive by itself, and
+//! an entry is dropped once its source or converted data is freed.
+
+use super::{AudioDataInner, PetalSonicAudioData};
+use crate::config::ResampleQuality;
+use crate::error::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+struct CacheEntry {
+    /// Source data, to detect a freed source whose address was reused
+    source: Weak<AudioDataInner>,
+    converted: Weak<PetalSonicAudioData>,
+    quality: ResampleQuality,
+}
+
+#[derive(Default)]
+struct ResampleCache {
+    entries: HashMap<(usize, u32), CacheEntry>,
+    hits: u64,
+    misses: u64,
+}
+
+static CACHE: Mutex<Option<ResampleCache>> = Mutex::new(None);
+
+/// Statistics of the resample cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResampleCacheStats {
+    /// Converted buffers currently alive and shared through the cache
+    pub entries: usize,
+    /// Bytes of sample data of those buffers
+    pub bytes: usize,
+    /// Conversions served from the cache
+    pub hits: u64,
+    /// Conversions that had to resample
+    pub misses: u64,
+}
+
+impl ResampleCache {
+    fn remove_stale(&mut self) {
+        self.entries.retain(|_, entry| {
+            entry.source.strong_count() > 0 && entry.converted.strong_count() > 0
+        });
+    }
+}
+
+/// Resample `audio_data` to `target_sample_rate`, sharing the result with earlier
+/// conversions of the same data at the same quality that are still alive
+pub(crate) fn resample_shared(
+    audio_data: &Arc<PetalSonicAudioData>,
+    target_sample_rate: u32,
+    quality: ResampleQuality,
+) -> Result<Arc<PetalSonicAudioData>> {
+    if audio_data.sample_rate() == target_sample_rate {
+        return Ok(audio_data.clone());
+    }
+
+    let key = (audio_data.storage_id(), target_sample_rate);
+    {
+        let mut cache = CACHE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let cache = cache.get_or_insert_with(ResampleCache::default);
+        let cached = cache
+            .entries
+            .get(&key)
+            .filter(|entry| entry.quality == quality)
+            .and_then(|entry| {
+                entry
+                    .source
+                    .upgrade()
+                    .filter(|source| Arc::ptr_eq(source, &audio_data.inner))
+                    .and_then(|_| entry.converted.upgrade())
+            });
+        if let Some(converted) = cached {
+            cache.hits += 1;
+            return Ok(converted);
+        }
+        cache.misses += 1;
+    }
+
+    // Resample without holding the lock; a concurrent conversion of the same data just
+    // replaces the entry
+    let converted = Arc::new(audio_data.resample_with_quality(target_sample_rate, quality)?);
+    let mut cache = CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let cache = cache.get_or_insert_with(ResampleCache::default);
+    cache.remove_stale();
+    cache.entries.insert(
+        key,
+        CacheEntry {
+            source: Arc::downgrade(&audio_data.inner),
+            converted: Arc::downgrade(&converted),
+            quality,
+        },
+    );
+    Ok(converted)
+}
+
+/// Returns statistics of the process-wide resample cache
+pub fn resample_cache_stats() -> ResampleCacheStats {
+    let mut cache = CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(cache) = cache.as_mut() else {
+        return ResampleCacheStats::default();
+    };
+    cache.remove_stale();
+
+    let mut stats = ResampleCacheStats {
+        hits: cache.hits,
+        misses: cache.misses,
+        ..Default::default()
+    };
+    for entry in cache.entries.values() {
+        if let Some(converted) = entry.converted.upgrade() {
+            stats.entries += 1;
+            stats.bytes += converted.memory_size();
+        }
+    }
+    stats
+}
@@ -0,0 +1,279 @@
+//! Offline onset and beat analysis of audio clips.
+//!
+//! [`PetalSonicAudioData::rhythm`] analyzes a clip the first time it is called and keeps
+//! the result with the data, so the music system and rhythm-reactive gameplay share one
+//! analysis per asset. The analysis reads the whole clip and is too slow for the render
+//! thread or a game frame; run it on load with [`LoadOptions::analyze_rhythm`] or on
+//! demand with [`PetalSonicAudioData::analyze_rhythm_in_background`]:
+//!
+//! ```ignore
+//! let music = PetalSonicAudioData::from_path_with_options(
+//!     "level_theme.ogg",
+//!     &LoadOptions::new().analyze_rhythm(true),
+//! )?;
+//! // Later, once the background analysis finished:
+//! if let Some(rhythm) = music.cached_rhythm() {
+//!     let next_beat = rhythm.next_beat(position_frames);
+//! }
+//! ```
+//!
+//! Onsets are found by the spectral flux of three bands (below 200 Hz, 200 Hz to 2 kHz and
+//! above 2 kHz) over 10 ms hops, picked against a moving threshold. The tempo is the
+//! strongest periodicity of the flux between 60 and 200 BPM, favoring tempos near
+//! 120 BPM, and the beat grid is that period aligned to the flux.
+//!
+//! [`LoadOptions::analyze_rhythm`]: super::LoadOptions::analyze_rhythm
+
+use super::PetalSonicAudioData;
+use std::f32::consts::TAU;
+
+/// Length of an analysis hop in seconds
+const HOP_SECONDS: f64 = 0.01;
+/// Crossover frequencies of the analysis bands in Hz
+const BAND_CROSSOVERS_HZ: [f32; 2] = [200.0, 2000.0];
+/// Hops on each side of a hop averaged for the onset threshold
+const THRESHOLD_RADIUS: usize = 10;
+/// Factor of the local mean flux an onset must exceed
+const THRESHOLD_FACTOR: f32 = 1.5;
+/// Hops on each side of an onset it must be the maximum of
+const PEAK_RADIUS: usize = 3;
+/// Shortest time between two onsets in seconds
+const MIN_ONSET_GAP_SECONDS: f64 = 0.05;
+/// Tempo range considered, in beats per minute
+const TEMPO_RANGE_BPM: (f64, f64) = (60.0, 200.0);
+/// Tempo the estimate is biased towards, in beats per minute
+const PREFERRED_TEMPO_BPM: f64 = 120.0;
+/// Onsets needed to estimate a tempo
+const MIN_TEMPO_ONSETS: usize = 4;
+/// Periodicity (normalized autocorrelation) below which the clip has no steady pulse
+const MIN_PERIODICITY: f32 = 0.1;
+
+/// Onsets and beat grid of a clip, in frames of the analyzed data
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RhythmAnalysis {
+    /// Frames at which notes or hits start, in order
+    pub onsets: Vec<usize>,
+    /// Strength of each onset, the strongest being 1
+    pub onset_strengths: Vec<f32>,
+    /// Estimated tempo in beats per minute, `None` if the clip has no steady pulse
+    pub tempo_bpm: Option<f32>,
+    /// Frames of the beats at the estimated tempo, empty without a tempo
+    pub beats: Vec<usize>,
+}
+
+impl RhythmAnalysis {
+    /// Analyze a clip on the calling thread
+    pub fn analyze(audio: &PetalSonicAudioData) -> Self {
+        let sample_rate = audio.sample_rate();
+        let hop = ((sample_rate as f64 * HOP_SECONDS).round() as usize).max(1);
+        let flux = band_flux(audio, hop);
+        let (onsets, onset_strengths) = pick_onsets(&flux, hop, sample_rate);
+
+        let frames_per_minute = sample_rate as f64 * 60.0;
+        let beat_period = (onsets.len() >= MIN_TEMPO_ONSETS)
+            .then(|| beat_period_hops(&flux, hop, sample_rate))
+            .flatten()
+            .map(|period| {
+                let phase = beat_phase_hops(&flux, period);
+                fit_grid(&onsets, period * hop as f64, phase as f64 * hop as f64)
+            });
+        let tempo_bpm = beat_period.map(|(period, _)| (frames_per_minute / period) as f32);
+        let beats = beat_period
+            .map(|(period, phase)| {
+                (0..)
+                    .map(|beat| (phase + beat as f64 * period).round() as usize)
+                    .take_while(|frame| *frame < audio.total_frames())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            onsets,
+            onset_strengths,
+            tempo_bpm,
+            beats,
+        }
+    }
+
+    /// First beat at or after `frame`
+    pub fn next_beat(&self, frame: usize) -> Option<usize> {
+        let index = self.beats.partition_point(|beat| *beat < frame);
+        self.beats.get(index).copied()
+    }
+
+    /// First onset at or after `frame`
+    pub fn next_onset(&self, frame: usize) -> Option<usize> {
+        let index = self.onsets.partition_point(|onset| *onset < frame);
+        self.onsets.get(index).copied()
+    }
+}
+
+/// Positive change of the log energy of each band per hop, summed over the bands
+fn band_flux(audio: &PetalSonicAudioData, hop: usize) -> Vec<f32> {
+    let channels = audio.channels() as usize;
+    let sample_rate = audio.sample_rate() as f32;
+    let coefficients = BAND_CROSSOVERS_HZ.map(|cutoff| 1.0 - (-TAU * cutoff / sample_rate).exp());
+
+    let mut lowpass = [0.0f32; 2];
+    let mut previous = [0.0f32; 3];
+    let mut flux = Vec::with_capacity(audio.total_frames() / hop + 1);
+    for block in audio.samples().chunks(hop * channels) {
+        let mut energy = [0.0f32; 3];
+        for frame in block.chunks_exact(channels) {
+            let sample = frame.iter().sum::<f32>() / channels as f32;
+            for (state, coefficient) in lowpass.iter_mut().zip(coefficients) {
+                *state += coefficient * (sample - *state);
+            }
+            let bands = [lowpass[0], lowpass[1] - lowpass[0], sample - lowpass[1]];
+            for (energy, band) in energy.iter_mut().zip(bands) {
+                *energy += band * band;
+            }
+        }
+
+        let mut hop_flux = 0.0;
+        for (energy, previous) in energy.iter().zip(previous.iter_mut()) {
+            let level = (1.0 + 1000.0 * energy / hop as f32).ln();
+            hop_flux += (level - *previous).max(0.0);
+            *previous = level;
+        }
+        flux.push(hop_flux);
+    }
+    flux
+}
+
+/// Onset frames and normalized strengths: local flux maxima above the moving threshold
+fn pick_onsets(flux: &[f32], hop: usize, sample_rate: u32) -> (Vec<usize>, Vec<f32>) {
+    let min_gap = (MIN_ONSET_GAP_SECONDS * sample_rate as f64 / hop as f64).ceil() as usize;
+    let max_flux = flux.iter().copied().fold(0.0f32, f32::max);
+    if max_flux <= 0.0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let window = |index: usize, radius: usize| {
+        &flux[index.saturating_sub(radius)..(index + radius + 1).min(flux.len())]
+    };
+    let mut onsets: Vec<usize> = Vec::new();
+    let mut strengths = Vec::new();
+    for (index, value) in flux.iter().copied().enumerate() {
+        let neighbors = window(index, THRESHOLD_RADIUS);
+        let mean = neighbors.iter().sum::<f32>() / neighbors.len() as f32;
+        let threshold = mean * THRESHOLD_FACTOR + 0.01 * max_flux;
+        let is_peak = window(index, PEAK_RADIUS)
+            .iter()
+            .all(|other| *other <= value);
+        let after_gap = onsets
+            .last()
+            .is_none_or(|last| index * hop >= last + min_gap * hop);
+        if value > threshold && is_peak && after_gap {
+            onsets.push(index * hop);
+            strengths.push(value / max_flux);
+        }
+    }
+    (onsets, strengths)
+}
+
+/// Beat period in (fractional) hops: the lag of the strongest flux autocorrelation in the
+/// tempo range, weighted towards the preferred tempo
+fn beat_period_hops(flux: &[f32], hop: usize, sample_rate: u32) -> Option<f64> {
+    let hops_per_minute = sample_rate as f64 * 60.0 / hop as f64;
+    let min_lag = (hops_per_minute / TEMPO_RANGE_BPM.1).floor().max(1.0) as usize;
+    let max_lag = (hops_per_minute / TEMPO_RANGE_BPM.0).ceil() as usize;
+    if flux.len() <= max_lag + 1 {
+        return None;
+    }
+
+    let mean = flux.iter().sum::<f32>() / flux.len() as f32;
+    let centered: Vec<f32> = flux.iter().map(|value| value - mean).collect();
+    let autocorrelation = |lag: usize| -> f32 {
+        centered
+            .iter()
+            .zip(&centered[lag..])
+            .map(|(a, b)| a * b)
+            .sum::<f32>()
+            / (centered.len() - lag) as f32
+    };
+    let energy = autocorrelation(0);
+    if energy <= 0.0 {
+        return None;
+    }
+
+    let correlations: Vec<f32> = (min_lag - 1..=max_lag + 1).map(autocorrelation).collect();
+    let (best, periodicity) = (1..correlations.len() - 1)
+        .map(|index| {
+            let lag = (min_lag - 1 + index) as f64;
+            // Log-Gaussian weight, one octave wide, against octave errors
+            let octaves = (hops_per_minute / lag / PREFERRED_TEMPO_BPM).log2();
+            let weight = (-0.5 * octaves * octaves).exp() as f32;
+            (index, correlations[index] * weight)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if periodicity / energy < MIN_PERIODICITY {
+        return None;
+    }
+
+    // Parabolic interpolation between the neighboring lags
+    let (left, center, right) = (
+        correlations[best - 1],
+        correlations[best],
+        correlations[best + 1],
+    );
+    let curvature = left - 2.0 * center + right;
+    let offset = if curvature < 0.0 {
+        (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    Some((min_lag - 1 + best) as f64 + offset as f64)
+}
+
+/// Hop of the first beat of a grid spaced by `period` hops: the phase collecting the most
+/// flux
+fn beat_phase_hops(flux: &[f32], period: f64) -> usize {
+    (0..period.ceil() as usize)
+        .map(|phase| {
+            let score: f32 = (0..)
+                .map(|beat| (phase as f64 + beat as f64 * period).round() as usize)
+                .take_while(|position| *position < flux.len())
+                .map(|position| flux[position])
+                .sum();
+            (phase, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(phase, _)| phase)
+}
+
+/// Refine a beat grid (period and first beat in frames) by a least-squares fit of the
+/// onsets within a quarter period of a beat, which the hop resolution of the
+/// autocorrelation cannot match over a long clip
+fn fit_grid(onsets: &[usize], period: f64, phase: f64) -> (f64, f64) {
+    let points: Vec<(f64, f64)> = onsets
+        .iter()
+        .map(|onset| *onset as f64)
+        .filter_map(|onset| {
+            let beat = ((onset - phase) / period).round();
+            ((onset - (phase + beat * period)).abs() < period / 4.0).then_some((beat, onset))
+        })
+        .collect();
+    let count = points.len() as f64;
+    let mean_beat = points.iter().map(|(beat, _)| beat).sum::<f64>() / count;
+    let mean_onset = points.iter().map(|(_, onset)| onset).sum::<f64>() / count;
+    let variance: f64 = points
+        .iter()
+        .map(|(beat, _)| (beat - mean_beat).powi(2))
+        .sum();
+    if points.len() < 2 || variance == 0.0 {
+        return (period, phase);
+    }
+
+    let covariance: f64 = points
+        .iter()
+        .map(|(beat, onset)| (beat - mean_beat) * (onset - mean_onset))
+        .sum();
+    let fitted_period = covariance / variance;
+    if (fitted_period / period - 1.0).abs() > 0.1 {
+        return (period, phase);
+    }
+    // First beat at or after the start of the clip
+    let fitted_phase = (mean_onset - mean_beat * fitted_period).rem_euclid(fitted_period);
+    (fitted_period, fitted_phase)
+}
@@ -1,6 +1,7 @@
+use crate::config::ResampleQuality;
 use crate::error::{PetalSonicError, Result};
 use rubato::{
-    FastFixedIn, PolynomialDegree, Resampler, SincFixedIn, SincInterpolationParameters,
+    FastFixedIn, FftFixedIn, PolynomialDegree, Resampler, SincFixedIn, SincInterpolationParameters,
     SincInterpolationType, WindowFunction,
 };
 
@@ -19,19 +20,91 @@ impl Default for ResamplerType {
     }
 }
 
-enum ResamplerImpl {
+impl From<ResamplerType> for ResampleQuality {
+    fn from(resampler_type: ResamplerType) -> Self {
+        match resampler_type {
+            ResamplerType::Fast => Self::Fast,
+            ResamplerType::Sinc => Self::Sinc(Default::default()),
+        }
+    }
+}
+
+pub(crate) enum ResamplerImpl {
     Fast(FastFixedIn<f32>),
     Sinc(SincFixedIn<f32>),
+    Fft(FftFixedIn<f32>),
 }
 
 impl ResamplerImpl {
-    fn process(
+    /// Create a resampler of the given quality taking `input_frames` frames per call
+    pub(crate) fn new(
+        quality: ResampleQuality,
+        source_sample_rate: u32,
+        target_sample_rate: u32,
+        input_frames: usize,
+        channels: usize,
+    ) -> Result<Self> {
+        // target/source (output/input)
+        let resample_ratio = target_sample_rate as f64 / source_sample_rate as f64;
+        match quality {
+            ResampleQuality::Fast => {
+                let fast = FastFixedIn::new(
+                    resample_ratio,
+                    1.0, // the ratio's always fixed
+                    PolynomialDegree::Septic,
+                    input_frames,
+                    channels,
+                )
+                .map_err(|e| {
+                    PetalSonicError::AudioLoading(format!("Failed to create fast resampler: {}", e))
+                })?;
+                Ok(Self::Fast(fast))
+            }
+            ResampleQuality::Sinc(parameters) => {
+                let params = SincInterpolationParameters {
+                    sinc_len: parameters.sinc_len,
+                    f_cutoff: parameters.f_cutoff,
+                    interpolation: SincInterpolationType::Linear,
+                    oversampling_factor: parameters.oversampling_factor,
+                    window: WindowFunction::BlackmanHarris2,
+                };
+
+                let sinc = SincFixedIn::new(
+                    resample_ratio,
+                    1.0, // we're not changing it dynamically
+                    params,
+                    input_frames,
+                    channels,
+                )
+                .map_err(|e| {
+                    PetalSonicError::AudioLoading(format!("Failed to create sinc resampler: {}", e))
+                })?;
+                Ok(Self::Sinc(sinc))
+            }
+            ResampleQuality::Fft => {
+                let fft = FftFixedIn::new(
+                    source_sample_rate as usize,
+                    target_sample_rate as usize,
+                    input_frames,
+                    2, // sub_chunks
+                    channels,
+                )
+                .map_err(|e| {
+                    PetalSonicError::AudioLoading(format!("Failed to create FFT resampler: {}", e))
+                })?;
+                Ok(Self::Fft(fft))
+            }
+        }
+    }
+
+    pub(crate) fn process(
         &mut self,
         input: &[Vec<f32>],
     ) -> std::result::Result<Vec<Vec<f32>>, rubato::ResampleError> {
         match self {
             Self::Fast(r) => r.process(input, None),
             Self::Sinc(r) => r.process(input, None),
+            Self::Fft(r) => r.process(input, None),
         }
     }
 
@@ -39,6 +112,7 @@ impl ResamplerImpl {
         match self {
             Self::Fast(r) => r.reset(),
             Self::Sinc(r) => r.reset(),
+            Self::Fft(r) => r.reset(),
         }
     }
 }
@@ -73,6 +147,25 @@ impl StreamingResampler {
         channels: u16,
         input_frames: usize,
         resampler_type: Option<ResamplerType>,
+    ) -> Result<Self> {
+        Self::with_quality(
+            source_sample_rate,
+            target_sample_rate,
+            channels,
+            input_frames,
+            resampler_type.unwrap_or_default().into(),
+        )
+    }
+
+    /// Creates a new streaming resampler with fixed input size and the given quality
+    ///
+    /// Like [`Self::new`], with the sinc parameters or the FFT converter selectable.
+    pub fn with_quality(
+        source_sample_rate: u32,
+        target_sample_rate: u32,
+        channels: u16,
+        input_frames: usize,
+        quality: ResampleQuality,
     ) -> Result<Self> {
         if source_sample_rate == 0 || target_sample_rate == 0 {
             return Err(PetalSonicError::AudioFormat(
@@ -92,54 +185,21 @@ impl StreamingResampler {
             ));
         }
 
-        // target/source (output/input)
-        let resample_ratio = target_sample_rate as f64 / source_sample_rate as f64;
-        let resampler_type = resampler_type.unwrap_or_default();
-
         log::info!(
             "Creating {:?} resampler: {} Hz -> {} Hz (fixed input: {} frames)",
-            resampler_type,
+            quality,
             source_sample_rate,
             target_sample_rate,
             input_frames
         );
 
-        let resampler = match resampler_type {
-            ResamplerType::Fast => {
-                let fast = FastFixedIn::new(
-                    resample_ratio,
-                    1.0, // the ratio's always fixed
-                    PolynomialDegree::Septic,
-                    input_frames,
-                    channels as usize,
-                )
-                .map_err(|e| {
-                    PetalSonicError::AudioLoading(format!("Failed to create fast resampler: {}", e))
-                })?;
-                ResamplerImpl::Fast(fast)
-            }
-            ResamplerType::Sinc => {
-                let params = SincInterpolationParameters {
-                    sinc_len: 256,
-                    f_cutoff: 0.95,
-                    interpolation: SincInterpolationType::Linear,
-                    oversampling_factor: 256,
-                    window: WindowFunction::BlackmanHarris2,
-                };
-
-                let sinc = SincFixedIn::new(
-                    resample_ratio,
-                    1.0, // we're not changing it dynamically
-                    params,
-                    input_frames,
-                    channels as usize,
-                )
-                .map_err(|e| {
-                    PetalSonicError::AudioLoading(format!("Failed to create sinc resampler: {}", e))
-                })?;
-                ResamplerImpl::Sinc(sinc)
-            }
-        };
+        let resampler = ResamplerImpl::new(
+            quality,
+            source_sample_rate,
+            target_sample_rate,
+            input_frames,
+            channels as usize,
+        )?;
 
         Ok(Self {
             resampler,
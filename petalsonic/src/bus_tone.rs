@@ -0,0 +1,103 @@
+//! Tone controls of output buses.
+//!
+//! A [`BusTone`] is a low shelf ("bass") and a high shelf ("treble") applied to all sources
+//! on a bus, for quick mix adjustments such as a "brightness" slider in an options menu:
+//!
+//! ```ignore
+//! world.set_source_bus(dialog, Some("dialog"))?;
+//! world.set_bus_tone(Some("dialog"), BusTone::tilt(3.0))?;
+//! // Sources on no bus (the main mix) have a tone too
+//! world.set_bus_tone(None, BusTone { bass_db: -2.0, treble_db: 0.0 })?;
+//! ```
+//!
+//! The shelves are applied to each source's signal before it is spatialized, so they color
+//! the bus the same way a filter on its mix would, including the source's reverb send.
+//! Changes take effect from the next rendered block.
+
+use crate::error::{PetalSonicError, Result};
+use crate::loudness::Biquad;
+use crate::output_eq::EqBand;
+
+/// Corner frequency of the bass shelf
+pub const BASS_CORNER_HZ: f32 = 250.0;
+/// Corner frequency of the treble shelf
+pub const TREBLE_CORNER_HZ: f32 = 4_000.0;
+/// Largest boost or cut of either shelf
+pub const MAX_TONE_DB: f32 = 15.0;
+/// Q of both shelves (a shelf slope of 1, without overshoot)
+const SHELF_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Bass and treble shelves of a bus, in dB (0 is flat)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BusTone {
+    /// Boost (positive) or cut below [`BASS_CORNER_HZ`]
+    pub bass_db: f32,
+    /// Boost (positive) or cut above [`TREBLE_CORNER_HZ`]
+    pub treble_db: f32,
+}
+
+impl BusTone {
+    /// Spectrum tilt around the middle of the range: positive `brightness_db` raises the
+    /// treble and lowers the bass by half of it each
+    pub fn tilt(brightness_db: f32) -> Self {
+        Self {
+            bass_db: -brightness_db / 2.0,
+            treble_db: brightness_db / 2.0,
+        }
+    }
+
+    /// True if neither shelf boosts or cuts
+    pub fn is_flat(&self) -> bool {
+        self.bass_db == 0.0 && self.treble_db == 0.0
+    }
+
+    /// Check that both gains are finite and within [`MAX_TONE_DB`]
+    pub fn validate(&self) -> Result<()> {
+        for (name, gain_db) in [("bass", self.bass_db), ("treble", self.treble_db)] {
+            if !gain_db.is_finite() || gain_db.abs() > MAX_TONE_DB {
+                return Err(PetalSonicError::Configuration(format!(
+                    "Tone {} gain must be within ±{} dB, got {}",
+                    name, MAX_TONE_DB, gain_db
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-source state of the tone of its bus, applied on the render thread
+#[derive(Debug, Clone)]
+pub(crate) struct ToneFilter {
+    bass: Biquad,
+    treble: Biquad,
+}
+
+impl ToneFilter {
+    pub fn new(tone: &BusTone, sample_rate: u32) -> Self {
+        let (bass, treble) = Self::shelves(tone, sample_rate);
+        Self { bass, treble }
+    }
+
+    /// Take the gains of `tone`, keeping the filter state so the change does not click
+    pub fn set_tone(&mut self, tone: &BusTone, sample_rate: u32) {
+        let (bass, treble) = Self::shelves(tone, sample_rate);
+        self.bass.set_coefficients(&bass);
+        self.treble.set_coefficients(&treble);
+    }
+
+    fn shelves(tone: &BusTone, sample_rate: u32) -> (Biquad, Biquad) {
+        (
+            EqBand::low_shelf(BASS_CORNER_HZ, tone.bass_db, SHELF_Q).biquad(sample_rate),
+            EqBand::high_shelf(TREBLE_CORNER_HZ, tone.treble_db, SHELF_Q).biquad(sample_rate),
+        )
+    }
+
+    /// Apply both shelves to a block of mono samples in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let value = self.bass.process(*sample as f64);
+            *sample = self.treble.process(value) as f32;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Caption events for subtitles and closed captions.
+//!
+//! A [`Caption`] attached to a source with
+//! [`PetalSonicWorld::set_caption`](crate::PetalSonicWorld::set_caption) (or for a single
+//! call with [`PetalSonicWorld::play_with_caption`](crate::PetalSonicWorld::play_with_caption))
+//! is emitted as [`PetalSonicEvent::Caption`](crate::PetalSonicEvent::Caption) when the
+//! render thread renders the first block of the playback, so on-screen captions follow the
+//! dialogue as it is actually heard rather than when `play` was called:
+//!
+//! ```ignore
+//! world.play_with_caption(
+//!     line,
+//!     LoopMode::Once,
+//!     Caption::new("Get down!")
+//!         .with_cue(Duration::from_millis(0), "Get")
+//!         .with_cue(Duration::from_millis(250), "down!"),
+//! )?;
+//! for event in engine.poll_events() {
+//!     match event {
+//!         PetalSonicEvent::Caption { text, duration, .. } => show_caption(&text, duration),
+//!         PetalSonicEvent::CaptionCue { text, .. } => highlight_word(&text),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! Cues are positions in the clip: they fire when playback passes them, so a source
+//! played from the middle of its clip skips the earlier cues, and a looping source
+//! captions every pass. Clips played in reverse emit the caption but no cues.
+
+use crate::events::PetalSonicEvent;
+use crate::world::SourceId;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Caption text of a source, with optional word-level cues
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption {
+    /// Text shown while the source plays
+    pub text: String,
+    /// How long the caption stays on screen; `None` for the rest of the clip from where
+    /// playback started
+    pub duration: Option<Duration>,
+    /// Cue points sorted by offset
+    pub cues: Vec<CaptionCue>,
+}
+
+/// Text emitted when playback passes an offset of the clip
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionCue {
+    /// Position in the clip
+    pub offset: Duration,
+    pub text: String,
+}
+
+impl Caption {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            duration: None,
+            cues: Vec::new(),
+        }
+    }
+
+    /// Show the caption for `duration` instead of the rest of the clip
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Add a cue emitting `text` when playback passes `offset` of the clip
+    pub fn with_cue(mut self, offset: Duration, text: impl Into<String>) -> Self {
+        let index = self.cues.partition_point(|cue| cue.offset <= offset);
+        self.cues.insert(
+            index,
+            CaptionCue {
+                offset,
+                text: text.into(),
+            },
+        );
+        self
+    }
+}
+
+/// Render-side state of a source's caption
+#[derive(Debug, Clone)]
+pub(crate) struct CaptionTrack {
+    caption: Arc<Caption>,
+    /// The caption itself is still to be emitted for the current playback
+    pending: bool,
+    /// Clip position in seconds up to which cues were emitted
+    position: f64,
+}
+
+impl CaptionTrack {
+    pub(crate) fn new(caption: Arc<Caption>) -> Self {
+        Self {
+            caption,
+            pending: false,
+            position: 0.0,
+        }
+    }
+
+    /// Arm the caption for a playback starting at `position` seconds of the clip
+    pub(crate) fn restart(&mut self, position: f64) {
+        self.pending = true;
+        self.position = position;
+    }
+
+    /// Emit the caption and the cues passed since the last block, now that playback is at
+    /// `position` seconds of a clip `length` seconds long
+    pub(crate) fn advance(
+        &mut self,
+        source_id: SourceId,
+        position: f64,
+        length: f64,
+        events: &mut Vec<PetalSonicEvent>,
+    ) {
+        if self.pending {
+            self.pending = false;
+            let duration = self
+                .caption
+                .duration
+                .unwrap_or_else(|| Duration::from_secs_f64((length - self.position).max(0.0)));
+            events.push(PetalSonicEvent::Caption {
+                source_id,
+                text: self.caption.text.clone(),
+                duration,
+            });
+        }
+
+        // Reverse playback moves backwards through the clip and passes no cues
+        if position > self.position {
+            for cue in &self.caption.cues {
+                let offset = cue.offset.as_secs_f64();
+                if offset >= self.position && offset < position {
+                    events.push(PetalSonicEvent::CaptionCue {
+                        source_id,
+                        text: cue.text.clone(),
+                        offset: cue.offset,
+                    });
+                }
+            }
+        }
+        self.position = position;
+    }
+}
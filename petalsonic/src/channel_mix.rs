@@ -0,0 +1,403 @@
+//! Channel layouts and down-/up-mixing.
+//!
+//! [`ChannelLayout`] describes which speaker each channel of a buffer feeds, so that e.g. a
+//! 5.1 file can be remapped or downmixed correctly instead of being treated as "6 channels".
+//!
+//! The world always renders `PetalSonicWorldDesc::channels` channels. When the output device
+//! cannot open a stream with that many channels (e.g. a mono Bluetooth headset or a 5.1
+//! interface), the engine opens the device with its own channel count and converts every
+//! frame through a [`ChannelMixMatrix`] in the audio callback.
+
+use crate::error::{PetalSonicError, Result};
+
+/// Gain of a channel folded into two others (-3 dB)
+const FOLD_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Speaker position of a single channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelPosition {
+    /// Single channel of a mono buffer
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    /// Low frequency effects (subwoofer)
+    LowFrequency,
+    BackLeft,
+    BackRight,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    /// Position not known or not supported (e.g. height channels)
+    Unknown,
+}
+
+impl ChannelPosition {
+    /// Output positions to fold this channel into when the output layout lacks it,
+    /// tried in order, with the gain to use
+    fn fold_targets(self) -> &'static [&'static [(ChannelPosition, f32)]] {
+        use ChannelPosition::*;
+        match self {
+            Mono => &[
+                &[(FrontLeft, 1.0), (FrontRight, 1.0)],
+                &[(FrontCenter, 1.0)],
+            ],
+            FrontLeft | FrontRight => &[&[(Mono, 1.0)]],
+            FrontCenter => &[&[(FrontLeft, FOLD_GAIN), (FrontRight, FOLD_GAIN)]],
+            BackLeft => &[&[(SideLeft, 1.0)], &[(FrontLeft, FOLD_GAIN)]],
+            BackRight => &[&[(SideRight, 1.0)], &[(FrontRight, FOLD_GAIN)]],
+            SideLeft => &[&[(BackLeft, 1.0)], &[(FrontLeft, FOLD_GAIN)]],
+            SideRight => &[&[(BackRight, 1.0)], &[(FrontRight, FOLD_GAIN)]],
+            BackCenter => &[
+                &[(BackLeft, FOLD_GAIN), (BackRight, FOLD_GAIN)],
+                &[(SideLeft, FOLD_GAIN), (SideRight, FOLD_GAIN)],
+                &[(FrontLeft, FOLD_GAIN), (FrontRight, FOLD_GAIN)],
+            ],
+            FrontLeftOfCenter => &[&[(FrontLeft, 1.0)]],
+            FrontRightOfCenter => &[&[(FrontRight, 1.0)]],
+            LowFrequency | Unknown => &[],
+        }
+    }
+}
+
+/// Ordered speaker positions of the channels of an interleaved buffer
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelLayout {
+    positions: Vec<ChannelPosition>,
+}
+
+impl ChannelLayout {
+    /// Create a layout from channel positions, in buffer order
+    pub fn new(positions: Vec<ChannelPosition>) -> Self {
+        Self { positions }
+    }
+
+    /// Single mono channel
+    pub fn mono() -> Self {
+        Self::new(vec![ChannelPosition::Mono])
+    }
+
+    /// Front left and right
+    pub fn stereo() -> Self {
+        use ChannelPosition::*;
+        Self::new(vec![FrontLeft, FrontRight])
+    }
+
+    /// Front left/right and back left/right
+    pub fn quad() -> Self {
+        use ChannelPosition::*;
+        Self::new(vec![FrontLeft, FrontRight, BackLeft, BackRight])
+    }
+
+    /// 5.1 in WAV order: FL, FR, C, LFE, BL, BR
+    pub fn surround_5_1() -> Self {
+        use ChannelPosition::*;
+        Self::new(vec![
+            FrontLeft,
+            FrontRight,
+            FrontCenter,
+            LowFrequency,
+            BackLeft,
+            BackRight,
+        ])
+    }
+
+    /// 7.1 in WAV order: FL, FR, C, LFE, BL, BR, SL, SR
+    pub fn surround_7_1() -> Self {
+        use ChannelPosition::*;
+        Self::new(vec![
+            FrontLeft,
+            FrontRight,
+            FrontCenter,
+            LowFrequency,
+            BackLeft,
+            BackRight,
+            SideLeft,
+            SideRight,
+        ])
+    }
+
+    /// Conventional layout for a channel count (mono, stereo, quad, 5.1, 7.1), or
+    /// [`ChannelPosition::Unknown`] for every channel otherwise
+    pub fn default_for(channels: u16) -> Self {
+        match channels {
+            1 => Self::mono(),
+            2 => Self::stereo(),
+            4 => Self::quad(),
+            6 => Self::surround_5_1(),
+            8 => Self::surround_7_1(),
+            _ => Self::new(vec![ChannelPosition::Unknown; channels as usize]),
+        }
+    }
+
+    /// Channel positions in buffer order
+    pub fn positions(&self) -> &[ChannelPosition] {
+        &self.positions
+    }
+
+    /// Number of channels
+    pub fn channels(&self) -> u16 {
+        self.positions.len() as u16
+    }
+
+    /// Index of the channel at `position`, if present
+    pub fn index_of(&self, position: ChannelPosition) -> Option<usize> {
+        self.positions.iter().position(|p| *p == position)
+    }
+
+    /// Returns true if any channel position is unknown
+    pub fn has_unknown(&self) -> bool {
+        self.positions.contains(&ChannelPosition::Unknown)
+    }
+}
+
+/// Gain matrix mapping input (world) channels to output (device) channels.
+///
+/// `gain(output, input)` is the amount of input channel `input` mixed into output channel
+/// `output`. Channel order follows the usual WAV/SMPTE layout (L, R, C, LFE, Ls, Rs, ...).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawChannelMixMatrix")
+)]
+pub struct ChannelMixMatrix {
+    input_channels: u16,
+    output_channels: u16,
+    /// Row-major gains, one row of `input_channels` gains per output channel
+    gains: Vec<f32>,
+}
+
+/// Deserialized form of a [`ChannelMixMatrix`], checked by [`ChannelMixMatrix::new`]
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawChannelMixMatrix {
+    input_channels: u16,
+    output_channels: u16,
+    gains: Vec<f32>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawChannelMixMatrix> for ChannelMixMatrix {
+    type Error = PetalSonicError;
+
+    fn try_from(raw: RawChannelMixMatrix) -> Result<Self> {
+        Self::new(raw.input_channels, raw.output_channels, raw.gains)
+    }
+}
+
+impl ChannelMixMatrix {
+    /// Create a matrix from row-major gains (one row per output channel)
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Configuration` if a channel count is zero or `gains` does
+    /// not contain `input_channels * output_channels` values.
+    pub fn new(input_channels: u16, output_channels: u16, gains: Vec<f32>) -> Result<Self> {
+        if input_channels == 0 || output_channels == 0 {
+            return Err(PetalSonicError::Configuration(
+                "Mix matrix channel counts must be greater than 0".to_string(),
+            ));
+        }
+
+        let expected = input_channels as usize * output_channels as usize;
+        if gains.len() != expected {
+            return Err(PetalSonicError::Configuration(format!(
+                "Mix matrix {}x{} needs {} gains, got {}",
+                output_channels,
+                input_channels,
+                expected,
+                gains.len()
+            )));
+        }
+
+        Ok(Self {
+            input_channels,
+            output_channels,
+            gains,
+        })
+    }
+
+    /// Matrix that passes through the first `min(input, output)` channels unchanged
+    pub fn identity(input_channels: u16, output_channels: u16) -> Self {
+        let mut matrix = Self::silent(input_channels, output_channels);
+        for channel in 0..input_channels.min(output_channels) {
+            matrix.set_gain(channel, channel, 1.0);
+        }
+        matrix
+    }
+
+    /// Default matrix for converting `input_channels` to `output_channels`
+    ///
+    /// - Same channel count: identity
+    /// - Mono input: copied to the front left and right channels
+    /// - Mono output: average of all input channels except LFE
+    /// - 5.1 to stereo: ITU-R BS.775 downmix (center and surrounds at -3 dB, LFE dropped)
+    /// - Stereo to more channels: front left/right only, other channels silent
+    /// - Otherwise: shared channels pass through, extra input channels are dropped
+    pub fn default_for(input_channels: u16, output_channels: u16) -> Self {
+        let input_channels = input_channels.max(1);
+        let output_channels = output_channels.max(1);
+        let mut matrix = Self::silent(input_channels, output_channels);
+
+        match (input_channels, output_channels) {
+            (input, output) if input == output => return Self::identity(input, output),
+            (1, output) => {
+                for channel in 0..output.min(2) {
+                    matrix.set_gain(channel, 0, 1.0);
+                }
+            }
+            (input, 1) => {
+                let lfe = (input == 6 || input == 8).then_some(3);
+                let summed = (0..input).filter(|channel| Some(*channel) != lfe);
+                let gain = 1.0 / summed.clone().count() as f32;
+                for channel in summed {
+                    matrix.set_gain(0, channel, gain);
+                }
+            }
+            (6, 2) => {
+                matrix.set_gain(0, 0, 1.0);
+                matrix.set_gain(1, 1, 1.0);
+                matrix.set_gain(0, 2, FOLD_GAIN);
+                matrix.set_gain(1, 2, FOLD_GAIN);
+                matrix.set_gain(0, 4, FOLD_GAIN);
+                matrix.set_gain(1, 5, FOLD_GAIN);
+            }
+            (input, output) => return Self::identity(input, output),
+        }
+
+        matrix
+    }
+
+    /// Matrix converting between two channel layouts by speaker position
+    ///
+    /// Channels present in both layouts are copied, channels missing from the output are
+    /// folded into the nearest available speakers (e.g. center into left and right at -3 dB),
+    /// and LFE is dropped when the output has no LFE channel. A mono output receives the
+    /// average of all non-LFE input channels. Falls back to [`Self::default_for`] if either
+    /// layout contains unknown positions.
+    pub fn for_layouts(input: &ChannelLayout, output: &ChannelLayout) -> Self {
+        if input.has_unknown() || output.has_unknown() {
+            return Self::default_for(input.channels(), output.channels());
+        }
+
+        let mut matrix = Self::silent(input.channels().max(1), output.channels().max(1));
+
+        if output.positions() == [ChannelPosition::Mono] {
+            let summed: Vec<usize> = (0..input.positions().len())
+                .filter(|&channel| input.positions()[channel] != ChannelPosition::LowFrequency)
+                .collect();
+            for &channel in &summed {
+                matrix.set_gain(0, channel as u16, 1.0 / summed.len() as f32);
+            }
+            return matrix;
+        }
+
+        for (in_channel, &position) in input.positions().iter().enumerate() {
+            if let Some(out_channel) = output.index_of(position) {
+                matrix.set_gain(out_channel as u16, in_channel as u16, 1.0);
+                continue;
+            }
+
+            let targets = position.fold_targets().iter().find(|targets| {
+                targets
+                    .iter()
+                    .all(|(target, _)| output.index_of(*target).is_some())
+            });
+            for (target, gain) in targets.copied().unwrap_or_default() {
+                if let Some(out_channel) = output.index_of(*target) {
+                    matrix.set_gain(out_channel as u16, in_channel as u16, *gain);
+                }
+            }
+        }
+
+        matrix
+    }
+
+    fn silent(input_channels: u16, output_channels: u16) -> Self {
+        Self {
+            input_channels,
+            output_channels,
+            gains: vec![0.0; input_channels as usize * output_channels as usize],
+        }
+    }
+
+    /// Number of input (world) channels
+    pub fn input_channels(&self) -> u16 {
+        self.input_channels
+    }
+
+    /// Number of output (device) channels
+    pub fn output_channels(&self) -> u16 {
+        self.output_channels
+    }
+
+    /// Gain of input channel `input` in output channel `output`
+    pub fn gain(&self, output: u16, input: u16) -> f32 {
+        self.gains
+            .get(self.index(output, input))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Set the gain of input channel `input` in output channel `output`
+    ///
+    /// Out-of-range channels are ignored.
+    pub fn set_gain(&mut self, output: u16, input: u16, gain: f32) {
+        if output < self.output_channels && input < self.input_channels {
+            let index = self.index(output, input);
+            self.gains[index] = gain;
+        }
+    }
+
+    fn index(&self, output: u16, input: u16) -> usize {
+        output as usize * self.input_channels as usize + input as usize
+    }
+
+    /// Convert one interleaved frame
+    ///
+    /// `input` must hold `input_channels` samples and `output` `output_channels` samples.
+    pub fn apply(&self, input: &[f32], output: &mut [f32]) {
+        for (sample, row) in output
+            .iter_mut()
+            .zip(self.gains.chunks_exact(self.input_channels as usize))
+        {
+            *sample = row.iter().zip(input).map(|(gain, x)| gain * x).sum();
+        }
+    }
+}
+
+/// A mix matrix with preallocated frame buffers, for use in the audio callback
+pub(crate) struct FrameMixer {
+    matrix: ChannelMixMatrix,
+    input: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl FrameMixer {
+    pub fn new(matrix: ChannelMixMatrix) -> Self {
+        Self {
+            input: vec![0.0; matrix.input_channels as usize],
+            output: vec![0.0; matrix.output_channels as usize],
+            matrix,
+        }
+    }
+
+    /// Channel count of the frames returned by [`Self::mix`]
+    pub fn output_channels(&self) -> u16 {
+        self.matrix.output_channels
+    }
+
+    /// Input frame buffer to fill before calling [`Self::mix`]
+    pub fn input_mut(&mut self) -> &mut [f32] {
+        &mut self.input
+    }
+
+    /// Convert the input frame and return the output frame
+    pub fn mix(&mut self) -> &[f32] {
+        self.matrix.apply(&self.input, &mut self.output);
+        &self.output
+    }
+}
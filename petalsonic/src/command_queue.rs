@@ -0,0 +1,117 @@
+//! Bounded queue carrying playback commands from the world to the render thread.
+//!
+//! [`PetalSonicWorld`](crate::PetalSonicWorld) methods such as `play()` and `stop()` push
+//! [`PlaybackCommand`]s into a fixed-size single-producer single-consumer ring buffer,
+//! allocated once when the world is created. Producers on the main side take turns through
+//! a mutex; the render thread is the only consumer and drains the queue before each batch
+//! of blocks it renders, without blocking.
+//!
+//! When the render thread falls behind (or no engine is rendering the world) and the queue
+//! fills up, further commands are rejected with an error instead of growing the queue, and
+//! counted in [`CommandQueueStats::rejected`]. Size the queue for the largest burst of
+//! commands sent between two render batches with
+//! [`PetalSonicWorldDesc::command_queue_capacity`](crate::PetalSonicWorldDesc::command_queue_capacity).
+
+use crate::error::{PetalSonicError, Result};
+use crate::playback::PlaybackCommand;
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Observer, Producer, Split},
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Fill state of a world's command queue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommandQueueStats {
+    /// Number of commands the queue holds
+    pub capacity: usize,
+    /// Commands waiting for the render thread
+    pub pending: usize,
+    /// Commands rejected because the queue was full, since the world was created
+    pub rejected: u64,
+}
+
+/// Command queue shared by a world and the engine rendering it
+pub(crate) struct CommandQueue {
+    producer: Mutex<HeapProd<PlaybackCommand>>,
+    consumer: Mutex<HeapCons<PlaybackCommand>>,
+    rejected: AtomicU64,
+}
+
+impl CommandQueue {
+    pub fn new(capacity: usize) -> Self {
+        let (producer, consumer) = HeapRb::new(capacity.max(1)).split();
+        Self {
+            producer: Mutex::new(producer),
+            consumer: Mutex::new(consumer),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a command for the render thread
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Engine` if the queue is full.
+    pub fn send(&self, command: PlaybackCommand) -> Result<()> {
+        let mut producer = self.producer.lock().unwrap();
+        match producer.try_push(command) {
+            Ok(()) => Ok(()),
+            Err(command) => Err(self.reject(&producer, 1, &command)),
+        }
+    }
+
+    /// Queue commands the render thread must see together, e.g. a play and the limit of
+    /// that playback: either all of them are queued, or none is
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Engine` if the queue has no room for all of them.
+    pub fn send_all(&self, commands: Vec<PlaybackCommand>) -> Result<()> {
+        let mut producer = self.producer.lock().unwrap();
+        if producer.vacant_len() < commands.len() {
+            return Err(self.reject(&producer, commands.len(), &commands));
+        }
+        producer.push_iter(commands.into_iter());
+        Ok(())
+    }
+
+    /// Count `count` rejected commands and describe them in an error
+    fn reject(
+        &self,
+        producer: &HeapProd<PlaybackCommand>,
+        count: usize,
+        commands: &dyn std::fmt::Debug,
+    ) -> PetalSonicError {
+        let previous = self.rejected.fetch_add(count as u64, Ordering::Relaxed);
+        let rejected = previous + count as u64;
+        // Warn whenever the total crosses a power of two
+        if previous.checked_ilog2() != rejected.checked_ilog2() {
+            log::warn!(
+                "Command queue full ({} commands), {} commands rejected so far",
+                producer.capacity(),
+                rejected
+            );
+        }
+        PetalSonicError::Engine(format!(
+            "Command queue full ({} commands), dropped {:?}",
+            producer.capacity(),
+            commands
+        ))
+    }
+
+    /// The consumer side, for the render thread; `None` while it is held elsewhere
+    pub fn try_consumer(&self) -> Option<MutexGuard<'_, HeapCons<PlaybackCommand>>> {
+        self.consumer.try_lock().ok()
+    }
+
+    pub fn stats(&self) -> CommandQueueStats {
+        let producer = self.producer.lock().unwrap();
+        CommandQueueStats {
+            capacity: producer.capacity().get(),
+            pending: producer.occupied_len(),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
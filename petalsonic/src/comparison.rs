@@ -0,0 +1,60 @@
+//! A/B comparison of spatial settings.
+//!
+//! Tuning an HRTF or a quality preset by ear needs both versions of the same moment, not two
+//! takes of a scene that moved on in between.
+//! [`PetalSonicEngine::render_ab`](crate::PetalSonicEngine::render_ab) renders the block of
+//! the scene that would play next twice, once with each [`SpatialVariant`], from the same
+//! playback state. Both renders start at the same frame and have the same length, so they
+//! can be played back to back or toggled between at any frame without a jump in time:
+//!
+//! ```ignore
+//! let default_hrtf = SpatialVariant::from_desc(engine.config());
+//! let personalized = SpatialVariant {
+//!     hrtf: HrtfConfig {
+//!         sofa_path: Some("hrtf/subject_12.sofa".into()),
+//!         ..default_hrtf.hrtf.clone()
+//!     },
+//!     ..default_hrtf.clone()
+//! };
+//! let ab = engine.render_ab(&default_hrtf, &personalized, 5 * 48_000)?;
+//! let a = PetalSonicAudioData::from_samples(ab.a, ab.sample_rate, ab.channels)?;
+//! let b = PetalSonicAudioData::from_samples(ab.b, ab.sample_rate, ab.channels)?;
+//! ```
+//!
+//! Each variant is rendered with a fresh spatial processor, so neither inherits the effect
+//! state of the other. The scene itself does not advance; rendering again renders the same
+//! block. Events of the renders are discarded.
+
+use crate::config::{HrtfConfig, PetalSonicWorldDesc, SpatialQuality};
+
+/// HRTF and quality a scene is rendered with in an A/B comparison
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialVariant {
+    pub hrtf: HrtfConfig,
+    pub quality: SpatialQuality,
+}
+
+impl SpatialVariant {
+    /// The HRTF and quality set in `desc`, e.g. an engine's current settings from
+    /// [`PetalSonicEngine::config`](crate::PetalSonicEngine::config)
+    pub fn from_desc(desc: &PetalSonicWorldDesc) -> Self {
+        Self {
+            hrtf: desc.hrtf_config(),
+            quality: desc.spatial_quality,
+        }
+    }
+}
+
+/// The same block of a scene rendered with two spatial variants
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbRender {
+    /// Interleaved samples rendered with the first variant
+    pub a: Vec<f32>,
+    /// Interleaved samples rendered with the second variant, aligned frame for frame
+    /// with `a`
+    pub b: Vec<f32>,
+    /// Sample rate of both renders (the world's)
+    pub sample_rate: u32,
+    /// Channels of both renders (the world's)
+    pub channels: u16,
+}
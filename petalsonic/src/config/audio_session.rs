@@ -0,0 +1,46 @@
+/// How the application's audio mixes with other audio on the device.
+///
+/// On iOS this maps to the `AVAudioSession` category. Other platforms ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioSessionCategory {
+    /// Game audio that respects the silent switch and mixes with other apps
+    /// (`AVAudioSessionCategoryAmbient`)
+    Ambient,
+    /// Game audio that respects the silent switch and silences other apps
+    /// (`AVAudioSessionCategorySoloAmbient`)
+    #[default]
+    SoloAmbient,
+    /// Audio that keeps playing with the silent switch on (`AVAudioSessionCategoryPlayback`)
+    Playback,
+}
+
+/// Device buffer size hint trading output latency for power usage.
+///
+/// Applied by requesting a fixed device buffer size within the range the device supports.
+/// On Android the buffer size is the AAudio frames-per-callback value; AAudio's own
+/// performance mode is not exposed by the audio backend, so the stream is always opened in
+/// AAudio's default mode and only the buffer size follows this hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputBufferSizeHint {
+    /// Let the device choose its buffer size
+    #[default]
+    Default,
+    /// Request small device buffers for minimum output latency
+    LowLatency,
+    /// Request large device buffers so the device wakes up less often
+    PowerSaving,
+}
+
+/// Platform audio session configuration, mainly relevant on mobile.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioSessionConfig {
+    /// Session category (iOS only)
+    pub category: AudioSessionCategory,
+    /// Allow other apps' audio to keep playing alongside ours (iOS only)
+    pub mix_with_others: bool,
+    /// Device buffer size hint for the output latency / power trade-off
+    pub buffer_size_hint: OutputBufferSizeHint,
+}
@@ -0,0 +1,65 @@
+/// How the loudness of an HRTF is normalized when it is loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HrtfNormalization {
+    /// Use the HRTF as measured (default)
+    #[default]
+    None,
+    /// Normalize to a common RMS level, so HRTFs measured at different levels (e.g.
+    /// personalized SOFA files) sound equally loud
+    RootMeanSquared,
+}
+
+/// HRTF used for binaural rendering
+///
+/// Built from `PetalSonicWorldDesc::hrtf_path`, `hrtf_volume_db` and `hrtf_normalization`
+/// with `PetalSonicWorldDesc::hrtf_config`, and replaceable at runtime with
+/// `PetalSonicEngine::set_hrtf`, e.g. to load a user's personalized SOFA file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HrtfConfig {
+    /// Path to a SOFA file (`None` uses Steam Audio's default HRTF); relative paths are
+    /// looked up in the asset directories (see [`crate::assets`])
+    pub sofa_path: Option<String>,
+    /// Gain applied to the HRTF in dB
+    pub volume_db: f32,
+    pub normalization: HrtfNormalization,
+}
+
+/// Interaural cues that can be adjusted while rendering, e.g. from an accessibility or
+/// calibration screen (see `PetalSonicEngine::set_listener_calibration`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListenerCalibration {
+    /// Head radius in meters, which sets the interaural time difference of panned sources
+    /// (clamped to 0.05..=0.15). HRTF rendering uses the delays measured in the HRTF.
+    pub head_radius: f32,
+    /// Scale of the differences between the ears: 1 leaves them unchanged, 0 collapses
+    /// spatial sources to the center, values above 1 exaggerate them (up to 2)
+    pub interaural_width: f32,
+}
+
+impl ListenerCalibration {
+    /// Smallest supported head radius in meters
+    pub const MIN_HEAD_RADIUS: f32 = 0.05;
+    /// Largest supported head radius in meters
+    pub const MAX_HEAD_RADIUS: f32 = 0.15;
+
+    /// Returns the calibration with both parameters clamped to their supported ranges
+    pub fn clamped(self) -> Self {
+        Self {
+            head_radius: self
+                .head_radius
+                .clamp(Self::MIN_HEAD_RADIUS, Self::MAX_HEAD_RADIUS),
+            interaural_width: self.interaural_width.clamp(0.0, 2.0),
+        }
+    }
+}
+
+impl Default for ListenerCalibration {
+    fn default() -> Self {
+        Self {
+            head_radius: 0.0875,
+            interaural_width: 1.0,
+        }
+    }
+}
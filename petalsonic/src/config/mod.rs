@@ -1,5 +1,26 @@
+mod audio_session;
+mod hrtf;
+mod output_mode;
+mod resample_policy;
+mod resample_quality;
+mod source_clustering;
 mod source_config;
+mod spatial_lod;
+mod spatial_quality;
 mod world_desc;
+mod world_desc_builder;
 
+pub use audio_session::{AudioSessionCategory, AudioSessionConfig, OutputBufferSizeHint};
+pub use hrtf::{HrtfConfig, HrtfNormalization, ListenerCalibration};
+pub use output_mode::OutputMode;
+pub use resample_policy::ResamplePolicy;
+pub use resample_quality::{ResampleQuality, SincParameters};
+pub use source_clustering::SourceClustering;
 pub use source_config::SourceConfig;
-pub use world_desc::PetalSonicWorldDesc;
+pub use spatial_lod::SpatialLod;
+pub use spatial_quality::{SimulationQuality, SpatialQuality};
+pub use world_desc::{
+    MAX_BLOCK_SIZE, MAX_CHANNELS, MAX_SAMPLE_RATE, MIN_BLOCK_SIZE, MIN_SAMPLE_RATE,
+    PetalSonicWorldDesc,
+};
+pub use world_desc_builder::PetalSonicWorldDescBuilder;
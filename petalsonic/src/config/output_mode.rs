@@ -0,0 +1,16 @@
+/// How spatial sources are rendered for the listener's playback device.
+///
+/// Set in `PetalSonicWorldDesc::output_mode` and switchable at runtime with
+/// `PetalSonicEngine::set_output_mode`, e.g. from an audio options menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputMode {
+    /// Binaural HRTF rendering for headphones (default)
+    #[default]
+    Headphones,
+    /// Plain stereo panning for loudspeakers. HRTF cues assume each ear hears only its own
+    /// channel and sound phasey over speakers, so spatial sources are amplitude-panned
+    /// instead (Steam Audio decodes to the stereo layout, the stereo panner drops its
+    /// interaural delay).
+    Speakers,
+}
@@ -0,0 +1,20 @@
+/// When audio registered at a sample rate other than the world's is converted.
+///
+/// Set in `PetalSonicWorldDesc::resample_policy`. Converting at registration doubles the
+/// memory of mismatched assets while the caller keeps the original; the lazy policies
+/// trade that memory for CPU time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResamplePolicy {
+    /// Resample when the audio is registered (default)
+    #[default]
+    Eager,
+    /// Keep the original data and resample it the first time the source is played. The
+    /// converted copy then replaces the original in the world.
+    OnFirstPlay,
+    /// Never store a converted copy: each playback instance converts the clip while it
+    /// renders. Uses cubic interpolation rather than the `asset_resample_quality` converter
+    /// used for eager conversion, since the clip may be read from any position and in
+    /// reverse.
+    Streaming,
+}
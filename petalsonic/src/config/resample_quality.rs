@@ -0,0 +1,85 @@
+/// Algorithm of a sample rate converter.
+///
+/// Set for the conversion of the world's output to the device rate in
+/// `PetalSonicWorldDesc::output_resample_quality`, and for the conversion of registered
+/// audio to the world rate in `PetalSonicWorldDesc::asset_resample_quality`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResampleQuality {
+    /// Septic polynomial interpolation: cheapest, but bright content can alias audibly
+    Fast,
+    /// Windowed sinc interpolation with the given parameters
+    Sinc(SincParameters),
+    /// FFT-based conversion: high quality at a low cost for a fixed ratio, with one block
+    /// of added latency
+    Fft,
+}
+
+/// Parameters of the sinc resampler; longer filters alias less and cost more
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SincParameters {
+    /// Length of the sinc filter in samples
+    pub sinc_len: usize,
+    /// Cutoff relative to the lower of the two Nyquist frequencies, in `0.0..=1.0`
+    pub f_cutoff: f32,
+    /// Sub-sample positions the filter is precomputed for
+    pub oversampling_factor: usize,
+}
+
+impl Default for SincParameters {
+    fn default() -> Self {
+        Self {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            oversampling_factor: 256,
+        }
+    }
+}
+
+impl SincParameters {
+    /// Shorter filter for cheaper conversion with some aliasing near Nyquist
+    pub fn low() -> Self {
+        Self {
+            sinc_len: 64,
+            f_cutoff: 0.915,
+            oversampling_factor: 128,
+        }
+    }
+
+    /// Longer filter for conversion with almost no audible aliasing
+    pub fn high() -> Self {
+        Self {
+            sinc_len: 512,
+            f_cutoff: 0.97,
+            oversampling_factor: 512,
+        }
+    }
+
+    /// Returns an error message if the parameters cannot be used
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        if self.sinc_len == 0 || self.oversampling_factor == 0 {
+            return Err(format!(
+                "Sinc length and oversampling factor must be greater than 0, got {} and {}",
+                self.sinc_len, self.oversampling_factor
+            ));
+        }
+        if !(self.f_cutoff > 0.0 && self.f_cutoff <= 1.0) {
+            return Err(format!(
+                "Sinc cutoff must be in 0.0..=1.0, got {}",
+                self.f_cutoff
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ResampleQuality {
+    /// Returns an error message if the quality's parameters cannot be used
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        match self {
+            Self::Sinc(parameters) => parameters.validate(),
+            Self::Fast | Self::Fft => Ok(()),
+        }
+    }
+}
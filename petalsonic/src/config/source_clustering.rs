@@ -0,0 +1,49 @@
+/// Clustering of distant spatial sources into shared virtual emitters.
+///
+/// Sources at least `min_distance` from the listener whose directions lie within
+/// `max_angle` of each other are mixed after their per-source direct effect (distance
+/// attenuation, air absorption, occlusion) and encoded once, at the average direction of
+/// the group, instead of once each. Crowd and battlefield scenes with many far-field
+/// emitters then cost one encode per cluster. Sources joining or leaving a cluster are
+/// crossfaded over one block, and hold their cluster within slightly wider margins so they
+/// do not flip between clusters near a threshold.
+///
+/// Set in `PetalSonicWorldDesc::source_clustering` and switchable at runtime with
+/// `PetalSonicEngine::set_source_clustering`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceClustering {
+    /// Distance from the listener, in world units, from which sources may be clustered
+    pub min_distance: f32,
+    /// Largest angle in radians, seen from the listener, between a source joining a
+    /// cluster and the cluster's direction
+    pub max_angle: f32,
+}
+
+impl Default for SourceClustering {
+    fn default() -> Self {
+        Self {
+            min_distance: 10.0,
+            max_angle: 0.2,
+        }
+    }
+}
+
+impl SourceClustering {
+    /// Returns an error message if the parameters cannot be used
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        if !(self.min_distance >= 0.0 && self.min_distance.is_finite()) {
+            return Err(format!(
+                "Minimum distance must be finite and non-negative, got {}",
+                self.min_distance
+            ));
+        }
+        if !(self.max_angle > 0.0 && self.max_angle <= std::f32::consts::PI) {
+            return Err(format!(
+                "Maximum angle must be in (0, pi] radians, got {}",
+                self.max_angle
+            ));
+        }
+        Ok(())
+    }
+}
@@ -11,6 +11,9 @@ pub enum SourceConfig {
         position: Vec3,
         /// Volume multiplier (0.0 = silent, 1.0 = full volume)
         volume: f32,
+        /// Amount of the source fed to the reverb bus (0.0 = dry, 1.0 = full send). Only
+        /// used when the world has a reverb (see `PetalSonicWorldDesc::reverb`).
+        reverb_send: f32,
     },
 }
 
@@ -31,12 +34,25 @@ impl SourceConfig {
         Self::Spatial {
             position,
             volume: 1.0,
+            reverb_send: 1.0,
         }
     }
 
     /// Create a spatial source configuration with position and volume
     pub fn spatial_with_volume(position: Vec3, volume: f32) -> Self {
-        Self::Spatial { position, volume }
+        Self::Spatial {
+            position,
+            volume,
+            reverb_send: 1.0,
+        }
+    }
+
+    /// Set the reverb send amount of a spatial source (no effect on non-spatial sources)
+    pub fn with_reverb_send(mut self, send: f32) -> Self {
+        if let Self::Spatial { reverb_send, .. } = &mut self {
+            *reverb_send = send.max(0.0);
+        }
+        self
     }
 
     /// Returns true if this is a spatial source
@@ -59,4 +75,12 @@ impl SourceConfig {
             Self::NonSpatial => None,
         }
     }
+
+    /// Returns the reverb send amount (0.0 for non-spatial sources)
+    pub fn reverb_send(&self) -> f32 {
+        match self {
+            Self::Spatial { reverb_send, .. } => *reverb_send,
+            Self::NonSpatial => 0.0,
+        }
+    }
 }
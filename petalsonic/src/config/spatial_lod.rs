@@ -0,0 +1,53 @@
+/// Level of detail for distant and quiet spatial sources.
+///
+/// Sources at or beyond `far_distance` from the listener, or whose simulated gain
+/// (distance attenuation times occlusion) drops to `quiet_gain`, are rendered at a reduced
+/// level of detail: first-order instead of second-order ambisonics, with their direction
+/// and simulation outputs refreshed only every `update_interval` blocks. Sources switching
+/// level are crossfaded over one block, so scenes with hundreds of emitters spend most of
+/// the spatial processing on the few that are near and loud.
+///
+/// Set in `PetalSonicWorldDesc::spatial_lod` and switchable at runtime with
+/// `PetalSonicEngine::set_spatial_lod`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpatialLod {
+    /// Distance from the listener, in world units, from which sources are reduced
+    pub far_distance: f32,
+    /// Simulated gain at or below which sources are reduced regardless of their distance
+    pub quiet_gain: f32,
+    /// Blocks between two refreshes of a reduced source's direction and simulation outputs
+    pub update_interval: usize,
+}
+
+impl Default for SpatialLod {
+    fn default() -> Self {
+        Self {
+            far_distance: 5.0,
+            quiet_gain: 0.01,
+            update_interval: 4,
+        }
+    }
+}
+
+impl SpatialLod {
+    /// Returns an error message if the parameters cannot be used
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        if !(self.far_distance > 0.0 && self.far_distance.is_finite()) {
+            return Err(format!(
+                "Far distance must be finite and greater than 0, got {}",
+                self.far_distance
+            ));
+        }
+        if !(self.quiet_gain >= 0.0 && self.quiet_gain.is_finite()) {
+            return Err(format!(
+                "Quiet gain must be finite and non-negative, got {}",
+                self.quiet_gain
+            ));
+        }
+        if self.update_interval == 0 {
+            return Err("Update interval must be at least 1 block".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+/// Steam Audio simulation parameters.
+///
+/// Higher values improve the accuracy of simulated effects at the cost of CPU time.
+/// Ray counts, bounces and duration only matter for reflection/pathing simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationQuality {
+    /// Number of rays traced from the listener for reflections
+    pub num_rays: usize,
+    /// Number of times each ray may bounce
+    pub num_bounces: usize,
+    /// Length of the simulated impulse response in seconds
+    pub duration: f32,
+    /// Minimum distance (in meters) used when computing irradiance, avoiding
+    /// infinite energy for sources very close to a surface
+    pub irradiance_min_distance: f32,
+    /// Number of samples used for volumetric occlusion
+    pub num_occlusion_samples: usize,
+}
+
+/// Simulation quality preset for the spatial pipeline.
+///
+/// Set in `PetalSonicWorldDesc::spatial_quality` and switchable at runtime with
+/// `PetalSonicEngine::set_spatial_quality`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpatialQuality {
+    /// Cheapest simulation, for low-end or mobile devices
+    Low,
+    /// Balanced simulation (default)
+    #[default]
+    Medium,
+    /// Most accurate simulation, for desktop-class CPUs
+    High,
+    /// User-provided simulation parameters
+    Custom(SimulationQuality),
+}
+
+impl SpatialQuality {
+    /// Returns the simulation parameters for this preset
+    pub fn settings(&self) -> SimulationQuality {
+        match self {
+            Self::Low => SimulationQuality {
+                num_rays: 256,
+                num_bounces: 4,
+                duration: 1.0,
+                irradiance_min_distance: 1.0,
+                num_occlusion_samples: 8,
+            },
+            Self::Medium => SimulationQuality {
+                num_rays: 1024,
+                num_bounces: 10,
+                duration: 3.0,
+                irradiance_min_distance: 1.0,
+                num_occlusion_samples: 32,
+            },
+            Self::High => SimulationQuality {
+                num_rays: 4096,
+                num_bounces: 32,
+                duration: 3.0,
+                irradiance_min_distance: 1.0,
+                num_occlusion_samples: 64,
+            },
+            Self::Custom(settings) => *settings,
+        }
+    }
+}
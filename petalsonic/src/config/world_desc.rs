@@ -1,22 +1,176 @@
+use crate::channel_mix::ChannelMixMatrix;
+use crate::config::{
+    AudioSessionConfig, HrtfConfig, HrtfNormalization, ListenerCalibration, OutputMode,
+    PetalSonicWorldDescBuilder, ResamplePolicy, ResampleQuality, SourceClustering, SpatialLod,
+    SpatialQuality,
+};
+use crate::dither::Dither;
+use crate::error::{ConfigError, PetalSonicError, Result};
+use crate::focus::BackgroundPolicy;
+use crate::math::CoordinateConvention;
+use crate::memory::MemoryBudget;
+use crate::output_eq::OutputEq;
+use crate::reverb::ReverbSettings;
+use crate::silence::SilenceDetection;
 use std::time::Duration;
 
+/// Smallest block size accepted by the spatial pipeline (Steam Audio frame size lower bound)
+pub const MIN_BLOCK_SIZE: usize = 64;
+
+/// Largest block size accepted by the spatial pipeline (Steam Audio frame size upper bound)
+pub const MAX_BLOCK_SIZE: usize = 8192;
+
+/// Lowest supported world sample rate in Hz
+pub const MIN_SAMPLE_RATE: u32 = 8_000;
+
+/// Highest supported world sample rate in Hz
+pub const MAX_SAMPLE_RATE: u32 = 384_000;
+
+/// Largest supported world channel count
+pub const MAX_CHANNELS: u16 = 64;
+
 /// Configuration descriptor for a PetalSonic world
+///
+/// Build one with [`PetalSonicWorldDesc::builder`] to have it validated up front, or set the
+/// fields directly and let the engine validate it. With the `serde` feature it can be loaded
+/// from a config file; fields missing from the file keep their defaults.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct PetalSonicWorldDesc {
     /// Sample rate for the world processing (may differ from device sample rate)
     pub sample_rate: u32,
     /// Block size in world sample rate (number of frames to generate per audio processing chunk).
     /// This is the fixed number of frames generated at the world's sample rate, which are then
     /// resampled to the device's sample rate (producing variable output based on the ratio).
+    ///
+    /// Must be between [`MIN_BLOCK_SIZE`] and [`MAX_BLOCK_SIZE`]. Values outside that range
+    /// are clamped by [`PetalSonicWorldDesc::validated`] when the engine is created.
     pub block_size: usize,
     /// Number of audio channels (typically 2 for stereo)
+    ///
+    /// If the output device cannot be opened with this many channels, it is opened with its
+    /// default channel count and the world output is converted with `output_mix`.
     pub channels: u16,
+    /// Matrix converting world channels to device channels. `None` uses
+    /// [`ChannelMixMatrix::default_for`] when the channel counts differ. Offline renders
+    /// are converted with it as well.
+    pub output_mix: Option<ChannelMixMatrix>,
     /// Buffer duration for audio processing
     pub buffer_duration: Duration,
     /// Maximum number of concurrent audio sources
     pub max_sources: usize,
-    /// Optional path to a custom HRTF SOFA file (None uses Steam Audio's default HRTF)
+    /// Number of playback commands (`play()`, `stop()`, ...) queued for the render thread
+    /// before further ones are rejected (see [`crate::command_queue`])
+    pub command_queue_capacity: usize,
+    /// Maximum number of sources rendered with HRTF at once, each holding Steam Audio
+    /// effects and a simulator source. Spatial sources beyond it are panned in stereo until
+    /// a slot frees up (see [`PetalSonicEngine::spatial_source_stats`](crate::PetalSonicEngine::spatial_source_stats)).
+    pub max_spatial_sources: usize,
+    /// Optional path to a custom HRTF SOFA file (None uses Steam Audio's default HRTF).
+    /// Relative paths are looked up in the asset directories (see [`crate::assets`]).
     pub hrtf_path: Option<String>,
+    /// Gain applied to the HRTF in dB
+    pub hrtf_volume_db: f32,
+    /// Loudness normalization of the HRTF, e.g. to level personalized SOFA files
+    pub hrtf_normalization: HrtfNormalization,
+    /// Interaural cue adjustments (can be changed at runtime on the engine)
+    pub listener_calibration: ListenerCalibration,
+    /// Binaural rendering for headphones or stereo panning for speakers (can be changed at
+    /// runtime on the engine)
+    pub output_mode: OutputMode,
+    /// Steam Audio simulation quality (can be changed at runtime on the engine)
+    pub spatial_quality: SpatialQuality,
+    /// Steam Audio simulation updates per second. Simulation runs on its own thread at
+    /// this rate, independent of the render block rate.
+    pub simulation_rate_hz: u32,
+    /// Platform audio session settings (session category, latency/power trade-off)
+    pub audio_session: AudioSessionConfig,
+    /// Measure the loudness of the master mix (see `PetalSonicEngine::loudness`)
+    pub loudness_metering: bool,
+    /// Spatialize spatial sources with Steam Audio. When disabled, Steam Audio is never
+    /// initialized and spatial sources are panned in stereo by their direction from the
+    /// listener, with distance attenuation.
+    pub enable_spatialization: bool,
+    /// Fraction of a block's duration Steam Audio may spend rendering spatial sources.
+    /// While the measured cost exceeds it, the most distant sources are panned in stereo
+    /// instead of HRTF-rendered (see `PetalSonicEvent::SpatialDegradationChanged`).
+    /// `None` disables the budget.
+    pub spatial_budget: Option<f32>,
+    /// Render distant and quiet spatial sources at a reduced level of detail (see
+    /// [`SpatialLod`]); can be changed at runtime on the engine. `None` renders every source
+    /// at full detail.
+    pub spatial_lod: Option<SpatialLod>,
+    /// Mix distant spatial sources in similar directions into shared emitters (see
+    /// [`SourceClustering`]); can be changed at runtime on the engine. `None` encodes every
+    /// source on its own.
+    pub source_clustering: Option<SourceClustering>,
+    /// Coordinate convention of the positions, rotations and poses passed to the world.
+    /// They are converted to PetalSonic's right-handed, Y-up convention on the way in (and
+    /// back by getters such as `PetalSonicWorld::listener`), so e.g. a Unity or Unreal host
+    /// can pass its transforms unchanged.
+    pub coordinate_convention: CoordinateConvention,
+    /// Seed of the world's random number generator, used for audio variation such as
+    /// random containers and sampler pitch variation (see [`crate::random`]). `None` seeds
+    /// it from the system clock.
+    pub random_seed: Option<u64>,
+    /// Cap on the bytes of audio data the world keeps registered (see [`crate::memory`]).
+    /// `None` leaves it unbounded.
+    pub memory_budget: Option<MemoryBudget>,
+    /// When audio registered at another sample rate is converted to `sample_rate`
+    pub resample_policy: ResamplePolicy,
+    /// Converter used for audio registered at another sample rate
+    pub asset_resample_quality: ResampleQuality,
+    /// Converter from the world's sample rate to the device's, when they differ. The
+    /// default polynomial converter is cheap; a sinc or FFT converter avoids its aliasing at
+    /// some CPU cost (and, for FFT, one block of latency).
+    pub output_resample_quality: ResampleQuality,
+    /// Dithering of the output when the device takes 16-bit samples (see
+    /// [`crate::dither`])
+    pub dither: Dither,
+    /// Emit `PetalSonicEvent::SourceSilent` for playing sources that stay silent (see
+    /// [`crate::silence`]). `None` disables detection.
+    pub silence_detection: Option<SilenceDetection>,
+    /// Delay spatial sources by their distance to the listener at the speed of sound (see
+    /// [`crate::distance_delay`])
+    pub distance_delay: bool,
+    /// Longest propagation delay; farther sources are delayed by this much
+    pub max_distance_delay: Duration,
+    /// Longest alignment delay a source can be given (see [`crate::source_delay`])
+    pub max_source_delay: Duration,
+    /// Shared reverb bus fed by the spatial sources' `reverb_send` (see [`crate::reverb`]).
+    /// `None` disables it until an environment preset is set with
+    /// [`PetalSonicWorld::set_environment`](crate::PetalSonicWorld::set_environment).
+    pub reverb: Option<ReverbSettings>,
+    /// Calibration EQ applied to the master output, e.g. a headphone correction profile
+    /// (see [`crate::output_eq`]). `None` disables it.
+    pub output_eq: Option<OutputEq>,
+    /// Linear gain of the device output (can be changed at runtime on the engine)
+    pub master_volume: f32,
+    /// Boost the low and high end as the master volume is lowered, so quiet listening keeps
+    /// its bass (see [`crate::master_volume`])
+    pub loudness_compensation: bool,
+    /// Compress and limit the output for late-night play (see [`crate::night_mode`])
+    pub night_mode: bool,
+    /// Play the same downmix on all output channels, for players with hearing in one ear
+    /// (see [`crate::accessibility`])
+    pub mono_output: bool,
+    /// Swap the left and right output channel (see [`crate::accessibility`])
+    pub swap_channels: bool,
+    /// Balance between the left (-1.0) and right (1.0) output channel
+    pub balance: f32,
+    /// Time the render stages of every block and send the timings to
+    /// `PetalSonicEngine::poll_timing_events` (can be toggled at runtime on the engine).
+    /// Disable in production builds to skip the clock reads and channel sends.
+    pub profiling: bool,
+    /// What the engine does with the output while the application is in the background
+    /// (see [`crate::focus`]); can be changed at runtime on the engine
+    pub background_policy: BackgroundPolicy,
+    /// Length of the fade out and in when the application changes focus
+    pub background_fade: Duration,
 }
 
 impl Default for PetalSonicWorldDesc {
@@ -25,9 +179,181 @@ impl Default for PetalSonicWorldDesc {
             sample_rate: 48000,
             block_size: 1024,
             channels: 2,
+            output_mix: None,
             buffer_duration: Duration::from_millis(10),
             max_sources: 64,
+            command_queue_capacity: 1024,
+            max_spatial_sources: 64,
             hrtf_path: None,
+            hrtf_volume_db: 0.0,
+            hrtf_normalization: HrtfNormalization::None,
+            listener_calibration: ListenerCalibration::default(),
+            output_mode: OutputMode::default(),
+            spatial_quality: SpatialQuality::default(),
+            simulation_rate_hz: 30,
+            audio_session: AudioSessionConfig::default(),
+            loudness_metering: false,
+            enable_spatialization: true,
+            spatial_budget: Some(0.5),
+            spatial_lod: None,
+            source_clustering: None,
+            coordinate_convention: CoordinateConvention::default(),
+            random_seed: None,
+            memory_budget: None,
+            resample_policy: ResamplePolicy::default(),
+            asset_resample_quality: ResampleQuality::Fft,
+            output_resample_quality: ResampleQuality::Fast,
+            dither: Dither::default(),
+            silence_detection: None,
+            distance_delay: false,
+            max_distance_delay: Duration::from_secs(2),
+            max_source_delay: Duration::from_secs(1),
+            reverb: None,
+            output_eq: None,
+            master_volume: 1.0,
+            loudness_compensation: false,
+            night_mode: false,
+            mono_output: false,
+            swap_channels: false,
+            balance: 0.0,
+            profiling: true,
+            background_policy: BackgroundPolicy::default(),
+            background_fade: Duration::from_millis(250),
+        }
+    }
+}
+
+impl PetalSonicWorldDesc {
+    /// Returns a copy of this descriptor with `block_size` clamped to Steam Audio frame
+    /// limits ([`MIN_BLOCK_SIZE`]..=[`MAX_BLOCK_SIZE`]).
+    ///
+    /// A warning is logged whenever the block size is adjusted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::InvalidConfig` if the sample rate or channel count is out of
+    /// range, the output mix does not take `channels` inputs, the simulation rate or
+    /// spatial source limit is zero, the spatial budget is not positive, the spatial level
+    /// of detail or source clustering has invalid parameters, the master volume is negative or the output EQ has
+    /// invalid bands.
+    pub fn validated(&self) -> Result<Self> {
+        self.check_ranges()?;
+
+        let block_size = self.block_size.clamp(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE);
+        if block_size != self.block_size {
+            log::warn!(
+                "Block size {} is outside the spatial pipeline limits, using {} instead",
+                self.block_size,
+                block_size
+            );
+        }
+
+        Ok(Self {
+            block_size,
+            ..self.clone()
+        })
+    }
+
+    /// Returns a builder starting from the default settings, which validates them when
+    /// built (see [`PetalSonicWorldDescBuilder::build`]).
+    pub fn builder() -> PetalSonicWorldDescBuilder {
+        PetalSonicWorldDescBuilder::default()
+    }
+
+    /// Check every setting except the block size, which `validated` adjusts
+    pub(crate) fn check_ranges(&self) -> std::result::Result<(), ConfigError> {
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&self.sample_rate) {
+            return Err(ConfigError::SampleRate(self.sample_rate));
+        }
+
+        if !(1..=MAX_CHANNELS).contains(&self.channels) {
+            return Err(ConfigError::Channels(self.channels));
+        }
+
+        if let Some(mix) = &self.output_mix
+            && mix.input_channels() != self.channels
+        {
+            return Err(ConfigError::OutputMix {
+                mix_channels: mix.input_channels(),
+                channels: self.channels,
+            });
+        }
+
+        if self.simulation_rate_hz == 0 {
+            return Err(ConfigError::SimulationRate);
+        }
+
+        if self.command_queue_capacity == 0 {
+            return Err(ConfigError::CommandQueueCapacity);
         }
+
+        if self.max_spatial_sources == 0 {
+            return Err(ConfigError::MaxSpatialSources);
+        }
+
+        if let Some(budget) = self.spatial_budget
+            && (budget.is_nan() || budget <= 0.0)
+        {
+            return Err(ConfigError::SpatialBudget(budget));
+        }
+
+        if let Some(lod) = &self.spatial_lod {
+            lod.validate().map_err(ConfigError::SpatialLod)?;
+        }
+
+        if let Some(clustering) = &self.source_clustering {
+            clustering
+                .validate()
+                .map_err(ConfigError::SourceClustering)?;
+        }
+
+        if !(self.master_volume >= 0.0 && self.master_volume.is_finite()) {
+            return Err(ConfigError::MasterVolume(self.master_volume));
+        }
+
+        if !(-1.0..=1.0).contains(&self.balance) {
+            return Err(ConfigError::Balance(self.balance));
+        }
+
+        if let Some(eq) = &self.output_eq {
+            eq.validate().map_err(|e| match e {
+                PetalSonicError::Configuration(message) => ConfigError::OutputEq(message),
+                other => ConfigError::OutputEq(other.to_string()),
+            })?;
+        }
+
+        self.asset_resample_quality
+            .validate()
+            .map_err(ConfigError::ResampleQuality)?;
+        self.output_resample_quality
+            .validate()
+            .map_err(ConfigError::ResampleQuality)?;
+
+        Ok(())
+    }
+
+    /// Returns the HRTF settings of this descriptor.
+    pub fn hrtf_config(&self) -> HrtfConfig {
+        HrtfConfig {
+            sofa_path: self.hrtf_path.clone(),
+            volume_db: self.hrtf_volume_db,
+            normalization: self.hrtf_normalization,
+        }
+    }
+
+    /// Returns the time it takes to play back one block at the world sample rate.
+    ///
+    /// This is the minimum processing latency introduced by the fixed-size render blocks.
+    pub fn processing_latency(&self) -> Duration {
+        if self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(self.block_size as f64 / self.sample_rate as f64)
+    }
+
+    /// Returns the time per block the spatial processor may spend, if budgeted
+    pub fn spatial_budget_duration(&self) -> Option<Duration> {
+        self.spatial_budget
+            .map(|budget| self.processing_latency().mul_f32(budget))
     }
 }
@@ -0,0 +1,270 @@
+use crate::channel_mix::ChannelMixMatrix;
+use crate::config::{
+    AudioSessionConfig, HrtfNormalization, ListenerCalibration, MAX_BLOCK_SIZE, MIN_BLOCK_SIZE,
+    OutputMode, PetalSonicWorldDesc, ResamplePolicy, ResampleQuality, SourceClustering, SpatialLod,
+    SpatialQuality,
+};
+use crate::dither::Dither;
+use crate::error::{ConfigError, Result};
+use crate::focus::BackgroundPolicy;
+use crate::math::CoordinateConvention;
+use crate::memory::MemoryBudget;
+use crate::output_eq::OutputEq;
+use crate::reverb::ReverbSettings;
+use crate::silence::SilenceDetection;
+use std::time::Duration;
+
+/// Builder of a [`PetalSonicWorldDesc`] that rejects unsupported settings when built
+///
+/// ```no_run
+/// # use petalsonic::*;
+/// let desc = PetalSonicWorldDesc::builder()
+///     .sample_rate(44_100)
+///     .block_size(512)
+///     .hrtf_path("assets/hrtf.sofa")
+///     .build()?;
+/// # Ok::<(), PetalSonicError>(())
+/// ```
+///
+/// Each setter sets the [`PetalSonicWorldDesc`] field of the same name; unset fields keep
+/// their defaults.
+#[derive(Debug, Clone, Default)]
+pub struct PetalSonicWorldDescBuilder {
+    desc: PetalSonicWorldDesc,
+}
+
+impl PetalSonicWorldDescBuilder {
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.desc.sample_rate = sample_rate;
+        self
+    }
+
+    /// Must be a power of two between [`MIN_BLOCK_SIZE`] and [`MAX_BLOCK_SIZE`]; unlike
+    /// [`PetalSonicWorldDesc::validated`], the builder does not adjust other values
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.desc.block_size = block_size;
+        self
+    }
+
+    pub fn channels(mut self, channels: u16) -> Self {
+        self.desc.channels = channels;
+        self
+    }
+
+    pub fn output_mix(mut self, output_mix: ChannelMixMatrix) -> Self {
+        self.desc.output_mix = Some(output_mix);
+        self
+    }
+
+    pub fn buffer_duration(mut self, buffer_duration: Duration) -> Self {
+        self.desc.buffer_duration = buffer_duration;
+        self
+    }
+
+    pub fn max_sources(mut self, max_sources: usize) -> Self {
+        self.desc.max_sources = max_sources;
+        self
+    }
+
+    pub fn command_queue_capacity(mut self, capacity: usize) -> Self {
+        self.desc.command_queue_capacity = capacity;
+        self
+    }
+
+    pub fn max_spatial_sources(mut self, max_spatial_sources: usize) -> Self {
+        self.desc.max_spatial_sources = max_spatial_sources;
+        self
+    }
+
+    pub fn hrtf_path(mut self, hrtf_path: impl Into<String>) -> Self {
+        self.desc.hrtf_path = Some(hrtf_path.into());
+        self
+    }
+
+    pub fn hrtf_volume_db(mut self, hrtf_volume_db: f32) -> Self {
+        self.desc.hrtf_volume_db = hrtf_volume_db;
+        self
+    }
+
+    pub fn hrtf_normalization(mut self, hrtf_normalization: HrtfNormalization) -> Self {
+        self.desc.hrtf_normalization = hrtf_normalization;
+        self
+    }
+
+    pub fn listener_calibration(mut self, listener_calibration: ListenerCalibration) -> Self {
+        self.desc.listener_calibration = listener_calibration;
+        self
+    }
+
+    pub fn output_mode(mut self, output_mode: OutputMode) -> Self {
+        self.desc.output_mode = output_mode;
+        self
+    }
+
+    pub fn spatial_quality(mut self, spatial_quality: SpatialQuality) -> Self {
+        self.desc.spatial_quality = spatial_quality;
+        self
+    }
+
+    pub fn simulation_rate_hz(mut self, simulation_rate_hz: u32) -> Self {
+        self.desc.simulation_rate_hz = simulation_rate_hz;
+        self
+    }
+
+    pub fn audio_session(mut self, audio_session: AudioSessionConfig) -> Self {
+        self.desc.audio_session = audio_session;
+        self
+    }
+
+    pub fn loudness_metering(mut self, loudness_metering: bool) -> Self {
+        self.desc.loudness_metering = loudness_metering;
+        self
+    }
+
+    pub fn enable_spatialization(mut self, enable_spatialization: bool) -> Self {
+        self.desc.enable_spatialization = enable_spatialization;
+        self
+    }
+
+    pub fn spatial_budget(mut self, spatial_budget: Option<f32>) -> Self {
+        self.desc.spatial_budget = spatial_budget;
+        self
+    }
+
+    pub fn spatial_lod(mut self, spatial_lod: SpatialLod) -> Self {
+        self.desc.spatial_lod = Some(spatial_lod);
+        self
+    }
+
+    pub fn source_clustering(mut self, source_clustering: SourceClustering) -> Self {
+        self.desc.source_clustering = Some(source_clustering);
+        self
+    }
+
+    pub fn coordinate_convention(mut self, coordinate_convention: CoordinateConvention) -> Self {
+        self.desc.coordinate_convention = coordinate_convention;
+        self
+    }
+
+    pub fn random_seed(mut self, random_seed: u64) -> Self {
+        self.desc.random_seed = Some(random_seed);
+        self
+    }
+
+    pub fn memory_budget(mut self, memory_budget: MemoryBudget) -> Self {
+        self.desc.memory_budget = Some(memory_budget);
+        self
+    }
+
+    pub fn resample_policy(mut self, resample_policy: ResamplePolicy) -> Self {
+        self.desc.resample_policy = resample_policy;
+        self
+    }
+
+    pub fn asset_resample_quality(mut self, asset_resample_quality: ResampleQuality) -> Self {
+        self.desc.asset_resample_quality = asset_resample_quality;
+        self
+    }
+
+    pub fn output_resample_quality(mut self, output_resample_quality: ResampleQuality) -> Self {
+        self.desc.output_resample_quality = output_resample_quality;
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.desc.dither = dither;
+        self
+    }
+
+    pub fn silence_detection(mut self, silence_detection: SilenceDetection) -> Self {
+        self.desc.silence_detection = Some(silence_detection);
+        self
+    }
+
+    pub fn distance_delay(mut self, distance_delay: bool) -> Self {
+        self.desc.distance_delay = distance_delay;
+        self
+    }
+
+    pub fn max_distance_delay(mut self, max_distance_delay: Duration) -> Self {
+        self.desc.max_distance_delay = max_distance_delay;
+        self
+    }
+
+    pub fn max_source_delay(mut self, max_source_delay: Duration) -> Self {
+        self.desc.max_source_delay = max_source_delay;
+        self
+    }
+
+    pub fn reverb(mut self, reverb: ReverbSettings) -> Self {
+        self.desc.reverb = Some(reverb);
+        self
+    }
+
+    pub fn output_eq(mut self, output_eq: OutputEq) -> Self {
+        self.desc.output_eq = Some(output_eq);
+        self
+    }
+
+    pub fn master_volume(mut self, master_volume: f32) -> Self {
+        self.desc.master_volume = master_volume;
+        self
+    }
+
+    pub fn loudness_compensation(mut self, loudness_compensation: bool) -> Self {
+        self.desc.loudness_compensation = loudness_compensation;
+        self
+    }
+
+    pub fn night_mode(mut self, night_mode: bool) -> Self {
+        self.desc.night_mode = night_mode;
+        self
+    }
+
+    pub fn mono_output(mut self, mono_output: bool) -> Self {
+        self.desc.mono_output = mono_output;
+        self
+    }
+
+    pub fn swap_channels(mut self, swap_channels: bool) -> Self {
+        self.desc.swap_channels = swap_channels;
+        self
+    }
+
+    pub fn balance(mut self, balance: f32) -> Self {
+        self.desc.balance = balance;
+        self
+    }
+
+    pub fn profiling(mut self, profiling: bool) -> Self {
+        self.desc.profiling = profiling;
+        self
+    }
+
+    pub fn background_policy(mut self, background_policy: BackgroundPolicy) -> Self {
+        self.desc.background_policy = background_policy;
+        self
+    }
+
+    pub fn background_fade(mut self, background_fade: Duration) -> Self {
+        self.desc.background_fade = background_fade;
+        self
+    }
+
+    /// Validate the settings and return the descriptor
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::InvalidConfig` naming the first unsupported setting: a
+    /// block size that is not a power of two in range, or any setting rejected by
+    /// [`PetalSonicWorldDesc::validated`].
+    pub fn build(self) -> Result<PetalSonicWorldDesc> {
+        let block_size = self.desc.block_size;
+        if !block_size.is_power_of_two() || !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&block_size)
+        {
+            return Err(ConfigError::BlockSize(block_size).into());
+        }
+        self.desc.check_ranges()?;
+        Ok(self.desc)
+    }
+}
@@ -0,0 +1,124 @@
+//! Debug draw data: a snapshot of what the engine renders, for live overlays in editors.
+//!
+//! [`PetalSonicEngine::debug_snapshot`](crate::PetalSonicEngine::debug_snapshot) collects
+//! the listener pose, every active source with the gains computed for it on the render
+//! thread, and the output and bus levels of the last rendered block:
+//!
+//! ```ignore
+//! let snapshot = engine.debug_snapshot();
+//! for source in &snapshot.sources {
+//!     if let Some(position) = source.position {
+//!         draw_sphere(position, source.level, source.occlusion.unwrap_or(1.0));
+//!     }
+//! }
+//! ```
+//!
+//! Positions and the listener pose are in the world's coordinate convention. Taking a
+//! snapshot briefly locks the render state, so call it at UI rate (not per audio block).
+//! The engine only simulates direct paths, so there are no reflection rays to report.
+
+use crate::math::{Pose, Vec3};
+use crate::playback::PlaybackInfo;
+use crate::world::SourceId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Render state of the engine at one point in time
+#[derive(Debug, Clone)]
+pub struct DebugSnapshot {
+    /// Listener pose
+    pub listener: Pose,
+    /// Active sources (playing or paused)
+    pub sources: Vec<SourceDebugInfo>,
+    /// Peak of each output channel in the last block, after the master volume
+    pub output_peaks: Vec<f32>,
+    /// Level of each source bus (see
+    /// [`PetalSonicWorld::set_source_bus`](crate::PetalSonicWorld::set_source_bus)): the
+    /// peak of its loudest source in the last block
+    pub bus_levels: HashMap<String, f32>,
+}
+
+/// Render state of one source
+#[derive(Debug, Clone)]
+pub struct SourceDebugInfo {
+    pub source_id: SourceId,
+    /// Position of a spatial source, `None` for non-spatial sources
+    pub position: Option<Vec3>,
+    /// Play state and position
+    pub playback: PlaybackInfo,
+    /// Source volume
+    pub volume: f32,
+    /// Distance attenuation applied in the last block (linear gain), if the source is
+    /// spatialized by the spatial processor or the stereo panner
+    pub distance_attenuation: Option<f32>,
+    /// Occlusion applied in the last block (1 = unoccluded), if the spatial processor
+    /// renders the source
+    pub occlusion: Option<f32>,
+    /// Peak of the source's signal in the last block, after its volume
+    pub level: f32,
+    /// Bus the source is mixed into, `None` for the main mix
+    pub bus: Option<String>,
+}
+
+/// Output peaks published by the render thread, or device levels by the audio callback
+pub(crate) struct SharedOutputLevels {
+    peaks: Vec<AtomicU32>,
+    /// Loudest block RMS of each channel since the last `take_held_rms`
+    held_rms: Vec<AtomicU32>,
+}
+
+impl SharedOutputLevels {
+    pub fn new(channels: u16) -> Self {
+        Self {
+            peaks: (0..channels).map(|_| AtomicU32::new(0)).collect(),
+            held_rms: (0..channels).map(|_| AtomicU32::new(0)).collect(),
+        }
+    }
+
+    /// Publish the peaks of an interleaved block
+    pub fn update(&self, samples: &[f32]) {
+        let channels = self.peaks.len();
+        let frames = (samples.len() / channels.max(1)).max(1);
+        for (channel, (peak, held_rms)) in self.peaks.iter().zip(&self.held_rms).enumerate() {
+            let (value, energy) = samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .fold((0.0f32, 0.0f32), |(peak, energy), sample| {
+                    (peak.max(sample.abs()), energy + sample * sample)
+                });
+            Self::store(peak, held_rms, value, energy, frames);
+        }
+    }
+
+    /// Publish the peak and summed energy of `frames` frames of one channel
+    pub fn update_channel(&self, channel: usize, peak: f32, energy: f32, frames: usize) {
+        if let (Some(stored_peak), Some(held_rms)) =
+            (self.peaks.get(channel), self.held_rms.get(channel))
+        {
+            Self::store(stored_peak, held_rms, peak, energy, frames.max(1));
+        }
+    }
+
+    fn store(peak: &AtomicU32, held_rms: &AtomicU32, value: f32, energy: f32, frames: usize) {
+        peak.store(value.to_bits(), Ordering::Relaxed);
+        // The bits of non-negative floats order like the floats themselves
+        let rms = (energy / frames as f32).sqrt();
+        held_rms.fetch_max(rms.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the loudest block RMS of each channel since the last call, and resets it
+    pub fn take_held_rms(&self) -> Vec<f32> {
+        self.held_rms
+            .iter()
+            .map(|rms| f32::from_bits(rms.swap(0, Ordering::Relaxed)))
+            .collect()
+    }
+
+    pub fn peaks(&self) -> Vec<f32> {
+        self.peaks
+            .iter()
+            .map(|peak| f32::from_bits(peak.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
@@ -0,0 +1,634 @@
+//! Output diagnostics: test tones and device callback statistics.
+//!
+//! - [`PetalSonicEngine::play_test_tone`](crate::PetalSonicEngine::play_test_tone) plays a sine
+//!   tone on a single channel of the output device, mixed on top of the world after it is
+//!   mixed to the device's channels.
+//! - [`PetalSonicEngine::run_diagnostics`](crate::PetalSonicEngine::run_diagnostics) plays a tone
+//!   on every channel, checks that the device consumed audio and that each channel carried its
+//!   tone, and returns a [`DiagnosticsReport`].
+//!
+//! [`PetalSonicEngine::output_info`](crate::PetalSonicEngine::output_info) returns the
+//! negotiated device configuration as an [`OutputInfo`], e.g. for a settings UI, and
+//! [`PetalSonicEngine::stats`](crate::PetalSonicEngine::stats) the running counters of the
+//! engine as [`EngineStats`], including the conversion from the world to the device sample
+//! rate ([`ResampleStats`]) and the device clock rate measured from its callbacks.
+//!
+//! The render thread times every block against its real-time budget, the block's duration
+//! at the world sample rate. The load of the last block and its maximum over the last
+//! [`LOAD_WINDOW_BLOCKS`] blocks are reported in [`RenderLoadStats`], and a block exceeding
+//! the budget emits `PetalSonicEvent::BudgetExceeded`, e.g. to lower the spatial quality.
+//!
+//! Callback statistics are gathered by the audio callback with relaxed atomics only, so they
+//! do not affect real-time safety.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Test tone amplitude (-12 dBFS)
+const TEST_TONE_AMPLITUDE: f32 = 0.25;
+/// Test tones that play at the same time; further tones wait, or are dropped by the
+/// render thread
+pub(crate) const MAX_TEST_TONES: usize = 16;
+/// Output RMS below which a channel counts as silent during `run_diagnostics` (-80 dBFS)
+pub(crate) const SILENT_CHANNEL_RMS: f32 = 1e-4;
+/// Fade in/out length of a test tone, to avoid clicks
+const TEST_TONE_FADE: Duration = Duration::from_millis(10);
+/// Shortest span of callbacks the device clock rate is measured over; shorter spans are
+/// dominated by callback jitter
+const MIN_RATE_MEASUREMENT: Duration = Duration::from_secs(1);
+/// Blocks the rolling maximum of the render load is taken over
+pub const LOAD_WINDOW_BLOCKS: usize = 64;
+
+/// A sine tone mixed into a single device channel by the audio callback, or a chirp
+/// rendered on all world channels by the render thread to measure latency
+#[derive(Debug, Clone)]
+pub(crate) struct TestTone {
+    /// Channel the tone plays on, `None` for all
+    channel: Option<u16>,
+    phase_increment: f32,
+    /// Change of the phase increment per frame, 0 for a steady tone
+    increment_step: f32,
+    phase: f32,
+    total_frames: usize,
+    fade_frames: usize,
+    position: usize,
+    /// Receives the output frame (see `PetalSonicEngine::frames_processed`) the tone
+    /// starts at once it is rendered
+    start_frame: Option<Arc<AtomicUsize>>,
+}
+
+impl TestTone {
+    pub fn new(channel: u16, frequency: f32, duration: Duration, sample_rate: u32) -> Self {
+        let total_frames = (duration.as_secs_f64() * sample_rate as f64) as usize;
+        let fade_frames = ((TEST_TONE_FADE.as_secs_f64() * sample_rate as f64) as usize)
+            .min(total_frames / 2)
+            .max(1);
+
+        Self {
+            channel: Some(channel),
+            phase_increment: std::f32::consts::TAU * frequency / sample_rate as f32,
+            increment_step: 0.0,
+            phase: 0.0,
+            total_frames,
+            fade_frames,
+            position: 0,
+            start_frame: None,
+        }
+    }
+
+    /// Linear sweep from `start_hz` to `end_hz` on all channels (see
+    /// [`crate::latency::chirp`]); `start_frame` receives the output frame it starts at
+    pub fn chirp(
+        start_hz: f32,
+        end_hz: f32,
+        duration: Duration,
+        sample_rate: u32,
+        start_frame: Arc<AtomicUsize>,
+    ) -> Self {
+        let total_frames = ((duration.as_secs_f64() * sample_rate as f64) as usize).max(1);
+        let step_hz = (end_hz - start_hz) / total_frames as f32;
+        Self {
+            channel: None,
+            phase_increment: std::f32::consts::TAU * start_hz / sample_rate as f32,
+            increment_step: std::f32::consts::TAU * step_hz / sample_rate as f32,
+            phase: 0.0,
+            total_frames,
+            fade_frames: crate::latency::chirp_fade_frames(total_frames),
+            position: 0,
+            start_frame: Some(start_frame),
+        }
+    }
+
+    /// Mix the tone into an interleaved buffer whose first frame is heard at
+    /// `output_frame`. Returns false once the tone has finished.
+    pub fn mix_into(&mut self, buffer: &mut [f32], channels: u16, output_frame: usize) -> bool {
+        let channels = channels as usize;
+        let channel_range = match self.channel {
+            Some(channel) if channel as usize >= channels => return false,
+            Some(channel) => channel as usize..channel as usize + 1,
+            None => 0..channels,
+        };
+        if self.position == 0
+            && let Some(start_frame) = &self.start_frame
+        {
+            start_frame.store(output_frame, Ordering::Relaxed);
+        }
+
+        for frame in buffer.chunks_exact_mut(channels) {
+            if self.position >= self.total_frames {
+                return false;
+            }
+
+            let remaining = self.total_frames - self.position;
+            let envelope = (self.position.min(remaining) as f32 / self.fade_frames as f32).min(1.0);
+            let sample = self.phase.sin() * TEST_TONE_AMPLITUDE * envelope;
+            for output in &mut frame[channel_range.clone()] {
+                *output += sample;
+            }
+
+            self.phase = (self.phase + self.phase_increment) % std::f32::consts::TAU;
+            self.phase_increment += self.increment_step;
+            self.position += 1;
+        }
+
+        self.position < self.total_frames
+    }
+}
+
+/// Device callback statistics, written by the audio callback
+pub(crate) struct CallbackStats {
+    callbacks: AtomicU64,
+    min_frames: AtomicU64,
+    max_frames: AtomicU64,
+    total_frames: AtomicU64,
+    min_interval_us: AtomicU64,
+    max_interval_us: AtomicU64,
+    total_interval_us: AtomicU64,
+    intervals: AtomicU64,
+    underruns: AtomicU64,
+    /// Reference of the callback timestamps below
+    epoch: Instant,
+    /// Nanoseconds from `epoch` to the first and the latest callback (`u64::MAX` before the
+    /// first), and the frames of the latest callback
+    first_callback_ns: AtomicU64,
+    last_callback_ns: AtomicU64,
+    last_frames: AtomicU64,
+}
+
+impl CallbackStats {
+    pub fn new() -> Self {
+        Self {
+            callbacks: AtomicU64::new(0),
+            min_frames: AtomicU64::new(u64::MAX),
+            max_frames: AtomicU64::new(0),
+            total_frames: AtomicU64::new(0),
+            min_interval_us: AtomicU64::new(u64::MAX),
+            max_interval_us: AtomicU64::new(0),
+            total_interval_us: AtomicU64::new(0),
+            intervals: AtomicU64::new(0),
+            underruns: AtomicU64::new(0),
+            epoch: Instant::now(),
+            first_callback_ns: AtomicU64::new(u64::MAX),
+            last_callback_ns: AtomicU64::new(0),
+            last_frames: AtomicU64::new(0),
+        }
+    }
+
+    /// Clear all statistics
+    pub fn reset(&self) {
+        self.callbacks.store(0, Ordering::Relaxed);
+        self.min_frames.store(u64::MAX, Ordering::Relaxed);
+        self.max_frames.store(0, Ordering::Relaxed);
+        self.total_frames.store(0, Ordering::Relaxed);
+        self.min_interval_us.store(u64::MAX, Ordering::Relaxed);
+        self.max_interval_us.store(0, Ordering::Relaxed);
+        self.total_interval_us.store(0, Ordering::Relaxed);
+        self.intervals.store(0, Ordering::Relaxed);
+        self.underruns.store(0, Ordering::Relaxed);
+        self.first_callback_ns.store(u64::MAX, Ordering::Relaxed);
+        self.last_callback_ns.store(0, Ordering::Relaxed);
+        self.last_frames.store(0, Ordering::Relaxed);
+    }
+
+    /// Record one device callback of `frames` frames, `interval` after the previous one
+    pub fn record_callback(&self, frames: usize, interval: Option<Duration>) {
+        let frames = frames as u64;
+        self.callbacks.fetch_add(1, Ordering::Relaxed);
+        self.min_frames.fetch_min(frames, Ordering::Relaxed);
+        self.max_frames.fetch_max(frames, Ordering::Relaxed);
+        self.total_frames.fetch_add(frames, Ordering::Relaxed);
+
+        let now_ns = self.epoch.elapsed().as_nanos() as u64;
+        let _ = self.first_callback_ns.compare_exchange(
+            u64::MAX,
+            now_ns,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        self.last_callback_ns.store(now_ns, Ordering::Relaxed);
+        self.last_frames.store(frames, Ordering::Relaxed);
+
+        if let Some(interval) = interval {
+            let interval_us = interval.as_micros() as u64;
+            self.min_interval_us
+                .fetch_min(interval_us, Ordering::Relaxed);
+            self.max_interval_us
+                .fetch_max(interval_us, Ordering::Relaxed);
+            self.total_interval_us
+                .fetch_add(interval_us, Ordering::Relaxed);
+            self.intervals.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Largest callback size in frames, or `None` before the first callback
+    pub fn max_frames(&self) -> Option<usize> {
+        (self.callbacks.load(Ordering::Relaxed) > 0)
+            .then(|| self.max_frames.load(Ordering::Relaxed) as usize)
+    }
+
+    /// Frames per second the device consumed between its first and latest callback, or
+    /// `None` until they are [`MIN_RATE_MEASUREMENT`] apart
+    pub fn measured_rate(&self) -> Option<f64> {
+        let first_ns = self.first_callback_ns.load(Ordering::Relaxed);
+        let last_ns = self.last_callback_ns.load(Ordering::Relaxed);
+        let span = Duration::from_nanos(last_ns.checked_sub(first_ns)?);
+        if span < MIN_RATE_MEASUREMENT {
+            return None;
+        }
+        // The latest callback's frames are played after its timestamp
+        let frames =
+            self.total_frames.load(Ordering::Relaxed) - self.last_frames.load(Ordering::Relaxed);
+        Some(frames as f64 / span.as_secs_f64())
+    }
+
+    /// Record a callback that could not be fully served from the ring buffer
+    pub fn record_underrun(&self) {
+        self.underruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Fill the callback-related fields of a report built from the engine configuration
+    pub fn report(&self, mut report: DiagnosticsReport) -> DiagnosticsReport {
+        let callbacks = self.callbacks.load(Ordering::Relaxed);
+        let intervals = self.intervals.load(Ordering::Relaxed);
+
+        report.callback_count = callbacks;
+        report.underrun_count = self.underruns.load(Ordering::Relaxed);
+
+        if callbacks > 0 {
+            report.min_callback_frames = self.min_frames.load(Ordering::Relaxed) as usize;
+            report.max_callback_frames = self.max_frames.load(Ordering::Relaxed) as usize;
+            report.avg_callback_frames =
+                self.total_frames.load(Ordering::Relaxed) as f64 / callbacks as f64;
+        }
+
+        if let Some(avg_interval_us) = self
+            .total_interval_us
+            .load(Ordering::Relaxed)
+            .checked_div(intervals)
+        {
+            report.min_callback_interval =
+                Duration::from_micros(self.min_interval_us.load(Ordering::Relaxed));
+            report.max_callback_interval =
+                Duration::from_micros(self.max_interval_us.load(Ordering::Relaxed));
+            report.avg_callback_interval = Duration::from_micros(avg_interval_us);
+        }
+
+        report
+    }
+}
+
+/// Sample-rate conversion counters, written by the render thread
+pub(crate) struct ResampleCounters {
+    input_frames: AtomicU64,
+    output_frames: AtomicU64,
+    /// Output frames expected from the nominal ratios, as `f64` bits
+    expected_output_frames: AtomicU64,
+    /// Ratio of the current converter as `f64` bits, 0 before the first stream
+    ratio: AtomicU64,
+    ratio_adjustments: AtomicU64,
+}
+
+impl ResampleCounters {
+    pub fn new() -> Self {
+        Self {
+            input_frames: AtomicU64::new(0),
+            output_frames: AtomicU64::new(0),
+            expected_output_frames: AtomicU64::new(0f64.to_bits()),
+            ratio: AtomicU64::new(0f64.to_bits()),
+            ratio_adjustments: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the ratio (device rate / world rate) of a new stream's converter, counting an
+    /// adjustment if it differs from the previous stream's
+    pub fn set_ratio(&self, ratio: f64) {
+        let previous = f64::from_bits(self.ratio.swap(ratio.to_bits(), Ordering::Relaxed));
+        if previous != 0.0 && previous != ratio {
+            self.ratio_adjustments.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record one conversion of `input_frames` world frames into `output_frames` device
+    /// frames (render thread only)
+    pub fn record(&self, input_frames: usize, output_frames: usize) {
+        self.input_frames
+            .fetch_add(input_frames as u64, Ordering::Relaxed);
+        self.output_frames
+            .fetch_add(output_frames as u64, Ordering::Relaxed);
+        let expected = f64::from_bits(self.expected_output_frames.load(Ordering::Relaxed))
+            + input_frames as f64 * self.ratio();
+        self.expected_output_frames
+            .store(expected.to_bits(), Ordering::Relaxed);
+    }
+
+    fn ratio(&self) -> f64 {
+        match f64::from_bits(self.ratio.load(Ordering::Relaxed)) {
+            0.0 => 1.0,
+            ratio => ratio,
+        }
+    }
+
+    /// Snapshot of the counters, with the device clock rate measured by the callbacks
+    pub fn stats(
+        &self,
+        device_sample_rate: u32,
+        measured_device_rate: Option<f64>,
+    ) -> ResampleStats {
+        let output_frames = self.output_frames.load(Ordering::Relaxed);
+        let expected = f64::from_bits(self.expected_output_frames.load(Ordering::Relaxed));
+        ResampleStats {
+            input_frames: self.input_frames.load(Ordering::Relaxed),
+            output_frames,
+            ratio: self.ratio(),
+            ratio_adjustments: self.ratio_adjustments.load(Ordering::Relaxed),
+            conversion_drift_frames: output_frames as f64 - expected,
+            measured_device_rate,
+            device_clock_drift_ppm: measured_device_rate
+                .map(|rate| (rate / device_sample_rate as f64 - 1.0) * 1_000_000.0),
+        }
+    }
+}
+
+/// Counters of the conversion from the world to the device sample rate
+///
+/// Part of [`EngineStats`]. Frame totals count since the engine was created; the device
+/// clock is measured since the last `start()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResampleStats {
+    /// World frames fed to the converter
+    pub input_frames: u64,
+    /// Device frames the converter produced
+    pub output_frames: u64,
+    /// Ratio of the current converter (device rate / world rate), 1.0 when the rates match
+    pub ratio: f64,
+    /// Times the ratio changed, e.g. when the engine restarted on a device running at
+    /// another sample rate
+    pub ratio_adjustments: u64,
+    /// Output frames produced minus those expected from the ratios; stays within the
+    /// converter's delay unless it drops or repeats frames
+    pub conversion_drift_frames: f64,
+    /// Frames per second the device actually consumed, measured from its callback timing
+    /// once at least a second of callbacks was seen
+    pub measured_device_rate: Option<f64>,
+    /// Deviation of the measured device rate from its nominal sample rate in parts per
+    /// million; a few hundred ppm or more points at a problematic device clock
+    pub device_clock_drift_ppm: Option<f64>,
+}
+
+/// Render load counters, written by the render thread
+pub(crate) struct RenderLoadCounters {
+    /// Load of the last block and its rolling maximum, as `f32` bits
+    load: AtomicU32,
+    rolling_max: AtomicU32,
+    /// Highest load of any block as `f32` bits, which order like the (non-negative) loads
+    peak: AtomicU32,
+    blocks: AtomicU64,
+    blocks_over_budget: AtomicU64,
+}
+
+impl RenderLoadCounters {
+    pub fn new() -> Self {
+        Self {
+            load: AtomicU32::new(0f32.to_bits()),
+            rolling_max: AtomicU32::new(0f32.to_bits()),
+            peak: AtomicU32::new(0f32.to_bits()),
+            blocks: AtomicU64::new(0),
+            blocks_over_budget: AtomicU64::new(0),
+        }
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> RenderLoadStats {
+        RenderLoadStats {
+            load: f32::from_bits(self.load.load(Ordering::Relaxed)),
+            rolling_max: f32::from_bits(self.rolling_max.load(Ordering::Relaxed)),
+            peak: f32::from_bits(self.peak.load(Ordering::Relaxed)),
+            blocks: self.blocks.load(Ordering::Relaxed),
+            blocks_over_budget: self.blocks_over_budget.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Render-side timing of blocks against their real-time budget, publishing to
+/// [`RenderLoadCounters`]
+pub(crate) struct RenderLoadMeter {
+    counters: Arc<RenderLoadCounters>,
+    window: [f32; LOAD_WINDOW_BLOCKS],
+    next: usize,
+    /// Set by a block over budget until the rolling maximum is back within it, so a
+    /// sustained overload is reported once
+    over_budget: bool,
+}
+
+impl RenderLoadMeter {
+    pub fn new(counters: Arc<RenderLoadCounters>) -> Self {
+        Self {
+            counters,
+            window: [0.0; LOAD_WINDOW_BLOCKS],
+            next: 0,
+            over_budget: false,
+        }
+    }
+
+    /// Record a block rendered in `elapsed` with a budget of `budget`; returns the load
+    /// and its rolling maximum if the block exceeded the budget after blocks within it
+    pub fn record(&mut self, elapsed: Duration, budget: Duration) -> Option<(f32, f32)> {
+        let load = elapsed.as_secs_f32() / budget.as_secs_f32().max(f32::EPSILON);
+        self.window[self.next] = load;
+        self.next = (self.next + 1) % LOAD_WINDOW_BLOCKS;
+        let rolling_max = self.window.iter().copied().fold(0.0, f32::max);
+
+        let counters = &self.counters;
+        counters.load.store(load.to_bits(), Ordering::Relaxed);
+        counters
+            .rolling_max
+            .store(rolling_max.to_bits(), Ordering::Relaxed);
+        counters.peak.fetch_max(load.to_bits(), Ordering::Relaxed);
+        counters.blocks.fetch_add(1, Ordering::Relaxed);
+        if load > 1.0 {
+            counters.blocks_over_budget.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if load > 1.0 && !self.over_budget {
+            self.over_budget = true;
+            Some((load, rolling_max))
+        } else {
+            self.over_budget &= rolling_max > 1.0;
+            None
+        }
+    }
+}
+
+/// Time the render thread spends per block relative to the block's duration
+///
+/// Part of [`EngineStats`]. A load of 1.0 uses the whole real-time budget of a block;
+/// sustained loads above it drain the output buffer until the device underruns.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderLoadStats {
+    /// Load of the last rendered block
+    pub load: f32,
+    /// Highest load of the last [`LOAD_WINDOW_BLOCKS`] blocks
+    pub rolling_max: f32,
+    /// Highest load of any block since the engine was created
+    pub peak: f32,
+    /// Blocks rendered
+    pub blocks: u64,
+    /// Blocks that took longer than their duration to render
+    pub blocks_over_budget: u64,
+}
+
+/// Running counters of the engine, returned by
+/// [`PetalSonicEngine::stats`](crate::PetalSonicEngine::stats)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EngineStats {
+    /// Frames consumed by the device since start
+    pub frames_processed: usize,
+    /// Conversion from the world to the device sample rate
+    pub resampling: ResampleStats,
+    /// Render time per block relative to its real-time budget
+    pub render_load: RenderLoadStats,
+}
+
+/// Measures the interval between consecutive audio callbacks
+pub(crate) struct CallbackClock {
+    last_callback: Option<Instant>,
+}
+
+impl CallbackClock {
+    pub fn new() -> Self {
+        Self {
+            last_callback: None,
+        }
+    }
+
+    /// Returns the time since the previous tick, if any
+    pub fn tick(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let interval = self.last_callback.map(|last| now.duration_since(last));
+        self.last_callback = Some(now);
+        interval
+    }
+}
+
+/// Configuration negotiated with the output device when the engine started
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    /// Name of the output device, if the platform reports one
+    pub device_name: Option<String>,
+    /// Device sample rate in Hz (the world is resampled to it if they differ)
+    pub sample_rate: u32,
+    /// Number of device channels
+    pub channels: u16,
+    /// Frames per device callback: the fixed size requested from the device, otherwise the
+    /// largest callback measured so far (`None` before the first callback)
+    pub buffer_size: Option<usize>,
+    /// Sample format of the device stream (e.g. "f32")
+    pub sample_format: String,
+    /// Estimated output latency: the processing latency plus one device buffer
+    pub latency: Duration,
+}
+
+/// Structured snapshot of the engine's output configuration and device callback behavior.
+///
+/// Implements `Display` for a human-readable summary suitable for logs or support tickets.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsReport {
+    /// Name of the output device, if known
+    pub device_name: Option<String>,
+    /// Sample format used by the device stream (e.g. "F32")
+    pub sample_format: Option<String>,
+    /// Device sample rate in Hz
+    pub device_sample_rate: u32,
+    /// World sample rate in Hz
+    pub world_sample_rate: u32,
+    /// Number of world output channels
+    pub channels: u16,
+    /// Number of device channels (differs from `channels` when the output is re-mixed)
+    pub device_channels: u16,
+    /// Render block size in world frames
+    pub block_size: usize,
+    /// Whether the Steam Audio spatial processor is available
+    pub spatial_enabled: bool,
+    /// Effective processing latency (see `PetalSonicEngine::processing_latency`)
+    pub processing_latency: Duration,
+    /// Total frames consumed by the device since start
+    pub frames_processed: usize,
+    /// Number of device callbacks measured
+    pub callback_count: u64,
+    /// Smallest callback buffer size in frames
+    pub min_callback_frames: usize,
+    /// Largest callback buffer size in frames
+    pub max_callback_frames: usize,
+    /// Average callback buffer size in frames
+    pub avg_callback_frames: f64,
+    /// Shortest time between two callbacks
+    pub min_callback_interval: Duration,
+    /// Longest time between two callbacks
+    pub max_callback_interval: Duration,
+    /// Average time between two callbacks
+    pub avg_callback_interval: Duration,
+    /// Number of callbacks that ran out of rendered audio
+    pub underrun_count: u64,
+    /// Set by `run_diagnostics`: true if the device consumed audio while the test tones
+    /// played, and every channel carried its tone
+    pub output_verified: Option<bool>,
+    /// Set by `run_diagnostics`: channels that stayed silent while their test tone played
+    pub silent_channels: Vec<u16>,
+}
+
+impl fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PetalSonic diagnostics")?;
+        writeln!(
+            f,
+            "  Device: {} ({})",
+            self.device_name.as_deref().unwrap_or("unknown"),
+            self.sample_format.as_deref().unwrap_or("unknown format")
+        )?;
+        writeln!(
+            f,
+            "  Sample rate: device {} Hz, world {} Hz",
+            self.device_sample_rate, self.world_sample_rate
+        )?;
+        writeln!(
+            f,
+            "  Channels: world {}, device {}, block size: {} frames",
+            self.channels, self.device_channels, self.block_size
+        )?;
+        writeln!(f, "  Spatial audio: {}", self.spatial_enabled)?;
+        writeln!(
+            f,
+            "  Processing latency: {:.2} ms",
+            self.processing_latency.as_secs_f64() * 1000.0
+        )?;
+        writeln!(f, "  Frames processed: {}", self.frames_processed)?;
+        writeln!(
+            f,
+            "  Callbacks: {} (frames min {} / avg {:.1} / max {})",
+            self.callback_count,
+            self.min_callback_frames,
+            self.avg_callback_frames,
+            self.max_callback_frames
+        )?;
+        writeln!(
+            f,
+            "  Callback interval: min {:.2} ms / avg {:.2} ms / max {:.2} ms",
+            self.min_callback_interval.as_secs_f64() * 1000.0,
+            self.avg_callback_interval.as_secs_f64() * 1000.0,
+            self.max_callback_interval.as_secs_f64() * 1000.0
+        )?;
+        writeln!(f, "  Underruns: {}", self.underrun_count)?;
+        match self.output_verified {
+            Some(true) => write!(f, "  Output: verified"),
+            Some(false) if !self.silent_channels.is_empty() => write!(
+                f,
+                "  Output: NOT verified (no signal on channels {:?})",
+                self.silent_channels
+            ),
+            Some(false) => write!(f, "  Output: NOT verified (device consumed no audio)"),
+            None => write!(f, "  Output: not tested"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Propagation delay of spatial sources (speed of sound).
+//!
+//! With [`PetalSonicWorldDesc::distance_delay`](crate::PetalSonicWorldDesc::distance_delay)
+//! enabled, each spatial source is played through a delay line set from its distance to
+//! the listener at 343 m/s, so a distant explosion is seen before it is heard:
+//!
+//! ```ignore
+//! let desc = PetalSonicWorldDesc {
+//!     distance_delay: true,
+//!     max_distance_delay: Duration::from_secs(2), // ~690 m
+//!     ..Default::default()
+//! };
+//! ```
+//!
+//! The delay follows the source and listener while the source plays, ramped across each
+//! block, which also produces a natural Doppler shift. A source playing once keeps
+//! rendering until the end of its clip has left the delay line, so completion events
+//! arrive late by the delay too. Live sources (voice, network) are not delayed.
+
+use crate::spatial::SPEED_OF_SOUND;
+use std::time::Duration;
+
+/// Per-source delay line, updated on the render thread
+#[derive(Debug, Clone)]
+pub(crate) struct DistanceDelay {
+    sample_rate: f32,
+    max_frames: usize,
+    /// Ring buffer of past input, allocated on first use
+    buffer: Vec<f32>,
+    write: usize,
+    /// Delay in frames at the end of the last block, `None` before the first block
+    delay: Option<f32>,
+    /// Delay in frames for the current distance
+    target: f32,
+    /// Frames of input still in the line after the input stopped
+    tail: usize,
+}
+
+impl DistanceDelay {
+    pub fn new(sample_rate: u32, max_delay: Duration) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            max_frames: (max_delay.as_secs_f64() * sample_rate as f64) as usize,
+            buffer: Vec::new(),
+            write: 0,
+            delay: None,
+            target: 0.0,
+            tail: 0,
+        }
+    }
+
+    /// Set the distance to the listener in meters
+    pub fn set_distance(&mut self, meters: f32) {
+        self.target =
+            (meters / SPEED_OF_SOUND * self.sample_rate).clamp(0.0, self.max_frames as f32);
+    }
+
+    /// Forget the buffered audio; the next block starts at the target delay
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.delay = None;
+        self.tail = 0;
+    }
+
+    /// True while input that already ended is still leaving the line
+    pub fn is_draining(&self) -> bool {
+        self.tail > 0
+    }
+
+    /// Delay a block of mono samples in place, of which the first `input_frames` are input
+    /// and the rest is treated as silence. Returns the number of leading frames that can
+    /// carry signal; the rest of the block is silent.
+    pub fn process(&mut self, samples: &mut [f32], input_frames: usize) -> usize {
+        if self.buffer.is_empty() {
+            self.buffer = vec![0.0; self.max_frames + 2];
+        }
+
+        let len = self.buffer.len();
+        let start = self.delay.unwrap_or(self.target);
+        let step = (self.target - start) / samples.len().max(1) as f32;
+        let mut delay = start;
+        for (index, sample) in samples.iter_mut().enumerate() {
+            self.buffer[self.write] = if index < input_frames { *sample } else { 0.0 };
+            delay += step;
+
+            // Linear interpolation between the two frames around the delayed position
+            let position = self.write as f32 + len as f32 - delay;
+            let frame = position.floor();
+            let fraction = position - frame;
+            let earlier = self.buffer[frame as usize % len];
+            let later = self.buffer[(frame as usize + 1) % len];
+            *sample = earlier + (later - earlier) * fraction;
+
+            self.write = (self.write + 1) % len;
+        }
+        self.delay = Some(self.target);
+
+        let pending = if input_frames > 0 {
+            self.target.ceil() as usize + 1
+        } else {
+            self.tail
+        };
+        self.tail = pending.saturating_sub(samples.len() - input_frames);
+        (input_frames + pending).min(samples.len())
+    }
+}
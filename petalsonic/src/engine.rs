@@ -1,12 +1,47 @@
-use crate::audio_data::{ResamplerType, StreamingResampler};
-use crate::config::PetalSonicWorldDesc;
-use crate::error::PetalSonicError;
+use crate::accessibility::{AccessibilityMix, ChannelBalance, SharedAccessibility};
+use crate::assets::{AssetLookup, resolve_asset};
+use crate::audio_data::{PetalSonicAudioData, StreamingResampler};
+use crate::bus_tone::ToneFilter;
+use crate::channel_mix::{ChannelMixMatrix, FrameMixer};
+use crate::comparison::{AbRender, SpatialVariant};
+use crate::config::{
+    HrtfConfig, ListenerCalibration, OutputBufferSizeHint, OutputMode, PetalSonicWorldDesc,
+    ResampleQuality, SimulationQuality, SourceClustering, SourceConfig, SpatialLod, SpatialQuality,
+};
+use crate::debug_snapshot::{DebugSnapshot, SharedOutputLevels, SourceDebugInfo};
+use crate::diagnostics::{
+    CallbackClock, CallbackStats, DiagnosticsReport, EngineStats, MAX_TEST_TONES, OutputInfo,
+    RenderLoadCounters, RenderLoadMeter, ResampleCounters, SILENT_CHANNEL_RMS, TestTone,
+};
+use crate::dither::Ditherer;
 use crate::error::Result;
+use crate::error::{ConfigError, PetalSonicError};
 use crate::events::{PetalSonicEvent, RenderTimingEvent};
+use crate::focus::{BackgroundPolicy, FocusFade, SharedFocus};
+use crate::latency::{self, LatencyProbe, LatencyReport, LatencyTestConfig};
+use crate::logging::{self, StatsLogger, rt_debug, rt_error, rt_info, rt_warn};
+use crate::loudness::{LoudnessMeter, LoudnessReading, SharedLoudness};
+use crate::master_volume::{MasterVolume, SharedMasterVolume};
+use crate::math::Pose;
 use crate::mixer;
-use crate::playback::{PlaybackCommand, PlaybackInstance};
-use crate::spatial::SpatialProcessor;
+use crate::output_eq::{OutputEq, OutputEqFilter};
+use crate::platform::{self, RouteMonitor};
+use crate::playback::{LoopMode, PlayState, PlaybackCommand, PlaybackInstance};
+use crate::reverb::{Reverb, ReverbSettings};
+use crate::rt_guard::{RtPath, RtSection};
+use crate::sampler::{Sampler, SamplerVoices};
+use crate::secondary_output::{
+    SecondaryMix, SecondaryOutput, SecondaryOutputDesc, SecondaryOutputParams, SecondaryOutputStats,
+};
+use crate::spatial::{
+    self, DISTANCE_SCALER, SpatialBypass, SpatialProcessor, SpatialSourceStats, Spatializer,
+    StereoPanner,
+};
+use crate::spatial_info::SpatialInfo;
+use crate::stems::{StemRecorder, StemStats, StemTarget, StemWriter};
+use crate::tap::{OutputTap, TapEncoder, TapProducer, TapSink, TapStats};
 use crate::world::{PetalSonicWorld, SourceId};
+use crate::zones::ZoneEvaluator;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{FromSample, SizedSample};
 use crossbeam_channel::{Receiver, Sender};
@@ -16,27 +51,21 @@ use ringbuf::{
 };
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
-// Stereo frame for ring buffer
-#[derive(Clone, Copy, Debug)]
-struct StereoFrame {
-    left: f32,
-    right: f32,
-}
+/// Number of world blocks the render thread keeps queued in the ring buffer
+const TARGET_FILL_BLOCKS: usize = 4;
 
-impl Default for StereoFrame {
-    fn default() -> Self {
-        Self {
-            left: 0.0,
-            right: 0.0,
-        }
-    }
-}
+/// Device buffer size requested in `OutputBufferSizeHint::LowLatency`
+const LOW_LATENCY_BUFFER_FRAMES: u32 = 256;
+
+/// Device buffer size requested in `OutputBufferSizeHint::PowerSaving`
+const POWER_SAVING_BUFFER_FRAMES: u32 = 4096;
 
 // Thread-local buffers to avoid allocations in audio callback
 thread_local! {
@@ -48,26 +77,211 @@ thread_local! {
 struct AudioCallbackContext {
     is_running: Arc<AtomicBool>,
     frames_processed: Arc<AtomicUsize>,
-    active_playback: Arc<Mutex<HashMap<SourceId, PlaybackInstance>>>,
-    world: Arc<PetalSonicWorld>,
-    ring_buffer_consumer: HeapCons<StereoFrame>,
+    /// Interleaved samples, always pushed and popped in whole frames of `channels` samples
+    ring_buffer_consumer: HeapCons<f32>,
     channels: u16,
+    /// Channel count of the device stream
+    device_channels: u16,
+    /// Converts world frames to device frames when the channel counts (or layouts) differ
+    output_mix: Option<FrameMixer>,
+    /// Callback size/interval statistics for diagnostics
+    callback_stats: Arc<CallbackStats>,
+    callback_clock: CallbackClock,
+    /// Frames to queue before the device consumes any, 0 once reached (see
+    /// `PetalSonicEngine::start_prebuffered`)
+    prebuffer_frames: usize,
+    /// Quantizes the output for integer device formats
+    ditherer: Ditherer,
+    /// Channel swap and balance options, and the state applying them to device frames
+    accessibility: Arc<SharedAccessibility>,
+    channel_balance: ChannelBalance,
+    /// Test tones requested via `play_test_tone`, mixed into device frames
+    test_tone_receiver: Receiver<TestTone>,
+    /// Test tones being played, with capacity for `MAX_TEST_TONES`
+    test_tones: Vec<TestTone>,
+    /// Device frame the test tones are mixed into before dithering
+    device_frame: Vec<f32>,
+    /// Peak and energy of each device channel in this callback while test tones play
+    tone_levels: Vec<(f32, f32)>,
+    /// Receives the levels of `tone_levels`, for `run_diagnostics`
+    device_levels: Arc<SharedOutputLevels>,
+}
+
+/// Drain request shared between `stop_with_drain` and the render thread
+#[derive(Default)]
+struct DrainState {
+    /// Set by `stop_with_drain`; the render thread winds the mix down
+    requested: AtomicBool,
+    /// Length of the master fade-out in world frames, 0 for no fade
+    fade_frames: AtomicUsize,
+    /// Set by the render thread once it stopped producing audio
+    finished: AtomicBool,
+}
+
+/// Block size change handed from `set_block_size` to the render thread, with the state
+/// sized for the new blocks built beforehand
+struct BlockSizeChange {
+    block_size: usize,
+    resampler: Arc<Mutex<StreamingResampler>>,
+    spatial_processor: Option<Arc<Mutex<SpatialProcessor>>>,
+    /// Resampler of the secondary output, if one is open
+    secondary_resampler: Option<StreamingResampler>,
+}
+
+/// Spatializer backends available to the render thread for one `generate_samples` call
+struct Spatializers<'a> {
+    listener_pose: Pose,
+    custom: &'a Mutex<Option<Box<dyn Spatializer>>>,
+    processor: Option<&'a Mutex<SpatialProcessor>>,
+    panner: &'a mut StereoPanner,
+}
+
+impl<'a> Spatializers<'a> {
+    /// Pick the backend for the next block and update its listener pose: a custom backend,
+    /// else the spatial processor, else the stereo panner if there is no processor.
+    /// `custom` and `processor` are the locked backends (`None` if not set or contended).
+    fn select<'b>(
+        &'b mut self,
+        custom: Option<&'b mut Option<Box<dyn Spatializer>>>,
+        processor: Option<&'b mut SpatialProcessor>,
+    ) -> Option<&'b mut dyn Spatializer> {
+        let spatializer: &mut dyn Spatializer = match (custom, processor) {
+            (Some(Some(custom)), _) => custom.as_mut(),
+            (_, Some(processor)) => processor,
+            _ if self.processor.is_none() => &mut *self.panner,
+            _ => return None,
+        };
+
+        if let Err(e) = spatializer.set_listener_pose(self.listener_pose) {
+            log::error!("Failed to update listener pose: {}", e);
+        }
+        Some(spatializer)
+    }
 }
 
 /// Context for render thread
 struct RenderThreadContext {
     shutdown: Arc<AtomicBool>,
+    /// Set when the output device became unavailable; rendering is parked until resumed
+    device_lost: Arc<AtomicBool>,
     active_playback: Arc<Mutex<HashMap<SourceId, PlaybackInstance>>>,
     resampler: Arc<Mutex<StreamingResampler>>,
-    ring_buffer_producer: HeapProd<StereoFrame>,
+    /// Sample-rate conversion counters reported by `PetalSonicEngine::stats`
+    resample_counters: Arc<ResampleCounters>,
+    /// Times blocks against their budget for `PetalSonicEngine::stats`
+    render_load: RenderLoadMeter,
+    /// Interleaved samples, always pushed and popped in whole frames of `channels` samples
+    ring_buffer_producer: HeapProd<f32>,
     channels: u16,
     block_size: usize,
     spatial_processor: Option<Arc<Mutex<SpatialProcessor>>>,
+    /// Backend installed via `set_spatializer`, used instead of the spatial processor
+    custom_spatializer: Arc<Mutex<Option<Box<dyn Spatializer>>>>,
+    /// Attenuation zone parameters, updated from the listener pose before rendering
+    zone_evaluator: ZoneEvaluator,
+    /// Pans spatial sources in stereo while there is no spatial processor
+    stereo_panner: StereoPanner,
+    /// Listener calibration set via `set_listener_calibration`, applied to the panner
+    listener_calibration: Arc<Mutex<ListenerCalibration>>,
+    /// Output mode set via `set_output_mode`, applied to the panner
+    output_mode: Arc<Mutex<OutputMode>>,
     world: Arc<PetalSonicWorld>,
     /// Event sender for emitting playback events (e.g., SourceCompleted)
     event_sender: Sender<PetalSonicEvent>,
+    /// Frames played by the output, the clock haptics events are stamped on
+    frames_processed: Arc<AtomicUsize>,
     /// Timing event sender for performance profiling
     timing_sender: Sender<RenderTimingEvent>,
+    /// Whether render stages are timed and timing events sent
+    profiling: Arc<AtomicBool>,
+    /// Loudness meter for the master mix (only fed while metering is enabled)
+    loudness_meter: LoudnessMeter,
+    /// Published loudness readings and metering toggle
+    loudness: Arc<SharedLoudness>,
+    /// Output peaks published for debug snapshots
+    output_levels: Arc<SharedOutputLevels>,
+    /// Master volume settings and the gain and shelves applying them
+    master_volume: Arc<SharedMasterVolume>,
+    master: MasterVolume,
+    /// Focus state and background policy, and the fade applying them
+    focus: Arc<SharedFocus>,
+    focus_fade: FocusFade,
+    /// Accessibility options and the downmix applying them
+    accessibility: Arc<SharedAccessibility>,
+    accessibility_mix: AccessibilityMix,
+    /// Reverb bus, if the world has one
+    reverb: Option<Reverb>,
+    /// Test tones requested via `play_test_tone`
+    test_tone_receiver: Receiver<TestTone>,
+    /// Test tones currently being rendered
+    test_tones: Vec<TestTone>,
+    /// Samplers created via `create_sampler`
+    samplers: Arc<Mutex<Vec<SamplerVoices>>>,
+    /// Output tap FIFO, set while a tap is running
+    tap_producer: Arc<Mutex<Option<TapProducer>>>,
+    /// Stem FIFOs, set while stems are recorded
+    stem_recorder: Arc<Mutex<Option<StemRecorder>>>,
+    /// Mix of the secondary output, set while one is open
+    secondary_mix: Arc<Mutex<Option<SecondaryMix>>>,
+    /// Calibration EQ of the master output, set via `set_output_eq`
+    output_eq: Arc<Mutex<Option<OutputEqFilter>>>,
+    /// Drain request from `stop_with_drain`
+    drain: Arc<DrainState>,
+    /// True once the render thread reacted to a drain request
+    drain_started: bool,
+    /// Frames of the drain fade-out rendered so far
+    drain_fade_position: usize,
+    /// Bus rendered alone while it is frozen (see [`PetalSonicEngine::freeze_bus`]); the
+    /// sources on other buses are held where they are
+    solo_bus: Option<String>,
+    /// Block size change requested via `set_block_size`, applied between blocks
+    block_size_change: Arc<Mutex<Option<BlockSizeChange>>>,
+    /// Spatial sources stopped by commands whose spatializer state is still to be released
+    stopped_sources: Vec<SourceId>,
+}
+
+/// Render state of `render_offline`: a render context without a device, whose ring
+/// buffer is drained by the caller
+struct OfflineRenderer {
+    ctx: RenderThreadContext,
+    consumer: HeapCons<f32>,
+    /// Converts world frames with the configured `output_mix`, as for a device
+    output_mix: Option<FrameMixer>,
+}
+
+impl OfflineRenderer {
+    /// Render `frames` interleaved frames, processing the world's playback commands before
+    /// each block
+    fn render(&mut self, frames: usize) -> Vec<f32> {
+        let channels = self.ctx.channels as usize;
+        let block_size = self.ctx.block_size;
+        let output_channels = self
+            .output_mix
+            .as_ref()
+            .map_or(channels, |output_mix| output_mix.output_channels() as usize);
+        let mut output = Vec::with_capacity(frames * output_channels);
+        let mut rendered = 0;
+        while rendered < frames {
+            if self.consumer.is_empty() {
+                PetalSonicEngine::render_batch(&mut self.ctx, block_size);
+            }
+            let popped = (self.consumer.occupied_len() / channels).min(frames - rendered);
+            match self.output_mix.as_mut() {
+                None => output.extend(self.consumer.pop_iter().take(popped * channels)),
+                Some(output_mix) => {
+                    for _ in 0..popped {
+                        self.consumer.pop_slice(output_mix.input_mut());
+                        output.extend_from_slice(output_mix.mix());
+                    }
+                }
+            }
+            rendered += popped;
+            self.ctx
+                .frames_processed
+                .fetch_add(popped, Ordering::Relaxed);
+        }
+        output
+    }
 }
 
 /// Parameters for stream creation - groups related parameters to reduce argument count
@@ -77,11 +291,17 @@ struct StreamCreationParams {
     world_sample_rate: u32,
     device_sample_rate: u32,
     channels: u16,
+    device_channels: u16,
+    output_mix: Option<ChannelMixMatrix>,
     active_playback: Arc<Mutex<HashMap<SourceId, PlaybackInstance>>>,
     world: Arc<PetalSonicWorld>,
     render_shutdown: Arc<AtomicBool>,
+    device_lost: Arc<AtomicBool>,
     event_sender: Sender<PetalSonicEvent>,
     timing_sender: Sender<RenderTimingEvent>,
+    callback_stats: Arc<CallbackStats>,
+    test_tone_receiver: Receiver<TestTone>,
+    prebuffer_frames: usize,
 }
 
 /// Callback function type for filling audio samples
@@ -105,12 +325,24 @@ pub struct PetalSonicEngine {
     active_playback: Arc<std::sync::Mutex<HashMap<SourceId, PlaybackInstance>>>,
     /// The actual sample rate used by the audio device (may differ from desc.sample_rate)
     device_sample_rate: u32,
+    /// The channel count used by the audio device (may differ from desc.channels)
+    device_channels: u16,
     /// Render thread handle
     render_thread: Option<thread::JoinHandle<()>>,
     /// Shutdown signal for render thread
     render_shutdown: Arc<AtomicBool>,
+    /// Set by the stream error callback when the device goes away (e.g. OS suspend)
+    device_lost: Arc<AtomicBool>,
+    /// True after `suspend()` until `resume()`
+    suspended: bool,
     /// Spatial audio processor
     spatial_processor: Option<Arc<Mutex<SpatialProcessor>>>,
+    /// Spatializer backend replacing the spatial processor, set via `set_spatializer`
+    custom_spatializer: Arc<Mutex<Option<Box<dyn Spatializer>>>>,
+    /// Listener calibration shared with the render thread's stereo panner
+    listener_calibration: Arc<Mutex<ListenerCalibration>>,
+    /// Output mode shared with the render thread's stereo panner
+    output_mode: Arc<Mutex<OutputMode>>,
     /// Event channel for playback events (e.g., SourceCompleted)
     /// The sender is cloned to render thread, receiver stays here for polling
     event_sender: Sender<PetalSonicEvent>,
@@ -119,40 +351,144 @@ pub struct PetalSonicEngine {
     /// The sender is cloned to render thread, receiver stays here for polling
     timing_sender: Sender<RenderTimingEvent>,
     timing_receiver: Receiver<RenderTimingEvent>,
+    /// Whether the render thread times its stages and sends timing events
+    profiling: Arc<AtomicBool>,
+    /// Focus state and background policy read by the render thread
+    focus: Arc<SharedFocus>,
+    /// Accessibility options read by the render thread
+    accessibility: Arc<SharedAccessibility>,
+    /// Watches the default output device to report route changes
+    route_monitor: Option<RouteMonitor>,
+    /// Master loudness readings published by the render thread
+    loudness: Arc<SharedLoudness>,
+    /// Output peaks published by the render thread
+    output_levels: Arc<SharedOutputLevels>,
+    /// Master volume settings read by the render thread
+    master_volume: Arc<SharedMasterVolume>,
+    /// Chirp channel of latency measurements; the receiver is cloned to the render thread
+    test_tone_sender: Sender<TestTone>,
+    test_tone_receiver: Receiver<TestTone>,
+    /// Test tone channel of `play_test_tone`; the receiver is cloned to the audio callback
+    device_tone_sender: Sender<TestTone>,
+    device_tone_receiver: Receiver<TestTone>,
+    /// Levels of the device channels measured by the audio callback while test tones play
+    device_levels: Arc<SharedOutputLevels>,
+    /// Device callback statistics gathered by the audio callback
+    callback_stats: Arc<CallbackStats>,
+    /// Sample-rate conversion counters, written by the render thread
+    resample_counters: Arc<ResampleCounters>,
+    /// Render load counters, written by the render thread
+    render_load: Arc<RenderLoadCounters>,
+    /// Render-side state of all samplers, shared with the render thread
+    samplers: Arc<Mutex<Vec<SamplerVoices>>>,
+    /// Output tap thread and the FIFO the render thread feeds it through
+    output_tap: Option<OutputTap>,
+    tap_producer: Arc<Mutex<Option<TapProducer>>>,
+    /// Stem writer thread and the FIFOs the render thread feeds it through
+    stem_writer: Option<StemWriter>,
+    stem_recorder: Arc<Mutex<Option<StemRecorder>>>,
+    /// Secondary output stream and the mix the render thread feeds it through
+    secondary_output: Option<SecondaryOutput>,
+    secondary_mix: Arc<Mutex<Option<SecondaryMix>>>,
+    /// Calibration EQ of the master output, shared with the render thread
+    output_eq: Arc<Mutex<Option<OutputEqFilter>>>,
+    /// Drain request shared with the render thread
+    drain: Arc<DrainState>,
+    /// Block size change waiting for the render thread to apply it
+    block_size_change: Arc<Mutex<Option<BlockSizeChange>>>,
+    /// Name, sample format and requested buffer size of the device opened by the last
+    /// `start()`
+    device_name: Option<String>,
+    sample_format: Option<cpal::SampleFormat>,
+    device_buffer_size: Option<usize>,
+    /// Time from a frame being counted in `frames_processed` to it being heard, set via
+    /// `set_clock_offset`
+    clock_offset: Duration,
+    /// Offline render state, created by the first `render_offline` call
+    offline: Option<OfflineRenderer>,
 }
 
 impl PetalSonicEngine {
     /// Create a new audio engine with the given configuration and world
+    ///
+    /// The configuration is validated first; an unsupported `block_size` is adjusted
+    /// (see [`PetalSonicWorldDesc::validated`]), so check [`Self::config`] for the
+    /// effective value.
+    ///
+    /// A world can be rendered by only one engine at a time (see
+    /// [`PetalSonicWorld`#worlds-and-engines](PetalSonicWorld#worlds-and-engines)). The
+    /// engine releases the world when it is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid or another engine is attached to
+    /// `world`.
     pub fn new(desc: PetalSonicWorldDesc, world: Arc<PetalSonicWorld>) -> Result<Self> {
-        // Initialize spatial processor
-        // Use distance_scaler of 10.0 (converts game units to meters, as in reference)
-        let spatial_processor = match SpatialProcessor::new(
-            desc.sample_rate,
+        let mut desc = desc.validated()?;
+        world.attach_engine()?;
+        log::info!(
+            "Engine block size: {} frames ({:.2} ms per block)",
             desc.block_size,
-            10.0,
-            desc.hrtf_path.as_deref(),
-        ) {
-            Ok(processor) => {
-                log::info!("Spatial audio processor initialized");
-                Some(Arc::new(Mutex::new(processor)))
-            }
-            Err(e) => {
-                log::warn!("Failed to initialize spatial audio processor: {}", e);
-                log::warn!("Spatial audio will be disabled");
-                None
+            desc.processing_latency().as_secs_f64() * 1000.0
+        );
+
+        // The world owns the event channel, so its own events are delivered in order with
+        // the render thread's. Unbounded to ensure event emission never blocks the audio thread
+        let (event_sender, event_receiver) = world.event_channel();
+
+        // Later rebuilds of the spatial processor use the resolved path
+        match Self::resolve_hrtf(desc.hrtf_config()) {
+            Ok(hrtf) => desc.hrtf_path = hrtf.sofa_path,
+            Err(lookup) => {
+                let path = desc.hrtf_path.take().unwrap_or_default();
+                log::error!(
+                    "HRTF file '{}' not found, using the default HRTF (tried {})",
+                    path,
+                    Self::format_attempted(&lookup)
+                );
+                let _ = event_sender.send(PetalSonicEvent::HrtfNotFound {
+                    path,
+                    attempted: lookup.attempted,
+                });
             }
-        };
+        }
 
-        // Create event channel for playback events
-        // Unbounded channel to ensure event emission never blocks the audio thread
-        let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+        let spatial_processor = Self::create_spatial_processor(&desc, &event_sender);
+        let loudness = Arc::new(SharedLoudness::new(desc.loudness_metering));
+        let output_levels = Arc::new(SharedOutputLevels::new(desc.channels));
+        let master_volume = Arc::new(SharedMasterVolume::new(
+            desc.master_volume,
+            desc.loudness_compensation,
+            desc.night_mode,
+        ));
+        let listener_calibration = Arc::new(Mutex::new(desc.listener_calibration.clamped()));
+        let output_mode = Arc::new(Mutex::new(desc.output_mode));
+        let output_eq = desc
+            .output_eq
+            .as_ref()
+            .map(|eq| OutputEqFilter::new(eq, desc.sample_rate, desc.channels));
 
         // Create timing channel for performance profiling
         // Unbounded channel to ensure timing emission never blocks the render thread
         let (timing_sender, timing_receiver) = crossbeam_channel::unbounded();
+        let profiling = Arc::new(AtomicBool::new(desc.profiling));
+        let focus = Arc::new(SharedFocus::new(
+            desc.background_policy,
+            (desc.background_fade.as_secs_f64() * desc.sample_rate as f64) as usize,
+        ));
+
+        let accessibility = Arc::new(SharedAccessibility::new(
+            desc.mono_output,
+            desc.swap_channels,
+            desc.balance,
+        ));
+        let (test_tone_sender, test_tone_receiver) = crossbeam_channel::unbounded();
+        let (device_tone_sender, device_tone_receiver) = crossbeam_channel::unbounded();
+        let device_levels = Arc::new(SharedOutputLevels::new(desc.channels));
 
         Ok(Self {
             device_sample_rate: desc.sample_rate, // Will be updated when stream starts
+            device_channels: desc.channels,
             desc,
             stream: None,
             is_running: Arc::new(AtomicBool::new(false)),
@@ -162,14 +498,117 @@ impl PetalSonicEngine {
             active_playback: Arc::new(std::sync::Mutex::new(HashMap::new())),
             render_thread: None,
             render_shutdown: Arc::new(AtomicBool::new(false)),
+            device_lost: Arc::new(AtomicBool::new(false)),
+            suspended: false,
             spatial_processor,
+            custom_spatializer: Arc::new(Mutex::new(None)),
+            listener_calibration,
+            output_mode,
             event_sender,
             event_receiver,
             timing_sender,
             timing_receiver,
+            profiling,
+            focus,
+            accessibility,
+            route_monitor: None,
+            loudness,
+            output_levels,
+            master_volume,
+            test_tone_sender,
+            test_tone_receiver,
+            device_tone_sender,
+            device_tone_receiver,
+            device_levels,
+            callback_stats: Arc::new(CallbackStats::new()),
+            resample_counters: Arc::new(ResampleCounters::new()),
+            render_load: Arc::new(RenderLoadCounters::new()),
+            samplers: Arc::new(Mutex::new(Vec::new())),
+            output_tap: None,
+            tap_producer: Arc::new(Mutex::new(None)),
+            stem_writer: None,
+            stem_recorder: Arc::new(Mutex::new(None)),
+            secondary_output: None,
+            secondary_mix: Arc::new(Mutex::new(None)),
+            output_eq: Arc::new(Mutex::new(output_eq)),
+            drain: Arc::new(DrainState::default()),
+            block_size_change: Arc::new(Mutex::new(None)),
+            device_name: None,
+            sample_format: None,
+            device_buffer_size: None,
+            clock_offset: Duration::ZERO,
+            offline: None,
         })
     }
 
+    /// `hrtf` with its SOFA file looked up in the asset directories (see
+    /// [`crate::assets`]), or the lookup if the file was found nowhere
+    fn resolve_hrtf(hrtf: HrtfConfig) -> std::result::Result<HrtfConfig, AssetLookup> {
+        let Some(path) = &hrtf.sofa_path else {
+            return Ok(hrtf);
+        };
+        let lookup = resolve_asset(path);
+        match lookup.resolved {
+            Some(resolved) => Ok(HrtfConfig {
+                sofa_path: Some(resolved.to_string_lossy().into_owned()),
+                ..hrtf
+            }),
+            None => Err(lookup),
+        }
+    }
+
+    fn format_attempted(lookup: &AssetLookup) -> String {
+        lookup
+            .attempted
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Create the spatial processor for the given configuration
+    ///
+    /// Returns `None` (spatial sources are panned in stereo instead) if spatialization is
+    /// disabled or Steam Audio fails to initialize; the latter is reported with
+    /// `PetalSonicEvent::SpatialFallbackActive`.
+    fn create_spatial_processor(
+        desc: &PetalSonicWorldDesc,
+        event_sender: &Sender<PetalSonicEvent>,
+    ) -> Option<Arc<Mutex<SpatialProcessor>>> {
+        if !desc.enable_spatialization {
+            log::info!("Spatialization disabled, spatial sources will be panned in stereo");
+            return None;
+        }
+
+        match SpatialProcessor::new(
+            desc.sample_rate,
+            desc.block_size,
+            DISTANCE_SCALER,
+            &desc.hrtf_config(),
+            desc.spatial_quality.settings(),
+            desc.simulation_rate_hz,
+            desc.max_spatial_sources,
+        ) {
+            Ok(mut processor) => {
+                log::info!("Spatial audio processor initialized");
+                processor.set_budget(desc.spatial_budget_duration());
+                processor.set_calibration(desc.listener_calibration);
+                processor.set_output_mode(desc.output_mode);
+                processor.set_lod(desc.spatial_lod);
+                processor.set_clustering(desc.source_clustering);
+                Some(Arc::new(Mutex::new(processor)))
+            }
+            Err(e) => {
+                log::warn!("Failed to initialize spatial audio processor: {}", e);
+                log::warn!("Spatial sources will be panned in stereo");
+                let _ = event_sender.send(PetalSonicEvent::SpatialFallbackActive {
+                    reason: e.to_string(),
+                });
+                None
+            }
+        }
+    }
+
     /// Set the callback function that will be called to fill audio buffers
     /// This is the non-blocking callback required by the TODO
     pub fn set_fill_callback<F>(&mut self, callback: F)
@@ -185,31 +624,90 @@ impl PetalSonicEngine {
 
     /// Start the audio engine with automatic playback management
     pub fn start(&mut self) -> Result<()> {
+        self.start_stream(None)
+    }
+
+    /// Start the audio engine, holding the device output silent until the render thread has
+    /// queued `min_fill` of audio
+    ///
+    /// Avoids the underruns slower machines can hit in the first callbacks after
+    /// [`Self::start`], at the cost of `min_fill` of extra startup latency. `min_fill` is
+    /// capped at the render queue target (`block_size` times the queued block count), which
+    /// is all the render thread ever queues.
+    pub fn start_prebuffered(&mut self, min_fill: Duration) -> Result<()> {
+        self.start_stream(Some(min_fill))
+    }
+
+    fn start_stream(&mut self, min_fill: Option<Duration>) -> Result<()> {
         if self.is_running() {
             return Ok(());
         }
+        self.offline = None;
+
+        platform::configure_audio_session(&self.desc.audio_session)?;
 
         let (device, device_config) = Self::init_audio_device()?;
         let device_sample_rate = device_config.sample_rate().0;
 
+        let device_channels =
+            Self::select_device_channels(&device, &device_config, self.desc.channels);
+        let output_mix = self.create_output_mix(device_channels)?;
+
         self.device_sample_rate = device_sample_rate;
+        self.device_channels = device_channels;
+        self.device_levels = Arc::new(SharedOutputLevels::new(device_channels));
         self.log_sample_rate_info(device_sample_rate);
-
-        // Use default buffer size - let the device decide
-        let buffer_size = cpal::BufferSize::Default;
-        let config =
-            Self::create_stream_config(self.desc.channels, device_sample_rate, buffer_size);
-
-        let (stream, render_thread) =
-            self.build_and_start_stream(&device, &device_config, &config, device_sample_rate)?;
+        self.warn_on_device_buffer_size(&device_config);
+
+        let buffer_size =
+            Self::select_buffer_size(&device_config, self.desc.audio_session.buffer_size_hint);
+        let config = Self::create_stream_config(device_channels, device_sample_rate, buffer_size);
+
+        let prebuffer_frames = min_fill.map_or(0, |min_fill| {
+            self.prebuffer_frames(min_fill, device_sample_rate)
+        });
+        let (stream, render_thread) = self.build_and_start_stream(
+            &device,
+            &device_config,
+            &config,
+            device_sample_rate,
+            output_mix,
+            prebuffer_frames,
+        )?;
 
         self.stream = Some(stream);
         self.render_thread = Some(render_thread);
         self.is_running.store(true, Ordering::Relaxed);
 
+        self.device_name = device.name().ok();
+        self.sample_format = Some(device_config.sample_format());
+        self.device_buffer_size = match buffer_size {
+            cpal::BufferSize::Fixed(frames) => Some(frames as usize),
+            cpal::BufferSize::Default => None,
+        };
+        self.route_monitor = Some(RouteMonitor::start(
+            self.device_name.clone(),
+            self.event_sender.clone(),
+        ));
+
         Ok(())
     }
 
+    /// Device frames to queue before the device starts consuming, capped at the render
+    /// queue target
+    fn prebuffer_frames(&self, min_fill: Duration, device_sample_rate: u32) -> usize {
+        let frames = (min_fill.as_secs_f64() * device_sample_rate as f64).ceil() as usize;
+        let queued_frames = self.desc.block_size * TARGET_FILL_BLOCKS;
+        if frames > queued_frames {
+            log::warn!(
+                "Pre-buffer of {} frames exceeds the render queue target, using {} frames",
+                frames,
+                queued_frames
+            );
+        }
+        frames.min(queued_frames)
+    }
+
     /// Initialize the audio device and retrieve its configuration
     fn init_audio_device() -> Result<(cpal::Device, cpal::SupportedStreamConfig)> {
         let host = cpal::default_host();
@@ -241,6 +739,147 @@ impl PetalSonicEngine {
         }
     }
 
+    /// Pick the device channel count: the world channel count if the device supports it,
+    /// otherwise the device's default
+    fn select_device_channels(
+        device: &cpal::Device,
+        device_config: &cpal::SupportedStreamConfig,
+        channels: u16,
+    ) -> u16 {
+        let mut supported: Vec<u16> = match device.supported_output_configs() {
+            Ok(configs) => configs.map(|config| config.channels()).collect(),
+            Err(e) => {
+                log::warn!("Could not query supported output configs: {}", e);
+                return channels;
+            }
+        };
+        supported.sort_unstable();
+        supported.dedup();
+
+        if supported.is_empty() || supported.contains(&channels) {
+            log::info!(
+                "Output channels: {} (device supports {:?})",
+                channels,
+                supported
+            );
+            return channels;
+        }
+
+        let device_channels = device_config.channels();
+        log::warn!(
+            "Output device does not support {} channels (supported: {:?}), mixing to {} channels",
+            channels,
+            supported,
+            device_channels
+        );
+        device_channels
+    }
+
+    /// Create the world-to-device channel mix, or `None` if frames can be copied as is
+    fn create_output_mix(&self, device_channels: u16) -> Result<Option<ChannelMixMatrix>> {
+        match &self.desc.output_mix {
+            Some(matrix)
+                if matrix.input_channels() != self.desc.channels
+                    || matrix.output_channels() != device_channels =>
+            {
+                Err(PetalSonicError::Configuration(format!(
+                    "Output mix maps {} to {} channels, but world has {} and device {} channels",
+                    matrix.input_channels(),
+                    matrix.output_channels(),
+                    self.desc.channels,
+                    device_channels
+                )))
+            }
+            Some(matrix) => Ok(Some(matrix.clone())),
+            None if device_channels == self.desc.channels => Ok(None),
+            None => Ok(Some(ChannelMixMatrix::default_for(
+                self.desc.channels,
+                device_channels,
+            ))),
+        }
+    }
+
+    /// Log the device callback size range and the effective processing latency, and warn if
+    /// the device callback can request more frames than the render thread keeps queued
+    ///
+    /// The block size is not adjusted; a warning suggests a larger one instead.
+    fn warn_on_device_buffer_size(&self, device_config: &cpal::SupportedStreamConfig) {
+        let queued_frames = self.desc.block_size * TARGET_FILL_BLOCKS;
+
+        match device_config.buffer_size() {
+            cpal::SupportedBufferSize::Range { min, max } => {
+                log::info!(
+                    "Device callback size range: {}-{} frames, render queue target: {} frames",
+                    min,
+                    max,
+                    queued_frames
+                );
+                if *min as usize > queued_frames {
+                    log::warn!(
+                        "Device callback size ({} frames) exceeds the render queue target ({} frames); \
+                         increase block_size to avoid underruns",
+                        min,
+                        queued_frames
+                    );
+                }
+            }
+            cpal::SupportedBufferSize::Unknown => {
+                log::info!(
+                    "Device callback size unknown, render queue target: {} frames",
+                    queued_frames
+                );
+            }
+        }
+
+        log::info!(
+            "Effective processing latency: {:.2} ms",
+            self.processing_latency().as_secs_f64() * 1000.0
+        );
+    }
+
+    /// Get the effective processing latency of the engine
+    ///
+    /// This is the duration of one render block plus the audio the render thread keeps
+    /// queued ahead of the device callback. It does not include the device's own latency.
+    pub fn processing_latency(&self) -> Duration {
+        // The ring buffer holds device-rate frames, topped up to TARGET_FILL_BLOCKS blocks
+        let queued_frames = self.desc.block_size * TARGET_FILL_BLOCKS;
+        let queued = Duration::from_secs_f64(queued_frames as f64 / self.device_sample_rate as f64);
+        self.desc.processing_latency() + queued
+    }
+
+    /// Pick the device buffer size for the buffer size hint
+    fn select_buffer_size(
+        device_config: &cpal::SupportedStreamConfig,
+        hint: OutputBufferSizeHint,
+    ) -> cpal::BufferSize {
+        let requested = match hint {
+            // Use default buffer size - let the device decide
+            OutputBufferSizeHint::Default => return cpal::BufferSize::Default,
+            OutputBufferSizeHint::LowLatency => LOW_LATENCY_BUFFER_FRAMES,
+            OutputBufferSizeHint::PowerSaving => POWER_SAVING_BUFFER_FRAMES,
+        };
+
+        match device_config.buffer_size() {
+            cpal::SupportedBufferSize::Range { min, max } => {
+                let frames = requested.clamp(*min, *max);
+                log::info!(
+                    "Requesting device buffer size of {} frames ({:?})",
+                    frames,
+                    hint
+                );
+                cpal::BufferSize::Fixed(frames)
+            }
+            cpal::SupportedBufferSize::Unknown => {
+                log::warn!(
+                    "Device does not report its buffer size range, ignoring {:?}",
+                    hint
+                );
+                cpal::BufferSize::Default
+            }
+        }
+    }
+
     /// Create the stream configuration
     fn create_stream_config(
         channels: u16,
@@ -261,6 +900,8 @@ impl PetalSonicEngine {
         device_config: &cpal::SupportedStreamConfig,
         config: &cpal::StreamConfig,
         device_sample_rate: u32,
+        output_mix: Option<ChannelMixMatrix>,
+        prebuffer_frames: usize,
     ) -> Result<(cpal::Stream, thread::JoinHandle<()>)> {
         let is_running = self.is_running.clone();
         let frames_processed = self.frames_processed.clone();
@@ -279,55 +920,35 @@ impl PetalSonicEngine {
         // Clone timing sender for passing to render thread
         let timing_sender = self.timing_sender.clone();
 
+        // Reset device-lost signal
+        self.device_lost.store(false, Ordering::Relaxed);
+        let device_lost = self.device_lost.clone();
+
+        self.callback_stats.reset();
+
+        let params = StreamCreationParams {
+            is_running,
+            frames_processed,
+            world_sample_rate,
+            device_sample_rate,
+            channels,
+            device_channels: config.channels,
+            output_mix,
+            active_playback,
+            world,
+            render_shutdown,
+            device_lost,
+            event_sender,
+            timing_sender,
+            callback_stats: self.callback_stats.clone(),
+            test_tone_receiver: self.test_tone_receiver.clone(),
+            prebuffer_frames,
+        };
+
         let result = match device_config.sample_format() {
-            cpal::SampleFormat::F32 => self.create_stream::<f32>(
-                device,
-                config,
-                StreamCreationParams {
-                    is_running,
-                    frames_processed,
-                    world_sample_rate,
-                    device_sample_rate,
-                    channels,
-                    active_playback,
-                    world,
-                    render_shutdown,
-                    event_sender,
-                    timing_sender,
-                },
-            )?,
-            cpal::SampleFormat::I16 => self.create_stream::<i16>(
-                device,
-                config,
-                StreamCreationParams {
-                    is_running,
-                    frames_processed,
-                    world_sample_rate,
-                    device_sample_rate,
-                    channels,
-                    active_playback,
-                    world,
-                    render_shutdown,
-                    event_sender,
-                    timing_sender,
-                },
-            )?,
-            cpal::SampleFormat::U16 => self.create_stream::<u16>(
-                device,
-                config,
-                StreamCreationParams {
-                    is_running,
-                    frames_processed,
-                    world_sample_rate,
-                    device_sample_rate,
-                    channels,
-                    active_playback,
-                    world,
-                    render_shutdown,
-                    event_sender,
-                    timing_sender,
-                },
-            )?,
+            cpal::SampleFormat::F32 => self.create_stream::<f32>(device, config, params)?,
+            cpal::SampleFormat::I16 => self.create_stream::<i16>(device, config, params)?,
+            cpal::SampleFormat::U16 => self.create_stream::<u16>(device, config, params)?,
             _ => {
                 return Err(PetalSonicError::AudioFormat(
                     "Unsupported sample format".into(),
@@ -344,158 +965,2013 @@ impl PetalSonicEngine {
         Ok((stream, render_thread))
     }
 
-    /// Stop the audio engine
-    pub fn stop(&mut self) -> Result<()> {
-        // Signal render thread to shutdown
-        self.render_shutdown.store(true, Ordering::Relaxed);
+    /// Change the render block size without recreating the engine
+    ///
+    /// The spatial processor and resampler for the new block size are built on the calling
+    /// thread and handed to the render thread, which switches to them between two blocks;
+    /// the device stream keeps running. Active playback instances (including their
+    /// positions) and registered sources are preserved. Per-source spatial effects are
+    /// recreated on the next processed block.
+    ///
+    /// The block size is validated the same way as in [`Self::new`], so the effective value
+    /// may differ from the requested one; check [`Self::config`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid or the resampler cannot be created.
+    pub fn set_block_size(&mut self, block_size: usize) -> Result<()> {
+        let desc = PetalSonicWorldDesc {
+            block_size,
+            ..self.desc.clone()
+        }
+        .validated()?;
 
-        // Stop the audio stream
-        if let Some(stream) = self.stream.take() {
-            self.is_running.store(false, Ordering::Relaxed);
-            drop(stream); // This stops the stream
+        if desc.block_size == self.desc.block_size {
+            return Ok(());
         }
 
-        // Wait for render thread to finish
+        log::info!(
+            "Changing block size: {} -> {} frames",
+            self.desc.block_size,
+            desc.block_size
+        );
+
+        // Steam Audio effects are sized for a fixed frame size, so the processor is rebuilt
+        let bypass = self.spatial_bypass();
+        let spatial_processor = Self::create_spatial_processor(&desc, &self.event_sender);
+        if !bypass.is_none()
+            && let Some(processor) = &spatial_processor
+            && let Ok(mut processor) = processor.lock()
+        {
+            processor.set_bypass(bypass);
+        }
+
+        // The secondary mix converts blocks of the old size
+        let secondary_resampler = self
+            .secondary_output
+            .as_ref()
+            .map(|output| {
+                StreamingResampler::with_quality(
+                    desc.sample_rate,
+                    output.sample_rate(),
+                    desc.channels,
+                    desc.block_size,
+                    desc.output_resample_quality,
+                )
+            })
+            .transpose()?;
+
+        if self.is_running() {
+            let resampler = Self::create_resampler(
+                desc.sample_rate,
+                self.device_sample_rate,
+                desc.channels,
+                desc.block_size,
+                desc.output_resample_quality,
+            )?;
+            *self
+                .block_size_change
+                .lock()
+                .map_err(|_| PetalSonicError::Engine("Block size change lock poisoned".into()))? =
+                Some(BlockSizeChange {
+                    block_size: desc.block_size,
+                    resampler,
+                    spatial_processor: spatial_processor.clone(),
+                    secondary_resampler,
+                });
+        } else if let Some(resampler) = secondary_resampler
+            && let Ok(mut mix) = self.secondary_mix.lock()
+            && let Some(mix) = mix.as_mut()
+        {
+            mix.set_block_size(desc.block_size, resampler);
+        }
+
+        self.spatial_processor = spatial_processor;
+        self.offline = None;
+        self.desc = desc;
+        Ok(())
+    }
+
+    /// Switch the render state to the block size of a change from `set_block_size`
+    ///
+    /// Runs on the render thread between two blocks, outside of the real-time section, as
+    /// it resizes buffers.
+    fn apply_block_size_change(ctx: &mut RenderThreadContext, change: BlockSizeChange) {
+        ctx.block_size = change.block_size;
+        ctx.resampler = change.resampler;
+        ctx.spatial_processor = change.spatial_processor;
+        if let Some(reverb) = ctx.reverb.as_mut() {
+            reverb.set_block_size(change.block_size);
+        }
+        // A secondary output opened after the change is already sized for it
+        if let Some(resampler) = change.secondary_resampler
+            && let Ok(mut mix) = ctx.secondary_mix.lock()
+            && let Some(mix) = mix.as_mut()
+            && mix.block_size() != change.block_size
+        {
+            mix.set_block_size(change.block_size, resampler);
+        }
+        log::info!("Render thread switched to {} frame blocks", ctx.block_size);
+    }
+
+    /// Stop the render thread and wait for the audio it already queued to reach the device
+    fn drain_render_thread(&mut self) {
+        self.render_shutdown.store(true, Ordering::Relaxed);
+
         if let Some(thread) = self.render_thread.take()
             && let Err(e) = thread.join()
         {
             log::error!("Error joining render thread: {:?}", e);
         }
 
-        Ok(())
+        // The audio callback keeps consuming the ring buffer until the stream is dropped
+        thread::sleep(self.processing_latency());
     }
 
-    /// Get the number of audio frames processed since start
-    pub fn frames_processed(&self) -> usize {
-        self.frames_processed.load(Ordering::Relaxed)
-    }
+    /// Suspend the engine, releasing the audio device
+    ///
+    /// The stream and render thread are shut down, but all playback state (active sources,
+    /// their positions, the spatial processor) is kept. Commands sent to the world while
+    /// suspended are applied after [`Self::resume`]. Emits `EngineSuspended`.
+    ///
+    /// Use this when the app goes to the background or loses focus.
+    pub fn suspend(&mut self) -> Result<()> {
+        if self.suspended || !self.is_running() {
+            return Ok(());
+        }
 
-    /// Get the engine configuration
-    pub fn config(&self) -> &PetalSonicWorldDesc {
-        &self.desc
+        log::info!("Suspending audio engine");
+        self.stop()?;
+        self.suspended = true;
+        let _ = self.event_sender.send(PetalSonicEvent::EngineSuspended);
+
+        Ok(())
     }
 
-    /// Poll for playback events (non-blocking)
+    /// Resume the engine after [`Self::suspend`] or after the device was lost
     ///
-    /// Returns a vector of all events that have occurred since the last poll.
-    /// This should be called regularly (e.g., each frame) to receive events like
-    /// `SourceCompleted` which indicate when audio sources finish playing.
+    /// Reopens the (current default) output device and continues playback where it left
+    /// off. Emits `EngineResumed`. Does nothing if the engine is not suspended.
     ///
-    /// # Example Flow
+    /// # Errors
     ///
-    /// 1. Audio finishes playing in render thread
-    /// 2. `SourceCompleted` event is emitted to the channel
-    /// 3. Source is auto-removed from `active_playback` (stops mixing)
-    /// 4. Source remains in world storage for potential replay
-    /// 5. GUI calls `poll_events()` and receives the event
-    /// 6. GUI removes from UI and optionally calls `world.remove_audio_data(id)`
-    pub fn poll_events(&self) -> Vec<PetalSonicEvent> {
-        let mut events = Vec::new();
-        while let Ok(event) = self.event_receiver.try_recv() {
-            events.push(event);
+    /// Returns an error if the audio device cannot be reopened.
+    pub fn resume(&mut self) -> Result<()> {
+        if !self.is_suspended() {
+            return Ok(());
         }
-        events
+
+        log::info!("Resuming audio engine");
+        if self.is_running() {
+            // Device was lost while running, the stream is dead but still owned
+            self.stop()?;
+        }
+        self.start()?;
+        self.suspended = false;
+        let _ = self.event_sender.send(PetalSonicEvent::EngineResumed);
+
+        Ok(())
     }
 
-    /// Poll for timing events (non-blocking)
+    /// Returns true if the engine was suspended, either explicitly via [`Self::suspend`]
+    /// or automatically because the output device became unavailable
+    pub fn is_suspended(&self) -> bool {
+        self.suspended || self.device_lost.load(Ordering::Relaxed)
+    }
+
+    /// Stop the audio engine, letting the current audio play out first
     ///
-    /// Returns a vector of all timing events that have occurred since the last poll.
-    /// This should be called regularly (e.g., each frame) for performance profiling.
+    /// Instead of cutting playback off like [`Self::stop`], the engine keeps rendering until
+    /// all sources that play once have finished; looping sources play to the end of their
+    /// current iteration. With `fade`, the master mix additionally fades out over that
+    /// duration and the drain ends once the fade is done, which also covers live sources
+    /// that never end. The audio already queued for the device then plays out before the
+    /// stream is closed.
     ///
-    /// Each event contains timing information for a single render iteration:
-    /// - Mixing time (microseconds)
-    /// - Spatial processing time (microseconds)
-    /// - Resampling time (microseconds)
-    /// - Total render time (microseconds)
-    pub fn poll_timing_events(&self) -> Vec<RenderTimingEvent> {
-        let mut events = Vec::new();
-        while let Ok(event) = self.timing_receiver.try_recv() {
-            events.push(event);
+    /// Blocks the calling thread for at most about `timeout`; after that the engine is
+    /// stopped regardless. Emits `Drained` with `timed_out` set accordingly.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the engine drained completely, `false` if it was cut off at the timeout.
+    pub fn stop_with_drain(&mut self, timeout: Duration, fade: Option<Duration>) -> Result<bool> {
+        if !self.is_running() || self.render_thread.is_none() {
+            self.stop()?;
+            return Ok(true);
+        }
+
+        let fade_frames = fade.map_or(0, |fade| {
+            ((fade.as_secs_f64() * self.desc.sample_rate as f64) as usize).max(1)
+        });
+        log::info!(
+            "Draining audio engine (timeout {:?}, fade {:?})",
+            timeout,
+            fade
+        );
+        self.drain.fade_frames.store(fade_frames, Ordering::Relaxed);
+        self.drain.finished.store(false, Ordering::Relaxed);
+        self.drain.requested.store(true, Ordering::Release);
+
+        let deadline = Instant::now() + timeout;
+        while !self.drain.finished.load(Ordering::Acquire) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let drained = self.drain.finished.load(Ordering::Acquire);
+        if drained {
+            self.drain_render_thread();
+        } else {
+            log::warn!("Drain timed out after {:?}, stopping", timeout);
+        }
+        self.stop()?;
+        self.drain.requested.store(false, Ordering::Relaxed);
+
+        let _ = self.event_sender.send(PetalSonicEvent::Drained {
+            timed_out: !drained,
+        });
+        Ok(drained)
+    }
+
+    /// Stop the audio engine
+    ///
+    /// Playback is cut off immediately; use [`Self::stop_with_drain`] to let it play out.
+    pub fn stop(&mut self) -> Result<()> {
+        // Signal render thread to shutdown
+        self.render_shutdown.store(true, Ordering::Relaxed);
+        self.route_monitor = None;
+
+        // Stop the audio stream
+        if let Some(stream) = self.stream.take() {
+            self.is_running.store(false, Ordering::Relaxed);
+            drop(stream); // This stops the stream
+        }
+
+        // Wait for render thread to finish
+        if let Some(thread) = self.render_thread.take()
+            && let Err(e) = thread.join()
+        {
+            log::error!("Error joining render thread: {:?}", e);
+        }
+
+        // The next start sizes its render state from the configuration; a change the render
+        // thread did not pick up would bring the resampler of the old device
+        if let Ok(mut change) = self.block_size_change.lock() {
+            *change = None;
+        }
+
+        Ok(())
+    }
+
+    /// Render `frames` frames of the world without an output device
+    ///
+    /// Runs the same processing as the render thread (playback commands, spatialization,
+    /// reverb, master volume, EQ) at the world sample rate and returns the interleaved
+    /// samples, `frames * channels` of them. If the world description sets an `output_mix`,
+    /// the frames are converted with it as for a device with its output channels. Events of the rendered blocks are delivered
+    /// through [`Self::poll_events`] as usual. Successive calls continue where the last one
+    /// stopped, so a test or tool can move sources between calls and render faster than
+    /// real time:
+    ///
+    /// ```ignore
+    /// world.play(source_id, LoopMode::Once)?;
+    /// let first_second = engine.render_offline(48_000)?;
+    /// world.update_source_config(source_id, SourceConfig::spatial(moved))?;
+    /// let next_second = engine.render_offline(48_000)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is running; offline rendering would compete with the
+    /// render thread for the playback state.
+    pub fn render_offline(&mut self, frames: usize) -> Result<Vec<f32>> {
+        if self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot render offline while the engine is running".into(),
+            ));
+        }
+
+        if self.offline.is_none() {
+            self.offline = Some(self.create_offline_renderer()?);
+        }
+        let Some(offline) = self.offline.as_mut() else {
+            return Ok(Vec::new());
+        };
+        Ok(offline.render(frames))
+    }
+
+    /// Render `duration` of a bus into a new clip ("freeze" it)
+    ///
+    /// Renders the sources assigned to `bus` (see
+    /// [`PetalSonicWorld::set_source_bus`](crate::PetalSonicWorld::set_source_bus)) offline,
+    /// as they would enter the mix, with their spatialization and reverb. Sources on other
+    /// buses are held where they are, and the master volume, EQ, test tones and samplers
+    /// are left out. A static combination of layers, e.g. procedural ambience, then costs
+    /// the memory of one clip instead of the CPU of all its sources:
+    ///
+    /// ```ignore
+    /// for layer in &ambience_layers {
+    ///     world.set_source_bus(*layer, Some("ambience"))?;
+    ///     world.play(*layer, LoopMode::Infinite)?;
+    /// }
+    /// let frozen = engine.freeze_bus("ambience", Duration::from_secs(30))?;
+    /// for layer in &ambience_layers {
+    ///     world.stop(*layer)?;
+    /// }
+    /// let ambience = world.register_audio(Arc::new(frozen.to_mono()?), SourceConfig::non_spatial())?;
+    /// world.play(ambience, LoopMode::Infinite)?;
+    /// ```
+    ///
+    /// The clip has the world's sample rate and channel count. The sources on the bus
+    /// advance by `duration` as if they had played, emitting their events as usual; the
+    /// output clock of the engine does not. Playback commands sent before the call apply
+    /// to all sources first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is running, as for [`Self::render_offline`], or if
+    /// `duration` is shorter than a frame.
+    pub fn freeze_bus(
+        &mut self,
+        bus: &str,
+        duration: Duration,
+    ) -> Result<Arc<PetalSonicAudioData>> {
+        if self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot freeze a bus while the engine is running".into(),
+            ));
+        }
+        let frames = (duration.as_secs_f64() * self.desc.sample_rate as f64).round() as usize;
+        if frames == 0 {
+            return Err(PetalSonicError::Configuration(format!(
+                "Freeze duration {:?} is shorter than a frame",
+                duration
+            )));
+        }
+
+        log::info!("Freezing bus '{}' ({:?})", bus, duration);
+        let mut renderer = self.create_freeze_renderer(bus)?;
+        let samples = renderer.render(frames);
+        PetalSonicAudioData::from_samples(samples, self.desc.sample_rate, self.desc.channels)
+    }
+
+    /// Render the next `frames` frames of the scene twice, with the spatial variants `a`
+    /// and `b`, for A/B comparisons (see [`crate::comparison`])
+    ///
+    /// Both renders start from the current playback state, after the playback commands
+    /// sent so far, and are aligned frame for frame. Each uses a fresh spatial processor
+    /// with the variant's HRTF and quality and the engine's other spatial settings
+    /// (bypass, calibration, output mode, level of detail, clustering). Like
+    /// [`Self::freeze_bus`] they leave out the output EQ, test tones, samplers, taps and
+    /// stems; the master volume applies. The scene does not advance and events of the
+    /// renders are discarded. Live sources are read by both renders and lose the audio
+    /// they consume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is running, as for [`Self::render_offline`], if
+    /// spatial audio is not available, or if a variant's HRTF cannot be found or loaded.
+    pub fn render_ab(
+        &mut self,
+        a: &SpatialVariant,
+        b: &SpatialVariant,
+        frames: usize,
+    ) -> Result<AbRender> {
+        if self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot render an A/B comparison while the engine is running".into(),
+            ));
+        }
+        let processor_a = self.create_variant_processor(a)?;
+        let processor_b = self.create_variant_processor(b)?;
+
+        // Start both renders from the state the next real block would start from
+        let mut stopped_sources = Vec::new();
+        Self::process_playback_commands(&self.world, &self.active_playback, &mut stopped_sources);
+        if let Some(processor) = &self.spatial_processor
+            && let Ok(mut processor) = processor.lock()
+        {
+            for source_id in stopped_sources {
+                processor.remove_effects_for_source(source_id);
+            }
+        }
+        let pending_reverb = self.world.take_pending_reverb();
+
+        let render = |processor: SpatialProcessor| -> Result<Vec<f32>> {
+            let (event_sender, _event_receiver) = crossbeam_channel::unbounded();
+            let (timing_sender, _timing_receiver) = crossbeam_channel::unbounded();
+            let mut renderer = self.create_comparison_renderer(processor)?;
+            renderer.ctx.event_sender = event_sender;
+            renderer.ctx.timing_sender = timing_sender;
+            Ok(renderer.render(frames))
+        };
+        let rendered = render(processor_a).and_then(|a| Ok((a, render(processor_b)?)));
+
+        if let Some(pending) = pending_reverb {
+            self.world.restore_pending_reverb(pending);
+        }
+        let (a, b) = rendered?;
+        Ok(AbRender {
+            a,
+            b,
+            sample_rate: self.desc.sample_rate,
+            channels: self.desc.channels,
+        })
+    }
+
+    /// Create a spatial processor rendering `variant` with the engine's other spatial
+    /// settings, for `render_ab`
+    fn create_variant_processor(&self, variant: &SpatialVariant) -> Result<SpatialProcessor> {
+        let current = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+        let bypass = current
+            .lock()
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+            })?
+            .bypass();
+        let hrtf = Self::resolve_hrtf(variant.hrtf.clone()).map_err(|lookup| {
+            PetalSonicError::Configuration(format!(
+                "HRTF file not found (tried {})",
+                Self::format_attempted(&lookup)
+            ))
+        })?;
+
+        let mut processor = SpatialProcessor::new(
+            self.desc.sample_rate,
+            self.desc.block_size,
+            DISTANCE_SCALER,
+            &hrtf,
+            variant.quality.settings(),
+            self.desc.simulation_rate_hz,
+            self.desc.max_spatial_sources,
+        )?;
+        processor.set_bypass(bypass);
+        processor.set_budget(self.desc.spatial_budget_duration());
+        processor.set_calibration(self.desc.listener_calibration);
+        processor.set_output_mode(self.desc.output_mode);
+        processor.set_lod(self.desc.spatial_lod);
+        processor.set_clustering(self.desc.source_clustering);
+        Ok(processor)
+    }
+
+    /// Create the render state of one `render_ab` variant: an offline renderer of a copy of
+    /// the playback state, spatialized by `processor`, with its own clock and reverb and
+    /// without the engine's EQ, taps and stems
+    fn create_comparison_renderer(&self, processor: SpatialProcessor) -> Result<OfflineRenderer> {
+        let sample_rate = self.desc.sample_rate;
+        let channels = self.desc.channels;
+        let playback: HashMap<SourceId, PlaybackInstance> = self
+            .active_playback
+            .lock()
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to lock playback state: {}", e)))?
+            .iter()
+            .map(|(source_id, instance)| (*source_id, instance.clone()))
+            .collect();
+
+        let mut renderer = self.create_offline_renderer()?;
+        let ctx = &mut renderer.ctx;
+        ctx.active_playback = Arc::new(Mutex::new(playback));
+        ctx.spatial_processor = Some(Arc::new(Mutex::new(processor)));
+        ctx.custom_spatializer = Arc::new(Mutex::new(None));
+        ctx.frames_processed = Arc::new(AtomicUsize::new(self.frames_processed()));
+        ctx.loudness = Arc::new(SharedLoudness::new(false));
+        ctx.output_levels = Arc::new(SharedOutputLevels::new(channels));
+        ctx.focus = Arc::new(SharedFocus::new(BackgroundPolicy::ContinueInBackground, 0));
+        ctx.focus_fade = FocusFade::new(&ctx.focus, 0);
+        // The environment replaces the reverb of the world description
+        if let Some(environment) = self.world.environment() {
+            ctx.reverb = Some(Reverb::new(
+                environment.reverb_settings(),
+                sample_rate,
+                self.desc.block_size,
+            ));
+        }
+        ctx.test_tone_receiver = crossbeam_channel::never();
+        ctx.samplers = Arc::new(Mutex::new(Vec::new()));
+        ctx.tap_producer = Arc::new(Mutex::new(None));
+        ctx.stem_recorder = Arc::new(Mutex::new(None));
+        ctx.secondary_mix = Arc::new(Mutex::new(None));
+        ctx.output_eq = Arc::new(Mutex::new(None));
+        ctx.drain = Arc::new(DrainState::default());
+        Ok(renderer)
+    }
+
+    /// Create the render state of `freeze_bus`: an offline renderer of `bus` alone, with
+    /// its own clock and without the engine's master processing, taps and stems
+    fn create_freeze_renderer(&self, bus: &str) -> Result<OfflineRenderer> {
+        let sample_rate = self.desc.sample_rate;
+        let channels = self.desc.channels;
+        let mut renderer = self.create_offline_renderer()?;
+        let ctx = &mut renderer.ctx;
+        ctx.solo_bus = Some(bus.to_string());
+        ctx.frames_processed = Arc::new(AtomicUsize::new(self.frames_processed()));
+        ctx.loudness = Arc::new(SharedLoudness::new(false));
+        ctx.output_levels = Arc::new(SharedOutputLevels::new(channels));
+        ctx.master_volume = Arc::new(SharedMasterVolume::new(1.0, false, false));
+        ctx.master = MasterVolume::new(sample_rate, channels, &ctx.master_volume);
+        ctx.focus = Arc::new(SharedFocus::new(BackgroundPolicy::ContinueInBackground, 0));
+        ctx.focus_fade = FocusFade::new(&ctx.focus, 0);
+        ctx.accessibility = Arc::new(SharedAccessibility::new(false, false, 0.0));
+        ctx.accessibility_mix = AccessibilityMix::new(&ctx.accessibility);
+        ctx.test_tone_receiver = crossbeam_channel::never();
+        ctx.samplers = Arc::new(Mutex::new(Vec::new()));
+        ctx.tap_producer = Arc::new(Mutex::new(None));
+        ctx.stem_recorder = Arc::new(Mutex::new(None));
+        ctx.secondary_mix = Arc::new(Mutex::new(None));
+        ctx.output_eq = Arc::new(Mutex::new(None));
+        ctx.drain = Arc::new(DrainState::default());
+        Ok(renderer)
+    }
+
+    /// Create the render state of `render_offline`, resampling world to world rate
+    fn create_offline_renderer(&self) -> Result<OfflineRenderer> {
+        let block_size = self.desc.block_size;
+        let channels = self.desc.channels;
+        let resampler = Self::create_resampler(
+            self.desc.sample_rate,
+            self.desc.sample_rate,
+            channels,
+            block_size,
+            self.desc.output_resample_quality,
+        )?;
+        let ring_buffer = HeapRb::<f32>::new(block_size * 4 * channels as usize);
+        let (producer, consumer) = ring_buffer.split();
+
+        let params = StreamCreationParams {
+            is_running: self.is_running.clone(),
+            frames_processed: self.frames_processed.clone(),
+            world_sample_rate: self.desc.sample_rate,
+            device_sample_rate: self.desc.sample_rate,
+            channels,
+            device_channels: channels,
+            output_mix: None,
+            active_playback: self.active_playback.clone(),
+            world: self.world.clone(),
+            render_shutdown: self.render_shutdown.clone(),
+            device_lost: self.device_lost.clone(),
+            event_sender: self.event_sender.clone(),
+            timing_sender: self.timing_sender.clone(),
+            callback_stats: self.callback_stats.clone(),
+            test_tone_receiver: self.test_tone_receiver.clone(),
+            prebuffer_frames: 0,
+        };
+        let mut ctx = self.create_render_context(&params, producer, resampler);
+        // Offline conversions and blocks are not the device's
+        ctx.resample_counters = Arc::new(ResampleCounters::new());
+        ctx.render_load = RenderLoadMeter::new(Arc::new(RenderLoadCounters::new()));
+        Ok(OfflineRenderer {
+            ctx,
+            consumer,
+            output_mix: self.desc.output_mix.clone().map(FrameMixer::new),
+        })
+    }
+
+    /// Get the number of audio frames processed since start
+    pub fn frames_processed(&self) -> usize {
+        self.frames_processed.load(Ordering::Relaxed)
+    }
+
+    /// Get the engine configuration
+    pub fn config(&self) -> &PetalSonicWorldDesc {
+        &self.desc
+    }
+
+    /// Poll for playback events (non-blocking)
+    ///
+    /// Returns a vector of all events that have occurred since the last poll.
+    /// This should be called regularly (e.g., each frame) to receive events like
+    /// `SourceCompleted` which indicate when audio sources finish playing.
+    ///
+    /// # Example Flow
+    ///
+    /// 1. Audio finishes playing in render thread
+    /// 2. `SourceCompleted` event is emitted to the channel
+    /// 3. Source is auto-removed from `active_playback` (stops mixing)
+    /// 4. Source remains in world storage for potential replay
+    /// 5. GUI calls `poll_events()` and receives the event
+    /// 6. GUI removes from UI and optionally calls `world.remove_audio_data(id)`
+    ///
+    /// While the engine is running, a background thread checks once per second whether the
+    /// default output device changed, e.g. when headphones are plugged in, and reports it as
+    /// `DeviceChanged`. Restart the engine with `stop()` + `start()` to move to the new device.
+    pub fn poll_events(&self) -> Vec<PetalSonicEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.event_receiver.try_recv() {
+            events.push(event);
+        }
+
+        events
+    }
+
+    /// Poll for timing events (non-blocking)
+    ///
+    /// Returns a vector of all timing events that have occurred since the last poll.
+    /// This should be called regularly (e.g., each frame) for performance profiling.
+    ///
+    /// Each event contains timing information for a single render iteration:
+    /// - Mixing time (microseconds)
+    /// - Spatial processing time (microseconds)
+    /// - Resampling time (microseconds)
+    /// - Total render time (microseconds)
+    ///
+    /// No events are sent while profiling is disabled (see
+    /// [`set_profiling_enabled`](Self::set_profiling_enabled)).
+    pub fn poll_timing_events(&self) -> Vec<RenderTimingEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.timing_receiver.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    /// Enable or disable timing of the render stages
+    ///
+    /// While disabled, the render thread skips the clock reads timing the mixing,
+    /// resampling and total stages, and sends no timing events. Some timings always run,
+    /// because features other than profiling depend on them: each block is timed for the
+    /// render load in [`stats`](Self::stats), and the spatial processor times every source
+    /// and its shared stages to keep within its CPU budget (and its whole pass, which costs
+    /// one more clock read per block). Takes effect from the next rendered block.
+    pub fn set_profiling_enabled(&self, enabled: bool) {
+        self.profiling.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether the render stages are being timed
+    pub fn is_profiling_enabled(&self) -> bool {
+        self.profiling.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable loudness metering of the master mix
+    ///
+    /// Metering runs on the render thread after mixing. Readings are kept when metering is
+    /// disabled and continue from where they left off when it is re-enabled.
+    pub fn set_loudness_metering(&self, enabled: bool) {
+        self.loudness.set_enabled(enabled);
+    }
+
+    /// Get the latest loudness reading of the master mix
+    ///
+    /// Returns `None` if loudness metering is disabled. See [`LoudnessReading`] for the
+    /// measured values.
+    pub fn loudness(&self) -> Option<LoudnessReading> {
+        self.loudness.is_enabled().then(|| self.loudness.reading())
+    }
+
+    /// Reset the loudness meter, starting a new integrated loudness measurement
+    pub fn reset_loudness(&self) {
+        self.loudness.request_reset();
+    }
+
+    /// Set the linear gain of the device output
+    ///
+    /// Negative values are treated as 0. Ramped over the next rendered block.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        let volume = if volume.is_finite() {
+            volume.max(0.0)
+        } else {
+            1.0
+        };
+        self.master_volume.set_volume(volume);
+        self.desc.master_volume = volume;
+    }
+
+    /// Get the linear gain of the device output
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume.volume()
+    }
+
+    /// Enable or disable loudness compensation of the master volume
+    ///
+    /// While enabled, volumes below 1.0 also boost the low and high end (see
+    /// [`crate::master_volume`]). Takes effect on the next rendered block.
+    pub fn set_loudness_compensation(&mut self, enabled: bool) {
+        self.master_volume.set_compensation(enabled);
+        self.desc.loudness_compensation = enabled;
+    }
+
+    /// Check whether loudness compensation of the master volume is enabled
+    pub fn loudness_compensation(&self) -> bool {
+        self.master_volume.compensation()
+    }
+
+    /// Enable or disable night mode, which reduces the dynamic range of the output
+    ///
+    /// The compressor and limiter (see [`crate::night_mode`]) fade in or out over half a
+    /// second, starting with the next rendered block.
+    pub fn set_night_mode(&mut self, enabled: bool) {
+        self.master_volume.set_night_mode(enabled);
+        self.desc.night_mode = enabled;
+    }
+
+    /// Check whether night mode is enabled
+    pub fn night_mode(&self) -> bool {
+        self.master_volume.night_mode()
+    }
+
+    /// Tell the engine whether the application is in the foreground, e.g. on window focus
+    /// changes; the output is then handled by the background policy (see
+    /// [`crate::focus`])
+    pub fn set_foreground(&self, foreground: bool) {
+        if foreground != self.focus.is_foreground() {
+            log::info!(
+                "Application moved to the {} ({:?})",
+                if foreground {
+                    "foreground"
+                } else {
+                    "background"
+                },
+                self.focus.policy()
+            );
+        }
+        self.focus.set_foreground(foreground);
+    }
+
+    /// Check whether the application is in the foreground (see [`Self::set_foreground`])
+    pub fn is_foreground(&self) -> bool {
+        self.focus.is_foreground()
+    }
+
+    /// Change what the engine does while the application is in the background; applies
+    /// immediately if it is
+    pub fn set_background_policy(&mut self, policy: BackgroundPolicy) {
+        self.focus.set_policy(policy);
+        self.desc.background_policy = policy;
+    }
+
+    /// Get the current background policy
+    pub fn background_policy(&self) -> BackgroundPolicy {
+        self.focus.policy()
+    }
+
+    /// Enable or disable mono output, which plays the same downmix on all output channels
+    ///
+    /// Meant for players with hearing in one ear only (see [`crate::accessibility`]).
+    /// Crossfaded over the next rendered block.
+    pub fn set_mono_output(&mut self, enabled: bool) {
+        self.accessibility.set_mono(enabled);
+        self.desc.mono_output = enabled;
+    }
+
+    /// Check whether mono output is enabled
+    pub fn mono_output(&self) -> bool {
+        self.accessibility.mono()
+    }
+
+    /// Swap the left and right output channel, e.g. for headphones worn the other way
+    ///
+    /// Applies to the first two device channels (see [`crate::accessibility`]). Crossfaded
+    /// over the next audio callback.
+    pub fn set_swap_channels(&mut self, enabled: bool) {
+        self.accessibility.set_swap_channels(enabled);
+        self.desc.swap_channels = enabled;
+    }
+
+    /// Check whether the left and right output channel are swapped
+    pub fn swap_channels(&self) -> bool {
+        self.accessibility.swap_channels()
+    }
+
+    /// Set the balance between the left and right output channel
+    ///
+    /// -1.0 plays the left channel only, 1.0 the right channel only and 0.0 both at full
+    /// level. Values are clamped to that range; non-finite values reset the balance to
+    /// 0.0. Ramped over the next audio callback.
+    pub fn set_balance(&mut self, balance: f32) {
+        let balance = if balance.is_finite() {
+            balance.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.accessibility.set_balance(balance);
+        self.desc.balance = balance;
+    }
+
+    /// Get the balance between the left and right output channel
+    pub fn balance(&self) -> f32 {
+        self.accessibility.balance()
+    }
+
+    /// Set or clear the calibration EQ of the master output
+    ///
+    /// Takes effect on the next rendered block. The new EQ starts with cleared filter
+    /// state, so switching profiles during playback may click briefly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the EQ has invalid bands (see [`OutputEq::validate`]).
+    pub fn set_output_eq(&mut self, eq: Option<OutputEq>) -> Result<()> {
+        if let Some(eq) = &eq {
+            eq.validate()?;
+        }
+
+        // Built here so the render thread never allocates filter state
+        let filter = eq
+            .as_ref()
+            .map(|eq| OutputEqFilter::new(eq, self.desc.sample_rate, self.desc.channels));
+        *self
+            .output_eq
+            .lock()
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to lock output EQ: {}", e)))? =
+            filter;
+
+        self.desc.output_eq = eq;
+        Ok(())
+    }
+
+    /// Get the calibration EQ of the master output, if set
+    pub fn output_eq(&self) -> Option<&OutputEq> {
+        self.desc.output_eq.as_ref()
+    }
+
+    /// Switch the Steam Audio simulation quality at runtime
+    ///
+    /// Takes effect on the next rendered block. If the new quality needs more occlusion
+    /// samples than the current simulator supports, the spatial processor is recreated
+    /// (per-source effects are rebuilt lazily, global bypass toggles are kept).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if spatial audio is not available or the processor cannot be
+    /// recreated.
+    pub fn set_spatial_quality(&mut self, quality: SpatialQuality) -> Result<()> {
+        let processor = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+
+        let settings = quality.settings();
+        let applied = processor
+            .lock()
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+            })?
+            .set_quality(settings)
+            .is_ok();
+
+        if !applied {
+            log::info!(
+                "Recreating spatial processor for {} occlusion samples",
+                settings.num_occlusion_samples
+            );
+            // Built without holding the lock, so the render thread keeps rendering meanwhile
+            let new_processor = self.build_spatial_processor(&self.desc.hrtf_config(), settings)?;
+            Self::swap_spatial_processor(processor, new_processor)?;
+        }
+
+        self.desc.spatial_quality = quality;
+        Ok(())
+    }
+
+    /// Create a spatial processor with the engine's settings, `hrtf` and `settings`
+    fn build_spatial_processor(
+        &self,
+        hrtf: &HrtfConfig,
+        settings: SimulationQuality,
+    ) -> Result<SpatialProcessor> {
+        let mut processor = SpatialProcessor::new(
+            self.desc.sample_rate,
+            self.desc.block_size,
+            DISTANCE_SCALER,
+            hrtf,
+            settings,
+            self.desc.simulation_rate_hz,
+            self.desc.max_spatial_sources,
+        )?;
+        processor.set_budget(self.desc.spatial_budget_duration());
+        processor.set_calibration(self.desc.listener_calibration);
+        processor.set_output_mode(self.desc.output_mode);
+        processor.set_lod(self.desc.spatial_lod);
+        processor.set_clustering(self.desc.source_clustering);
+        Ok(processor)
+    }
+
+    /// Replace the processor behind `shared`, keeping its global bypass toggles
+    ///
+    /// Swapped in place so the running render thread picks up the new processor; the lock
+    /// is only held for the swap.
+    fn swap_spatial_processor(
+        shared: &Mutex<SpatialProcessor>,
+        mut processor: SpatialProcessor,
+    ) -> Result<()> {
+        let mut current = shared.lock().map_err(|e| {
+            PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+        })?;
+        processor.set_bypass(current.bypass());
+        *current = processor;
+        Ok(())
+    }
+
+    /// Replace the HRTF used for binaural rendering, e.g. with a user's personalized SOFA
+    /// file
+    ///
+    /// The spatial processor is recreated with the new HRTF (per-source effects are rebuilt
+    /// lazily, global bypass toggles and the listener calibration are kept). Takes effect
+    /// on the next rendered block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if spatial audio is not available or the HRTF cannot be found (the
+    /// message lists the paths tried, see [`crate::assets`]) or loaded; the current HRTF is
+    /// kept in that case.
+    pub fn set_hrtf(&mut self, hrtf: HrtfConfig) -> Result<()> {
+        let hrtf = Self::resolve_hrtf(hrtf).map_err(|lookup| {
+            PetalSonicError::Configuration(format!(
+                "HRTF file not found (tried {})",
+                Self::format_attempted(&lookup)
+            ))
+        })?;
+        let processor = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+
+        // Loading the HRTF takes a while; the render thread keeps the current one meanwhile
+        let new_processor =
+            self.build_spatial_processor(&hrtf, self.desc.spatial_quality.settings())?;
+        Self::swap_spatial_processor(processor, new_processor)?;
+
+        log::info!(
+            "Loaded HRTF {}",
+            hrtf.sofa_path.as_deref().unwrap_or("(default)")
+        );
+        self.desc.hrtf_path = hrtf.sofa_path;
+        self.desc.hrtf_volume_db = hrtf.volume_db;
+        self.desc.hrtf_normalization = hrtf.normalization;
+        Ok(())
+    }
+
+    /// Adjust the listener's head radius and interaural width at runtime
+    ///
+    /// Applies to the spatial processor and to the stereo panner used without one. Values
+    /// outside the supported ranges are clamped (see [`ListenerCalibration::clamped`]).
+    /// Takes effect on the next rendered block.
+    pub fn set_listener_calibration(&mut self, calibration: ListenerCalibration) {
+        let calibration = calibration.clamped();
+        self.desc.listener_calibration = calibration;
+
+        if let Some(processor) = &self.spatial_processor
+            && let Ok(mut processor) = processor.lock()
+        {
+            processor.set_calibration(calibration);
+        }
+        if let Ok(mut shared) = self.listener_calibration.lock() {
+            *shared = calibration;
+        }
+    }
+
+    /// Get the current listener calibration
+    pub fn listener_calibration(&self) -> ListenerCalibration {
+        self.desc.listener_calibration
+    }
+
+    /// Switch between binaural rendering for headphones and stereo panning for speakers
+    ///
+    /// Applies to the spatial processor and to the stereo panner used without one, without
+    /// restarting the engine. Takes effect on the next rendered block.
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        self.desc.output_mode = output_mode;
+
+        if let Some(processor) = &self.spatial_processor
+            && let Ok(mut processor) = processor.lock()
+        {
+            processor.set_output_mode(output_mode);
+        }
+        if let Ok(mut shared) = self.output_mode.lock() {
+            *shared = output_mode;
+        }
+    }
+
+    /// Get the current output mode
+    pub fn output_mode(&self) -> OutputMode {
+        self.desc.output_mode
+    }
+
+    /// Bypass spatial pipeline stages for all sources
+    ///
+    /// Combined with per-source toggles set via `PetalSonicWorld::set_spatial_bypass`.
+    /// Takes effect on the next rendered block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if spatial audio is not available.
+    pub fn set_spatial_bypass(&self, bypass: SpatialBypass) -> Result<()> {
+        let processor = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+
+        processor
+            .lock()
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+            })?
+            .set_bypass(bypass);
+
+        Ok(())
+    }
+
+    /// Get the spatial pipeline stages bypassed for all sources
+    pub fn spatial_bypass(&self) -> SpatialBypass {
+        self.spatial_processor
+            .as_ref()
+            .and_then(|processor| processor.lock().ok().map(|p| p.bypass()))
+            .unwrap_or_default()
+    }
+
+    /// Render distant and quiet spatial sources at a reduced level of detail (see
+    /// [`SpatialLod`]); `None` renders every source at full detail
+    ///
+    /// Sources changing level are crossfaded over one block. Takes effect on the next
+    /// rendered block and is kept when the spatial processor is recreated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::InvalidConfig` if the parameters cannot be used, or an
+    /// error if spatial audio is not available.
+    pub fn set_spatial_lod(&mut self, lod: Option<SpatialLod>) -> Result<()> {
+        if let Some(lod) = &lod {
+            lod.validate().map_err(ConfigError::SpatialLod)?;
+        }
+        let processor = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+
+        processor
+            .lock()
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+            })?
+            .set_lod(lod);
+        self.desc.spatial_lod = lod;
+        Ok(())
+    }
+
+    /// Get the level of detail settings of spatial sources
+    pub fn spatial_lod(&self) -> Option<SpatialLod> {
+        self.desc.spatial_lod
+    }
+
+    /// Mix distant spatial sources in similar directions into shared emitters (see
+    /// [`SourceClustering`]); `None` encodes every source on its own
+    ///
+    /// Sources joining or leaving a cluster are crossfaded over one block. Takes effect on
+    /// the next rendered block and is kept when the spatial processor is recreated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::InvalidConfig` if the parameters cannot be used, or an
+    /// error if spatial audio is not available.
+    pub fn set_source_clustering(&mut self, clustering: Option<SourceClustering>) -> Result<()> {
+        if let Some(clustering) = &clustering {
+            clustering
+                .validate()
+                .map_err(ConfigError::SourceClustering)?;
+        }
+        let processor = self.spatial_processor.as_ref().ok_or_else(|| {
+            PetalSonicError::SpatialAudio("Spatial processor not available".into())
+        })?;
+
+        processor
+            .lock()
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to lock spatial processor: {}", e))
+            })?
+            .set_clustering(clustering);
+        self.desc.source_clustering = clustering;
+        Ok(())
+    }
+
+    /// Get the clustering settings of distant spatial sources
+    pub fn source_clustering(&self) -> Option<SourceClustering> {
+        self.desc.source_clustering
+    }
+
+    /// Usage of the spatial processor's source capacity, or `None` if spatial audio is not
+    /// available
+    pub fn spatial_source_stats(&self) -> Option<SpatialSourceStats> {
+        self.spatial_processor
+            .as_ref()
+            .and_then(|processor| processor.lock().ok().map(|p| p.source_stats()))
+    }
+
+    /// Render spatial sources with a custom [`Spatializer`] backend instead of Steam Audio
+    ///
+    /// The backend is used from the next rendered block until it is replaced or removed;
+    /// pass `None` to go back to the built-in spatial processor (or the stereo panner if
+    /// spatialization is disabled). Spatial quality and bypass settings only apply to the
+    /// built-in processor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be handed to the render thread.
+    pub fn set_spatializer(&self, spatializer: Option<Box<dyn Spatializer>>) -> Result<()> {
+        let mut custom = self.custom_spatializer.lock().map_err(|e| {
+            PetalSonicError::SpatialAudio(format!("Failed to lock spatializer: {}", e))
+        })?;
+        log::info!(
+            "{} custom spatializer",
+            if spatializer.is_some() {
+                "Installing"
+            } else {
+                "Removing"
+            }
+        );
+        *custom = spatializer;
+        Ok(())
+    }
+
+    /// Returns true if a custom spatializer installed via [`Self::set_spatializer`] is active
+    pub fn has_custom_spatializer(&self) -> bool {
+        self.custom_spatializer
+            .lock()
+            .map(|custom| custom.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Play a sine test tone on a single output channel
+    ///
+    /// The tone is mixed on top of the world output at -12 dBFS with short fades, so it can
+    /// be used to check speaker wiring and channel order while sources are playing. It is
+    /// added to the device frames after the world is mixed to the device's channels, so
+    /// every channel of the opened stream can be tested whatever the world's channel count.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - Zero-based channel index of the output device
+    /// * `frequency` - Tone frequency in Hz
+    /// * `duration` - How long the tone plays
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is not running, the channel does not exist or the
+    /// frequency is not between 0 Hz and the Nyquist frequency.
+    pub fn play_test_tone(&self, channel: u16, frequency: f32, duration: Duration) -> Result<()> {
+        if !self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot play test tone, engine is not running".into(),
+            ));
+        }
+
+        if channel >= self.device_channels {
+            return Err(PetalSonicError::Configuration(format!(
+                "Test tone channel {} out of range (device has {} channels)",
+                channel, self.device_channels
+            )));
+        }
+
+        let nyquist = self.device_sample_rate as f32 / 2.0;
+        if !(frequency > 0.0 && frequency < nyquist) {
+            return Err(PetalSonicError::Configuration(format!(
+                "Test tone frequency {} Hz must be between 0 and {} Hz",
+                frequency, nyquist
+            )));
+        }
+
+        log::info!(
+            "Playing {} Hz test tone on channel {} for {:?}",
+            frequency,
+            channel,
+            duration
+        );
+
+        self.device_tone_sender
+            .send(TestTone::new(
+                channel,
+                frequency,
+                duration,
+                self.device_sample_rate,
+            ))
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to send test tone: {}", e)))
+    }
+
+    /// Create a sampler that plays note-triggered clips polyphonically
+    ///
+    /// The sampler keeps at most `max_voices` voices, stealing the oldest one when a new
+    /// note starts while all are in use. It persists across `stop()`/`start()` and is
+    /// removed once the returned handle is dropped and its voices have finished. Its pitch
+    /// variation draws from a generator forked from the world's (see [`crate::random`]).
+    /// See [`crate::sampler`] for details.
+    pub fn create_sampler(&self, max_voices: usize) -> Sampler {
+        let (sampler, voices) =
+            SamplerVoices::new(self.desc.sample_rate, max_voices, self.world.fork_rng());
+        self.samplers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(voices);
+        sampler
+    }
+
+    /// Stream the master mix to `sink`, encoded with `encoder`
+    ///
+    /// The render thread copies each rendered block (world sample rate and channels) into a
+    /// lock-free FIFO; encoding and delivery run on a separate thread. Replaces any running
+    /// tap. See [`crate::tap`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tap thread cannot be spawned.
+    pub fn start_output_tap<E, S>(&mut self, encoder: E, sink: S) -> Result<()>
+    where
+        E: TapEncoder,
+        S: TapSink,
+    {
+        self.stop_output_tap();
+
+        let (tap, producer) =
+            OutputTap::spawn(encoder, sink, self.desc.sample_rate, self.desc.channels)?;
+        *self
+            .tap_producer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(producer);
+        self.output_tap = Some(tap);
+
+        log::info!("Output tap started");
+        Ok(())
+    }
+
+    /// Stop the output tap, if running
+    pub fn stop_output_tap(&mut self) {
+        self.tap_producer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if self.output_tap.take().is_some() {
+            log::info!("Output tap stopped");
+        }
+    }
+
+    /// Counters of the running output tap, or `None` if no tap is running
+    pub fn output_tap_stats(&self) -> Option<TapStats> {
+        self.output_tap.as_ref().map(OutputTap::stats)
+    }
+
+    /// Record the selected sources and buses and the master mix to WAV files in `dir`
+    ///
+    /// Writes `master.wav`, one `source_<n>.wav` per source and one `bus_<name>.wav` per
+    /// bus, recorded from the next rendered block on, in parallel with playback. Sources
+    /// and buses that are not playing are recorded as silence so all stems stay aligned.
+    /// Replaces (and finalizes) any running recording. See [`crate::stems`] for what a stem
+    /// contains.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a file cannot be created, or if the writer
+    /// thread cannot be spawned.
+    pub fn record_stems(&mut self, targets: &[StemTarget], dir: impl AsRef<Path>) -> Result<()> {
+        self.stop_stem_recording()?;
+
+        let (writer, recorder) = StemWriter::spawn(
+            targets,
+            dir.as_ref(),
+            self.desc.sample_rate,
+            self.desc.channels,
+            self.desc.max_sources,
+        )?;
+        *self
+            .stem_recorder
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(recorder);
+        self.stem_writer = Some(writer);
+
+        log::info!(
+            "Recording {} stems to {}",
+            targets.len(),
+            dir.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Stop recording stems and finalize the files, if recording
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing the remaining audio or finalizing a file failed.
+    pub fn stop_stem_recording(&mut self) -> Result<()> {
+        self.stem_recorder
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        match self.stem_writer.take() {
+            Some(writer) => {
+                writer.finish()?;
+                log::info!("Stem recording stopped");
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Counters of the running stem recording, or `None` if not recording
+    pub fn stem_stats(&self) -> Option<StemStats> {
+        self.stem_writer.as_ref().map(StemWriter::stats)
+    }
+
+    /// Names of the available output devices, for picking a secondary output
+    pub fn output_device_names() -> Result<Vec<String>> {
+        let host = cpal::default_host();
+        let devices = host.output_devices().map_err(|e| {
+            PetalSonicError::AudioDevice(format!("Failed to list output devices: {}", e))
+        })?;
+        Ok(devices.filter_map(|device| device.name().ok()).collect())
+    }
+
+    /// Open a second output stream on another device, playing the sources on
+    /// `desc.buses` there instead of on the main output
+    ///
+    /// Replaces any open secondary output. The stream stays open until
+    /// [`Self::close_secondary_output`] or the engine is dropped, and is silent while the
+    /// engine is stopped. See [`crate::secondary_output`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device is not found or the stream fails to open.
+    pub fn open_secondary_output(&mut self, desc: SecondaryOutputDesc) -> Result<()> {
+        self.close_secondary_output();
+
+        let device = SecondaryOutput::find_device(&desc.device_name)?;
+        let device_config = device.default_output_config().map_err(|e| {
+            PetalSonicError::AudioDevice(format!("Failed to get default config: {}", e))
+        })?;
+        let device_channels =
+            Self::select_device_channels(&device, &device_config, self.desc.channels);
+
+        let (output, mix) = SecondaryOutput::open(
+            desc,
+            &device,
+            &device_config,
+            SecondaryOutputParams {
+                world_sample_rate: self.desc.sample_rate,
+                channels: self.desc.channels,
+                device_channels,
+                block_size: self.desc.block_size,
+                resample_quality: self.desc.output_resample_quality,
+                dither: self.desc.dither,
+                is_running: self.is_running.clone(),
+                event_sender: self.event_sender.clone(),
+            },
+        )?;
+        *self
+            .secondary_mix
+            .lock()
+            .map_err(|_| PetalSonicError::Engine("Secondary output lock poisoned".into()))? =
+            Some(mix);
+        self.secondary_output = Some(output);
+        Ok(())
+    }
+
+    /// Close the secondary output, if open; its buses play on the main output again
+    pub fn close_secondary_output(&mut self) {
+        if let Ok(mut mix) = self.secondary_mix.lock() {
+            *mix = None;
+        }
+        if let Some(output) = self.secondary_output.take() {
+            log::info!("Closed secondary output on '{}'", output.device_name());
+        }
+    }
+
+    /// Get the configuration of the secondary output, or `None` if none is open
+    pub fn secondary_output_info(&self) -> Option<OutputInfo> {
+        self.secondary_output.as_ref().map(|output| OutputInfo {
+            device_name: Some(output.device_name().to_string()),
+            sample_rate: output.sample_rate(),
+            channels: output.channels(),
+            buffer_size: None,
+            sample_format: output.sample_format().to_string(),
+            latency: self.processing_latency(),
+        })
+    }
+
+    /// Counters of the secondary output, or `None` if none is open
+    pub fn secondary_output_stats(&self) -> Option<SecondaryOutputStats> {
+        self.secondary_output.as_ref().map(SecondaryOutput::stats)
+    }
+
+    /// Collect the render state for a debug overlay (see [`crate::debug_snapshot`])
+    ///
+    /// Briefly blocks the render thread; call it at UI rate.
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        let convention = self.desc.coordinate_convention;
+        let processor = self
+            .spatial_processor
+            .as_ref()
+            .and_then(|processor| processor.lock().ok());
+        let listener_pose = self.world.native_listener_pose();
+
+        let mut sources = Vec::new();
+        let mut bus_levels: HashMap<String, f32> = HashMap::new();
+        if let Ok(active_playback) = self.active_playback.lock() {
+            for (source_id, instance) in active_playback.iter() {
+                let position = match instance.config {
+                    SourceConfig::Spatial { position, .. } => Some(position),
+                    SourceConfig::NonSpatial => None,
+                };
+                let spatialized = position.is_some() && !instance.output_routing.is_routed();
+                let bypass = processor
+                    .as_ref()
+                    .map_or(instance.spatial_bypass, |processor| {
+                        processor.bypass().union(instance.spatial_bypass)
+                    });
+                let (distance_attenuation, occlusion) = match (&processor, position) {
+                    (Some(processor), Some(_)) if spatialized => processor
+                        .source_gains(*source_id)
+                        .map_or((None, None), |(attenuation, occlusion)| {
+                            (Some(attenuation), Some(occlusion))
+                        }),
+                    // The stereo panner's inverse-distance model
+                    (None, Some(position)) if spatialized => (
+                        Some(spatial::distance_attenuation(
+                            position.distance(listener_pose.position),
+                        )),
+                        None,
+                    ),
+                    _ => (None, None),
+                };
+                let distance_attenuation = distance_attenuation.map(|gain| {
+                    if bypass.distance_attenuation {
+                        1.0
+                    } else {
+                        gain
+                    }
+                });
+
+                if let Some(bus) = &instance.bus {
+                    let level = bus_levels.entry(bus.clone()).or_default();
+                    *level = level.max(instance.level);
+                }
+                sources.push(SourceDebugInfo {
+                    source_id: *source_id,
+                    position: position.map(|position| convention.vec_from_native(position)),
+                    playback: instance.info.clone(),
+                    volume: instance.config.volume().unwrap_or(1.0),
+                    distance_attenuation,
+                    occlusion,
+                    level: instance.level,
+                    bus: instance.bus.clone(),
+                });
+            }
+        }
+
+        DebugSnapshot {
+            listener: convention.pose_from_native(listener_pose),
+            sources,
+            output_peaks: self.output_levels.peaks(),
+            bus_levels,
+        }
+    }
+
+    /// Get a snapshot of the output configuration and device callback statistics
+    ///
+    /// Callback statistics are measured since the last `start()`.
+    pub fn diagnostics(&self) -> DiagnosticsReport {
+        self.callback_stats.report(DiagnosticsReport {
+            device_name: self.device_name.clone(),
+            sample_format: self.sample_format.map(|format| format.to_string()),
+            device_sample_rate: self.device_sample_rate,
+            world_sample_rate: self.desc.sample_rate,
+            channels: self.desc.channels,
+            device_channels: self.device_channels,
+            block_size: self.desc.block_size,
+            spatial_enabled: self.spatial_processor.is_some(),
+            processing_latency: self.processing_latency(),
+            frames_processed: self.frames_processed(),
+            ..Default::default()
+        })
+    }
+
+    /// Get the running counters of the engine, including the sample-rate conversion to the
+    /// device and its measured clock rate (see [`crate::diagnostics`])
+    ///
+    /// A `device_clock_drift_ppm` far from zero, or a growing `conversion_drift_frames`,
+    /// points at a device whose clock does not match its reported sample rate. The render
+    /// load (see [`crate::diagnostics::RenderLoadStats`]) tells how much of the real-time
+    /// budget the render thread uses.
+    pub fn stats(&self) -> EngineStats {
+        EngineStats {
+            frames_processed: self.frames_processed(),
+            resampling: self
+                .resample_counters
+                .stats(self.device_sample_rate, self.callback_stats.measured_rate()),
+            render_load: self.render_load.stats(),
+        }
+    }
+
+    /// Get the configuration negotiated with the output device, or `None` if the engine is
+    /// not running
+    pub fn output_info(&self) -> Option<OutputInfo> {
+        if !self.is_running() {
+            return None;
+        }
+
+        let buffer_size = self
+            .device_buffer_size
+            .or_else(|| self.callback_stats.max_frames());
+        let buffer_latency = buffer_size.map_or(Duration::ZERO, |frames| {
+            Duration::from_secs_f64(frames as f64 / self.device_sample_rate as f64)
+        });
+        Some(OutputInfo {
+            device_name: self.device_name.clone(),
+            sample_rate: self.device_sample_rate,
+            channels: self.device_channels,
+            buffer_size,
+            sample_format: self
+                .sample_format
+                .map_or_else(String::new, |format| format.to_string()),
+            latency: self.processing_latency() + buffer_latency,
+        })
+    }
+
+    /// Run an output check and return a diagnostics report
+    ///
+    /// Plays a 440 Hz test tone on each channel in turn for `tone_duration`, blocking the
+    /// calling thread, and verifies that the device consumed audio meanwhile and that each
+    /// channel carried signal while its tone played (channels without signal are listed in
+    /// `silent_channels`). The levels are measured on the device output, where the tones are
+    /// added after the channel mix. Callback statistics in the report cover only this run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is not running.
+    pub fn run_diagnostics(&self, tone_duration: Duration) -> Result<DiagnosticsReport> {
+        if !self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot run diagnostics, engine is not running".into(),
+            ));
+        }
+
+        self.callback_stats.reset();
+        let frames_before = self.frames_processed();
+
+        let mut silent_channels = Vec::new();
+        for channel in 0..self.device_channels {
+            self.device_levels.take_held_rms();
+            self.play_test_tone(channel, 440.0, tone_duration)?;
+            thread::sleep(tone_duration);
+            if self.device_levels.take_held_rms()[channel as usize] < SILENT_CHANNEL_RMS {
+                silent_channels.push(channel);
+            }
+        }
+
+        let mut report = self.diagnostics();
+        report.output_verified =
+            Some(report.frames_processed > frames_before && silent_channels.is_empty());
+        report.silent_channels = silent_channels;
+        log::info!("{}", report);
+
+        Ok(report)
+    }
+
+    /// Measure the output latency through an input device hearing the output (see
+    /// [`crate::latency`])
+    ///
+    /// Plays `config.repetitions` chirps on all channels on top of the world, blocking the
+    /// calling thread for about `config.interval` per chirp. The result does not change
+    /// the engine; pass [`LatencyReport::latency`] to [`Self::set_clock_offset`] to apply
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine is not running, the settings are invalid, the input
+    /// device cannot be opened or no chirp was found in the captured input.
+    pub fn measure_latency(&self, config: &LatencyTestConfig) -> Result<LatencyReport> {
+        if !self.is_running() {
+            return Err(PetalSonicError::Engine(
+                "Cannot measure latency, engine is not running".into(),
+            ));
+        }
+
+        latency::measure(
+            LatencyProbe {
+                tone_sender: self.test_tone_sender.clone(),
+                frames_processed: self.frames_processed.clone(),
+                world_sample_rate: self.desc.sample_rate,
+                device_sample_rate: self.device_sample_rate,
+            },
+            config,
+        )
+    }
+
+    /// Set the time between a frame being counted in [`Self::frames_processed`] and the
+    /// listener hearing it, e.g. from [`Self::measure_latency`]
+    pub fn set_clock_offset(&mut self, offset: Duration) {
+        self.clock_offset = offset;
+    }
+
+    /// Returns the offset set via [`Self::set_clock_offset`]
+    pub fn clock_offset(&self) -> Duration {
+        self.clock_offset
+    }
+
+    /// Output frame the listener hears now: [`Self::frames_processed`] minus the clock
+    /// offset, on the same clock as the `output_frame` of events
+    pub fn audible_frame(&self) -> usize {
+        let offset_frames =
+            (self.clock_offset.as_secs_f64() * self.device_sample_rate as f64).round() as usize;
+        self.frames_processed().saturating_sub(offset_frames)
+    }
+
+    /// Render thread loop that continuously fills the ring buffer
+    fn render_thread_loop(mut ctx: RenderThreadContext) {
+        log::info!("Render thread started");
+
+        let mut stats_logger = StatsLogger::new();
+
+        while !ctx.shutdown.load(Ordering::Relaxed) {
+            if let Some(change) = ctx
+                .block_size_change
+                .try_lock()
+                .ok()
+                .and_then(|mut change| change.take())
+            {
+                Self::apply_block_size_change(&mut ctx, change);
+            }
+
+            // Device is gone: keep state untouched and wait for resume()/stop()
+            if ctx.device_lost.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+
+            if ctx.drain.requested.load(Ordering::Acquire) {
+                // Drained: stop producing and let the ring buffer play out
+                if ctx.drain.finished.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(1));
+                    continue;
+                }
+                if !ctx.drain_started {
+                    ctx.drain_started = Self::begin_drain(&ctx.active_playback);
+                }
+            }
+
+            // Check ring buffer occupancy in frames (lock-free!)
+            let channels = ctx.channels as usize;
+            let occupied = ctx.ring_buffer_producer.occupied_len() / channels;
+            let should_generate = occupied < ctx.block_size * TARGET_FILL_BLOCKS;
+
+            if should_generate {
+                // Generate samples to fill the buffer (lock-free!)
+                let free_space = ctx.ring_buffer_producer.vacant_len() / channels;
+
+                if free_space > 0 {
+                    let samples_to_generate = free_space.min(ctx.block_size * 2);
+                    let _section = RtSection::enter(RtPath::Render);
+                    Self::render_batch(&mut ctx, samples_to_generate);
+                }
+            }
+
+            stats_logger.tick();
+
+            // Small sleep to avoid busy-waiting
+            thread::sleep(Duration::from_micros(500));
+        }
+
+        log::info!("Render thread stopped");
+    }
+
+    /// Render at least `samples_to_generate` frames into the ring buffer and emit the
+    /// events of the rendered blocks
+    fn render_batch(ctx: &mut RenderThreadContext, samples_to_generate: usize) {
+        // Process playback commands (stop/pause/play)
+        Self::process_playback_commands(&ctx.world, &ctx.active_playback, &mut ctx.stopped_sources);
+
+        // Pick up newly requested test tones, within the capacity reserved for them
+        while let Ok(tone) = ctx.test_tone_receiver.try_recv() {
+            if ctx.test_tones.len() < MAX_TEST_TONES {
+                ctx.test_tones.push(tone);
+            } else {
+                rt_warn!(
+                    "Engine: {} test tones playing, dropping tone",
+                    MAX_TEST_TONES
+                );
+            }
+        }
+
+        let listener_pose = ctx.world.native_listener_pose();
+        Self::release_stopped_sources(ctx, listener_pose);
+
+        // Keep the previous zone parameters while zones are being modified
+        if let Some(zones) = ctx.world.try_zones() {
+            ctx.zone_evaluator.update(zones.values(), &listener_pose);
+        }
+        if let Ok(calibration) = ctx.listener_calibration.try_lock() {
+            ctx.stereo_panner.set_calibration(*calibration);
+        }
+        if let Ok(output_mode) = ctx.output_mode.try_lock() {
+            ctx.stereo_panner.set_output_mode(*output_mode);
+        }
+        if let Some((settings, transition)) = ctx.world.take_pending_reverb() {
+            Self::apply_environment(ctx, settings, transition);
+        }
+        if ctx.focus_fade.needs_playback_update(&ctx.focus)
+            && let Ok(mut active_playback) = ctx.active_playback.try_lock()
+        {
+            ctx.focus_fade
+                .update_playback(&ctx.focus, &mut active_playback);
+        }
+        let (completed_sources, looped_sources, voice_activity, timing) = Self::generate_samples(
+            &mut ctx.ring_buffer_producer,
+            samples_to_generate,
+            ctx.channels as usize,
+            ctx.channels,
+            (&ctx.resampler, &ctx.resample_counters),
+            &ctx.active_playback,
+            ctx.block_size,
+            Spatializers {
+                listener_pose,
+                custom: &ctx.custom_spatializer,
+                processor: ctx.spatial_processor.as_deref(),
+                panner: &mut ctx.stereo_panner,
+            },
+            &mut ctx.test_tones,
+            &ctx.samplers,
+            &ctx.tap_producer,
+            &ctx.zone_evaluator,
+            &ctx.stem_recorder,
+            &ctx.secondary_mix,
+            (ctx.master_volume.as_ref(), &mut ctx.master),
+            (ctx.focus.as_ref(), &mut ctx.focus_fade),
+            (ctx.accessibility.as_ref(), &mut ctx.accessibility_mix),
+            ctx.reverb.as_mut(),
+            &ctx.output_eq,
+            &ctx.output_levels,
+            ctx.solo_bus.as_deref(),
+            &ctx.event_sender,
+            &ctx.frames_processed,
+            &mut ctx.render_load,
+            ctx.profiling.load(Ordering::Relaxed),
+            ctx.drain_started.then_some((
+                ctx.drain.fade_frames.load(Ordering::Relaxed),
+                &mut ctx.drain_fade_position,
+            )),
+            ctx.loudness
+                .is_enabled()
+                .then_some((ctx.loudness.as_ref(), &mut ctx.loudness_meter)),
+        );
+
+        Self::publish_spatial_info(ctx, &listener_pose);
+        Self::publish_playing_sources(ctx);
+
+        if ctx.drain_started && Self::drain_complete(ctx) {
+            log::info!("Render thread drained");
+            ctx.drain.finished.store(true, Ordering::Release);
+        }
+
+        // Send timing event (non-blocking)
+        if let Some(timing) = timing
+            && let Err(e) = ctx.timing_sender.send(timing)
+        {
+            logging::count_render_error();
+            rt_error!("Failed to send timing event: {}", e);
+        }
+
+        // Emit SourceCompleted events for sources that finished (LoopMode::Once)
+        // This is lock-free and non-blocking since we use an unbounded channel
+        for source_id in completed_sources {
+            if let Err(e) = ctx
+                .event_sender
+                .send(PetalSonicEvent::SourceCompleted { source_id })
+            {
+                logging::count_render_error();
+                rt_error!("Failed to send SourceCompleted event: {}", e);
+            } else {
+                rt_info!(
+                    "RenderThread: Emitted SourceCompleted event for source {}",
+                    source_id
+                );
+            }
+        }
+
+        // Emit SourceLooped events for sources that looped (LoopMode::Infinite)
+        for source_id in looped_sources {
+            if let Err(e) = ctx.event_sender.send(PetalSonicEvent::SourceLooped {
+                source_id,
+                loop_count: 0, // Could track actual loop count if needed
+            }) {
+                logging::count_render_error();
+                rt_error!("Failed to send SourceLooped event: {}", e);
+            } else {
+                rt_info!(
+                    "RenderThread: Emitted SourceLooped event for source {}",
+                    source_id
+                );
+            }
+        }
+
+        // Emit talking state changes of live voice sources
+        for (source_id, talking) in voice_activity {
+            let event = if talking {
+                PetalSonicEvent::VoiceActivityStarted { source_id }
+            } else {
+                PetalSonicEvent::VoiceActivityStopped { source_id }
+            };
+            if let Err(e) = ctx.event_sender.send(event) {
+                logging::count_render_error();
+                rt_error!("Failed to send voice activity event: {}", e);
+            }
+        }
+    }
+
+    /// Free the spatializer state of the spatial sources stopped by commands, like the mixer
+    /// does for finished ones. Sources stay pending while the spatializer is contended.
+    fn release_stopped_sources(ctx: &mut RenderThreadContext, listener_pose: Pose) {
+        if ctx.stopped_sources.is_empty() {
+            return;
+        }
+        let mut custom_guard = ctx.custom_spatializer.try_lock().ok();
+        let mut processor_guard = ctx
+            .spatial_processor
+            .as_deref()
+            .and_then(|processor| processor.try_lock().ok());
+        let mut spatializers = Spatializers {
+            listener_pose,
+            custom: &ctx.custom_spatializer,
+            processor: ctx.spatial_processor.as_deref(),
+            panner: &mut ctx.stereo_panner,
+        };
+        let Some(spatializer) =
+            spatializers.select(custom_guard.as_deref_mut(), processor_guard.as_deref_mut())
+        else {
+            logging::count_lock_contention();
+            return;
+        };
+        for source_id in ctx.stopped_sources.drain(..) {
+            spatializer.release_source(source_id);
         }
-        events
     }
 
-    /// Render thread loop that continuously fills the ring buffer
-    fn render_thread_loop(mut ctx: RenderThreadContext) {
-        log::info!("Render thread started");
-
-        let target_buffer_fill = ctx.block_size * 4;
+    /// Fade the reverb bus to new settings, creating it silent first if the world has none
+    fn apply_environment(
+        ctx: &mut RenderThreadContext,
+        settings: ReverbSettings,
+        transition: Duration,
+    ) {
+        let sample_rate = ctx.world.sample_rate();
+        let reverb = ctx.reverb.get_or_insert_with(|| {
+            let silent = ReverbSettings {
+                wet_gain: 0.0,
+                ..settings
+            };
+            Reverb::new(silent, sample_rate, ctx.block_size)
+        });
+        let frames = (transition.as_secs_f64() * sample_rate as f64) as usize;
+        reverb.transition_to(settings, frames);
+    }
 
-        while !ctx.shutdown.load(Ordering::Relaxed) {
-            // Update listener pose in spatial processor if available
-            if let Some(ref spatial_processor) = ctx.spatial_processor
-                && let Ok(mut processor) = spatial_processor.try_lock()
-            {
-                let listener_pose = ctx.world.listener().pose();
-                if let Err(e) = processor.set_listener_pose(listener_pose) {
-                    log::error!("Failed to update listener pose: {}", e);
-                }
+    /// Publish the propagation of each playing spatial source to the world (see
+    /// [`PetalSonicWorld::spatial_info`])
+    fn publish_spatial_info(ctx: &RenderThreadContext, listener_pose: &Pose) {
+        let (Some(mut infos), Ok(active_playback)) =
+            (ctx.world.try_spatial_info(), ctx.active_playback.try_lock())
+        else {
+            logging::count_lock_contention();
+            return;
+        };
+        let processor = ctx
+            .spatial_processor
+            .as_ref()
+            .and_then(|processor| processor.try_lock().ok());
+
+        infos.retain(|source_id, _| active_playback.contains_key(source_id));
+        for (source_id, instance) in active_playback.iter() {
+            let SourceConfig::Spatial { position, .. } = instance.config else {
+                infos.remove(source_id);
+                continue;
+            };
+            if instance.output_routing.is_routed() {
+                infos.remove(source_id);
+                continue;
             }
 
-            // Check ring buffer occupancy (lock-free!)
-            let occupied = ctx.ring_buffer_producer.occupied_len();
-            let should_generate = occupied < target_buffer_fill;
-
-            if should_generate {
-                // Generate samples to fill the buffer (lock-free!)
-                let free_space = ctx.ring_buffer_producer.vacant_len();
-
-                if free_space > 0 {
-                    let samples_to_generate = free_space.min(ctx.block_size * 2);
-                    let (completed_sources, looped_sources, timing) = Self::generate_samples(
-                        &mut ctx.ring_buffer_producer,
-                        samples_to_generate,
-                        ctx.channels as usize,
-                        ctx.channels,
-                        &ctx.resampler,
-                        &ctx.active_playback,
-                        ctx.block_size,
-                        ctx.spatial_processor.as_ref(),
-                    );
-
-                    // Send timing event (non-blocking)
-                    if let Err(e) = ctx.timing_sender.send(timing) {
-                        log::error!("Failed to send timing event: {}", e);
-                    }
+            let offset = position - listener_pose.position;
+            let distance = offset.length();
+            let local = listener_pose.rotation.inverse() * offset;
+            let simulated = processor.as_ref().and_then(|processor| {
+                let (attenuation, occlusion) = processor.source_gains(*source_id)?;
+                Some((
+                    attenuation,
+                    processor.source_air_absorption(*source_id)?,
+                    occlusion,
+                ))
+            });
+            let (distance_attenuation, air_absorption, occlusion) = simulated.unwrap_or((
+                spatial::distance_attenuation(distance),
+                spatial::air_absorption(distance),
+                1.0,
+            ));
+            infos.insert(
+                *source_id,
+                SpatialInfo {
+                    distance,
+                    direction: offset.normalize_or_zero(),
+                    azimuth: local.x.atan2(-local.z),
+                    distance_attenuation,
+                    air_absorption,
+                    occlusion,
+                },
+            );
+        }
+    }
 
-                    // Emit SourceCompleted events for sources that finished (LoopMode::Once)
-                    // This is lock-free and non-blocking since we use an unbounded channel
-                    for source_id in completed_sources {
-                        if let Err(e) = ctx
-                            .event_sender
-                            .send(PetalSonicEvent::SourceCompleted { source_id })
-                        {
-                            log::error!("Failed to send SourceCompleted event: {}", e);
-                        } else {
-                            log::info!(
-                                "RenderThread: Emitted SourceCompleted event for source {}",
-                                source_id
-                            );
-                        }
-                    }
+    /// Publish the sources with a playback instance, which the world keeps in memory
+    fn publish_playing_sources(ctx: &RenderThreadContext) {
+        let (Some(mut playing), Ok(active_playback)) = (
+            ctx.world.try_playing_sources(),
+            ctx.active_playback.try_lock(),
+        ) else {
+            logging::count_lock_contention();
+            return;
+        };
+        playing.clear();
+        playing.extend(active_playback.keys().copied());
+    }
 
-                    // Emit SourceLooped events for sources that looped (LoopMode::Infinite)
-                    for source_id in looped_sources {
-                        if let Err(e) = ctx.event_sender.send(PetalSonicEvent::SourceLooped {
-                            source_id,
-                            loop_count: 0, // Could track actual loop count if needed
-                        }) {
-                            log::error!("Failed to send SourceLooped event: {}", e);
-                        } else {
-                            log::info!(
-                                "RenderThread: Emitted SourceLooped event for source {}",
-                                source_id
-                            );
-                        }
-                    }
-                }
+    /// Switch looping sources to play out their current iteration
+    ///
+    /// Returns false if the playback lock was busy; the caller retries on the next iteration.
+    fn begin_drain(
+        active_playback: &Arc<std::sync::Mutex<HashMap<SourceId, PlaybackInstance>>>,
+    ) -> bool {
+        let Ok(mut active_playback) = active_playback.try_lock() else {
+            return false;
+        };
+        for instance in active_playback.values_mut() {
+            if instance.live_source.is_none() {
+                instance.set_loop_mode(LoopMode::Once);
             }
+        }
+        true
+    }
 
-            // Small sleep to avoid busy-waiting
-            thread::sleep(Duration::from_micros(500));
+    /// Returns true once the drain fade is done or no finite source is playing
+    fn drain_complete(ctx: &RenderThreadContext) -> bool {
+        let fade_frames = ctx.drain.fade_frames.load(Ordering::Relaxed);
+        if fade_frames > 0 && ctx.drain_fade_position >= fade_frames {
+            return true;
         }
 
-        log::info!("Render thread stopped");
+        let Ok(active_playback) = ctx.active_playback.try_lock() else {
+            return false;
+        };
+        !active_playback.values().any(|instance| {
+            instance.live_source.is_none() && matches!(instance.info.play_state, PlayState::Playing)
+        })
+    }
+
+    /// Create the render state of a stream, sharing the engine's settings
+    fn create_render_context(
+        &self,
+        params: &StreamCreationParams,
+        producer: HeapProd<f32>,
+        resampler: Arc<Mutex<StreamingResampler>>,
+    ) -> RenderThreadContext {
+        RenderThreadContext {
+            shutdown: params.render_shutdown.clone(),
+            device_lost: params.device_lost.clone(),
+            active_playback: params.active_playback.clone(),
+            resampler,
+            resample_counters: self.resample_counters.clone(),
+            render_load: RenderLoadMeter::new(self.render_load.clone()),
+            ring_buffer_producer: producer,
+            channels: params.channels,
+            block_size: self.desc.block_size,
+            spatial_processor: self.spatial_processor.clone(),
+            custom_spatializer: self.custom_spatializer.clone(),
+            zone_evaluator: ZoneEvaluator::default(),
+            stereo_panner: StereoPanner::new(params.world_sample_rate),
+            listener_calibration: self.listener_calibration.clone(),
+            output_mode: self.output_mode.clone(),
+            world: params.world.clone(),
+            event_sender: params.event_sender.clone(),
+            frames_processed: params.frames_processed.clone(),
+            timing_sender: params.timing_sender.clone(),
+            profiling: self.profiling.clone(),
+            loudness_meter: LoudnessMeter::new(params.world_sample_rate, params.channels),
+            loudness: self.loudness.clone(),
+            output_levels: self.output_levels.clone(),
+            master_volume: self.master_volume.clone(),
+            master: MasterVolume::new(
+                params.world_sample_rate,
+                params.channels,
+                &self.master_volume,
+            ),
+            focus: self.focus.clone(),
+            focus_fade: FocusFade::new(&self.focus, self.desc.max_sources),
+            accessibility: self.accessibility.clone(),
+            accessibility_mix: AccessibilityMix::new(&self.accessibility),
+            reverb: self.desc.reverb.map(|settings| {
+                Reverb::new(settings, params.world_sample_rate, self.desc.block_size)
+            }),
+            test_tone_receiver: params.test_tone_receiver.clone(),
+            test_tones: Vec::with_capacity(MAX_TEST_TONES),
+            samplers: self.samplers.clone(),
+            tap_producer: self.tap_producer.clone(),
+            stem_recorder: self.stem_recorder.clone(),
+            secondary_mix: self.secondary_mix.clone(),
+            output_eq: self.output_eq.clone(),
+            drain: self.drain.clone(),
+            drain_started: false,
+            drain_fade_position: 0,
+            solo_bus: None,
+            block_size_change: self.block_size_change.clone(),
+            stopped_sources: Vec::with_capacity(self.desc.max_sources),
+        }
     }
 
     /// Create a typed audio stream
@@ -514,13 +2990,16 @@ impl PetalSonicEngine {
             params.device_sample_rate,
             params.channels,
             block_size,
+            self.desc.output_resample_quality,
         )?;
+        self.resample_counters
+            .set_ratio(params.device_sample_rate as f64 / params.world_sample_rate as f64);
 
         // TODO: the audio callback may need even more samples at a time, we should consider that too,
         // otherwise when that exceeds the ring buffer size, we will never be able to fill enough samples
         const RING_BUFFER_SIZE_MIN: usize = 100000;
         let ring_buffer_size = RING_BUFFER_SIZE_MIN.max(block_size * 8);
-        let ring_buffer = HeapRb::<StereoFrame>::new(ring_buffer_size);
+        let ring_buffer = HeapRb::<f32>::new(ring_buffer_size * params.channels as usize);
 
         log::info!("Created ring buffer with size: {} frames", ring_buffer_size);
 
@@ -529,18 +3008,7 @@ impl PetalSonicEngine {
         let (producer, consumer) = ring_buffer.split();
 
         // Create context for render thread
-        let render_ctx = RenderThreadContext {
-            shutdown: params.render_shutdown,
-            active_playback: params.active_playback.clone(),
-            resampler: resampler.clone(),
-            ring_buffer_producer: producer,
-            channels: params.channels,
-            block_size,
-            spatial_processor: self.spatial_processor.clone(),
-            world: params.world.clone(),
-            event_sender: params.event_sender,
-            timing_sender: params.timing_sender,
-        };
+        let render_ctx = self.create_render_context(&params, producer, resampler);
 
         // Spawn render thread
         let render_thread = thread::Builder::new()
@@ -554,14 +3022,28 @@ impl PetalSonicEngine {
 
         log::info!("Spawned render thread");
 
+        let device_lost = params.device_lost;
+        let error_event_sender = params.event_sender.clone();
+
         // Create context for audio callback (simplified - just consumes from ring buffer)
         let mut context = AudioCallbackContext {
             is_running: params.is_running,
             frames_processed: params.frames_processed,
-            active_playback: params.active_playback,
-            world: params.world,
             ring_buffer_consumer: consumer,
             channels: params.channels,
+            device_channels: params.device_channels,
+            output_mix: params.output_mix.map(FrameMixer::new),
+            callback_stats: params.callback_stats,
+            callback_clock: CallbackClock::new(),
+            prebuffer_frames: params.prebuffer_frames,
+            ditherer: Ditherer::new(self.desc.dither, T::FORMAT, params.device_channels as usize),
+            accessibility: self.accessibility.clone(),
+            channel_balance: ChannelBalance::new(&self.accessibility, params.device_channels),
+            test_tone_receiver: self.device_tone_receiver.clone(),
+            test_tones: Vec::with_capacity(MAX_TEST_TONES),
+            device_frame: vec![0.0; params.device_channels as usize],
+            tone_levels: vec![(0.0, 0.0); params.device_channels as usize],
+            device_levels: self.device_levels.clone(),
         };
 
         let stream = device
@@ -572,6 +3054,16 @@ impl PetalSonicEngine {
                 },
                 move |err| {
                     log::error!("Audio stream error: {}", err);
+                    if matches!(err, cpal::StreamError::DeviceNotAvailable)
+                        && !device_lost.swap(true, Ordering::Relaxed)
+                    {
+                        // The OS suspended audio or the device was removed. Park rendering
+                        // and let the host call `resume()` to reopen the device.
+                        let _ = error_event_sender.send(PetalSonicEvent::EngineError {
+                            error: err.to_string(),
+                        });
+                        let _ = error_event_sender.send(PetalSonicEvent::EngineSuspended);
+                    }
                 },
                 None,
             )
@@ -586,13 +3078,14 @@ impl PetalSonicEngine {
         device_sample_rate: u32,
         channels: u16,
         world_block_size: usize,
+        quality: ResampleQuality,
     ) -> Result<Arc<Mutex<StreamingResampler>>> {
-        let resampler = StreamingResampler::new(
+        let resampler = StreamingResampler::with_quality(
             world_sample_rate,
             device_sample_rate,
             channels,
             world_block_size,
-            Some(ResamplerType::Fast),
+            quality,
         )?;
 
         if world_sample_rate == device_sample_rate {
@@ -619,7 +3112,9 @@ impl PetalSonicEngine {
     where
         T: SizedSample + FromSample<f32>,
     {
+        let _section = RtSection::enter(RtPath::Callback);
         let channels_usize = ctx.channels as usize;
+        let device_channels = ctx.device_channels as usize;
 
         // If not running, fill silence
         if !ctx.is_running.load(Ordering::Relaxed) {
@@ -627,48 +3122,87 @@ impl PetalSonicEngine {
             return;
         }
 
-        // Process playback commands (stop/pause/play)
-        Self::process_playback_commands(&ctx.world, &ctx.active_playback);
+        let device_frames = data.len() / device_channels;
+        let interval = ctx.callback_clock.tick();
+        ctx.callback_stats.record_callback(device_frames, interval);
+
+        // Hold the output silent until the pre-buffer is queued
+        if ctx.prebuffer_frames > 0 {
+            if ctx.ring_buffer_consumer.occupied_len() / channels_usize < ctx.prebuffer_frames {
+                Self::fill_silence(data);
+                return;
+            }
+            rt_debug!("Pre-buffer of {} frames queued", ctx.prebuffer_frames);
+            ctx.prebuffer_frames = 0;
+        }
 
-        let device_frames = data.len() / channels_usize;
+        // Pick up newly requested test tones; tones beyond the capacity wait in the channel
+        while ctx.test_tones.len() < MAX_TEST_TONES
+            && let Ok(tone) = ctx.test_tone_receiver.try_recv()
+        {
+            ctx.test_tones.push(tone);
+        }
+        let measure_tones = !ctx.test_tones.is_empty();
+        if measure_tones {
+            ctx.tone_levels.fill((0.0, 0.0));
+        }
 
-        // Consume samples from ring buffer to fill output (lock-free!)
-        let mut samples_consumed = 0;
-        for i in 0..device_frames {
-            if let Some(frame) = ctx.ring_buffer_consumer.try_pop() {
-                let left_idx = i * channels_usize;
-                let right_idx = left_idx + 1;
-                if left_idx < data.len() {
-                    data[left_idx] = T::from_sample(frame.left);
+        // Consume whole frames from ring buffer to fill output (lock-free!)
+        let available_frames = ctx.ring_buffer_consumer.occupied_len() / channels_usize;
+        let frames_consumed = device_frames.min(available_frames);
+        let (filled, remaining) = data.split_at_mut(frames_consumed * device_channels);
+        let balance = &mut ctx.channel_balance;
+        balance.begin_callback(&ctx.accessibility, frames_consumed);
+        for frame in filled.chunks_exact_mut(device_channels) {
+            match ctx.output_mix.as_mut() {
+                None => {
+                    ctx.ring_buffer_consumer.pop_slice(balance.frame_mut());
                 }
-                if right_idx < data.len() {
-                    data[right_idx] = T::from_sample(frame.right);
+                Some(output_mix) => {
+                    ctx.ring_buffer_consumer.pop_slice(output_mix.input_mut());
+                    balance.frame_mut().copy_from_slice(output_mix.mix());
                 }
-                samples_consumed += 1;
-            } else {
-                // Not enough samples in ring buffer, fill rest with silence
-                // This indicates the render thread is falling behind
-                log::warn!(
-                    "Ring buffer underrun: only {} of {} frames available",
-                    samples_consumed,
-                    device_frames
-                );
-                for j in i..device_frames {
-                    let left_idx = j * channels_usize;
-                    let right_idx = left_idx + 1;
-                    if left_idx < data.len() {
-                        data[left_idx] = T::from_sample(0.0f32);
-                    }
-                    if right_idx < data.len() {
-                        data[right_idx] = T::from_sample(0.0f32);
-                    }
+            }
+            // Channel swap and balance act on the device's channels
+            for (output, value) in ctx.device_frame.iter_mut().zip(balance.process()) {
+                *output = *value;
+            }
+            // Test tones play on the device's channels as they are wired, after the mix
+            if measure_tones {
+                ctx.test_tones.retain_mut(|tone| {
+                    tone.mix_into(&mut ctx.device_frame, ctx.device_channels, 0)
+                });
+                for ((peak, energy), sample) in ctx.tone_levels.iter_mut().zip(&ctx.device_frame) {
+                    *peak = peak.max(sample.abs());
+                    *energy += sample * sample;
                 }
-                break;
             }
+            for (channel, (sample, value)) in frame.iter_mut().zip(&ctx.device_frame).enumerate() {
+                *sample = T::from_sample(ctx.ditherer.process(*value, channel));
+            }
+        }
+        if measure_tones && frames_consumed > 0 {
+            for (channel, &(peak, energy)) in ctx.tone_levels.iter().enumerate() {
+                ctx.device_levels
+                    .update_channel(channel, peak, energy, frames_consumed);
+            }
+        }
+
+        if frames_consumed < device_frames {
+            // Not enough samples in ring buffer, fill rest with silence
+            // This indicates the render thread is falling behind
+            ctx.callback_stats.record_underrun();
+            logging::count_underrun();
+            rt_debug!(
+                "Ring buffer underrun: only {} of {} frames available",
+                frames_consumed,
+                device_frames
+            );
+            Self::fill_silence(remaining);
         }
 
         ctx.frames_processed
-            .fetch_add(samples_consumed, Ordering::Relaxed);
+            .fetch_add(frames_consumed, Ordering::Relaxed);
     }
 
     /// Fill buffer with silence
@@ -681,90 +3215,382 @@ impl PetalSonicEngine {
         }
     }
 
+    /// Create or restart the playback instance of a source at `start_frame`, or at the
+    /// beginning of the clip in its playback direction if `None`
+    fn start_playback(
+        world: &PetalSonicWorld,
+        active_playback: &mut HashMap<SourceId, PlaybackInstance>,
+        audio_id: SourceId,
+        config: SourceConfig,
+        loop_mode: LoopMode,
+        start_frame: Option<usize>,
+    ) {
+        rt_debug!(
+            "Engine: Received Play command for source {} from frame {:?} (loop mode: {:?})",
+            audio_id,
+            start_frame,
+            loop_mode
+        );
+
+        let Some(audio_data) = world.get_audio_data(audio_id) else {
+            rt_warn!("Engine: Audio data not found for source {}", audio_id);
+            return;
+        };
+
+        let instance = active_playback.entry(audio_id).or_insert_with(|| {
+            rt_debug!(
+                "Engine: Creating new PlaybackInstance for source {}",
+                audio_id
+            );
+            PlaybackInstance::new(audio_id, audio_data.clone(), config.clone(), loop_mode)
+                .with_render_rate(world.sample_rate())
+                .with_live_source(world.live_source(audio_id))
+                .with_playlist(world.playlist(audio_id))
+        });
+
+        if instance.envelope.is_none() {
+            instance.envelope = world.envelope_follower(audio_id);
+        }
+
+        // Always update config and loop_mode when playing
+        instance.config = config;
+        instance.spatial_bypass = world.spatial_bypass(audio_id);
+        instance.output_routing = world.output_routing(audio_id);
+        instance.bus = world.source_bus(audio_id);
+        instance.tone = world.tone_filter(instance.bus.as_deref());
+        instance.silence = world.silence_detector();
+        instance.caption = world.caption_track(audio_id);
+        instance.haptics = world.haptics_cursor(audio_id);
+        instance.group_gain = world.sync_group_gain(audio_id);
+        // Playing a member on its own takes it off its group's clock
+        instance.sync_clock = None;
+        if instance.reverb_send.is_none() && world.has_reverb() {
+            instance.reverb_send = Some(Vec::new());
+        }
+        if instance.distance_delay.is_none() && instance.live_source.is_none() {
+            instance.distance_delay = world.distance_delay();
+        }
+        if instance.source_delay.is_none() && instance.live_source.is_none() {
+            instance.source_delay = world.source_delay_line(audio_id);
+        }
+        instance.set_loop_mode(loop_mode);
+        instance.set_direction(world.playback_direction(audio_id));
+        match start_frame {
+            Some(start_frame) => instance.play_from_frame(start_frame),
+            None => instance.play_from_beginning(),
+        }
+    }
+
     /// Process playback commands from the world and updates the active playback instances.
+    ///
+    /// Only the render thread (or the offline renderer in its place) drains the queue.
+    /// Stopped spatial sources are appended to `stopped_sources`, for their spatializer state
+    /// to be released.
     fn process_playback_commands(
         world: &Arc<PetalSonicWorld>,
         active_playback: &Arc<std::sync::Mutex<HashMap<SourceId, PlaybackInstance>>>,
+        stopped_sources: &mut Vec<SourceId>,
     ) {
-        while let Ok(command) = world.command_receiver().try_recv() {
-            let Ok(mut active_playback) = active_playback.try_lock() else {
-                continue;
-            };
+        let Some(mut commands) = world.try_command_consumer() else {
+            logging::count_lock_contention();
+            return;
+        };
+        if commands.is_empty() {
+            return;
+        }
+        // Commands stay queued until the next batch while the playback state is busy
+        let Ok(mut active_playback) = active_playback.try_lock() else {
+            logging::count_lock_contention();
+            return;
+        };
 
+        while let Some(command) = commands.try_pop() {
             match command {
                 PlaybackCommand::Play(audio_id, config, loop_mode) => {
-                    log::debug!(
-                        "Engine: Received Play command for source {} (loop mode: {:?})",
+                    Self::start_playback(
+                        world,
+                        &mut active_playback,
                         audio_id,
-                        loop_mode
+                        config,
+                        loop_mode,
+                        None,
                     );
-
-                    let Some(audio_data) = world.get_audio_data(audio_id) else {
-                        log::warn!("Engine: Audio data not found for source {}", audio_id);
-                        continue;
-                    };
-
-                    let instance = active_playback.entry(audio_id).or_insert_with(|| {
-                        log::debug!(
-                            "Engine: Creating new PlaybackInstance for source {}",
+                }
+                PlaybackCommand::PlayFrom(audio_id, config, loop_mode, start_frame) => {
+                    Self::start_playback(
+                        world,
+                        &mut active_playback,
+                        audio_id,
+                        config,
+                        loop_mode,
+                        Some(start_frame),
+                    );
+                }
+                PlaybackCommand::StopAfter(audio_id, frames) => {
+                    rt_debug!(
+                        "Engine: Received StopAfter({}) command for source {}",
+                        frames,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.stop_after(frames);
+                    } else {
+                        rt_warn!(
+                            "Engine: Cannot limit playback, source {} not in active playback",
                             audio_id
                         );
-                        PlaybackInstance::new(
-                            audio_id,
-                            audio_data.clone(),
-                            config.clone(),
-                            loop_mode,
-                        )
-                    });
-
-                    // Always update config and loop_mode when playing
-                    instance.config = config;
-                    instance.set_loop_mode(loop_mode);
-                    instance.play_from_beginning();
+                    }
+                }
+                PlaybackCommand::StopAt(audio_id, output_frame) => {
+                    rt_debug!(
+                        "Engine: Received StopAt({}) command for source {}",
+                        output_frame,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.stop_at = Some(output_frame);
+                    } else {
+                        rt_warn!(
+                            "Engine: Cannot schedule stop, source {} not in active playback",
+                            audio_id
+                        );
+                    }
                 }
                 PlaybackCommand::Pause(audio_id) => {
-                    log::debug!("Engine: Received Pause command for source {}", audio_id);
+                    rt_debug!("Engine: Received Pause command for source {}", audio_id);
                     if let Some(instance) = active_playback.get_mut(&audio_id) {
                         instance.pause();
                     } else {
-                        log::warn!(
+                        rt_warn!(
                             "Engine: Cannot pause, source {} not in active playback",
                             audio_id
                         );
                     }
                 }
                 PlaybackCommand::Stop(audio_id) => {
-                    log::debug!("Engine: Received Stop command for source {}", audio_id);
-                    if active_playback.remove(&audio_id).is_some() {
-                        log::debug!("Engine: Removed source {} from active playback", audio_id);
+                    rt_debug!("Engine: Received Stop command for source {}", audio_id);
+                    if let Some(instance) = active_playback.remove(&audio_id) {
+                        if instance.config.is_spatial() {
+                            stopped_sources.push(audio_id);
+                        }
+                        rt_debug!("Engine: Removed source {} from active playback", audio_id);
                     } else {
-                        log::warn!(
+                        rt_warn!(
                             "Engine: Cannot stop, source {} not in active playback",
                             audio_id
                         );
                     }
                 }
                 PlaybackCommand::UpdateConfig(audio_id, config) => {
-                    log::debug!(
+                    rt_debug!(
                         "Engine: Received UpdateConfig command for source {}",
                         audio_id
                     );
                     if let Some(instance) = active_playback.get_mut(&audio_id) {
-                        instance.config = config;
+                        instance.update_config(config);
                     } else {
-                        log::warn!(
+                        rt_warn!(
                             "Engine: Cannot update config, source {} not in active playback",
                             audio_id
                         );
                     }
                 }
+                PlaybackCommand::SetSolo(audio_id, soloed) => {
+                    rt_debug!(
+                        "Engine: Received SetSolo({}) command for source {}",
+                        soloed,
+                        audio_id
+                    );
+                    // The world re-sends the state of a soloed source after each Play
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.soloed = soloed;
+                    }
+                }
+                PlaybackCommand::SetSpatialBypass(audio_id, bypass) => {
+                    rt_debug!(
+                        "Engine: Received SetSpatialBypass({:?}) command for source {}",
+                        bypass,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.spatial_bypass = bypass;
+                    }
+                }
+                PlaybackCommand::SetDirection(audio_id, direction) => {
+                    rt_debug!(
+                        "Engine: Received SetDirection({:?}) command for source {}",
+                        direction,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.set_direction(direction);
+                    }
+                }
+                PlaybackCommand::SetOutputRouting(audio_id, routing) => {
+                    rt_debug!(
+                        "Engine: Received SetOutputRouting({:?}) command for source {}",
+                        routing,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.output_routing = routing;
+                    }
+                }
+                PlaybackCommand::SetBus(audio_id, bus) => {
+                    rt_debug!(
+                        "Engine: Received SetBus({:?}) command for source {}",
+                        bus,
+                        audio_id
+                    );
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.tone = world.tone_filter(bus.as_deref());
+                        instance.bus = bus;
+                    }
+                }
+                PlaybackCommand::SetBusTone(bus, tone) => {
+                    rt_debug!(
+                        "Engine: Received SetBusTone({:?}) command for bus {:?}",
+                        tone,
+                        bus
+                    );
+                    let sample_rate = world.sample_rate();
+                    for instance in active_playback.values_mut() {
+                        if instance.bus != bus {
+                            continue;
+                        }
+                        match instance.tone.as_mut() {
+                            Some(filter) => filter.set_tone(&tone, sample_rate),
+                            None if !tone.is_flat() => {
+                                instance.tone = Some(ToneFilter::new(&tone, sample_rate));
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                PlaybackCommand::SetSourceDelay(audio_id, frames) => {
+                    rt_debug!(
+                        "Engine: Received SetSourceDelay({}) command for source {}",
+                        frames,
+                        audio_id
+                    );
+                    // Inactive sources pick up their delay from the world on Play
+                    if let Some(instance) = active_playback.get_mut(&audio_id)
+                        && instance.live_source.is_none()
+                    {
+                        match instance.source_delay.as_mut() {
+                            Some(delay) => delay.set_delay(frames),
+                            None => instance.source_delay = world.source_delay_line(audio_id),
+                        }
+                    }
+                }
+                PlaybackCommand::SetEnvelopeFollower(audio_id, config) => {
+                    rt_debug!(
+                        "Engine: Received SetEnvelopeFollower({:?}) command for source {}",
+                        config,
+                        audio_id
+                    );
+                    // Inactive sources pick up their follower from the world on Play
+                    if let Some(instance) = active_playback.get_mut(&audio_id) {
+                        instance.envelope = world.envelope_follower(audio_id);
+                    }
+                }
+                PlaybackCommand::PlayGroup(sources, loop_mode, start_frame) => {
+                    rt_debug!(
+                        "Engine: Received PlayGroup command for {} sources from frame {}",
+                        sources.len(),
+                        start_frame
+                    );
+                    for (audio_id, config) in sources {
+                        Self::start_playback(
+                            world,
+                            &mut active_playback,
+                            audio_id,
+                            config,
+                            loop_mode,
+                            Some(start_frame),
+                        );
+                        if let Some(instance) = active_playback.get_mut(&audio_id) {
+                            instance.sync_clock = Some(start_frame);
+                        }
+                    }
+                }
+                PlaybackCommand::PauseGroup(members) => {
+                    rt_debug!("Engine: Received PauseGroup command for {:?}", members);
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.get_mut(&audio_id) {
+                            instance.pause();
+                        }
+                    }
+                }
+                PlaybackCommand::ResumeGroup(members) => {
+                    rt_debug!("Engine: Received ResumeGroup command for {:?}", members);
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.get_mut(&audio_id)
+                            && matches!(instance.info.play_state, PlayState::Paused)
+                        {
+                            instance.resume();
+                        }
+                    }
+                }
+                PlaybackCommand::SeekGroup(members, frame) => {
+                    rt_debug!(
+                        "Engine: Received SeekGroup({}) command for {:?}",
+                        frame,
+                        members
+                    );
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.get_mut(&audio_id) {
+                            instance.seek(frame);
+                            if instance.sync_clock.is_some() {
+                                instance.sync_clock = Some(frame);
+                            }
+                        }
+                    }
+                }
+                PlaybackCommand::StopGroup(members) => {
+                    rt_debug!("Engine: Received StopGroup command for {:?}", members);
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.remove(&audio_id)
+                            && instance.config.is_spatial()
+                        {
+                            stopped_sources.push(audio_id);
+                        }
+                    }
+                }
+                PlaybackCommand::SetGroupVolume(members, volume) => {
+                    rt_debug!(
+                        "Engine: Received SetGroupVolume({}) command for {:?}",
+                        volume,
+                        members
+                    );
+                    // Inactive members pick up their group's volume from the world on Play
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.get_mut(&audio_id) {
+                            instance.group_gain = volume;
+                        }
+                    }
+                }
+                PlaybackCommand::LeaveGroup(members) => {
+                    rt_debug!("Engine: Received LeaveGroup command for {:?}", members);
+                    for audio_id in members {
+                        if let Some(instance) = active_playback.get_mut(&audio_id) {
+                            instance.group_gain = 1.0;
+                            instance.sync_clock = None;
+                        }
+                    }
+                }
                 PlaybackCommand::StopAll => {
                     let count = active_playback.len();
-                    log::info!(
+                    rt_info!(
                         "Engine: Received StopAll command, stopping {} sources",
                         count
                     );
-                    active_playback.clear();
+                    stopped_sources.extend(
+                        active_playback
+                            .drain()
+                            .filter(|(_, instance)| instance.config.is_spatial())
+                            .map(|(audio_id, _)| audio_id),
+                    );
                 }
             }
         }
@@ -774,42 +3600,79 @@ impl PetalSonicEngine {
     /// Returns a tuple of (completed_sources, looped_sources, timing_event)
     #[allow(clippy::too_many_arguments)] // All parameters are necessary for this complex function
     fn generate_samples(
-        producer: &mut impl Producer<Item = StereoFrame>,
+        producer: &mut impl Producer<Item = f32>,
         samples_needed: usize,
         channels_usize: usize,
         channels: u16,
-        resampler_arc: &Arc<Mutex<StreamingResampler>>,
+        (resampler_arc, resample_counters): (&Arc<Mutex<StreamingResampler>>, &ResampleCounters),
         active_playback: &Arc<std::sync::Mutex<HashMap<SourceId, PlaybackInstance>>>,
         block_size: usize,
-        spatial_processor: Option<&Arc<Mutex<SpatialProcessor>>>,
-    ) -> (Vec<SourceId>, Vec<SourceId>, RenderTimingEvent) {
-        let total_start = Instant::now();
+        mut spatializers: Spatializers<'_>,
+        test_tones: &mut Vec<TestTone>,
+        samplers: &Mutex<Vec<SamplerVoices>>,
+        tap_producer: &Mutex<Option<TapProducer>>,
+        zones: &ZoneEvaluator,
+        stem_recorder: &Mutex<Option<StemRecorder>>,
+        secondary_mix: &Mutex<Option<SecondaryMix>>,
+        master: (&SharedMasterVolume, &mut MasterVolume),
+        focus: (&SharedFocus, &mut FocusFade),
+        accessibility: (&SharedAccessibility, &mut AccessibilityMix),
+        mut reverb: Option<&mut Reverb>,
+        output_eq: &Mutex<Option<OutputEqFilter>>,
+        output_levels: &SharedOutputLevels,
+        solo_bus: Option<&str>,
+        event_sender: &Sender<PetalSonicEvent>,
+        frames_processed: &AtomicUsize,
+        render_load: &mut RenderLoadMeter,
+        profiling: bool,
+        mut drain_fade: Option<(usize, &mut usize)>,
+        mut loudness: Option<(&SharedLoudness, &mut LoudnessMeter)>,
+    ) -> (
+        Vec<SourceId>,
+        Vec<SourceId>,
+        Vec<mixer::VoiceActivityChange>,
+        Option<RenderTimingEvent>,
+    ) {
+        // Stage timings are only measured while profiling is enabled
+        let total_start = profiling.then(Instant::now);
         let mut total_mixing_time_us = 0u64;
-        let total_spatial_time_us = 0u64;
+        let mut total_spatial_time_us = 0u64;
         let mut total_resampling_time_us = 0u64;
+        let mut total_input_frames = 0usize;
+        let mut total_output_frames = 0usize;
 
         let Ok(mut resampler) = resampler_arc.try_lock() else {
-            log::warn!("Failed to acquire resampler lock in generate_resampled_samples");
+            logging::count_lock_contention();
+            rt_debug!("Failed to acquire resampler lock in generate_resampled_samples");
             return (
                 Vec::new(),
                 Vec::new(),
-                RenderTimingEvent {
+                Vec::new(),
+                profiling.then_some(RenderTimingEvent {
                     mixing_time_us: 0,
                     spatial_time_us: 0,
                     resampling_time_us: 0,
                     total_time_us: 0,
-                },
+                    resampler_input_frames: 0,
+                    resampler_output_frames: 0,
+                }),
             );
         };
 
+        // Real-time budget of one block
+        let block_duration =
+            Duration::from_secs_f64(block_size as f64 / resampler.source_sample_rate() as f64);
+
         // Track all completed and looped sources across all mixing iterations
         let mut all_completed_sources = Vec::new();
         let mut all_looped_sources = Vec::new();
+        let mut all_voice_activity = Vec::new();
 
         // Generate samples in fixed world block_size chunks, output is variable
         let mut total_generated = 0;
         while total_generated < samples_needed {
             // Use thread-local buffers to avoid allocations
+            let block_start = Instant::now();
             WORLD_BUFFER.with(|buf| {
                 let mut world_buffer = buf.borrow_mut();
                 // Generate exactly block_size frames at world sample rate
@@ -818,31 +3681,149 @@ impl PetalSonicEngine {
                 world_buffer.resize(world_buffer_size, 0.0f32);
                 world_buffer.fill(0.0f32);
 
+                // The block is heard once the output played everything queued before it
+                let output_frame = frames_processed.load(Ordering::Relaxed)
+                    + producer.occupied_len() / channels_usize;
+
                 // Measure mixing time (includes both spatial and non-spatial)
-                let mixing_start = Instant::now();
+                let mixing_start = profiling.then(Instant::now);
 
                 // Use the mixer module to mix all playback instances
-                // Pass spatial processor if available
-                let mut spatial_processor_guard =
-                    spatial_processor.and_then(|sp| sp.try_lock().ok());
+                let listener_position = spatializers.listener_pose.position;
+                let mut custom_guard = spatializers.custom.try_lock().ok();
+                let mut processor_guard = spatializers.processor.and_then(|sp| sp.try_lock().ok());
+                let spatializer = spatializers
+                    .select(custom_guard.as_deref_mut(), processor_guard.as_deref_mut());
+
+                // Stems are accumulated by the mixer while recording
+                let mut stem_guard = stem_recorder.try_lock().ok();
+                let mut stems = stem_guard.as_mut().and_then(|guard| guard.as_mut());
+                if let Some(stems) = stems.as_mut() {
+                    stems.begin_block(block_size);
+                }
+                let mut secondary_guard = secondary_mix.try_lock().ok();
+                let mut secondary = secondary_guard.as_mut().and_then(|guard| guard.as_mut());
+                if let Some(secondary) = secondary.as_mut() {
+                    secondary.begin_block(block_size);
+                }
 
                 // Mix returns MixResult with completed and looped sources
                 let mix_result = mixer::mix_playback_instances(
                     &mut world_buffer,
                     channels,
                     active_playback,
-                    spatial_processor_guard.as_deref_mut(),
+                    spatializer,
+                    zones,
+                    listener_position,
+                    stems.as_deref_mut(),
+                    reverb.as_deref_mut(),
+                    secondary.as_deref_mut(),
+                    solo_bus,
+                    output_frame,
                 );
+                if let Some(secondary) = secondary {
+                    secondary.end_block();
+                }
+
+                let mixing_elapsed_us = elapsed_us(mixing_start);
+
+                // Spatial cost and budget degradation reported by the spatial processor
+                if let Some(processor) = processor_guard.as_deref_mut() {
+                    total_spatial_time_us += processor.take_process_time().as_micros() as u64;
+                    if let Some((degraded_sources, total_sources)) =
+                        processor.take_degradation_change()
+                    {
+                        if degraded_sources > 0 {
+                            rt_warn!(
+                                "Spatial budget exceeded, panning {} of {} sources",
+                                degraded_sources,
+                                total_sources
+                            );
+                        }
+                        let _ = event_sender.send(PetalSonicEvent::SpatialDegradationChanged {
+                            degraded_sources,
+                            total_sources,
+                        });
+                    }
+                }
+
+                // Test tones are mixed on top of the world output
+                test_tones
+                    .retain_mut(|tone| tone.mix_into(&mut world_buffer, channels, output_frame));
+
+                // Sampler voices are mixed on top of the world sources
+                if let Ok(mut samplers) = samplers.try_lock() {
+                    samplers.retain_mut(|sampler| {
+                        sampler.mix_into(&mut world_buffer, channels);
+                        !sampler.is_finished()
+                    });
+                }
+
+                // Fade the master mix out while draining
+                if let Some((fade_frames, position)) = drain_fade.as_mut()
+                    && *fade_frames > 0
+                {
+                    for frame in world_buffer.chunks_exact_mut(channels_usize) {
+                        let gain = 1.0 - (**position as f32 / *fade_frames as f32).min(1.0);
+                        frame.iter_mut().for_each(|sample| *sample *= gain);
+                        **position += 1;
+                    }
+                }
+
+                // Meter the master mix at the world sample rate
+                if let Some((shared, meter)) = loudness.as_mut() {
+                    shared.process(meter, &world_buffer);
+                }
+
+                // Feed the output tap (lock-free FIFO, encoded on the tap thread)
+                if let Ok(mut tap) = tap_producer.try_lock()
+                    && let Some(tap) = tap.as_mut()
+                {
+                    tap.push(&world_buffer);
+                }
+
+                if let Some(stems) = stems {
+                    stems.end_block(&world_buffer);
+                }
 
-                let mixing_elapsed = mixing_start.elapsed();
+                // Master volume and headphone calibration apply to the device output only
+                master.1.process(master.0, &mut world_buffer);
+                focus.1.process(focus.0, &mut world_buffer, channels_usize);
+                accessibility
+                    .1
+                    .process(accessibility.0, &mut world_buffer, channels_usize);
+                if let Ok(mut eq) = output_eq.try_lock()
+                    && let Some(eq) = eq.as_mut()
+                {
+                    eq.process(&mut world_buffer);
+                }
+                output_levels.update(&world_buffer);
 
                 // Collect completed and looped sources for event emission
                 all_completed_sources.extend(mix_result.completed_sources);
                 all_looped_sources.extend(mix_result.looped_sources);
+                all_voice_activity.extend(mix_result.voice_activity);
+                for (source_id, duration) in mix_result.silent_sources {
+                    rt_debug!("Source {} silent for {:?}", source_id, duration);
+                    let _ = event_sender.send(PetalSonicEvent::SourceSilent {
+                        source_id,
+                        duration,
+                    });
+                }
+                for event in mix_result.captions.into_iter().chain(mix_result.markers) {
+                    let _ = event_sender.send(event);
+                }
+                for (source_id, intensity) in mix_result.haptics {
+                    let _ = event_sender.send(PetalSonicEvent::Haptics {
+                        source_id,
+                        intensity,
+                        output_frame,
+                    });
+                }
 
                 // Note: Spatial processing time is embedded in mixing time
                 // We'll extract it from the mixer in the future if needed
-                total_mixing_time_us += mixing_elapsed.as_micros() as u64;
+                total_mixing_time_us += mixing_elapsed_us;
 
                 RESAMPLED_BUFFER.with(|rbuf| {
                     let mut resampled_buffer = rbuf.borrow_mut();
@@ -854,65 +3835,75 @@ impl PetalSonicEngine {
                     resampled_buffer.resize(expected_output, 0.0f32);
 
                     // Measure resampling time
-                    let resampling_start = Instant::now();
+                    let resampling_start = profiling.then(Instant::now);
 
                     match resampler.process_interleaved(&world_buffer, &mut resampled_buffer) {
-                        Ok((frames_out, _frames_in)) => {
-                            let resampling_elapsed = resampling_start.elapsed();
-                            total_resampling_time_us += resampling_elapsed.as_micros() as u64;
-
-                            // Push all generated frames to ring buffer
-                            let mut pushed = 0;
-                            for i in 0..frames_out {
-                                let left_idx = i * channels_usize;
-                                let right_idx = left_idx + 1;
-                                let frame = StereoFrame {
-                                    left: *resampled_buffer.get(left_idx).unwrap_or(&0.0),
-                                    right: *resampled_buffer.get(right_idx).unwrap_or(&0.0),
-                                };
-                                if producer.try_push(frame).is_ok() {
-                                    pushed += 1;
-                                } else {
-                                    // Ring buffer is full
-                                    break;
-                                }
-                            }
+                        Ok((frames_out, frames_in)) => {
+                            total_resampling_time_us += elapsed_us(resampling_start);
+                            resample_counters.record(frames_in, frames_out);
+                            total_input_frames += frames_in;
+                            total_output_frames += frames_out;
+
+                            // Push as many whole generated frames as fit into the ring buffer
+                            let vacant_frames = producer.vacant_len() / channels_usize;
+                            let frames_to_push = frames_out
+                                .min(vacant_frames)
+                                .min(resampled_buffer.len() / channels_usize);
+                            let pushed = producer
+                                .push_slice(&resampled_buffer[..frames_to_push * channels_usize])
+                                / channels_usize;
 
                             total_generated += pushed;
-
-                            // If we couldn't push any frames, ring buffer is full
-                            if pushed == 0 {}
                         }
                         Err(e) => {
-                            log::error!("Resampling error: {}", e);
+                            logging::count_render_error();
+                            rt_error!("Resampling error: {}", e);
                         }
                     }
                 });
             });
 
+            if let Some((load, rolling_max)) =
+                render_load.record(block_start.elapsed(), block_duration)
+            {
+                rt_warn!(
+                    "Render block took {:.0}% of its budget (recent max {:.0}%)",
+                    load * 100.0,
+                    rolling_max * 100.0
+                );
+                let _ = event_sender.send(PetalSonicEvent::BudgetExceeded { load, rolling_max });
+            }
+
             // If we've generated enough or can't push more, stop
             if total_generated >= samples_needed {
                 break;
             }
         }
 
-        let total_elapsed = total_start.elapsed();
-
         (
             all_completed_sources,
             all_looped_sources,
-            RenderTimingEvent {
+            all_voice_activity,
+            total_start.map(|start| RenderTimingEvent {
                 mixing_time_us: total_mixing_time_us,
-                spatial_time_us: total_spatial_time_us, // TODO: Extract from mixer
+                spatial_time_us: total_spatial_time_us,
                 resampling_time_us: total_resampling_time_us,
-                total_time_us: total_elapsed.as_micros() as u64,
-            },
+                total_time_us: elapsed_us(Some(start)),
+                resampler_input_frames: total_input_frames as u64,
+                resampler_output_frames: total_output_frames as u64,
+            }),
         )
     }
 }
 
+/// Microseconds since `start`, 0 if the stage was not timed
+fn elapsed_us(start: Option<Instant>) -> u64 {
+    start.map_or(0, |start| start.elapsed().as_micros() as u64)
+}
+
 impl Drop for PetalSonicEngine {
     fn drop(&mut self) {
         let _ = self.stop();
+        self.world.detach_engine();
     }
 }
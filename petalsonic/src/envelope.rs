@@ -0,0 +1,116 @@
+//! Envelope followers for audio-reactive visuals.
+//!
+//! Enable a follower on a source with
+//! [`PetalSonicWorld::enable_envelope`](crate::PetalSonicWorld::enable_envelope) and poll
+//! its level with [`PetalSonicWorld::envelope`](crate::PetalSonicWorld::envelope), e.g. once
+//! per frame to drive speaker cones, lights or lip flap:
+//!
+//! ```ignore
+//! world.enable_envelope(source_id, EnvelopeConfig::default())?;
+//! // every frame:
+//! let level = world.envelope(source_id); // 0.0..=1.0 for unclipped audio
+//! ```
+//!
+//! The follower runs on the render thread on the source's signal as it enters the mix
+//! (after volume, before spatialization), so it reflects what is actually played,
+//! including pauses, solo muting and loop points. Its level drops to zero when the source
+//! is paused or stops.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+/// Response times of an envelope follower
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeConfig {
+    /// Time for the level to rise by ~63% towards a louder signal
+    pub attack: Duration,
+    /// Time for the level to fall by ~63% towards a quieter signal
+    pub release: Duration,
+}
+
+impl Default for EnvelopeConfig {
+    fn default() -> Self {
+        Self {
+            attack: Duration::from_millis(10),
+            release: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Envelope level published by the render thread, read by the world
+#[derive(Debug, Default)]
+pub(crate) struct SharedEnvelope {
+    level: AtomicU32,
+}
+
+impl SharedEnvelope {
+    pub fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, level: f32) {
+        self.level.store(level.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Render-side peak envelope follower of a playback instance
+#[derive(Debug, Clone)]
+pub(crate) struct EnvelopeFollower {
+    shared: Arc<SharedEnvelope>,
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new(shared: Arc<SharedEnvelope>, config: &EnvelopeConfig, sample_rate: u32) -> Self {
+        let coefficient = |time: Duration| {
+            let samples = time.as_secs_f32() * sample_rate as f32;
+            if samples <= 0.0 {
+                1.0
+            } else {
+                1.0 - (-1.0 / samples).exp()
+            }
+        };
+
+        Self {
+            level: shared.level(),
+            shared,
+            attack: coefficient(config.attack),
+            release: coefficient(config.release),
+        }
+    }
+
+    /// Follow a block of mono samples and publish the resulting level
+    pub fn process(&mut self, samples: &[f32]) {
+        for sample in samples {
+            let input = sample.abs();
+            let coefficient = if input > self.level {
+                self.attack
+            } else {
+                self.release
+            };
+            self.level += (input - self.level) * coefficient;
+        }
+        self.shared.store(self.level);
+    }
+
+    /// Follow `frames` frames of silence
+    pub fn process_silence(&mut self, frames: usize) {
+        self.level *= (1.0 - self.release).powi(frames.min(i32::MAX as usize) as i32);
+        self.shared.store(self.level);
+    }
+
+    /// Drop the level to zero
+    pub fn reset(&mut self) {
+        self.level = 0.0;
+        self.shared.store(0.0);
+    }
+}
+
+impl Drop for EnvelopeFollower {
+    fn drop(&mut self) {
+        self.shared.store(0.0);
+    }
+}
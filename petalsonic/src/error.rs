@@ -1,5 +1,8 @@
 //! Error types for PetalSonic
 
+use crate::config::{
+    MAX_BLOCK_SIZE, MAX_CHANNELS, MAX_SAMPLE_RATE, MIN_BLOCK_SIZE, MIN_SAMPLE_RATE,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -31,8 +34,60 @@ pub enum PetalSonicError {
     #[error("Spatial audio error: {0}")]
     SpatialAudio(String),
 
+    #[error("Spatial source limit of {0} reached")]
+    SpatialSourceLimit(usize),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(#[from] ConfigError),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
+/// A [`PetalSonicWorldDesc`](crate::PetalSonicWorldDesc) setting the engine cannot run with
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    #[error("Sample rate {0} Hz is outside {min}..={max} Hz", min = MIN_SAMPLE_RATE, max = MAX_SAMPLE_RATE)]
+    SampleRate(u32),
+
+    #[error("Block size {0} is not a power of two between {min} and {max}", min = MIN_BLOCK_SIZE, max = MAX_BLOCK_SIZE)]
+    BlockSize(usize),
+
+    #[error("Channel count {0} is outside 1..={max}", max = MAX_CHANNELS)]
+    Channels(u16),
+
+    #[error("Output mix takes {mix_channels} channels, the world has {channels}")]
+    OutputMix { mix_channels: u16, channels: u16 },
+
+    #[error("Simulation rate must be greater than 0")]
+    SimulationRate,
+
+    #[error("Command queue capacity must be greater than 0")]
+    CommandQueueCapacity,
+
+    #[error("Maximum spatial source count must be greater than 0")]
+    MaxSpatialSources,
+
+    #[error("Spatial budget must be greater than 0, got {0}")]
+    SpatialBudget(f32),
+
+    #[error("Spatial level of detail: {0}")]
+    SpatialLod(String),
+
+    #[error("Source clustering: {0}")]
+    SourceClustering(String),
+
+    #[error("Master volume must be finite and non-negative, got {0}")]
+    MasterVolume(f32),
+
+    #[error("Balance must be between -1.0 and 1.0, got {0}")]
+    Balance(f32),
+
+    #[error("Output EQ: {0}")]
+    OutputEq(String),
+
+    #[error("Resample quality: {0}")]
+    ResampleQuality(String),
+}
+
 pub type Result<T> = std::result::Result<T, PetalSonicError>;
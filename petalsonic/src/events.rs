@@ -2,6 +2,7 @@
 
 use crate::math::Vec3;
 use crate::world::SourceId;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Timing information for a single render iteration
@@ -16,6 +17,10 @@ pub struct RenderTimingEvent {
     pub resampling_time_us: u64,
     /// Total time for the entire render iteration (microseconds)
     pub total_time_us: u64,
+    /// World frames fed to the sample-rate converter in this iteration
+    pub resampler_input_frames: u64,
+    /// Device frames the sample-rate converter produced in this iteration
+    pub resampler_output_frames: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,6 +38,11 @@ pub enum PetalSonicEvent {
     SourceStopped {
         source_id: SourceId,
     },
+    /// The source's data is stored at the world's sample rate, so `play()` starts it
+    /// without converting it first (see [`crate::world::PetalSonicWorld::is_ready`])
+    SourceReady {
+        source_id: SourceId,
+    },
     BufferUnderrun {
         source_id: Option<SourceId>,
     },
@@ -64,11 +74,91 @@ pub enum PetalSonicEvent {
         old_position: Vec3,
         new_position: Vec3,
     },
+    /// A live voice source started talking (see [`crate::voice`])
+    VoiceActivityStarted {
+        source_id: SourceId,
+    },
+    /// A live voice source stopped talking
+    VoiceActivityStopped {
+        source_id: SourceId,
+    },
+    /// A playing source stayed below the silence threshold for `duration` (see
+    /// [`crate::silence`])
+    SourceSilent {
+        source_id: SourceId,
+        duration: Duration,
+    },
+    /// The caption of a source (see [`crate::caption`]) is due: the first block of its
+    /// playback was rendered. `duration` is how long to show it.
+    Caption {
+        source_id: SourceId,
+        text: String,
+        duration: Duration,
+    },
+    /// Playback of a captioned source passed one of its cues at `offset` of the clip
+    CaptionCue {
+        source_id: SourceId,
+        text: String,
+        offset: Duration,
+    },
+    /// Playback of a source crossed a marker of its clip (see
+    /// [`PetalSonicAudioData::markers`](crate::audio_data::PetalSonicAudioData::markers)),
+    /// at `frame` of the clip. Markers are crossed in either playback direction.
+    Marker {
+        source_id: SourceId,
+        name: String,
+        frame: usize,
+    },
+    /// Peak intensity of a source's haptics track (see [`crate::haptics`]) over a rendered
+    /// block, which is heard from `output_frame` on the clock of
+    /// [`PetalSonicEngine::frames_processed`](crate::PetalSonicEngine::frames_processed)
+    Haptics {
+        source_id: SourceId,
+        intensity: f32,
+        output_frame: usize,
+    },
+    /// The number of spatial sources rendered with stereo panning instead of HRTF because
+    /// spatial processing exceeded its CPU budget changed (`degraded_sources` is 0 once
+    /// all sources are fully spatialized again)
+    SpatialDegradationChanged {
+        degraded_sources: usize,
+        total_sources: usize,
+    },
+    /// A block took longer to render than its duration (`load` above 1.0), after blocks
+    /// within the budget; `rolling_max` is the highest load of the recent blocks (see
+    /// [`crate::diagnostics::RenderLoadStats`]). Emitted again once the recent blocks were
+    /// all within the budget.
+    BudgetExceeded {
+        load: f32,
+        rolling_max: f32,
+    },
+    /// Steam Audio could not be initialized (e.g. its libraries are missing), so all
+    /// spatial sources are panned in stereo by azimuth with inverse-distance attenuation
+    /// instead of HRTF-rendered
+    SpatialFallbackActive {
+        reason: String,
+    },
     EngineStarted,
     EngineStopped,
+    /// `PetalSonicEngine::stop_with_drain` finished; `timed_out` is true if playback was
+    /// cut off at the timeout instead of playing out
+    Drained {
+        timed_out: bool,
+    },
+    /// The engine released the audio device but kept its state, either via
+    /// `PetalSonicEngine::suspend` or because the device became unavailable
+    EngineSuspended,
+    /// The engine reopened the audio device after being suspended
+    EngineResumed,
     EngineError {
         error: String,
     },
+    /// The HRTF file `path` was not found in any of the `attempted` locations (see
+    /// [`crate::assets`]); Steam Audio's built-in HRTF is used instead
+    HrtfNotFound {
+        path: String,
+        attempted: Vec<PathBuf>,
+    },
 }
 
 impl PetalSonicEvent {
@@ -78,10 +168,18 @@ impl PetalSonicEvent {
             | Self::SourceLooped { source_id, .. }
             | Self::SourceStarted { source_id }
             | Self::SourceStopped { source_id }
+            | Self::SourceReady { source_id }
             | Self::SpatializationError { source_id, .. }
             | Self::SourceReachedEnd { source_id, .. }
             | Self::SourceVolumeChanged { source_id, .. }
-            | Self::SourcePoseChanged { source_id, .. } => Some(*source_id),
+            | Self::SourcePoseChanged { source_id, .. }
+            | Self::VoiceActivityStarted { source_id }
+            | Self::VoiceActivityStopped { source_id }
+            | Self::SourceSilent { source_id, .. }
+            | Self::Caption { source_id, .. }
+            | Self::CaptionCue { source_id, .. }
+            | Self::Marker { source_id, .. }
+            | Self::Haptics { source_id, .. } => Some(*source_id),
             Self::BufferUnderrun { source_id } | Self::BufferOverrun { source_id } => *source_id,
             _ => None,
         }
@@ -94,6 +192,7 @@ impl PetalSonicEvent {
                 | Self::BufferOverrun { .. }
                 | Self::SpatializationError { .. }
                 | Self::EngineError { .. }
+                | Self::HrtfNotFound { .. }
         )
     }
 
@@ -104,9 +203,17 @@ impl PetalSonicEvent {
                 | Self::SourceLooped { .. }
                 | Self::SourceStarted { .. }
                 | Self::SourceStopped { .. }
+                | Self::SourceReady { .. }
                 | Self::SourceReachedEnd { .. }
                 | Self::SourceVolumeChanged { .. }
                 | Self::SourcePoseChanged { .. }
+                | Self::VoiceActivityStarted { .. }
+                | Self::VoiceActivityStopped { .. }
+                | Self::SourceSilent { .. }
+                | Self::Caption { .. }
+                | Self::CaptionCue { .. }
+                | Self::Marker { .. }
+                | Self::Haptics { .. }
         )
     }
 }
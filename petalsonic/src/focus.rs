@@ -0,0 +1,171 @@
+//! Muting or pausing the output while the application is in the background.
+//!
+//! Games usually go quiet when the player switches to another window. Rather than every
+//! host pausing its sources one by one, the host reports focus changes with
+//! [`PetalSonicEngine::set_foreground`](crate::PetalSonicEngine::set_foreground) and the
+//! engine applies the world's [`BackgroundPolicy`]:
+//!
+//! ```ignore
+//! let mut engine = PetalSonicEngine::new(
+//!     PetalSonicWorldDesc {
+//!         background_policy: BackgroundPolicy::PauseInBackground,
+//!         ..Default::default()
+//!     },
+//!     world.clone(),
+//! )?;
+//! // from the window event loop:
+//! engine.set_foreground(window_focused);
+//! ```
+//!
+//! Going to the background fades the device output out over
+//! [`PetalSonicWorldDesc::background_fade`](crate::PetalSonicWorldDesc::background_fade);
+//! coming back fades it in again. With [`BackgroundPolicy::PauseInBackground`], the sources
+//! playing once the fade-out completed are paused, and sources started while in the
+//! background are paused as they start. Back in the foreground, the sources the engine
+//! paused resume where they were, unless they were stopped in the meantime. Sources the
+//! host paused before going to the background stay paused.
+//!
+//! Like the master volume, the fade applies to the device output only; the output tap,
+//! stems and loudness metering keep seeing the mix.
+
+use crate::playback::{PlayState, PlaybackInstance};
+use crate::world::SourceId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+/// What the engine does with the output while the application is in the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BackgroundPolicy {
+    /// Keep playing as in the foreground (default)
+    #[default]
+    ContinueInBackground,
+    /// Keep the sources playing with the output faded to silence
+    MuteInBackground,
+    /// Fade the output out, then pause all sources until back in the foreground
+    PauseInBackground,
+}
+
+impl BackgroundPolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::MuteInBackground,
+            2 => Self::PauseInBackground,
+            _ => Self::ContinueInBackground,
+        }
+    }
+}
+
+/// Focus state and policy shared between the engine (main thread) and the render thread
+pub(crate) struct SharedFocus {
+    foreground: AtomicBool,
+    policy: AtomicU8,
+    /// Length of the fades in world frames
+    fade_frames: AtomicUsize,
+}
+
+impl SharedFocus {
+    pub fn new(policy: BackgroundPolicy, fade_frames: usize) -> Self {
+        Self {
+            foreground: AtomicBool::new(true),
+            policy: AtomicU8::new(policy as u8),
+            fade_frames: AtomicUsize::new(fade_frames),
+        }
+    }
+
+    pub fn is_foreground(&self) -> bool {
+        self.foreground.load(Ordering::Relaxed)
+    }
+
+    pub fn set_foreground(&self, foreground: bool) {
+        self.foreground.store(foreground, Ordering::Relaxed);
+    }
+
+    pub fn policy(&self) -> BackgroundPolicy {
+        BackgroundPolicy::from_u8(self.policy.load(Ordering::Relaxed))
+    }
+
+    pub fn set_policy(&self, policy: BackgroundPolicy) {
+        self.policy.store(policy as u8, Ordering::Relaxed);
+    }
+
+    /// Whether the output should be faded out now
+    fn silenced(&self) -> bool {
+        !self.is_foreground() && self.policy() != BackgroundPolicy::ContinueInBackground
+    }
+}
+
+/// Render-side fade of the device output and the sources paused by the policy
+pub(crate) struct FocusFade {
+    gain: f32,
+    /// Sources paused because the application went to the background, resumed when it
+    /// comes back
+    paused_sources: Vec<SourceId>,
+}
+
+impl FocusFade {
+    pub fn new(shared: &SharedFocus, max_sources: usize) -> Self {
+        Self {
+            gain: if shared.silenced() { 0.0 } else { 1.0 },
+            paused_sources: Vec::with_capacity(max_sources),
+        }
+    }
+
+    /// Whether `update_playback` has sources to pause or resume
+    pub fn needs_playback_update(&self, shared: &SharedFocus) -> bool {
+        let pausing = shared.silenced() && shared.policy() == BackgroundPolicy::PauseInBackground;
+        (pausing && self.gain == 0.0) || (!pausing && !self.paused_sources.is_empty())
+    }
+
+    /// Pause the playing sources once faded out in the background, or resume the sources
+    /// paused that way once allowed to play again
+    pub fn update_playback(
+        &mut self,
+        shared: &SharedFocus,
+        active_playback: &mut HashMap<SourceId, PlaybackInstance>,
+    ) {
+        let pausing = shared.silenced() && shared.policy() == BackgroundPolicy::PauseInBackground;
+        if pausing {
+            if self.gain > 0.0 {
+                return;
+            }
+            for (source_id, instance) in active_playback.iter_mut() {
+                if matches!(instance.info.play_state, PlayState::Playing) {
+                    instance.pause();
+                    if !self.paused_sources.contains(source_id) {
+                        self.paused_sources.push(*source_id);
+                    }
+                }
+            }
+        } else {
+            for source_id in self.paused_sources.drain(..) {
+                if let Some(instance) = active_playback.get_mut(&source_id)
+                    && matches!(instance.info.play_state, PlayState::Paused)
+                {
+                    instance.resume();
+                }
+            }
+        }
+    }
+
+    /// Ramp the fade towards its target and apply it to interleaved samples in place
+    pub fn process(&mut self, shared: &SharedFocus, samples: &mut [f32], channels: usize) {
+        let target = if shared.silenced() { 0.0 } else { 1.0 };
+        if self.gain == target {
+            if target == 0.0 {
+                samples.fill(0.0);
+            }
+            return;
+        }
+
+        let step = 1.0 / shared.fade_frames.load(Ordering::Relaxed).max(1) as f32;
+        for frame in samples.chunks_exact_mut(channels) {
+            self.gain = if target > self.gain {
+                (self.gain + step).min(target)
+            } else {
+                (self.gain - step).max(target)
+            };
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Haptics tracks played in sync with their source.
+//!
+//! A [`HapticsTrack`] is a mono rumble signal attached to a source with
+//! [`PetalSonicWorld::set_haptics_track`](crate::PetalSonicWorld::set_haptics_track). It is
+//! either an auxiliary channel of the source's asset, split off before registering, or a
+//! paired file:
+//!
+//! ```ignore
+//! let asset = PetalSonicAudioData::from_path("explosion_with_rumble.wav")?;
+//! let (audio, haptics) = HapticsTrack::split_channel(&asset, 2)?;
+//! let source_id = world.register_audio(audio, SourceConfig::spatial(position))?;
+//! world.set_haptics_track(source_id, Some(haptics))?;
+//!
+//! // or from a paired file:
+//! let rumble = PetalSonicAudioData::from_path("explosion.haptics.wav")?;
+//! world.set_haptics_track(source_id, Some(HapticsTrack::from_audio(&rumble)?))?;
+//! ```
+//!
+//! While the source plays, the render thread reads the track at the clip position of every
+//! block it renders and emits [`PetalSonicEvent::Haptics`](crate::PetalSonicEvent::Haptics)
+//! with the block's peak intensity, stamped with the output frame the block will be heard
+//! at. That frame counts on the same clock as
+//! [`PetalSonicEngine::frames_processed`](crate::PetalSonicEngine::frames_processed), so a
+//! controller or vest can rumble exactly when the audio plays:
+//!
+//! ```ignore
+//! let now = engine.frames_processed();
+//! for event in engine.poll_events() {
+//!     if let PetalSonicEvent::Haptics { intensity, output_frame, .. } = event {
+//!         let delay = output_frame.saturating_sub(now) as f64 / sample_rate as f64;
+//!         gamepad.schedule_rumble(Duration::from_secs_f64(delay), intensity);
+//!     }
+//! }
+//! ```
+//!
+//! The track follows the clip through seeks, loops, reverse playback and pauses (no events
+//! while paused). It is not affected by the source's volume, spatialization or solo.
+
+use crate::audio_data::PetalSonicAudioData;
+use crate::error::{PetalSonicError, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Mono rumble signal of a source, with the clip's timing
+#[derive(Debug, Clone)]
+pub struct HapticsTrack {
+    samples: Arc<[f32]>,
+    sample_rate: u32,
+}
+
+impl HapticsTrack {
+    /// Track from a paired file, mixed to mono if it has several channels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data cannot be mixed to mono.
+    pub fn from_audio(audio: &PetalSonicAudioData) -> Result<Self> {
+        let mono = audio.to_mono()?;
+        Ok(Self {
+            samples: mono.samples().into(),
+            sample_rate: mono.sample_rate(),
+        })
+    }
+
+    /// Track from one channel of an asset
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `channel` is out of range.
+    pub fn from_channel(audio: &PetalSonicAudioData, channel: usize) -> Result<Self> {
+        Ok(Self {
+            samples: audio.channel_samples(channel)?.into(),
+            sample_rate: audio.sample_rate(),
+        })
+    }
+
+    /// Split an asset into its audio, without `channel`, and the track read from `channel`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `channel` is out of range or the asset has no other channel.
+    pub fn split_channel(
+        audio: &PetalSonicAudioData,
+        channel: usize,
+    ) -> Result<(Arc<PetalSonicAudioData>, Self)> {
+        if audio.channels() < 2 {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "Cannot split haptics channel {} off audio with {} channel",
+                channel,
+                audio.channels()
+            )));
+        }
+        let track = Self::from_channel(audio, channel)?;
+        let remaining = (0..audio.channels() as usize)
+            .filter(|other| *other != channel)
+            .map(|other| audio.channel_samples(other))
+            .collect::<Result<Vec<_>>>()?;
+        let audio = PetalSonicAudioData::from_planar(remaining, audio.sample_rate())?;
+        Ok((audio, track))
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.sample_rate as f64)
+    }
+
+    /// The track converted to `sample_rate`
+    pub(crate) fn resample(&self, sample_rate: u32) -> Result<Self> {
+        if sample_rate == self.sample_rate {
+            return Ok(self.clone());
+        }
+        let audio = PetalSonicAudioData::from_samples(self.samples.to_vec(), self.sample_rate, 1)?
+            .resample(sample_rate)?;
+        Ok(Self {
+            samples: audio.samples().into(),
+            sample_rate,
+        })
+    }
+}
+
+/// Render-side reader of a source's haptics track, at the world's sample rate
+#[derive(Debug, Clone)]
+pub(crate) struct HapticsCursor {
+    track: HapticsTrack,
+    /// Clip frame up to which the track was read
+    position: usize,
+}
+
+impl HapticsCursor {
+    pub(crate) fn new(track: HapticsTrack) -> Self {
+        Self { track, position: 0 }
+    }
+
+    /// Continue reading at `position`, after a seek or a loop
+    pub(crate) fn restart(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Peak intensity of the track between the last position and `position`, which is
+    /// before it while the clip plays in reverse
+    pub(crate) fn advance(&mut self, position: usize) -> f32 {
+        let len = self.track.samples.len();
+        let start = self.position.min(position).min(len);
+        let end = self.position.max(position).min(len);
+        self.position = position;
+        self.track.samples[start..end]
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+    }
+}
@@ -0,0 +1,415 @@
+//! Loopback measurement of the output latency.
+//!
+//! [`PetalSonicEngine::frames_processed`](crate::PetalSonicEngine::frames_processed) counts
+//! the frames handed to the device, but the listener hears them later: after the device and
+//! driver buffers, the DAC and, for wireless headphones, the radio link. Rhythm games and
+//! other sync-sensitive applications calibrate that offset once per output setup.
+//! [`PetalSonicEngine::measure_latency`](crate::PetalSonicEngine::measure_latency) measures
+//! it with a microphone (or a cable looped back from the output to an input):
+//!
+//! 1. A short chirp is played on all output channels a few times, each stamped with the
+//!    output frame it starts at.
+//! 2. An input stream captures the microphone meanwhile, mapping each captured frame to
+//!    the output clock at which it was captured.
+//! 3. Each chirp is found in the capture by cross-correlation; the difference of the two
+//!    clocks is one measurement.
+//!
+//! ```ignore
+//! let report = engine.measure_latency(&LatencyTestConfig::default())?;
+//! println!("{}", report);
+//! engine.set_clock_offset(report.latency);
+//! // Later, the frame the listener hears right now:
+//! let heard = engine.audible_frame();
+//! ```
+//!
+//! The input side's own buffering is subtracted using the capture timestamps of the
+//! input stream; on hosts that do not report them accurately it is included in the result.
+//! Keep the room quiet and the output loud enough for the microphone while measuring.
+
+use crate::diagnostics::TestTone;
+use crate::error::{PetalSonicError, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use crossbeam_channel::Sender;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Start and end frequency of the chirp in Hz
+const CHIRP_RANGE_HZ: (f32, f32) = (500.0, 8000.0);
+/// Fade in/out of the chirp as a fraction of its length
+const CHIRP_FADE: f32 = 0.1;
+/// Time the input stream runs before the first chirp
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Settings of a latency measurement
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyTestConfig {
+    /// Input device capturing the output, `None` for the default input device
+    pub input_device: Option<String>,
+    /// Number of chirps played
+    pub repetitions: usize,
+    /// Time between two chirps; must exceed `max_latency` plus `chirp_duration`
+    pub interval: Duration,
+    /// Length of each chirp
+    pub chirp_duration: Duration,
+    /// Longest latency searched for
+    pub max_latency: Duration,
+    /// Normalized correlation (0 to 1) a chirp must reach in the capture to be counted
+    pub min_correlation: f32,
+}
+
+impl Default for LatencyTestConfig {
+    fn default() -> Self {
+        Self {
+            input_device: None,
+            repetitions: 8,
+            interval: Duration::from_millis(600),
+            chirp_duration: Duration::from_millis(20),
+            max_latency: Duration::from_millis(500),
+            min_correlation: 0.3,
+        }
+    }
+}
+
+/// Result of a latency measurement
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyReport {
+    /// Mean time from a frame being counted in `frames_processed` to it being captured
+    pub latency: Duration,
+    /// Standard deviation of the measurements
+    pub jitter: Duration,
+    /// Shortest measurement
+    pub min: Duration,
+    /// Longest measurement
+    pub max: Duration,
+    /// Latency of each chirp found in the capture, in the order played
+    pub measurements: Vec<Duration>,
+    /// Chirps not found in the capture
+    pub missed: usize,
+}
+
+impl LatencyReport {
+    fn from_measurements(measurements: Vec<Duration>, missed: usize) -> Self {
+        let seconds: Vec<f64> = measurements.iter().map(Duration::as_secs_f64).collect();
+        let count = seconds.len().max(1) as f64;
+        let mean = seconds.iter().sum::<f64>() / count;
+        let variance = seconds.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / count;
+        Self {
+            latency: Duration::from_secs_f64(mean),
+            jitter: Duration::from_secs_f64(variance.sqrt()),
+            min: measurements.iter().copied().min().unwrap_or_default(),
+            max: measurements.iter().copied().max().unwrap_or_default(),
+            measurements,
+            missed,
+        }
+    }
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PetalSonic latency measurement")?;
+        writeln!(
+            f,
+            "  Latency: {:.2} ms (jitter {:.2} ms, min {:.2} ms, max {:.2} ms)",
+            self.latency.as_secs_f64() * 1000.0,
+            self.jitter.as_secs_f64() * 1000.0,
+            self.min.as_secs_f64() * 1000.0,
+            self.max.as_secs_f64() * 1000.0
+        )?;
+        write!(
+            f,
+            "  Chirps: {} found, {} missed",
+            self.measurements.len(),
+            self.missed
+        )
+    }
+}
+
+/// Engine state a measurement plays and stamps its chirps with
+pub(crate) struct LatencyProbe {
+    pub tone_sender: Sender<TestTone>,
+    pub frames_processed: Arc<AtomicUsize>,
+    pub world_sample_rate: u32,
+    pub device_sample_rate: u32,
+}
+
+/// Frames faded in and out at each end of a chirp of `total_frames`
+pub(crate) fn chirp_fade_frames(total_frames: usize) -> usize {
+    ((total_frames as f32 * CHIRP_FADE) as usize).max(1)
+}
+
+/// The chirp as played by the render thread, at `sample_rate`
+pub(crate) fn chirp(duration: Duration, sample_rate: u32) -> Vec<f32> {
+    let total_frames = ((duration.as_secs_f64() * sample_rate as f64) as usize).max(1);
+    let fade_frames = chirp_fade_frames(total_frames);
+    let (start_hz, end_hz) = CHIRP_RANGE_HZ;
+    let mut phase_increment = std::f32::consts::TAU * start_hz / sample_rate as f32;
+    let increment_step =
+        std::f32::consts::TAU * (end_hz - start_hz) / total_frames as f32 / sample_rate as f32;
+    let mut phase = 0.0f32;
+    (0..total_frames)
+        .map(|position| {
+            let remaining = total_frames - position;
+            let envelope = (position.min(remaining) as f32 / fade_frames as f32).min(1.0);
+            let sample = phase.sin() * envelope;
+            phase = (phase + phase_increment) % std::f32::consts::TAU;
+            phase_increment += increment_step;
+            sample
+        })
+        .collect()
+}
+
+/// Mono capture of the input and the output clock it was captured at
+#[derive(Default)]
+struct Capture {
+    samples: Vec<f32>,
+    /// (input frame, output frame) pairs, one per input callback
+    clock: Vec<(f64, f64)>,
+}
+
+/// Play the chirps, capture them and return the measured latency; blocks the calling
+/// thread for the whole measurement
+pub(crate) fn measure(probe: LatencyProbe, config: &LatencyTestConfig) -> Result<LatencyReport> {
+    if config.repetitions == 0 || config.interval <= config.max_latency + config.chirp_duration {
+        return Err(PetalSonicError::Configuration(format!(
+            "Latency test needs at least one chirp and an interval longer than the maximum \
+             latency plus the chirp, got {} chirps every {:?}",
+            config.repetitions, config.interval
+        )));
+    }
+
+    let host = cpal::default_host();
+    let device = match &config.input_device {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| {
+                PetalSonicError::AudioDevice(format!("Failed to list input devices: {}", e))
+            })?
+            .find(|device| device.name().is_ok_and(|device_name| &device_name == name))
+            .ok_or_else(|| {
+                PetalSonicError::AudioDevice(format!("Input device '{}' not found", name))
+            })?,
+        None => host.default_input_device().ok_or_else(|| {
+            PetalSonicError::AudioDevice("No default input device available".into())
+        })?,
+    };
+    let device_config = device.default_input_config().map_err(|e| {
+        PetalSonicError::AudioDevice(format!("Failed to get input device config: {}", e))
+    })?;
+    let input_sample_rate = device_config.sample_rate().0;
+
+    let total_time = SETTLE_TIME + config.interval * config.repetitions as u32;
+    let capture = Arc::new(Mutex::new(Capture {
+        samples: Vec::with_capacity(
+            (total_time.as_secs_f64() * input_sample_rate as f64) as usize * 2,
+        ),
+        clock: Vec::new(),
+    }));
+    let stream_config: cpal::StreamConfig = device_config.config();
+    let stream = match device_config.sample_format() {
+        cpal::SampleFormat::F32 => {
+            build_input_stream::<f32>(&device, &stream_config, &probe, &capture)?
+        }
+        cpal::SampleFormat::I16 => {
+            build_input_stream::<i16>(&device, &stream_config, &probe, &capture)?
+        }
+        cpal::SampleFormat::U16 => {
+            build_input_stream::<u16>(&device, &stream_config, &probe, &capture)?
+        }
+        _ => {
+            return Err(PetalSonicError::AudioFormat(
+                "Unsupported input sample format".into(),
+            ));
+        }
+    };
+    stream.play().map_err(|e| {
+        PetalSonicError::AudioDevice(format!("Failed to start input stream: {}", e))
+    })?;
+
+    log::info!(
+        "Measuring latency with {} chirps captured from {} Hz input",
+        config.repetitions,
+        input_sample_rate
+    );
+    thread::sleep(SETTLE_TIME);
+    let mut start_frames = Vec::with_capacity(config.repetitions);
+    for _ in 0..config.repetitions {
+        let start_frame = Arc::new(AtomicUsize::new(usize::MAX));
+        let (start_hz, end_hz) = CHIRP_RANGE_HZ;
+        probe
+            .tone_sender
+            .send(TestTone::chirp(
+                start_hz,
+                end_hz,
+                config.chirp_duration,
+                probe.world_sample_rate,
+                start_frame.clone(),
+            ))
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to send chirp: {}", e)))?;
+        start_frames.push(start_frame);
+        thread::sleep(config.interval);
+    }
+    drop(stream);
+
+    let capture = std::mem::take(
+        &mut *capture
+            .lock()
+            .map_err(|_| PetalSonicError::Engine("Latency capture lock poisoned".into()))?,
+    );
+    let Some((offset, slope)) = fit_clock(&capture.clock) else {
+        return Err(PetalSonicError::AudioDevice(
+            "Input device delivered no audio while measuring latency".into(),
+        ));
+    };
+
+    let reference = chirp(config.chirp_duration, input_sample_rate);
+    let max_lag = (config.max_latency.as_secs_f64() * input_sample_rate as f64) as usize;
+    let mut measurements = Vec::with_capacity(start_frames.len());
+    for start_frame in &start_frames {
+        let start_frame = start_frame.load(Ordering::Relaxed);
+        if start_frame == usize::MAX {
+            continue;
+        }
+        // Earliest input frame the chirp can appear at, then search up to the max latency
+        let earliest = ((start_frame as f64 - offset) / slope).max(0.0) as usize;
+        let found = find_chirp(
+            &capture.samples,
+            &reference,
+            earliest,
+            max_lag,
+            config.min_correlation,
+        );
+        if let Some(input_frame) = found {
+            let heard_frame = offset + slope * input_frame as f64;
+            let latency = (heard_frame - start_frame as f64).max(0.0);
+            measurements.push(Duration::from_secs_f64(
+                latency / probe.device_sample_rate as f64,
+            ));
+        }
+    }
+
+    let missed = config.repetitions - measurements.len();
+    if measurements.is_empty() {
+        return Err(PetalSonicError::AudioDevice(
+            "No chirp found in the captured input; check that the input hears the output".into(),
+        ));
+    }
+    let report = LatencyReport::from_measurements(measurements, missed);
+    log::info!("{}", report);
+    Ok(report)
+}
+
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    probe: &LatencyProbe,
+    capture: &Arc<Mutex<Capture>>,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let frames_processed = probe.frames_processed.clone();
+    let device_sample_rate = probe.device_sample_rate as f64;
+    let capture = capture.clone();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], info: &cpal::InputCallbackInfo| {
+                let output_frame = frames_processed.load(Ordering::Relaxed) as f64;
+                let timestamp = info.timestamp();
+                let input_delay = timestamp
+                    .callback
+                    .duration_since(&timestamp.capture)
+                    .unwrap_or_default();
+                let Ok(mut capture) = capture.lock() else {
+                    return;
+                };
+                // The buffer's first frame was captured `input_delay` before this callback
+                let input_frame = capture.samples.len() as f64;
+                capture.clock.push((
+                    input_frame,
+                    output_frame - input_delay.as_secs_f64() * device_sample_rate,
+                ));
+                for frame in data.chunks_exact(channels) {
+                    let sum: f32 = frame.iter().map(|sample| f32::from_sample(*sample)).sum();
+                    capture.samples.push(sum / channels as f32);
+                }
+            },
+            |err| log::error!("Latency test input stream error: {}", err),
+            None,
+        )
+        .map_err(|e| PetalSonicError::AudioDevice(format!("Failed to build input stream: {}", e)))
+}
+
+/// Least-squares line mapping input frames to output frames, as (offset, slope). Fitting
+/// all callbacks smooths out the granularity of the output clock, which only advances
+/// once per output callback.
+fn fit_clock(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let mean_input = points.iter().map(|(input, _)| input).sum::<f64>() / count;
+    let mean_output = points.iter().map(|(_, output)| output).sum::<f64>() / count;
+    let variance: f64 = points
+        .iter()
+        .map(|(input, _)| (input - mean_input).powi(2))
+        .sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let covariance: f64 = points
+        .iter()
+        .map(|(input, output)| (input - mean_input) * (output - mean_output))
+        .sum();
+    let slope = covariance / variance;
+    (slope > 0.0).then_some((mean_output - slope * mean_input, slope))
+}
+
+/// Input frame in `earliest..earliest + max_lag` where `reference` correlates best with
+/// `samples`, if the normalized correlation reaches `min_correlation`
+fn find_chirp(
+    samples: &[f32],
+    reference: &[f32],
+    earliest: usize,
+    max_lag: usize,
+    min_correlation: f32,
+) -> Option<usize> {
+    let len = reference.len();
+    let end = (earliest + max_lag + len).min(samples.len());
+    if earliest + len > end {
+        return None;
+    }
+    let window = &samples[earliest..end];
+    let reference_energy: f32 = reference.iter().map(|r| r * r).sum();
+
+    // Energy of the capture under the reference, updated as it slides
+    let mut window_energy: f32 = window[..len].iter().map(|s| s * s).sum();
+    let mut best: Option<(usize, f32)> = None;
+    for lag in 0..=window.len() - len {
+        if lag > 0 {
+            let (outgoing, incoming) = (window[lag - 1], window[lag + len - 1]);
+            window_energy = (window_energy - outgoing * outgoing + incoming * incoming).max(0.0);
+        }
+        let norm = (reference_energy * window_energy).sqrt();
+        if norm <= f32::EPSILON {
+            continue;
+        }
+        let dot: f32 = window[lag..lag + len]
+            .iter()
+            .zip(reference)
+            .map(|(s, r)| s * r)
+            .sum();
+        let correlation = dot / norm;
+        if best.is_none_or(|(_, best)| correlation > best) {
+            best = Some((lag, correlation));
+        }
+    }
+    best.filter(|(_, correlation)| *correlation >= min_correlation)
+        .map(|(lag, _)| earliest + lag)
+}
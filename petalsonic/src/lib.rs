@@ -61,36 +61,135 @@
 //!
 //! PetalSonic uses a three-layer threading model:
 //!
-//! 1. **Main Thread**: Owns `PetalSonicWorld`, loads audio, sends commands
+//! 1. **Main Thread**: Owns `PetalSonicWorld`, loads audio, sends commands through a bounded
+//!    lock-free queue
 //! 2. **Render Thread**: Processes commands, spatializes audio, generates samples
 //! 3. **Audio Callback**: Lock-free consumption from ring buffer to audio device
 //!
 //! This architecture ensures real-time safety: no allocations or locks in the audio callback path.
 //!
+//! Each world is rendered by one engine at a time; creating a second engine for a world
+//! that already has one fails.
+//!
 //! ## Features
 //!
 //! - Steam Audio integration for high-quality HRTF-based spatialization
 //! - Support for both spatial and non-spatial audio sources
 //! - Real-time safe audio processing
-//! - Automatic resampling to world sample rate
+//! - Automatic resampling to world sample rate, with selectable converter quality
 //! - Loop modes: once, infinite, or counted loops
+//! - Gapless playlists of clips (intro, loop, outro) with sample-accurate switching
+//! - Master volume with optional loudness compensation
+//! - Night mode compressing the output for late-night play
+//! - Mono output, channel swap and balance for players with hearing asymmetries
+//! - TPDF dither with optional noise shaping for 16-bit outputs
+//! - Mute or pause with fades while the application is in the background
+//! - Headphone calibration EQ on the master output
+//! - Shared room reverb with per-source send amounts and environment presets
+//! - Secondary output device for a subset of buses (e.g. voice chat on a headset)
+//! - Bus freeze: render a bus offline into a clip to trade CPU for memory
+//! - Bass and treble tone controls per bus
+//! - A/B renders of the same scene with two HRTFs or quality presets for tuning by ear
+//! - Optional speed-of-sound propagation delay for distant sources
+//! - Per-source alignment delay for syncing sounds to video or animation
+//! - Caption events timed to playback for subtitles and closed captions
+//! - Haptics tracks emitted in sync with their source on the output clock
+//! - Marker events from WAV cue points and programmatic markers
+//! - Loop seam analysis to catch clicky loops before shipping
+//! - Offline onset and beat analysis of assets for rhythm-reactive gameplay
+//! - Seeded randomness for reproducible audio variation
+//! - Sync groups for sample-locked playback of stems
+//! - Tracker module (MOD) music rendered as it plays (`tracker` feature)
+//! - Loopback output latency measurement for clock calibration
 //! - Event-driven architecture for playback notifications
 //! - Performance profiling via timing events
+//! - Optional allocation guard for the real-time paths in tests (`rt-alloc-guard` feature)
 
+pub mod accessibility;
+pub mod assets;
 pub mod audio_data;
+pub mod bus_tone;
+pub mod caption;
+pub mod channel_mix;
+pub mod command_queue;
+pub mod comparison;
 pub mod config;
+pub mod debug_snapshot;
+pub mod diagnostics;
+pub mod distance_delay;
+pub mod dither;
 pub mod engine;
+pub mod envelope;
 pub mod error;
 pub mod events;
+pub mod focus;
+pub mod haptics;
+pub mod latency;
+pub mod logging;
+pub mod loudness;
+pub mod master_volume;
 pub mod math;
+pub mod memory;
 pub mod mixer;
+pub mod network;
+pub mod night_mode;
+pub mod output_eq;
+mod platform;
 pub mod playback;
+pub mod random;
+pub mod rate_limit;
+pub mod reverb;
+pub mod rt_guard;
+pub mod sampler;
+pub mod secondary_output;
+pub mod silence;
+pub mod source_delay;
 pub mod spatial;
+pub mod spatial_info;
+pub mod stems;
+pub mod sync_group;
+pub mod tap;
+#[cfg(feature = "tracker")]
+pub mod tracker;
+pub mod voice;
 pub mod world;
+pub mod zones;
 
+pub use bus_tone::BusTone;
+pub use caption::{Caption, CaptionCue};
+pub use channel_mix::{ChannelLayout, ChannelMixMatrix, ChannelPosition};
+pub use command_queue::CommandQueueStats;
+pub use comparison::{AbRender, SpatialVariant};
 pub use config::{PetalSonicWorldDesc, SourceConfig};
+pub use debug_snapshot::{DebugSnapshot, SourceDebugInfo};
+pub use diagnostics::{DiagnosticsReport, EngineStats, OutputInfo, RenderLoadStats, ResampleStats};
+pub use dither::Dither;
 pub use engine::{AudioFillCallback, PetalSonicEngine};
-pub use error::PetalSonicError;
+pub use envelope::EnvelopeConfig;
+pub use error::{ConfigError, PetalSonicError};
 pub use events::{PetalSonicEvent, RenderTimingEvent};
-pub use playback::{PlayState, PlaybackCommand, PlaybackInfo, PlaybackInstance};
+pub use focus::BackgroundPolicy;
+pub use haptics::HapticsTrack;
+pub use latency::{LatencyReport, LatencyTestConfig};
+pub use logging::{LogPolicy, RenderCounters};
+pub use loudness::{LoudnessMeter, LoudnessReading};
+pub use memory::{MemoryBudget, MemoryBudgetPolicy, MemoryStats};
+pub use network::{NetworkAudioSource, NetworkSourceConfig};
+pub use output_eq::{EqBand, EqFilterType, OutputEq};
+pub use playback::{
+    OutputRouting, PlayState, PlaybackCommand, PlaybackDirection, PlaybackInfo, PlaybackInstance,
+    Playlist, PlaylistSegment,
+};
+pub use random::AudioRng;
+pub use rate_limit::{RateLimit, RateLimitOverflow, RateLimitStats};
+pub use reverb::{Environment, ReverbSettings};
+pub use sampler::{Sampler, SamplerZone};
+pub use secondary_output::{SecondaryOutputDesc, SecondaryOutputStats};
+pub use silence::SilenceDetection;
+pub use spatial_info::SpatialInfo;
+pub use sync_group::{SyncGroup, SyncGroupId};
+#[cfg(feature = "tracker")]
+pub use tracker::TrackerModule;
+pub use voice::{VoiceActivityConfig, VoiceInput};
 pub use world::{PetalSonicAudioListener, PetalSonicAudioSource, PetalSonicWorld, SourceId};
+pub use zones::{AttenuationZone, ZoneId, ZoneShape};
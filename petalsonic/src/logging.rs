@@ -0,0 +1,176 @@
+//! Logging policy of the real-time paths.
+//!
+//! The render thread and the device callback must not spend time formatting log lines, so
+//! their logging is restricted:
+//!
+//! - Per-block and per-command debug lines (mixer, playback, command processing) are only
+//!   compiled in with the `hot-path-logging` cargo feature, meant for debugging builds.
+//! - Warnings and errors of the real-time paths follow the crate-wide [`LogPolicy`].
+//! - Recurring conditions (device underruns, contended locks, processing errors) are
+//!   counted instead of logged; read them with [`render_counters`]. With
+//!   [`LogPolicy::Stats`] the render thread logs a summary of the counters once per second
+//!   while they change.
+//!
+//! ```ignore
+//! petalsonic::logging::set_log_policy(LogPolicy::Stats);
+//! let counters = petalsonic::logging::render_counters();
+//! ```
+//!
+//! Logging outside the real-time paths (loading, engine setup) is not affected.
+
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// What the real-time paths log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogPolicy {
+    /// Nothing; conditions are still counted
+    Off,
+    /// Warnings and errors
+    #[default]
+    Errors,
+    /// Warnings and errors, plus a periodic summary of the [`RenderCounters`]
+    Stats,
+}
+
+impl LogPolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            2 => Self::Stats,
+            _ => Self::Errors,
+        }
+    }
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(LogPolicy::Errors as u8);
+
+/// Set the logging policy of the real-time paths for all engines
+pub fn set_log_policy(policy: LogPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Get the logging policy of the real-time paths
+pub fn log_policy() -> LogPolicy {
+    LogPolicy::from_u8(POLICY.load(Ordering::Relaxed))
+}
+
+/// Conditions counted on the real-time paths since the process started, for all engines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderCounters {
+    /// Device callbacks that ran out of rendered audio
+    pub underruns: u64,
+    /// Blocks where the render thread skipped work because a lock was held elsewhere
+    pub lock_contentions: u64,
+    /// Spatial processing, resampling and event delivery failures
+    pub render_errors: u64,
+}
+
+static UNDERRUNS: AtomicU64 = AtomicU64::new(0);
+static LOCK_CONTENTIONS: AtomicU64 = AtomicU64::new(0);
+static RENDER_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Get the counters of the real-time paths
+pub fn render_counters() -> RenderCounters {
+    RenderCounters {
+        underruns: UNDERRUNS.load(Ordering::Relaxed),
+        lock_contentions: LOCK_CONTENTIONS.load(Ordering::Relaxed),
+        render_errors: RENDER_ERRORS.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn count_underrun() {
+    UNDERRUNS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_lock_contention() {
+    LOCK_CONTENTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_render_error() {
+    RENDER_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Whether warnings and errors of the real-time paths are logged
+pub(crate) fn errors_enabled() -> bool {
+    log_policy() != LogPolicy::Off
+}
+
+/// Interval of the counter summary logged with [`LogPolicy::Stats`]
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Logs the counter summary for [`LogPolicy::Stats`] from the render thread
+pub(crate) struct StatsLogger {
+    last_log: Instant,
+    last_counters: RenderCounters,
+}
+
+impl StatsLogger {
+    pub fn new() -> Self {
+        Self {
+            last_log: Instant::now(),
+            last_counters: render_counters(),
+        }
+    }
+
+    /// Log the counters if the interval elapsed and they changed since the last summary
+    pub fn tick(&mut self) {
+        if log_policy() != LogPolicy::Stats || self.last_log.elapsed() < STATS_INTERVAL {
+            return;
+        }
+        self.last_log = Instant::now();
+
+        let counters = render_counters();
+        if counters != self.last_counters {
+            log::info!(
+                "Render stats: {} underruns (+{}), {} lock contentions (+{}), {} errors (+{})",
+                counters.underruns,
+                counters.underruns - self.last_counters.underruns,
+                counters.lock_contentions,
+                counters.lock_contentions - self.last_counters.lock_contentions,
+                counters.render_errors,
+                counters.render_errors - self.last_counters.render_errors
+            );
+            self.last_counters = counters;
+        }
+    }
+}
+
+/// Debug log line of a real-time path, compiled in only with the `hot-path-logging`
+/// feature
+macro_rules! rt_debug {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "hot-path-logging") {
+            log::debug!($($arg)+);
+        }
+    };
+}
+
+/// Info log line of a real-time path, compiled in only with the `hot-path-logging` feature
+macro_rules! rt_info {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "hot-path-logging") {
+            log::info!($($arg)+);
+        }
+    };
+}
+
+/// Warning of a real-time path, logged unless the [`LogPolicy`] is `Off`
+macro_rules! rt_warn {
+    ($($arg:tt)+) => {
+        if $crate::logging::errors_enabled() {
+            log::warn!($($arg)+);
+        }
+    };
+}
+
+/// Error of a real-time path, logged unless the [`LogPolicy`] is `Off`
+macro_rules! rt_error {
+    ($($arg:tt)+) => {
+        if $crate::logging::errors_enabled() {
+            log::error!($($arg)+);
+        }
+    };
+}
+
+pub(crate) use {rt_debug, rt_error, rt_info, rt_warn};
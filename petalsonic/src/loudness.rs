@@ -0,0 +1,361 @@
+//! Loudness measurement following ITU-R BS.1770 / EBU R128.
+//!
+//! [`LoudnessMeter`] computes momentary (400 ms), short-term (3 s) and gated integrated
+//! loudness in LUFS from interleaved audio. The engine can run a meter on the master mix
+//! (see [`PetalSonicEngine::set_loudness_metering`](crate::PetalSonicEngine::set_loudness_metering)),
+//! but the meter can also be used directly on offline buffers.
+//!
+//! All memory is allocated up front, so processing is real-time safe. Integrated loudness
+//! is gated using a fixed-resolution histogram (0.1 LU bins) instead of storing every block.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Duration of one measurement hop in milliseconds (blocks overlap by 75%)
+const HOP_MS: usize = 100;
+/// Number of hops in a momentary block (400 ms)
+const MOMENTARY_HOPS: usize = 4;
+/// Number of hops in a short-term block (3 s)
+const SHORT_TERM_HOPS: usize = 30;
+/// Absolute gating threshold for integrated loudness
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Relative gating threshold below the ungated loudness
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Upper bound of the integrated loudness histogram
+const HISTOGRAM_MAX_LUFS: f64 = 10.0;
+/// Histogram bins per LU
+const HISTOGRAM_BINS_PER_LU: f64 = 10.0;
+
+/// A snapshot of loudness measurements, in LUFS.
+///
+/// Values are `f32::NEG_INFINITY` until enough audio has been measured (or for silence).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessReading {
+    /// Loudness of the last 400 ms
+    pub momentary_lufs: f32,
+    /// Loudness of the last 3 s
+    pub short_term_lufs: f32,
+    /// Gated loudness since the meter was started or reset
+    pub integrated_lufs: f32,
+}
+
+impl Default for LoudnessReading {
+    fn default() -> Self {
+        Self {
+            momentary_lufs: f32::NEG_INFINITY,
+            short_term_lufs: f32::NEG_INFINITY,
+            integrated_lufs: f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// Second-order IIR section (transposed direct form II)
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    /// Section with the given coefficients, normalized so that `a0` is 1
+    pub fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            ..Default::default()
+        }
+    }
+
+    /// Take the coefficients of `other`, keeping the filter state
+    pub fn set_coefficients(&mut self, other: &Biquad) {
+        self.b0 = other.b0;
+        self.b1 = other.b1;
+        self.b2 = other.b2;
+        self.a1 = other.a1;
+        self.a2 = other.a2;
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// Build the two K-weighting filter stages (high shelf + RLB high-pass) for a sample rate
+fn k_weighting_filters(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    // Stage 1: high shelf modelling the acoustic effect of the head
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b0: (vh + vb * k / q + k * k) / a0,
+        b1: 2.0 * (k * k - vh) / a0,
+        b2: (vh - vb * k / q + k * k) / a0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        ..Default::default()
+    };
+
+    // Stage 2: RLB high-pass
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        ..Default::default()
+    };
+
+    [shelf, high_pass]
+}
+
+/// Convert a mean-square energy to LUFS
+fn energy_to_lufs(energy: f64) -> f64 {
+    if energy <= 0.0 {
+        f64::NEG_INFINITY
+    } else {
+        -0.691 + 10.0 * energy.log10()
+    }
+}
+
+/// ITU-R BS.1770 loudness meter for interleaved audio.
+///
+/// All channels are weighted equally, which matches BS.1770 for mono and stereo.
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+
+    hop_frames: usize,
+    hop_position: usize,
+    hop_energy: f64,
+
+    /// Mean-square energy of the most recent hops (ring buffer)
+    hops: [f64; SHORT_TERM_HOPS],
+    hop_index: usize,
+    hops_measured: usize,
+
+    /// Per-bin block count and energy sum for gated integrated loudness
+    histogram_counts: Vec<u64>,
+    histogram_energy: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    /// Create a meter for audio at `sample_rate` with `channels` interleaved channels
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        let bins =
+            ((HISTOGRAM_MAX_LUFS - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU).ceil() as usize;
+
+        Self {
+            channels,
+            filters: vec![k_weighting_filters(sample_rate); channels],
+            hop_frames: (sample_rate as usize * HOP_MS / 1000).max(1),
+            hop_position: 0,
+            hop_energy: 0.0,
+            hops: [0.0; SHORT_TERM_HOPS],
+            hop_index: 0,
+            hops_measured: 0,
+            histogram_counts: vec![0; bins],
+            histogram_energy: vec![0.0; bins],
+        }
+    }
+
+    /// Clear all measurements and filter state
+    pub fn reset(&mut self) {
+        for stages in self.filters.iter_mut() {
+            for stage in stages.iter_mut() {
+                stage.reset();
+            }
+        }
+        self.hop_position = 0;
+        self.hop_energy = 0.0;
+        self.hops = [0.0; SHORT_TERM_HOPS];
+        self.hop_index = 0;
+        self.hops_measured = 0;
+        self.histogram_counts.fill(0);
+        self.histogram_energy.fill(0.0);
+    }
+
+    /// Feed interleaved samples to the meter
+    pub fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (sample, stages) in frame.iter().zip(self.filters.iter_mut()) {
+                let shelved = stages[0].process(*sample as f64);
+                let weighted = stages[1].process(shelved);
+                self.hop_energy += weighted * weighted;
+            }
+
+            self.hop_position += 1;
+            if self.hop_position == self.hop_frames {
+                self.finish_hop();
+            }
+        }
+    }
+
+    /// Store the energy of a completed 100 ms hop and update the integrated histogram
+    fn finish_hop(&mut self) {
+        self.hops[self.hop_index] = self.hop_energy / self.hop_frames as f64;
+        self.hop_index = (self.hop_index + 1) % SHORT_TERM_HOPS;
+        self.hops_measured = self.hops_measured.saturating_add(1);
+        self.hop_position = 0;
+        self.hop_energy = 0.0;
+
+        // Each hop completes a new (overlapping) 400 ms gating block
+        if self.hops_measured >= MOMENTARY_HOPS {
+            let block_energy = self.recent_energy(MOMENTARY_HOPS);
+            let block_lufs = energy_to_lufs(block_energy);
+            if block_lufs >= ABSOLUTE_GATE_LUFS {
+                let bin = Self::histogram_bin(block_lufs).min(self.histogram_counts.len() - 1);
+                self.histogram_counts[bin] += 1;
+                self.histogram_energy[bin] += block_energy;
+            }
+        }
+    }
+
+    /// Mean energy of the last `count` hops
+    fn recent_energy(&self, count: usize) -> f64 {
+        let sum: f64 = (1..=count)
+            .map(|i| self.hops[(self.hop_index + SHORT_TERM_HOPS - i) % SHORT_TERM_HOPS])
+            .sum();
+        sum / count as f64
+    }
+
+    fn histogram_bin(lufs: f64) -> usize {
+        ((lufs - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU).max(0.0) as usize
+    }
+
+    /// Loudness of the last 400 ms
+    pub fn momentary(&self) -> f64 {
+        if self.hops_measured < MOMENTARY_HOPS {
+            return f64::NEG_INFINITY;
+        }
+        energy_to_lufs(self.recent_energy(MOMENTARY_HOPS))
+    }
+
+    /// Loudness of the last 3 s
+    pub fn short_term(&self) -> f64 {
+        if self.hops_measured < SHORT_TERM_HOPS {
+            return f64::NEG_INFINITY;
+        }
+        energy_to_lufs(self.recent_energy(SHORT_TERM_HOPS))
+    }
+
+    /// Gated integrated loudness since creation or the last reset
+    pub fn integrated(&self) -> f64 {
+        let (count, energy) = self.gated_sum(0);
+        if count == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        let relative_gate = energy_to_lufs(energy / count as f64) + RELATIVE_GATE_LU;
+        let first_bin = Self::histogram_bin(relative_gate);
+        let (count, energy) = self.gated_sum(first_bin);
+        if count == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        energy_to_lufs(energy / count as f64)
+    }
+
+    /// Sum block counts and energies of all histogram bins from `first_bin` upwards
+    fn gated_sum(&self, first_bin: usize) -> (u64, f64) {
+        self.histogram_counts
+            .iter()
+            .zip(self.histogram_energy.iter())
+            .skip(first_bin)
+            .fold((0, 0.0), |(count, energy), (c, e)| (count + c, energy + e))
+    }
+
+    /// Current momentary, short-term and integrated loudness
+    pub fn reading(&self) -> LoudnessReading {
+        LoudnessReading {
+            momentary_lufs: self.momentary() as f32,
+            short_term_lufs: self.short_term() as f32,
+            integrated_lufs: self.integrated() as f32,
+        }
+    }
+}
+
+/// Loudness state shared between the engine (main thread) and the render thread
+pub(crate) struct SharedLoudness {
+    enabled: AtomicBool,
+    reset_requested: AtomicBool,
+    momentary: AtomicU32,
+    short_term: AtomicU32,
+    integrated: AtomicU32,
+}
+
+impl SharedLoudness {
+    pub fn new(enabled: bool) -> Self {
+        let silent = f32::NEG_INFINITY.to_bits();
+        Self {
+            enabled: AtomicBool::new(enabled),
+            reset_requested: AtomicBool::new(false),
+            momentary: AtomicU32::new(silent),
+            short_term: AtomicU32::new(silent),
+            integrated: AtomicU32::new(silent),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Ask the render thread to reset its meter on the next block
+    pub fn request_reset(&self) {
+        self.reset_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Called by the render thread to meter a block of the master mix and publish the result
+    pub fn process(&self, meter: &mut LoudnessMeter, samples: &[f32]) {
+        if self.reset_requested.swap(false, Ordering::Relaxed) {
+            meter.reset();
+        }
+
+        meter.process(samples);
+
+        let reading = meter.reading();
+        self.momentary
+            .store(reading.momentary_lufs.to_bits(), Ordering::Relaxed);
+        self.short_term
+            .store(reading.short_term_lufs.to_bits(), Ordering::Relaxed);
+        self.integrated
+            .store(reading.integrated_lufs.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn reading(&self) -> LoudnessReading {
+        LoudnessReading {
+            momentary_lufs: f32::from_bits(self.momentary.load(Ordering::Relaxed)),
+            short_term_lufs: f32::from_bits(self.short_term.load(Ordering::Relaxed)),
+            integrated_lufs: f32::from_bits(self.integrated.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Master volume with optional loudness compensation.
+//!
+//! The engine scales its device output by a master volume (see
+//! [`PetalSonicEngine::set_master_volume`](crate::PetalSonicEngine::set_master_volume)).
+//! The ear loses sensitivity to low (and, less so, high) frequencies at low listening
+//! levels, so turning the volume down makes the mix sound thin. With loudness compensation
+//! enabled, lowering the volume also boosts the low and high end with gentle shelves, in
+//! proportion to the attenuation:
+//!
+//! ```ignore
+//! engine.set_loudness_compensation(true);
+//! engine.set_master_volume(0.25); // -12 dB, with +3 dB below 100 Hz and +1.2 dB above 10 kHz
+//! ```
+//!
+//! At full volume (1.0) the compensation is neutral. Volume changes are ramped over one
+//! block. Like the output EQ, the master volume applies to the device output only; loudness
+//! metering, the output tap and stems see the mix before it.
+
+use crate::loudness::Biquad;
+use crate::night_mode::NightMode;
+use crate::output_eq::EqBand;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Corner frequency of the low compensation shelf
+const LOW_SHELF_HZ: f32 = 100.0;
+/// Corner frequency of the high compensation shelf
+const HIGH_SHELF_HZ: f32 = 10_000.0;
+/// Low shelf boost per dB of attenuation
+const LOW_BOOST_PER_DB: f32 = 0.25;
+/// High shelf boost per dB of attenuation
+const HIGH_BOOST_PER_DB: f32 = 0.1;
+/// Largest low shelf boost in dB
+const MAX_LOW_BOOST_DB: f32 = 10.0;
+/// Largest high shelf boost in dB
+const MAX_HIGH_BOOST_DB: f32 = 4.0;
+/// Attenuation beyond which the compensation stops growing (the output is near silent)
+const MAX_COMPENSATED_DB: f32 = 60.0;
+/// Shelf slope (Butterworth)
+const SHELF_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Master volume settings shared between the engine (main thread) and the render thread
+pub(crate) struct SharedMasterVolume {
+    volume: AtomicU32,
+    compensation: AtomicBool,
+    night_mode: AtomicBool,
+}
+
+impl SharedMasterVolume {
+    pub fn new(volume: f32, compensation: bool, night_mode: bool) -> Self {
+        Self {
+            volume: AtomicU32::new(volume.max(0.0).to_bits()),
+            compensation: AtomicBool::new(compensation),
+            night_mode: AtomicBool::new(night_mode),
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.volume
+            .store(volume.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn compensation(&self) -> bool {
+        self.compensation.load(Ordering::Relaxed)
+    }
+
+    pub fn set_compensation(&self, enabled: bool) {
+        self.compensation.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn night_mode(&self) -> bool {
+        self.night_mode.load(Ordering::Relaxed)
+    }
+
+    pub fn set_night_mode(&self, enabled: bool) {
+        self.night_mode.store(enabled, Ordering::Relaxed);
+    }
+}
+
+/// Render-side night mode, master gain and compensation shelves for interleaved audio
+pub(crate) struct MasterVolume {
+    sample_rate: u32,
+    gain: f32,
+    /// Volume and compensation state the shelf coefficients were computed for
+    shelf_volume: f32,
+    compensating: bool,
+    /// Low and high shelf of each channel
+    shelves: Vec<[Biquad; 2]>,
+    /// Compressor and limiter of night mode (see [`crate::night_mode`])
+    night_mode: NightMode,
+}
+
+impl MasterVolume {
+    pub fn new(sample_rate: u32, channels: u16, shared: &SharedMasterVolume) -> Self {
+        let mut master = Self {
+            sample_rate,
+            gain: shared.volume(),
+            shelf_volume: f32::NAN,
+            compensating: false,
+            shelves: vec![[Biquad::default(); 2]; channels as usize],
+            night_mode: NightMode::new(sample_rate, channels, shared.night_mode()),
+        };
+        master.update_shelves(shared.volume(), shared.compensation());
+        master
+    }
+
+    /// Recompute the shelves when the volume or the compensation toggle changed
+    fn update_shelves(&mut self, volume: f32, compensation: bool) {
+        let compensating = compensation && volume < 1.0;
+        if compensating == self.compensating && (!compensating || volume == self.shelf_volume) {
+            return;
+        }
+        self.compensating = compensating;
+        self.shelf_volume = volume;
+        if !compensating {
+            return;
+        }
+
+        let attenuation_db =
+            (-20.0 * volume.max(f32::MIN_POSITIVE).log10()).clamp(0.0, MAX_COMPENSATED_DB);
+        let low = EqBand::low_shelf(
+            LOW_SHELF_HZ,
+            (attenuation_db * LOW_BOOST_PER_DB).min(MAX_LOW_BOOST_DB),
+            SHELF_Q,
+        )
+        .biquad(self.sample_rate);
+        let high = EqBand::high_shelf(
+            HIGH_SHELF_HZ,
+            (attenuation_db * HIGH_BOOST_PER_DB).min(MAX_HIGH_BOOST_DB),
+            SHELF_Q,
+        )
+        .biquad(self.sample_rate);
+        for [low_shelf, high_shelf] in self.shelves.iter_mut() {
+            low_shelf.set_coefficients(&low);
+            high_shelf.set_coefficients(&high);
+        }
+    }
+
+    /// Apply night mode and the master volume to interleaved samples in place
+    pub fn process(&mut self, shared: &SharedMasterVolume, samples: &mut [f32]) {
+        self.night_mode.process(shared.night_mode(), samples);
+
+        let target = shared.volume();
+        self.update_shelves(target, shared.compensation());
+        if !self.compensating && target == 1.0 && self.gain == 1.0 {
+            return;
+        }
+
+        let channels = self.shelves.len();
+        let frames = samples.len() / channels;
+        let gain_step = (target - self.gain) / frames.max(1) as f32;
+        for frame in samples.chunks_exact_mut(channels) {
+            self.gain += gain_step;
+            for (sample, [low_shelf, high_shelf]) in frame.iter_mut().zip(self.shelves.iter_mut()) {
+                let mut value = *sample;
+                if self.compensating {
+                    value = high_shelf.process(low_shelf.process(value as f64)) as f32;
+                }
+                *sample = value * self.gain;
+            }
+        }
+        self.gain = target;
+    }
+}
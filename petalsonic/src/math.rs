@@ -1,7 +1,31 @@
 //! Math types for PetalSonic
+//!
+//! Positions and rotations use [glam](https://docs.rs/glam) types, re-exported here as
+//! [`Vec3`] and [`Quat`], and are combined into a [`Pose`].
+//!
+//! # Coordinate convention
+//!
+//! PetalSonic works in a right-handed, Y-up coordinate system: +X points right, +Y up and
+//! -Z forward (the listener looks down -Z). This is the convention of Steam Audio and
+//! OpenGL. Host engines often use a different one; [`CoordinateConvention`] converts
+//! positions, rotations and poses between a host convention and PetalSonic's:
+//!
+//! ```ignore
+//! // Unreal: X forward, Y right, Z up (left-handed)
+//! let pose = CoordinateConvention::LeftHandedZUp.pose_to_native(unreal_pose);
+//! world.set_listener_pose(pose);
+//! ```
+//!
+//! # Interop
+//!
+//! With the `mint` feature, [`Vec3`], [`Quat`] and [`Pose`] convert to and from
+//! [mint](https://docs.rs/mint) types, which most math libraries support. With the
+//! `nalgebra` feature, they convert to and from nalgebra vectors, unit quaternions and
+//! isometries.
 
-pub use glam::{Quat, Vec3};
+pub use glam::{Mat3, Mat4, Quat, Vec3};
 
+/// Position and orientation of a listener or source
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Pose {
     pub position: Vec3,
@@ -34,6 +58,36 @@ impl Pose {
         }
     }
 
+    /// Pose at `position` facing `target`, with its up axis as close to `up` as possible
+    ///
+    /// If `target` is at `position` the pose keeps the identity rotation.
+    pub fn looking_at(position: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self {
+            position,
+            rotation: look_rotation(target - position, up).unwrap_or(Quat::IDENTITY),
+        }
+    }
+
+    /// Pose at `position` facing along `direction`, with its up axis as close to `up` as
+    /// possible
+    pub fn looking_to(position: Vec3, direction: Vec3, up: Vec3) -> Self {
+        Self {
+            position,
+            rotation: look_rotation(direction, up).unwrap_or(Quat::IDENTITY),
+        }
+    }
+
+    /// Build a pose from a rigid transform matrix (scale is discarded)
+    pub fn from_mat4(matrix: &Mat4) -> Self {
+        let (_, rotation, position) = matrix.to_scale_rotation_translation();
+        Self { position, rotation }
+    }
+
+    /// Rigid transform matrix of this pose
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position)
+    }
+
     pub fn forward(&self) -> Vec3 {
         self.rotation * (-Vec3::Z)
     }
@@ -50,9 +104,46 @@ impl Pose {
         self.position.distance(other.position)
     }
 
-    pub fn look_at(&mut self, target: Vec3, _up: Option<Vec3>) {
+    /// Turn to face `target`. With `up`, the pose is kept upright relative to it;
+    /// otherwise it is rotated along the shortest arc.
+    pub fn look_at(&mut self, target: Vec3, up: Option<Vec3>) {
         let forward = (target - self.position).normalize();
-        self.rotation = Quat::from_rotation_arc(Vec3::Z, -forward);
+        self.rotation = up
+            .and_then(|up| look_rotation(forward, up))
+            .unwrap_or_else(|| Quat::from_rotation_arc(Vec3::Z, -forward));
+    }
+
+    /// Combine two poses: `other` expressed relative to this pose, e.g. a source attached
+    /// to a moving object, is moved into this pose's parent space
+    pub fn mul_pose(&self, other: &Self) -> Self {
+        Self {
+            position: self.transform_point(other.position),
+            rotation: (self.rotation * other.rotation).normalize(),
+        }
+    }
+
+    /// Pose undoing this one, so that `pose.mul_pose(&pose.inverse())` is the identity
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        Self {
+            position: rotation * -self.position,
+            rotation,
+        }
+    }
+
+    /// Transform a point from this pose's local space to its parent space
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation * point + self.position
+    }
+
+    /// Transform a direction from this pose's local space to its parent space
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation * vector
+    }
+
+    /// Transform a point from the parent space into this pose's local space
+    pub fn inverse_transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * (point - self.position)
     }
 }
 
@@ -61,3 +152,171 @@ impl Default for Pose {
         Self::identity()
     }
 }
+
+impl std::ops::Mul for Pose {
+    type Output = Pose;
+
+    fn mul(self, rhs: Pose) -> Pose {
+        self.mul_pose(&rhs)
+    }
+}
+
+/// Rotation whose forward axis (-Z) points along `forward` and whose up axis is as close
+/// to `up` as possible; `None` if `forward` is zero or parallel to `up`
+fn look_rotation(forward: Vec3, up: Vec3) -> Option<Quat> {
+    let forward = forward.try_normalize()?;
+    let right = forward.cross(up).try_normalize()?;
+    let up = right.cross(forward);
+    Some(Quat::from_mat3(&Mat3::from_cols(right, up, -forward)))
+}
+
+/// Axis convention of a host engine's coordinate system
+///
+/// Variants name the handedness and up axis; the other axes follow the engines listed.
+/// PetalSonic's own ("native") convention is [`CoordinateConvention::RightHandedYUp`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CoordinateConvention {
+    /// +X right, +Y up, -Z forward (PetalSonic, Steam Audio, OpenGL, Godot)
+    #[default]
+    RightHandedYUp,
+    /// +X right, +Y up, +Z forward (Unity)
+    LeftHandedYUp,
+    /// +X right, +Y forward, +Z up (Blender)
+    RightHandedZUp,
+    /// +X forward, +Y right, +Z up (Unreal)
+    LeftHandedZUp,
+}
+
+impl CoordinateConvention {
+    /// Matrix mapping this convention's axes to native axes (columns are the native
+    /// directions of this convention's X, Y and Z axes)
+    pub fn to_native_matrix(self) -> Mat3 {
+        match self {
+            Self::RightHandedYUp => Mat3::IDENTITY,
+            Self::LeftHandedYUp => Mat3::from_cols(Vec3::X, Vec3::Y, -Vec3::Z),
+            Self::RightHandedZUp => Mat3::from_cols(Vec3::X, -Vec3::Z, Vec3::Y),
+            Self::LeftHandedZUp => Mat3::from_cols(-Vec3::Z, Vec3::X, Vec3::Y),
+        }
+    }
+
+    /// True if this convention has a different handedness than the native one
+    pub fn flips_handedness(self) -> bool {
+        matches!(self, Self::LeftHandedYUp | Self::LeftHandedZUp)
+    }
+
+    /// Convert a position or direction from this convention to the native one
+    pub fn vec_to_native(self, vector: Vec3) -> Vec3 {
+        self.to_native_matrix() * vector
+    }
+
+    /// Convert a position or direction from the native convention to this one
+    pub fn vec_from_native(self, vector: Vec3) -> Vec3 {
+        self.to_native_matrix().transpose() * vector
+    }
+
+    /// Convert a rotation from this convention to the native one
+    pub fn quat_to_native(self, rotation: Quat) -> Quat {
+        let basis = self.to_native_matrix();
+        Quat::from_mat3(&(basis * Mat3::from_quat(rotation) * basis.transpose())).normalize()
+    }
+
+    /// Convert a rotation from the native convention to this one
+    pub fn quat_from_native(self, rotation: Quat) -> Quat {
+        let basis = self.to_native_matrix();
+        Quat::from_mat3(&(basis.transpose() * Mat3::from_quat(rotation) * basis)).normalize()
+    }
+
+    /// Convert a pose from this convention to the native one
+    ///
+    /// The pose's rotation is converted as a change of basis: a host object facing the
+    /// host's forward axis faces native forward (-Z) afterwards.
+    pub fn pose_to_native(self, pose: Pose) -> Pose {
+        Pose {
+            position: self.vec_to_native(pose.position),
+            rotation: self.quat_to_native(pose.rotation),
+        }
+    }
+
+    /// Convert a pose from the native convention to this one
+    pub fn pose_from_native(self, pose: Pose) -> Pose {
+        Pose {
+            position: self.vec_from_native(pose.position),
+            rotation: self.quat_from_native(pose.rotation),
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+mod mint_conversions {
+    use super::{Pose, Quat, Vec3};
+
+    impl Pose {
+        /// Build a pose from mint position and rotation types
+        pub fn from_mint(
+            position: impl Into<mint::Vector3<f32>>,
+            rotation: impl Into<mint::Quaternion<f32>>,
+        ) -> Self {
+            Self {
+                position: Vec3::from(position.into()),
+                rotation: Quat::from(rotation.into()),
+            }
+        }
+
+        /// Position and rotation of this pose as mint types
+        pub fn to_mint(&self) -> (mint::Vector3<f32>, mint::Quaternion<f32>) {
+            (self.position.into(), self.rotation.into())
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_conversions {
+    use super::{Pose, Quat, Vec3};
+
+    /// Convert a nalgebra vector to a [`Vec3`]
+    pub fn vec3_from_nalgebra(vector: &nalgebra::Vector3<f32>) -> Vec3 {
+        Vec3::new(vector.x, vector.y, vector.z)
+    }
+
+    /// Convert a [`Vec3`] to a nalgebra vector
+    pub fn vec3_to_nalgebra(vector: Vec3) -> nalgebra::Vector3<f32> {
+        nalgebra::Vector3::new(vector.x, vector.y, vector.z)
+    }
+
+    /// Convert a nalgebra unit quaternion to a [`Quat`]
+    pub fn quat_from_nalgebra(rotation: &nalgebra::UnitQuaternion<f32>) -> Quat {
+        let q = rotation.quaternion();
+        Quat::from_xyzw(q.i, q.j, q.k, q.w)
+    }
+
+    /// Convert a [`Quat`] to a nalgebra unit quaternion
+    pub fn quat_to_nalgebra(rotation: Quat) -> nalgebra::UnitQuaternion<f32> {
+        nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(
+            rotation.w, rotation.x, rotation.y, rotation.z,
+        ))
+    }
+
+    impl From<nalgebra::Isometry3<f32>> for Pose {
+        fn from(isometry: nalgebra::Isometry3<f32>) -> Self {
+            Self {
+                position: vec3_from_nalgebra(&isometry.translation.vector),
+                rotation: quat_from_nalgebra(&isometry.rotation),
+            }
+        }
+    }
+
+    impl From<Pose> for nalgebra::Isometry3<f32> {
+        fn from(pose: Pose) -> Self {
+            nalgebra::Isometry3::from_parts(
+                nalgebra::Translation3::from(vec3_to_nalgebra(pose.position)),
+                quat_to_nalgebra(pose.rotation),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+pub use nalgebra_conversions::{
+    quat_from_nalgebra, quat_to_nalgebra, vec3_from_nalgebra, vec3_to_nalgebra,
+};
@@ -0,0 +1,152 @@
+//! Memory usage of the audio data held by a world.
+//!
+//! [`PetalSonicWorld::memory_stats`](crate::PetalSonicWorld::memory_stats) reports the bytes
+//! of sample data the world keeps alive, per source and in total. Sources registered with
+//! the same data share its memory, which is counted once in the total.
+//!
+//! A [`MemoryBudget`] set in
+//! [`PetalSonicWorldDesc::memory_budget`](crate::PetalSonicWorldDesc::memory_budget) caps
+//! that total. Registering audio that would exceed it either fails or evicts the least
+//! recently used sources first:
+//!
+//! ```ignore
+//! let world = PetalSonicWorld::new(PetalSonicWorldDesc {
+//!     memory_budget: Some(MemoryBudget::evicting(256 * 1024 * 1024)),
+//!     ..Default::default()
+//! })?;
+//! // later, e.g. once per frame:
+//! for source_id in world.take_evicted_sources() {
+//!     // forget the handle, reload on demand
+//! }
+//! ```
+//!
+//! Evicted sources are removed from the world as by
+//! [`PetalSonicWorld::remove_audio_data`](crate::PetalSonicWorld::remove_audio_data).
+//! Sources that are playing (as of the last block the engine rendered) are skipped: their
+//! playback holds its own reference to the data, so evicting them would free nothing. Live
+//! sources (voice, network) hold no clip data and are never evicted either.
+
+use crate::audio_data::ResampleCacheStats;
+use crate::world::SourceId;
+use std::collections::{HashMap, HashSet};
+
+/// What registering audio does when it would exceed the memory budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryBudgetPolicy {
+    /// Fail the registration
+    #[default]
+    Reject,
+    /// Remove the least recently registered or played sources until the new data fits
+    /// (fails if it cannot fit even then). Live sources and sources that are still
+    /// playing are never removed.
+    EvictLeastRecentlyUsed,
+}
+
+/// Cap on the sample data a world keeps alive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryBudget {
+    /// Maximum total bytes of sample data
+    pub max_bytes: usize,
+    pub policy: MemoryBudgetPolicy,
+}
+
+impl MemoryBudget {
+    /// Budget rejecting registrations beyond `max_bytes`
+    pub fn rejecting(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            policy: MemoryBudgetPolicy::Reject,
+        }
+    }
+
+    /// Budget evicting least recently used sources beyond `max_bytes`
+    pub fn evicting(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            policy: MemoryBudgetPolicy::EvictLeastRecentlyUsed,
+        }
+    }
+}
+
+/// Snapshot of a world's memory usage
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStats {
+    /// Bytes of sample data of each registered source (shared data is counted for each
+    /// source using it)
+    pub source_bytes: HashMap<SourceId, usize>,
+    /// Bytes of sample data held by the world, counting shared data once
+    pub total_bytes: usize,
+    /// Bytes of data the world resampled to its sample rate. These are copies: the
+    /// original data stays alive as long as the caller keeps it.
+    pub resampled_bytes: usize,
+    /// Configured budget, if any
+    pub budget_bytes: Option<usize>,
+    /// Number of sources evicted to stay within the budget since the world was created
+    pub evicted_sources: u64,
+    /// Occupancy of the process-wide resample cache, which shares resampled data between
+    /// worlds and registrations
+    pub resample_cache: ResampleCacheStats,
+}
+
+/// Usage bookkeeping of the world's sources, for statistics and eviction
+#[derive(Debug, Default)]
+pub(crate) struct MemoryTracker {
+    /// Sources whose stored data is a resampled copy
+    resampled: HashSet<SourceId>,
+    /// Logical time each source was last registered or played at
+    last_used: HashMap<SourceId, u64>,
+    clock: u64,
+    /// Evicted sources not yet taken by the host
+    evicted: Vec<SourceId>,
+    evicted_total: u64,
+}
+
+impl MemoryTracker {
+    /// Record a newly registered source
+    pub fn register(&mut self, source_id: SourceId, resampled: bool) {
+        if resampled {
+            self.resampled.insert(source_id);
+        }
+        self.touch(source_id);
+    }
+
+    /// Mark a source as used now
+    pub fn touch(&mut self, source_id: SourceId) {
+        self.clock += 1;
+        self.last_used.insert(source_id, self.clock);
+    }
+
+    pub fn remove(&mut self, source_id: SourceId) {
+        self.resampled.remove(&source_id);
+        self.last_used.remove(&source_id);
+    }
+
+    pub fn is_resampled(&self, source_id: SourceId) -> bool {
+        self.resampled.contains(&source_id)
+    }
+
+    /// Sources ordered from least to most recently used
+    pub fn least_recently_used(&self) -> Vec<SourceId> {
+        let mut sources: Vec<_> = self.last_used.iter().collect();
+        sources.sort_by_key(|(_, used)| **used);
+        sources
+            .into_iter()
+            .map(|(source_id, _)| *source_id)
+            .collect()
+    }
+
+    pub fn record_eviction(&mut self, source_id: SourceId) {
+        self.evicted.push(source_id);
+        self.evicted_total += 1;
+    }
+
+    pub fn take_evicted(&mut self) -> Vec<SourceId> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    pub fn evicted_total(&self) -> u64 {
+        self.evicted_total
+    }
+}
@@ -1,17 +1,39 @@
 // Mixer module - handles mixing of audio sources
 // This contains the mixing logic for both spatial and non-spatial sources
 
+use crate::config::SourceConfig;
+use crate::events::PetalSonicEvent;
+use crate::logging::{self, rt_debug, rt_error, rt_info};
+use crate::math::Vec3;
 use crate::playback::{LoopMode, PlayState, PlaybackInstance};
-use crate::spatial::SpatialProcessor;
+use crate::reverb::Reverb;
+use crate::secondary_output::SecondaryMix;
+use crate::silence::SilenceDetector;
+use crate::spatial::{DISTANCE_SCALER, Spatializer};
+use crate::stems::StemRecorder;
 use crate::world::SourceId;
+use crate::zones::ZoneEvaluator;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A live source that started (`true`) or stopped (`false`) talking
+pub type VoiceActivityChange = (SourceId, bool);
 
 /// Result of mixing - contains both the number of frames and loop events
 pub struct MixResult {
     pub frames_filled: usize,
     pub completed_sources: Vec<SourceId>,
     pub looped_sources: Vec<SourceId>,
+    pub voice_activity: Vec<VoiceActivityChange>,
+    /// Sources that just became silent, with how long they have been silent
+    pub silent_sources: Vec<(SourceId, Duration)>,
+    /// Caption and caption cue events due after this block
+    pub captions: Vec<PetalSonicEvent>,
+    /// Marker events of the markers crossed in this block
+    pub markers: Vec<PetalSonicEvent>,
+    /// Peak haptics intensity of the sources with a haptics track over this block
+    pub haptics: Vec<(SourceId, f32)>,
 }
 
 /// Mix all active playback instances into the buffer
@@ -19,46 +41,101 @@ pub struct MixResult {
 /// - The number of frames filled
 /// - Vector of source IDs that completed (LoopMode::Once finished)
 /// - Vector of source IDs that looped (LoopMode::Infinite completed one iteration)
+/// - Talking state changes of live voice sources
+/// - Sources that stayed silent for the silence detection time
+/// - Captions and caption cues reached by the sources
+/// - Markers crossed by the sources
+/// - Haptics intensities of the sources with a haptics track
 ///
 /// # Arguments
 /// * `world_buffer` - Output buffer to fill with mixed audio
 /// * `channels` - Number of audio channels (typically 2 for stereo)
 /// * `active_playback` - Map of active playback instances
-/// * `spatial_processor` - Optional spatial processor for 3D audio
+/// * `spatializer` - Backend rendering spatial sources (Steam Audio, the stereo panner or a
+///   custom backend); released from the sources leaving the spatial path
+/// * `zones` - Attenuation zone parameters of the sources for the current listener pose
+/// * `listener_position` - Listener position the distance delay of spatial sources is set
+///   from
+/// * `stems` - Stem recorder to capture recorded sources into, while recording stems
+/// * `reverb` - Reverb bus fed by the sources' sends, if the world has one
+/// * `secondary` - Secondary output the sources on its buses are mixed into instead of
+///   `world_buffer`, while one is open
+/// * `solo_bus` - Bus mixed alone while it is frozen; the sources on other buses are held
+///   without advancing
+/// * `output_frame` - Output frame the block is heard from, which the sources' stop times
+///   are counted against
 ///
 /// # Loop Event Detection
 ///
 /// All loop modes emit events when reaching the end of playback:
 /// - `LoopMode::Once`: Emits `SourceCompleted`, stops playing, removed from active_playback
 /// - `LoopMode::Infinite`: Emits `SourceLooped`, continues playing (loops automatically)
+#[allow(clippy::too_many_arguments)] // Mirrors the render stages a block passes through
 pub fn mix_playback_instances(
     world_buffer: &mut [f32],
     channels: u16,
     active_playback: &Arc<Mutex<HashMap<SourceId, PlaybackInstance>>>,
-    spatial_processor: Option<&mut SpatialProcessor>,
+    mut spatializer: Option<&mut dyn Spatializer>,
+    zones: &ZoneEvaluator,
+    listener_position: Vec3,
+    mut stems: Option<&mut StemRecorder>,
+    reverb: Option<&mut Reverb>,
+    mut secondary: Option<&mut SecondaryMix>,
+    solo_bus: Option<&str>,
+    output_frame: usize,
 ) -> MixResult {
     let Ok(mut active_playback) = active_playback.try_lock() else {
-        log::warn!("Failed to acquire active playback lock in mixer");
+        logging::count_lock_contention();
+        rt_debug!("Failed to acquire active playback lock in mixer");
         return MixResult {
             frames_filled: 0,
             completed_sources: Vec::new(),
             looped_sources: Vec::new(),
+            voice_activity: Vec::new(),
+            silent_sources: Vec::new(),
+            captions: Vec::new(),
+            markers: Vec::new(),
+            haptics: Vec::new(),
         };
     };
 
     // Separate spatial and non-spatial sources FIRST
     let mut spatial_instances = Vec::new();
     let mut non_spatial_instances = Vec::new();
+    // Sources crossfading between the paths, and sources that left the spatial path
+    let mut fading_sources = Vec::new();
+    let mut released_sources = Vec::new();
+
+    // Sources started before the reverb bus existed (see `PetalSonicWorld::set_environment`)
+    // get their send once it does
+    let has_reverb = reverb.is_some();
+
+    // While a bus is frozen, the sources on other buses are held where they are
+    let held = |instance: &PlaybackInstance| {
+        solo_bus.is_some_and(|bus| instance.bus.as_deref() != Some(bus))
+    };
+
+    // When any source is soloed, all other sources are muted but keep advancing
+    let any_soloed = active_playback
+        .values()
+        .any(|instance| instance.soloed && !held(instance));
+    let frame_count = world_buffer.len() / channels as usize;
 
-    log::debug!(
+    rt_debug!(
         "Mixer: Starting mix with {} active sources",
         active_playback.len()
     );
 
     for (source_id, instance) in active_playback.iter_mut() {
+        if held(instance) {
+            continue;
+        }
+        instance.resolve_stop_at(output_frame);
+        instance.poll_playlist();
+
         // Only process playing instances
         if !matches!(instance.info.play_state, PlayState::Playing) {
-            log::debug!(
+            rt_debug!(
                 "Mixer: Skipping source {} - not playing (state: {:?})",
                 source_id,
                 instance.info.play_state
@@ -66,7 +143,9 @@ pub fn mix_playback_instances(
             continue;
         }
 
-        log::debug!(
+        instance.advance_sync_clock(frame_count);
+
+        rt_debug!(
             "Mixer: Processing source {} - frame {}/{} (spatial: {})",
             source_id,
             instance.info.current_frame,
@@ -74,49 +153,162 @@ pub fn mix_playback_instances(
             instance.config.is_spatial()
         );
 
-        if instance.config.is_spatial() {
+        if has_reverb && instance.reverb_send.is_none() {
+            instance.reverb_send = Some(Vec::new());
+        }
+        instance.zone_filter.set_target(zones.params(*source_id));
+        if let (Some(delay), SourceConfig::Spatial { position, .. }) =
+            (instance.distance_delay.as_mut(), &instance.config)
+        {
+            delay.set_distance(position.distance(listener_position) * DISTANCE_SCALER);
+        }
+
+        // A path switch needs no crossfade while the source is silent or not spatialized
+        let muted = any_soloed && !instance.soloed;
+        let on_secondary = secondary
+            .as_deref()
+            .is_some_and(|secondary| secondary.routes(instance.bus.as_deref()));
+        let bypasses_spatial = instance.output_routing.is_routed() || on_secondary;
+        if (muted || bypasses_spatial) && instance.finish_path_fade() {
+            released_sources.push(*source_id);
+        }
+
+        if muted {
+            rt_debug!("Mixer: Source {} muted by solo", source_id);
+            instance.skip_frames(frame_count);
+            continue;
+        }
+
+        // Sources routed to specific channels or to the secondary output bypass
+        // spatialization. Sources switching paths are rendered on the spatial path, with
+        // their non-spatial share split off.
+        if instance.config.is_spatial() && !bypasses_spatial {
+            if instance.path_fade.is_some() {
+                fading_sources.push(*source_id);
+            }
             spatial_instances.push((*source_id, instance as &mut PlaybackInstance));
         } else {
-            non_spatial_instances.push(instance);
+            non_spatial_instances.push((instance, on_secondary));
         }
     }
 
     let mut frames_filled_max = 0;
 
     // Process non-spatial sources first
-    for instance in non_spatial_instances {
-        let frames_filled = instance.fill_buffer(world_buffer, channels);
+    for (instance, on_secondary) in non_spatial_instances {
+        let output = match secondary.as_deref_mut() {
+            Some(secondary) if on_secondary => secondary.block_mut(),
+            _ => &mut *world_buffer,
+        };
+        let bus = instance.bus.as_deref();
+        let stem = stems
+            .as_deref_mut()
+            .and_then(|stems| stems.block_mut(instance.audio_id, bus));
+        let frames_filled = match stem {
+            // Recorded sources render into a stem block, which is then added to the mix
+            Some(stem) => {
+                let frames_filled = instance.fill_buffer(stem, channels);
+                for (output, sample) in output.iter_mut().zip(stem.iter()) {
+                    *output += sample;
+                }
+                if let Some(stems) = stems.as_deref_mut() {
+                    stems.capture_block(instance.audio_id, instance.bus.as_deref());
+                }
+                frames_filled
+            }
+            None => instance.fill_buffer(output, channels),
+        };
         frames_filled_max = frames_filled_max.max(frames_filled);
     }
 
-    // Process spatial sources if spatial processor is available
-    if let Some(processor) = spatial_processor {
+    // Spatializers record the sources' mono input, which also feeds their bus stems
+    if let Some(stems) = stems.as_deref_mut() {
+        for (source_id, instance) in &spatial_instances {
+            stems.assign_bus(*source_id, instance.bus.as_deref());
+        }
+    }
+
+    // Process spatial sources with the active spatializer backend
+    if let Some(spatializer) = spatializer.as_deref_mut() {
         if !spatial_instances.is_empty() {
-            match processor.process_spatial_sources(&mut spatial_instances, world_buffer) {
+            match spatializer.process(&mut spatial_instances, world_buffer, channels, stems) {
                 Ok(frames_filled) => {
                     frames_filled_max = frames_filled_max.max(frames_filled);
                 }
                 Err(e) => {
-                    log::error!("Error processing spatial sources: {}", e);
+                    logging::count_render_error();
+                    rt_error!("Error processing spatial sources: {}", e);
                 }
             }
         }
     } else if !spatial_instances.is_empty() {
-        log::warn!(
-            "Spatial processor not available, {} spatial sources will be silent",
+        logging::count_lock_contention();
+        rt_debug!(
+            "Spatializer not available, {} spatial sources will be silent",
             spatial_instances.len()
         );
+        // Keep them in sync with the mix so they still complete on time
+        for (_, instance) in spatial_instances.iter_mut() {
+            instance.skip_frames(frame_count);
+        }
+    }
+
+    // Complete the path crossfades with the non-spatial share of the block
+    for source_id in fading_sources {
+        if let Some(instance) = active_playback.get_mut(&source_id) {
+            instance.mix_path_fade(world_buffer, channels);
+            if instance.finish_path_fade() {
+                released_sources.push(source_id);
+            }
+        }
+    }
+
+    // Feed the sources' sends to the reverb bus; it keeps ringing after they stop
+    if let Some(reverb) = reverb {
+        for instance in active_playback.values_mut() {
+            let volume = instance.config.volume().unwrap_or(1.0);
+            if let Some(send) = instance.reverb_send.as_mut() {
+                reverb.add_send(send, volume);
+                send.clear();
+            }
+        }
+        reverb.process(world_buffer, channels);
     }
 
     // NOW check for sources that reached the end during this mix iteration
     // This must happen AFTER fill_buffer() has been called on all sources
     let mut completed_sources = Vec::new();
     let mut looped_sources = Vec::new();
+    let mut voice_activity = Vec::new();
+    let mut silent_sources = Vec::new();
+    let mut captions = Vec::new();
+    let mut markers = Vec::new();
+    let mut haptics = Vec::new();
 
-    log::debug!("Mixer: Checking for completed/looped sources...");
+    rt_debug!("Mixer: Checking for completed/looped sources...");
 
     for (source_id, instance) in active_playback.iter_mut() {
-        log::debug!(
+        if held(instance) {
+            continue;
+        }
+        if let Some(talking) = instance.take_voice_activity_change() {
+            voice_activity.push((*source_id, talking));
+        }
+        if let Some(duration) = instance
+            .silence
+            .as_mut()
+            .and_then(SilenceDetector::take_silence)
+        {
+            silent_sources.push((*source_id, duration));
+        }
+        // Before a looping source restarts, so the cues up to the end of the clip fire
+        instance.take_captions(&mut captions);
+        instance.take_markers(&mut markers);
+        if let Some(intensity) = instance.take_haptics() {
+            haptics.push((*source_id, intensity));
+        }
+
+        rt_debug!(
             "Mixer: Checking source {} - reached_end_flag: {}, state: {:?}",
             source_id,
             instance.reached_end_this_iteration,
@@ -124,7 +316,7 @@ pub fn mix_playback_instances(
         );
 
         if let Some(loop_mode) = instance.check_and_clear_end_flag() {
-            log::debug!(
+            rt_debug!(
                 "Mixer: Source {} reached end with loop mode: {:?}",
                 source_id,
                 loop_mode
@@ -132,40 +324,60 @@ pub fn mix_playback_instances(
             match loop_mode {
                 LoopMode::Once => {
                     // Source finished - will be removed and emit SourceCompleted
-                    log::info!(
+                    rt_info!(
                         "Mixer: Source {} completed (Once mode), will be removed",
                         source_id
                     );
                     completed_sources.push(*source_id);
                 }
                 LoopMode::Infinite => {
-                    // Source reached end - explicitly restart from beginning
-                    log::info!(
-                        "Mixer: Source {} reached end (Infinite mode), restarting from beginning",
+                    // Source reached end - explicitly restart from the loop start
+                    rt_info!(
+                        "Mixer: Source {} reached end (Infinite mode), restarting loop",
                         source_id
                     );
-                    instance.play_from_beginning();
+                    instance.restart_loop();
                     looped_sources.push(*source_id);
                 }
             }
         }
+        // Keep sync group members on their group's clock
+        instance.resync();
     }
 
     // Only remove instances that are actually finished (stopped playing)
     // Infinite looping sources were explicitly restarted, so they keep playing
     let removed_count = active_playback.len();
-    active_playback.retain(|_, instance| !instance.info.is_finished());
+    active_playback.retain(|source_id, instance| {
+        let finished = instance.is_finished();
+        if finished && instance.config.is_spatial() {
+            released_sources.push(*source_id);
+        }
+        !finished
+    });
     let removed = removed_count - active_playback.len();
     if removed > 0 {
-        log::debug!(
+        rt_debug!(
             "Mixer: Removed {} finished sources from active playback",
             removed
         );
     }
 
+    // Free the spatializer state of the sources that left the spatial path
+    if let Some(spatializer) = spatializer {
+        for source_id in released_sources {
+            spatializer.release_source(source_id);
+        }
+    }
+
     MixResult {
         frames_filled: frames_filled_max,
         completed_sources,
         looped_sources,
+        voice_activity,
+        silent_sources,
+        captions,
+        markers,
+        haptics,
     }
 }
@@ -0,0 +1,432 @@
+//! Jitter-buffered network audio sources.
+//!
+//! A [`NetworkAudioSource`] receives timestamped audio packets from user code (e.g. from a
+//! game's networking layer), decodes them with a [`PacketDecoder`] and feeds the result
+//! through an adaptive jitter buffer into the regular playback and spatialization pipeline.
+//! Register one with
+//! [`PetalSonicWorld::register_network_source`](crate::PetalSonicWorld::register_network_source):
+//!
+//! ```ignore
+//! let (source_id, mut network) = world.register_network_source(
+//!     SourceConfig::spatial(player_position, 1.0),
+//!     NetworkSourceConfig::default(),
+//!     Pcm16Decoder,
+//! )?;
+//! world.play(source_id, LoopMode::Infinite)?;
+//!
+//! // for every packet received from the remote player:
+//! network.push_packet(packet.timestamp, &packet.payload)?;
+//! ```
+//!
+//! Timestamps count samples since the start of the sender's stream, so consecutive packets
+//! of 960 samples carry timestamps 0, 960, 1920, ... Decoded audio must be mono at the world
+//! sample rate.
+//!
+//! The jitter buffer delays playout by an amount that adapts to the measured arrival jitter
+//! (between [`NetworkSourceConfig::min_delay`] and [`NetworkSourceConfig::max_delay`]).
+//! Packets arriving after their playout time are dropped. Gaps from lost or late packets are
+//! concealed by repeating the last packet with a fade-out; after
+//! [`NetworkSourceConfig::max_concealment`] without audio the buffer refills before playing
+//! again.
+//!
+//! PetalSonic ships a PCM decoder ([`Pcm16Decoder`]). Compressed formats such as Opus plug
+//! in by implementing [`PacketDecoder`] on top of a decoder crate.
+
+use crate::error::{PetalSonicError, Result};
+use crate::voice::{LiveSource, VoiceActivityConfig, VoiceGate};
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Gain applied per concealed sample, fading repeated audio out over roughly 60 ms at 48 kHz
+const CONCEALMENT_DECAY: f32 = 0.9985;
+
+/// Weight of a new transit time difference in the jitter estimate (RFC 3550)
+const JITTER_SMOOTHING: f64 = 1.0 / 16.0;
+
+/// Target delay in multiples of the estimated jitter
+const JITTER_DELAY_FACTOR: f64 = 3.0;
+
+/// Decodes packet payloads into mono f32 samples at the world sample rate
+pub trait PacketDecoder: Send + 'static {
+    /// Decode `payload` and append the samples to `pcm`
+    fn decode(&mut self, payload: &[u8], pcm: &mut Vec<f32>) -> Result<()>;
+}
+
+/// Decodes signed 16-bit little-endian PCM, the counterpart of
+/// [`Pcm16Encoder`](crate::tap::Pcm16Encoder)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pcm16Decoder;
+
+impl PacketDecoder for Pcm16Decoder {
+    fn decode(&mut self, payload: &[u8], pcm: &mut Vec<f32>) -> Result<()> {
+        if !payload.len().is_multiple_of(2) {
+            return Err(PetalSonicError::AudioFormat(format!(
+                "PCM16 packet has an odd length of {} bytes",
+                payload.len()
+            )));
+        }
+        pcm.extend(
+            payload
+                .chunks_exact(2)
+                .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32),
+        );
+        Ok(())
+    }
+}
+
+/// Jitter buffer settings of a network source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkSourceConfig {
+    /// Lowest playout delay the jitter buffer adapts to
+    pub min_delay: Duration,
+    /// Highest playout delay; buffered audio beyond it is skipped
+    pub max_delay: Duration,
+    /// How long gaps are concealed before the buffer refills
+    pub max_concealment: Duration,
+    /// Gate the source by voice activity (see [`crate::voice`]), or `None` to always play it
+    pub voice_activity: Option<VoiceActivityConfig>,
+}
+
+impl Default for NetworkSourceConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_millis(40),
+            max_delay: Duration::from_millis(300),
+            max_concealment: Duration::from_millis(120),
+            voice_activity: None,
+        }
+    }
+}
+
+/// Counters of a network source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkSourceStats {
+    /// Packets handed to the jitter buffer
+    pub packets_received: u64,
+    /// Packets dropped because they arrived after their playout time
+    pub late_packets: u64,
+    /// Samples synthesized by packet-loss concealment
+    pub concealed_samples: u64,
+    /// Current target playout delay
+    pub target_delay: Duration,
+}
+
+#[derive(Default)]
+struct SharedNetworkStats {
+    packets_received: AtomicU64,
+    late_packets: AtomicU64,
+    concealed_samples: AtomicU64,
+    target_delay_us: AtomicU64,
+}
+
+struct Packet {
+    timestamp: u64,
+    arrival: Instant,
+    samples: Vec<f32>,
+}
+
+/// Main-thread (or network-thread) handle used to feed a network source.
+///
+/// Returned by
+/// [`PetalSonicWorld::register_network_source`](crate::PetalSonicWorld::register_network_source).
+pub struct NetworkAudioSource {
+    decoder: Box<dyn PacketDecoder>,
+    sender: Sender<Packet>,
+    stats: Arc<SharedNetworkStats>,
+}
+
+impl NetworkAudioSource {
+    /// Decode a packet and hand it to the jitter buffer.
+    ///
+    /// `timestamp` is the position of the packet's first sample in the sender's stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns the decoder's error, or `PetalSonicError::Engine` if the source was removed.
+    pub fn push_packet(&mut self, timestamp: u64, payload: &[u8]) -> Result<()> {
+        let mut samples = Vec::new();
+        self.decoder.decode(payload, &mut samples)?;
+        self.push_samples(timestamp, samples)
+    }
+
+    /// Hand already decoded mono samples to the jitter buffer
+    pub fn push_samples(&mut self, timestamp: u64, samples: Vec<f32>) -> Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        self.sender
+            .send(Packet {
+                timestamp,
+                arrival: Instant::now(),
+                samples,
+            })
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to send network packet: {}", e)))
+    }
+
+    /// Returns the current counters of the source
+    pub fn stats(&self) -> NetworkSourceStats {
+        NetworkSourceStats {
+            packets_received: self.stats.packets_received.load(Ordering::Relaxed),
+            late_packets: self.stats.late_packets.load(Ordering::Relaxed),
+            concealed_samples: self.stats.concealed_samples.load(Ordering::Relaxed),
+            target_delay: Duration::from_micros(self.stats.target_delay_us.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Render-side adaptive jitter buffer of a network source
+pub(crate) struct JitterBuffer {
+    receiver: Receiver<Packet>,
+    packets: BTreeMap<u64, Vec<f32>>,
+    sample_rate: f64,
+    min_delay: usize,
+    max_delay: usize,
+    max_concealment: usize,
+    /// Arrival time of the first packet, the reference for transit times
+    epoch: Option<Instant>,
+    last_transit: Option<f64>,
+    /// Estimated arrival jitter in seconds
+    jitter: f64,
+    target_delay: usize,
+    /// Stream position of the next sample to play, `None` while buffering
+    playout: Option<u64>,
+    /// Audio repeated to conceal gaps
+    concealment: Vec<f32>,
+    concealment_position: usize,
+    concealment_gain: f32,
+    concealed_run: usize,
+    gate: Option<VoiceGate>,
+    stats: Arc<SharedNetworkStats>,
+}
+
+impl JitterBuffer {
+    /// Create a network source at `sample_rate`; returns the input handle and the render side
+    pub fn new<D: PacketDecoder>(
+        config: &NetworkSourceConfig,
+        decoder: D,
+        sample_rate: u32,
+    ) -> (NetworkAudioSource, Self) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let stats = Arc::new(SharedNetworkStats::default());
+        let to_samples =
+            |duration: Duration| (duration.as_secs_f64() * sample_rate as f64) as usize;
+        let min_delay = to_samples(config.min_delay);
+        let max_delay = to_samples(config.max_delay).max(min_delay);
+
+        (
+            NetworkAudioSource {
+                decoder: Box::new(decoder),
+                sender,
+                stats: stats.clone(),
+            },
+            Self {
+                receiver,
+                packets: BTreeMap::new(),
+                sample_rate: sample_rate as f64,
+                min_delay,
+                max_delay,
+                max_concealment: to_samples(config.max_concealment),
+                epoch: None,
+                last_transit: None,
+                jitter: 0.0,
+                target_delay: min_delay,
+                playout: None,
+                concealment: Vec::new(),
+                concealment_position: 0,
+                concealment_gain: 0.0,
+                concealed_run: 0,
+                gate: config
+                    .voice_activity
+                    .map(|vad| VoiceGate::new(&vad, sample_rate)),
+                stats,
+            },
+        )
+    }
+
+    /// Move received packets into the buffer, dropping late ones and updating the jitter estimate
+    fn receive_packets(&mut self) {
+        while let Ok(packet) = self.receiver.try_recv() {
+            self.stats.packets_received.fetch_add(1, Ordering::Relaxed);
+
+            let end = packet.timestamp + packet.samples.len() as u64;
+            if self.playout.is_some_and(|playout| end <= playout) {
+                self.stats.late_packets.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            // RFC 3550 interarrival jitter from the change in transit time
+            let epoch = *self.epoch.get_or_insert(packet.arrival);
+            let transit = packet
+                .arrival
+                .saturating_duration_since(epoch)
+                .as_secs_f64()
+                - packet.timestamp as f64 / self.sample_rate;
+            if let Some(last_transit) = self.last_transit {
+                let difference = (transit - last_transit).abs();
+                self.jitter += (difference - self.jitter) * JITTER_SMOOTHING;
+            }
+            self.last_transit = Some(transit);
+
+            self.packets.insert(packet.timestamp, packet.samples);
+        }
+
+        let jitter_delay = (self.jitter * JITTER_DELAY_FACTOR * self.sample_rate) as usize;
+        self.target_delay = jitter_delay.clamp(self.min_delay, self.max_delay);
+        self.stats.target_delay_us.store(
+            (self.target_delay as f64 / self.sample_rate * 1e6) as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Number of samples buffered ahead of `position`
+    fn buffered_from(&self, position: u64) -> usize {
+        self.packets
+            .iter()
+            .next_back()
+            .map_or(0, |(timestamp, samples)| {
+                (timestamp + samples.len() as u64).saturating_sub(position) as usize
+            })
+    }
+
+    /// Fill `output` from the buffer, concealing gaps. Returns false if playout stopped.
+    fn play(&mut self, output: &mut [f32], mut playout: u64) -> bool {
+        // Skip ahead when more than the maximum delay is buffered
+        let excess = self.buffered_from(playout).saturating_sub(self.max_delay);
+        playout += excess as u64;
+
+        let mut written = 0;
+        while written < output.len() {
+            // Drop packets that were played completely
+            while let Some(entry) = self.packets.first_entry() {
+                if entry.key() + entry.get().len() as u64 <= playout {
+                    entry.remove();
+                } else {
+                    break;
+                }
+            }
+
+            let remaining = output.len() - written;
+            let next = self
+                .packets
+                .first_key_value()
+                .map(|(timestamp, samples)| (*timestamp, samples));
+
+            match next {
+                Some((timestamp, samples)) if timestamp <= playout => {
+                    let offset = (playout - timestamp) as usize;
+                    let count = (samples.len() - offset).min(remaining);
+                    output[written..written + count]
+                        .copy_from_slice(&samples[offset..offset + count]);
+
+                    // Remember the packet for concealing a following gap
+                    self.concealment.clear();
+                    self.concealment.extend_from_slice(samples);
+                    self.concealment_position = (offset + count) % samples.len();
+                    self.concealment_gain = 1.0;
+                    self.concealed_run = 0;
+
+                    written += count;
+                    playout += count as u64;
+                }
+                next => {
+                    let gap = next.map_or(remaining, |(timestamp, _)| {
+                        ((timestamp - playout) as usize).min(remaining)
+                    });
+                    self.conceal(&mut output[written..written + gap]);
+                    written += gap;
+                    playout += gap as u64;
+
+                    if self.concealed_run >= self.max_concealment && self.packets.is_empty() {
+                        // The stream dried up: refill the buffer before playing again
+                        output[written..].fill(0.0);
+                        self.playout = None;
+                        return false;
+                    }
+                }
+            }
+        }
+
+        self.playout = Some(playout);
+        true
+    }
+
+    /// Synthesize a gap by repeating the last packet with a fade-out
+    fn conceal(&mut self, output: &mut [f32]) {
+        self.concealed_run += output.len();
+        self.stats
+            .concealed_samples
+            .fetch_add(output.len() as u64, Ordering::Relaxed);
+
+        if self.concealment.is_empty() {
+            output.fill(0.0);
+            return;
+        }
+
+        for sample in output {
+            *sample = self.concealment[self.concealment_position] * self.concealment_gain;
+            self.concealment_position = (self.concealment_position + 1) % self.concealment.len();
+            self.concealment_gain *= CONCEALMENT_DECAY;
+        }
+    }
+}
+
+impl std::fmt::Debug for JitterBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JitterBuffer")
+            .field("packets", &self.packets.len())
+            .field("jitter", &self.jitter)
+            .field("target_delay", &self.target_delay)
+            .field("playout", &self.playout)
+            .finish()
+    }
+}
+
+impl LiveSource for JitterBuffer {
+    fn read(&mut self, output: &mut [f32]) -> bool {
+        self.receive_packets();
+
+        let playing = match self.playout {
+            Some(playout) => self.play(output, playout),
+            None => {
+                // Buffering: start once the target delay is buffered
+                let start = self
+                    .packets
+                    .first_key_value()
+                    .map(|(timestamp, _)| *timestamp);
+                match start {
+                    Some(start) if self.buffered_from(start) >= self.target_delay => {
+                        self.play(output, start)
+                    }
+                    _ => {
+                        output.fill(0.0);
+                        false
+                    }
+                }
+            }
+        };
+
+        match self.gate.as_mut() {
+            Some(gate) => gate.process(output),
+            None => playing,
+        }
+    }
+
+    fn take_activity_change(&mut self) -> Option<bool> {
+        self.gate.as_mut()?.take_change()
+    }
+
+    fn reset(&mut self) {
+        while self.receiver.try_recv().is_ok() {}
+        self.packets.clear();
+        self.playout = None;
+        self.concealment.clear();
+        self.concealed_run = 0;
+        if let Some(gate) = self.gate.as_mut() {
+            gate.reset();
+        }
+    }
+}
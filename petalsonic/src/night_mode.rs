@@ -0,0 +1,128 @@
+//! Night mode: reduced dynamic range for late-night play.
+//!
+//! With night mode enabled (see
+//! [`PetalSonicEngine::set_night_mode`](crate::PetalSonicEngine::set_night_mode) or
+//! [`PetalSonicWorldDesc::night_mode`](crate::PetalSonicWorldDesc::night_mode)), the master
+//! output runs through a preconfigured compressor and limiter: loud moments such as
+//! explosions are pulled down, quiet ones such as dialog are brought up, and peaks stay
+//! below -1 dBFS. The volume can then be set low without losing the quiet parts:
+//!
+//! ```ignore
+//! engine.set_night_mode(true);
+//! ```
+//!
+//! The chain is fixed: a soft-knee 3:1 compressor from -30 dBFS with 10 ms attack, 250 ms
+//! release and 9 dB makeup gain, followed by a limiter. All channels share one gain so the
+//! stereo image does not shift. Toggling night mode crossfades between the processed and
+//! untouched gain over half a second. Like the master volume it applies to the device
+//! output only, before the master volume, so the amount of compression does not depend on
+//! the volume.
+
+/// Level above which the compressor reduces the gain
+const THRESHOLD_DB: f32 = -30.0;
+/// Input dB above the threshold per output dB
+const RATIO: f32 = 3.0;
+/// Width of the soft knee around the threshold
+const KNEE_DB: f32 = 6.0;
+/// Gain added after compression to bring quiet passages up
+const MAKEUP_DB: f32 = 9.0;
+const ATTACK_SECS: f32 = 0.010;
+const RELEASE_SECS: f32 = 0.250;
+/// Peak level the limiter keeps the output below
+const CEILING_DB: f32 = -1.0;
+const LIMITER_RELEASE_SECS: f32 = 0.050;
+/// Time to fade the processing in or out when night mode is toggled
+const TOGGLE_RAMP_SECS: f32 = 0.5;
+/// Floor of the level detector, so silence does not yield -inf
+const SILENCE_DB: f32 = -120.0;
+
+/// Render-side compressor and limiter for interleaved audio
+pub(crate) struct NightMode {
+    channels: usize,
+    attack: f32,
+    release: f32,
+    limiter_release: f32,
+    /// Change of `amount` per frame while toggling
+    ramp_step: f32,
+    /// Share of the processing applied, from 0 (off) to 1 (on)
+    amount: f32,
+    /// Smoothed compressor gain change in dB (zero or negative)
+    compression_db: f32,
+    /// Smoothed limiter gain change in dB (zero or negative)
+    limiting_db: f32,
+}
+
+impl NightMode {
+    pub fn new(sample_rate: u32, channels: u16, enabled: bool) -> Self {
+        let sample_rate = sample_rate as f32;
+        let coefficient = |secs: f32| (-1.0 / (secs * sample_rate)).exp();
+        Self {
+            channels: channels as usize,
+            attack: coefficient(ATTACK_SECS),
+            release: coefficient(RELEASE_SECS),
+            limiter_release: coefficient(LIMITER_RELEASE_SECS),
+            ramp_step: 1.0 / (TOGGLE_RAMP_SECS * sample_rate),
+            amount: if enabled { 1.0 } else { 0.0 },
+            compression_db: 0.0,
+            limiting_db: 0.0,
+        }
+    }
+
+    /// Gain change of the compressor's static curve for a level in dB
+    fn compression_curve(level_db: f32) -> f32 {
+        let over = level_db - THRESHOLD_DB;
+        let slope = 1.0 / RATIO - 1.0;
+        if over <= -KNEE_DB / 2.0 {
+            0.0
+        } else if over < KNEE_DB / 2.0 {
+            slope * (over + KNEE_DB / 2.0).powi(2) / (2.0 * KNEE_DB)
+        } else {
+            slope * over
+        }
+    }
+
+    /// Compress and limit interleaved samples in place, fading toward `enabled`
+    pub fn process(&mut self, enabled: bool, samples: &mut [f32]) {
+        let target = if enabled { 1.0 } else { 0.0 };
+        if self.amount == 0.0 && target == 0.0 {
+            self.compression_db = 0.0;
+            self.limiting_db = 0.0;
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(self.channels) {
+            self.amount = if target > self.amount {
+                (self.amount + self.ramp_step).min(target)
+            } else {
+                (self.amount - self.ramp_step).max(target)
+            };
+
+            let peak = frame
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            let level_db = (20.0 * peak.log10()).max(SILENCE_DB);
+
+            let compression = Self::compression_curve(level_db);
+            let coefficient = if compression < self.compression_db {
+                self.attack
+            } else {
+                self.release
+            };
+            self.compression_db = compression + (self.compression_db - compression) * coefficient;
+
+            // The limiter acts at once on peaks the compressed signal would push past the
+            // ceiling and recovers quickly
+            let compressed_db = level_db + self.compression_db + MAKEUP_DB;
+            let limiting = (CEILING_DB - compressed_db).min(0.0);
+            self.limiting_db = if limiting < self.limiting_db {
+                limiting
+            } else {
+                limiting + (self.limiting_db - limiting) * self.limiter_release
+            };
+
+            let gain_db = (self.compression_db + MAKEUP_DB + self.limiting_db) * self.amount;
+            let gain = 10f32.powf(gain_db / 20.0);
+            frame.iter_mut().for_each(|sample| *sample *= gain);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Output EQ for headphone calibration.
+//!
+//! HRTF rendering assumes a neutral playback chain, but headphones color the sound with
+//! their own frequency response. An [`OutputEq`] set in
+//! [`PetalSonicWorldDesc::output_eq`](crate::PetalSonicWorldDesc::output_eq) (or at runtime
+//! with [`PetalSonicEngine::set_output_eq`](crate::PetalSonicEngine::set_output_eq))
+//! corrects it with a preamp and a chain of parametric bands applied to the master output.
+//!
+//! Profiles in the parametric format of [AutoEq](https://github.com/jaakkopasanen/AutoEq)
+//! and Equalizer APO can be loaded directly:
+//!
+//! ```ignore
+//! let eq = OutputEq::from_file("Sennheiser HD 600 ParametricEQ.txt")?;
+//! engine.set_output_eq(Some(eq))?;
+//! ```
+//!
+//! The EQ runs after loudness metering, the output tap and stem recording, so measurements
+//! and recordings are not colored by the correction for one pair of headphones.
+
+use crate::error::{PetalSonicError, Result};
+use crate::loudness::Biquad;
+use std::path::Path;
+
+/// Shape of a parametric EQ band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EqFilterType {
+    /// Bell around the center frequency
+    Peaking,
+    /// Shelf boosting or cutting below the corner frequency
+    LowShelf,
+    /// Shelf boosting or cutting above the corner frequency
+    HighShelf,
+}
+
+/// One band of an [`OutputEq`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EqBand {
+    pub filter_type: EqFilterType,
+    /// Center (peaking) or corner (shelf) frequency in Hz
+    pub frequency_hz: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl EqBand {
+    pub fn peaking(frequency_hz: f32, gain_db: f32, q: f32) -> Self {
+        Self {
+            filter_type: EqFilterType::Peaking,
+            frequency_hz,
+            gain_db,
+            q,
+        }
+    }
+
+    pub fn low_shelf(frequency_hz: f32, gain_db: f32, q: f32) -> Self {
+        Self {
+            filter_type: EqFilterType::LowShelf,
+            frequency_hz,
+            gain_db,
+            q,
+        }
+    }
+
+    pub fn high_shelf(frequency_hz: f32, gain_db: f32, q: f32) -> Self {
+        Self {
+            filter_type: EqFilterType::HighShelf,
+            frequency_hz,
+            gain_db,
+            q,
+        }
+    }
+
+    /// Filter coefficients of the band at `sample_rate` (RBJ audio EQ cookbook)
+    pub(crate) fn biquad(&self, sample_rate: u32) -> Biquad {
+        let fs = sample_rate as f64;
+        // Bands above Nyquist are pulled just below it
+        let frequency = (self.frequency_hz as f64).min(fs * 0.49);
+        let a = 10f64.powf(self.gain_db as f64 / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * frequency / fs;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * self.q as f64);
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match self.filter_type {
+            EqFilterType::Peaking => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            EqFilterType::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ),
+            EqFilterType::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ),
+        };
+
+        Biquad::new(b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0)
+    }
+}
+
+/// Calibration EQ applied to the master output
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputEq {
+    /// Gain in dB applied before the bands, usually negative to leave headroom for boosts
+    pub preamp_db: f32,
+    pub bands: Vec<EqBand>,
+}
+
+impl OutputEq {
+    /// Parse a parametric EQ profile in AutoEq / Equalizer APO format
+    ///
+    /// Reads `Preamp: <gain> dB` and `Filter <n>: ON <type> Fc <freq> Hz Gain <gain> dB Q <q>`
+    /// lines, where the type is `PK`/`PEQ`, `LS`/`LSC` or `HS`/`HSC`. Filters marked `OFF`,
+    /// comments (`#`) and other directives are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Configuration` for malformed preamp or filter lines,
+    /// unsupported filter types and invalid band parameters.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut eq = OutputEq::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let malformed = || {
+                PetalSonicError::Configuration(format!(
+                    "Malformed EQ profile line {}: {}",
+                    index + 1,
+                    line
+                ))
+            };
+
+            if let Some(rest) = line.strip_prefix("Preamp:") {
+                let value = rest.split_whitespace().next().ok_or_else(malformed)?;
+                eq.preamp_db += value.parse::<f32>().map_err(|_| malformed())?;
+            } else if line.starts_with("Filter") {
+                let (_, settings) = line.split_once(':').ok_or_else(malformed)?;
+                let tokens: Vec<&str> = settings.split_whitespace().collect();
+                if tokens.first() != Some(&"ON") {
+                    continue;
+                }
+
+                let filter_type = match tokens.get(1).copied() {
+                    Some("PK" | "PEQ") => EqFilterType::Peaking,
+                    Some("LS" | "LSC") => EqFilterType::LowShelf,
+                    Some("HS" | "HSC") => EqFilterType::HighShelf,
+                    Some(other) => {
+                        return Err(PetalSonicError::Configuration(format!(
+                            "Unsupported EQ filter type '{}' on line {}",
+                            other,
+                            index + 1
+                        )));
+                    }
+                    None => return Err(malformed()),
+                };
+                let value = |key: &str| -> Result<f32> {
+                    let position = tokens
+                        .iter()
+                        .position(|token| *token == key)
+                        .ok_or_else(malformed)?;
+                    tokens
+                        .get(position + 1)
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(malformed)
+                };
+
+                eq.bands.push(EqBand {
+                    filter_type,
+                    frequency_hz: value("Fc")?,
+                    gain_db: value("Gain")?,
+                    q: value("Q")?,
+                });
+            }
+        }
+
+        eq.validate()?;
+        Ok(eq)
+    }
+
+    /// Load a parametric EQ profile from a file (see [`OutputEq::parse`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid profile.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Check that the preamp and all bands have usable parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Configuration` if a gain is not finite, or a frequency or Q
+    /// is not positive.
+    pub fn validate(&self) -> Result<()> {
+        if !self.preamp_db.is_finite() {
+            return Err(PetalSonicError::Configuration(format!(
+                "EQ preamp must be finite, got {}",
+                self.preamp_db
+            )));
+        }
+
+        for (index, band) in self.bands.iter().enumerate() {
+            let positive = |value: f32| value.is_finite() && value > 0.0;
+            if !positive(band.frequency_hz) || !positive(band.q) || !band.gain_db.is_finite() {
+                return Err(PetalSonicError::Configuration(format!(
+                    "EQ band {} has invalid parameters: {:?}",
+                    index + 1,
+                    band
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Render-side state of an [`OutputEq`] for interleaved audio
+#[derive(Debug)]
+pub(crate) struct OutputEqFilter {
+    preamp: f64,
+    /// Filter chain of each channel
+    filters: Vec<Vec<Biquad>>,
+}
+
+impl OutputEqFilter {
+    pub fn new(eq: &OutputEq, sample_rate: u32, channels: u16) -> Self {
+        let chain: Vec<Biquad> = eq
+            .bands
+            .iter()
+            .map(|band| band.biquad(sample_rate))
+            .collect();
+        Self {
+            preamp: 10f64.powf(eq.preamp_db as f64 / 20.0),
+            filters: vec![chain; channels as usize],
+        }
+    }
+
+    /// Apply the EQ to interleaved samples in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let channels = self.filters.len();
+        for frame in samples.chunks_exact_mut(channels) {
+            for (sample, chain) in frame.iter_mut().zip(self.filters.iter_mut()) {
+                let mut value = *sample as f64 * self.preamp;
+                for stage in chain.iter_mut() {
+                    value = stage.process(value);
+                }
+                *sample = value as f32;
+            }
+        }
+    }
+}
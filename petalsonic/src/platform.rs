@@ -0,0 +1,208 @@
+//! Platform-specific audio session handling.
+//!
+//! Desktop platforms need no session setup. On iOS the shared `AVAudioSession` is configured
+//! with the category from [`AudioSessionConfig`] and activated before the output stream is
+//! opened. Route changes (e.g. speaker ↔ headphones) are detected on every platform by
+//! watching the default output device, see [`RouteMonitor`].
+
+use crate::config::AudioSessionConfig;
+use crate::error::Result;
+use crate::events::PetalSonicEvent;
+use cpal::traits::{DeviceTrait, HostTrait};
+use crossbeam_channel::Sender;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Minimum time between two default output device queries
+const ROUTE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configure and activate the platform audio session
+///
+/// This is a no-op on platforms without an audio session concept.
+pub(crate) fn configure_audio_session(config: &AudioSessionConfig) -> Result<()> {
+    #[cfg(target_os = "ios")]
+    {
+        ios::configure_audio_session(config)
+    }
+
+    #[cfg(not(target_os = "ios"))]
+    {
+        log::debug!(
+            "Audio session configuration not applicable on this platform: {:?}",
+            config
+        );
+        Ok(())
+    }
+}
+
+/// Name of the current default output device, if any
+fn default_output_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.name().ok())
+}
+
+/// Detects audio route changes by polling the default output device at a low rate
+///
+/// Enumerating devices can take milliseconds (and longer on some hosts), so the polling runs
+/// on a `petalsonic-route` thread, which reports changes as `DeviceChanged` events. The
+/// thread is stopped when the monitor is dropped.
+pub(crate) struct RouteMonitor {
+    shutdown: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl RouteMonitor {
+    /// Start watching for the default output device to differ from `current_device`
+    pub fn start(current_device: Option<String>, event_sender: Sender<PetalSonicEvent>) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("petalsonic-route".to_string())
+                .spawn(move || Self::run(current_device, &event_sender, &shutdown))
+                .map_err(|e| log::warn!("Failed to spawn route monitor thread: {}", e))
+                .ok()
+        };
+        Self { shutdown, thread }
+    }
+
+    fn run(
+        mut current_device: Option<String>,
+        event_sender: &Sender<PetalSonicEvent>,
+        shutdown: &AtomicBool,
+    ) {
+        loop {
+            thread::park_timeout(ROUTE_CHECK_INTERVAL);
+            if shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let device = default_output_device_name();
+            if device.is_none() || device == current_device {
+                continue;
+            }
+            log::info!("Audio route changed: {:?} -> {:?}", current_device, device);
+            current_device = device.clone();
+            if let Some(device_name) = device {
+                let _ = event_sender.send(PetalSonicEvent::DeviceChanged { device_name });
+            }
+        }
+    }
+}
+
+impl Drop for RouteMonitor {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(target_os = "ios")]
+mod ios {
+    use crate::config::{AudioSessionCategory, AudioSessionConfig};
+    use crate::error::{PetalSonicError, Result};
+    use std::ffi::{CStr, c_void};
+
+    type Id = *mut c_void;
+    type Sel = *mut c_void;
+
+    /// `AVAudioSessionCategoryOptionMixWithOthers`
+    const CATEGORY_OPTION_MIX_WITH_OTHERS: usize = 0x1;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    unsafe extern "C" {
+        static AVAudioSessionCategoryAmbient: Id;
+        static AVAudioSessionCategorySoloAmbient: Id;
+        static AVAudioSessionCategoryPlayback: Id;
+    }
+
+    #[link(name = "objc")]
+    unsafe extern "C" {
+        fn objc_getClass(name: *const std::ffi::c_char) -> Id;
+        fn sel_registerName(name: *const std::ffi::c_char) -> Sel;
+        fn objc_msgSend();
+    }
+
+    fn selector(name: &CStr) -> Sel {
+        unsafe { sel_registerName(name.as_ptr()) }
+    }
+
+    pub(super) fn configure_audio_session(config: &AudioSessionConfig) -> Result<()> {
+        let category = unsafe {
+            match config.category {
+                AudioSessionCategory::Ambient => AVAudioSessionCategoryAmbient,
+                AudioSessionCategory::SoloAmbient => AVAudioSessionCategorySoloAmbient,
+                AudioSessionCategory::Playback => AVAudioSessionCategoryPlayback,
+            }
+        };
+        let options = if config.mix_with_others {
+            CATEGORY_OPTION_MIX_WITH_OTHERS
+        } else {
+            0
+        };
+
+        // SAFETY: objc_msgSend is called through function pointer types matching the
+        // Objective-C method signatures of the selectors being sent.
+        unsafe {
+            let send_id: extern "C" fn(Id, Sel) -> Id =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            let send_set_category: extern "C" fn(Id, Sel, Id, usize, *mut Id) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            let send_set_active: extern "C" fn(Id, Sel, i8, *mut Id) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+
+            let class = objc_getClass(c"AVAudioSession".as_ptr());
+            if class.is_null() {
+                return Err(PetalSonicError::AudioDevice(
+                    "AVAudioSession class not available".into(),
+                ));
+            }
+
+            let session = send_id(class, selector(c"sharedInstance"));
+            if session.is_null() {
+                return Err(PetalSonicError::AudioDevice(
+                    "Failed to get shared AVAudioSession".into(),
+                ));
+            }
+
+            let ok = send_set_category(
+                session,
+                selector(c"setCategory:withOptions:error:"),
+                category,
+                options,
+                std::ptr::null_mut(),
+            );
+            if ok == 0 {
+                return Err(PetalSonicError::AudioDevice(format!(
+                    "Failed to set AVAudioSession category {:?}",
+                    config.category
+                )));
+            }
+
+            let ok = send_set_active(
+                session,
+                selector(c"setActive:error:"),
+                1,
+                std::ptr::null_mut(),
+            );
+            if ok == 0 {
+                return Err(PetalSonicError::AudioDevice(
+                    "Failed to activate AVAudioSession".into(),
+                ));
+            }
+        }
+
+        log::info!(
+            "Configured AVAudioSession (category: {:?}, mix with others: {})",
+            config.category,
+            config.mix_with_others
+        );
+        Ok(())
+    }
+}
@@ -2,18 +2,63 @@
 //!
 //! This module provides types and functionality for controlling audio playback:
 //! - [`LoopMode`]: Control how audio loops (once, infinite)
+//! - [`PlaybackDirection`]: Play a clip forwards or backwards
 //! - [`PlayState`]: Current playback state (playing, paused, stopped)
 //! - [`PlaybackInfo`]: Detailed playback position and timing information
 //! - [`PlaybackInstance`]: Active playback instance with state management
+//! - [`PlaylistSegment`], [`Playlist`]: Gapless sequences of clips (intro, loop, outro)
 //! - [`PlaybackCommand`]: Commands for controlling playback (internal)
 //!
 //! Most users will interact with playback through [`PetalSonicWorld`](crate::PetalSonicWorld)
 //! methods like `play()`, `pause()`, and `stop()`, rather than using these types directly.
+//!
+//! # Playlists
+//!
+//! A playlist source plays several clips back to back as one source, switching between
+//! them on the exact sample a segment ends, even in the middle of a block. Each segment
+//! plays a fixed number of times or repeats until the host moves on, which is how music
+//! with an intro, a loop and an outro is put together:
+//!
+//! ```ignore
+//! let (source_id, playlist) = world.register_playlist(
+//!     vec![
+//!         PlaylistSegment::new(intro),
+//!         PlaylistSegment::new(combat_loop).looping(),
+//!         PlaylistSegment::new(outro),
+//!     ],
+//!     SourceConfig::non_spatial(),
+//! )?;
+//! world.play(source_id, LoopMode::Once)?;
+//! // once the fight is over, finish the current loop iteration and play the outro:
+//! playlist.advance();
+//! ```
+//!
+//! The playlist completes after the last play of its last segment, or starts over at its
+//! first segment with [`LoopMode::Infinite`]. Playlists always play forward and ignore the
+//! loop regions of their clips.
 
 use crate::audio_data::PetalSonicAudioData;
+use crate::bus_tone::{BusTone, ToneFilter};
+use crate::caption::CaptionTrack;
 use crate::config::SourceConfig;
+use crate::distance_delay::DistanceDelay;
+use crate::envelope::{EnvelopeConfig, EnvelopeFollower};
+use crate::events::PetalSonicEvent;
+use crate::haptics::HapticsCursor;
+use crate::logging::rt_debug;
+use crate::silence::SilenceDetector;
+use crate::source_delay::SourceDelay;
+use crate::spatial::SpatialBypass;
+use crate::voice::SharedLiveSource;
 use crate::world::SourceId;
+use crate::zones::ZoneFilter;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Frames over which a clip played once fades out at its end, so clips ending on a non-zero
+/// sample do not click. Clips shorter than four times this fade over a quarter of their
+/// length.
+const DECLICK_FRAMES: usize = 64;
 
 /// Loop mode for audio playback
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,6 +77,43 @@ impl Default for LoopMode {
     }
 }
 
+/// Direction in which a clip is played
+///
+/// In reverse, a clip plays from its end towards its beginning; with
+/// [`LoopMode::Infinite`] each iteration restarts at the end of the clip (or of its loop
+/// region). Live sources always play forward.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackDirection {
+    /// Play from the beginning towards the end
+    #[default]
+    Forward,
+    /// Play from the end towards the beginning
+    Reverse,
+}
+
+/// Output channels a source is played on
+///
+/// Channels are indices into the world's output channels
+/// ([`PetalSonicWorldDesc::channels`](crate::PetalSonicWorldDesc::channels)), which are the
+/// device channels unless the device was opened with another channel count (then
+/// [`PetalSonicWorldDesc::output_mix`](crate::PetalSonicWorldDesc::output_mix) applies).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputRouting {
+    /// The source's signal on every output channel (default)
+    #[default]
+    AllChannels,
+    /// The source's signal only on the listed channels, bypassing spatialization, e.g. a
+    /// rumble track on the LFE channel
+    Channels(Vec<u16>),
+}
+
+impl OutputRouting {
+    /// Whether the source is routed to specific channels
+    pub fn is_routed(&self) -> bool {
+        matches!(self, Self::Channels(_))
+    }
+}
+
 /// Represents the current playback state of an audio source.
 ///
 /// Used to track whether an audio source is currently playing, paused, or stopped.
@@ -82,8 +164,189 @@ impl PlaybackInfo {
     }
 }
 
+/// A clip of a playlist with how often it plays (see [Playlists](self#playlists))
+#[derive(Debug, Clone)]
+pub struct PlaylistSegment {
+    /// Clip played by the segment
+    pub audio_data: Arc<PetalSonicAudioData>,
+    /// Times the clip plays before the playlist moves on, `None` to repeat it until
+    /// [`Playlist::advance`]
+    pub play_count: Option<u32>,
+}
+
+impl PlaylistSegment {
+    /// Create a segment that plays its clip once
+    pub fn new(audio_data: Arc<PetalSonicAudioData>) -> Self {
+        Self {
+            audio_data,
+            play_count: Some(1),
+        }
+    }
+
+    /// Play the clip `count` times before moving on
+    pub fn with_play_count(mut self, count: u32) -> Self {
+        self.play_count = Some(count);
+        self
+    }
+
+    /// Repeat the clip until [`Playlist::advance`] is called
+    pub fn looping(mut self) -> Self {
+        self.play_count = None;
+        self
+    }
+}
+
+/// Advance requests and progress of a playlist, shared between its [`Playlist`] handle and
+/// the render thread
+#[derive(Debug, Default)]
+struct SharedPlaylist {
+    /// Number of [`Playlist::advance`] calls so far
+    advance_requests: AtomicUsize,
+    /// Index of the segment playing
+    segment: AtomicUsize,
+}
+
+/// Main-thread handle to a playlist source.
+///
+/// Returned by [`PetalSonicWorld::register_playlist`](crate::PetalSonicWorld::register_playlist).
+/// Play, pause and stop the source through the world like any other.
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    shared: Arc<SharedPlaylist>,
+    segment_count: usize,
+}
+
+impl Playlist {
+    /// Leave the segment playing at the end of its current play, whatever its play count.
+    ///
+    /// Each call moves on from one segment: calling twice during a looping segment skips
+    /// the segment after it once that one has played once.
+    pub fn advance(&self) {
+        self.shared.advance_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Index of the segment playing (or last played)
+    pub fn current_segment(&self) -> usize {
+        self.shared.segment.load(Ordering::Relaxed)
+    }
+
+    /// Number of segments in the playlist
+    pub fn segment_count(&self) -> usize {
+        self.segment_count
+    }
+}
+
+/// Segments of a playlist source as registered with the world
+#[derive(Debug, Clone)]
+pub(crate) struct PlaylistSource {
+    segments: Arc<[PlaylistSegment]>,
+    shared: Arc<SharedPlaylist>,
+}
+
+impl PlaylistSource {
+    /// Segments are expected at the world's sample rate
+    pub(crate) fn new(segments: Vec<PlaylistSegment>) -> Self {
+        Self {
+            segments: segments.into(),
+            shared: Arc::new(SharedPlaylist::default()),
+        }
+    }
+
+    pub(crate) fn handle(&self) -> Playlist {
+        Playlist {
+            shared: self.shared.clone(),
+            segment_count: self.segments.len(),
+        }
+    }
+}
+
+/// Place in a playlist: a segment and how far through its plays
+#[derive(Debug, Clone, Copy, Default)]
+struct PlaylistPosition {
+    segment: usize,
+    /// Plays of the segment completed
+    played: u32,
+    /// Advance requests handled
+    advances: usize,
+}
+
+/// Render-side progress of a playlist source
+#[derive(Debug, Clone)]
+struct PlaylistCursor {
+    source: PlaylistSource,
+    position: PlaylistPosition,
+    /// Advance requests seen at the start of the block, so reading and advancing a block
+    /// agree on where it switches segments
+    requested: usize,
+}
+
+impl PlaylistCursor {
+    /// Position after the current play of the segment at `position`, `None` at the end of
+    /// the playlist unless it `wraps` around
+    fn next(&self, position: PlaylistPosition, wraps: bool) -> Option<PlaylistPosition> {
+        let segments = &self.source.segments;
+        let played = position.played + 1;
+        let (segment, played, advances) = if position.advances < self.requested {
+            (position.segment + 1, 0, position.advances + 1)
+        } else if segments[position.segment]
+            .play_count
+            .is_none_or(|count| played < count)
+        {
+            (position.segment, played, position.advances)
+        } else {
+            (position.segment + 1, 0, position.advances)
+        };
+
+        if segment < segments.len() {
+            Some(PlaylistPosition {
+                segment,
+                played,
+                advances,
+            })
+        } else if wraps {
+            Some(PlaylistPosition {
+                segment: 0,
+                played: 0,
+                advances,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn clip(&self, position: PlaylistPosition) -> &Arc<PetalSonicAudioData> {
+        &self.source.segments[position.segment].audio_data
+    }
+}
+
+/// Crossfade of a source switching between the spatial and non-spatial path, rendered over
+/// one block on the spatial path with the non-spatial share split off
+#[derive(Debug, Clone)]
+pub(crate) struct PathFade {
+    /// Non-spatial configuration taking over at the end of the fade, `None` when fading
+    /// into the spatial path
+    pending: Option<SourceConfig>,
+    /// Non-spatial share of the last processed block
+    dry: Vec<f32>,
+}
+
+impl PathFade {
+    /// Split a block into its spatial (kept in place) and non-spatial share
+    fn split(&mut self, samples: &mut [f32]) {
+        let fading_out = self.pending.is_some();
+        let len = samples.len().max(1) as f32;
+        self.dry.clear();
+        for (i, sample) in samples.iter_mut().enumerate() {
+            let t = (i + 1) as f32 / len;
+            let dry_gain = if fading_out { t } else { 1.0 - t };
+            self.dry.push(*sample * dry_gain);
+            *sample *= 1.0 - dry_gain;
+        }
+    }
+}
+
 /// Active playback instance
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlaybackInstance {
     /// SourceId of the audio data being played
     pub audio_id: SourceId,
@@ -95,8 +358,70 @@ pub struct PlaybackInstance {
     pub config: SourceConfig,
     /// Loop mode for this playback
     pub loop_mode: LoopMode,
+    /// Direction the clip is played in
+    pub direction: PlaybackDirection,
     /// Flag to track if we've reached the end this iteration (for event emission)
     pub(crate) reached_end_this_iteration: bool,
+    /// Whether this source is soloed (see [`PetalSonicWorld::solo`](crate::PetalSonicWorld::solo))
+    pub soloed: bool,
+    /// Spatial pipeline stages bypassed for this source
+    pub spatial_bypass: SpatialBypass,
+    /// Output channels this source is played on; routed sources are not spatialized
+    pub output_routing: OutputRouting,
+    /// Output bus this source is mixed into, `None` for the main mix (see
+    /// [`PetalSonicWorld::set_source_bus`](crate::PetalSonicWorld::set_source_bus))
+    pub bus: Option<String>,
+    /// Runtime-fed audio played instead of `audio_data` (see [`crate::voice`])
+    pub(crate) live_source: Option<SharedLiveSource>,
+    /// Segments played in sequence, `audio_data` being the segment playing (see
+    /// [Playlists](self#playlists))
+    playlist: Option<PlaylistCursor>,
+    /// Scratch buffer for mixing a live source or reading the clip
+    scratch: Vec<f32>,
+    /// Envelope follower enabled via [`PetalSonicWorld::enable_envelope`](crate::PetalSonicWorld::enable_envelope)
+    pub(crate) envelope: Option<EnvelopeFollower>,
+    /// Gain and low-pass of the attenuation zones affecting this source
+    pub(crate) zone_filter: ZoneFilter,
+    /// Tone of the source's bus set via [`PetalSonicWorld::set_bus_tone`](crate::PetalSonicWorld::set_bus_tone)
+    pub(crate) tone: Option<ToneFilter>,
+    /// Silence detection enabled via `PetalSonicWorldDesc::silence_detection`
+    pub(crate) silence: Option<SilenceDetector>,
+    /// Caption set via [`PetalSonicWorld::set_caption`](crate::PetalSonicWorld::set_caption)
+    pub(crate) caption: Option<CaptionTrack>,
+    /// Haptics track set via [`PetalSonicWorld::set_haptics_track`](crate::PetalSonicWorld::set_haptics_track)
+    pub(crate) haptics: Option<HapticsCursor>,
+    /// Frame up to which markers were checked for crossings
+    marker_position: usize,
+    /// Propagation delay enabled via `PetalSonicWorldDesc::distance_delay`
+    pub(crate) distance_delay: Option<DistanceDelay>,
+    /// Alignment delay set via [`PetalSonicWorld::set_source_delay`](crate::PetalSonicWorld::set_source_delay)
+    pub(crate) source_delay: Option<SourceDelay>,
+    /// Last processed block scaled by the reverb send, set while the world has a reverb
+    pub(crate) reverb_send: Option<Vec<f32>>,
+    /// Pending switch between the spatial and non-spatial path (see [`Self::update_config`])
+    pub(crate) path_fade: Option<PathFade>,
+    /// Frames left to play before playback ends, set via
+    /// [`PetalSonicWorld::play_for`](crate::PetalSonicWorld::play_for) and
+    /// [`PetalSonicWorld::stop_at`](crate::PetalSonicWorld::stop_at)
+    pub(crate) play_limit: Option<usize>,
+    /// Output frame at which playback ends, turned into `play_limit` when the next block
+    /// is mixed (see [`Self::resolve_stop_at`])
+    pub(crate) stop_at: Option<usize>,
+    /// Volume applied at the end of the last block, `None` before the first block
+    volume: Option<f32>,
+    /// Volume of the source's sync group, applied on top of its own (see
+    /// [`crate::sync_group`])
+    pub(crate) group_gain: f32,
+    /// Frames the sync group played up to the end of the last block, set while the source
+    /// plays as part of its group
+    pub(crate) sync_clock: Option<usize>,
+    /// Peak of the last block after the volume, for debug views
+    pub(crate) level: f32,
+    /// Sample rate the instance renders at
+    sample_rate: u32,
+    /// Clip frames per rendered frame: other than 1 while the clip is converted during
+    /// rendering (see [`ResamplePolicy::Streaming`](crate::config::ResamplePolicy::Streaming))
+    rate_ratio: f64,
 }
 
 impl PlaybackInstance {
@@ -116,13 +441,340 @@ impl PlaybackInstance {
             info,
             config,
             loop_mode,
+            direction: PlaybackDirection::Forward,
             reached_end_this_iteration: false,
+            soloed: false,
+            spatial_bypass: SpatialBypass::NONE,
+            output_routing: OutputRouting::AllChannels,
+            bus: None,
+            live_source: None,
+            playlist: None,
+            scratch: Vec::new(),
+            envelope: None,
+            zone_filter: ZoneFilter::new(sample_rate),
+            tone: None,
+            silence: None,
+            caption: None,
+            haptics: None,
+            marker_position: 0,
+            distance_delay: None,
+            source_delay: None,
+            reverb_send: None,
+            path_fade: None,
+            play_limit: None,
+            stop_at: None,
+            volume: None,
+            group_gain: 1.0,
+            sync_clock: None,
+            level: 0.0,
+            sample_rate,
+            rate_ratio: 1.0,
+        }
+    }
+
+    /// Render at `sample_rate`, converting the clip while reading it if its own rate differs
+    pub(crate) fn with_render_rate(mut self, sample_rate: u32) -> Self {
+        if sample_rate != self.audio_data.sample_rate() {
+            self.sample_rate = sample_rate;
+            self.rate_ratio = self.audio_data.sample_rate() as f64 / sample_rate as f64;
+            self.info = PlaybackInfo::new(self.clip_frames(), sample_rate);
+            self.zone_filter = ZoneFilter::new(sample_rate);
+        }
+        self
+    }
+
+    /// Length of the clip in rendered frames
+    fn clip_frames(&self) -> usize {
+        (self.audio_data.samples().len() as f64 / self.rate_ratio) as usize
+    }
+
+    /// Rendered frame corresponding to a frame of the clip
+    fn render_frame(&self, clip_frame: usize) -> usize {
+        (clip_frame as f64 / self.rate_ratio).round() as usize
+    }
+
+    /// Play a live source instead of the clip
+    pub(crate) fn with_live_source(mut self, live_source: Option<SharedLiveSource>) -> Self {
+        self.live_source = live_source;
+        self
+    }
+
+    /// Play the segments of a playlist, starting with the first one, which must be the
+    /// instance's clip
+    pub(crate) fn with_playlist(mut self, playlist: Option<PlaylistSource>) -> Self {
+        self.playlist = playlist.map(|source| PlaylistCursor {
+            requested: source.shared.advance_requests.load(Ordering::Relaxed),
+            source,
+            position: PlaylistPosition::default(),
+        });
+        self
+    }
+
+    /// Pick up the playlist advance requests made since the last block
+    pub(crate) fn poll_playlist(&mut self) {
+        if let Some(playlist) = self.playlist.as_mut() {
+            playlist.requested = playlist
+                .source
+                .shared
+                .advance_requests
+                .load(Ordering::Relaxed);
+        }
+    }
+
+    /// Switch to the clip of a playlist position, at its beginning
+    fn enter_playlist_position(&mut self, position: PlaylistPosition) {
+        let Some(playlist) = self.playlist.as_mut() else {
+            return;
+        };
+        if position.segment != playlist.position.segment {
+            rt_debug!(
+                "Source {} playlist moving to segment {}",
+                self.audio_id,
+                position.segment
+            );
+        }
+        playlist.position = position;
+        playlist
+            .source
+            .shared
+            .segment
+            .store(position.segment, Ordering::Relaxed);
+        self.audio_data = playlist.clip(position).clone();
+        self.info.total_frames = self.clip_frames();
+        self.info.total_time = self.info.total_frames as f64 / self.sample_rate as f64;
+        self.info.update_position(0, self.sample_rate);
+        self.marker_position = 0;
+    }
+
+    /// Apply a new configuration
+    ///
+    /// A source switching between the spatial and non-spatial path is crossfaded over the
+    /// next block it plays: the mixer renders that block on the spatial path, with the
+    /// spatial configuration, while the non-spatial share fades in or out beside it.
+    pub(crate) fn update_config(&mut self, config: SourceConfig) {
+        match self.path_fade.as_mut() {
+            // Still fading out of the spatial path: either retarget or cancel the fade
+            Some(fade) if fade.pending.is_some() => {
+                if config.is_spatial() {
+                    self.path_fade = None;
+                    self.config = config;
+                } else {
+                    fade.pending = Some(config);
+                }
+            }
+            // Still fading into the spatial path
+            Some(_) => {
+                if !config.is_spatial() {
+                    self.path_fade = None;
+                }
+                self.config = config;
+            }
+            None => match (self.config.is_spatial(), config.is_spatial()) {
+                (true, false) => {
+                    self.path_fade = Some(PathFade {
+                        pending: Some(config),
+                        dry: Vec::new(),
+                    });
+                }
+                (false, true) => {
+                    self.path_fade = Some(PathFade {
+                        pending: None,
+                        dry: Vec::new(),
+                    });
+                    self.config = config;
+                }
+                _ => self.config = config,
+            },
+        }
+    }
+
+    /// Mix the non-spatial share of the last block of a path crossfade into an interleaved
+    /// buffer
+    pub(crate) fn mix_path_fade(&self, buffer: &mut [f32], channels: u16) {
+        if let Some(fade) = self.path_fade.as_ref() {
+            self.mix_routed(buffer, channels as usize, &fade.dry);
+        }
+    }
+
+    /// End the path crossfade, applying the pending non-spatial configuration. Returns true
+    /// if the source left the spatial path.
+    pub(crate) fn finish_path_fade(&mut self) -> bool {
+        match self.path_fade.take().and_then(|fade| fade.pending) {
+            Some(config) => {
+                self.config = config;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply the per-source processing (attenuation zones, bus tone) to a block of the
+    /// source's mono signal and follow it with the envelope follower and silence detection, if
+    /// enabled. The block is captured for the reverb send and, while switching paths,
+    /// split into its spatial and non-spatial share.
+    pub(crate) fn process_block(&mut self, samples: &mut [f32]) {
+        self.zone_filter.process(samples);
+        if let Some(tone) = self.tone.as_mut() {
+            tone.process(samples);
+        }
+        if let Some(send) = self.reverb_send.as_mut() {
+            let gain = self.config.reverb_send();
+            send.clear();
+            if gain > 0.0 {
+                send.extend(samples.iter().map(|sample| sample * gain));
+            }
+        }
+        if let Some(envelope) = self.envelope.as_mut() {
+            envelope.process(samples);
+        }
+        if let Some(silence) = self.silence.as_mut() {
+            silence.process(samples);
+        }
+        if let Some(fade) = self.path_fade.as_mut() {
+            fade.split(samples);
+        }
+    }
+
+    /// Scale a processed block by the source volume, ramping from the volume of the
+    /// previous block so volume changes do not cause zipper noise
+    pub(crate) fn apply_volume(&mut self, samples: &mut [f32]) {
+        let target = self.config.volume().unwrap_or(1.0) * self.group_gain;
+        let start = self.volume.unwrap_or(target);
+        self.volume = Some(target);
+
+        if start == target {
+            if target != 1.0 {
+                samples.iter_mut().for_each(|sample| *sample *= target);
+            }
+        } else {
+            let step = (target - start) / samples.len().max(1) as f32;
+            for (i, sample) in samples.iter_mut().enumerate() {
+                *sample *= start + step * (i + 1) as f32;
+            }
+        }
+        self.level = samples
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    }
+
+    /// Run the first `frames` frames of a block read from the clip through the alignment
+    /// and distance delays, if enabled. Returns the number of leading frames that can carry
+    /// signal.
+    pub(crate) fn apply_delays(&mut self, samples: &mut [f32], frames: usize) -> usize {
+        let frames = match self.source_delay.as_mut() {
+            Some(delay) => delay.process(samples, frames),
+            None => frames,
+        };
+        match self.distance_delay.as_mut() {
+            Some(delay) => delay.process(samples, frames),
+            None => frames,
+        }
+    }
+
+    /// Returns true once playback reached the end (the beginning when playing in reverse)
+    /// or its play limit. Live sources never finish.
+    pub fn is_finished(&self) -> bool {
+        let limit_reached =
+            self.play_limit == Some(0) && !matches!(self.info.play_state, PlayState::Playing);
+        self.live_source.is_none()
+            && (limit_reached
+                || match self.direction {
+                    PlaybackDirection::Forward => self.info.is_finished(),
+                    PlaybackDirection::Reverse => self.info.current_frame == 0,
+                })
+    }
+
+    /// End playback after `frames` more rendered frames, replacing an earlier limit
+    pub(crate) fn stop_after(&mut self, frames: usize) {
+        self.play_limit = Some(frames);
+        self.stop_at = None;
+    }
+
+    /// Turn a pending [`Self::stop_at`] into a play limit, now that the next block is heard
+    /// from `output_frame`. A stop time already passed ends playback with this block.
+    pub(crate) fn resolve_stop_at(&mut self, output_frame: usize) {
+        if let Some(stop_at) = self.stop_at.take() {
+            self.play_limit = Some(stop_at.saturating_sub(output_frame));
+        }
+    }
+
+    /// Read the next block of a live source into `output`.
+    ///
+    /// Returns `None` for clip sources, otherwise whether the block carries voice (`false`
+    /// if it is gated and only holds comfort noise).
+    pub(crate) fn read_live(&mut self, output: &mut [f32]) -> Option<bool> {
+        let live_source = self.live_source.as_ref()?;
+        let Ok(mut live_source) = live_source.try_lock() else {
+            output.fill(0.0);
+            return Some(false);
+        };
+        Some(live_source.read(output))
+    }
+
+    /// Returns the talking state of a live source if it changed since the last call
+    pub(crate) fn take_voice_activity_change(&mut self) -> Option<bool> {
+        self.live_source
+            .as_ref()?
+            .try_lock()
+            .ok()?
+            .take_activity_change()
+    }
+
+    /// Collect the caption events due after the last block, while playing or on reaching
+    /// the end of the clip
+    pub(crate) fn take_captions(&mut self, events: &mut Vec<PetalSonicEvent>) {
+        let playing = matches!(self.info.play_state, PlayState::Playing);
+        if let Some(caption) = self.caption.as_mut()
+            && (playing || self.reached_end_this_iteration)
+        {
+            caption.advance(
+                self.audio_id,
+                self.info.current_time,
+                self.info.total_time,
+                events,
+            );
+        }
+    }
+
+    /// Collect a marker event for each marker of the clip crossed since the last block, in
+    /// either direction, while playing or on reaching the end of the clip
+    pub(crate) fn take_markers(&mut self, events: &mut Vec<PetalSonicEvent>) {
+        let playing = matches!(self.info.play_state, PlayState::Playing);
+        if self.audio_data.markers().is_empty() || (!playing && !self.reached_end_this_iteration) {
+            return;
+        }
+
+        // The frames played since the last block, wherever the cursor moved
+        let position = self.info.current_frame;
+        let last = std::mem::replace(&mut self.marker_position, position);
+        let played = last.min(position)..last.max(position);
+        for marker in self.audio_data.markers() {
+            if played.contains(&self.render_frame(marker.frame)) {
+                events.push(PetalSonicEvent::Marker {
+                    source_id: self.audio_id,
+                    name: marker.name.clone(),
+                    frame: marker.frame,
+                });
+            }
         }
     }
 
+    /// Peak intensity of the haptics track over the last block, while playing or on
+    /// reaching the end of the clip
+    pub(crate) fn take_haptics(&mut self) -> Option<f32> {
+        let playing = matches!(self.info.play_state, PlayState::Playing);
+        if !playing && !self.reached_end_this_iteration {
+            return None;
+        }
+        let position = self.info.current_frame;
+        self.haptics
+            .as_mut()
+            .map(|haptics| haptics.advance(position))
+    }
+
     /// Resume playing from current position
     pub fn resume(&mut self) {
-        log::debug!(
+        rt_debug!(
             "Source {} resuming from frame {} (loop mode: {:?})",
             self.audio_id,
             self.info.current_frame,
@@ -133,25 +785,325 @@ impl PlaybackInstance {
 
     /// Reset playback cursor to the beginning
     pub fn reset(&mut self) {
-        log::debug!("Source {} resetting cursor to beginning", self.audio_id);
+        rt_debug!("Source {} resetting cursor to beginning", self.audio_id);
         self.info.current_frame = 0;
         self.info.current_time = 0.0;
     }
 
-    /// Play from the beginning (reset + resume)
+    /// Frame at which the current iteration ends: the end of the clip's loop region for
+    /// `LoopMode::Infinite`, otherwise the end of the clip
+    pub(crate) fn end_frame(&self) -> usize {
+        let total_frames = self.clip_frames();
+        match (self.loop_mode, self.audio_data.loop_region()) {
+            (LoopMode::Infinite, Some(region)) if self.playlist.is_none() => {
+                self.render_frame(region.end_frame).min(total_frames)
+            }
+            _ => total_frames,
+        }
+    }
+
+    /// Frame at which the current iteration starts: the start of the clip's loop region
+    /// for `LoopMode::Infinite`, otherwise the beginning of the clip
+    fn start_frame(&self) -> usize {
+        match (self.loop_mode, self.audio_data.loop_region()) {
+            (LoopMode::Infinite, Some(region)) if self.playlist.is_none() => {
+                self.render_frame(region.start_frame).min(self.end_frame())
+            }
+            _ => 0,
+        }
+    }
+
+    /// Frames left until the end of the current iteration in playback direction, or until
+    /// the play limit if it comes first
+    pub(crate) fn remaining_frames(&self) -> usize {
+        let remaining = self.iteration_remaining_frames();
+        self.play_limit
+            .map_or(remaining, |limit| remaining.min(limit))
+    }
+
+    /// Frames left until the end of the current iteration in playback direction
+    fn iteration_remaining_frames(&self) -> usize {
+        match self.direction {
+            PlaybackDirection::Forward => self.end_frame().saturating_sub(self.info.current_frame),
+            PlaybackDirection::Reverse => self
+                .info
+                .current_frame
+                .min(self.end_frame())
+                .saturating_sub(self.start_frame()),
+        }
+    }
+
+    /// Copy the next frames of the clip in playback direction into `output` without
+    /// advancing the cursor. Returns the number of frames copied; the rest of `output` is
+    /// left untouched.
+    ///
+    /// When playing once, the last frames of the clip are faded out (see
+    /// [`DECLICK_FRAMES`]), as are the last frames before the play limit. Playlists
+    /// continue into the following segments up to the end of `output`.
+    pub(crate) fn read_clip(&self, output: &mut [f32]) -> usize {
+        let frames = output.len().min(self.remaining_frames());
+        self.copy_clip(&mut output[..frames]);
+        if let Some(playlist) = self.playlist.as_ref() {
+            return self.read_playlist(playlist, output, frames);
+        }
+
+        let limit_ends_iteration = self
+            .play_limit
+            .is_some_and(|limit| limit <= self.iteration_remaining_frames());
+        if self.loop_mode == LoopMode::Once || limit_ends_iteration {
+            let fade = DECLICK_FRAMES.min((self.end_frame() - self.start_frame()) / 4);
+            let remaining = self.remaining_frames();
+            for (index, sample) in output[..frames]
+                .iter_mut()
+                .enumerate()
+                .skip(remaining.saturating_sub(fade))
+            {
+                *sample *= (remaining - index) as f32 / (fade + 1) as f32;
+            }
+        }
+        frames
+    }
+
+    /// Continue a block of a playlist read up to `frames` from the segment playing into the
+    /// segments after it, and fade out the end of the playlist or the play limit. Returns
+    /// the number of frames read.
+    fn read_playlist(&self, playlist: &PlaylistCursor, output: &mut [f32], frames: usize) -> usize {
+        let budget = self
+            .play_limit
+            .map_or(output.len(), |limit| limit.min(output.len()));
+        let wraps = self.loop_mode == LoopMode::Infinite;
+        let mut frames = frames;
+        let mut position = playlist.position;
+        let mut play_remaining = self.iteration_remaining_frames().saturating_sub(frames);
+        while play_remaining == 0 && frames < budget {
+            let Some(next) = playlist.next(position, wraps) else {
+                break;
+            };
+            position = next;
+            let samples = playlist.clip(position).samples();
+            let count = (budget - frames).min(samples.len());
+            output[frames..frames + count].copy_from_slice(&samples[..count]);
+            frames += count;
+            play_remaining = samples.len() - count;
+        }
+
+        // Frames until playback ends, counted from the start of the block
+        let playlist_end = playlist
+            .next(position, wraps)
+            .is_none()
+            .then_some(frames + play_remaining);
+        if let Some(end) = playlist_end.into_iter().chain(self.play_limit).min() {
+            let fade = DECLICK_FRAMES.min(playlist.clip(position).samples().len() / 4);
+            for (index, sample) in output[..end.min(frames)]
+                .iter_mut()
+                .enumerate()
+                .skip(end.saturating_sub(fade))
+            {
+                *sample *= (end - index) as f32 / (fade + 1) as f32;
+            }
+        }
+        frames
+    }
+
+    /// Copy the next `output.len()` frames of the clip in playback direction into `output`
+    fn copy_clip(&self, output: &mut [f32]) {
+        let samples = self.audio_data.samples();
+        let frames = output.len();
+        let current = self.info.current_frame;
+        if self.rate_ratio != 1.0 {
+            for (index, output) in output.iter_mut().enumerate() {
+                let frame = match self.direction {
+                    PlaybackDirection::Forward => current + index,
+                    PlaybackDirection::Reverse => current.min(self.end_frame()) - 1 - index,
+                };
+                *output = self.interpolate(frame);
+            }
+            return;
+        }
+
+        match self.direction {
+            PlaybackDirection::Forward => {
+                output.copy_from_slice(&samples[current..current + frames]);
+            }
+            PlaybackDirection::Reverse => {
+                let current = current.min(self.end_frame());
+                for (output, sample) in output
+                    .iter_mut()
+                    .zip(samples[current - frames..current].iter().rev())
+                {
+                    *output = *sample;
+                }
+            }
+        }
+    }
+
+    /// Clip sample at a rendered frame, interpolated with a Catmull-Rom spline
+    fn interpolate(&self, frame: usize) -> f32 {
+        let samples = self.audio_data.samples();
+        let position = frame as f64 * self.rate_ratio;
+        let index = position as isize;
+        let t = (position - index as f64) as f32;
+        let sample =
+            |offset: isize| samples[(index + offset).clamp(0, samples.len() as isize - 1) as usize];
+        let (y0, y1, y2, y3) = (sample(-1), sample(0), sample(1), sample(2));
+        y1 + 0.5
+            * t
+            * (y2 - y0
+                + t * (2.0 * y0 - 5.0 * y1 + 4.0 * y2 - y3 + t * (3.0 * (y1 - y2) + y3 - y0)))
+    }
+
+    /// Set the playback direction; the clip continues from the current position
+    pub fn set_direction(&mut self, direction: PlaybackDirection) {
+        if self.live_source.is_some() || self.playlist.is_some() {
+            return;
+        }
+        rt_debug!(
+            "Source {} direction changed: {:?} -> {:?}",
+            self.audio_id,
+            self.direction,
+            direction
+        );
+        self.direction = direction;
+    }
+
+    /// Start the next loop iteration: jump to the start of the clip's loop region (or the
+    /// beginning of the clip) and resume. In reverse, jump to the end of the loop region
+    /// (or of the clip) instead.
+    pub fn restart_loop(&mut self) {
+        let start_frame = match self.direction {
+            PlaybackDirection::Forward => self
+                .audio_data
+                .loop_region()
+                .map_or(0, |region| self.render_frame(region.start_frame)),
+            PlaybackDirection::Reverse => self.end_frame(),
+        };
+        rt_debug!(
+            "Source {} looping back to frame {}",
+            self.audio_id,
+            start_frame
+        );
+        self.info.update_position(start_frame, self.sample_rate);
+        if let Some(caption) = self.caption.as_mut() {
+            caption.restart(self.info.current_time);
+        }
+        if let Some(haptics) = self.haptics.as_mut() {
+            haptics.restart(self.info.current_frame);
+        }
+        self.marker_position = self.info.current_frame;
+        self.resume();
+    }
+
+    /// Play from the beginning (reset + resume); from the end when playing in reverse
     pub fn play_from_beginning(&mut self) {
-        log::debug!(
-            "Source {} playing from beginning (loop mode: {:?})",
+        match self.direction {
+            PlaybackDirection::Forward => self.play_from_frame(0),
+            PlaybackDirection::Reverse => self.play_from_frame(self.end_frame()),
+        }
+    }
+
+    /// Play from `start_frame` of the clip (clamped to its end). Playlists start over at
+    /// their first segment, discarding pending advance requests.
+    pub fn play_from_frame(&mut self, start_frame: usize) {
+        rt_debug!(
+            "Source {} playing from frame {} (loop mode: {:?})",
             self.audio_id,
+            start_frame,
             self.loop_mode
         );
-        self.reset();
+        self.seek(start_frame);
         self.resume();
     }
 
+    /// Move to `start_frame` of the clip without changing the play state, as
+    /// [`Self::play_from_frame`] does before resuming
+    pub(crate) fn seek(&mut self, start_frame: usize) {
+        if let Some(playlist) = self.playlist.as_ref() {
+            let position = PlaylistPosition {
+                advances: playlist.requested,
+                ..Default::default()
+            };
+            self.enter_playlist_position(position);
+        }
+        self.info.update_position(start_frame, self.sample_rate);
+        self.play_limit = None;
+        self.stop_at = None;
+        if let Some(caption) = self.caption.as_mut() {
+            caption.restart(self.info.current_time);
+        }
+        if let Some(haptics) = self.haptics.as_mut() {
+            haptics.restart(self.info.current_frame);
+        }
+        self.marker_position = self.info.current_frame;
+        if let Some(delay) = self.distance_delay.as_mut() {
+            delay.reset();
+        }
+        if let Some(delay) = self.source_delay.as_mut() {
+            delay.reset();
+        }
+        if let Some(live_source) = &self.live_source
+            && let Ok(mut live_source) = live_source.try_lock()
+        {
+            live_source.reset();
+        }
+    }
+
+    /// Advance the sync group clock by a block of `frames` frames, if the source plays as
+    /// part of its group
+    pub(crate) fn advance_sync_clock(&mut self, frames: usize) {
+        if let Some(clock) = self.sync_clock.as_mut() {
+            *clock += frames;
+        }
+    }
+
+    /// Move the source to where its sync group clock says it should be, after its loop
+    /// restarts or when its clip length differs from the other members'
+    pub(crate) fn resync(&mut self) {
+        let Some(clock) = self.sync_clock else {
+            return;
+        };
+        if !matches!(self.info.play_state, PlayState::Playing)
+            || self.direction != PlaybackDirection::Forward
+            || self.live_source.is_some()
+            || self.playlist.is_some()
+        {
+            return;
+        }
+
+        let end_frame = self.end_frame();
+        let expected = match self.loop_mode {
+            LoopMode::Infinite if clock >= end_frame => {
+                let start_frame = self.start_frame();
+                let length = end_frame - start_frame;
+                if length == 0 {
+                    return;
+                }
+                start_frame + (clock - end_frame) % length
+            }
+            _ => clock.min(end_frame),
+        };
+        if expected == self.info.current_frame {
+            return;
+        }
+
+        rt_debug!(
+            "Source {} resynced to its group from frame {} to {}",
+            self.audio_id,
+            self.info.current_frame,
+            expected
+        );
+        self.info.update_position(expected, self.sample_rate);
+        if let Some(caption) = self.caption.as_mut() {
+            caption.restart(self.info.current_time);
+        }
+        if let Some(haptics) = self.haptics.as_mut() {
+            haptics.restart(expected);
+        }
+        self.marker_position = expected;
+    }
+
     /// Set the loop mode
     pub fn set_loop_mode(&mut self, loop_mode: LoopMode) {
-        log::debug!(
+        rt_debug!(
             "Source {} loop mode changed: {:?} -> {:?}",
             self.audio_id,
             self.loop_mode,
@@ -162,22 +1114,28 @@ impl PlaybackInstance {
 
     /// Pause this instance
     pub fn pause(&mut self) {
-        log::debug!(
+        rt_debug!(
             "Source {} paused at frame {}",
             self.audio_id,
             self.info.current_frame
         );
         self.info.play_state = PlayState::Paused;
+        if let Some(envelope) = self.envelope.as_mut() {
+            envelope.reset();
+        }
     }
 
     /// Stop this instance (keeps current position)
     pub fn stop(&mut self) {
-        log::debug!(
+        rt_debug!(
             "Source {} stopped at frame {}",
             self.audio_id,
             self.info.current_frame
         );
         self.info.play_state = PlayState::Stopped;
+        if let Some(envelope) = self.envelope.as_mut() {
+            envelope.reset();
+        }
     }
 
     /// Advance playback cursor and check for completion
@@ -191,22 +1149,51 @@ impl PlaybackInstance {
     ///
     /// # Behavior
     /// - Updates current_frame and timing info
-    /// - If reached end of audio data:
+    /// - If reached end of audio data (or of the loop region, see [`Self::end_frame`], or the
+    ///   play limit):
     ///   - Sets `reached_end_this_iteration` flag for event emission
     ///   - Sets state to Stopped (for BOTH Once and Infinite modes)
     ///   - The mixer will handle restart for Infinite mode
     pub(crate) fn advance_and_check_completion(&mut self, frames_consumed: usize) {
-        self.info.current_frame += frames_consumed;
-        self.info
-            .update_position(self.info.current_frame, self.audio_data.sample_rate());
+        let frames_consumed = self.advance_playlist(frames_consumed);
+        let frames_consumed = frames_consumed.min(self.remaining_frames());
+        let current_frame = match self.direction {
+            PlaybackDirection::Forward => self.info.current_frame + frames_consumed,
+            PlaybackDirection::Reverse => {
+                self.info.current_frame.min(self.end_frame()) - frames_consumed
+            }
+        };
+        self.info.update_position(current_frame, self.sample_rate);
 
-        // Check if we've reached the end
-        if self.info.current_frame >= self.audio_data.samples().len() {
-            log::debug!(
+        // A play limit ends playback as the end of a final iteration would
+        if let Some(limit) = self.play_limit.as_mut() {
+            *limit -= frames_consumed;
+            if *limit == 0 {
+                self.loop_mode = LoopMode::Once;
+            }
+        }
+
+        // Check if we've reached the end (the iteration start in reverse)
+        let end_frame = match self.direction {
+            PlaybackDirection::Forward => self.end_frame(),
+            PlaybackDirection::Reverse => self.start_frame(),
+        };
+        // The final iteration ends once its end has left the delay lines
+        let draining = self.loop_mode == LoopMode::Once
+            && (self
+                .distance_delay
+                .as_ref()
+                .is_some_and(DistanceDelay::is_draining)
+                || self
+                    .source_delay
+                    .as_ref()
+                    .is_some_and(SourceDelay::is_draining));
+        if self.remaining_frames() == 0 && !draining {
+            rt_debug!(
                 "Source {} reached end at frame {}/{} (loop mode: {:?}, consumed {} frames)",
                 self.audio_id,
                 self.info.current_frame,
-                self.audio_data.samples().len(),
+                end_frame,
                 self.loop_mode,
                 frames_consumed
             );
@@ -219,9 +1206,37 @@ impl PlaybackInstance {
         }
     }
 
+    /// Move a playlist through the segments that end within `frames_consumed` frames, the
+    /// way [`Self::read_clip`] read them. Returns the frames left to consume in the segment
+    /// reached.
+    fn advance_playlist(&mut self, mut frames_consumed: usize) -> usize {
+        let wraps = self.loop_mode == LoopMode::Infinite;
+        loop {
+            let Some(playlist) = self.playlist.as_ref() else {
+                return frames_consumed;
+            };
+            let play_remaining = self.iteration_remaining_frames();
+            if frames_consumed < play_remaining
+                || self.play_limit.is_some_and(|limit| limit <= play_remaining)
+            {
+                return frames_consumed;
+            }
+            let Some(next) = playlist.next(playlist.position, wraps) else {
+                return frames_consumed;
+            };
+            frames_consumed -= play_remaining;
+            if let Some(limit) = self.play_limit.as_mut() {
+                *limit -= play_remaining;
+            }
+            self.enter_playlist_position(next);
+        }
+    }
+
     /// Fill audio buffer for this instance
     /// Returns the number of frames actually filled
     ///
+    /// The source volume is applied, ramped across the block when it changed.
+    ///
     /// # Behavior
     /// When reaching the end of audio data:
     /// - Calls advance_and_check_completion() which handles all completion logic
@@ -234,36 +1249,92 @@ impl PlaybackInstance {
 
         let channels_usize = channels as usize;
         let frame_count = buffer.len() / channels_usize;
-        let samples = self.audio_data.samples();
-        let mut frames_filled = 0;
 
-        for frame_idx in 0..frame_count {
-            let sample_idx = self.info.current_frame + frame_idx;
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize(frame_count, 0.0);
 
-            if sample_idx >= samples.len() {
-                // Reached end - stop here
-                break;
-            }
+        if self.live_source.is_some() {
+            self.read_live(&mut scratch);
+            self.process_block(&mut scratch);
+            self.apply_volume(&mut scratch);
+            self.mix_routed(buffer, channels_usize, &scratch);
+            self.scratch = scratch;
+            return frame_count;
+        }
+
+        // Stops early at the end of the clip (or loop region)
+        let clip_frames = self.read_clip(&mut scratch);
+        let frames_filled = self.apply_delays(&mut scratch, clip_frames);
+        self.process_block(&mut scratch[..frames_filled]);
+        self.apply_volume(&mut scratch[..frames_filled]);
+        self.mix_routed(buffer, channels_usize, &scratch[..frames_filled]);
+        self.scratch = scratch;
+
+        // Advance cursor and check for completion (single source of truth!)
+        if clip_frames > 0 || self.remaining_frames() == 0 {
+            self.advance_and_check_completion(clip_frames);
+        }
 
-            let sample = samples[sample_idx];
+        frames_filled
+    }
 
+    /// Mix mono samples into an interleaved buffer on the channels of the output routing
+    fn mix_routed(&self, buffer: &mut [f32], channels: usize, samples: &[f32]) {
+        match &self.output_routing {
             // Fill all channels with the same sample (mono to stereo)
-            for channel in 0..channels_usize {
-                let buffer_idx = frame_idx * channels_usize + channel;
-                if buffer_idx < buffer.len() {
-                    buffer[buffer_idx] += sample; // Mix into existing buffer
+            OutputRouting::AllChannels => {
+                for (frame, sample) in buffer.chunks_exact_mut(channels).zip(samples) {
+                    frame.iter_mut().for_each(|output| *output += sample);
                 }
             }
+            OutputRouting::Channels(routed) => {
+                for (frame, sample) in buffer.chunks_exact_mut(channels).zip(samples) {
+                    for channel in routed {
+                        if let Some(output) = frame.get_mut(*channel as usize) {
+                            *output += sample;
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-            frames_filled += 1;
+    /// Advance the playback cursor without producing audio
+    /// Returns the number of frames skipped
+    ///
+    /// Used by the mixer for sources muted by solo, so they stay in sync with the rest of
+    /// the mix and still emit completion/loop events.
+    pub fn skip_frames(&mut self, frame_count: usize) -> usize {
+        if !matches!(self.info.play_state, PlayState::Playing) {
+            return 0;
         }
 
-        // Advance cursor and check for completion (single source of truth!)
-        if frames_filled > 0 {
-            self.advance_and_check_completion(frames_filled);
+        // Muted sources are silent in the mix
+        if let Some(envelope) = self.envelope.as_mut() {
+            envelope.process_silence(frame_count);
         }
 
-        frames_filled
+        // Live sources keep consuming their input so they stay current
+        if self.live_source.is_some() {
+            let mut scratch = std::mem::take(&mut self.scratch);
+            scratch.resize(frame_count, 0.0);
+            self.read_live(&mut scratch);
+            self.scratch = scratch;
+            return frame_count;
+        }
+
+        // Playlists skip on into the following segments
+        let frames_skipped = if self.playlist.is_some() {
+            frame_count.min(self.play_limit.unwrap_or(usize::MAX))
+        } else {
+            frame_count.min(self.remaining_frames())
+        };
+
+        if frames_skipped > 0 {
+            self.advance_and_check_completion(frames_skipped);
+        }
+
+        frames_skipped
     }
 
     /// Check if this instance reached the end of playback this iteration
@@ -290,12 +1361,28 @@ impl PlaybackInstance {
 /// - `Play`: Start playing an audio source with specified configuration and loop mode
 /// - `Pause`: Pause a playing audio source
 /// - `Stop`: Stop an audio source and reset its position
+/// - `StopAfter`, `StopAt`: End playback of an audio source at a precise frame
 /// - `StopAll`: Stop all currently playing audio sources
 /// - `UpdateConfig`: Update the spatial configuration of a playing source
+/// - `SetSolo`: Solo or unsolo a source
+/// - `SetSpatialBypass`: Bypass spatial pipeline stages for a source
+/// - `SetEnvelopeFollower`: Enable or disable the envelope follower of a source
+/// - `SetOutputRouting`: Route a source to specific output channels
+/// - `SetBus`: Assign a source to an output bus
+/// - `SetSourceDelay`: Set the alignment delay of a source
+/// - `SetBusTone`: Set the tone of the sources on a bus
+/// - `PlayGroup`, `PauseGroup`, `ResumeGroup`, `SeekGroup`, `StopGroup`, `SetGroupVolume`,
+///   `LeaveGroup`: Control the members of a sync group in the same block
 #[derive(Debug)]
 pub enum PlaybackCommand {
     /// Play a source with given configuration and loop mode
     Play(SourceId, SourceConfig, LoopMode),
+    /// Play a source starting at the given frame of its clip
+    PlayFrom(SourceId, SourceConfig, LoopMode, usize),
+    /// End a source's playback after the given number of rendered frames
+    StopAfter(SourceId, usize),
+    /// End a source's playback at the given output frame
+    StopAt(SourceId, usize),
     /// Pause a specific source
     Pause(SourceId),
     /// Stop a specific source
@@ -304,4 +1391,34 @@ pub enum PlaybackCommand {
     StopAll,
     /// Update the configuration of a source
     UpdateConfig(SourceId, SourceConfig),
+    /// Solo (true) or unsolo (false) a source
+    SetSolo(SourceId, bool),
+    /// Set the spatial pipeline stages bypassed for a source
+    SetSpatialBypass(SourceId, SpatialBypass),
+    /// Enable (`Some`) or disable (`None`) the envelope follower of a source
+    SetEnvelopeFollower(SourceId, Option<EnvelopeConfig>),
+    /// Set the direction a source's clip is played in
+    SetDirection(SourceId, PlaybackDirection),
+    /// Set the output channels a source is played on
+    SetOutputRouting(SourceId, OutputRouting),
+    /// Assign a source to an output bus (`None` for the main mix)
+    SetBus(SourceId, Option<String>),
+    /// Set the alignment delay of a source in frames
+    SetSourceDelay(SourceId, usize),
+    /// Set the tone of the sources on a bus (`None` for the main mix)
+    SetBusTone(Option<String>, BusTone),
+    /// Play the members of a sync group together from the given frame
+    PlayGroup(Vec<(SourceId, SourceConfig)>, LoopMode, usize),
+    /// Pause the members of a sync group together
+    PauseGroup(Vec<SourceId>),
+    /// Resume the paused members of a sync group together
+    ResumeGroup(Vec<SourceId>),
+    /// Move the members of a sync group together to the given frame
+    SeekGroup(Vec<SourceId>, usize),
+    /// Stop the members of a sync group together
+    StopGroup(Vec<SourceId>),
+    /// Set the group volume of the members of a sync group
+    SetGroupVolume(Vec<SourceId>, f32),
+    /// Take sources out of their sync group's clock (the group was removed)
+    LeaveGroup(Vec<SourceId>),
 }
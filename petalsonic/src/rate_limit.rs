@@ -0,0 +1,165 @@
+//! Per-tag rate limits for play triggers.
+//!
+//! Chaotic gameplay can trigger the same kind of sound dozens of times in a few
+//! milliseconds (bullet impacts, debris), which wastes voices and turns into noise. Sources
+//! can be tagged with [`PetalSonicWorld::set_source_tag`](crate::PetalSonicWorld::set_source_tag)
+//! and each tag limited to a number of triggers per time window:
+//!
+//! ```ignore
+//! world.set_source_tag(impact_a, Some("bullet_impact"))?;
+//! world.set_source_tag(impact_b, Some("bullet_impact"))?;
+//! world.set_rate_limit("bullet_impact", Some(RateLimit::new(5, Duration::from_millis(100))));
+//! // later, while tuning:
+//! let stats = world.rate_limit_stats();
+//! ```
+//!
+//! Limits are enforced by [`PetalSonicWorld::play`](crate::PetalSonicWorld::play) and
+//! [`PetalSonicWorld::play_from`](crate::PetalSonicWorld::play_from) before the play
+//! command is sent. An excess trigger is either dropped or merged into the tag's most
+//! recent trigger, which restarts that source instead of starting another one.
+
+use crate::world::SourceId;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// What happens to a trigger beyond a tag's limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitOverflow {
+    /// Ignore the trigger
+    #[default]
+    Drop,
+    /// Restart the source of the tag's most recent trigger instead, so the event is still
+    /// heard without adding a voice
+    Merge,
+}
+
+/// Maximum number of triggers of a tag within a sliding time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_triggers: u32,
+    pub window: Duration,
+    pub overflow: RateLimitOverflow,
+}
+
+impl RateLimit {
+    /// Limit dropping triggers beyond `max_triggers` per `window`
+    pub fn new(max_triggers: u32, window: Duration) -> Self {
+        Self {
+            max_triggers,
+            window,
+            overflow: RateLimitOverflow::Drop,
+        }
+    }
+
+    /// Use `overflow` for triggers beyond the limit
+    pub fn with_overflow(mut self, overflow: RateLimitOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// Trigger counters of a tag since its limit was set or the stats were reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitStats {
+    /// Triggers within the limit
+    pub played: u64,
+    /// Triggers ignored
+    pub dropped: u64,
+    /// Triggers merged into an earlier trigger
+    pub merged: u64,
+}
+
+/// Outcome of a trigger checked against its tag's limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Play,
+    Drop,
+    /// Play this source (the tag's most recent trigger) instead
+    Merge(SourceId),
+}
+
+#[derive(Debug)]
+struct TagLimiter {
+    limit: RateLimit,
+    /// Times of the admitted triggers within the window, oldest first
+    recent: VecDeque<Instant>,
+    last_source: Option<SourceId>,
+    stats: RateLimitStats,
+}
+
+/// Rate limits and trigger history of all tags
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    tags: HashMap<String, TagLimiter>,
+}
+
+impl RateLimiter {
+    pub fn set_limit(&mut self, tag: &str, limit: Option<RateLimit>) {
+        match limit {
+            Some(limit) => {
+                self.tags
+                    .entry(tag.to_string())
+                    .and_modify(|limiter| limiter.limit = limit)
+                    .or_insert_with(|| TagLimiter {
+                        limit,
+                        recent: VecDeque::new(),
+                        last_source: None,
+                        stats: RateLimitStats::default(),
+                    });
+            }
+            None => {
+                self.tags.remove(tag);
+            }
+        }
+    }
+
+    pub fn limit(&self, tag: &str) -> Option<RateLimit> {
+        self.tags.get(tag).map(|limiter| limiter.limit)
+    }
+
+    /// Check a trigger of `source_id` tagged `tag` at `now` and record it
+    pub fn admit(&mut self, tag: &str, source_id: SourceId, now: Instant) -> Admission {
+        let Some(limiter) = self.tags.get_mut(tag) else {
+            return Admission::Play;
+        };
+
+        while limiter
+            .recent
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= limiter.limit.window)
+        {
+            limiter.recent.pop_front();
+        }
+
+        if limiter.recent.len() < limiter.limit.max_triggers as usize {
+            limiter.recent.push_back(now);
+            limiter.last_source = Some(source_id);
+            limiter.stats.played += 1;
+            return Admission::Play;
+        }
+
+        match (limiter.limit.overflow, limiter.last_source) {
+            (RateLimitOverflow::Merge, Some(last_source)) => {
+                limiter.stats.merged += 1;
+                Admission::Merge(last_source)
+            }
+            _ => {
+                limiter.stats.dropped += 1;
+                Admission::Drop
+            }
+        }
+    }
+
+    pub fn stats(&self) -> HashMap<String, RateLimitStats> {
+        self.tags
+            .iter()
+            .map(|(tag, limiter)| (tag.clone(), limiter.stats))
+            .collect()
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.tags
+            .values_mut()
+            .for_each(|limiter| limiter.stats = RateLimitStats::default());
+    }
+}
@@ -0,0 +1,303 @@
+//! Shared reverb bus.
+//!
+//! With [`PetalSonicWorldDesc::reverb`](crate::PetalSonicWorldDesc::reverb) set, the engine
+//! runs one algorithmic room reverb (Freeverb: eight parallel damped combs followed by four
+//! allpasses per side) on the render thread. Spatial sources feed it by their
+//! `reverb_send` amount (see [`SourceConfig::with_reverb_send`](crate::SourceConfig::with_reverb_send)),
+//! so a gunshot can ring through the room while dialogue in the same room stays dry:
+//!
+//! ```ignore
+//! let desc = PetalSonicWorldDesc {
+//!     reverb: Some(ReverbSettings::default()),
+//!     ..Default::default()
+//! };
+//! let gunshot = SourceConfig::spatial(position).with_reverb_send(1.0);
+//! let dialogue = SourceConfig::spatial(position).with_reverb_send(0.1);
+//! ```
+//!
+//! Sends are taken after the source's volume and per-source processing (attenuation zones,
+//! distance delay), but before spatialization; the reverb output is added to the first two
+//! output channels (their average for mono output). Non-spatial sources do not feed it.
+//!
+//! # Environment presets
+//!
+//! [`PetalSonicWorld::set_environment`](crate::PetalSonicWorld::set_environment) sets the
+//! reverb from an [`Environment`] preset at runtime, fading from the current settings, so a
+//! player walking from a hall into a cave hears the room change without any geometry:
+//!
+//! ```ignore
+//! world.set_environment(Environment::Cave);
+//! world.set_environment_with_transition(Environment::Outdoor, Duration::from_secs(3));
+//! ```
+//!
+//! A world created without [`PetalSonicWorldDesc::reverb`](crate::PetalSonicWorldDesc::reverb)
+//! gets its reverb bus on the first preset, faded in from silence.
+
+/// Comb filter lengths at 44.1 kHz
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Allpass filter lengths at 44.1 kHz
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// Extra delay of the right channel's filters at 44.1 kHz, decorrelating the sides
+const STEREO_SPREAD: usize = 23;
+/// Input attenuation keeping the summed comb feedback in range
+const INPUT_GAIN: f32 = 0.015;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+/// Parameters of the reverb bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReverbSettings {
+    /// Size of the simulated room, 0 (small) to 1 (large); sets the decay time
+    pub room_size: f32,
+    /// High frequency absorption of the walls, 0 (bright) to 1 (dark)
+    pub damping: f32,
+    /// Stereo width of the reverb, 0 (mono) to 1
+    pub width: f32,
+    /// Linear gain of the reverb output
+    pub wet_gain: f32,
+}
+
+impl Default for ReverbSettings {
+    fn default() -> Self {
+        Self {
+            room_size: 0.5,
+            damping: 0.5,
+            width: 1.0,
+            wet_gain: 0.3,
+        }
+    }
+}
+
+impl ReverbSettings {
+    /// Settings `t` (0 to 1) of the way from `self` to `other`
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            room_size: mix(self.room_size, other.room_size),
+            damping: mix(self.damping, other.damping),
+            width: mix(self.width, other.width),
+            wet_gain: mix(self.wet_gain, other.wet_gain),
+        }
+    }
+}
+
+/// Reverb presets for common spaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Environment {
+    /// Small furnished room: short, dark decay
+    SmallRoom,
+    /// Concert or entrance hall: long, bright decay
+    Hall,
+    /// Cave: very long decay off hard rock walls
+    Cave,
+    /// Open air: almost dry, a hint of distant reflections
+    Outdoor,
+}
+
+impl Environment {
+    /// Reverb settings of the preset
+    pub fn reverb_settings(self) -> ReverbSettings {
+        match self {
+            Self::SmallRoom => ReverbSettings {
+                room_size: 0.3,
+                damping: 0.6,
+                width: 0.7,
+                wet_gain: 0.2,
+            },
+            Self::Hall => ReverbSettings {
+                room_size: 0.85,
+                damping: 0.35,
+                width: 1.0,
+                wet_gain: 0.35,
+            },
+            Self::Cave => ReverbSettings {
+                room_size: 0.95,
+                damping: 0.2,
+                width: 1.0,
+                wet_gain: 0.5,
+            },
+            Self::Outdoor => ReverbSettings {
+                room_size: 0.1,
+                damping: 0.8,
+                width: 1.0,
+                wet_gain: 0.05,
+            },
+        }
+    }
+}
+
+/// Fade between two settings of the bus
+#[derive(Debug)]
+struct Transition {
+    from: ReverbSettings,
+    to: ReverbSettings,
+    /// Frames rendered since the fade started
+    position: usize,
+    frames: usize,
+}
+
+#[derive(Debug)]
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filter_state: f32,
+}
+
+impl Comb {
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filter_state = output * (1.0 - damping) + self.filter_state * damping;
+        self.buffer[self.index] = input + self.filter_state * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+#[derive(Debug)]
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn process(&mut self, input: f32) -> f32 {
+        let buffered = self.buffer[self.index];
+        self.buffer[self.index] = input + buffered * ALLPASS_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        buffered - input
+    }
+}
+
+/// Filters of one output side
+#[derive(Debug)]
+struct ReverbSide {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl ReverbSide {
+    fn new(sample_rate: u32, spread: usize) -> Self {
+        let scale = |length: usize| ((length + spread) * sample_rate as usize / 44_100).max(1);
+        Self {
+            combs: COMB_TUNING
+                .iter()
+                .map(|length| Comb {
+                    buffer: vec![0.0; scale(*length)],
+                    index: 0,
+                    filter_state: 0.0,
+                })
+                .collect(),
+            allpasses: ALLPASS_TUNING
+                .iter()
+                .map(|length| Allpass {
+                    buffer: vec![0.0; scale(*length)],
+                    index: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let mut output = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damping))
+            .sum();
+        for allpass in self.allpasses.iter_mut() {
+            output = allpass.process(output);
+        }
+        output
+    }
+}
+
+/// Render-side reverb bus, passed through the mixer: sources add their sends to it, then
+/// it is rendered into the output once per block
+#[derive(Debug)]
+pub struct Reverb {
+    settings: ReverbSettings,
+    /// Fade to new settings in progress
+    transition: Option<Transition>,
+    left: ReverbSide,
+    right: ReverbSide,
+    /// Mono send bus of the current block
+    input: Vec<f32>,
+}
+
+impl Reverb {
+    pub(crate) fn new(settings: ReverbSettings, sample_rate: u32, block_size: usize) -> Self {
+        Self {
+            settings,
+            transition: None,
+            left: ReverbSide::new(sample_rate, 0),
+            right: ReverbSide::new(sample_rate, STEREO_SPREAD),
+            input: vec![0.0; block_size],
+        }
+    }
+
+    /// Size the send bus for blocks of `block_size` frames
+    pub(crate) fn set_block_size(&mut self, block_size: usize) {
+        self.input.resize(block_size, 0.0);
+    }
+
+    /// Fade to `settings` over `frames` frames, starting from the current settings
+    pub(crate) fn transition_to(&mut self, settings: ReverbSettings, frames: usize) {
+        if frames == 0 {
+            self.settings = settings;
+            self.transition = None;
+            return;
+        }
+        self.transition = Some(Transition {
+            from: self.settings,
+            to: settings,
+            position: 0,
+            frames,
+        });
+    }
+
+    /// Advance the fade by a block of `frames` frames
+    fn advance_transition(&mut self, frames: usize) {
+        let Some(transition) = self.transition.as_mut() else {
+            return;
+        };
+        transition.position = (transition.position + frames).min(transition.frames);
+        let t = transition.position as f32 / transition.frames as f32;
+        self.settings = transition.from.lerp(&transition.to, t);
+        if transition.position == transition.frames {
+            self.transition = None;
+        }
+    }
+
+    /// Add a source's send signal to the bus
+    pub(crate) fn add_send(&mut self, samples: &[f32], gain: f32) {
+        if self.input.len() < samples.len() {
+            self.input.resize(samples.len(), 0.0);
+        }
+        for (bus, sample) in self.input.iter_mut().zip(samples) {
+            *bus += sample * gain;
+        }
+    }
+
+    /// Render the bus into an interleaved buffer and clear it for the next block
+    pub(crate) fn process(&mut self, output: &mut [f32], channels: u16) {
+        let channels = channels as usize;
+        self.advance_transition(output.len() / channels);
+        let feedback = 0.7 + 0.28 * self.settings.room_size.clamp(0.0, 1.0);
+        let damping = 0.4 * self.settings.damping.clamp(0.0, 1.0);
+        let width = self.settings.width.clamp(0.0, 1.0);
+        let direct = self.settings.wet_gain * (0.5 + 0.5 * width);
+        let cross = self.settings.wet_gain * (0.5 - 0.5 * width);
+
+        for (frame, input) in output.chunks_exact_mut(channels).zip(self.input.iter_mut()) {
+            let sample = *input * INPUT_GAIN;
+            *input = 0.0;
+            let left = self.left.process(sample, feedback, damping);
+            let right = self.right.process(sample, feedback, damping);
+            if channels == 1 {
+                frame[0] += 0.5 * (direct + cross) * (left + right);
+            } else {
+                frame[0] += left * direct + right * cross;
+                frame[1] += right * direct + left * cross;
+            }
+        }
+    }
+}
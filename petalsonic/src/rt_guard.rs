@@ -0,0 +1,288 @@
+//! Allocation guard for the real-time paths (`rt-alloc-guard` cargo feature).
+//!
+//! The render thread and the device callbacks must not allocate: the system allocator can
+//! take locks and page faults at any time, which shows up as dropouts long after the
+//! change that introduced the allocation. With the `rt-alloc-guard` feature, the engine
+//! marks the real-time sections and [`RtAllocGuard`], installed as the global allocator of
+//! a test or debug binary, catches every allocation and deallocation made inside them:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: petalsonic::rt_guard::RtAllocGuard = petalsonic::rt_guard::RtAllocGuard::system();
+//!
+//! #[test]
+//! fn playback_does_not_allocate() {
+//!     // ... start an engine and play sources ...
+//!     petalsonic::rt_guard::reset_rt_alloc_stats();
+//!     std::thread::sleep(Duration::from_secs(1));
+//!     assert_eq!(petalsonic::rt_guard::rt_alloc_stats().total(), 0);
+//! }
+//! ```
+//!
+//! With [`RtAllocPolicy::Count`] (the default) violations are only counted, per path; with
+//! [`RtAllocPolicy::Abort`] the process prints the offending path and aborts on the first
+//! one, so a debugger or core dump points at the allocation. An allocator must not unwind,
+//! so with [`RtAllocPolicy::Panic`] the section panics when it ends instead, which a test
+//! can expect with `#[should_panic]`.
+//!
+//! Locks cannot be observed from the allocator. The real-time paths only ever `try_lock`
+//! and count contention in [`RenderCounters`](crate::logging::RenderCounters); a blocking
+//! lock that allocates (e.g. on first use) is still caught here.
+//!
+//! Without the feature the sections compile to nothing, and installing the allocator
+//! counts nothing.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+/// What happens on an allocation inside a real-time section
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RtAllocPolicy {
+    /// Count it in [`RtAllocStats`]
+    #[default]
+    Count,
+    /// Count it, print the real-time path to stderr and abort the process
+    Abort,
+    /// Count it and panic when the real-time section ends
+    Panic,
+}
+
+/// Allocations and deallocations made inside the real-time sections since the process
+/// started or the last [`reset_rt_alloc_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RtAllocStats {
+    /// Allocations (including reallocations) on the render thread
+    pub render_allocations: u64,
+    /// Deallocations on the render thread
+    pub render_deallocations: u64,
+    /// Allocations (including reallocations) in the device callbacks
+    pub callback_allocations: u64,
+    /// Deallocations in the device callbacks
+    pub callback_deallocations: u64,
+}
+
+impl RtAllocStats {
+    /// All allocations and deallocations counted
+    pub fn total(&self) -> u64 {
+        self.render_allocations
+            + self.render_deallocations
+            + self.callback_allocations
+            + self.callback_deallocations
+    }
+}
+
+/// Real-time path a section belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RtPath {
+    /// Rendering blocks on the render thread
+    Render = 1,
+    /// A device callback (main or secondary output)
+    Callback = 2,
+}
+
+thread_local! {
+    /// Path of the real-time section the thread is in, 0 outside of one
+    static SECTION: Cell<u8> = const { Cell::new(0) };
+    /// Path of the first allocation under [`RtAllocPolicy::Panic`] not reported yet, 0 if none
+    static VIOLATION: Cell<u8> = const { Cell::new(0) };
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(RtAllocPolicy::Count as u8);
+/// Allocation and deallocation counters, indexed by `RtPath as usize - 1`
+static ALLOCATIONS: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+static DEALLOCATIONS: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+
+/// Set what happens on an allocation inside a real-time section
+pub fn set_rt_alloc_policy(policy: RtAllocPolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Get what happens on an allocation inside a real-time section
+pub fn rt_alloc_policy() -> RtAllocPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        1 => RtAllocPolicy::Abort,
+        2 => RtAllocPolicy::Panic,
+        _ => RtAllocPolicy::Count,
+    }
+}
+
+/// Get the allocations counted inside the real-time sections
+pub fn rt_alloc_stats() -> RtAllocStats {
+    RtAllocStats {
+        render_allocations: ALLOCATIONS[0].load(Ordering::Relaxed),
+        render_deallocations: DEALLOCATIONS[0].load(Ordering::Relaxed),
+        callback_allocations: ALLOCATIONS[1].load(Ordering::Relaxed),
+        callback_deallocations: DEALLOCATIONS[1].load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the counters of [`rt_alloc_stats`], e.g. once an engine finished starting up
+pub fn reset_rt_alloc_stats() {
+    for counter in ALLOCATIONS.iter().chain(&DEALLOCATIONS) {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Marks the current thread as running a real-time path until dropped
+pub(crate) struct RtSection {
+    #[cfg(feature = "rt-alloc-guard")]
+    previous: u8,
+}
+
+impl RtSection {
+    #[cfg_attr(not(feature = "rt-alloc-guard"), allow(unused_variables))]
+    #[inline]
+    pub(crate) fn enter(path: RtPath) -> Self {
+        Self {
+            #[cfg(feature = "rt-alloc-guard")]
+            previous: SECTION
+                .try_with(|section| section.replace(path as u8))
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(feature = "rt-alloc-guard")]
+impl Drop for RtSection {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = SECTION.try_with(|section| section.set(self.previous));
+        // Report an allocation of this section, or of a nested one, once outside of it
+        let violation = VIOLATION
+            .try_with(|violation| violation.replace(0))
+            .unwrap_or(0);
+        if violation != 0 && !std::thread::panicking() {
+            if violation == RtPath::Render as u8 {
+                panic!("petalsonic: allocation on the render thread");
+            }
+            panic!("petalsonic: allocation in a device callback");
+        }
+    }
+}
+
+/// Global allocator wrapper catching allocations inside the real-time sections
+///
+/// Forwards to `A` (the system allocator by default). See the [module docs](self).
+pub struct RtAllocGuard<A = System> {
+    inner: A,
+}
+
+impl RtAllocGuard<System> {
+    /// Guard the system allocator
+    pub const fn system() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl<A> RtAllocGuard<A> {
+    /// Guard another allocator
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    fn record(counters: &[AtomicU64; 2]) {
+        let path = SECTION.try_with(Cell::get).unwrap_or(0);
+        if path == 0 {
+            return;
+        }
+        counters[path as usize - 1].fetch_add(1, Ordering::Relaxed);
+        let policy = rt_alloc_policy();
+        if policy == RtAllocPolicy::Panic {
+            let _ = VIOLATION.try_with(|violation| {
+                if violation.get() == 0 {
+                    violation.set(path);
+                }
+            });
+        } else if policy == RtAllocPolicy::Abort {
+            // Leave the section so nothing below is caught again, and report without
+            // formatting (which could allocate)
+            let _ = SECTION.try_with(|section| section.set(0));
+            let message: &[u8] = if path == RtPath::Render as u8 {
+                b"petalsonic: allocation on the render thread, aborting\n"
+            } else {
+                b"petalsonic: allocation in a device callback, aborting\n"
+            };
+            let _ = std::io::stderr().write_all(message);
+            std::process::abort();
+        }
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to the wrapped allocator; counting touches
+// only atomics and a const-initialized thread local, which do not allocate.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for RtAllocGuard<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::record(&ALLOCATIONS);
+        // SAFETY: forwarded with the caller's guarantees
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::record(&ALLOCATIONS);
+        // SAFETY: forwarded with the caller's guarantees
+        unsafe { self.inner.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::record(&ALLOCATIONS);
+        // SAFETY: forwarded with the caller's guarantees
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        Self::record(&DEALLOCATIONS);
+        // SAFETY: forwarded with the caller's guarantees
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+}
+
+#[cfg(all(test, feature = "rt-alloc-guard"))]
+mod tests {
+    use super::*;
+    use crate::master_volume::{MasterVolume, SharedMasterVolume};
+    use crate::output_eq::{EqBand, OutputEq, OutputEqFilter};
+    use std::sync::Mutex;
+
+    #[global_allocator]
+    static ALLOCATOR: RtAllocGuard = RtAllocGuard::system();
+
+    /// The policy and counters are process-wide, so the tests take turns
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    #[should_panic(expected = "allocation on the render thread")]
+    fn allocation_in_render_section_panics() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        set_rt_alloc_policy(RtAllocPolicy::Panic);
+        let _section = RtSection::enter(RtPath::Render);
+        std::hint::black_box(vec![0.0f32; 256]);
+    }
+
+    #[test]
+    fn clean_render_block_passes() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        set_rt_alloc_policy(RtAllocPolicy::Panic);
+
+        // The master stage of a block, with its state and buffer set up beforehand
+        let shared = SharedMasterVolume::new(0.8, true, true);
+        let mut master_volume = MasterVolume::new(48000, 2, &shared);
+        let eq = OutputEq {
+            bands: vec![EqBand::peaking(1000.0, 3.0, 1.0)],
+            ..Default::default()
+        };
+        let mut output_eq = OutputEqFilter::new(&eq, 48000, 2);
+        let mut block: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.05).sin()).collect();
+        reset_rt_alloc_stats();
+
+        {
+            let _section = RtSection::enter(RtPath::Render);
+            master_volume.process(&shared, &mut block);
+            output_eq.process(&mut block);
+        }
+        assert_eq!(rt_alloc_stats().render_allocations, 0);
+        assert_eq!(rt_alloc_stats().render_deallocations, 0);
+    }
+}
@@ -0,0 +1,343 @@
+//! MIDI-style sampler for note-triggered playback.
+//!
+//! A [`Sampler`] maps MIDI note numbers to audio clips through [`SamplerZone`]s and plays
+//! them polyphonically on the render thread, pitch-shifted relative to each zone's root
+//! note. Create one with [`PetalSonicEngine::create_sampler`](crate::PetalSonicEngine::create_sampler)
+//! and trigger notes from the main thread:
+//!
+//! ```ignore
+//! let sampler = engine.create_sampler(16);
+//! sampler.add_zone(SamplerZone::new(piano_c4, 60).with_range(0, 127))?;
+//! sampler.note_on(64, 100)?; // E4, pitched up four semitones from the C4 sample
+//! sampler.note_off(64)?;
+//! ```
+//!
+//! Sampler output is mixed into the master mix (non-spatial), after the world sources.
+
+use crate::audio_data::PetalSonicAudioData;
+use crate::error::{PetalSonicError, Result};
+use crate::random::AudioRng;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default release time applied on note-off
+const DEFAULT_RELEASE: Duration = Duration::from_millis(50);
+
+/// A clip mapped to a range of MIDI notes
+#[derive(Debug, Clone)]
+pub struct SamplerZone {
+    /// Clip played for notes in this zone
+    pub audio_data: Arc<PetalSonicAudioData>,
+    /// Note at which the clip plays at its original pitch
+    pub root_note: u8,
+    /// Lowest note of the zone (inclusive)
+    pub low_note: u8,
+    /// Highest note of the zone (inclusive)
+    pub high_note: u8,
+    /// Linear gain applied to the zone
+    pub gain: f32,
+    /// Ignore note-off and always play the clip to its end (e.g. drum hits)
+    pub one_shot: bool,
+    /// Fade-out time after note-off
+    pub release: Duration,
+    /// Random pitch offset in semitones applied to each note, drawn uniformly from
+    /// `-pitch_variation..pitch_variation`
+    pub pitch_variation: f32,
+}
+
+impl SamplerZone {
+    /// Create a zone covering only `root_note`
+    pub fn new(audio_data: Arc<PetalSonicAudioData>, root_note: u8) -> Self {
+        Self {
+            audio_data,
+            root_note,
+            low_note: root_note,
+            high_note: root_note,
+            gain: 1.0,
+            one_shot: false,
+            release: DEFAULT_RELEASE,
+            pitch_variation: 0.0,
+        }
+    }
+
+    /// Set the note range of the zone (inclusive)
+    pub fn with_range(mut self, low_note: u8, high_note: u8) -> Self {
+        self.low_note = low_note;
+        self.high_note = high_note;
+        self
+    }
+
+    /// Set the zone gain
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Make the zone ignore note-off
+    pub fn one_shot(mut self) -> Self {
+        self.one_shot = true;
+        self
+    }
+
+    /// Set the fade-out time after note-off
+    pub fn with_release(mut self, release: Duration) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Randomize the pitch of each note by up to `semitones` in either direction
+    pub fn with_pitch_variation(mut self, semitones: f32) -> Self {
+        self.pitch_variation = semitones;
+        self
+    }
+
+    fn contains(&self, note: u8) -> bool {
+        (self.low_note..=self.high_note).contains(&note)
+    }
+}
+
+/// Commands sent from a [`Sampler`] to its render-side voices
+#[derive(Debug)]
+enum SamplerCommand {
+    AddZone(SamplerZone),
+    ClearZones,
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+    AllNotesOff,
+    SetVolume(f32),
+}
+
+/// Main-thread handle to a sampler created with
+/// [`PetalSonicEngine::create_sampler`](crate::PetalSonicEngine::create_sampler).
+///
+/// Dropping the handle removes the sampler once its voices have finished.
+pub struct Sampler {
+    sender: Sender<SamplerCommand>,
+}
+
+impl Sampler {
+    fn send(&self, command: SamplerCommand) -> Result<()> {
+        self.sender
+            .send(command)
+            .map_err(|e| PetalSonicError::Engine(format!("Failed to send sampler command: {}", e)))
+    }
+
+    /// Map a clip to a note range. Later zones take precedence where ranges overlap.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Configuration` if the range is reversed or a note is
+    /// above 127.
+    pub fn add_zone(&self, zone: SamplerZone) -> Result<()> {
+        if zone.low_note > zone.high_note || zone.high_note > 127 || zone.root_note > 127 {
+            return Err(PetalSonicError::Configuration(format!(
+                "Invalid sampler zone: notes {}-{}, root {}",
+                zone.low_note, zone.high_note, zone.root_note
+            )));
+        }
+        self.send(SamplerCommand::AddZone(zone))
+    }
+
+    /// Remove all zones. Playing voices keep playing.
+    pub fn clear_zones(&self) -> Result<()> {
+        self.send(SamplerCommand::ClearZones)
+    }
+
+    /// Start a note. `velocity` (1-127) scales the voice gain; 0 is treated as note-off.
+    pub fn note_on(&self, note: u8, velocity: u8) -> Result<()> {
+        if velocity == 0 {
+            return self.note_off(note);
+        }
+        self.send(SamplerCommand::NoteOn { note, velocity })
+    }
+
+    /// Release all voices playing `note`
+    pub fn note_off(&self, note: u8) -> Result<()> {
+        self.send(SamplerCommand::NoteOff { note })
+    }
+
+    /// Release all voices
+    pub fn all_notes_off(&self) -> Result<()> {
+        self.send(SamplerCommand::AllNotesOff)
+    }
+
+    /// Set the sampler output volume (linear gain)
+    pub fn set_volume(&self, volume: f32) -> Result<()> {
+        self.send(SamplerCommand::SetVolume(volume))
+    }
+}
+
+#[derive(Debug)]
+struct Voice {
+    note: u8,
+    audio_data: Arc<PetalSonicAudioData>,
+    /// Read position in source frames
+    position: f64,
+    /// Source frames advanced per output frame
+    increment: f64,
+    gain: f32,
+    one_shot: bool,
+    /// Gain decrement per frame once released
+    release_step: f32,
+    /// Current release envelope, 1.0 until released
+    envelope: f32,
+    released: bool,
+}
+
+impl Voice {
+    /// Mix the voice into an interleaved buffer. Returns false once the voice has finished.
+    fn mix_into(&mut self, buffer: &mut [f32], channels: usize) -> bool {
+        let samples = self.audio_data.samples();
+        let source_channels = self.audio_data.channels() as usize;
+        let total_frames = self.audio_data.total_frames();
+
+        for frame in buffer.chunks_exact_mut(channels) {
+            let index = self.position as usize;
+            if index >= total_frames || self.envelope <= 0.0 {
+                return false;
+            }
+
+            let fraction = (self.position - index as f64) as f32;
+            let next = (index + 1).min(total_frames - 1);
+            let gain = self.gain * self.envelope;
+            for (channel, output) in frame.iter_mut().enumerate() {
+                let source_channel = channel.min(source_channels - 1);
+                let a = samples[index * source_channels + source_channel];
+                let b = samples[next * source_channels + source_channel];
+                *output += (a + (b - a) * fraction) * gain;
+            }
+
+            self.position += self.increment;
+            if self.released {
+                self.envelope -= self.release_step;
+            }
+        }
+
+        true
+    }
+
+    fn release(&mut self) {
+        if !self.one_shot {
+            self.released = true;
+        }
+    }
+}
+
+/// Render-side state of a sampler, owned by the engine and mixed by the render thread
+pub(crate) struct SamplerVoices {
+    receiver: Receiver<SamplerCommand>,
+    zones: Vec<SamplerZone>,
+    voices: Vec<Voice>,
+    max_voices: usize,
+    volume: f32,
+    sample_rate: u32,
+    rng: AudioRng,
+    disconnected: bool,
+}
+
+impl SamplerVoices {
+    /// Create a sampler rendering at `sample_rate` with at most `max_voices` voices, drawing
+    /// pitch variation from `rng`
+    pub fn new(sample_rate: u32, max_voices: usize, rng: AudioRng) -> (Sampler, Self) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let max_voices = max_voices.max(1);
+        (
+            Sampler { sender },
+            Self {
+                receiver,
+                zones: Vec::new(),
+                voices: Vec::with_capacity(max_voices),
+                max_voices,
+                volume: 1.0,
+                sample_rate,
+                rng,
+                disconnected: false,
+            },
+        )
+    }
+
+    /// Returns true once the handle was dropped and all voices have finished
+    pub fn is_finished(&self) -> bool {
+        self.disconnected && self.voices.is_empty()
+    }
+
+    /// Apply pending commands and mix all voices into an interleaved buffer
+    pub fn mix_into(&mut self, buffer: &mut [f32], channels: u16) {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(command) => self.handle_command(command),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        if self.volume == 0.0 && self.voices.is_empty() {
+            return;
+        }
+
+        let volume = self.volume;
+        self.voices.retain_mut(|voice| {
+            let gain = voice.gain;
+            voice.gain *= volume;
+            let playing = voice.mix_into(buffer, channels as usize);
+            voice.gain = gain;
+            playing
+        });
+    }
+
+    fn handle_command(&mut self, command: SamplerCommand) {
+        match command {
+            SamplerCommand::AddZone(zone) => self.zones.push(zone),
+            SamplerCommand::ClearZones => self.zones.clear(),
+            SamplerCommand::NoteOn { note, velocity } => self.start_voice(note, velocity),
+            SamplerCommand::NoteOff { note } => self
+                .voices
+                .iter_mut()
+                .filter(|voice| voice.note == note)
+                .for_each(Voice::release),
+            SamplerCommand::AllNotesOff => self.voices.iter_mut().for_each(Voice::release),
+            SamplerCommand::SetVolume(volume) => self.volume = volume,
+        }
+    }
+
+    fn start_voice(&mut self, note: u8, velocity: u8) {
+        let Some(zone) = self.zones.iter().rev().find(|zone| zone.contains(note)) else {
+            log::debug!("Sampler: no zone mapped to note {}", note);
+            return;
+        };
+        if zone.audio_data.is_empty() {
+            return;
+        }
+
+        // Steal the oldest voice when all voices are in use
+        if self.voices.len() >= self.max_voices {
+            self.voices.remove(0);
+        }
+
+        let mut semitones = note as f64 - zone.root_note as f64;
+        if zone.pitch_variation > 0.0 {
+            semitones +=
+                self.rng
+                    .range_f32(-zone.pitch_variation, zone.pitch_variation) as f64;
+        }
+        let increment = 2f64.powf(semitones / 12.0) * zone.audio_data.sample_rate() as f64
+            / self.sample_rate as f64;
+        let release_frames = (zone.release.as_secs_f64() * self.sample_rate as f64).max(1.0);
+
+        self.voices.push(Voice {
+            note,
+            audio_data: zone.audio_data.clone(),
+            position: 0.0,
+            increment,
+            gain: zone.gain * velocity.min(127) as f32 / 127.0,
+            one_shot: zone.one_shot,
+            release_step: (1.0 / release_frames) as f32,
+            envelope: 1.0,
+            released: false,
+        });
+    }
+}
@@ -0,0 +1,404 @@
+//! Secondary output stream on another device.
+//!
+//! Besides its main output, the engine can play a subset of the mix on a second device,
+//! e.g. voice chat on a headset while the game plays on the speakers. Sources are grouped
+//! into named buses with [`PetalSonicWorld::set_source_bus`](crate::PetalSonicWorld::set_source_bus)
+//! and the buses listed in [`SecondaryOutputDesc::buses`] are played on the secondary device
+//! instead of the main one:
+//!
+//! ```ignore
+//! world.set_source_bus(voice, Some("voice"))?;
+//! let devices = PetalSonicEngine::output_device_names()?;
+//! engine.open_secondary_output(SecondaryOutputDesc::new(&devices[1], ["voice"]))?;
+//! ```
+//!
+//! The render thread mixes the secondary buses into their own block, converts it to the
+//! device sample rate and queues it in a ring buffer of its own, which the device callback
+//! of the second stream consumes. Sources on the secondary output are not spatialized (like
+//! sources routed to specific channels), and the master volume, output EQ, drain fade,
+//! loudness metering, output tap and stems apply to the main output only.
+//!
+//! The two devices run on independent clocks. The queue of the secondary output is capped,
+//! so if its device consumes slower than the main one, blocks are dropped to keep the
+//! latency bounded; if it consumes faster, it underruns. Both are counted in
+//! [`SecondaryOutputStats`].
+
+use crate::audio_data::StreamingResampler;
+use crate::channel_mix::{ChannelMixMatrix, FrameMixer};
+use crate::config::ResampleQuality;
+use crate::dither::{Dither, Ditherer};
+use crate::error::{PetalSonicError, Result};
+use crate::events::PetalSonicEvent;
+use crate::logging::{self, rt_error};
+use crate::rt_guard::{RtPath, RtSection};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use crossbeam_channel::Sender;
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// World blocks the secondary output queues at most before blocks are dropped
+const MAX_QUEUED_BLOCKS: usize = 8;
+
+/// Device and buses of a secondary output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecondaryOutputDesc {
+    /// Name of the output device, as listed by
+    /// [`PetalSonicEngine::output_device_names`](crate::PetalSonicEngine::output_device_names)
+    pub device_name: String,
+    /// Buses played on this output instead of the main one
+    pub buses: Vec<String>,
+}
+
+impl SecondaryOutputDesc {
+    /// Play `buses` on the device named `device_name`
+    pub fn new<I, S>(device_name: &str, buses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            device_name: device_name.to_string(),
+            buses: buses.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Counters of a secondary output since it was opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecondaryOutputStats {
+    /// Device callbacks that ran out of rendered audio
+    pub underruns: u64,
+    /// Device frames dropped because the queue was full
+    pub dropped_frames: u64,
+}
+
+#[derive(Default)]
+struct SharedSecondaryStats {
+    underruns: AtomicU64,
+    dropped_frames: AtomicU64,
+}
+
+/// Render-side end of a secondary output, passed through the mixer: sources on its buses
+/// render into its block, which is queued for the device once per block
+pub struct SecondaryMix {
+    buses: Vec<String>,
+    channels: usize,
+    /// Interleaved block at the world sample rate and channel count
+    block: Vec<f32>,
+    resampler: StreamingResampler,
+    resampled: Vec<f32>,
+    /// Interleaved device-rate frames, at the world channel count
+    producer: HeapProd<f32>,
+    max_queued_frames: usize,
+    stats: Arc<SharedSecondaryStats>,
+}
+
+impl SecondaryMix {
+    /// Whether sources on `bus` are mixed into this output
+    pub(crate) fn routes(&self, bus: Option<&str>) -> bool {
+        bus.is_some_and(|bus| self.buses.iter().any(|routed| routed == bus))
+    }
+
+    /// Frames of the blocks the resampler converts
+    pub(crate) fn block_size(&self) -> usize {
+        self.resampler.input_chunk_size()
+    }
+
+    /// Switch to blocks of `block_size` frames, converted by `resampler`
+    pub(crate) fn set_block_size(&mut self, block_size: usize, resampler: StreamingResampler) {
+        let block_frames = (block_size as u64 * resampler.target_sample_rate() as u64)
+            .div_ceil(resampler.source_sample_rate() as u64) as usize;
+        self.block = vec![0.0; block_size * self.channels];
+        self.resampled = vec![0.0; (block_frames + 10) * self.channels];
+        // The queue keeps its capacity, sized for the block size the output was opened with
+        self.max_queued_frames =
+            (block_frames * MAX_QUEUED_BLOCKS).min(self.producer.capacity().get() / self.channels);
+        self.resampler = resampler;
+    }
+
+    /// Start a block of `frames` frames
+    pub(crate) fn begin_block(&mut self, frames: usize) {
+        self.block.clear();
+        self.block.resize(frames * self.channels, 0.0);
+    }
+
+    /// Interleaved block for the mixer to add the sources on this output to
+    pub(crate) fn block_mut(&mut self) -> &mut [f32] {
+        &mut self.block
+    }
+
+    /// Finish the block: convert it to the device sample rate and queue it
+    pub(crate) fn end_block(&mut self) {
+        let (frames_out, _) = match self
+            .resampler
+            .process_interleaved(&self.block, &mut self.resampled)
+        {
+            Ok(frames) => frames,
+            Err(e) => {
+                logging::count_render_error();
+                rt_error!("Secondary output resampling error: {}", e);
+                return;
+            }
+        };
+
+        let queued_frames = self.producer.occupied_len() / self.channels;
+        if queued_frames + frames_out > self.max_queued_frames {
+            self.stats
+                .dropped_frames
+                .fetch_add(frames_out as u64, Ordering::Relaxed);
+            return;
+        }
+        self.producer
+            .push_slice(&self.resampled[..frames_out * self.channels]);
+    }
+}
+
+/// Device-side end of a secondary output
+struct SecondaryCallbackContext {
+    /// Set while the engine renders; the output is silent otherwise
+    is_running: Arc<AtomicBool>,
+    consumer: HeapCons<f32>,
+    channels: usize,
+    device_channels: usize,
+    /// Converts world frames to device frames when the channel counts differ
+    output_mix: Option<FrameMixer>,
+    ditherer: Ditherer,
+    stats: Arc<SharedSecondaryStats>,
+}
+
+/// Parameters of [`SecondaryOutput::open`]
+pub(crate) struct SecondaryOutputParams {
+    pub world_sample_rate: u32,
+    pub channels: u16,
+    pub device_channels: u16,
+    pub block_size: usize,
+    pub resample_quality: ResampleQuality,
+    pub dither: Dither,
+    pub is_running: Arc<AtomicBool>,
+    pub event_sender: Sender<PetalSonicEvent>,
+}
+
+/// An open secondary output stream
+pub(crate) struct SecondaryOutput {
+    desc: SecondaryOutputDesc,
+    _stream: cpal::Stream,
+    sample_rate: u32,
+    channels: u16,
+    sample_format: cpal::SampleFormat,
+    stats: Arc<SharedSecondaryStats>,
+}
+
+impl SecondaryOutput {
+    /// Find an output device by name
+    pub fn find_device(name: &str) -> Result<cpal::Device> {
+        let host = cpal::default_host();
+        let mut devices = host.output_devices().map_err(|e| {
+            PetalSonicError::AudioDevice(format!("Failed to list output devices: {}", e))
+        })?;
+        devices
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+            .ok_or_else(|| {
+                PetalSonicError::AudioDevice(format!("Output device '{}' not found", name))
+            })
+    }
+
+    /// Open the stream on `device` and start it; returns it with the mix for the render
+    /// thread
+    pub fn open(
+        desc: SecondaryOutputDesc,
+        device: &cpal::Device,
+        device_config: &cpal::SupportedStreamConfig,
+        params: SecondaryOutputParams,
+    ) -> Result<(Self, SecondaryMix)> {
+        let sample_rate = device_config.sample_rate().0;
+        let channels = params.channels as usize;
+        let resampler = StreamingResampler::with_quality(
+            params.world_sample_rate,
+            sample_rate,
+            params.channels,
+            params.block_size,
+            params.resample_quality,
+        )?;
+
+        let block_frames = (params.block_size as u64 * sample_rate as u64)
+            .div_ceil(params.world_sample_rate as u64) as usize;
+        let max_queued_frames = block_frames * MAX_QUEUED_BLOCKS;
+        let (producer, consumer) = HeapRb::<f32>::new(max_queued_frames * 2 * channels).split();
+        let stats = Arc::new(SharedSecondaryStats::default());
+
+        let mix = SecondaryMix {
+            buses: desc.buses.clone(),
+            channels,
+            block: vec![0.0; params.block_size * channels],
+            resampler,
+            resampled: vec![0.0; (block_frames + 10) * channels],
+            producer,
+            max_queued_frames,
+            stats: stats.clone(),
+        };
+
+        let output_mix = (params.device_channels != params.channels).then(|| {
+            FrameMixer::new(ChannelMixMatrix::default_for(
+                params.channels,
+                params.device_channels,
+            ))
+        });
+        let sample_format = device_config.sample_format();
+        let context = SecondaryCallbackContext {
+            is_running: params.is_running,
+            consumer,
+            channels,
+            device_channels: params.device_channels as usize,
+            output_mix,
+            ditherer: Ditherer::new(
+                params.dither,
+                sample_format,
+                params.device_channels as usize,
+            ),
+            stats: stats.clone(),
+        };
+        let config = cpal::StreamConfig {
+            channels: params.device_channels,
+            sample_rate: cpal::SampleRate(sample_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => {
+                Self::build_stream::<f32>(device, &config, context, params.event_sender)?
+            }
+            cpal::SampleFormat::I16 => {
+                Self::build_stream::<i16>(device, &config, context, params.event_sender)?
+            }
+            cpal::SampleFormat::U16 => {
+                Self::build_stream::<u16>(device, &config, context, params.event_sender)?
+            }
+            _ => {
+                return Err(PetalSonicError::AudioFormat(
+                    "Unsupported sample format".into(),
+                ));
+            }
+        };
+        stream.play().map_err(|e| {
+            PetalSonicError::AudioDevice(format!("Failed to start secondary stream: {}", e))
+        })?;
+
+        log::info!(
+            "Opened secondary output on '{}' ({} Hz, {} channels) for buses {:?}",
+            desc.device_name,
+            sample_rate,
+            params.device_channels,
+            desc.buses
+        );
+
+        Ok((
+            Self {
+                desc,
+                _stream: stream,
+                sample_rate,
+                channels: params.device_channels,
+                sample_format,
+                stats,
+            },
+            mix,
+        ))
+    }
+
+    fn build_stream<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mut context: SecondaryCallbackContext,
+        event_sender: Sender<PetalSonicEvent>,
+    ) -> Result<cpal::Stream>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                    Self::audio_callback(data, &mut context);
+                },
+                move |err| {
+                    log::error!("Secondary audio stream error: {}", err);
+                    let _ = event_sender.send(PetalSonicEvent::EngineError {
+                        error: format!("Secondary output: {}", err),
+                    });
+                },
+                None,
+            )
+            .map_err(|e| {
+                PetalSonicError::AudioDevice(format!("Failed to build secondary stream: {}", e))
+            })
+    }
+
+    fn audio_callback<T>(data: &mut [T], ctx: &mut SecondaryCallbackContext)
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let _section = RtSection::enter(RtPath::Callback);
+        let device_frames = data.len() / ctx.device_channels;
+        let available_frames = ctx.consumer.occupied_len() / ctx.channels;
+        let frames_consumed = if ctx.is_running.load(Ordering::Relaxed) {
+            device_frames.min(available_frames)
+        } else {
+            0
+        };
+
+        let (filled, remaining) = data.split_at_mut(frames_consumed * ctx.device_channels);
+        match ctx.output_mix.as_mut() {
+            None => {
+                for (index, sample) in filled.iter_mut().enumerate() {
+                    let value = ctx.consumer.try_pop().unwrap_or(0.0);
+                    *sample =
+                        T::from_sample(ctx.ditherer.process(value, index % ctx.device_channels));
+                }
+            }
+            Some(output_mix) => {
+                for frame in filled.chunks_exact_mut(ctx.device_channels) {
+                    ctx.consumer.pop_slice(output_mix.input_mut());
+                    for (channel, (sample, mixed)) in
+                        frame.iter_mut().zip(output_mix.mix()).enumerate()
+                    {
+                        *sample = T::from_sample(ctx.ditherer.process(*mixed, channel));
+                    }
+                }
+            }
+        }
+
+        if frames_consumed < device_frames && ctx.is_running.load(Ordering::Relaxed) {
+            ctx.stats.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+        for sample in remaining.iter_mut() {
+            *sample = T::from_sample(0.0f32);
+        }
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.desc.device_name
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_format(&self) -> cpal::SampleFormat {
+        self.sample_format
+    }
+
+    pub fn stats(&self) -> SecondaryOutputStats {
+        SecondaryOutputStats {
+            underruns: self.stats.underruns.load(Ordering::Relaxed),
+            dropped_frames: self.stats.dropped_frames.load(Ordering::Relaxed),
+        }
+    }
+}
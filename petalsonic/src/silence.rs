@@ -0,0 +1,88 @@
+//! Silence detection for sources.
+//!
+//! With [`PetalSonicWorldDesc::silence_detection`](crate::PetalSonicWorldDesc::silence_detection)
+//! set, the render thread watches the signal of every playing source and emits
+//! [`PetalSonicEvent::SourceSilent`](crate::PetalSonicEvent::SourceSilent) once a source
+//! has stayed below the threshold for the configured time, e.g. to notice a network voice
+//! stream that broke off or a generator that produces nothing:
+//!
+//! ```ignore
+//! let desc = PetalSonicWorldDesc {
+//!     silence_detection: Some(SilenceDetection::default()),
+//!     ..Default::default()
+//! };
+//! // later:
+//! for event in engine.poll_events() {
+//!     if let PetalSonicEvent::SourceSilent { source_id, duration } = event {
+//!         log::warn!("source {} silent for {:?}", source_id, duration);
+//!     }
+//! }
+//! ```
+//!
+//! The event is emitted once per silent stretch; the source must rise above the threshold
+//! before it can fire again. Paused sources and sources muted by solo are not watched.
+
+use std::time::Duration;
+
+/// Threshold and time after which a source counts as silent
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SilenceDetection {
+    /// Peak level in dBFS below which the source counts as silent
+    pub threshold_db: f32,
+    /// How long the source must stay below the threshold
+    pub min_duration: Duration,
+}
+
+impl Default for SilenceDetection {
+    fn default() -> Self {
+        Self {
+            threshold_db: -60.0,
+            min_duration: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Per-source silence state, updated on the render thread
+#[derive(Debug, Clone)]
+pub(crate) struct SilenceDetector {
+    threshold: f32,
+    min_frames: usize,
+    silent_frames: usize,
+    reported: bool,
+    sample_rate: u32,
+}
+
+impl SilenceDetector {
+    pub fn new(config: SilenceDetection, sample_rate: u32) -> Self {
+        Self {
+            threshold: 10f32.powf(config.threshold_db / 20.0),
+            min_frames: (config.min_duration.as_secs_f64() * sample_rate as f64) as usize,
+            silent_frames: 0,
+            reported: false,
+            sample_rate,
+        }
+    }
+
+    /// Track a block of the source's signal
+    pub fn process(&mut self, samples: &[f32]) {
+        if samples.iter().any(|sample| sample.abs() >= self.threshold) {
+            self.silent_frames = 0;
+            self.reported = false;
+        } else {
+            self.silent_frames += samples.len();
+        }
+    }
+
+    /// Returns how long the source has been silent, once per silent stretch, when it has
+    /// been silent for the configured time
+    pub fn take_silence(&mut self) -> Option<Duration> {
+        if self.reported || self.silent_frames < self.min_frames {
+            return None;
+        }
+        self.reported = true;
+        Some(Duration::from_secs_f64(
+            self.silent_frames as f64 / self.sample_rate as f64,
+        ))
+    }
+}
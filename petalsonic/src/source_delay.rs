@@ -0,0 +1,99 @@
+//! Per-source alignment delay.
+//!
+//! A source can be delayed by a fixed amount before it is mixed, e.g. to line a sound up
+//! with a cutscene's video or with an animation that reaches its impact frame later than
+//! the sound is triggered:
+//!
+//! ```ignore
+//! world.set_source_delay(footstep, Duration::from_millis(40))?;
+//! // Or in frames at the world's sample rate
+//! world.set_source_delay_frames(footstep, 1_920)?;
+//! ```
+//!
+//! The delay is capped by
+//! [`PetalSonicWorldDesc::max_source_delay`](crate::PetalSonicWorldDesc::max_source_delay).
+//! Changing it while the source plays crossfades from the old to the new delay over one
+//! block. It adds to the propagation delay of [`crate::distance_delay`]. A source playing
+//! once keeps rendering until the end of its clip has left the delay line, so its
+//! completion event arrives late by the delay too. Live sources (voice, network) are not
+//! delayed. Sounds that must lead their trigger cannot be delayed negatively; delay the
+//! other sounds instead, or start the clip partway in with
+//! [`PetalSonicWorld::play_from`](crate::PetalSonicWorld::play_from).
+
+/// Per-source delay line, updated on the render thread
+#[derive(Debug, Clone)]
+pub(crate) struct SourceDelay {
+    max_frames: usize,
+    /// Ring buffer of past input, allocated on first use
+    buffer: Vec<f32>,
+    write: usize,
+    /// Delay in frames
+    delay: usize,
+    /// Delay in frames during the last block, crossfaded from when the delay changed
+    previous: usize,
+    /// Frames of input still in the line after the input stopped
+    tail: usize,
+}
+
+impl SourceDelay {
+    pub fn new(max_frames: usize, delay: usize) -> Self {
+        let delay = delay.min(max_frames);
+        Self {
+            max_frames,
+            buffer: Vec::new(),
+            write: 0,
+            delay,
+            previous: delay,
+            tail: 0,
+        }
+    }
+
+    /// Set the delay in frames, applied from the next block
+    pub fn set_delay(&mut self, frames: usize) {
+        self.delay = frames.min(self.max_frames);
+    }
+
+    /// Forget the buffered audio; the next block starts at the current delay
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.previous = self.delay;
+        self.tail = 0;
+    }
+
+    /// True while input that already ended is still leaving the line
+    pub fn is_draining(&self) -> bool {
+        self.tail > 0
+    }
+
+    /// Delay a block of mono samples in place, of which the first `input_frames` are input
+    /// and the rest is treated as silence. Returns the number of leading frames that can
+    /// carry signal; the rest of the block is silent.
+    pub fn process(&mut self, samples: &mut [f32], input_frames: usize) -> usize {
+        if self.buffer.is_empty() {
+            self.buffer = vec![0.0; self.max_frames + 1];
+        }
+
+        let len = self.buffer.len();
+        let step = 1.0 / samples.len().max(1) as f32;
+        for (index, sample) in samples.iter_mut().enumerate() {
+            self.buffer[self.write] = if index < input_frames { *sample } else { 0.0 };
+            let delayed = self.buffer[(self.write + len - self.delay) % len];
+            *sample = if self.previous == self.delay {
+                delayed
+            } else {
+                let old = self.buffer[(self.write + len - self.previous) % len];
+                old + (delayed - old) * step * (index + 1) as f32
+            };
+            self.write = (self.write + 1) % len;
+        }
+
+        let pending = if input_frames > 0 {
+            self.delay.max(self.previous)
+        } else {
+            self.tail
+        };
+        self.previous = self.delay;
+        self.tail = pending.saturating_sub(samples.len() - input_frames);
+        (input_frames + pending).min(samples.len())
+    }
+}
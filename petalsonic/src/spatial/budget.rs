@@ -0,0 +1,141 @@
+// CPU budget for the spatial processor
+//
+// The processor measures how long each source takes to render (direct effect + ambisonics
+// encode) and how long the shared work takes (ambisonics decode, mixing). When the
+// estimated cost of a block exceeds the budget, the most distant sources are rendered with
+// the cheap stereo panner instead of full HRTF processing until the load drops again.
+
+use crate::config::SourceConfig;
+use crate::math::Vec3;
+use crate::playback::PlaybackInstance;
+use crate::world::SourceId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Weight of a new measurement in the running cost averages
+const COST_SMOOTHING: f32 = 0.1;
+
+/// Degraded sources are only restored once the estimated cost fits into this fraction of
+/// the budget, so sources do not flip between HRTF and panning every block
+const RECOVERY_HEADROOM: f32 = 0.8;
+
+/// Number of degraded sources out of the spatial sources of a block
+pub(crate) type SpatialDegradation = (usize, usize);
+
+#[derive(Debug)]
+pub(crate) struct SpatialBudget {
+    /// Budget per block in microseconds, `None` if unlimited
+    budget_us: Option<f32>,
+    /// Running average cost of each source in microseconds
+    source_costs: HashMap<SourceId, f32>,
+    /// Running average cost of the shared per-block work in microseconds
+    fixed_cost_us: f32,
+    /// Sources rendered with HRTF in the previous block
+    full_sources: usize,
+    degraded_sources: usize,
+    change: Option<SpatialDegradation>,
+}
+
+impl SpatialBudget {
+    pub fn new() -> Self {
+        Self {
+            budget_us: None,
+            source_costs: HashMap::new(),
+            fixed_cost_us: 0.0,
+            full_sources: usize::MAX,
+            degraded_sources: 0,
+            change: None,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget_us = budget.map(|budget| budget.as_secs_f32() * 1_000_000.0);
+    }
+
+    /// Decide which sources get full processing in this block
+    ///
+    /// Sorts `instances` nearest-first and returns how many of them fit into the budget;
+    /// the remaining (most distant) sources are to be degraded.
+    pub fn plan(
+        &mut self,
+        instances: &mut [(SourceId, &mut PlaybackInstance)],
+        listener_position: Vec3,
+    ) -> usize {
+        let total = instances.len();
+        self.source_costs
+            .retain(|id, _| instances.iter().any(|(source_id, _)| source_id == id));
+
+        let full = match self.budget_us {
+            Some(budget_us) => {
+                let distance = |instance: &PlaybackInstance| match instance.config {
+                    SourceConfig::Spatial { position, .. } => {
+                        (position - listener_position).length_squared()
+                    }
+                    _ => 0.0,
+                };
+                instances.sort_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)));
+
+                // Sources that were never measured are assumed to cost the average
+                let (sum, count) = self
+                    .source_costs
+                    .values()
+                    .fold((0.0, 0usize), |(sum, count), cost| (sum + cost, count + 1));
+                let default_cost = if count > 0 { sum / count as f32 } else { 0.0 };
+
+                let available = budget_us - self.fixed_cost_us;
+                let mut cumulative = 0.0;
+                let mut fits = 0;
+                let mut fits_with_headroom = 0;
+                for (source_id, _) in instances.iter() {
+                    cumulative += self
+                        .source_costs
+                        .get(source_id)
+                        .copied()
+                        .unwrap_or(default_cost);
+                    if cumulative > available {
+                        break;
+                    }
+                    fits += 1;
+                    if cumulative <= available * RECOVERY_HEADROOM {
+                        fits_with_headroom += 1;
+                    }
+                }
+
+                if fits < self.full_sources {
+                    fits
+                } else {
+                    self.full_sources.max(fits_with_headroom).min(fits)
+                }
+            }
+            None => total,
+        };
+
+        self.full_sources = full;
+        let degraded = total - full;
+        if degraded != self.degraded_sources {
+            self.degraded_sources = degraded;
+            self.change = Some((degraded, total));
+        }
+        full
+    }
+
+    /// Record the time one source took to render with full processing
+    pub fn record_source(&mut self, source_id: SourceId, elapsed: Duration) {
+        let elapsed_us = elapsed.as_secs_f32() * 1_000_000.0;
+        self.source_costs
+            .entry(source_id)
+            .and_modify(|cost| *cost += (elapsed_us - *cost) * COST_SMOOTHING)
+            .or_insert(elapsed_us);
+    }
+
+    /// Record the time the shared per-block work took
+    pub fn record_fixed(&mut self, elapsed: Duration) {
+        let elapsed_us = elapsed.as_secs_f32() * 1_000_000.0;
+        self.fixed_cost_us += (elapsed_us - self.fixed_cost_us) * COST_SMOOTHING;
+    }
+
+    /// Returns the degradation if it changed since the last call
+    pub fn take_change(&mut self) -> Option<SpatialDegradation> {
+        self.change.take()
+    }
+}
@@ -0,0 +1,46 @@
+/// Bypass toggles for the individual stages of the spatial pipeline
+///
+/// A bypassed stage passes its input through unchanged, which makes it easy to hear
+/// what each stage contributes. Toggles can be set globally on the engine
+/// (`PetalSonicEngine::set_spatial_bypass`) or per source
+/// (`PetalSonicWorld::set_spatial_bypass`); a stage is bypassed if either enables it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpatialBypass {
+    /// Skip the frequency-dependent air absorption EQ of the direct effect
+    pub air_absorption: bool,
+    /// Skip distance attenuation of the direct effect
+    pub distance_attenuation: bool,
+    /// Skip ambisonics encoding and HRTF decoding; the source is mixed equally into
+    /// both channels instead
+    pub spatialization: bool,
+}
+
+impl SpatialBypass {
+    /// No stage bypassed (normal processing)
+    pub const NONE: Self = Self {
+        air_absorption: false,
+        distance_attenuation: false,
+        spatialization: false,
+    };
+
+    /// All stages bypassed: the dry, unprocessed source signal
+    pub const ALL: Self = Self {
+        air_absorption: true,
+        distance_attenuation: true,
+        spatialization: true,
+    };
+
+    /// Returns true if no stage is bypassed
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Combine two sets of toggles; a stage is bypassed if either bypasses it
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            air_absorption: self.air_absorption || other.air_absorption,
+            distance_attenuation: self.distance_attenuation || other.distance_attenuation,
+            spatialization: self.spatialization || other.spatialization,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// Spatial source capacity
+//
+// The spatial processor renders a bounded number of sources with HRTF: each one needs its
+// own Steam Audio effects and a simulator source. Sources beyond the capacity are
+// virtualized, i.e. panned in stereo like sources over the CPU budget, until a slot frees up.
+
+/// Usage of the spatial processor's source capacity
+///
+/// Returned by
+/// [`PetalSonicEngine::spatial_source_stats`](crate::PetalSonicEngine::spatial_source_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpatialSourceStats {
+    /// Spatial sources rendered with their effects and simulator source in the last block
+    pub active: usize,
+    /// Maximum number of such sources (see
+    /// [`PetalSonicWorldDesc::max_spatial_sources`](crate::PetalSonicWorldDesc::max_spatial_sources))
+    pub capacity: usize,
+    /// Spatial sources panned in stereo in the last block because the capacity was reached
+    pub virtualized: usize,
+    /// Spatial sources rendered at a reduced level of detail in the last block (see
+    /// [`SpatialLod`](crate::config::SpatialLod))
+    pub reduced: usize,
+    /// Source clusters encoded as one emitter each in the last block (see
+    /// [`SourceClustering`](crate::config::SourceClustering))
+    pub clusters: usize,
+    /// Spatial sources mixed into those clusters
+    pub clustered: usize,
+}
@@ -0,0 +1,206 @@
+// Clustering of far-field spatial sources
+//
+// Distant sources seen from the listener in similar directions (see `SourceClustering`) are
+// assigned to cluster slots. The processor mixes the direct-effect output of a cluster's
+// members and encodes it once, at the average direction of the members, instead of once
+// per source. A slot with a single member is encoded with that source's own effect.
+//
+// Sources keep their cluster within wider margins than needed to join it, and a source
+// whose encode target changes is crossfaded over one block between the old and the new
+// target, so clusters forming and breaking up do not pop.
+
+use crate::config::{SourceClustering, SourceConfig};
+use crate::math::Vec3;
+use crate::playback::PlaybackInstance;
+use crate::world::SourceId;
+use std::collections::HashMap;
+
+/// Margin, relative to the thresholds, within which a source keeps its cluster
+const HYSTERESIS: f32 = 0.25;
+
+/// Where a source's direct-effect output is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EncodeTarget {
+    /// With the source's own encode effect, at its own direction
+    Own,
+    /// Mixed into the cluster in this slot
+    Cluster(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClusterSlot {
+    /// Unit direction from the listener, the average of the members' directions
+    direction: Vec3,
+    /// Sum of the members' unit directions in the current block
+    direction_sum: Vec3,
+    members: usize,
+    previous_members: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Membership {
+    slot: Option<usize>,
+    target: EncodeTarget,
+    previous_target: EncodeTarget,
+}
+
+#[derive(Debug)]
+pub(crate) struct SourceClusters {
+    config: Option<SourceClustering>,
+    slots: Vec<ClusterSlot>,
+    membership: HashMap<SourceId, Membership>,
+}
+
+impl SourceClusters {
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            slots: Vec::new(),
+            membership: HashMap::new(),
+        }
+    }
+
+    /// Change the clustering thresholds; `None` encodes every source on its own again
+    /// (crossfaded out of their clusters over the next block)
+    pub fn set_config(&mut self, config: Option<SourceClustering>) {
+        self.config = config;
+    }
+
+    pub fn config(&self) -> Option<SourceClustering> {
+        self.config
+    }
+
+    /// Assign the sources of this block to clusters
+    pub fn plan(
+        &mut self,
+        instances: &[(SourceId, &mut PlaybackInstance)],
+        listener_position: Vec3,
+    ) {
+        self.membership
+            .retain(|id, _| instances.iter().any(|(source_id, _)| source_id == id));
+        for slot in &mut self.slots {
+            slot.previous_members = slot.members;
+            slot.members = 0;
+            slot.direction_sum = Vec3::ZERO;
+        }
+
+        for (source_id, instance) in instances.iter() {
+            let SourceConfig::Spatial { position, .. } = instance.config else {
+                continue;
+            };
+            let offset = position - listener_position;
+            let membership = self.membership.entry(*source_id).or_insert(Membership {
+                slot: None,
+                target: EncodeTarget::Own,
+                previous_target: EncodeTarget::Own,
+            });
+            membership.slot = match &self.config {
+                Some(config) => Self::assign(&mut self.slots, config, membership.slot, offset),
+                None => None,
+            };
+        }
+
+        for slot in &mut self.slots {
+            if slot.members > 0 {
+                slot.direction = slot.direction_sum.normalize_or_zero();
+            }
+        }
+
+        let slots = &self.slots;
+        for membership in self.membership.values_mut() {
+            membership.previous_target = membership.target;
+            membership.target = match membership.slot {
+                Some(index) if slots[index].members > 1 => EncodeTarget::Cluster(index),
+                _ => EncodeTarget::Own,
+            };
+        }
+    }
+
+    /// Put a source at `offset` from the listener into a cluster slot, if it is far enough
+    fn assign(
+        slots: &mut Vec<ClusterSlot>,
+        config: &SourceClustering,
+        previous: Option<usize>,
+        offset: Vec3,
+    ) -> Option<usize> {
+        let distance = offset.length();
+        let direction = offset.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        let index = match previous {
+            // Stay in the previous cluster within the wider margins
+            Some(index)
+                if distance >= config.min_distance * (1.0 - HYSTERESIS)
+                    && slots[index].direction.angle_between(direction)
+                        <= config.max_angle * (1.0 + HYSTERESIS) =>
+            {
+                index
+            }
+            _ if distance < config.min_distance => return None,
+            _ => {
+                let nearest = slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, slot)| slot.members > 0 || slot.previous_members > 0)
+                    .map(|(index, slot)| (index, slot.direction.angle_between(direction)))
+                    .filter(|(_, angle)| *angle <= config.max_angle)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b));
+                match nearest {
+                    Some((index, _)) => index,
+                    None => {
+                        // Slots emptied in this block still carry the fade out of their
+                        // last members, so only reuse slots unused for a whole block
+                        let free = slots
+                            .iter()
+                            .position(|slot| slot.members == 0 && slot.previous_members == 0);
+                        let index = free.unwrap_or_else(|| {
+                            slots.push(ClusterSlot {
+                                direction,
+                                direction_sum: Vec3::ZERO,
+                                members: 0,
+                                previous_members: 0,
+                            });
+                            slots.len() - 1
+                        });
+                        slots[index].direction = direction;
+                        index
+                    }
+                }
+            }
+        };
+
+        let slot = &mut slots[index];
+        slot.members += 1;
+        slot.direction_sum += direction;
+        Some(index)
+    }
+
+    /// Encode targets of a source in the previous and the current block
+    pub fn targets(&self, source_id: SourceId) -> (EncodeTarget, EncodeTarget) {
+        self.membership
+            .get(&source_id)
+            .map_or((EncodeTarget::Own, EncodeTarget::Own), |membership| {
+                (membership.previous_target, membership.target)
+            })
+    }
+
+    /// Unit direction from the listener of the cluster in `slot`
+    pub fn direction(&self, slot: usize) -> Vec3 {
+        self.slots[slot].direction
+    }
+
+    /// Number of slots, some of which may be empty
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of clusters encoded in this block and of sources mixed into them
+    pub fn stats(&self) -> (usize, usize) {
+        let clusters = self.slots.iter().filter(|slot| slot.members > 1);
+        clusters.fold((0, 0), |(clusters, sources), slot| {
+            (clusters + 1, sources + slot.members)
+        })
+    }
+}
@@ -1,36 +1,35 @@
 use crate::error::{PetalSonicError, Result};
+use crate::logging::rt_debug;
+use crate::math::Vec3;
+use crate::spatial::lod::SourceLod;
+use crate::spatial::simulation::DirectOutputs;
 use crate::world::SourceId;
 use audionimbus::{
     AmbisonicsEncodeEffect, AmbisonicsEncodeEffectSettings, AudioSettings, Context, DirectEffect,
-    DirectEffectSettings, SimulationFlags, Simulator, Source, SourceSettings,
+    DirectEffectSettings,
 };
 use std::collections::HashMap;
 
 /// Per-source spatial effects (DirectEffect + AmbisonicsEncodeEffect)
+///
+/// The Steam Audio simulation source lives on the simulation thread.
 pub struct SpatialSourceEffects {
-    /// Steam Audio source object for simulation
-    pub source: Source,
     /// Direct effect (distance attenuation, air absorption)
     pub direct_effect: DirectEffect,
     /// Ambisonics encode effect (spatial encoding)
     pub ambisonics_encode_effect: AmbisonicsEncodeEffect,
+    /// Smoothed simulation outputs applied in the previous block
+    pub(crate) direct_outputs: Option<DirectOutputs>,
+    /// Level of detail the source is rendered at
+    pub(crate) lod: SourceLod,
+    /// Simulation outputs and direction of the last refresh, held by reduced sources
+    pub(crate) held_outputs: Option<DirectOutputs>,
+    pub(crate) held_direction: Option<Vec3>,
 }
 
 impl SpatialSourceEffects {
     /// Create effects for a new spatial source
-    pub fn new(
-        context: &Context,
-        simulator: &Simulator<audionimbus::Direct>,
-        audio_settings: &AudioSettings,
-    ) -> Result<Self> {
-        let source = Source::try_new(
-            simulator,
-            &SourceSettings {
-                flags: SimulationFlags::DIRECT,
-            },
-        )
-        .map_err(|e| PetalSonicError::SpatialAudio(format!("Failed to create source: {}", e)))?;
-
+    pub fn new(context: &Context, audio_settings: &AudioSettings) -> Result<Self> {
         let direct_effect = DirectEffect::try_new(
             context,
             audio_settings,
@@ -50,9 +49,42 @@ impl SpatialSourceEffects {
         })?;
 
         Ok(Self {
-            source,
             direct_effect,
             ambisonics_encode_effect,
+            direct_outputs: None,
+            lod: SourceLod::default(),
+            held_outputs: None,
+            held_direction: None,
+        })
+    }
+}
+
+/// Encode effect and mix of a source cluster (see `spatial::cluster`)
+pub struct ClusterEffects {
+    /// Ambisonics encode effect of the cluster's virtual emitter
+    pub ambisonics_encode_effect: AmbisonicsEncodeEffect,
+    /// Direct-effect output of the cluster's members summed in the current block
+    pub mix: Vec<f32>,
+    /// Set once a member was mixed in the current block
+    pub fed: bool,
+}
+
+impl ClusterEffects {
+    /// Create the effect and mix buffer of a new cluster slot
+    pub fn new(context: &Context, audio_settings: &AudioSettings) -> Result<Self> {
+        let ambisonics_encode_effect = AmbisonicsEncodeEffect::try_new(
+            context,
+            audio_settings,
+            &AmbisonicsEncodeEffectSettings { max_order: 2 },
+        )
+        .map_err(|e| {
+            PetalSonicError::SpatialAudio(format!("Failed to create AmbisonicsEncodeEffect: {}", e))
+        })?;
+
+        Ok(Self {
+            ambisonics_encode_effect,
+            mix: vec![0.0; audio_settings.frame_size as usize],
+            fed: false,
         })
     }
 }
@@ -74,27 +106,23 @@ impl SpatialEffectsManager {
         &mut self,
         source_id: SourceId,
         context: &Context,
-        simulator: &mut Simulator<audionimbus::Direct>,
         audio_settings: &AudioSettings,
     ) -> Result<()> {
         if self.effects.contains_key(&source_id) {
             log::warn!("Effects for source {} already exist, replacing", source_id);
         }
 
-        let effects = SpatialSourceEffects::new(context, simulator, audio_settings)?;
-
-        // Add source to simulator
-        simulator.add_source(&effects.source);
+        let effects = SpatialSourceEffects::new(context, audio_settings)?;
 
         self.effects.insert(source_id, effects);
-        log::debug!("Created spatial effects for source {}", source_id);
+        rt_debug!("Created spatial effects for source {}", source_id);
         Ok(())
     }
 
     /// Remove effects for a spatial source
     pub fn remove_effects_for_source(&mut self, source_id: SourceId) {
         if self.effects.remove(&source_id).is_some() {
-            log::debug!("Removed spatial effects for source {}", source_id);
+            rt_debug!("Removed spatial effects for source {}", source_id);
         }
     }
 
@@ -114,6 +142,16 @@ impl SpatialEffectsManager {
         self.effects.contains_key(&source_id)
     }
 
+    /// Number of sources with effects
+    pub fn source_count(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Sources with effects
+    pub fn source_ids(&self) -> impl Iterator<Item = SourceId> + '_ {
+        self.effects.keys().copied()
+    }
+
     /// Clear all effects
     #[allow(dead_code)]
     pub fn clear(&mut self) {
@@ -1,53 +1,49 @@
+use crate::config::{HrtfConfig, HrtfNormalization};
 use crate::error::{PetalSonicError, Result};
 use audionimbus::{AudioSettings, Context, Hrtf, HrtfSettings, Sofa, VolumeNormalization};
 
-/// Load HRTF with default settings
+/// Load the HRTF described by `config`
 ///
-/// This uses Steam Audio's built-in default HRTF. In the future, this can be extended
-/// to support custom SOFA files.
-pub fn create_default_hrtf(context: &Context, audio_settings: &AudioSettings) -> Result<Hrtf> {
-    let hrtf = Hrtf::try_new(
-        context,
-        audio_settings,
-        &HrtfSettings {
-            volume_normalization: VolumeNormalization::None,
-            sofa_information: None, // Use default HRTF
-            ..Default::default()
-        },
-    )
-    .map_err(|e| PetalSonicError::SpatialAudio(format!("Failed to create HRTF: {}", e)))?;
-
-    log::info!("Created default HRTF");
-    Ok(hrtf)
-}
-
-/// Load HRTF from a custom SOFA file
+/// Uses Steam Audio's built-in default HRTF unless `config` names a SOFA file.
 ///
 /// # Arguments
 /// * `context` - Steam Audio context
 /// * `audio_settings` - Audio settings
-/// * `sofa_path` - Path to the SOFA file
-pub fn create_hrtf_from_file(
+/// * `config` - SOFA file, gain and loudness normalization
+pub fn create_hrtf(
     context: &Context,
     audio_settings: &AudioSettings,
-    sofa_path: &str,
+    config: &HrtfConfig,
 ) -> Result<Hrtf> {
-    let hrtf_data = std::fs::read(sofa_path)
-        .map_err(|e| PetalSonicError::SpatialAudio(format!("Failed to read HRTF file: {}", e)))?;
+    let sofa_information = match &config.sofa_path {
+        Some(path) => {
+            let hrtf_data = std::fs::read(path).map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to read HRTF file: {}", e))
+            })?;
+            Some(Sofa::Buffer(hrtf_data))
+        }
+        None => None, // Use default HRTF
+    };
+
+    let volume_normalization = match config.normalization {
+        HrtfNormalization::None => VolumeNormalization::None,
+        HrtfNormalization::RootMeanSquared => VolumeNormalization::RootMeanSquared,
+    };
 
     let hrtf = Hrtf::try_new(
         context,
         audio_settings,
         &HrtfSettings {
-            volume_normalization: VolumeNormalization::None,
-            sofa_information: Some(Sofa::Buffer(hrtf_data)),
-            ..Default::default()
+            volume: 10f32.powf(config.volume_db / 20.0),
+            volume_normalization,
+            sofa_information,
         },
     )
-    .map_err(|e| {
-        PetalSonicError::SpatialAudio(format!("Failed to create HRTF from file: {}", e))
-    })?;
+    .map_err(|e| PetalSonicError::SpatialAudio(format!("Failed to create HRTF: {}", e)))?;
 
-    log::info!("Created HRTF from file: {}", sofa_path);
+    match &config.sofa_path {
+        Some(path) => log::info!("Created HRTF from file: {}", path),
+        None => log::info!("Created default HRTF"),
+    }
     Ok(hrtf)
 }
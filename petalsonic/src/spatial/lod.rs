@@ -0,0 +1,102 @@
+// Level of detail of spatial sources
+//
+// Distant and quiet sources (see `SpatialLod`) are encoded in first-order ambisonics, and
+// their direction and simulation outputs are held between refreshes every
+// `update_interval` blocks. The first-order channels are the first four channels of the
+// second-order field, so a source changing level is encoded at second order for one block
+// with its higher-order channels faded out (or in) over that block instead of stepping.
+
+use crate::config::SpatialLod;
+
+/// Ambisonics order of sources at full detail
+pub(crate) const FULL_ORDER: u32 = 2;
+
+/// Ambisonics order of reduced sources
+pub(crate) const REDUCED_ORDER: u32 = 1;
+
+/// Margin, relative to the thresholds, a reduced source must clear to be restored, so
+/// sources near a threshold do not switch level every block
+const HYSTERESIS: f32 = 0.1;
+
+/// How a source is rendered in one block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LodBlock {
+    /// Ambisonics order to encode at
+    pub order: u32,
+    /// Refresh the direction and simulation outputs (otherwise the held ones are used)
+    pub refresh: bool,
+    /// Gain of the channels above first order at the start and end of the block
+    pub higher_order_gain: (f32, f32),
+}
+
+/// Level of detail state of one source
+#[derive(Debug, Default)]
+pub(crate) struct SourceLod {
+    reduced: bool,
+    /// Blocks since the last refresh of a reduced source
+    blocks_since_refresh: usize,
+}
+
+impl SourceLod {
+    /// Pick the level of the next block from the source's distance to the listener and its
+    /// simulated gain; `lod` is `None` when every source is rendered at full detail
+    pub fn next_block(&mut self, lod: Option<&SpatialLod>, distance: f32, gain: f32) -> LodBlock {
+        let reduced = match lod {
+            None => false,
+            Some(lod) if self.reduced => {
+                distance >= lod.far_distance * (1.0 - HYSTERESIS)
+                    || gain <= lod.quiet_gain * (1.0 + HYSTERESIS)
+            }
+            Some(lod) => distance >= lod.far_distance || gain <= lod.quiet_gain,
+        };
+        let was_reduced = std::mem::replace(&mut self.reduced, reduced);
+
+        match (was_reduced, reduced) {
+            (true, true) => {
+                let interval = lod.map_or(1, |lod| lod.update_interval);
+                self.blocks_since_refresh = (self.blocks_since_refresh + 1) % interval;
+                LodBlock {
+                    order: REDUCED_ORDER,
+                    refresh: self.blocks_since_refresh == 0,
+                    higher_order_gain: (0.0, 0.0),
+                }
+            }
+            (false, false) => LodBlock {
+                order: FULL_ORDER,
+                refresh: true,
+                higher_order_gain: (1.0, 1.0),
+            },
+            // Crossfade at full order, refreshed so the fade starts from current values
+            (false, true) => {
+                self.blocks_since_refresh = 0;
+                LodBlock {
+                    order: FULL_ORDER,
+                    refresh: true,
+                    higher_order_gain: (1.0, 0.0),
+                }
+            }
+            (true, false) => LodBlock {
+                order: FULL_ORDER,
+                refresh: true,
+                higher_order_gain: (0.0, 1.0),
+            },
+        }
+    }
+
+    /// Returns true if the source was reduced in its last block
+    pub fn is_reduced(&self) -> bool {
+        self.reduced
+    }
+}
+
+/// Scale the channels above first order of a planar second-order block from `gain.0` at
+/// its first frame to `gain.1` at its last
+pub(crate) fn fade_higher_orders(encoded: &mut [f32], frame_size: usize, gain: (f32, f32)) {
+    let first_order_channels = ((REDUCED_ORDER + 1) * (REDUCED_ORDER + 1)) as usize;
+    let step = (gain.1 - gain.0) / frame_size.saturating_sub(1).max(1) as f32;
+    for channel in encoded[first_order_channels * frame_size..].chunks_exact_mut(frame_size) {
+        for (i, sample) in channel.iter_mut().enumerate() {
+            *sample *= gain.0 + step * i as f32;
+        }
+    }
+}
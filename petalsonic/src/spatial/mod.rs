@@ -1,11 +1,59 @@
 // Spatial audio module
 //
 // This module provides Steam Audio integration for 3D spatial audio processing.
-// It includes effect management, HRTF loading, and the main spatial processor.
+// It includes effect management, HRTF loading, and the main spatial processor, plus a
+// stereo ITD/ILD panner used when spatialization is disabled or Steam Audio is not built in
+// (the `steam-audio` cargo feature is off). Both implement the `Spatializer` backend trait.
 
+#[cfg(feature = "steam-audio")]
+mod budget;
+mod bypass;
+mod capacity;
+#[cfg(feature = "steam-audio")]
+mod cluster;
+#[cfg(feature = "steam-audio")]
 mod effects;
+#[cfg(feature = "steam-audio")]
 mod hrtf;
+#[cfg(feature = "steam-audio")]
+mod lod;
+mod panner;
+#[cfg(feature = "steam-audio")]
 mod processor;
+#[cfg(feature = "steam-audio")]
+mod simulation;
+mod spatializer;
+#[cfg(not(feature = "steam-audio"))]
+mod unavailable;
+
+/// Scale factor converting world units to the meters used for distance attenuation
+pub(crate) const DISTANCE_SCALER: f32 = 10.0;
+
+/// Speed of sound in meters per second
+pub(crate) const SPEED_OF_SOUND: f32 = 343.0;
+
+/// Air absorption coefficients per meter of the low, mid and high bands (Steam Audio's
+/// default model)
+const AIR_ABSORPTION_COEFFICIENTS: [f32; 3] = [0.0002, 0.0017, 0.0182];
+
+/// Gain of the inverse-distance model at `distance` world units (Steam Audio's default
+/// model, used wherever there is no simulation result)
+pub(crate) fn distance_attenuation(distance: f32) -> f32 {
+    1.0 / (distance * DISTANCE_SCALER).max(1.0)
+}
+
+/// Air absorption gains of the low, mid and high bands at `distance` world units
+pub(crate) fn air_absorption(distance: f32) -> [f32; 3] {
+    let meters = distance * DISTANCE_SCALER;
+    AIR_ABSORPTION_COEFFICIENTS.map(|coefficient| (-coefficient * meters).exp())
+}
 
 // Public API
+pub use bypass::SpatialBypass;
+pub use capacity::SpatialSourceStats;
+pub use panner::StereoPanner;
+#[cfg(feature = "steam-audio")]
 pub use processor::SpatialProcessor;
+pub use spatializer::Spatializer;
+#[cfg(not(feature = "steam-audio"))]
+pub use unavailable::SpatialProcessor;
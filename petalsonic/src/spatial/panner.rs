@@ -0,0 +1,225 @@
+// Stereo panner used for spatial sources when Steam Audio is not available
+//
+// Sources are placed with an interaural level difference (equal-power panning by azimuth)
+// and an interaural time difference (Woodworth's spherical head model), and attenuated with
+// the same inverse-distance model the spatial processor falls back to before its first
+// simulation result. Gains and delays are interpolated across each block to avoid zipper
+// noise when sources or the listener move.
+
+use crate::config::{ListenerCalibration, OutputMode, SourceConfig};
+use crate::math::{Pose, Vec3};
+use crate::playback::PlaybackInstance;
+use crate::spatial::{SPEED_OF_SOUND, distance_attenuation};
+use crate::stems::StemRecorder;
+use crate::world::SourceId;
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+
+/// Level and delay of one ear
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct EarParams {
+    gain: f32,
+    /// Delay in samples
+    delay: f32,
+}
+
+/// Per-source state carried across blocks
+#[derive(Debug)]
+struct PanState {
+    /// Tail of the previous input, read by delayed ears
+    history: Vec<f32>,
+    left: EarParams,
+    right: EarParams,
+    active: bool,
+}
+
+/// Stereo ITD/ILD panner for spatial sources, used instead of the spatial processor when
+/// spatialization is disabled or unavailable
+///
+/// It can also be installed explicitly as a cheap [`Spatializer`](crate::spatial::Spatializer)
+/// backend with [`PetalSonicEngine::set_spatializer`](crate::PetalSonicEngine::set_spatializer).
+#[derive(Debug)]
+pub struct StereoPanner {
+    listener: Pose,
+    sample_rate: f32,
+    /// Head radius of the spherical head model in meters
+    head_radius: f32,
+    /// Scale of the lateral position of sources (see [`ListenerCalibration`])
+    interaural_width: f32,
+    /// Interaural delays are only applied for headphones
+    output_mode: OutputMode,
+    /// Largest interaural delay in samples (sound arriving from the side with the largest
+    /// supported head radius)
+    max_delay: usize,
+    states: HashMap<SourceId, PanState>,
+    /// Scratch buffers: source input and history followed by the input
+    input: Vec<f32>,
+    extended: Vec<f32>,
+}
+
+impl StereoPanner {
+    /// Create a panner for audio at `sample_rate` (the world sample rate)
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        let max_itd = ListenerCalibration::MAX_HEAD_RADIUS / SPEED_OF_SOUND
+            * (std::f32::consts::FRAC_PI_2 + 1.0);
+        let calibration = ListenerCalibration::default();
+        Self {
+            listener: Pose::default(),
+            sample_rate,
+            head_radius: calibration.head_radius,
+            interaural_width: calibration.interaural_width,
+            output_mode: OutputMode::default(),
+            max_delay: (max_itd * sample_rate).ceil() as usize + 1,
+            states: HashMap::new(),
+            input: Vec::new(),
+            extended: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_listener_pose(&mut self, pose: Pose) {
+        self.listener = pose;
+    }
+
+    /// Adjust the head radius and interaural width (see [`ListenerCalibration`])
+    pub fn set_calibration(&mut self, calibration: ListenerCalibration) {
+        let calibration = calibration.clamped();
+        self.head_radius = calibration.head_radius;
+        self.interaural_width = calibration.interaural_width;
+    }
+
+    /// Pan with interaural delays for headphones, or by level only for speakers
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        self.output_mode = output_mode;
+    }
+
+    /// Forget the interaural delay history of a source
+    pub(crate) fn release_source(&mut self, source_id: SourceId) {
+        self.states.remove(&source_id);
+    }
+
+    /// Ear levels and delays of a source at `position`
+    fn ear_params(&self, position: Vec3) -> (EarParams, EarParams) {
+        let offset = position - self.listener.position;
+        let attenuation = distance_attenuation(offset.length());
+
+        let local = self.listener.rotation.inverse() * offset;
+        let lateral = if local.length_squared() > f32::EPSILON {
+            (local.x / local.length() * self.interaural_width).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        // ILD: equal-power law, -1 is hard left, 0 is centered, 1 is hard right
+        let angle = (lateral + 1.0) * FRAC_PI_4;
+
+        // ITD: the ear facing away from the source hears it later. Over speakers both ears
+        // hear both channels, so a delay would only comb-filter.
+        let azimuth = lateral.asin();
+        let itd = self.head_radius / SPEED_OF_SOUND * (azimuth.abs() + azimuth.abs().sin());
+        let delay = match self.output_mode {
+            OutputMode::Headphones => itd * self.sample_rate,
+            OutputMode::Speakers => 0.0,
+        };
+        let (left_delay, right_delay) = if lateral > 0.0 {
+            (delay, 0.0)
+        } else {
+            (0.0, delay)
+        };
+
+        (
+            EarParams {
+                gain: angle.cos() * attenuation,
+                delay: left_delay,
+            },
+            EarParams {
+                gain: angle.sin() * attenuation,
+                delay: right_delay,
+            },
+        )
+    }
+
+    /// Pan spatial sources into an interleaved buffer
+    ///
+    /// The left/right signal goes to the first two channels; a mono output receives the
+    /// average. Returns the number of frames filled.
+    pub(crate) fn process(
+        &mut self,
+        instances: &mut [(SourceId, &mut PlaybackInstance)],
+        output_buffer: &mut [f32],
+        channels: u16,
+        mut stems: Option<&mut StemRecorder>,
+    ) -> usize {
+        let channels = channels as usize;
+        let frame_count = output_buffer.len() / channels;
+        let history_len = self.max_delay + 1;
+        let mut frames_filled_max = 0;
+
+        self.states
+            .values_mut()
+            .for_each(|state| state.active = false);
+
+        for (source_id, instance) in instances.iter_mut() {
+            let SourceConfig::Spatial { position, .. } = instance.config else {
+                continue;
+            };
+
+            self.input.clear();
+            self.input.resize(frame_count, 0.0);
+            let frames_filled = instance.fill_buffer(&mut self.input, 1);
+            frames_filled_max = frames_filled_max.max(frames_filled);
+            if let Some(stems) = stems.as_deref_mut() {
+                stems.record_mono(*source_id, &self.input);
+            }
+
+            let (left, right) = self.ear_params(position);
+            let state = self.states.entry(*source_id).or_insert_with(|| PanState {
+                history: vec![0.0; history_len],
+                left,
+                right,
+                active: true,
+            });
+            state.active = true;
+
+            self.extended.clear();
+            self.extended.extend_from_slice(&state.history);
+            self.extended.extend_from_slice(&self.input);
+
+            for (i, frame) in output_buffer.chunks_exact_mut(channels).enumerate() {
+                let t = (i + 1) as f32 / frame_count as f32;
+                let left_sample = read_ear(&self.extended, history_len + i, state.left, left, t);
+                let right_sample = read_ear(&self.extended, history_len + i, state.right, right, t);
+                if channels == 1 {
+                    frame[0] += 0.5 * (left_sample + right_sample);
+                } else {
+                    frame[0] += left_sample;
+                    frame[1] += right_sample;
+                }
+            }
+
+            state.left = left;
+            state.right = right;
+            let tail = self.extended.len() - history_len;
+            state.history.copy_from_slice(&self.extended[tail..]);
+        }
+
+        // Forget sources that are no longer playing
+        self.states.retain(|_, state| state.active);
+
+        frames_filled_max
+    }
+}
+
+/// Read one ear's sample at `index`, interpolating gain and delay from `from` to `to`
+fn read_ear(extended: &[f32], index: usize, from: EarParams, to: EarParams, t: f32) -> f32 {
+    let gain = from.gain + (to.gain - from.gain) * t;
+    let delay = from.delay + (to.delay - from.delay) * t;
+
+    let position = index as f32 - delay;
+    let base = position.floor();
+    let fraction = position - base;
+    let base = base as usize;
+    let a = extended[base];
+    let b = extended.get(base + 1).copied().unwrap_or(a);
+    (a + (b - a) * fraction) * gain
+}
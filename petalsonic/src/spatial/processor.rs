@@ -1,53 +1,113 @@
-use crate::config::SourceConfig;
+use crate::config::{
+    HrtfConfig, ListenerCalibration, OutputMode, SimulationQuality, SourceClustering, SourceConfig,
+    SpatialLod,
+};
 use crate::error::{PetalSonicError, Result};
-use crate::math::{Pose, Vec3};
+use crate::logging::rt_warn;
+use crate::math::{Pose, Quat, Vec3};
 use crate::playback::PlaybackInstance;
-use crate::spatial::effects::SpatialEffectsManager;
+use crate::spatial::budget::{SpatialBudget, SpatialDegradation};
+use crate::spatial::cluster::{EncodeTarget, SourceClusters};
+use crate::spatial::effects::{ClusterEffects, SpatialEffectsManager};
 use crate::spatial::hrtf;
+use crate::spatial::lod::{self, FULL_ORDER, LodBlock};
+use crate::spatial::simulation::{DirectOutputs, SimulationThread};
+use crate::spatial::{SpatialBypass, SpatialSourceStats, StereoPanner, distance_attenuation};
+use crate::stems::StemRecorder;
 use crate::world::SourceId;
+use std::time::{Duration, Instant};
+
 use audionimbus::{
-    AirAbsorptionModel, AmbisonicsDecodeEffect, AmbisonicsDecodeEffectParams,
-    AmbisonicsDecodeEffectSettings, AmbisonicsEncodeEffectParams, AudioBufferSettings,
-    AudioSettings, Context, CoordinateSystem, Direct, DirectEffectParams,
-    DirectSimulationParameters, DirectSimulationSettings, Direction, DistanceAttenuationModel,
-    Equalizer, Hrtf, Point, Scene, SceneParams, SceneSettings, SimulationFlags, SimulationInputs,
-    SimulationSharedInputs, Simulator, SpeakerLayout, Vector3,
-    audio_buffer::AudioBuffer as AudioNimbusAudioBuffer, geometry,
+    AmbisonicsDecodeEffect, AmbisonicsDecodeEffectParams, AmbisonicsDecodeEffectSettings,
+    AmbisonicsEncodeEffectParams, AmbisonicsRotationEffect, AmbisonicsRotationEffectParams,
+    AmbisonicsRotationEffectSettings, AudioBufferSettings, AudioSettings, Context,
+    CoordinateSystem, DirectEffectParams, DirectSimulationSettings, Direction, Equalizer, Hrtf,
+    Scene, SceneParams, SceneSettings, Simulator, SpeakerLayout, Vector3,
+    audio_buffer::AudioBuffer as AudioNimbusAudioBuffer,
 };
 
+/// Fastest rotation of the sound field, in radians per second; faster listener turns (a
+/// head tracker jumping after a dropout) are spread over the following blocks
+const MAX_FIELD_ROTATION_SPEED: f32 = 4.0 * std::f32::consts::PI;
+
 /// Spatial audio processor that manages Steam Audio integration
 pub struct SpatialProcessor {
     // Steam Audio core objects
     context: Context,
-    simulator: Simulator<Direct>,
-    #[allow(dead_code)] // Must be kept alive for simulator lifetime
-    scene: Scene,
     hrtf: Hrtf,
 
+    // Simulator and scene live on the simulation thread
+    simulation: SimulationThread,
+
     // Shared ambisonics decode effect (used for all sources)
     ambisonics_decode_effect: AmbisonicsDecodeEffect,
+    // Rotates the summed sound field from world space into the listener's frame
+    ambisonics_rotation_effect: AmbisonicsRotationEffect,
 
     // Per-source effects management
     effects_manager: SpatialEffectsManager,
 
     // Configuration
+    quality: SimulationQuality,
+    /// Occlusion sample count the simulator was created with (upper bound for `quality`)
+    max_num_occlusion_samples: usize,
     frame_size: usize,
     sample_rate: u32,
     distance_scaler: f32,
+    /// Per-block smoothing applied to simulation outputs (see `smoothing_coefficient`)
+    smoothing_coefficient: f32,
 
     // Cached buffers to avoid allocations
     cached_input_buf: Vec<f32>,             // Input mono samples
     cached_direct_buf: Vec<f32>,            // After DirectEffect
     cached_summed_encoded_buf: Vec<f32>,    // Accumulated ambisonics (9 channels for order 2)
     cached_ambisonics_encode_buf: Vec<f32>, // Temp buffer for encoding
+    cached_rotated_buf: Vec<f32>,           // Summed ambisonics in the listener's frame
     cached_ambisonics_decode_buf: Vec<f32>, // After AmbisonicsDecode (stereo)
     cached_binaural_processed: Vec<f32>,    // Final binaural output (interleaved stereo)
+    cached_dry_buf: Vec<f32>,               // Accumulated mono of sources bypassing spatialization
+    cached_route_buf: Vec<f32>,             // Direct output of a source moving between clusters
+
+    // Stages bypassed for all sources
+    bypass: SpatialBypass,
+
+    /// Scale of the difference between the ears of the binaural output
+    interaural_width: f32,
+    /// Decode to binaural (headphones) or to the stereo speaker layout
+    output_mode: OutputMode,
+
+    /// Maximum number of sources with effects; sources beyond it are panned by
+    /// `fallback_panner`
+    max_sources: usize,
+    /// Sources rendered with their effects in the last block
+    rendered_sources: usize,
+    /// Sources panned in the last block because `max_sources` was reached
+    virtualized_sources: usize,
+
+    /// Level of detail of distant and quiet sources, `None` for full detail everywhere
+    lod: Option<SpatialLod>,
+    /// Sources rendered at a reduced level of detail in the last block
+    reduced_sources: usize,
+
+    // Distant sources mixed into shared emitters, and the effects of each cluster slot
+    clusters: SourceClusters,
+    cluster_effects: Vec<ClusterEffects>,
+
+    // CPU budget; sources over budget are panned by `fallback_panner`
+    budget: SpatialBudget,
+    fallback_panner: StereoPanner,
+    /// Duration of the last `process_spatial_sources` call, until taken
+    process_time: Duration,
 
     // Listener state
     listener_position: Vec3,
     listener_up: Vec3,
     listener_front: Vec3,
     listener_right: Vec3,
+    listener_rotation: Quat,
+    /// Rotation applied to the sound field in the last block, following `listener_rotation`;
+    /// `None` before the first block, which starts at the listener rotation
+    field_rotation: Option<Quat>,
 }
 
 impl SpatialProcessor {
@@ -57,13 +117,21 @@ impl SpatialProcessor {
     /// * `sample_rate` - Sample rate for audio processing
     /// * `frame_size` - Number of frames to process per call
     /// * `distance_scaler` - Scale factor to convert game units to meters (default: 10.0)
-    /// * `hrtf_path` - Optional path to a custom HRTF SOFA file (None uses default HRTF)
+    /// * `hrtf` - HRTF to load (SOFA file, gain and normalization)
+    /// * `quality` - Simulation parameters
+    /// * `simulation_rate_hz` - Simulation updates per second (runs on its own thread)
+    /// * `max_sources` - Number of sources rendered with HRTF (see
+    ///   [`PetalSonicWorldDesc::max_spatial_sources`](crate::PetalSonicWorldDesc::max_spatial_sources))
     pub fn new(
         sample_rate: u32,
         frame_size: usize,
         distance_scaler: f32,
-        hrtf_path: Option<&str>,
+        hrtf: &HrtfConfig,
+        quality: SimulationQuality,
+        simulation_rate_hz: u32,
+        max_sources: usize,
     ) -> Result<Self> {
+        let max_sources = max_sources.max(1);
         log::info!(
             "Initializing Steam Audio spatial processor (sample_rate: {} Hz, frame_size: {}, distance_scaler: {})",
             sample_rate,
@@ -84,11 +152,7 @@ impl SpatialProcessor {
         };
 
         // Create HRTF (custom or default)
-        let hrtf = if let Some(path) = hrtf_path {
-            hrtf::create_hrtf_from_file(&context, &audio_settings, path)?
-        } else {
-            hrtf::create_default_hrtf(&context, &audio_settings)?
-        };
+        let hrtf = hrtf::create_hrtf(&context, &audio_settings, hrtf)?;
 
         // Create ambisonics decode effect (shared across all sources)
         let ambisonics_decode_effect = AmbisonicsDecodeEffect::try_new(
@@ -106,11 +170,23 @@ impl SpatialProcessor {
 
         log::info!("Created shared AmbisonicsDecodeEffect");
 
+        let ambisonics_rotation_effect = AmbisonicsRotationEffect::try_new(
+            &context,
+            &audio_settings,
+            &AmbisonicsRotationEffectSettings { max_order: 2 },
+        )
+        .map_err(|e| {
+            PetalSonicError::SpatialAudio(format!(
+                "Failed to create AmbisonicsRotationEffect: {}",
+                e
+            ))
+        })?;
+
         // Create simulator
         let mut simulator =
             Simulator::builder(SceneParams::Default, sample_rate, frame_size as u32)
                 .with_direct(DirectSimulationSettings {
-                    max_num_occlusion_samples: 32,
+                    max_num_occlusion_samples: quality.num_occlusion_samples,
                 })
                 .try_build(&context)
                 .map_err(|e| {
@@ -128,37 +204,83 @@ impl SpatialProcessor {
 
         log::info!("Created Steam Audio scene");
 
+        let simulation = SimulationThread::spawn(
+            context.clone(),
+            simulator,
+            scene,
+            quality,
+            simulation_rate_hz,
+            max_sources,
+        )?;
+
         // Pre-allocate buffers
         let cached_input_buf = vec![0.0; frame_size];
         let cached_direct_buf = vec![0.0; frame_size];
         let cached_summed_encoded_buf = vec![0.0; frame_size * 9]; // 9 channels for order 2
         let cached_ambisonics_encode_buf = vec![0.0; frame_size * 9];
+        let cached_rotated_buf = vec![0.0; frame_size * 9];
         let cached_ambisonics_decode_buf = vec![0.0; frame_size * 2]; // Stereo
         let cached_binaural_processed = vec![0.0; frame_size * 2];
+        let cached_dry_buf = vec![0.0; frame_size];
+        let cached_route_buf = vec![0.0; frame_size];
 
         Ok(Self {
             context,
-            simulator,
-            scene,
             hrtf,
+            simulation,
             ambisonics_decode_effect,
+            ambisonics_rotation_effect,
             effects_manager: SpatialEffectsManager::new(),
+            quality,
+            max_num_occlusion_samples: quality.num_occlusion_samples,
             frame_size,
             sample_rate,
             distance_scaler,
+            smoothing_coefficient: Self::smoothing_coefficient(
+                sample_rate,
+                frame_size,
+                simulation_rate_hz,
+            ),
             cached_input_buf,
             cached_direct_buf,
             cached_summed_encoded_buf,
             cached_ambisonics_encode_buf,
+            cached_rotated_buf,
             cached_ambisonics_decode_buf,
             cached_binaural_processed,
+            cached_dry_buf,
+            cached_route_buf,
+            bypass: SpatialBypass::NONE,
+            interaural_width: 1.0,
+            output_mode: OutputMode::default(),
+            max_sources,
+            rendered_sources: 0,
+            virtualized_sources: 0,
+            lod: None,
+            reduced_sources: 0,
+            clusters: SourceClusters::new(),
+            cluster_effects: Vec::new(),
+            budget: SpatialBudget::new(),
+            fallback_panner: StereoPanner::new(sample_rate),
+            process_time: Duration::ZERO,
             listener_position: Vec3::ZERO,
             listener_up: Vec3::new(0.0, 1.0, 0.0),
             listener_front: Vec3::new(0.0, 0.0, -1.0),
             listener_right: Vec3::new(1.0, 0.0, 0.0),
+            listener_rotation: Quat::IDENTITY,
+            field_rotation: None,
         })
     }
 
+    /// One-pole smoothing coefficient for simulation outputs
+    ///
+    /// Uses a time constant of one simulation period, so each new simulation result is
+    /// approached over a few render blocks instead of being applied as a step.
+    fn smoothing_coefficient(sample_rate: u32, frame_size: usize, simulation_rate_hz: u32) -> f32 {
+        let block_duration = frame_size as f32 / sample_rate as f32;
+        1.0 - (-block_duration * simulation_rate_hz as f32).exp()
+    }
+
     /// Update listener pose
     pub fn set_listener_pose(&mut self, pose: Pose) -> Result<()> {
         // Extract position and orientation from pose
@@ -168,12 +290,161 @@ impl SpatialProcessor {
         self.listener_front = pose.forward();
         self.listener_up = pose.up();
         self.listener_right = pose.right();
+        self.listener_rotation = pose.rotation;
+        self.fallback_panner.set_listener_pose(pose);
 
         Ok(())
     }
 
+    /// Change the simulation parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `quality` needs more occlusion samples than the simulator was
+    /// created with; the processor must be recreated in that case.
+    pub fn set_quality(&mut self, quality: SimulationQuality) -> Result<()> {
+        if quality.num_occlusion_samples > self.max_num_occlusion_samples {
+            return Err(PetalSonicError::SpatialAudio(format!(
+                "{} occlusion samples exceed the simulator maximum of {}",
+                quality.num_occlusion_samples, self.max_num_occlusion_samples
+            )));
+        }
+
+        log::info!("Spatial simulation quality: {:?}", quality);
+        self.quality = quality;
+        self.simulation.set_quality(quality);
+        Ok(())
+    }
+
+    /// Get the current simulation parameters
+    pub fn quality(&self) -> SimulationQuality {
+        self.quality
+    }
+
+    /// Set the stages bypassed for all sources (combined with per-source bypass toggles)
+    pub fn set_bypass(&mut self, bypass: SpatialBypass) {
+        log::info!("Global spatial bypass: {:?}", bypass);
+        self.bypass = bypass;
+    }
+
+    /// Get the stages bypassed for all sources
+    pub fn bypass(&self) -> SpatialBypass {
+        self.bypass
+    }
+
+    /// Adjust the interaural cues of the output (see [`ListenerCalibration`])
+    pub fn set_calibration(&mut self, calibration: ListenerCalibration) {
+        let calibration = calibration.clamped();
+        self.interaural_width = calibration.interaural_width;
+        self.fallback_panner.set_calibration(calibration);
+    }
+
+    /// Switch between binaural and stereo speaker output
+    ///
+    /// Only the ambisonics decode changes, so per-source effects and simulation state are
+    /// kept and the switch takes effect on the next block.
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        log::info!("Spatial output mode: {:?}", output_mode);
+        self.output_mode = output_mode;
+        self.fallback_panner.set_output_mode(output_mode);
+    }
+
+    pub fn output_mode(&self) -> OutputMode {
+        self.output_mode
+    }
+
+    /// Limit the time spent on HRTF rendering per block
+    ///
+    /// While the measured cost of a block exceeds `budget`, the most distant sources are
+    /// panned in stereo instead. `None` removes the limit.
+    pub fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget.set_budget(budget);
+    }
+
+    /// Returns the number of degraded and total spatial sources if it changed since the
+    /// last call
+    pub fn take_degradation_change(&mut self) -> Option<SpatialDegradation> {
+        self.budget.take_change()
+    }
+
+    /// Returns the time the last block of spatial processing took and resets it to zero
+    pub fn take_process_time(&mut self) -> Duration {
+        std::mem::take(&mut self.process_time)
+    }
+
+    /// Render distant and quiet sources at a reduced level of detail (see [`SpatialLod`]);
+    /// `None` renders every source at full detail
+    ///
+    /// Sources changing level are crossfaded over their next block.
+    pub fn set_lod(&mut self, lod: Option<SpatialLod>) {
+        log::info!("Spatial level of detail: {:?}", lod);
+        self.lod = lod;
+    }
+
+    pub fn lod(&self) -> Option<SpatialLod> {
+        self.lod
+    }
+
+    /// Mix distant sources in similar directions into shared emitters (see
+    /// [`SourceClustering`]); `None` encodes every source on its own
+    ///
+    /// Sources joining or leaving a cluster are crossfaded over their next block.
+    pub fn set_clustering(&mut self, clustering: Option<SourceClustering>) {
+        log::info!("Source clustering: {:?}", clustering);
+        self.clusters.set_config(clustering);
+    }
+
+    pub fn clustering(&self) -> Option<SourceClustering> {
+        self.clusters.config()
+    }
+
+    /// Usage of the source capacity
+    pub fn source_stats(&self) -> SpatialSourceStats {
+        let (clusters, clustered) = self.clusters.stats();
+        SpatialSourceStats {
+            active: self
+                .rendered_sources
+                .min(self.effects_manager.source_count()),
+            capacity: self.max_sources,
+            virtualized: self.virtualized_sources,
+            reduced: self.reduced_sources,
+            clusters,
+            clustered,
+        }
+    }
+
+    /// Distance attenuation and occlusion simulated for a source in the last block (before
+    /// bypasses), if it has effects
+    pub fn source_gains(&self, source_id: SourceId) -> Option<(f32, f32)> {
+        let outputs = self
+            .effects_manager
+            .get_effects(source_id)?
+            .direct_outputs?;
+        Some((outputs.distance_attenuation, outputs.occlusion))
+    }
+
+    /// Air absorption gains simulated for a source in the last block, if it has effects
+    pub fn source_air_absorption(&self, source_id: SourceId) -> Option<[f32; 3]> {
+        let outputs = self
+            .effects_manager
+            .get_effects(source_id)?
+            .direct_outputs?;
+        Some(outputs.air_absorption)
+    }
+
     /// Create effects for a spatial source
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::SpatialSourceLimit` if the source capacity is used up, or
+    /// an error if Steam Audio fails to create the effects.
     pub fn create_effects_for_source(&mut self, source_id: SourceId) -> Result<()> {
+        if !self.effects_manager.has_effects(source_id)
+            && self.effects_manager.source_count() >= self.max_sources
+        {
+            return Err(PetalSonicError::SpatialSourceLimit(self.max_sources));
+        }
+
         let audio_settings = AudioSettings {
             sampling_rate: self.sample_rate,
             frame_size: self.frame_size as u32,
@@ -182,21 +453,27 @@ impl SpatialProcessor {
         self.effects_manager.create_effects_for_source(
             source_id,
             &self.context,
-            &mut self.simulator,
             &audio_settings,
-        )
+        )?;
+        self.simulation.add_source(source_id);
+        Ok(())
     }
 
     /// Remove effects for a spatial source
     pub fn remove_effects_for_source(&mut self, source_id: SourceId) {
         self.effects_manager.remove_effects_for_source(source_id);
+        self.simulation.remove_source(source_id);
+        self.fallback_panner.release_source(source_id);
     }
 
-    /// Process all spatial sources and output to stereo buffer
+    /// Process all spatial sources and mix the binaural result into the output buffer
     ///
     /// # Arguments
     /// * `instances` - Slice of spatial playback instances to process
-    /// * `output_buffer` - Stereo output buffer (interleaved L/R)
+    /// * `output_buffer` - Interleaved output buffer to mix into
+    /// * `channels` - Number of channels in `output_buffer`. The binaural L/R signal goes to
+    ///   the first two channels; a mono output receives the average of both.
+    /// * `stems` - Stem recorder receiving the (pre-spatialization) input of recorded sources
     ///
     /// # Returns
     /// Number of frames processed
@@ -204,48 +481,158 @@ impl SpatialProcessor {
         &mut self,
         instances: &mut [(SourceId, &mut PlaybackInstance)],
         output_buffer: &mut [f32],
+        channels: u16,
+        mut stems: Option<&mut StemRecorder>,
     ) -> Result<usize> {
         if instances.is_empty() {
-            // No spatial sources, output silence
-            output_buffer.fill(0.0);
+            self.rendered_sources = 0;
             return Ok(0);
         }
+        let process_start = Instant::now();
 
         // Clear accumulation buffer
         self.cached_summed_encoded_buf.fill(0.0);
         self.cached_binaural_processed.fill(0.0);
+        self.cached_dry_buf.fill(0.0);
+        self.reduced_sources = 0;
+
+        // Publish simulation inputs; outputs are picked up as the simulation thread produces them
+        self.update_simulation_inputs(instances);
 
-        // Run simulation for all sources
-        self.simulate(instances)?;
+        // Sources beyond the source capacity are virtualized, then sources beyond the CPU
+        // budget (the most distant ones) are degraded; both are panned
+        let capacity = self.allocate_sources(instances);
+        self.rendered_sources = capacity;
+        let full_sources = self
+            .budget
+            .plan(&mut instances[..capacity], self.listener_position);
+        let (full_instances, degraded_instances) = instances.split_at_mut(full_sources);
+
+        // Group the distant sources rendered with HRTF into clusters
+        self.clusters.plan(full_instances, self.listener_position);
+        self.allocate_cluster_effects()?;
 
         // Process each spatial source
-        for (source_id, instance) in instances.iter_mut() {
-            self.process_single_source(*source_id, instance)?;
+        let mut frames_read_max = 0;
+        for (source_id, instance) in full_instances.iter_mut() {
+            let source_start = Instant::now();
+            let frames_read =
+                self.process_single_source(*source_id, instance, stems.as_deref_mut())?;
+            frames_read_max = frames_read_max.max(frames_read);
+            self.budget
+                .record_source(*source_id, source_start.elapsed());
         }
 
-        // Decode accumulated ambisonics to binaural stereo
+        let shared_start = Instant::now();
+
+        // Encode the clusters mixed from their members
+        self.encode_clusters()?;
+
+        // Rotate the accumulated ambisonics into the listener's frame, then decode them to
+        // binaural stereo
+        self.apply_ambisonics_rotation_effect()?;
         self.apply_ambisonics_decode_effect()?;
 
-        // Copy to output buffer
-        let frames_to_copy = (output_buffer.len() / 2).min(self.frame_size);
-        for i in 0..frames_to_copy {
-            output_buffer[i * 2] = self.cached_binaural_processed[i * 2];
-            output_buffer[i * 2 + 1] = self.cached_binaural_processed[i * 2 + 1];
+        // Scale the side (left minus right) signal to widen or narrow the interaural cues
+        if self.interaural_width != 1.0 {
+            for frame in self.cached_binaural_processed.chunks_exact_mut(2) {
+                let mid = 0.5 * (frame[0] + frame[1]);
+                let side = 0.5 * (frame[0] - frame[1]) * self.interaural_width;
+                frame[0] = mid + side;
+                frame[1] = mid - side;
+            }
         }
 
-        Ok(frames_to_copy)
+        // Add sources that bypass spatialization equally to both channels
+        for (i, dry) in self.cached_dry_buf.iter().enumerate() {
+            self.cached_binaural_processed[i * 2] += dry;
+            self.cached_binaural_processed[i * 2 + 1] += dry;
+        }
+
+        // Mix into output buffer
+        let channels = channels as usize;
+        let frames_to_copy = (output_buffer.len() / channels).min(self.frame_size);
+        for (frame, binaural) in output_buffer
+            .chunks_exact_mut(channels)
+            .zip(self.cached_binaural_processed.chunks_exact(2))
+            .take(frames_to_copy)
+        {
+            if channels == 1 {
+                frame[0] += 0.5 * (binaural[0] + binaural[1]);
+            } else {
+                frame[0] += binaural[0];
+                frame[1] += binaural[1];
+            }
+        }
+        self.budget.record_fixed(shared_start.elapsed());
+
+        // Clips ending within the block fill only part of it
+        let mut frames_processed = frames_read_max.min(frames_to_copy);
+        if !degraded_instances.is_empty() {
+            let frames_panned = self.fallback_panner.process(
+                degraded_instances,
+                output_buffer,
+                channels as u16,
+                stems,
+            );
+            frames_processed = frames_processed.max(frames_panned);
+        }
+
+        self.process_time = process_start.elapsed();
+        Ok(frames_processed)
+    }
+
+    /// Order `instances` so the sources to render with HRTF come first, and return how
+    /// many of them fit into the source capacity
+    ///
+    /// Sources already holding effects keep them; effects of sources that are no longer
+    /// rendered are freed to make room for new ones.
+    fn allocate_sources(&mut self, instances: &mut [(SourceId, &mut PlaybackInstance)]) -> usize {
+        let new_sources = instances
+            .iter()
+            .filter(|(source_id, _)| !self.effects_manager.has_effects(*source_id))
+            .count();
+        if self.effects_manager.source_count() + new_sources > self.max_sources {
+            let stale: Vec<SourceId> = self
+                .effects_manager
+                .source_ids()
+                .filter(|id| !instances.iter().any(|(source_id, _)| source_id == id))
+                .collect();
+            for source_id in stale {
+                self.remove_effects_for_source(source_id);
+            }
+        }
+
+        let capacity = instances.len().min(self.max_sources);
+        if capacity < instances.len() {
+            instances.sort_unstable_by_key(|(source_id, _)| {
+                !self.effects_manager.has_effects(*source_id)
+            });
+        }
+
+        let virtualized = instances.len() - capacity;
+        if virtualized > 0 && self.virtualized_sources == 0 {
+            rt_warn!(
+                "Spatial source capacity of {} reached, panning {} sources",
+                self.max_sources,
+                virtualized
+            );
+        }
+        self.virtualized_sources = virtualized;
+        capacity
     }
 
-    /// Process a single spatial source
+    /// Process a single spatial source, returning the number of frames carrying signal
     fn process_single_source(
         &mut self,
         source_id: SourceId,
         instance: &mut PlaybackInstance,
-    ) -> Result<()> {
+        stems: Option<&mut StemRecorder>,
+    ) -> Result<usize> {
         // Get spatial configuration
-        let (position, volume) = match &instance.config {
-            SourceConfig::Spatial { position, volume } => (*position, *volume),
-            _ => return Ok(()), // Not a spatial source, skip
+        let position = match &instance.config {
+            SourceConfig::Spatial { position, .. } => *position,
+            _ => return Ok(0), // Not a spatial source, skip
         };
 
         // Check if effects exist for this source
@@ -254,63 +641,140 @@ impl SpatialProcessor {
             self.create_effects_for_source(source_id)?;
         }
 
+        let bypass = self.bypass.union(instance.spatial_bypass);
+
         // Fill input buffer with audio samples
-        self.fill_input_buffer(instance, volume);
+        let (frames_read, active) = self.fill_input_buffer(instance);
+        if let Some(stems) = stems {
+            stems.record_mono(source_id, &self.cached_input_buf);
+        }
+        if !active {
+            // Gated live source: pass the comfort noise through without spatializing it
+            for (dry, input) in self.cached_dry_buf.iter_mut().zip(&self.cached_input_buf) {
+                *dry += input;
+            }
+            return Ok(frames_read);
+        }
+
+        let lod_block = self.next_lod_block(source_id, position)?;
 
         // Apply direct effect (distance attenuation + air absorption)
-        self.apply_direct_effect(source_id)?;
+        self.apply_direct_effect(source_id, position, bypass, lod_block.refresh)?;
 
-        // Apply ambisonics encode effect
-        self.apply_ambisonics_encode_effect(source_id, position)?;
+        if bypass.spatialization {
+            for (dry, direct) in self.cached_dry_buf.iter_mut().zip(&self.cached_direct_buf) {
+                *dry += direct;
+            }
+        } else {
+            // Encode the source, on its own or as part of its cluster
+            self.route_direct_output(source_id, position, lod_block)?;
+        }
 
-        Ok(())
+        Ok(frames_read)
     }
 
-    /// Fill input buffer from playback instance
-    fn fill_input_buffer(&mut self, instance: &mut PlaybackInstance, volume: f32) {
-        self.cached_input_buf.fill(0.0);
+    /// Pick the level of detail of a source for this block from its distance and its
+    /// simulated gain in the previous block
+    fn next_lod_block(&mut self, source_id: SourceId, source_position: Vec3) -> Result<LodBlock> {
+        let distance = (source_position - self.listener_position).length();
+        let lod = self.lod;
+
+        let effects = self
+            .effects_manager
+            .get_effects_mut(source_id)
+            .ok_or_else(|| {
+                PetalSonicError::SpatialAudio(format!("No effects found for source {}", source_id))
+            })?;
 
-        let samples = instance.audio_data.samples();
-        let current_frame = instance.info.current_frame;
+        let gain = effects.direct_outputs.map_or(1.0, |outputs| {
+            outputs.distance_attenuation * outputs.occlusion
+        });
+        let lod_block = effects.lod.next_block(lod.as_ref(), distance, gain);
+        if effects.lod.is_reduced() {
+            self.reduced_sources += 1;
+        }
+        Ok(lod_block)
+    }
 
-        // Read samples for this block
-        for i in 0..self.frame_size {
-            let sample_idx = current_frame + i;
-            if sample_idx < samples.len() {
-                self.cached_input_buf[i] = samples[sample_idx] * volume;
-            }
+    /// Fill input buffer from playback instance
+    ///
+    /// Returns the number of leading frames carrying signal (less than a block at the end
+    /// of a clip), and false if the instance is a live source gated by voice activity
+    /// detection
+    fn fill_input_buffer(&mut self, instance: &mut PlaybackInstance) -> (usize, bool) {
+        // Volume is applied after the per-source processing, as for the other backends
+        if let Some(active) = instance.read_live(&mut self.cached_input_buf) {
+            instance.process_block(&mut self.cached_input_buf);
+            instance.apply_volume(&mut self.cached_input_buf);
+            return (self.frame_size, active);
         }
 
+        self.cached_input_buf.fill(0.0);
+
+        // Read samples for this block (in the instance's playback direction)
+        let clip_frames = instance.read_clip(&mut self.cached_input_buf);
+        let frames_read = instance.apply_delays(&mut self.cached_input_buf, clip_frames);
+        instance.process_block(&mut self.cached_input_buf);
+        instance.apply_volume(&mut self.cached_input_buf[..frames_read]);
+
         // Advance cursor and check for completion (single source of truth!)
         // This ensures both spatial and non-spatial paths use identical completion logic
         instance.advance_and_check_completion(self.frame_size);
+        (frames_read, true)
     }
 
-    /// Apply direct effect to the input buffer
-    fn apply_direct_effect(&mut self, source_id: SourceId) -> Result<()> {
+    /// Apply direct effect to the input buffer, skipping bypassed stages
+    ///
+    /// Without `refresh`, the simulation outputs of the last refresh are kept as target.
+    fn apply_direct_effect(
+        &mut self,
+        source_id: SourceId,
+        source_position: Vec3,
+        bypass: SpatialBypass,
+        refresh: bool,
+    ) -> Result<()> {
+        // Get simulation results
+        let held_outputs = self
+            .effects_manager
+            .get_effects(source_id)
+            .and_then(|effects| effects.held_outputs);
+        let target_outputs = match held_outputs {
+            Some(held) if !refresh => held,
+            _ => self.direct_outputs(source_id, source_position),
+        };
+        let smoothing_coefficient = self.smoothing_coefficient;
+
         let effects = self
             .effects_manager
             .get_effects_mut(source_id)
             .ok_or_else(|| {
                 PetalSonicError::SpatialAudio(format!("No effects found for source {}", source_id))
             })?;
+        effects.held_outputs = Some(target_outputs);
 
-        // Get simulation results
-        let outputs = effects.source.get_outputs(SimulationFlags::DIRECT);
-        let direct_outputs = outputs.direct();
+        // Smooth towards the latest simulation outputs to avoid steps between updates
+        let direct_outputs = match effects.direct_outputs {
+            Some(previous) => previous.smoothed_towards(target_outputs, smoothing_coefficient),
+            None => target_outputs,
+        };
+        effects.direct_outputs = Some(direct_outputs);
 
-        let distance_attenuation = direct_outputs.distance_attenuation.unwrap_or(1.0);
-        let air_absorption = direct_outputs
-            .air_absorption
-            .as_ref()
-            .map(|eq| Equalizer([eq[0], eq[1], eq[2]]))
-            .unwrap_or(Equalizer([1.0, 1.0, 1.0]));
+        let distance_attenuation = if bypass.distance_attenuation {
+            1.0
+        } else {
+            direct_outputs.distance_attenuation
+        };
+        let air_absorption = if bypass.air_absorption {
+            Equalizer([1.0, 1.0, 1.0])
+        } else {
+            Equalizer(direct_outputs.air_absorption)
+        };
 
         let direct_effect_params = DirectEffectParams {
             distance_attenuation: Some(distance_attenuation),
             air_absorption: Some(air_absorption),
             directivity: None,
-            occlusion: None,
+            occlusion: (direct_outputs.occlusion < 1.0).then_some(direct_outputs.occlusion),
             transmission: None,
         };
 
@@ -343,14 +807,147 @@ impl SpatialProcessor {
         Ok(())
     }
 
-    /// Apply ambisonics encode effect
+    /// Create the effects of cluster slots added by the last plan
+    fn allocate_cluster_effects(&mut self) -> Result<()> {
+        let audio_settings = AudioSettings {
+            sampling_rate: self.sample_rate,
+            frame_size: self.frame_size as u32,
+        };
+        while self.cluster_effects.len() < self.clusters.slot_count() {
+            self.cluster_effects
+                .push(ClusterEffects::new(&self.context, &audio_settings)?);
+        }
+        Ok(())
+    }
+
+    /// Encode the direct output of a source with its own effect or mix it into its
+    /// cluster, crossfading between the two targets over the block when it changed
+    fn route_direct_output(
+        &mut self,
+        source_id: SourceId,
+        source_position: Vec3,
+        lod_block: LodBlock,
+    ) -> Result<()> {
+        let (previous, current) = self.clusters.targets(source_id);
+        if previous == current {
+            return self.send_direct_output(source_id, source_position, lod_block, current);
+        }
+
+        let last_frame = self.frame_size.saturating_sub(1).max(1) as f32;
+        self.cached_route_buf
+            .copy_from_slice(&self.cached_direct_buf);
+        for (i, sample) in self.cached_direct_buf.iter_mut().enumerate() {
+            *sample *= 1.0 - i as f32 / last_frame;
+        }
+        self.send_direct_output(source_id, source_position, lod_block, previous)?;
+
+        for (i, (sample, routed)) in self
+            .cached_direct_buf
+            .iter_mut()
+            .zip(&self.cached_route_buf)
+            .enumerate()
+        {
+            *sample = routed * (i as f32 / last_frame);
+        }
+        self.send_direct_output(source_id, source_position, lod_block, current)
+    }
+
+    /// Encode the direct output buffer to `target`
+    fn send_direct_output(
+        &mut self,
+        source_id: SourceId,
+        source_position: Vec3,
+        lod_block: LodBlock,
+        target: EncodeTarget,
+    ) -> Result<()> {
+        match target {
+            EncodeTarget::Own => {
+                self.apply_ambisonics_encode_effect(source_id, source_position, lod_block)
+            }
+            EncodeTarget::Cluster(slot) => {
+                let cluster = &mut self.cluster_effects[slot];
+                for (mix, direct) in cluster.mix.iter_mut().zip(&self.cached_direct_buf) {
+                    *mix += direct;
+                }
+                cluster.fed = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Encode the mix of each cluster fed in this block at the cluster's direction
+    fn encode_clusters(&mut self) -> Result<()> {
+        for slot in 0..self.cluster_effects.len() {
+            if !self.cluster_effects[slot].fed {
+                continue;
+            }
+            let direction = self.clusters.direction(slot);
+            let cluster = &mut self.cluster_effects[slot];
+
+            let ambisonics_encode_effect_params = AmbisonicsEncodeEffectParams {
+                direction: Direction::new(direction.x, direction.y, direction.z),
+                order: FULL_ORDER,
+            };
+
+            let input_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
+                &cluster.mix,
+                AudioBufferSettings {
+                    num_channels: Some(1),
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to create cluster buffer: {}", e))
+            })?;
+
+            let output_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
+                &mut self.cached_ambisonics_encode_buf,
+                AudioBufferSettings {
+                    num_channels: Some(9),
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to create output buffer: {}", e))
+            })?;
+
+            cluster.ambisonics_encode_effect.apply(
+                &ambisonics_encode_effect_params,
+                &input_buf,
+                &output_buf,
+            );
+            cluster.mix.fill(0.0);
+            cluster.fed = false;
+
+            for (summed, encoded) in self
+                .cached_summed_encoded_buf
+                .iter_mut()
+                .zip(&self.cached_ambisonics_encode_buf)
+            {
+                *summed += encoded;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply ambisonics encode effect at the order of `lod_block`
+    ///
+    /// Without a refresh, the direction of the last refresh is kept.
     fn apply_ambisonics_encode_effect(
         &mut self,
         source_id: SourceId,
         source_position: Vec3,
+        lod_block: LodBlock,
     ) -> Result<()> {
         // Calculate direction first to avoid borrow checker issues
-        let direction = self.get_target_direction(source_position);
+        let held_direction = self
+            .effects_manager
+            .get_effects(source_id)
+            .and_then(|effects| effects.held_direction);
+        let direction = match held_direction {
+            Some(held) if !lod_block.refresh => held,
+            _ => self.get_source_direction(source_position),
+        };
 
         let effects = self
             .effects_manager
@@ -358,12 +955,17 @@ impl SpatialProcessor {
             .ok_or_else(|| {
                 PetalSonicError::SpatialAudio(format!("No effects found for source {}", source_id))
             })?;
+        effects.held_direction = Some(direction);
 
         let ambisonics_encode_effect_params = AmbisonicsEncodeEffectParams {
             direction: Direction::new(direction.x, direction.y, direction.z),
-            order: 2,
+            order: lod_block.order,
         };
 
+        // Lower orders only fill the leading channels of the planar buffer
+        let num_channels = (lod_block.order + 1) * (lod_block.order + 1);
+        let encoded_len = num_channels as usize * self.frame_size;
+
         let input_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
             &self.cached_direct_buf,
             AudioBufferSettings {
@@ -376,9 +978,9 @@ impl SpatialProcessor {
         })?;
 
         let output_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
-            &mut self.cached_ambisonics_encode_buf,
+            &mut self.cached_ambisonics_encode_buf[..encoded_len],
             AudioBufferSettings {
-                num_channels: Some(9), // Order 2 = 9 channels
+                num_channels: Some(num_channels),
                 ..Default::default()
             },
         )
@@ -392,28 +994,88 @@ impl SpatialProcessor {
             &output_buf,
         );
 
+        if lod_block.order == FULL_ORDER && lod_block.higher_order_gain != (1.0, 1.0) {
+            lod::fade_higher_orders(
+                &mut self.cached_ambisonics_encode_buf,
+                self.frame_size,
+                lod_block.higher_order_gain,
+            );
+        }
+
         // Accumulate encoded output to summed buffer
-        for i in 0..self.cached_ambisonics_encode_buf.len() {
+        for i in 0..encoded_len {
             self.cached_summed_encoded_buf[i] += self.cached_ambisonics_encode_buf[i];
         }
 
         Ok(())
     }
 
-    /// Apply ambisonics decode effect to convert accumulated ambisonics to binaural stereo
+    /// Apply ambisonics rotation effect to turn the accumulated world-space ambisonics into
+    /// the listener's frame
+    ///
+    /// The field rotation follows the listener rotation once per block, limited to
+    /// [`MAX_FIELD_ROTATION_SPEED`], so head tracking only costs one rotation of the summed
+    /// field per block instead of re-encoding every source.
+    fn apply_ambisonics_rotation_effect(&mut self) -> Result<()> {
+        let max_angle = MAX_FIELD_ROTATION_SPEED * self.frame_size as f32 / self.sample_rate as f32;
+        let target = self.listener_rotation;
+        let field_rotation = match self.field_rotation {
+            Some(previous) if previous.angle_between(target) > max_angle => {
+                previous.slerp(target, max_angle / previous.angle_between(target))
+            }
+            _ => target,
+        };
+        self.field_rotation = Some(field_rotation);
+
+        let ambisonics_rotation_effect_params = AmbisonicsRotationEffectParams {
+            orientation: Self::orientation(field_rotation),
+            order: 2,
+        };
+
+        let input_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
+            &self.cached_summed_encoded_buf,
+            AudioBufferSettings {
+                num_channels: Some(9),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| {
+            PetalSonicError::SpatialAudio(format!("Failed to create input buffer: {}", e))
+        })?;
+
+        let output_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
+            &mut self.cached_rotated_buf,
+            AudioBufferSettings {
+                num_channels: Some(9),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| {
+            PetalSonicError::SpatialAudio(format!("Failed to create output buffer: {}", e))
+        })?;
+
+        self.ambisonics_rotation_effect.apply(
+            &ambisonics_rotation_effect_params,
+            &input_buf,
+            &output_buf,
+        );
+
+        Ok(())
+    }
+
+    /// Apply ambisonics decode effect to convert the rotated ambisonics to stereo (binaural
+    /// or panned, depending on the output mode)
     fn apply_ambisonics_decode_effect(&mut self) -> Result<()> {
+        // The field is already in the listener's frame
         let ambisonics_decode_effect_params = AmbisonicsDecodeEffectParams {
             order: 2,
             hrtf: &self.hrtf,
-            orientation: CoordinateSystem {
-                ahead: Vector3::new(0.0, 0.0, -1.0),
-                ..Default::default()
-            },
-            binaural: true,
+            orientation: Self::orientation(Quat::IDENTITY),
+            binaural: self.output_mode == OutputMode::Headphones,
         };
 
         let input_buf = AudioNimbusAudioBuffer::try_with_data_and_settings(
-            &self.cached_summed_encoded_buf,
+            &self.cached_rotated_buf,
             AudioBufferSettings {
                 num_channels: Some(9),
                 ..Default::default()
@@ -457,85 +1119,59 @@ impl SpatialProcessor {
         Ok(())
     }
 
-    /// Calculate direction from listener to source in listener's coordinate system
-    fn get_target_direction(&self, source_position: Vec3) -> Vec3 {
-        let target_direction = (source_position - self.listener_position).normalize();
-        Vec3::new(
-            target_direction.dot(self.listener_right),
-            target_direction.dot(self.listener_up),
-            target_direction.dot(self.listener_front),
-        )
+    /// Calculate direction from listener to source in world space
+    ///
+    /// Sources are encoded in world space and the listener orientation is applied once, by
+    /// the rotation stage (see [`Self::apply_ambisonics_rotation_effect`]).
+    fn get_source_direction(&self, source_position: Vec3) -> Vec3 {
+        (source_position - self.listener_position).normalize()
     }
 
-    /// Run Steam Audio simulation for all sources
-    fn simulate(&mut self, instances: &[(SourceId, &mut PlaybackInstance)]) -> Result<()> {
-        // Set simulation inputs for each source
-        for (source_id, instance) in instances.iter() {
-            let position = match &instance.config {
-                SourceConfig::Spatial { position, .. } => *position,
-                _ => continue,
-            };
-
-            let scaled_position = position * self.distance_scaler;
-            let simulation_inputs = SimulationInputs {
-                source: geometry::CoordinateSystem {
-                    origin: Point::new(scaled_position.x, scaled_position.y, scaled_position.z),
-                    ..Default::default()
-                },
-                direct_simulation: Some(DirectSimulationParameters {
-                    distance_attenuation: Some(DistanceAttenuationModel::Default),
-                    air_absorption: Some(AirAbsorptionModel::Default),
-                    directivity: None,
-                    occlusion: None,
-                }),
-                reflections_simulation: None,
-                pathing_simulation: None,
-            };
+    /// Coordinate system of a rotation in world space, in Steam Audio's convention
+    /// (right-handed, y up, -z ahead for the identity rotation)
+    fn orientation(rotation: Quat) -> CoordinateSystem {
+        let to_vector = |v: Vec3| Vector3::new(v.x, v.y, v.z);
+        CoordinateSystem {
+            right: to_vector(rotation * Vec3::X),
+            up: to_vector(rotation * Vec3::Y),
+            ahead: to_vector(rotation * -Vec3::Z),
+            ..Default::default()
+        }
+    }
 
-            // Get the source and set inputs - need mutable access
-            if let Some(effects) = self.effects_manager.get_effects_mut(*source_id) {
-                effects
-                    .source
-                    .set_inputs(SimulationFlags::DIRECT, simulation_inputs);
+    /// Publish listener and source positions to the simulation thread
+    fn update_simulation_inputs(&self, instances: &[(SourceId, &mut PlaybackInstance)]) {
+        for (source_id, instance) in instances.iter() {
+            if let SourceConfig::Spatial { position, .. } = &instance.config {
+                self.simulation
+                    .set_source_position(*source_id, *position * self.distance_scaler);
             }
         }
 
-        self.simulator.commit();
-
-        // Set shared listener inputs
-        let scaled_listener_position = self.listener_position * self.distance_scaler;
-        let simulation_shared_inputs = SimulationSharedInputs {
-            listener: geometry::CoordinateSystem {
-                origin: Point::new(
-                    scaled_listener_position.x,
-                    scaled_listener_position.y,
-                    scaled_listener_position.z,
-                ),
-                right: Vector3::new(
-                    self.listener_right.x,
-                    self.listener_right.y,
-                    self.listener_right.z,
-                ),
-                up: Vector3::new(self.listener_up.x, self.listener_up.y, self.listener_up.z),
-                ahead: Vector3::new(
-                    self.listener_front.x,
-                    self.listener_front.y,
-                    self.listener_front.z,
-                ),
-            },
-            num_rays: 1024,
-            num_bounces: 10,
-            duration: 3.0,
-            order: 2,
-            irradiance_min_distance: 1.0,
-            pathing_visualization_callback: None,
-        };
-
-        self.simulator
-            .set_shared_inputs(SimulationFlags::DIRECT, &simulation_shared_inputs);
-        self.simulator.run_direct();
+        self.simulation.set_listener(
+            self.listener_position * self.distance_scaler,
+            self.listener_right,
+            self.listener_up,
+            self.listener_front,
+        );
+    }
 
-        Ok(())
+    /// Direct simulation outputs for a source
+    ///
+    /// Until the simulation thread has produced results for a new source, distance
+    /// attenuation is estimated with Steam Audio's default inverse-distance model so the
+    /// source does not start at full volume.
+    fn direct_outputs(&self, source_id: SourceId, source_position: Vec3) -> DirectOutputs {
+        self.simulation
+            .direct_outputs(source_id)
+            .unwrap_or_else(|| {
+                let distance = (source_position - self.listener_position).length();
+                DirectOutputs {
+                    distance_attenuation: distance_attenuation(distance),
+                    air_absorption: [1.0, 1.0, 1.0],
+                    occlusion: 1.0,
+                }
+            })
     }
 
     /// Get the frame size
@@ -0,0 +1,454 @@
+// Simulation thread
+//
+// Steam Audio simulation runs on a dedicated thread at a fixed, low rate instead of once per
+// render block. The render thread publishes the listener and source positions, and reads the
+// latest simulation outputs, through per-source atomic slots, so neither side ever blocks.
+// The slots are allocated up front, one per spatial source the processor may render, and the
+// simulation thread picks up sources claiming or releasing a slot on its next update.
+
+use crate::config::SimulationQuality;
+use crate::error::{PetalSonicError, Result};
+use crate::logging::rt_warn;
+use crate::math::Vec3;
+use crate::world::SourceId;
+use audionimbus::{
+    AirAbsorptionModel, Context, Direct, DirectSimulationParameters, DistanceAttenuationModel,
+    Point, Scene, SimulationFlags, SimulationInputs, SimulationSharedInputs, Simulator, Source,
+    SourceSettings, Vector3, geometry,
+};
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// `f32` stored in an `AtomicU32`
+#[derive(Default)]
+struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// `Vec3` stored as three atomic floats
+///
+/// Components are updated independently; a reader may see a mix of two consecutive
+/// writes, which is harmless for positions updated every block.
+#[derive(Default)]
+struct AtomicVec3([AtomicF32; 3]);
+
+impl AtomicVec3 {
+    fn new(value: Vec3) -> Self {
+        Self([
+            AtomicF32::new(value.x),
+            AtomicF32::new(value.y),
+            AtomicF32::new(value.z),
+        ])
+    }
+
+    fn load(&self) -> Vec3 {
+        Vec3::new(self.0[0].load(), self.0[1].load(), self.0[2].load())
+    }
+
+    fn store(&self, value: Vec3) {
+        self.0[0].store(value.x);
+        self.0[1].store(value.y);
+        self.0[2].store(value.z);
+    }
+}
+
+/// Listener coordinate system in simulation space (positions already scaled to meters)
+struct ListenerSlot {
+    position: AtomicVec3,
+    right: AtomicVec3,
+    up: AtomicVec3,
+    ahead: AtomicVec3,
+}
+
+/// Pending commands to the simulation thread; further commands are dropped until it catches up
+const COMMAND_CAPACITY: usize = 16;
+
+/// Simulation inputs and outputs of a single source
+#[derive(Default)]
+struct SourceSlot {
+    /// Set by the render thread while a source holds the slot
+    active: AtomicBool,
+    /// Incremented by the render thread each time the slot is claimed, so the simulation
+    /// thread notices a slot that was released and claimed again between two updates
+    generation: AtomicU32,
+    /// Source position in simulation space, written by the render thread
+    position: AtomicVec3,
+    /// Latest direct simulation outputs, written by the simulation thread
+    distance_attenuation: AtomicF32,
+    air_absorption: [AtomicF32; 3],
+    occlusion: AtomicF32,
+    /// Set once the simulation thread has produced outputs for this source
+    simulated: AtomicBool,
+}
+
+/// Direct simulation results for a source
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DirectOutputs {
+    pub distance_attenuation: f32,
+    pub air_absorption: [f32; 3],
+    pub occlusion: f32,
+}
+
+impl DirectOutputs {
+    /// Move each value towards `target` by `coefficient` (0 = keep, 1 = jump to target)
+    pub fn smoothed_towards(self, target: Self, coefficient: f32) -> Self {
+        let smooth = |current: f32, target: f32| current + (target - current) * coefficient;
+        Self {
+            distance_attenuation: smooth(self.distance_attenuation, target.distance_attenuation),
+            air_absorption: [
+                smooth(self.air_absorption[0], target.air_absorption[0]),
+                smooth(self.air_absorption[1], target.air_absorption[1]),
+                smooth(self.air_absorption[2], target.air_absorption[2]),
+            ],
+            occlusion: smooth(self.occlusion, target.occlusion),
+        }
+    }
+}
+
+enum SimulationCommand {
+    SetQuality(SimulationQuality),
+}
+
+/// Handle to the simulation thread, owned by the spatial processor on the render side
+pub(crate) struct SimulationThread {
+    command_sender: Sender<SimulationCommand>,
+    listener: Arc<ListenerSlot>,
+    slots: Arc<[SourceSlot]>,
+    /// Slot index of each simulated source
+    sources: HashMap<SourceId, usize>,
+    free_slots: Vec<usize>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl SimulationThread {
+    /// Spawn the simulation thread, moving the simulator and scene into it
+    ///
+    /// # Arguments
+    /// * `rate_hz` - Number of simulation updates per second
+    /// * `max_sources` - Number of sources that can be simulated at the same time
+    pub fn spawn(
+        context: Context,
+        simulator: Simulator<Direct>,
+        scene: Scene,
+        quality: SimulationQuality,
+        rate_hz: u32,
+        max_sources: usize,
+    ) -> Result<Self> {
+        let (command_sender, command_receiver) = crossbeam_channel::bounded(COMMAND_CAPACITY);
+        let listener = Arc::new(ListenerSlot {
+            position: AtomicVec3::new(Vec3::ZERO),
+            right: AtomicVec3::new(Vec3::new(1.0, 0.0, 0.0)),
+            up: AtomicVec3::new(Vec3::new(0.0, 1.0, 0.0)),
+            ahead: AtomicVec3::new(Vec3::new(0.0, 0.0, -1.0)),
+        });
+        let slots: Arc<[SourceSlot]> = (0..max_sources).map(|_| SourceSlot::default()).collect();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let worker = SimulationWorker {
+            _context: context,
+            simulator,
+            _scene: scene,
+            quality,
+            period: Duration::from_secs_f64(1.0 / rate_hz.max(1) as f64),
+            listener: listener.clone(),
+            slots: slots.clone(),
+            sources: (0..max_sources).map(|_| None).collect(),
+            command_receiver,
+            shutdown: shutdown.clone(),
+        };
+
+        let handle = thread::Builder::new()
+            .name("petalsonic-simulation".to_string())
+            .spawn(move || worker.run())
+            .map_err(|e| {
+                PetalSonicError::SpatialAudio(format!("Failed to spawn simulation thread: {}", e))
+            })?;
+
+        log::info!("Spawned simulation thread ({} Hz)", rate_hz);
+
+        Ok(Self {
+            command_sender,
+            listener,
+            slots,
+            sources: HashMap::with_capacity(max_sources),
+            free_slots: (0..max_sources).rev().collect(),
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// Publish the listener coordinate system (position in simulation space)
+    pub fn set_listener(&self, position: Vec3, right: Vec3, up: Vec3, ahead: Vec3) {
+        self.listener.position.store(position);
+        self.listener.right.store(right);
+        self.listener.up.store(up);
+        self.listener.ahead.store(ahead);
+    }
+
+    /// Start simulating a source
+    ///
+    /// Without a free slot the source is not simulated, and its direct outputs are
+    /// estimated by the caller.
+    pub fn add_source(&mut self, source_id: SourceId) {
+        if self.sources.contains_key(&source_id) {
+            return;
+        }
+        let Some(index) = self.free_slots.pop() else {
+            rt_warn!("Simulation: no free slot for source {}", source_id);
+            return;
+        };
+
+        let slot = &self.slots[index];
+        slot.simulated.store(false, Ordering::Relaxed);
+        slot.generation.fetch_add(1, Ordering::Relaxed);
+        slot.active.store(true, Ordering::Release);
+        self.sources.insert(source_id, index);
+    }
+
+    /// Stop simulating a source
+    pub fn remove_source(&mut self, source_id: SourceId) {
+        if let Some(index) = self.sources.remove(&source_id) {
+            self.slots[index].active.store(false, Ordering::Release);
+            self.free_slots.push(index);
+        }
+    }
+
+    /// Publish a source position (in simulation space)
+    pub fn set_source_position(&self, source_id: SourceId, position: Vec3) {
+        if let Some(&index) = self.sources.get(&source_id) {
+            self.slots[index].position.store(position);
+        }
+    }
+
+    /// Latest direct simulation outputs, or `None` if the source was not simulated yet
+    pub fn direct_outputs(&self, source_id: SourceId) -> Option<DirectOutputs> {
+        let slot = &self.slots[*self.sources.get(&source_id)?];
+        if !slot.simulated.load(Ordering::Acquire) {
+            return None;
+        }
+
+        Some(DirectOutputs {
+            distance_attenuation: slot.distance_attenuation.load(),
+            air_absorption: [
+                slot.air_absorption[0].load(),
+                slot.air_absorption[1].load(),
+                slot.air_absorption[2].load(),
+            ],
+            occlusion: slot.occlusion.load(),
+        })
+    }
+
+    /// Change the simulation parameters used from the next update
+    pub fn set_quality(&self, quality: SimulationQuality) {
+        self.send(SimulationCommand::SetQuality(quality));
+    }
+
+    fn send(&self, command: SimulationCommand) {
+        match self.command_sender.try_send(command) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                log::error!("Simulation thread is not keeping up, dropping command");
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                log::error!("Simulation thread is not running");
+            }
+        }
+    }
+}
+
+impl Drop for SimulationThread {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take()
+            && let Err(e) = handle.join()
+        {
+            log::error!("Error joining simulation thread: {:?}", e);
+        }
+    }
+}
+
+/// State owned by the simulation thread
+struct SimulationWorker {
+    // Context and scene must be kept alive for the simulator lifetime
+    _context: Context,
+    simulator: Simulator<Direct>,
+    _scene: Scene,
+    quality: SimulationQuality,
+    period: Duration,
+    listener: Arc<ListenerSlot>,
+    slots: Arc<[SourceSlot]>,
+    /// Steam Audio source and slot generation of each claimed slot, by slot index
+    sources: Vec<Option<(Source, u32)>>,
+    command_receiver: Receiver<SimulationCommand>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl SimulationWorker {
+    fn run(mut self) {
+        log::info!("Simulation thread started");
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let start = Instant::now();
+
+            self.process_commands();
+            self.sync_sources();
+            if self.sources.iter().any(Option::is_some) {
+                self.simulate();
+            }
+
+            if let Some(remaining) = self.period.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+
+        log::info!("Simulation thread stopped");
+    }
+
+    fn process_commands(&mut self) {
+        while let Ok(command) = self.command_receiver.try_recv() {
+            match command {
+                SimulationCommand::SetQuality(quality) => {
+                    self.quality = quality;
+                }
+            }
+        }
+    }
+
+    /// Create and remove Steam Audio sources for the slots claimed and released since the
+    /// last update
+    fn sync_sources(&mut self) {
+        for (index, (entry, slot)) in self.sources.iter_mut().zip(self.slots.iter()).enumerate() {
+            let generation = slot
+                .active
+                .load(Ordering::Acquire)
+                .then(|| slot.generation.load(Ordering::Relaxed));
+            if entry.as_ref().map(|(_, current)| *current) == generation {
+                continue;
+            }
+
+            if let Some((source, _)) = entry.take() {
+                self.simulator.remove_source(&source);
+                log::debug!("Simulation: released slot {}", index);
+            }
+            let Some(generation) = generation else {
+                continue;
+            };
+            match Source::try_new(
+                &self.simulator,
+                &SourceSettings {
+                    flags: SimulationFlags::DIRECT,
+                },
+            ) {
+                Ok(source) => {
+                    self.simulator.add_source(&source);
+                    *entry = Some((source, generation));
+                    log::debug!("Simulation: claimed slot {}", index);
+                }
+                Err(e) => {
+                    log::error!(
+                        "Simulation: failed to create source for slot {}: {}",
+                        index,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Run the direct simulation for all sources and publish the outputs
+    fn simulate(&mut self) {
+        for (entry, slot) in self.sources.iter_mut().zip(self.slots.iter()) {
+            let Some((source, _)) = entry else {
+                continue;
+            };
+            let position = slot.position.load();
+            let simulation_inputs = SimulationInputs {
+                source: geometry::CoordinateSystem {
+                    origin: Point::new(position.x, position.y, position.z),
+                    ..Default::default()
+                },
+                direct_simulation: Some(DirectSimulationParameters {
+                    distance_attenuation: Some(DistanceAttenuationModel::Default),
+                    air_absorption: Some(AirAbsorptionModel::Default),
+                    directivity: None,
+                    occlusion: None,
+                }),
+                reflections_simulation: None,
+                pathing_simulation: None,
+            };
+            source.set_inputs(SimulationFlags::DIRECT, simulation_inputs);
+        }
+
+        self.simulator.commit();
+
+        let to_vector = |v: Vec3| Vector3::new(v.x, v.y, v.z);
+        let listener_position = self.listener.position.load();
+        let simulation_shared_inputs = SimulationSharedInputs {
+            listener: geometry::CoordinateSystem {
+                origin: Point::new(
+                    listener_position.x,
+                    listener_position.y,
+                    listener_position.z,
+                ),
+                right: to_vector(self.listener.right.load()),
+                up: to_vector(self.listener.up.load()),
+                ahead: to_vector(self.listener.ahead.load()),
+            },
+            num_rays: self.quality.num_rays,
+            num_bounces: self.quality.num_bounces,
+            duration: self.quality.duration,
+            order: 2,
+            irradiance_min_distance: self.quality.irradiance_min_distance,
+            pathing_visualization_callback: None,
+        };
+
+        self.simulator
+            .set_shared_inputs(SimulationFlags::DIRECT, &simulation_shared_inputs);
+        self.simulator.run_direct();
+
+        for (entry, slot) in self.sources.iter().zip(self.slots.iter()) {
+            // Skip slots released or claimed again while this update ran
+            let Some((source, generation)) = entry else {
+                continue;
+            };
+            if !slot.active.load(Ordering::Acquire)
+                || slot.generation.load(Ordering::Relaxed) != *generation
+            {
+                continue;
+            }
+            let outputs = source.get_outputs(SimulationFlags::DIRECT);
+            let direct_outputs = outputs.direct();
+
+            slot.distance_attenuation
+                .store(direct_outputs.distance_attenuation.unwrap_or(1.0));
+            let air_absorption = direct_outputs
+                .air_absorption
+                .as_ref()
+                .map(|eq| [eq[0], eq[1], eq[2]])
+                .unwrap_or([1.0, 1.0, 1.0]);
+            for (band, value) in slot.air_absorption.iter().zip(air_absorption) {
+                band.store(value);
+            }
+            slot.occlusion
+                .store(direct_outputs.occlusion.unwrap_or(1.0));
+            slot.simulated.store(true, Ordering::Release);
+        }
+    }
+}
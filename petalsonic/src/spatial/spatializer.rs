@@ -0,0 +1,107 @@
+// Spatializer backend trait
+//
+// The mixer hands all spatial sources of a block to one `Spatializer`. PetalSonic ships two
+// backends: the Steam Audio `SpatialProcessor` (the default) and the `StereoPanner` (used
+// when Steam Audio is disabled or unavailable). Other backends, e.g. convolution with a
+// research HRTF set or VBAP for speaker arrays, can be installed with
+// `PetalSonicEngine::set_spatializer`.
+
+use crate::error::Result;
+use crate::math::Pose;
+use crate::playback::PlaybackInstance;
+use crate::spatial::{SpatialProcessor, StereoPanner};
+use crate::stems::StemRecorder;
+use crate::world::SourceId;
+
+/// A backend that renders spatial sources into the output mix
+///
+/// Methods are called on the render thread, once per block of
+/// [`PetalSonicWorldDesc::block_size`](crate::PetalSonicWorldDesc::block_size) frames, so
+/// implementations must not block or allocate in the steady state.
+///
+/// A backend reads each source's mono signal with
+/// [`PlaybackInstance::fill_buffer`] (passing 1 channel), which also advances its playback
+/// cursor and applies the source volume (smoothed across the block), and takes its position
+/// from [`PlaybackInstance::config`]. Every
+/// instance passed to [`Spatializer::process`] must be read exactly once per block.
+///
+/// Poses and positions are in PetalSonic's native convention (right-handed, Y-up, -Z
+/// forward), whatever the world's
+/// [`coordinate_convention`](crate::PetalSonicWorldDesc::coordinate_convention).
+pub trait Spatializer: Send {
+    /// Update the listener pose; called before every block
+    fn set_listener_pose(&mut self, pose: Pose) -> Result<()>;
+
+    /// Spatialize one block of spatial sources and mix the result into `output_buffer`
+    ///
+    /// # Arguments
+    /// * `instances` - Playing spatial sources
+    /// * `output_buffer` - Interleaved output buffer to mix into (add, do not overwrite)
+    /// * `channels` - Number of channels in `output_buffer`
+    /// * `stems` - Stem recorder, set while stems are recorded; pass each source's mono
+    ///   input to [`StemRecorder::record_mono`]
+    ///
+    /// # Returns
+    /// Number of frames processed
+    fn process(
+        &mut self,
+        instances: &mut [(SourceId, &mut PlaybackInstance)],
+        output_buffer: &mut [f32],
+        channels: u16,
+        stems: Option<&mut StemRecorder>,
+    ) -> Result<usize>;
+
+    /// Free the per-source state of a source that left the spatial path, because it
+    /// switched to a non-spatial configuration or finished playing
+    ///
+    /// State for a source is expected to be created again the next time it is processed.
+    /// The default does nothing.
+    fn release_source(&mut self, _source_id: SourceId) {}
+}
+
+impl Spatializer for SpatialProcessor {
+    fn set_listener_pose(&mut self, pose: Pose) -> Result<()> {
+        SpatialProcessor::set_listener_pose(self, pose)
+    }
+
+    fn process(
+        &mut self,
+        instances: &mut [(SourceId, &mut PlaybackInstance)],
+        output_buffer: &mut [f32],
+        channels: u16,
+        stems: Option<&mut StemRecorder>,
+    ) -> Result<usize> {
+        self.process_spatial_sources(instances, output_buffer, channels, stems)
+    }
+
+    fn release_source(&mut self, source_id: SourceId) {
+        self.remove_effects_for_source(source_id);
+    }
+}
+
+impl Spatializer for StereoPanner {
+    fn set_listener_pose(&mut self, pose: Pose) -> Result<()> {
+        StereoPanner::set_listener_pose(self, pose);
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        instances: &mut [(SourceId, &mut PlaybackInstance)],
+        output_buffer: &mut [f32],
+        channels: u16,
+        stems: Option<&mut StemRecorder>,
+    ) -> Result<usize> {
+        Ok(StereoPanner::process(
+            self,
+            instances,
+            output_buffer,
+            channels,
+            stems,
+        ))
+    }
+
+    fn release_source(&mut self, source_id: SourceId) {
+        StereoPanner::release_source(self, source_id);
+    }
+}
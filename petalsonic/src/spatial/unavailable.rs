@@ -0,0 +1,131 @@
+// Stand-in for the spatial processor when PetalSonic is built without the `steam-audio`
+// feature
+//
+// The processor can never be constructed, so the engine always runs without one and
+// spatial sources are panned in stereo by the `StereoPanner` instead.
+
+use crate::config::{
+    HrtfConfig, ListenerCalibration, OutputMode, SimulationQuality, SourceClustering, SpatialLod,
+};
+use crate::error::{PetalSonicError, Result};
+use crate::math::Pose;
+use crate::playback::PlaybackInstance;
+use crate::spatial::{SpatialBypass, SpatialSourceStats};
+use crate::stems::StemRecorder;
+use crate::world::SourceId;
+use std::time::Duration;
+
+/// Spatial audio processor; unavailable because PetalSonic was built without the
+/// `steam-audio` feature
+pub enum SpatialProcessor {}
+
+impl SpatialProcessor {
+    /// Always fails: Steam Audio is not compiled in
+    pub fn new(
+        _sample_rate: u32,
+        _frame_size: usize,
+        _distance_scaler: f32,
+        _hrtf: &HrtfConfig,
+        _quality: SimulationQuality,
+        _simulation_rate_hz: u32,
+        _max_sources: usize,
+    ) -> Result<Self> {
+        Err(PetalSonicError::SpatialAudio(
+            "PetalSonic was built without the `steam-audio` feature".to_string(),
+        ))
+    }
+
+    pub fn set_listener_pose(&mut self, _pose: Pose) -> Result<()> {
+        match *self {}
+    }
+
+    pub fn set_quality(&mut self, _quality: SimulationQuality) -> Result<()> {
+        match *self {}
+    }
+
+    pub fn quality(&self) -> SimulationQuality {
+        match *self {}
+    }
+
+    pub fn set_bypass(&mut self, _bypass: SpatialBypass) {
+        match *self {}
+    }
+
+    pub fn bypass(&self) -> SpatialBypass {
+        match *self {}
+    }
+
+    pub fn set_calibration(&mut self, _calibration: ListenerCalibration) {
+        match *self {}
+    }
+
+    pub fn set_output_mode(&mut self, _output_mode: OutputMode) {
+        match *self {}
+    }
+
+    pub fn output_mode(&self) -> OutputMode {
+        match *self {}
+    }
+
+    pub fn set_budget(&mut self, _budget: Option<Duration>) {
+        match *self {}
+    }
+
+    pub fn take_degradation_change(&mut self) -> Option<(usize, usize)> {
+        match *self {}
+    }
+
+    pub fn take_process_time(&mut self) -> Duration {
+        match *self {}
+    }
+
+    pub fn set_lod(&mut self, _lod: Option<SpatialLod>) {
+        match *self {}
+    }
+
+    pub fn lod(&self) -> Option<SpatialLod> {
+        match *self {}
+    }
+
+    pub fn set_clustering(&mut self, _clustering: Option<SourceClustering>) {
+        match *self {}
+    }
+
+    pub fn clustering(&self) -> Option<SourceClustering> {
+        match *self {}
+    }
+
+    pub fn source_stats(&self) -> SpatialSourceStats {
+        match *self {}
+    }
+
+    pub fn source_gains(&self, _source_id: SourceId) -> Option<(f32, f32)> {
+        match *self {}
+    }
+
+    pub fn source_air_absorption(&self, _source_id: SourceId) -> Option<[f32; 3]> {
+        match *self {}
+    }
+
+    pub fn create_effects_for_source(&mut self, _source_id: SourceId) -> Result<()> {
+        match *self {}
+    }
+
+    pub fn remove_effects_for_source(&mut self, _source_id: SourceId) {
+        match *self {}
+    }
+
+    pub fn process_spatial_sources(
+        &mut self,
+        _instances: &mut [(SourceId, &mut PlaybackInstance)],
+        _output_buffer: &mut [f32],
+        _channels: u16,
+        _stems: Option<&mut StemRecorder>,
+    ) -> Result<usize> {
+        match *self {}
+    }
+
+    pub fn frame_size(&self) -> usize {
+        match *self {}
+    }
+}
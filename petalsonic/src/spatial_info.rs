@@ -0,0 +1,37 @@
+//! Propagation results of spatial sources, for gameplay systems.
+//!
+//! The render thread publishes what it computed for each playing spatial source once per
+//! block, so AI hearing, subtitle direction indicators or minimap pings can reuse the audio
+//! engine's propagation model instead of duplicating it:
+//!
+//! ```ignore
+//! if let Some(info) = world.spatial_info(footsteps) {
+//!     let loudness = info.distance_attenuation * info.occlusion;
+//!     subtitles.show_direction(info.azimuth, loudness);
+//! }
+//! ```
+//!
+//! With Steam Audio the gains are the latest (smoothed) simulation outputs; with the stereo
+//! panner they follow the same inverse-distance and air absorption defaults, without
+//! occlusion. Sources that are not playing, non-spatial sources and sources routed to
+//! output channels have no info.
+
+use crate::math::Vec3;
+
+/// Propagation from a spatial source to the listener, as of the last rendered block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialInfo {
+    /// Distance to the listener in world units
+    pub distance: f32,
+    /// Unit direction from the listener to the source, in the world's coordinate convention
+    pub direction: Vec3,
+    /// Horizontal angle of the source around the listener in radians: 0 ahead, positive
+    /// to the right, ±π behind
+    pub azimuth: f32,
+    /// Gain of the distance model, 0 to 1
+    pub distance_attenuation: f32,
+    /// Air absorption gains of the low, mid and high bands, 0 to 1
+    pub air_absorption: [f32; 3],
+    /// Fraction of the source that is not blocked by geometry, 0 (occluded) to 1
+    pub occlusion: f32,
+}
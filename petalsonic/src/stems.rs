@@ -0,0 +1,422 @@
+//! Stem recording: per-source and per-bus WAV files written in parallel with the real-time
+//! mix.
+//!
+//! [`PetalSonicEngine::record_stems`](crate::PetalSonicEngine::record_stems) writes one WAV
+//! file per selected source or bus (see [`StemTarget`]) plus the master mix into a
+//! directory:
+//!
+//! ```text
+//! stems/
+//!   master.wav
+//!   source_0.wav
+//!   source_3.wav
+//!   bus_music.wav
+//! ```
+//!
+//! All files are 32-bit float at the world sample rate and channel count and start at the
+//! same block, so they line up sample-accurately in an editor. A stem holds the source's
+//! signal as it enters the mix: non-spatial sources as played, spatial sources after their
+//! volume but before spatialization (the binaural decode is shared by all sources), copied
+//! to every channel. A bus stem is the sum of the stems of the sources on the bus (see
+//! [`PetalSonicWorld::set_source_bus`](crate::PetalSonicWorld::set_source_bus)). Test tones
+//! and samplers appear only in the master stem.
+//!
+//! The render thread copies each block into lock-free FIFOs; a `petalsonic-stems` thread
+//! does the file I/O. If it falls behind, audio is dropped and counted in
+//! [`StemStats::dropped_frames`].
+
+use crate::error::{PetalSonicError, Result};
+use crate::world::SourceId;
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Length of audio each stem FIFO can hold
+const STEM_FIFO_DURATION_SECS: usize = 2;
+
+/// How long the writer thread sleeps when the FIFOs are empty
+const STEM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A source or bus recorded to its own stem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StemTarget {
+    /// A single source, written to `source_<index>.wav`
+    Source(SourceId),
+    /// The sources on a bus, written to `bus_<name>.wav`; characters other than ASCII
+    /// letters, digits, `-` and `_` in the name are replaced by `_`
+    Bus(String),
+}
+
+impl From<SourceId> for StemTarget {
+    fn from(source_id: SourceId) -> Self {
+        Self::Source(source_id)
+    }
+}
+
+impl StemTarget {
+    fn file_name(&self) -> String {
+        match self {
+            Self::Source(source_id) => format!("source_{}.wav", source_id.index()),
+            Self::Bus(bus) => {
+                let name: String = bus
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                format!("bus_{}.wav", name)
+            }
+        }
+    }
+}
+
+/// Counters of a running stem recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StemStats {
+    /// Frames written to each stem
+    pub frames_written: u64,
+    /// Frames dropped because the writer thread fell behind (summed over all stems)
+    pub dropped_frames: u64,
+}
+
+#[derive(Default)]
+struct SharedStemStats {
+    frames_written: AtomicU64,
+    dropped_frames: AtomicU64,
+}
+
+/// Minimal writer for 32-bit float WAV files; sizes are patched in on `finish`
+struct WavWriter {
+    writer: BufWriter<File>,
+    data_bytes: u64,
+}
+
+impl WavWriter {
+    fn create(path: &Path, sample_rate: u32, channels: u16) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let block_align = channels * 4;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&3u16.to_le_bytes())?; // IEEE float
+        writer.write_all(&channels.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&32u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            data_bytes: 0,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        for sample in samples {
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_bytes += samples.len() as u64 * 4;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        let data_bytes = u32::try_from(self.data_bytes).unwrap_or(u32::MAX);
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&data_bytes.saturating_add(36).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&data_bytes.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct StemTrack {
+    /// Recorded source or bus, `None` for the master stem
+    target: Option<StemTarget>,
+    /// Current block, accumulated by the mixer
+    block: Vec<f32>,
+    producer: HeapProd<f32>,
+}
+
+/// Render-side end of a stem recording, passed through the mixer while
+/// [`PetalSonicEngine::record_stems`](crate::PetalSonicEngine::record_stems) is active
+pub struct StemRecorder {
+    tracks: Vec<StemTrack>,
+    channels: usize,
+    /// Block a source is rendered into before it is added to its stems and the mix
+    scratch: Vec<f32>,
+    /// Bus stem (track index) of the spatial sources of the current block on a recorded bus
+    source_buses: Vec<(SourceId, usize)>,
+    stats: Arc<SharedStemStats>,
+}
+
+impl StemRecorder {
+    /// Start a block of `frames` frames: clear all source and bus stems
+    pub(crate) fn begin_block(&mut self, frames: usize) {
+        for track in &mut self.tracks {
+            track.block.clear();
+            track.block.resize(frames * self.channels, 0.0);
+        }
+        self.scratch.clear();
+        self.scratch.resize(frames * self.channels, 0.0);
+        self.source_buses.clear();
+    }
+
+    fn source_track(&self, source_id: SourceId) -> Option<usize> {
+        self.tracks.iter().position(
+            |track| matches!(&track.target, Some(StemTarget::Source(id)) if *id == source_id),
+        )
+    }
+
+    fn bus_track(&self, bus: Option<&str>) -> Option<usize> {
+        let bus = bus?;
+        self.tracks
+            .iter()
+            .position(|track| matches!(&track.target, Some(StemTarget::Bus(name)) if name == bus))
+    }
+
+    /// Zeroed interleaved block for the mixer to render a source into, if the source or
+    /// its bus is recorded; pass it on with [`Self::capture_block`]
+    pub(crate) fn block_mut(
+        &mut self,
+        source_id: SourceId,
+        bus: Option<&str>,
+    ) -> Option<&mut [f32]> {
+        if self.source_track(source_id).is_none() && self.bus_track(bus).is_none() {
+            return None;
+        }
+        self.scratch.fill(0.0);
+        Some(&mut self.scratch)
+    }
+
+    /// Add the block rendered into [`Self::block_mut`] to the stems of the source and its bus
+    pub(crate) fn capture_block(&mut self, source_id: SourceId, bus: Option<&str>) {
+        for index in [self.source_track(source_id), self.bus_track(bus)]
+            .into_iter()
+            .flatten()
+        {
+            let block = &mut self.tracks[index].block;
+            for (output, sample) in block.iter_mut().zip(&self.scratch) {
+                *output += sample;
+            }
+        }
+    }
+
+    /// Note the bus of a spatial source for [`Self::record_mono`]; call before the block is
+    /// spatialized
+    pub(crate) fn assign_bus(&mut self, source_id: SourceId, bus: Option<&str>) {
+        if let Some(index) = self.bus_track(bus)
+            && self.source_buses.len() < self.source_buses.capacity()
+        {
+            self.source_buses.push((source_id, index));
+        }
+    }
+
+    /// Add a mono signal of a source to every channel of its stem and of its bus's stem;
+    /// sources that are not recorded are ignored
+    pub fn record_mono(&mut self, source_id: SourceId, mono: &[f32]) {
+        let channels = self.channels;
+        let bus_track = self
+            .source_buses
+            .iter()
+            .find(|(id, _)| *id == source_id)
+            .map(|(_, index)| *index);
+        for index in [self.source_track(source_id), bus_track]
+            .into_iter()
+            .flatten()
+        {
+            let block = &mut self.tracks[index].block;
+            for (frame, sample) in block.chunks_exact_mut(channels).zip(mono) {
+                frame.iter_mut().for_each(|output| *output += sample);
+            }
+        }
+    }
+
+    /// Finish the block: queue the source and bus stems and `master` for the writer thread
+    pub(crate) fn end_block(&mut self, master: &[f32]) {
+        for track in &mut self.tracks {
+            let block = match track.target {
+                Some(_) => track.block.as_slice(),
+                None => master,
+            };
+            let frames = block.len() / self.channels;
+            let vacant_frames = track.producer.vacant_len() / self.channels;
+            let pushed = frames.min(vacant_frames);
+            track.producer.push_slice(&block[..pushed * self.channels]);
+
+            if pushed < frames {
+                self.stats
+                    .dropped_frames
+                    .fetch_add((frames - pushed) as u64, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// A running stem writer thread
+pub(crate) struct StemWriter {
+    shutdown: Arc<AtomicBool>,
+    stats: Arc<SharedStemStats>,
+    thread: Option<thread::JoinHandle<Result<()>>>,
+}
+
+impl StemWriter {
+    /// Create the stem files in `dir` and start the writer thread; returns it with the
+    /// recorder for the render thread
+    ///
+    /// # Arguments
+    /// * `max_sources` - Number of sources the render thread may play at once
+    pub fn spawn(
+        targets: &[StemTarget],
+        dir: &Path,
+        sample_rate: u32,
+        channels: u16,
+        max_sources: usize,
+    ) -> Result<(Self, StemRecorder)> {
+        std::fs::create_dir_all(dir)?;
+
+        let channels_usize = channels as usize;
+        let capacity = sample_rate as usize * STEM_FIFO_DURATION_SECS * channels_usize;
+        let stats = Arc::new(SharedStemStats::default());
+
+        let mut tracks = Vec::with_capacity(targets.len() + 1);
+        let mut outputs = Vec::with_capacity(targets.len() + 1);
+        let names = std::iter::once((None, "master.wav".to_string())).chain(
+            targets
+                .iter()
+                .map(|target| (Some(target.clone()), target.file_name())),
+        );
+        for (target, name) in names {
+            if tracks
+                .iter()
+                .any(|track: &StemTrack| track.target == target)
+            {
+                continue;
+            }
+            let writer = WavWriter::create(&dir.join(&name), sample_rate, channels)?;
+            let (producer, consumer): (HeapProd<f32>, HeapCons<f32>) =
+                HeapRb::<f32>::new(capacity.max(1)).split();
+            tracks.push(StemTrack {
+                target,
+                block: Vec::new(),
+                producer,
+            });
+            outputs.push((consumer, writer));
+        }
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let shutdown = shutdown.clone();
+            let stats = stats.clone();
+            thread::Builder::new()
+                .name("petalsonic-stems".into())
+                .spawn(move || {
+                    let mut chunk = vec![0.0f32; 4096 * channels_usize];
+                    loop {
+                        // Read the flag first so the final drain sees all pushed audio
+                        let finishing = shutdown.load(Ordering::Acquire);
+                        let mut idle = true;
+
+                        for (consumer, writer) in &mut outputs {
+                            let available = consumer.occupied_len() / channels_usize;
+                            let frames = available.min(chunk.len() / channels_usize);
+                            if frames == 0 {
+                                continue;
+                            }
+                            idle = false;
+                            let len = frames * channels_usize;
+                            consumer.pop_slice(&mut chunk[..len]);
+                            writer.write(&chunk[..len])?;
+                        }
+
+                        // The master stem defines the recorded length
+                        if let Some((_, master)) = outputs.first() {
+                            stats.frames_written.store(
+                                master.data_bytes / (4 * channels_usize as u64),
+                                Ordering::Relaxed,
+                            );
+                        }
+
+                        if idle {
+                            if finishing {
+                                break;
+                            }
+                            thread::sleep(STEM_POLL_INTERVAL);
+                        }
+                    }
+
+                    for (_, writer) in outputs {
+                        writer.finish()?;
+                    }
+                    Ok(())
+                })
+                .map_err(|e| {
+                    PetalSonicError::Engine(format!("Failed to spawn stem writer thread: {}", e))
+                })?
+        };
+
+        Ok((
+            Self {
+                shutdown,
+                stats: stats.clone(),
+                thread: Some(thread),
+            },
+            StemRecorder {
+                tracks,
+                channels: channels_usize,
+                scratch: Vec::new(),
+                source_buses: Vec::with_capacity(max_sources),
+                stats,
+            },
+        ))
+    }
+
+    pub fn stats(&self) -> StemStats {
+        StemStats {
+            frames_written: self.stats.frames_written.load(Ordering::Relaxed),
+            dropped_frames: self.stats.dropped_frames.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write the remaining audio, finalize the files and stop the thread
+    pub fn finish(mut self) -> Result<()> {
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<()> {
+        self.shutdown.store(true, Ordering::Release);
+        match self.thread.take().map(thread::JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(PetalSonicError::Engine(
+                "Stem writer thread panicked".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for StemWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.join() {
+            log::error!("Failed to finish stem recording: {}", e);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Sync groups: sources played sample-locked, e.g. the stems of a piece of music.
+//!
+//! A sync group starts, pauses, resumes, seeks and stops all its members in the same
+//! rendered block, and scales their volume together:
+//!
+//! ```ignore
+//! let stems = world.create_sync_group(&[drums, bass, melody])?;
+//! world.play_group(stems, LoopMode::Infinite)?;
+//! // Later, bring in a new section of the music
+//! world.seek_group(stems, Duration::from_secs(32))?;
+//! world.set_group_volume(stems, 0.5)?;
+//! ```
+//!
+//! Members keep their own configuration, volume, bus and routing. While a group plays,
+//! the render thread keeps a clock of the frames the group played and corrects any member
+//! that strays from it: looping members would otherwise lose the rest of the block they
+//! reach their loop end in, and clips converted to the world's sample rate may differ in
+//! length by a few frames. Members played in reverse, playlists and live sources are not
+//! corrected. Playing a member on its own (`PetalSonicWorld::play`) takes it out of the
+//! group's clock until the group is played again.
+
+use crate::world::SourceId;
+
+/// Identifier of a sync group in a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyncGroupId(pub(crate) u64);
+
+/// Members and volume of a sync group
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncGroup {
+    /// Sources played together, in the order given at creation
+    pub members: Vec<SourceId>,
+    /// Gain applied to all members on top of their own volume
+    pub volume: f32,
+}
+
+impl SyncGroup {
+    pub(crate) fn new(members: Vec<SourceId>) -> Self {
+        Self {
+            members,
+            volume: 1.0,
+        }
+    }
+}
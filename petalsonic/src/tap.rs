@@ -0,0 +1,323 @@
+//! Output tap: streams the master mix to an encoder and sink off the render thread.
+//!
+//! The render thread copies every rendered block (at the world sample rate and channel count)
+//! into a lock-free FIFO. A dedicated `petalsonic-tap` thread pops fixed-size frames,
+//! encodes them with a [`TapEncoder`] and hands the packets to a [`TapSink`], so encoding
+//! and network I/O never run on the render thread. If the tap thread falls behind, the
+//! newest audio is dropped and counted in [`TapStats::dropped_frames`].
+//!
+//! PetalSonic ships a PCM encoder ([`Pcm16Encoder`]), an Opus encoder (`OpusEncoder`, with
+//! the `opus` feature), a UDP sink ([`UdpSink`]) and accepts any `FnMut(&[u8])` closure as
+//! sink. Other formats plug in by implementing [`TapEncoder`] on top of an encoder crate; a
+//! WebRTC transport plugs in as a [`TapSink`].
+//!
+//! ```ignore
+//! let encoder = OpusEncoder::new(48000, 2, 96_000)?; // 20 ms packets at 96 kbit/s
+//! let sink = UdpSink::new("0.0.0.0:0", "192.168.1.20:5004")?;
+//! engine.start_output_tap(encoder, sink)?;
+//! ```
+
+use crate::error::{PetalSonicError, Result};
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Length of audio the tap FIFO can hold
+const TAP_FIFO_DURATION_SECS: usize = 1;
+
+/// How long the tap thread sleeps when no full frame is available
+const TAP_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Encodes interleaved f32 frames into packets
+pub trait TapEncoder: Send + 'static {
+    /// Number of frames (samples per channel) in each packet
+    fn frame_size(&self) -> usize;
+
+    /// Encode `frame_size()` interleaved frames into `packet` (cleared by the caller)
+    fn encode(&mut self, pcm: &[f32], channels: u16, packet: &mut Vec<u8>) -> Result<()>;
+}
+
+/// Receives encoded packets on the tap thread
+pub trait TapSink: Send + 'static {
+    /// Deliver one packet
+    fn send(&mut self, packet: &[u8]) -> Result<()>;
+}
+
+impl<F> TapSink for F
+where
+    F: FnMut(&[u8]) + Send + 'static,
+{
+    fn send(&mut self, packet: &[u8]) -> Result<()> {
+        self(packet);
+        Ok(())
+    }
+}
+
+/// Encodes frames as interleaved signed 16-bit little-endian PCM
+#[derive(Debug, Clone)]
+pub struct Pcm16Encoder {
+    frame_size: usize,
+}
+
+impl Pcm16Encoder {
+    /// Create an encoder producing packets of `frame_size` frames
+    pub fn new(frame_size: usize) -> Self {
+        Self {
+            frame_size: frame_size.max(1),
+        }
+    }
+}
+
+impl TapEncoder for Pcm16Encoder {
+    fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    fn encode(&mut self, pcm: &[f32], _channels: u16, packet: &mut Vec<u8>) -> Result<()> {
+        packet.reserve(pcm.len() * 2);
+        for sample in pcm {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            packet.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Largest Opus packet, as recommended by the libopus documentation
+#[cfg(feature = "opus")]
+const MAX_OPUS_PACKET: usize = 4000;
+
+/// Encodes frames as Opus packets of 20 ms (requires the `opus` feature)
+///
+/// Each packet is one raw Opus frame; the sink adds any framing or container (e.g. RTP or
+/// Ogg) the receiver needs. Opus only supports 8, 12, 16, 24 and 48 kHz and mono or stereo,
+/// so the world sample rate and channel count must be one of these.
+#[cfg(feature = "opus")]
+pub struct OpusEncoder {
+    encoder: audiopus::coder::Encoder,
+    channels: u16,
+    frame_size: usize,
+}
+
+#[cfg(feature = "opus")]
+impl OpusEncoder {
+    /// Create an encoder for audio at `sample_rate` with `channels`, targeting `bitrate`
+    /// bits per second
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Configuration` if Opus does not support the sample rate,
+    /// channel count or bitrate.
+    pub fn new(sample_rate: u32, channels: u16, bitrate: i32) -> Result<Self> {
+        let opus_error = |e: audiopus::Error| {
+            PetalSonicError::Configuration(format!("Unsupported Opus encoder setting: {}", e))
+        };
+        let opus_sample_rate =
+            audiopus::SampleRate::try_from(sample_rate as i32).map_err(opus_error)?;
+        let opus_channels = audiopus::Channels::try_from(channels as i32).map_err(opus_error)?;
+        let mut encoder = audiopus::coder::Encoder::new(
+            opus_sample_rate,
+            opus_channels,
+            audiopus::Application::Audio,
+        )
+        .map_err(opus_error)?;
+        encoder
+            .set_bitrate(audiopus::Bitrate::BitsPerSecond(bitrate))
+            .map_err(opus_error)?;
+        Ok(Self {
+            encoder,
+            channels,
+            frame_size: sample_rate as usize / 50,
+        })
+    }
+}
+
+#[cfg(feature = "opus")]
+impl TapEncoder for OpusEncoder {
+    fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    fn encode(&mut self, pcm: &[f32], channels: u16, packet: &mut Vec<u8>) -> Result<()> {
+        if channels != self.channels {
+            return Err(PetalSonicError::Configuration(format!(
+                "Opus encoder created for {} channels, tap has {}",
+                self.channels, channels
+            )));
+        }
+        packet.resize(MAX_OPUS_PACKET, 0);
+        let length = self
+            .encoder
+            .encode_float(pcm, packet)
+            .map_err(|e| PetalSonicError::Engine(format!("Opus encoding failed: {}", e)))?;
+        packet.truncate(length);
+        Ok(())
+    }
+}
+
+/// Sends each packet as one UDP datagram
+#[derive(Debug)]
+pub struct UdpSink {
+    socket: UdpSocket,
+}
+
+impl UdpSink {
+    /// Bind a socket to `bind_address` and send packets to `target`
+    pub fn new(bind_address: impl ToSocketAddrs, target: impl ToSocketAddrs) -> Result<Self> {
+        let socket = UdpSocket::bind(bind_address)?;
+        socket.connect(target)?;
+        Ok(Self { socket })
+    }
+}
+
+impl TapSink for UdpSink {
+    fn send(&mut self, packet: &[u8]) -> Result<()> {
+        self.socket.send(packet)?;
+        Ok(())
+    }
+}
+
+/// Counters of a running output tap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TapStats {
+    /// Packets delivered to the sink
+    pub packets_sent: u64,
+    /// Frames dropped because the tap thread fell behind
+    pub dropped_frames: u64,
+    /// Packets that failed to encode or send
+    pub errors: u64,
+}
+
+#[derive(Default)]
+struct SharedTapStats {
+    packets_sent: AtomicU64,
+    dropped_frames: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Render-side end of the tap FIFO
+pub(crate) struct TapProducer {
+    producer: HeapProd<f32>,
+    channels: usize,
+    stats: Arc<SharedTapStats>,
+}
+
+impl TapProducer {
+    /// Copy whole frames of an interleaved block into the FIFO, dropping what does not fit
+    pub fn push(&mut self, block: &[f32]) {
+        let vacant_frames = self.producer.vacant_len() / self.channels;
+        let block_frames = block.len() / self.channels;
+        let frames = block_frames.min(vacant_frames);
+        self.producer.push_slice(&block[..frames * self.channels]);
+
+        if frames < block_frames {
+            self.stats
+                .dropped_frames
+                .fetch_add((block_frames - frames) as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A running tap thread
+pub(crate) struct OutputTap {
+    shutdown: Arc<AtomicBool>,
+    stats: Arc<SharedTapStats>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl OutputTap {
+    /// Start the tap thread; returns it with the producer for the render thread
+    pub fn spawn<E, S>(
+        mut encoder: E,
+        mut sink: S,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<(Self, TapProducer)>
+    where
+        E: TapEncoder,
+        S: TapSink,
+    {
+        let channels_usize = channels as usize;
+        let frame_size = encoder.frame_size().max(1);
+        let capacity = (sample_rate as usize * TAP_FIFO_DURATION_SECS).max(frame_size * 2);
+        let (producer, mut consumer): (HeapProd<f32>, HeapCons<f32>) =
+            HeapRb::<f32>::new(capacity * channels_usize).split();
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stats = Arc::new(SharedTapStats::default());
+
+        let thread = {
+            let shutdown = shutdown.clone();
+            let stats = stats.clone();
+            thread::Builder::new()
+                .name("petalsonic-tap".into())
+                .spawn(move || {
+                    let mut pcm = vec![0.0f32; frame_size * channels_usize];
+                    let mut packet = Vec::new();
+
+                    while !shutdown.load(Ordering::Relaxed) {
+                        if consumer.occupied_len() < pcm.len() {
+                            thread::sleep(TAP_POLL_INTERVAL);
+                            continue;
+                        }
+
+                        consumer.pop_slice(&mut pcm);
+                        packet.clear();
+                        let result = encoder
+                            .encode(&pcm, channels, &mut packet)
+                            .and_then(|()| sink.send(&packet));
+                        match result {
+                            Ok(()) => {
+                                stats.packets_sent.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(e) => {
+                                log::warn!("Output tap failed to deliver packet: {}", e);
+                                stats.errors.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                    }
+                })
+                .map_err(|e| {
+                    PetalSonicError::Engine(format!("Failed to spawn output tap thread: {}", e))
+                })?
+        };
+
+        Ok((
+            Self {
+                shutdown,
+                stats: stats.clone(),
+                thread: Some(thread),
+            },
+            TapProducer {
+                producer,
+                channels: channels_usize,
+                stats,
+            },
+        ))
+    }
+
+    pub fn stats(&self) -> TapStats {
+        TapStats {
+            packets_sent: self.stats.packets_sent.load(Ordering::Relaxed),
+            dropped_frames: self.stats.dropped_frames.load(Ordering::Relaxed),
+            errors: self.stats.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for OutputTap {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            log::error!("Output tap thread panicked");
+        }
+    }
+}
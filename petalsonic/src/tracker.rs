@@ -0,0 +1,588 @@
+//! Tracker module music played as a generator source (requires the `tracker` feature).
+//!
+//! A [`TrackerModule`] holds a parsed ProTracker-compatible MOD file (4, 6, 8 and other
+//! `xCHN`/`xxCH` channel variants). Registered with
+//! [`PetalSonicWorld::register_tracker_module`](crate::PetalSonicWorld::register_tracker_module),
+//! it plays like any other source, but instead of reading a clip the render thread renders
+//! the song's patterns block by block while it plays. A song costs only the memory of its
+//! samples, however long it runs:
+//!
+//! ```ignore
+//! let module = TrackerModule::load("music/title.mod")?;
+//! let source_id = world.register_tracker_module(&module, SourceConfig::non_spatial())?;
+//! world.play(source_id, LoopMode::Infinite)?;
+//! ```
+//!
+//! Like voice and network sources, a module source is live: it never completes, so the loop
+//! mode passed to `play()` has no effect. Position jumps back to earlier patterns play on as
+//! in a tracker, and a song that ends starts over from its first pattern. `play()` restarts
+//! the song. Sources are mono in the mix, so the Amiga channel panning is folded down.
+//!
+//! Only the MOD format is supported; XM and IT modules are rejected when parsed.
+
+use crate::error::{PetalSonicError, Result};
+use crate::voice::LiveSource;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Amiga (PAL) clock used to convert note periods to sample rates
+const PAL_CLOCK: f64 = 7_093_789.2;
+/// Number of sample slots in a 31-sample module
+const NUM_SAMPLES: usize = 31;
+/// Offset of the format signature (e.g. "M.K.")
+const SIGNATURE_OFFSET: usize = 1080;
+/// Rows per pattern
+const ROWS_PER_PATTERN: usize = 64;
+/// Smallest and largest periods allowed by effects
+const MIN_PERIOD: f64 = 113.0;
+const MAX_PERIOD: f64 = 856.0;
+/// Speed (ticks per row) and tempo (BPM) a song starts at
+const INITIAL_SPEED: usize = 6;
+const INITIAL_TEMPO: usize = 125;
+
+/// A parsed MOD file, ready to be registered as a source (see the [module docs](self)).
+///
+/// Cloning is cheap; clones share the module's pattern and sample data.
+#[derive(Debug, Clone)]
+pub struct TrackerModule {
+    module: Arc<Module>,
+}
+
+impl TrackerModule {
+    /// Parse a MOD file from its bytes
+    ///
+    /// Sample data cut short by a truncated file is played as far as it goes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::AudioFormat` if the file is too short, has an unsupported
+    /// signature (e.g. an XM or IT module) or its pattern data is truncated.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        Ok(Self {
+            module: Arc::new(Module::parse(bytes)?),
+        })
+    }
+
+    /// Read and parse a MOD file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a supported module (see
+    /// [`Self::parse`]).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Song title stored in the module, `None` if it is empty
+    pub fn title(&self) -> Option<&str> {
+        Some(self.module.title.as_str()).filter(|title| !title.is_empty())
+    }
+
+    /// Number of channels (tracks) of the module
+    pub fn channels(&self) -> usize {
+        self.module.channels
+    }
+}
+
+/// Live source rendering a module on the render thread
+#[derive(Debug)]
+pub(crate) struct TrackerSource {
+    renderer: Renderer,
+    /// Frames left to render with the state of the current tick
+    tick_frames_left: usize,
+}
+
+impl TrackerSource {
+    pub fn new(module: &TrackerModule, sample_rate: u32) -> Self {
+        Self {
+            renderer: Renderer::new(module.module.clone(), sample_rate),
+            tick_frames_left: 0,
+        }
+    }
+}
+
+impl LiveSource for TrackerSource {
+    fn read(&mut self, output: &mut [f32]) -> bool {
+        output.fill(0.0);
+        let mut offset = 0;
+        while offset < output.len() {
+            if self.tick_frames_left == 0 {
+                self.tick_frames_left = self.renderer.start_tick();
+            }
+            let frames = self.tick_frames_left.min(output.len() - offset);
+            self.renderer.mix(&mut output[offset..offset + frames]);
+            offset += frames;
+            self.tick_frames_left -= frames;
+            if self.tick_frames_left == 0 {
+                self.renderer.end_tick();
+            }
+        }
+        true
+    }
+
+    fn take_activity_change(&mut self) -> Option<bool> {
+        None
+    }
+
+    fn reset(&mut self) {
+        self.renderer.restart();
+        self.tick_frames_left = 0;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sample {
+    data: Vec<f32>,
+    /// Finetune in 1/8 semitones (-8..=7)
+    finetune: i8,
+    volume: u8,
+    loop_start: usize,
+    loop_end: usize,
+}
+
+impl Sample {
+    fn is_looping(&self) -> bool {
+        self.loop_end > self.loop_start + 2
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    /// 1-based sample number, 0 for none
+    sample: usize,
+    period: u16,
+    effect: u8,
+    param: u8,
+}
+
+#[derive(Debug)]
+struct Module {
+    title: String,
+    channels: usize,
+    samples: Vec<Sample>,
+    orders: Vec<usize>,
+    /// Cells of each pattern, `ROWS_PER_PATTERN * channels` per pattern
+    patterns: Vec<Vec<Cell>>,
+}
+
+impl Module {
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let format_error =
+            |message: &str| PetalSonicError::AudioFormat(format!("Invalid MOD file: {}", message));
+
+        let signature = bytes
+            .get(SIGNATURE_OFFSET..SIGNATURE_OFFSET + 4)
+            .ok_or_else(|| format_error("file too short"))?;
+        let channels = channel_count(signature).ok_or_else(|| {
+            PetalSonicError::AudioFormat(format!(
+                "Unsupported MOD signature {:?}",
+                String::from_utf8_lossy(signature)
+            ))
+        })?;
+
+        let title = String::from_utf8_lossy(&bytes[0..20])
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+
+        let read_u16 = |offset: usize| u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+        let mut samples = Vec::with_capacity(NUM_SAMPLES);
+        let mut sample_lengths = Vec::with_capacity(NUM_SAMPLES);
+        for index in 0..NUM_SAMPLES {
+            let header = 20 + index * 30;
+            let length = read_u16(header + 22) as usize * 2;
+            let finetune = ((bytes[header + 24] & 0x0F) << 4) as i8 >> 4;
+            let volume = bytes[header + 25].min(64);
+            let loop_start = read_u16(header + 26) as usize * 2;
+            let loop_length = read_u16(header + 28) as usize * 2;
+
+            sample_lengths.push(length);
+            samples.push(Sample {
+                data: Vec::new(),
+                finetune,
+                volume,
+                loop_start: loop_start.min(length),
+                loop_end: (loop_start + loop_length).min(length),
+            });
+        }
+
+        let song_length = (bytes[950] as usize).clamp(1, 128);
+        let orders: Vec<usize> = bytes[952..952 + song_length]
+            .iter()
+            .map(|&order| order as usize)
+            .collect();
+        let num_patterns = bytes[952..1080]
+            .iter()
+            .map(|&order| order as usize + 1)
+            .max()
+            .unwrap_or(1);
+
+        let pattern_size = ROWS_PER_PATTERN * channels * 4;
+        let mut offset = SIGNATURE_OFFSET + 4;
+        let mut patterns = Vec::with_capacity(num_patterns);
+        for _ in 0..num_patterns {
+            let data = bytes
+                .get(offset..offset + pattern_size)
+                .ok_or_else(|| format_error("truncated pattern data"))?;
+            patterns.push(
+                data.chunks_exact(4)
+                    .map(|cell| Cell {
+                        sample: ((cell[0] & 0xF0) | (cell[2] >> 4)) as usize,
+                        period: (((cell[0] & 0x0F) as u16) << 8) | cell[1] as u16,
+                        effect: cell[2] & 0x0F,
+                        param: cell[3],
+                    })
+                    .collect(),
+            );
+            offset += pattern_size;
+        }
+
+        // Sample data follows the patterns; tolerate truncated files
+        for (sample, length) in samples.iter_mut().zip(sample_lengths) {
+            let end = (offset + length).min(bytes.len());
+            sample.data = bytes
+                .get(offset..end)
+                .unwrap_or_default()
+                .iter()
+                .map(|&byte| byte as i8 as f32 / 128.0)
+                .collect();
+            sample.loop_end = sample.loop_end.min(sample.data.len());
+            sample.loop_start = sample.loop_start.min(sample.loop_end);
+            offset = end;
+        }
+
+        Ok(Self {
+            title,
+            channels,
+            samples,
+            orders,
+            patterns,
+        })
+    }
+}
+
+/// Channel count for a module signature
+fn channel_count(signature: &[u8]) -> Option<usize> {
+    match signature {
+        b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => Some(4),
+        b"6CHN" => Some(6),
+        b"8CHN" | b"OCTA" | b"CD81" | b"FLT8" => Some(8),
+        [digit, b'C', b'H', b'N'] if digit.is_ascii_digit() => Some((digit - b'0') as usize),
+        [tens, ones, b'C', b'H'] if tens.is_ascii_digit() && ones.is_ascii_digit() => {
+            Some(((tens - b'0') * 10 + (ones - b'0')) as usize)
+        }
+        _ => None,
+    }
+    .filter(|&channels| channels > 0)
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    /// 0-based sample index, `None` if no sample was triggered yet
+    sample: Option<usize>,
+    position: f64,
+    playing: bool,
+    period: f64,
+    /// Period including vibrato and arpeggio for the current tick
+    output_period: f64,
+    finetune: i8,
+    volume: i32,
+    porta_target: f64,
+    porta_speed: u8,
+    vibrato_speed: u8,
+    vibrato_depth: u8,
+    vibrato_position: u8,
+    loop_row: usize,
+    loop_count: u8,
+}
+
+/// Renders a module tick by tick
+#[derive(Debug)]
+struct Renderer {
+    module: Arc<Module>,
+    sample_rate: u32,
+    channels: Vec<ChannelState>,
+    speed: usize,
+    tempo: usize,
+    order: usize,
+    row: usize,
+    tick: usize,
+    /// Fraction of a frame carried over from the previous tick
+    tick_remainder: f64,
+    /// Jump target (order, row) applied at the end of the current row
+    next_position: Option<(usize, usize)>,
+    /// Row to jump back to for a pattern loop (E6x), applied at the end of the current row
+    pattern_loop_row: Option<usize>,
+}
+
+impl Renderer {
+    fn new(module: Arc<Module>, sample_rate: u32) -> Self {
+        let channels = (0..module.channels)
+            .map(|_| ChannelState::default())
+            .collect();
+        Self {
+            module,
+            sample_rate,
+            channels,
+            speed: INITIAL_SPEED,
+            tempo: INITIAL_TEMPO,
+            order: 0,
+            row: 0,
+            tick: 0,
+            tick_remainder: 0.0,
+            next_position: None,
+            pattern_loop_row: None,
+        }
+    }
+
+    /// Go back to the beginning of the song, silencing all channels
+    fn restart(&mut self) {
+        self.channels
+            .iter_mut()
+            .for_each(|channel| *channel = ChannelState::default());
+        self.restart_song();
+        self.tick_remainder = 0.0;
+    }
+
+    /// Continue from the first pattern at the initial speed, letting notes ring on
+    fn restart_song(&mut self) {
+        self.speed = INITIAL_SPEED;
+        self.tempo = INITIAL_TEMPO;
+        self.order = 0;
+        self.row = 0;
+        self.tick = 0;
+        self.next_position = None;
+        self.pattern_loop_row = None;
+    }
+
+    /// Apply the effects of the current tick and return its length in frames
+    fn start_tick(&mut self) -> usize {
+        if self.tick == 0 {
+            self.process_row();
+        } else {
+            self.process_tick();
+        }
+
+        let tick_frames = self.sample_rate as f64 * 2.5 / self.tempo as f64 + self.tick_remainder;
+        let frames = tick_frames as usize;
+        self.tick_remainder = tick_frames - frames as f64;
+        frames
+    }
+
+    /// Move on to the next tick, and to the next row after the last tick of a row
+    fn end_tick(&mut self) {
+        self.tick += 1;
+        if self.tick >= self.speed {
+            self.tick = 0;
+            if !self.advance_row() {
+                self.restart_song();
+            }
+        }
+    }
+
+    /// Move to the next row. Returns false at the end of the song.
+    fn advance_row(&mut self) -> bool {
+        if let Some((order, row)) = self.next_position.take() {
+            self.order = order;
+            self.row = row;
+            self.pattern_loop_row = None;
+        } else if let Some(row) = self.pattern_loop_row.take() {
+            self.row = row;
+        } else {
+            self.row += 1;
+            if self.row >= ROWS_PER_PATTERN {
+                self.row = 0;
+                self.order += 1;
+            }
+        }
+        self.order < self.module.orders.len()
+    }
+
+    fn cell(&self, channel: usize) -> Cell {
+        let pattern = self.module.orders[self.order];
+        self.module
+            .patterns
+            .get(pattern)
+            .and_then(|cells| cells.get(self.row * self.module.channels + channel))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Tick 0: trigger notes and apply row effects
+    fn process_row(&mut self) {
+        for index in 0..self.channels.len() {
+            let cell = self.cell(index);
+            let channel = &mut self.channels[index];
+
+            if cell.sample > 0
+                && let Some(sample) = self.module.samples.get(cell.sample - 1)
+            {
+                channel.sample = Some(cell.sample - 1);
+                channel.volume = sample.volume as i32;
+                channel.finetune = sample.finetune;
+            }
+
+            if cell.period > 0 {
+                if cell.effect == 0x3 || cell.effect == 0x5 {
+                    channel.porta_target = cell.period as f64;
+                } else {
+                    channel.period = cell.period as f64;
+                    channel.position = 0.0;
+                    channel.playing = channel.sample.is_some();
+                    channel.vibrato_position = 0;
+                }
+            }
+            channel.output_period = channel.period;
+
+            let (x, y) = (cell.param >> 4, cell.param & 0x0F);
+            match cell.effect {
+                0x3 if cell.param > 0 => channel.porta_speed = cell.param,
+                0x4 => {
+                    if x > 0 {
+                        channel.vibrato_speed = x;
+                    }
+                    if y > 0 {
+                        channel.vibrato_depth = y;
+                    }
+                }
+                0x9 if cell.period > 0 => channel.position = cell.param as f64 * 256.0,
+                0xB => self.next_position = Some((cell.param as usize, 0)),
+                0xC => channel.volume = cell.param.min(64) as i32,
+                0xD => {
+                    let row = (x * 10 + y) as usize;
+                    self.next_position = Some((self.order + 1, row.min(ROWS_PER_PATTERN - 1)));
+                }
+                0xE => match x {
+                    0x1 => channel.period = (channel.period - y as f64).max(MIN_PERIOD),
+                    0x2 => channel.period = (channel.period + y as f64).min(MAX_PERIOD),
+                    0x6 if y == 0 => channel.loop_row = self.row,
+                    0x6 => {
+                        if channel.loop_count == 0 {
+                            channel.loop_count = y;
+                            self.pattern_loop_row = Some(channel.loop_row);
+                        } else {
+                            channel.loop_count -= 1;
+                            if channel.loop_count > 0 {
+                                self.pattern_loop_row = Some(channel.loop_row);
+                            }
+                        }
+                    }
+                    0xA => channel.volume = (channel.volume + y as i32).min(64),
+                    0xB => channel.volume = (channel.volume - y as i32).max(0),
+                    0xC if y == 0 => channel.volume = 0,
+                    _ => {}
+                },
+                0xF if cell.param > 0 => {
+                    if cell.param < 32 {
+                        self.speed = cell.param as usize;
+                    } else {
+                        self.tempo = cell.param as usize;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Ticks after the first: continuous effects
+    fn process_tick(&mut self) {
+        for index in 0..self.channels.len() {
+            let cell = self.cell(index);
+            let tick = self.tick;
+            let channel = &mut self.channels[index];
+            let (x, y) = (cell.param >> 4, cell.param & 0x0F);
+            channel.output_period = channel.period;
+
+            match cell.effect {
+                0x0 if cell.param > 0 => {
+                    let semitones = [0, x, y][tick % 3] as f64;
+                    channel.output_period = channel.period / 2f64.powf(semitones / 12.0);
+                }
+                0x1 => channel.period = (channel.period - cell.param as f64).max(MIN_PERIOD),
+                0x2 => channel.period = (channel.period + cell.param as f64).min(MAX_PERIOD),
+                0x3 => Self::tone_portamento(channel),
+                0x4 => Self::vibrato(channel),
+                0x5 => {
+                    Self::tone_portamento(channel);
+                    Self::volume_slide(channel, x, y);
+                }
+                0x6 => {
+                    Self::vibrato(channel);
+                    Self::volume_slide(channel, x, y);
+                }
+                0xA => Self::volume_slide(channel, x, y),
+                0xE if x == 0xC && tick == y as usize => channel.volume = 0,
+                _ => {}
+            }
+
+            if !matches!(cell.effect, 0x0 | 0x4 | 0x6) {
+                channel.output_period = channel.period;
+            }
+        }
+    }
+
+    fn tone_portamento(channel: &mut ChannelState) {
+        let speed = channel.porta_speed as f64;
+        if channel.porta_target <= 0.0 {
+            return;
+        }
+        if channel.period < channel.porta_target {
+            channel.period = (channel.period + speed).min(channel.porta_target);
+        } else {
+            channel.period = (channel.period - speed).max(channel.porta_target);
+        }
+    }
+
+    fn vibrato(channel: &mut ChannelState) {
+        let phase = channel.vibrato_position as f64 / 64.0 * std::f64::consts::TAU;
+        let delta = phase.sin() * 255.0 * channel.vibrato_depth as f64 / 128.0;
+        channel.output_period = channel.period + delta;
+        channel.vibrato_position = (channel.vibrato_position + channel.vibrato_speed) % 64;
+    }
+
+    fn volume_slide(channel: &mut ChannelState, up: u8, down: u8) {
+        if up > 0 {
+            channel.volume = (channel.volume + up as i32).min(64);
+        } else {
+            channel.volume = (channel.volume - down as i32).max(0);
+        }
+    }
+
+    /// Add the channels to the mono `output` with the current channel state
+    fn mix(&mut self, output: &mut [f32]) {
+        let gain = 1.0 / self.module.channels as f32;
+
+        for channel in self.channels.iter_mut() {
+            let Some(sample) = channel.sample.and_then(|s| self.module.samples.get(s)) else {
+                continue;
+            };
+            if !channel.playing || channel.output_period <= 0.0 || sample.data.is_empty() {
+                continue;
+            }
+
+            let frequency = PAL_CLOCK / (channel.output_period * 2.0)
+                * 2f64.powf(channel.finetune as f64 / 96.0);
+            let increment = frequency / self.sample_rate as f64;
+            let volume = channel.volume as f32 / 64.0 * gain;
+
+            for frame in output.iter_mut() {
+                if sample.is_looping() {
+                    if channel.position >= sample.loop_end as f64 {
+                        let loop_length = (sample.loop_end - sample.loop_start) as f64;
+                        channel.position = sample.loop_start as f64
+                            + (channel.position - sample.loop_start as f64) % loop_length;
+                    }
+                } else if channel.position >= sample.data.len() as f64 {
+                    channel.playing = false;
+                    break;
+                }
+
+                let index = channel.position as usize;
+                let fraction = (channel.position - index as f64) as f32;
+                let next = sample.data.get(index + 1).copied().unwrap_or(0.0);
+                *frame += (sample.data[index] * (1.0 - fraction) + next * fraction) * volume;
+                channel.position += increment;
+            }
+        }
+    }
+}
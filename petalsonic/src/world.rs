@@ -1,11 +1,40 @@
-use crate::audio_data::PetalSonicAudioData;
-use crate::config::{PetalSonicWorldDesc, SourceConfig};
+use crate::audio_data::{PetalSonicAudioData, resample_shared};
+use crate::bus_tone::{BusTone, ToneFilter};
+use crate::caption::{Caption, CaptionTrack};
+use crate::command_queue::{CommandQueue, CommandQueueStats};
+use crate::config::{PetalSonicWorldDesc, ResamplePolicy, SourceConfig};
+use crate::distance_delay::DistanceDelay;
+use crate::envelope::{EnvelopeConfig, EnvelopeFollower, SharedEnvelope};
 use crate::error::Result;
+use crate::events::PetalSonicEvent;
+use crate::haptics::{HapticsCursor, HapticsTrack};
 use crate::math::{Pose, Vec3};
-use crate::playback::{LoopMode, PlaybackCommand};
-use crossbeam_channel::{Receiver, Sender};
-use std::collections::HashMap;
+use crate::memory::{MemoryBudgetPolicy, MemoryStats, MemoryTracker};
+use crate::network::{JitterBuffer, NetworkAudioSource, NetworkSourceConfig, PacketDecoder};
+use crate::playback::{
+    LoopMode, OutputRouting, PlaybackCommand, PlaybackDirection, Playlist, PlaylistSegment,
+    PlaylistSource,
+};
+use crate::random::AudioRng;
+use crate::rate_limit::{Admission, RateLimit, RateLimitStats, RateLimiter};
+use crate::reverb::{Environment, ReverbSettings};
+use crate::silence::SilenceDetector;
+use crate::source_delay::SourceDelay;
+use crate::spatial::{self, SpatialBypass};
+use crate::spatial_info::SpatialInfo;
+use crate::sync_group::{SyncGroup, SyncGroupId};
+#[cfg(feature = "tracker")]
+use crate::tracker::{TrackerModule, TrackerSource};
+use crate::voice::{SharedLiveSource, VoiceActivityConfig, VoiceInput, VoiceStream};
+use crate::zones::{AttenuationZone, ZoneId};
+use ringbuf::HeapCons;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Fade time of [`PetalSonicWorld::set_environment`]
+const DEFAULT_ENVIRONMENT_TRANSITION: Duration = Duration::from_secs(1);
 
 /// Lightweight, type-safe handle for audio sources.
 ///
@@ -14,6 +43,13 @@ use std::sync::Arc;
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SourceId(u64);
 
+impl SourceId {
+    /// Numeric value of the handle, unique within a world
+    pub(crate) fn index(self) -> u64 {
+        self.0
+    }
+}
+
 impl std::fmt::Display for SourceId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "SourceId({})", self.0)
@@ -31,27 +67,116 @@ impl std::fmt::Display for SourceId {
 ///
 /// - **Main thread**: Owns the `PetalSonicWorld`, loads audio files, manages sources
 /// - **Audio thread**: Receives commands via channels, performs spatialization and playback
+///
+/// # Worlds and engines
+///
+/// A world is rendered by exactly one [`PetalSonicEngine`](crate::PetalSonicEngine) at a
+/// time: the engine consumes the world's command queue, so a second engine would steal
+/// commands from the first. Sharing the `Arc<PetalSonicWorld>` with other threads of your
+/// application is fine, but [`PetalSonicEngine::new`](crate::PetalSonicEngine::new) fails
+/// if another engine is still attached to the world. The world becomes available again
+/// when that engine is dropped.
+///
+/// An engine renders a single world with a single listener. To keep e.g. UI sounds apart
+/// from the game world, register them as non-spatial sources in the same world.
 pub struct PetalSonicWorld {
     desc: PetalSonicWorldDesc,
     audio_data_storage: std::sync::Mutex<HashMap<SourceId, Arc<PetalSonicAudioData>>>,
     source_configs: std::sync::Mutex<HashMap<SourceId, SourceConfig>>,
     listener: std::sync::Mutex<PetalSonicAudioListener>,
+    soloed_sources: std::sync::Mutex<HashSet<SourceId>>,
+    spatial_bypass: std::sync::Mutex<HashMap<SourceId, SpatialBypass>>,
+    /// Sources played in reverse (all others play forward)
+    reversed_sources: std::sync::Mutex<HashSet<SourceId>>,
+    /// Sources routed to specific output channels (all others play on every channel)
+    output_routing: std::sync::Mutex<HashMap<SourceId, OutputRouting>>,
+    /// Output bus of each source assigned to one (see [`Self::set_source_bus`])
+    source_buses: std::sync::Mutex<HashMap<SourceId, String>>,
+    /// Tone of each bus given one, `None` being the main mix (see [`Self::set_bus_tone`])
+    bus_tones: std::sync::Mutex<HashMap<Option<String>, BusTone>>,
+    /// Alignment delay in frames of each source given one (see [`Self::set_source_delay`])
+    source_delays: std::sync::Mutex<HashMap<SourceId, usize>>,
+    live_sources: std::sync::Mutex<HashMap<SourceId, SharedLiveSource>>,
+    /// Segments of each playlist source (see [`Self::register_playlist`])
+    playlists: std::sync::Mutex<HashMap<SourceId, PlaylistSource>>,
+    envelopes: std::sync::Mutex<HashMap<SourceId, (EnvelopeConfig, Arc<SharedEnvelope>)>>,
+    zones: std::sync::Mutex<HashMap<ZoneId, AttenuationZone>>,
+    /// Propagation results published by the render thread, in the native convention
+    spatial_info: std::sync::Mutex<HashMap<SourceId, SpatialInfo>>,
+    /// Sources with a playback instance on the render thread, published by the render
+    /// thread; they are never evicted from the memory budget
+    playing_sources: std::sync::Mutex<HashSet<SourceId>>,
+    /// Environment preset set via `set_environment`
+    environment: std::sync::Mutex<Option<Environment>>,
+    /// Reverb settings and fade time not yet picked up by the render thread
+    pending_reverb: std::sync::Mutex<Option<(ReverbSettings, Duration)>>,
+    /// Event channel of the attached engine, so events raised by the world (e.g.
+    /// `SourceReady`) are delivered in order with the render thread's
+    event_sender: crossbeam_channel::Sender<PetalSonicEvent>,
+    event_receiver: crossbeam_channel::Receiver<PetalSonicEvent>,
+    next_zone_id: std::sync::atomic::AtomicU64,
+    /// Sync groups created via `create_sync_group`
+    sync_groups: std::sync::Mutex<HashMap<SyncGroupId, SyncGroup>>,
+    next_sync_group_id: std::sync::atomic::AtomicU64,
     next_source_id: std::sync::Mutex<u64>,
-    command_sender: Sender<PlaybackCommand>,
-    command_receiver: Receiver<PlaybackCommand>,
+    /// Seed the random number generator was last seeded with
+    random_seed: std::sync::atomic::AtomicU64,
+    rng: std::sync::Mutex<AudioRng>,
+    memory: std::sync::Mutex<MemoryTracker>,
+    /// Rate limit tag of each tagged source
+    source_tags: std::sync::Mutex<HashMap<SourceId, String>>,
+    /// Caption of each captioned source
+    captions: std::sync::Mutex<HashMap<SourceId, Arc<Caption>>>,
+    /// Haptics track of each source that has one, at the world's sample rate
+    haptics_tracks: std::sync::Mutex<HashMap<SourceId, HapticsTrack>>,
+    rate_limiter: std::sync::Mutex<RateLimiter>,
+    /// Playback commands for the render thread (see [`crate::command_queue`])
+    commands: CommandQueue,
+    /// Set while an engine renders this world
+    engine_attached: AtomicBool,
 }
 
 impl PetalSonicWorld {
     pub fn new(config: PetalSonicWorldDesc) -> Result<Self> {
-        let (command_sender, command_receiver) = crossbeam_channel::unbounded();
+        let random_seed = config.random_seed.unwrap_or_else(AudioRng::clock_seed);
+        let commands = CommandQueue::new(config.command_queue_capacity);
+        let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+        let playing_sources = HashSet::with_capacity(config.max_sources);
         Ok(Self {
             desc: config,
             audio_data_storage: std::sync::Mutex::new(HashMap::new()),
             source_configs: std::sync::Mutex::new(HashMap::new()),
             listener: std::sync::Mutex::new(PetalSonicAudioListener::default()),
+            soloed_sources: std::sync::Mutex::new(HashSet::new()),
+            spatial_bypass: std::sync::Mutex::new(HashMap::new()),
+            reversed_sources: std::sync::Mutex::new(HashSet::new()),
+            output_routing: std::sync::Mutex::new(HashMap::new()),
+            source_buses: std::sync::Mutex::new(HashMap::new()),
+            bus_tones: std::sync::Mutex::new(HashMap::new()),
+            source_delays: std::sync::Mutex::new(HashMap::new()),
+            live_sources: std::sync::Mutex::new(HashMap::new()),
+            playlists: std::sync::Mutex::new(HashMap::new()),
+            envelopes: std::sync::Mutex::new(HashMap::new()),
+            zones: std::sync::Mutex::new(HashMap::new()),
+            spatial_info: std::sync::Mutex::new(HashMap::new()),
+            playing_sources: std::sync::Mutex::new(playing_sources),
+            environment: std::sync::Mutex::new(None),
+            pending_reverb: std::sync::Mutex::new(None),
+            event_sender,
+            event_receiver,
+            next_zone_id: std::sync::atomic::AtomicU64::new(0),
+            sync_groups: std::sync::Mutex::new(HashMap::new()),
+            next_sync_group_id: std::sync::atomic::AtomicU64::new(0),
             next_source_id: std::sync::Mutex::new(0),
-            command_sender,
-            command_receiver,
+            random_seed: std::sync::atomic::AtomicU64::new(random_seed),
+            rng: std::sync::Mutex::new(AudioRng::new(random_seed)),
+            memory: std::sync::Mutex::new(MemoryTracker::default()),
+            source_tags: std::sync::Mutex::new(HashMap::new()),
+            captions: std::sync::Mutex::new(HashMap::new()),
+            haptics_tracks: std::sync::Mutex::new(HashMap::new()),
+            rate_limiter: std::sync::Mutex::new(RateLimiter::default()),
+            commands,
+            engine_attached: AtomicBool::new(false),
         })
     }
 
@@ -65,23 +190,38 @@ impl PetalSonicWorld {
     /// This pre-loads and prepares the audio for playback but does not start playing it.
     /// Call `play()` with the returned SourceId to actually start playback.
     ///
-    /// The audio data is automatically resampled to match the world's sample rate if needed.
+    /// The audio data is automatically resampled to match the world's sample rate if needed,
+    /// at registration or later depending on [`PetalSonicWorldDesc::resample_policy`].
+    /// Once no conversion is left before playback, a `SourceReady` event is emitted and
+    /// [`Self::is_ready`] returns true.
     ///
     /// # Arguments
     ///
     /// * `audio_data` - The audio data to register
     /// * `config` - Configuration for how the source should be processed (spatial or non-spatial)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if resampling fails or the data does not fit in the world's
+    /// [memory budget](PetalSonicWorldDesc::memory_budget).
     pub fn register_audio(
         &self,
         audio_data: Arc<PetalSonicAudioData>,
         config: SourceConfig,
     ) -> Result<SourceId> {
         // Automatically resample if the audio data sample rate doesn't match the world's sample rate
-        let resampled_audio_data = if audio_data.sample_rate() != self.desc.sample_rate {
-            Arc::new(audio_data.resample(self.desc.sample_rate)?)
+        let resampled = audio_data.sample_rate() != self.desc.sample_rate
+            && self.desc.resample_policy == ResamplePolicy::Eager;
+        let resampled_audio_data = if resampled {
+            resample_shared(
+                &audio_data,
+                self.desc.sample_rate,
+                self.desc.asset_resample_quality,
+            )?
         } else {
             audio_data
         };
+        self.reserve_memory(&resampled_audio_data)?;
 
         let mut next_id = self.next_source_id.lock().unwrap();
         let id = SourceId(*next_id);
@@ -92,10 +232,334 @@ impl PetalSonicWorld {
             .lock()
             .unwrap()
             .insert(id, resampled_audio_data);
-        self.source_configs.lock().unwrap().insert(id, config);
+        self.source_configs
+            .lock()
+            .unwrap()
+            .insert(id, self.config_to_native(config));
+        self.memory.lock().unwrap().register(id, resampled);
+        if self.is_ready(id) {
+            self.emit_source_ready(id);
+        }
+        Ok(id)
+    }
+
+    /// Returns true if `play()` starts the source without converting its data first.
+    ///
+    /// This is the case once the stored data is at the world's sample rate, or under
+    /// [`ResamplePolicy::Streaming`], which converts it while it renders. Under
+    /// [`ResamplePolicy::OnFirstPlay`] a mismatched source only becomes ready once its
+    /// first `play()` stored the converted data. Returns false for unknown sources.
+    pub fn is_ready(&self, source_id: SourceId) -> bool {
+        self.get_audio_data(source_id).is_some_and(|audio_data| {
+            audio_data.sample_rate() == self.desc.sample_rate
+                || self.desc.resample_policy == ResamplePolicy::Streaming
+        })
+    }
+
+    /// Sends `SourceReady` through the engine's event channel, behind the events already
+    /// emitted
+    fn emit_source_ready(&self, source_id: SourceId) {
+        let _ = self
+            .event_sender
+            .send(PetalSonicEvent::SourceReady { source_id });
+    }
+
+    /// Sender and receiver of the event channel, for the attached engine
+    pub(crate) fn event_channel(
+        &self,
+    ) -> (
+        crossbeam_channel::Sender<PetalSonicEvent>,
+        crossbeam_channel::Receiver<PetalSonicEvent>,
+    ) {
+        (self.event_sender.clone(), self.event_receiver.clone())
+    }
+
+    /// Converts a source's data to the world's sample rate if that was deferred to its
+    /// first playback (see [`ResamplePolicy::OnFirstPlay`])
+    fn resample_on_first_play(&self, audio_id: SourceId) -> Result<()> {
+        if self.desc.resample_policy != ResamplePolicy::OnFirstPlay {
+            return Ok(());
+        }
+        let Some(audio_data) = self.get_audio_data(audio_id) else {
+            return Ok(());
+        };
+        if audio_data.sample_rate() == self.desc.sample_rate {
+            return Ok(());
+        }
+
+        log::debug!(
+            "Resampling source {} from {} Hz on first play",
+            audio_id,
+            audio_data.sample_rate()
+        );
+        let resampled = resample_shared(
+            &audio_data,
+            self.desc.sample_rate,
+            self.desc.asset_resample_quality,
+        )?;
+        if let Some(stored) = self.audio_data_storage.lock().unwrap().get_mut(&audio_id) {
+            *stored = resampled;
+        }
+        self.memory.lock().unwrap().register(audio_id, true);
+        self.emit_source_ready(audio_id);
+        Ok(())
+    }
+
+    /// Makes room for `audio_data` within the memory budget, evicting sources if the budget
+    /// policy allows it
+    fn reserve_memory(&self, audio_data: &PetalSonicAudioData) -> Result<()> {
+        let Some(budget) = self.desc.memory_budget else {
+            return Ok(());
+        };
+
+        let mut evictions = Vec::new();
+        {
+            let storage = self.audio_data_storage.lock().unwrap();
+            // Bytes and number of sources of each distinct allocation
+            let mut allocations: HashMap<usize, (usize, usize)> = HashMap::new();
+            for data in storage.values() {
+                allocations
+                    .entry(data.storage_id())
+                    .or_insert((data.memory_size(), 0))
+                    .1 += 1;
+            }
+            let in_use: usize = allocations.values().map(|(bytes, _)| bytes).sum();
+            let required = if allocations.contains_key(&audio_data.storage_id()) {
+                0
+            } else {
+                audio_data.memory_size()
+            };
+
+            let mut total = in_use;
+            if budget.policy == MemoryBudgetPolicy::EvictLeastRecentlyUsed {
+                // Playing sources keep their data alive on the render thread, so evicting
+                // them would free nothing
+                let live_sources = self.live_sources.lock().unwrap();
+                let playing_sources = self.playing_sources.lock().unwrap();
+                for source_id in self.memory.lock().unwrap().least_recently_used() {
+                    if total + required <= budget.max_bytes {
+                        break;
+                    }
+                    if live_sources.contains_key(&source_id) || playing_sources.contains(&source_id)
+                    {
+                        continue;
+                    }
+                    let Some(data) = storage.get(&source_id) else {
+                        continue;
+                    };
+                    if let Some((bytes, sources)) = allocations.get_mut(&data.storage_id()) {
+                        *sources -= 1;
+                        if *sources == 0 {
+                            total -= *bytes;
+                        }
+                    }
+                    evictions.push(source_id);
+                }
+            }
+
+            if total + required > budget.max_bytes {
+                return Err(crate::error::PetalSonicError::AudioLoading(format!(
+                    "Registering {} bytes of audio would exceed the memory budget ({} of {} bytes in use)",
+                    required, in_use, budget.max_bytes
+                )));
+            }
+        }
+
+        for source_id in evictions {
+            log::info!(
+                "Evicting source {} to stay within the memory budget",
+                source_id
+            );
+            self.remove_audio_data(source_id);
+            self.memory.lock().unwrap().record_eviction(source_id);
+        }
+        Ok(())
+    }
+
+    /// Returns the memory used by the world's audio data. See [`crate::memory`].
+    pub fn memory_stats(&self) -> MemoryStats {
+        let storage = self.audio_data_storage.lock().unwrap();
+        let memory = self.memory.lock().unwrap();
+
+        let mut stats = MemoryStats {
+            budget_bytes: self.desc.memory_budget.map(|budget| budget.max_bytes),
+            evicted_sources: memory.evicted_total(),
+            resample_cache: crate::audio_data::resample_cache_stats(),
+            ..Default::default()
+        };
+        let mut counted = HashSet::new();
+        for (source_id, data) in storage.iter() {
+            let bytes = data.memory_size();
+            stats.source_bytes.insert(*source_id, bytes);
+            if counted.insert(data.storage_id()) {
+                stats.total_bytes += bytes;
+            }
+            if memory.is_resampled(*source_id) {
+                stats.resampled_bytes += bytes;
+            }
+        }
+        stats
+    }
+
+    /// Returns the sources evicted to stay within the memory budget since the last call.
+    ///
+    /// Their IDs are no longer valid; register the audio again to play it.
+    pub fn take_evicted_sources(&self) -> Vec<SourceId> {
+        self.memory.lock().unwrap().take_evicted()
+    }
+
+    /// Registers a live voice source and returns its SourceId with the input handle.
+    ///
+    /// The source plays mono samples pushed through the returned [`VoiceInput`] at the
+    /// world sample rate instead of a clip. It is gated by voice activity detection:
+    /// silent blocks skip spatialization and are replaced by comfort noise, and
+    /// `VoiceActivityStarted`/`VoiceActivityStopped` events report when the source starts
+    /// and stops talking. See [`crate::voice`].
+    ///
+    /// Play, pause, stop and remove the source like any other. A live source never
+    /// completes, so the loop mode passed to `play()` has no effect; `play()` discards
+    /// audio buffered while the source was stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration for how the source should be processed (spatial or non-spatial)
+    /// * `vad` - Voice activity detection settings
+    pub fn register_voice(
+        &self,
+        config: SourceConfig,
+        vad: VoiceActivityConfig,
+    ) -> Result<(SourceId, VoiceInput)> {
+        let (input, stream) = VoiceStream::new(&vad, self.desc.sample_rate);
+        let id = self.register_live_source(config, Arc::new(std::sync::Mutex::new(stream)))?;
+        Ok((id, input))
+    }
+
+    /// Registers a jitter-buffered network source and returns its SourceId with the
+    /// packet input handle.
+    ///
+    /// The source plays timestamped packets pushed through the returned
+    /// [`NetworkAudioSource`], decoded by `decoder` to mono at the world sample rate. Like
+    /// voice sources it never completes; `play()` discards buffered packets. See
+    /// [`crate::network`].
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration for how the source should be processed (spatial or non-spatial)
+    /// * `network` - Jitter buffer settings
+    /// * `decoder` - Decoder for the packet payloads
+    pub fn register_network_source<D: PacketDecoder>(
+        &self,
+        config: SourceConfig,
+        network: NetworkSourceConfig,
+        decoder: D,
+    ) -> Result<(SourceId, NetworkAudioSource)> {
+        let (input, buffer) = JitterBuffer::new(&network, decoder, self.desc.sample_rate);
+        let id = self.register_live_source(config, Arc::new(std::sync::Mutex::new(buffer)))?;
+        Ok((id, input))
+    }
+
+    /// Registers a tracker module and returns its SourceId.
+    ///
+    /// The render thread renders the module's patterns block by block while the source
+    /// plays, at the world sample rate. Like other live sources it never completes: the
+    /// loop mode passed to `play()` has no effect, and `play()` restarts the song. See
+    /// [`crate::tracker`].
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The parsed module
+    /// * `config` - Configuration for how the source should be processed (spatial or non-spatial)
+    #[cfg(feature = "tracker")]
+    pub fn register_tracker_module(
+        &self,
+        module: &TrackerModule,
+        config: SourceConfig,
+    ) -> Result<SourceId> {
+        let source = TrackerSource::new(module, self.desc.sample_rate);
+        self.register_live_source(config, Arc::new(std::sync::Mutex::new(source)))
+    }
+
+    /// Register a live source under a new SourceId, with an empty clip as placeholder so
+    /// the regular playback commands apply to it
+    fn register_live_source(
+        &self,
+        config: SourceConfig,
+        live_source: SharedLiveSource,
+    ) -> Result<SourceId> {
+        let placeholder = PetalSonicAudioData::from_samples(Vec::new(), self.desc.sample_rate, 1)?;
+        let id = self.register_audio(placeholder, config)?;
+        self.live_sources.lock().unwrap().insert(id, live_source);
         Ok(id)
     }
 
+    /// Returns the live source registered under `id`, if any
+    pub(crate) fn live_source(&self, id: SourceId) -> Option<SharedLiveSource> {
+        self.live_sources.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Registers a playlist source and returns its SourceId with the playlist handle.
+    ///
+    /// The source plays `segments` back to back without gaps, each as often as its
+    /// [`play_count`](PlaylistSegment::play_count) says or until
+    /// [`Playlist::advance`] is called. See [Playlists](crate::playback#playlists).
+    ///
+    /// Segments are converted to the world's sample rate right away, whatever the
+    /// [`ResamplePolicy`]. Only the first segment counts towards the memory budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments` - The segments in playing order
+    /// * `config` - Configuration for how the source should be processed (spatial or non-spatial)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no segments, a segment has no audio or a play count
+    /// of 0, or if resampling or registering the first segment fails.
+    pub fn register_playlist(
+        &self,
+        segments: Vec<PlaylistSegment>,
+        config: SourceConfig,
+    ) -> Result<(SourceId, Playlist)> {
+        if segments.is_empty() {
+            return Err(crate::error::PetalSonicError::Configuration(
+                "Playlist has no segments".to_string(),
+            ));
+        }
+        let mut resampled = Vec::with_capacity(segments.len());
+        for (index, segment) in segments.into_iter().enumerate() {
+            if segment.audio_data.total_frames() == 0 || segment.play_count == Some(0) {
+                return Err(crate::error::PetalSonicError::Configuration(format!(
+                    "Playlist segment {} has no audio or a play count of 0",
+                    index
+                )));
+            }
+            let audio_data = if segment.audio_data.sample_rate() != self.desc.sample_rate {
+                resample_shared(
+                    &segment.audio_data,
+                    self.desc.sample_rate,
+                    self.desc.asset_resample_quality,
+                )?
+            } else {
+                segment.audio_data
+            };
+            resampled.push(PlaylistSegment {
+                audio_data,
+                ..segment
+            });
+        }
+
+        let id = self.register_audio(resampled[0].audio_data.clone(), config)?;
+        let source = PlaylistSource::new(resampled);
+        let playlist = source.handle();
+        self.playlists.lock().unwrap().insert(id, source);
+        Ok((id, playlist))
+    }
+
+    /// Returns the playlist registered under `id`, if any
+    pub(crate) fn playlist(&self, id: SourceId) -> Option<PlaylistSource> {
+        self.playlists.lock().unwrap().get(&id).cloned()
+    }
+
     /// Retrieves audio data by its SourceId.
     ///
     /// # Arguments
@@ -120,6 +584,22 @@ impl PetalSonicWorld {
     /// The removed audio data if it existed, `None` otherwise
     pub fn remove_audio_data(&self, id: SourceId) -> Option<Arc<PetalSonicAudioData>> {
         self.source_configs.lock().unwrap().remove(&id);
+        self.soloed_sources.lock().unwrap().remove(&id);
+        self.spatial_bypass.lock().unwrap().remove(&id);
+        self.reversed_sources.lock().unwrap().remove(&id);
+        self.output_routing.lock().unwrap().remove(&id);
+        self.source_buses.lock().unwrap().remove(&id);
+        self.source_delays.lock().unwrap().remove(&id);
+        self.live_sources.lock().unwrap().remove(&id);
+        self.playlists.lock().unwrap().remove(&id);
+        self.envelopes.lock().unwrap().remove(&id);
+        self.memory.lock().unwrap().remove(id);
+        self.source_tags.lock().unwrap().remove(&id);
+        self.captions.lock().unwrap().remove(&id);
+        self.haptics_tracks.lock().unwrap().remove(&id);
+        for group in self.sync_groups.lock().unwrap().values_mut() {
+            group.members.retain(|member| *member != id);
+        }
         self.audio_data_storage.lock().unwrap().remove(&id)
     }
 
@@ -137,6 +617,15 @@ impl PetalSonicWorld {
         self.audio_data_storage.lock().unwrap().contains_key(&id)
     }
 
+    /// Returns an error if no audio data is stored for `id`
+    fn ensure_source(&self, id: SourceId) -> Result<()> {
+        if self.contains_audio(id) {
+            Ok(())
+        } else {
+            Err(source_not_found(id))
+        }
+    }
+
     /// Sets the listener pose (position and orientation) for spatial audio.
     ///
     /// The listener represents the position and orientation of the "ears" in the 3D world.
@@ -144,20 +633,48 @@ impl PetalSonicWorld {
     ///
     /// # Arguments
     ///
-    /// * `pose` - The new pose for the listener
+    /// * `pose` - The new pose for the listener, in the world's coordinate convention
     pub fn set_listener_pose(&self, pose: Pose) {
-        self.listener.lock().unwrap().pose = pose;
+        self.listener.lock().unwrap().pose = self.desc.coordinate_convention.pose_to_native(pose);
     }
 
-    /// Returns a copy of the current listener.
+    /// Returns a copy of the current listener, in the world's coordinate convention.
     pub fn listener(&self) -> PetalSonicAudioListener {
-        self.listener.lock().unwrap().clone()
+        PetalSonicAudioListener::new(
+            self.desc
+                .coordinate_convention
+                .pose_from_native(self.native_listener_pose()),
+        )
+    }
+
+    /// Listener pose in PetalSonic's native convention (used by the render thread)
+    pub(crate) fn native_listener_pose(&self) -> Pose {
+        self.listener.lock().unwrap().pose()
+    }
+
+    /// Converts the position of a spatial source from the world's coordinate convention
+    fn config_to_native(&self, config: SourceConfig) -> SourceConfig {
+        match config {
+            SourceConfig::Spatial {
+                position,
+                volume,
+                reverb_send,
+            } => SourceConfig::Spatial {
+                position: self.desc.coordinate_convention.vec_to_native(position),
+                volume,
+                reverb_send,
+            },
+            SourceConfig::NonSpatial => SourceConfig::NonSpatial,
+        }
     }
 
     /// Updates the configuration for a source (e.g., position, volume).
     ///
     /// This is useful for dynamically changing spatial audio properties without
-    /// stopping and restarting playback.
+    /// stopping and restarting playback. A source can also switch between spatial and
+    /// non-spatial (e.g. a radio picked up by the player): the switch is crossfaded over
+    /// one block, and the spatializer's per-source effects are released once the source
+    /// leaves the spatial path.
     ///
     /// # Arguments
     ///
@@ -169,29 +686,14 @@ impl PetalSonicWorld {
     /// Returns an error if the audio source ID is not found or if the command
     /// fails to send to the audio engine.
     pub fn update_source_config(&self, audio_id: SourceId, config: SourceConfig) -> Result<()> {
-        if !self.contains_audio(audio_id) {
-            return Err(crate::error::PetalSonicError::Engine(format!(
-                "Audio data with ID {:?} not found",
-                audio_id
-            )));
-        }
-
-        // Update the config in storage
-        self.source_configs
-            .lock()
-            .unwrap()
-            .insert(audio_id, config.clone());
-
-        // Send command to update active playback instance if it exists
-        self.command_sender
-            .send(PlaybackCommand::UpdateConfig(audio_id, config))
-            .map_err(|e| {
-                crate::error::PetalSonicError::Engine(format!(
-                    "Failed to send update config command: {}",
-                    e
-                ))
-            })?;
+        self.ensure_source(audio_id)?;
 
+        // Update the active playback instance if it exists, then the config in storage
+        let config = self.config_to_native(config);
+        let mut source_configs = self.source_configs.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::UpdateConfig(audio_id, config.clone()))?;
+        source_configs.insert(audio_id, config);
         Ok(())
     }
 
@@ -209,32 +711,331 @@ impl PetalSonicWorld {
     ///
     /// Returns an error if the audio source ID is not found in the world storage
     /// or if the command fails to send to the audio engine.
+    ///
+    /// A trigger beyond the [rate limit](Self::set_rate_limit) of the source's tag is
+    /// dropped or merged without an error.
     pub fn play(&self, audio_id: SourceId, loop_mode: LoopMode) -> Result<()> {
-        if !self.contains_audio(audio_id) {
-            return Err(crate::error::PetalSonicError::Engine(format!(
-                "Audio data with ID {:?} not found",
-                audio_id
+        self.ensure_source(audio_id)?;
+        let Some(audio_id) = self.admit_trigger(audio_id) else {
+            return Ok(());
+        };
+
+        self.resample_on_first_play(audio_id)?;
+        self.send_play(
+            audio_id,
+            PlaybackCommand::Play(audio_id, self.source_config(audio_id), loop_mode),
+            None,
+        )
+    }
+
+    /// Starts playing an audio source partway into its clip.
+    ///
+    /// Like [`Self::play`], but playback (re)starts `offset` into the clip instead of at its
+    /// beginning, e.g. to skip a pre-roll or to resume music where it left off before a
+    /// level load. Loop iterations after the first start at the beginning of the clip (or
+    /// of its loop region). The offset is ignored for live sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source to play
+    /// * `offset` - Position in the clip to start at
+    /// * `loop_mode` - How the audio should loop (Once, Infinite, or Count(n))
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found, `offset` is beyond the end
+    /// of the clip, or the command fails to send to the audio engine. Rate limits apply as
+    /// for [`Self::play`]; a merged trigger restarts the other source at `offset`.
+    pub fn play_from(
+        &self,
+        audio_id: SourceId,
+        offset: Duration,
+        loop_mode: LoopMode,
+    ) -> Result<()> {
+        self.ensure_source(audio_id)?;
+        let Some(audio_id) = self.admit_trigger(audio_id) else {
+            return Ok(());
+        };
+
+        self.resample_on_first_play(audio_id)?;
+        let audio_data = self
+            .get_audio_data(audio_id)
+            .ok_or_else(|| source_not_found(audio_id))?;
+
+        // Frames at the world's sample rate, which differs from the data's while streaming
+        let sample_rate = self.desc.sample_rate as f64;
+        let start_frame = (offset.as_secs_f64() * sample_rate).round() as usize;
+        let total_frames = (audio_data.total_frames() as f64 * sample_rate
+            / audio_data.sample_rate() as f64) as usize;
+        if start_frame > total_frames {
+            return Err(crate::error::PetalSonicError::Configuration(format!(
+                "Start offset {:?} is beyond the end of source {} ({:?})",
+                offset,
+                audio_id,
+                audio_data.duration()
             )));
         }
 
-        // Get the source config for this audio source
-        let config = self
-            .source_configs
+        self.send_play(
+            audio_id,
+            PlaybackCommand::PlayFrom(
+                audio_id,
+                self.source_config(audio_id),
+                loop_mode,
+                start_frame,
+            ),
+            None,
+        )
+    }
+
+    /// Plays an audio source for at most `duration`, ending playback at that exact sample.
+    ///
+    /// Like [`Self::play`], but playback ends (with a short fade, emitting
+    /// `SourceCompleted`) once `duration` of audio has played, even partway through a
+    /// loop, e.g. to cap an ability sound to the ability's duration. Time spent paused does
+    /// not count. A clip shorter than `duration` ends as it would with [`Self::play`]. The
+    /// limit is ignored for live sources.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found or the command fails to send
+    /// to the audio engine. Rate limits apply as for [`Self::play`]; a merged trigger
+    /// limits the other source.
+    pub fn play_for(
+        &self,
+        audio_id: SourceId,
+        duration: Duration,
+        loop_mode: LoopMode,
+    ) -> Result<()> {
+        self.ensure_source(audio_id)?;
+        let Some(audio_id) = self.admit_trigger(audio_id) else {
+            return Ok(());
+        };
+
+        self.resample_on_first_play(audio_id)?;
+        // Frames at the world's sample rate, counted from the first block of the playback
+        let frames = (duration.as_secs_f64() * self.desc.sample_rate as f64).round() as usize;
+        self.send_play(
+            audio_id,
+            PlaybackCommand::Play(audio_id, self.source_config(audio_id), loop_mode),
+            Some(frames),
+        )
+    }
+
+    /// Plays one of `candidates`, picked at random (a random container, e.g. for
+    /// footstep or impact variations). Returns the source that was played.
+    ///
+    /// The pick draws from the world's random number generator, so it is reproducible
+    /// with [`PetalSonicWorldDesc::random_seed`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `candidates` is empty, the picked source is not found or the
+    /// command fails to send to the audio engine.
+    pub fn play_random(&self, candidates: &[SourceId], loop_mode: LoopMode) -> Result<SourceId> {
+        if candidates.is_empty() {
+            return Err(crate::error::PetalSonicError::Configuration(
+                "Random container has no candidates".to_string(),
+            ));
+        }
+
+        let audio_id = candidates[self.rng.lock().unwrap().index(candidates.len())];
+        self.play(audio_id, loop_mode)?;
+        Ok(audio_id)
+    }
+
+    /// Returns the seed the world's random number generator was last seeded with.
+    ///
+    /// When [`PetalSonicWorldDesc::random_seed`] is `None` this is the clock-derived seed,
+    /// which can be logged to reproduce a session.
+    pub fn random_seed(&self) -> u64 {
+        self.random_seed.load(Ordering::Relaxed)
+    }
+
+    /// Restarts the world's random number generator from `seed`, e.g. at the start of a
+    /// replay.
+    ///
+    /// Generators handed out earlier (samplers, [`Self::fork_rng`]) keep their sequence.
+    pub fn reseed_random(&self, seed: u64) {
+        *self.rng.lock().unwrap() = AudioRng::new(seed);
+        self.random_seed.store(seed, Ordering::Relaxed);
+    }
+
+    /// Returns an independent random number generator derived from the world's sequence.
+    ///
+    /// Use it for variation in host systems (e.g. scattering ambience emitters) so they are
+    /// reproducible under the world's seed. See [`crate::random`].
+    pub fn fork_rng(&self) -> AudioRng {
+        self.rng.lock().unwrap().fork()
+    }
+
+    /// Sets the caption emitted when a source starts playing (see [`crate::caption`]);
+    /// `None` removes it.
+    ///
+    /// The caption applies from the next `play` of the source; a playback in progress
+    /// keeps the caption it started with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage.
+    pub fn set_caption(&self, audio_id: SourceId, caption: Option<Caption>) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut captions = self.captions.lock().unwrap();
+        match caption {
+            Some(caption) => captions.insert(audio_id, Arc::new(caption)),
+            None => captions.remove(&audio_id),
+        };
+        Ok(())
+    }
+
+    /// Returns the caption of a source, if any.
+    pub fn caption(&self, audio_id: SourceId) -> Option<Caption> {
+        self.captions
             .lock()
             .unwrap()
             .get(&audio_id)
-            .cloned()
-            .unwrap_or_default();
+            .map(|caption| caption.as_ref().clone())
+    }
+
+    /// Sets the caption of a source and plays it, see [`Self::set_caption`] and
+    /// [`Self::play`].
+    ///
+    /// The caption stays attached to the source for later plays.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage or the
+    /// command fails to send to the audio engine.
+    pub fn play_with_caption(
+        &self,
+        audio_id: SourceId,
+        loop_mode: LoopMode,
+        caption: Caption,
+    ) -> Result<()> {
+        self.set_caption(audio_id, Some(caption))?;
+        self.play(audio_id, loop_mode)
+    }
+
+    /// Sets the haptics track played along with a source (see [`crate::haptics`]); `None`
+    /// removes it.
+    ///
+    /// The track is converted to the world's sample rate and applies from the next `play`
+    /// of the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage or the
+    /// track cannot be resampled.
+    pub fn set_haptics_track(&self, audio_id: SourceId, track: Option<HapticsTrack>) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        match track {
+            Some(track) => {
+                let track = track.resample(self.desc.sample_rate)?;
+                self.haptics_tracks.lock().unwrap().insert(audio_id, track);
+            }
+            None => {
+                self.haptics_tracks.lock().unwrap().remove(&audio_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if a source has a haptics track.
+    pub fn has_haptics_track(&self, audio_id: SourceId) -> bool {
+        self.haptics_tracks.lock().unwrap().contains_key(&audio_id)
+    }
 
-        self.command_sender
-            .send(PlaybackCommand::Play(audio_id, config, loop_mode))
-            .map_err(|e| {
-                crate::error::PetalSonicError::Engine(format!("Failed to send play command: {}", e))
-            })?;
+    /// Tags a source for rate limiting (see [`crate::rate_limit`]); `None` removes its
+    /// tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage.
+    pub fn set_source_tag(&self, audio_id: SourceId, tag: Option<&str>) -> Result<()> {
+        self.ensure_source(audio_id)?;
 
+        let mut source_tags = self.source_tags.lock().unwrap();
+        match tag {
+            Some(tag) => source_tags.insert(audio_id, tag.to_string()),
+            None => source_tags.remove(&audio_id),
+        };
         Ok(())
     }
 
+    /// Returns the rate limiting tag of a source, if any.
+    pub fn source_tag(&self, audio_id: SourceId) -> Option<String> {
+        self.source_tags.lock().unwrap().get(&audio_id).cloned()
+    }
+
+    /// Limits how often sources tagged `tag` can be triggered; `None` removes the limit.
+    ///
+    /// Changing the limit of a tag keeps its trigger history and counters.
+    pub fn set_rate_limit(&self, tag: &str, limit: Option<RateLimit>) {
+        self.rate_limiter.lock().unwrap().set_limit(tag, limit);
+    }
+
+    /// Returns the rate limit of a tag, if any.
+    pub fn rate_limit(&self, tag: &str) -> Option<RateLimit> {
+        self.rate_limiter.lock().unwrap().limit(tag)
+    }
+
+    /// Returns the trigger counters of every rate limited tag, for tuning the limits.
+    pub fn rate_limit_stats(&self) -> HashMap<String, RateLimitStats> {
+        self.rate_limiter.lock().unwrap().stats()
+    }
+
+    /// Resets the trigger counters of all rate limited tags.
+    pub fn reset_rate_limit_stats(&self) {
+        self.rate_limiter.lock().unwrap().reset_stats();
+    }
+
+    /// Check a trigger against the rate limit of the source's tag. Returns the source to
+    /// play, or `None` if the trigger is dropped.
+    fn admit_trigger(&self, audio_id: SourceId) -> Option<SourceId> {
+        let source_tags = self.source_tags.lock().unwrap();
+        let Some(tag) = source_tags.get(&audio_id) else {
+            return Some(audio_id);
+        };
+
+        match self
+            .rate_limiter
+            .lock()
+            .unwrap()
+            .admit(tag, audio_id, Instant::now())
+        {
+            Admission::Play => Some(audio_id),
+            Admission::Merge(merged_into) if self.contains_audio(merged_into) => {
+                log::debug!(
+                    "Trigger of {} merged into {} ({})",
+                    audio_id,
+                    merged_into,
+                    tag
+                );
+                Some(merged_into)
+            }
+            Admission::Merge(_) | Admission::Drop => {
+                log::debug!(
+                    "Trigger of {} dropped by the rate limit of {}",
+                    audio_id,
+                    tag
+                );
+                None
+            }
+        }
+    }
+
+    /// Configuration a source is played with
+    fn source_config(&self, audio_id: SourceId) -> SourceConfig {
+        self.source_configs
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Pauses a playing audio source by its SourceId.
     ///
     /// Sends a pause command to the audio engine thread. The audio will stop playing
@@ -248,16 +1049,7 @@ impl PetalSonicWorld {
     ///
     /// Returns an error if the command fails to send to the audio engine.
     pub fn pause(&self, audio_id: SourceId) -> Result<()> {
-        self.command_sender
-            .send(PlaybackCommand::Pause(audio_id))
-            .map_err(|e| {
-                crate::error::PetalSonicError::Engine(format!(
-                    "Failed to send pause command: {}",
-                    e
-                ))
-            })?;
-
-        Ok(())
+        self.commands.send(PlaybackCommand::Pause(audio_id))
     }
 
     /// Stops a playing audio source by its SourceId.
@@ -273,13 +1065,30 @@ impl PetalSonicWorld {
     ///
     /// Returns an error if the command fails to send to the audio engine.
     pub fn stop(&self, audio_id: SourceId) -> Result<()> {
-        self.command_sender
-            .send(PlaybackCommand::Stop(audio_id))
-            .map_err(|e| {
-                crate::error::PetalSonicError::Engine(format!("Failed to send stop command: {}", e))
-            })?;
+        self.commands.send(PlaybackCommand::Stop(audio_id))
+    }
 
-        Ok(())
+    /// Ends the playback of an audio source at an exact output frame.
+    ///
+    /// `output_frame` counts on the clock of
+    /// [`PetalSonicEngine::frames_processed`](crate::PetalSonicEngine::frames_processed):
+    /// playback ends (with a short fade, emitting `SourceCompleted`) on the sample heard at
+    /// that frame, e.g. to cut a stinger on a beat. A frame already passed ends playback
+    /// with the next rendered block. The stop replaces an earlier `stop_at` or
+    /// [`Self::play_for`] limit and is cleared when the source is played again; it is
+    /// ignored if the source is not playing and for live sources.
+    ///
+    /// ```ignore
+    /// let beat = engine.frames_processed() + frames_per_beat;
+    /// world.stop_at(stinger, beat - beat % frames_per_beat)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to send to the audio engine.
+    pub fn stop_at(&self, audio_id: SourceId, output_frame: usize) -> Result<()> {
+        self.commands
+            .send(PlaybackCommand::StopAt(audio_id, output_frame))
     }
 
     /// Stops all currently playing audio sources.
@@ -291,30 +1100,872 @@ impl PetalSonicWorld {
     ///
     /// Returns an error if the command fails to send to the audio engine.
     pub fn stop_all(&self) -> Result<()> {
-        self.command_sender
-            .send(PlaybackCommand::StopAll)
-            .map_err(|e| {
-                crate::error::PetalSonicError::Engine(format!(
-                    "Failed to send stop all command: {}",
-                    e
-                ))
-            })?;
-
-        Ok(())
+        self.commands.send(PlaybackCommand::StopAll)
     }
 
-    /// Returns a reference to the command receiver for the audio engine.
+    /// Creates a sync group of sources played sample-locked, see [`crate::sync_group`].
+    ///
+    /// # Errors
     ///
-    /// This receiver is used by the audio engine thread to poll for playback commands
-    /// sent from the main thread. This method is primarily used internally when
-    /// initializing the audio engine.
+    /// Returns an error if `members` is empty, or a member is not found, is a live source
+    /// or already belongs to a sync group.
+    pub fn create_sync_group(&self, members: &[SourceId]) -> Result<SyncGroupId> {
+        if members.is_empty() {
+            return Err(crate::error::PetalSonicError::Configuration(
+                "A sync group needs at least one member".to_string(),
+            ));
+        }
+        let mut groups = self.sync_groups.lock().unwrap();
+        for &member in members {
+            self.ensure_source(member)?;
+            if self.live_source(member).is_some() {
+                return Err(crate::error::PetalSonicError::Configuration(format!(
+                    "Live source {} cannot join a sync group",
+                    member
+                )));
+            }
+            if groups.values().any(|group| group.members.contains(&member)) {
+                return Err(crate::error::PetalSonicError::Configuration(format!(
+                    "Source {} already belongs to a sync group",
+                    member
+                )));
+            }
+        }
+
+        let group_id = SyncGroupId(self.next_sync_group_id.fetch_add(1, Ordering::Relaxed));
+        groups.insert(group_id, SyncGroup::new(members.to_vec()));
+        Ok(group_id)
+    }
+
+    /// Removes a sync group. Its members keep playing at their own volume, no longer
+    /// locked to each other.
     ///
     /// # Returns
     ///
-    /// A reference to the `Receiver<PlaybackCommand>` channel
-    pub fn command_receiver(&self) -> &Receiver<PlaybackCommand> {
-        &self.command_receiver
-    }
+    /// The removed group if it existed, `None` otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to send to the audio engine.
+    pub fn remove_sync_group(&self, group_id: SyncGroupId) -> Result<Option<SyncGroup>> {
+        let mut groups = self.sync_groups.lock().unwrap();
+        let Some(group) = groups.get(&group_id) else {
+            return Ok(None);
+        };
+        self.commands
+            .send(PlaybackCommand::LeaveGroup(group.members.clone()))?;
+        Ok(groups.remove(&group_id))
+    }
+
+    /// Returns the members and volume of a sync group, `None` if it does not exist.
+    pub fn sync_group(&self, group_id: SyncGroupId) -> Option<SyncGroup> {
+        self.sync_groups.lock().unwrap().get(&group_id).cloned()
+    }
+
+    /// Returns the members of a sync group, or an error if it does not exist
+    fn sync_group_members(&self, group_id: SyncGroupId) -> Result<Vec<SourceId>> {
+        self.sync_groups
+            .lock()
+            .unwrap()
+            .get(&group_id)
+            .map(|group| group.members.clone())
+            .ok_or_else(|| {
+                crate::error::PetalSonicError::Engine(format!(
+                    "Sync group {:?} not found",
+                    group_id
+                ))
+            })
+    }
+
+    /// Volume of the sync group a source belongs to, 1.0 if it belongs to none
+    pub(crate) fn sync_group_gain(&self, audio_id: SourceId) -> f32 {
+        self.sync_groups
+            .lock()
+            .unwrap()
+            .values()
+            .find(|group| group.members.contains(&audio_id))
+            .map_or(1.0, |group| group.volume)
+    }
+
+    /// Frame at the world's sample rate `position` into a group's clips, or an error if
+    /// that is beyond the end of all of them
+    fn sync_group_frame(&self, members: &[SourceId], position: Duration) -> Result<usize> {
+        let longest = members
+            .iter()
+            .filter_map(|&member| self.get_audio_data(member))
+            .map(|audio_data| audio_data.duration())
+            .max()
+            .unwrap_or_default();
+        if position > longest {
+            return Err(crate::error::PetalSonicError::Configuration(format!(
+                "Position {:?} is beyond the end of the sync group ({:?})",
+                position, longest
+            )));
+        }
+        Ok((position.as_secs_f64() * self.desc.sample_rate as f64).round() as usize)
+    }
+
+    /// Starts all members of a sync group from the beginning, in the same block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist, converting a member's sample rate
+    /// fails, or the command fails to send to the audio engine. Rate limits do not apply
+    /// to sync groups.
+    pub fn play_group(&self, group_id: SyncGroupId, loop_mode: LoopMode) -> Result<()> {
+        self.play_group_from(group_id, Duration::ZERO, loop_mode)
+    }
+
+    /// Starts all members of a sync group `offset` into their clips, in the same block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist, `offset` is beyond the end of every
+    /// member, converting a member's sample rate fails, or the command fails to send to
+    /// the audio engine.
+    pub fn play_group_from(
+        &self,
+        group_id: SyncGroupId,
+        offset: Duration,
+        loop_mode: LoopMode,
+    ) -> Result<()> {
+        let members = self.sync_group_members(group_id)?;
+        let start_frame = self.sync_group_frame(&members, offset)?;
+        let mut sources = Vec::with_capacity(members.len());
+        for &member in &members {
+            self.resample_on_first_play(member)?;
+            sources.push((member, self.source_config(member)));
+        }
+
+        // Soloed members are re-soloed in the same batch as the group's play command
+        let soloed_sources = self.soloed_sources.lock().unwrap();
+        let mut commands = vec![PlaybackCommand::PlayGroup(sources, loop_mode, start_frame)];
+        commands.extend(
+            members
+                .iter()
+                .filter(|member| soloed_sources.contains(member))
+                .map(|&member| PlaybackCommand::SetSolo(member, true)),
+        );
+        self.commands.send_all(commands)?;
+        drop(soloed_sources);
+
+        let mut memory = self.memory.lock().unwrap();
+        for &member in &members {
+            memory.touch(member);
+        }
+        Ok(())
+    }
+
+    /// Pauses all members of a sync group in the same block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist or the command fails to send to the
+    /// audio engine.
+    pub fn pause_group(&self, group_id: SyncGroupId) -> Result<()> {
+        let members = self.sync_group_members(group_id)?;
+        self.commands.send(PlaybackCommand::PauseGroup(members))
+    }
+
+    /// Resumes the paused members of a sync group in the same block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist or the command fails to send to the
+    /// audio engine.
+    pub fn resume_group(&self, group_id: SyncGroupId) -> Result<()> {
+        let members = self.sync_group_members(group_id)?;
+        self.commands.send(PlaybackCommand::ResumeGroup(members))
+    }
+
+    /// Moves all members of a sync group to `position` in the same block. Playing
+    /// members keep playing and paused ones stay paused.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist, `position` is beyond the end of
+    /// every member, or the command fails to send to the audio engine.
+    pub fn seek_group(&self, group_id: SyncGroupId, position: Duration) -> Result<()> {
+        let members = self.sync_group_members(group_id)?;
+        let frame = self.sync_group_frame(&members, position)?;
+        self.commands
+            .send(PlaybackCommand::SeekGroup(members, frame))
+    }
+
+    /// Stops all members of a sync group in the same block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist or the command fails to send to the
+    /// audio engine.
+    pub fn stop_group(&self, group_id: SyncGroupId) -> Result<()> {
+        let members = self.sync_group_members(group_id)?;
+        self.commands.send(PlaybackCommand::StopGroup(members))
+    }
+
+    /// Sets the volume of a sync group, applied to every member on top of its own volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group does not exist, `volume` is negative or not finite,
+    /// or the command fails to send to the audio engine.
+    pub fn set_group_volume(&self, group_id: SyncGroupId, volume: f32) -> Result<()> {
+        if !volume.is_finite() || volume < 0.0 {
+            return Err(crate::error::PetalSonicError::Configuration(format!(
+                "Group volume must be finite and non-negative, got {}",
+                volume
+            )));
+        }
+        let mut groups = self.sync_groups.lock().unwrap();
+        let group = groups.get_mut(&group_id).ok_or_else(|| {
+            crate::error::PetalSonicError::Engine(format!("Sync group {:?} not found", group_id))
+        })?;
+        self.commands.send(PlaybackCommand::SetGroupVolume(
+            group.members.clone(),
+            volume,
+        ))?;
+        group.volume = volume;
+        Ok(())
+    }
+
+    /// Solos or unsolos an audio source by its SourceId.
+    ///
+    /// While at least one source is soloed, all other sources are muted in the mixer.
+    /// Muted sources keep advancing their playback cursors (and emitting loop/completion
+    /// events), so unsoloing brings them back in sync. Several sources can be soloed at
+    /// once. The solo state persists across `play()`/`stop()` until it is cleared or the
+    /// audio data is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source to solo
+    /// * `soloed` - `true` to solo the source, `false` to unsolo it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage
+    /// or if the command fails to send to the audio engine.
+    pub fn solo(&self, audio_id: SourceId, soloed: bool) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut soloed_sources = self.soloed_sources.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetSolo(audio_id, soloed))?;
+        if soloed {
+            soloed_sources.insert(audio_id);
+        } else {
+            soloed_sources.remove(&audio_id);
+        }
+        Ok(())
+    }
+
+    /// Returns true if the audio source is soloed.
+    pub fn is_soloed(&self, audio_id: SourceId) -> bool {
+        self.soloed_sources.lock().unwrap().contains(&audio_id)
+    }
+
+    /// Sends a play command for a source together with the state the new playback needs:
+    /// its solo state and, if given, a limit of `stop_after` frames.
+    ///
+    /// A new playback instance starts unsoloed, and the render thread does not look the
+    /// state up itself, so a soloed source is re-soloed right after its play command. The
+    /// commands are queued all or none, so a full queue never leaves a playback half set up.
+    fn send_play(
+        &self,
+        audio_id: SourceId,
+        play: PlaybackCommand,
+        stop_after: Option<usize>,
+    ) -> Result<()> {
+        let soloed_sources = self.soloed_sources.lock().unwrap();
+        let mut commands = vec![play];
+        if soloed_sources.contains(&audio_id) {
+            commands.push(PlaybackCommand::SetSolo(audio_id, true));
+        }
+        if let Some(frames) = stop_after {
+            commands.push(PlaybackCommand::StopAfter(audio_id, frames));
+        }
+        self.commands.send_all(commands)?;
+        drop(soloed_sources);
+
+        self.memory.lock().unwrap().touch(audio_id);
+        Ok(())
+    }
+
+    /// Bypasses spatial pipeline stages for a single source.
+    ///
+    /// Bypassed stages pass the signal through unprocessed, which helps to find out which
+    /// stage makes a source sound wrong. Use [`SpatialBypass::ALL`] to hear the dry signal
+    /// and [`SpatialBypass::NONE`] to restore normal processing. Per-source toggles are
+    /// combined with the global ones set on the engine. Has no effect on non-spatial sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source
+    /// * `bypass` - Stages to bypass
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage
+    /// or if the command fails to send to the audio engine.
+    pub fn set_spatial_bypass(&self, audio_id: SourceId, bypass: SpatialBypass) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut spatial_bypass = self.spatial_bypass.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetSpatialBypass(audio_id, bypass))?;
+        if bypass.is_none() {
+            spatial_bypass.remove(&audio_id);
+        } else {
+            spatial_bypass.insert(audio_id, bypass);
+        }
+        Ok(())
+    }
+
+    /// Returns the spatial pipeline stages bypassed for a source.
+    pub fn spatial_bypass(&self, audio_id: SourceId) -> SpatialBypass {
+        self.spatial_bypass
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the direction a source's clip is played in.
+    ///
+    /// Takes effect immediately if the source is playing, continuing from the current
+    /// position, and applies to later [`Self::play`] calls, which start a reversed source
+    /// at the end of its clip. [`Self::play_from`] offsets are still measured from the
+    /// beginning of the clip. Has no effect on live sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source
+    /// * `direction` - Forward or reverse playback
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage
+    /// or if the command fails to send to the audio engine.
+    pub fn set_playback_direction(
+        &self,
+        audio_id: SourceId,
+        direction: PlaybackDirection,
+    ) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut reversed_sources = self.reversed_sources.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetDirection(audio_id, direction))?;
+        match direction {
+            PlaybackDirection::Forward => reversed_sources.remove(&audio_id),
+            PlaybackDirection::Reverse => reversed_sources.insert(audio_id),
+        };
+        Ok(())
+    }
+
+    /// Returns the direction a source's clip is played in.
+    pub fn playback_direction(&self, audio_id: SourceId) -> PlaybackDirection {
+        if self.reversed_sources.lock().unwrap().contains(&audio_id) {
+            PlaybackDirection::Reverse
+        } else {
+            PlaybackDirection::Forward
+        }
+    }
+
+    /// Routes a source to specific output channels.
+    ///
+    /// A routed source is played only on the given channels and is never spatialized, even
+    /// if it is configured as spatial, e.g. to send a rumble track to the LFE channel or a
+    /// haptics track to a dedicated channel of a controller. Takes effect immediately if
+    /// the source is playing. See [`OutputRouting`].
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source
+    /// * `routing` - Output channels to play the source on
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found, the routing lists no channel
+    /// or a channel the world does not have, or the command fails to send to the audio
+    /// engine.
+    pub fn set_output_routing(&self, audio_id: SourceId, routing: OutputRouting) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        if let OutputRouting::Channels(channels) = &routing {
+            if channels.is_empty() {
+                return Err(crate::error::PetalSonicError::Configuration(
+                    "Output routing must list at least one channel".to_string(),
+                ));
+            }
+            if let Some(channel) = channels
+                .iter()
+                .find(|&&channel| channel >= self.desc.channels)
+            {
+                return Err(crate::error::PetalSonicError::Configuration(format!(
+                    "Output channel {} is out of range for {} world channels",
+                    channel, self.desc.channels
+                )));
+            }
+        }
+
+        let mut output_routing = self.output_routing.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetOutputRouting(audio_id, routing.clone()))?;
+        match routing {
+            OutputRouting::AllChannels => output_routing.remove(&audio_id),
+            OutputRouting::Channels(_) => output_routing.insert(audio_id, routing),
+        };
+        Ok(())
+    }
+
+    /// Returns the output channels a source is played on.
+    pub fn output_routing(&self, audio_id: SourceId) -> OutputRouting {
+        self.output_routing
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Assigns a source to an output bus; `None` returns it to the main mix.
+    ///
+    /// Buses are named groups of sources that can be routed to a secondary output device
+    /// with [`PetalSonicEngine::open_secondary_output`](crate::PetalSonicEngine::open_secondary_output),
+    /// e.g. voice chat to a headset while the game plays on the speakers. Sources on a bus
+    /// no secondary output plays are mixed into the main output as usual. Takes effect
+    /// immediately if the source is playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found or the command fails to send to
+    /// the audio engine.
+    pub fn set_source_bus(&self, audio_id: SourceId, bus: Option<&str>) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut source_buses = self.source_buses.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetBus(audio_id, bus.map(str::to_string)))?;
+        match bus {
+            Some(bus) => source_buses.insert(audio_id, bus.to_string()),
+            None => source_buses.remove(&audio_id),
+        };
+        Ok(())
+    }
+
+    /// Returns the output bus of a source, if it is assigned to one.
+    pub fn source_bus(&self, audio_id: SourceId) -> Option<String> {
+        self.source_buses.lock().unwrap().get(&audio_id).cloned()
+    }
+
+    /// Sets the bass and treble of the sources on a bus; `None` sets the tone of the
+    /// sources on no bus (see [`crate::bus_tone`]). Takes effect immediately for playing
+    /// sources, and follows sources moved onto the bus.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a gain of `tone` is out of range or the command fails to send
+    /// to the audio engine.
+    pub fn set_bus_tone(&self, bus: Option<&str>, tone: BusTone) -> Result<()> {
+        tone.validate()?;
+        let bus = bus.map(str::to_string);
+        let mut bus_tones = self.bus_tones.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetBusTone(bus.clone(), tone))?;
+        if tone.is_flat() {
+            bus_tones.remove(&bus);
+        } else {
+            bus_tones.insert(bus, tone);
+        }
+        Ok(())
+    }
+
+    /// Returns the tone of a bus (`None` for the main mix), flat if it has none.
+    pub fn bus_tone(&self, bus: Option<&str>) -> BusTone {
+        self.bus_tones
+            .lock()
+            .unwrap()
+            .get(&bus.map(str::to_string))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Creates the render-side tone filter of a source on `bus`, if the bus has a tone
+    pub(crate) fn tone_filter(&self, bus: Option<&str>) -> Option<ToneFilter> {
+        let tone = self.bus_tone(bus);
+        (!tone.is_flat()).then(|| ToneFilter::new(&tone, self.desc.sample_rate))
+    }
+
+    /// Delays a source by `delay` before it is mixed, e.g. to align it with video (see
+    /// [`crate::source_delay`]). The delay is rounded to frames at the world's sample rate.
+    /// Takes effect immediately if the source is playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found, `delay` exceeds
+    /// `PetalSonicWorldDesc::max_source_delay`, or the command fails to send to the audio
+    /// engine.
+    pub fn set_source_delay(&self, audio_id: SourceId, delay: Duration) -> Result<()> {
+        let frames = (delay.as_secs_f64() * self.desc.sample_rate as f64).round() as usize;
+        self.set_source_delay_frames(audio_id, frames)
+    }
+
+    /// Delays a source by `frames` frames at the world's sample rate before it is mixed.
+    /// See [`Self::set_source_delay`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found, the delay exceeds
+    /// `PetalSonicWorldDesc::max_source_delay`, or the command fails to send to the audio
+    /// engine.
+    pub fn set_source_delay_frames(&self, audio_id: SourceId, frames: usize) -> Result<()> {
+        self.ensure_source(audio_id)?;
+        if frames > self.max_source_delay_frames() {
+            return Err(crate::error::PetalSonicError::Configuration(format!(
+                "Source delay of {} frames exceeds max_source_delay ({:?})",
+                frames, self.desc.max_source_delay
+            )));
+        }
+
+        let mut source_delays = self.source_delays.lock().unwrap();
+        self.commands
+            .send(PlaybackCommand::SetSourceDelay(audio_id, frames))?;
+        if frames == 0 {
+            source_delays.remove(&audio_id);
+        } else {
+            source_delays.insert(audio_id, frames);
+        }
+        Ok(())
+    }
+
+    /// Returns the alignment delay of a source, zero if it has none.
+    pub fn source_delay(&self, audio_id: SourceId) -> Duration {
+        let frames = self.source_delay_frames(audio_id);
+        Duration::from_secs_f64(frames as f64 / self.desc.sample_rate as f64)
+    }
+
+    /// Returns the alignment delay of a source in frames at the world's sample rate.
+    pub fn source_delay_frames(&self, audio_id: SourceId) -> usize {
+        self.source_delays
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn max_source_delay_frames(&self) -> usize {
+        (self.desc.max_source_delay.as_secs_f64() * self.desc.sample_rate as f64) as usize
+    }
+
+    /// Adds an attenuation zone (see [`crate::zones`]).
+    ///
+    /// The zone applies from the next rendered block. Its shape is given in the world's
+    /// coordinate convention.
+    pub fn add_attenuation_zone(&self, zone: AttenuationZone) -> ZoneId {
+        let zone_id = ZoneId(self.next_zone_id.fetch_add(1, Ordering::Relaxed));
+        self.zones
+            .lock()
+            .unwrap()
+            .insert(zone_id, self.zone_to_native(zone));
+        zone_id
+    }
+
+    /// Replaces an attenuation zone, e.g. to move it or change its effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the zone ID is not found.
+    pub fn update_attenuation_zone(&self, zone_id: ZoneId, zone: AttenuationZone) -> Result<()> {
+        match self.zones.lock().unwrap().get_mut(&zone_id) {
+            Some(existing) => {
+                *existing = self.zone_to_native(zone);
+                Ok(())
+            }
+            None => Err(crate::error::PetalSonicError::Configuration(format!(
+                "Attenuation zone {:?} not found",
+                zone_id
+            ))),
+        }
+    }
+
+    /// Removes an attenuation zone. Returns the zone if it existed.
+    pub fn remove_attenuation_zone(&self, zone_id: ZoneId) -> Option<AttenuationZone> {
+        let mut zone = self.zones.lock().unwrap().remove(&zone_id)?;
+        zone.shape = zone
+            .shape
+            .converted_from_native(self.desc.coordinate_convention);
+        Some(zone)
+    }
+
+    fn zone_to_native(&self, mut zone: AttenuationZone) -> AttenuationZone {
+        zone.shape = zone
+            .shape
+            .converted_to_native(self.desc.coordinate_convention);
+        zone
+    }
+
+    /// Returns the attenuation zones, unless they are being modified (used by the render
+    /// thread, which must not block)
+    pub(crate) fn try_zones(
+        &self,
+    ) -> Option<std::sync::MutexGuard<'_, HashMap<ZoneId, AttenuationZone>>> {
+        self.zones.try_lock().ok()
+    }
+
+    /// Returns the propagation from a spatial source to the listener computed by the
+    /// engine in the last rendered block.
+    ///
+    /// Returns `None` if the source is not playing, is non-spatial or routed to output
+    /// channels, or no engine renders the world. See [`crate::spatial_info`].
+    pub fn spatial_info(&self, audio_id: SourceId) -> Option<SpatialInfo> {
+        let mut info = *self.spatial_info.lock().unwrap().get(&audio_id)?;
+        info.direction = self
+            .desc
+            .coordinate_convention
+            .vec_from_native(info.direction);
+        Some(info)
+    }
+
+    /// Estimates how audible a sound emitted at `from` is at `to`, from 0 (inaudible) to 1.
+    ///
+    /// Uses the propagation model of the renderer: the inverse-distance attenuation and the
+    /// air absorption (averaged over its bands) that an unsimulated spatial source gets.
+    /// For a playing source, [`Self::spatial_info`] has the values actually rendered.
+    ///
+    /// # Limitations
+    ///
+    /// There is no occlusion term: the engine has no scene geometry yet, so walls between
+    /// `from` and `to` do not lower the estimate, which depends on the distance only.
+    ///
+    /// ```ignore
+    /// // Stealth AI: does the guard hear the player's footsteps?
+    /// let heard = world.audibility(player_position, guard_position) * footstep_loudness;
+    /// if heard > guard.hearing_threshold { guard.investigate(player_position); }
+    /// ```
+    pub fn audibility(&self, from: Vec3, to: Vec3) -> f32 {
+        // The conventions only permute and flip axes, so distances need no conversion
+        let distance = from.distance(to);
+        let air_absorption = spatial::air_absorption(distance);
+        let absorption = air_absorption.iter().sum::<f32>() / air_absorption.len() as f32;
+        spatial::distance_attenuation(distance) * absorption
+    }
+
+    /// Returns the published propagation results, unless they are being read (used by the
+    /// render thread, which must not block)
+    pub(crate) fn try_spatial_info(
+        &self,
+    ) -> Option<std::sync::MutexGuard<'_, HashMap<SourceId, SpatialInfo>>> {
+        self.spatial_info.try_lock().ok()
+    }
+
+    /// Returns the published set of playing sources, unless it is being read (used by the
+    /// render thread, which must not block)
+    pub(crate) fn try_playing_sources(
+        &self,
+    ) -> Option<std::sync::MutexGuard<'_, HashSet<SourceId>>> {
+        self.playing_sources.try_lock().ok()
+    }
+
+    /// Sets the reverb to an environment preset, fading over one second.
+    ///
+    /// See [`crate::reverb`] and [`Self::set_environment_with_transition`].
+    pub fn set_environment(&self, environment: Environment) {
+        self.set_environment_with_transition(environment, DEFAULT_ENVIRONMENT_TRANSITION);
+    }
+
+    /// Sets the reverb to an environment preset, fading from the current reverb settings
+    /// over `transition`.
+    ///
+    /// Replaces the reverb settings of [`PetalSonicWorldDesc::reverb`]; a world without
+    /// reverb gets one, faded in from silence. Spatial sources feed it by their
+    /// `reverb_send` amount.
+    pub fn set_environment_with_transition(&self, environment: Environment, transition: Duration) {
+        *self.environment.lock().unwrap() = Some(environment);
+        *self.pending_reverb.lock().unwrap() = Some((environment.reverb_settings(), transition));
+    }
+
+    /// Returns the environment preset last set, if any.
+    pub fn environment(&self) -> Option<Environment> {
+        *self.environment.lock().unwrap()
+    }
+
+    /// Takes the reverb settings and fade time set since the last call, unless they are
+    /// being modified (used by the render thread, which must not block)
+    pub(crate) fn take_pending_reverb(&self) -> Option<(ReverbSettings, Duration)> {
+        self.pending_reverb.try_lock().ok()?.take()
+    }
+
+    /// Puts back reverb settings taken with [`Self::take_pending_reverb`] without applying
+    /// them, unless newer ones were set meanwhile
+    pub(crate) fn restore_pending_reverb(&self, pending: (ReverbSettings, Duration)) {
+        self.pending_reverb.lock().unwrap().get_or_insert(pending);
+    }
+
+    /// Enables an envelope follower on a source.
+    ///
+    /// The follower tracks the level of the source's signal on the render thread; poll it
+    /// with [`Self::envelope`]. Enabling it again replaces the response times. See
+    /// [`crate::envelope`].
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_id` - SourceId of the audio source
+    /// * `config` - Attack and release times of the follower
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audio source ID is not found in the world storage
+    /// or if the command fails to send to the audio engine.
+    pub fn enable_envelope(&self, audio_id: SourceId, config: EnvelopeConfig) -> Result<()> {
+        self.ensure_source(audio_id)?;
+
+        let mut envelopes = self.envelopes.lock().unwrap();
+        self.send_envelope_command(audio_id, Some(config))?;
+        envelopes
+            .entry(audio_id)
+            .and_modify(|(existing, _)| *existing = config)
+            .or_insert_with(|| (config, Arc::new(SharedEnvelope::default())));
+        Ok(())
+    }
+
+    /// Disables the envelope follower of a source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to send to the audio engine.
+    pub fn disable_envelope(&self, audio_id: SourceId) -> Result<()> {
+        let mut envelopes = self.envelopes.lock().unwrap();
+        if !envelopes.contains_key(&audio_id) {
+            return Ok(());
+        }
+        self.send_envelope_command(audio_id, None)?;
+        envelopes.remove(&audio_id);
+        Ok(())
+    }
+
+    /// Returns the current envelope level of a source.
+    ///
+    /// Returns 0.0 if no envelope follower is enabled for the source or it is not playing.
+    pub fn envelope(&self, audio_id: SourceId) -> f32 {
+        self.envelopes
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .map_or(0.0, |(_, shared)| shared.level())
+    }
+
+    /// Silence detector for a newly started source, if detection is enabled
+    pub(crate) fn silence_detector(&self) -> Option<SilenceDetector> {
+        self.desc
+            .silence_detection
+            .map(|config| SilenceDetector::new(config, self.desc.sample_rate))
+    }
+
+    /// Render-side caption state for a newly started source, if it has a caption
+    pub(crate) fn caption_track(&self, audio_id: SourceId) -> Option<CaptionTrack> {
+        self.captions
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .map(|caption| CaptionTrack::new(caption.clone()))
+    }
+
+    /// Render-side reader of a newly started source's haptics track, if it has one
+    pub(crate) fn haptics_cursor(&self, audio_id: SourceId) -> Option<HapticsCursor> {
+        self.haptics_tracks
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .map(|track| HapticsCursor::new(track.clone()))
+    }
+
+    /// True if the world has a reverb bus that sources send to
+    pub(crate) fn has_reverb(&self) -> bool {
+        self.desc.reverb.is_some()
+    }
+
+    pub(crate) fn distance_delay(&self) -> Option<DistanceDelay> {
+        self.desc
+            .distance_delay
+            .then(|| DistanceDelay::new(self.desc.sample_rate, self.desc.max_distance_delay))
+    }
+
+    /// Creates the render-side delay line of a source given an alignment delay
+    pub(crate) fn source_delay_line(&self, audio_id: SourceId) -> Option<SourceDelay> {
+        match self.source_delay_frames(audio_id) {
+            0 => None,
+            frames => Some(SourceDelay::new(self.max_source_delay_frames(), frames)),
+        }
+    }
+
+    /// Creates the render-side follower for a source, if enabled
+    pub(crate) fn envelope_follower(&self, audio_id: SourceId) -> Option<EnvelopeFollower> {
+        self.envelopes
+            .lock()
+            .unwrap()
+            .get(&audio_id)
+            .map(|(config, shared)| {
+                EnvelopeFollower::new(shared.clone(), config, self.desc.sample_rate)
+            })
+    }
+
+    fn send_envelope_command(
+        &self,
+        audio_id: SourceId,
+        config: Option<EnvelopeConfig>,
+    ) -> Result<()> {
+        self.commands
+            .send(PlaybackCommand::SetEnvelopeFollower(audio_id, config))
+    }
+
+    /// Returns true while an engine renders this world.
+    pub fn has_engine(&self) -> bool {
+        self.engine_attached.load(Ordering::Acquire)
+    }
+
+    /// Claims the world for an engine.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PetalSonicError::Engine` if another engine is attached.
+    pub(crate) fn attach_engine(&self) -> Result<()> {
+        self.engine_attached
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| {
+                crate::error::PetalSonicError::Engine(
+                    "World is already rendered by another engine".to_string(),
+                )
+            })
+    }
+
+    /// Releases the world claimed by [`Self::attach_engine`].
+    pub(crate) fn detach_engine(&self) {
+        self.spatial_info.lock().unwrap().clear();
+        self.playing_sources.lock().unwrap().clear();
+        self.engine_attached.store(false, Ordering::Release);
+    }
+
+    /// Returns the fill state of the queue carrying playback commands to the render
+    /// thread. See [`crate::command_queue`].
+    pub fn command_queue_stats(&self) -> CommandQueueStats {
+        self.commands.stats()
+    }
+
+    /// The consumer side of the command queue, for the render thread; `None` while it is
+    /// held elsewhere
+    pub(crate) fn try_command_consumer(
+        &self,
+    ) -> Option<std::sync::MutexGuard<'_, HeapCons<PlaybackCommand>>> {
+        self.commands.try_consumer()
+    }
+}
+
+fn source_not_found(id: SourceId) -> crate::error::PetalSonicError {
+    crate::error::PetalSonicError::Engine(format!("Audio data with ID {:?} not found", id))
 }
 
 /// Represents a 3D audio source in the world.
@@ -0,0 +1,248 @@
+//! Attenuation zones: listener-position dependent gain and low-pass filtering.
+//!
+//! A zone is a sphere or box in the world together with a set of sources it affects.
+//! While the listener is inside the zone, the affected sources are attenuated and/or
+//! low-pass filtered, e.g. to muffle the exterior ambience when the player enters a
+//! building:
+//!
+//! ```ignore
+//! let building = AttenuationZone {
+//!     shape: ZoneShape::cuboid(Vec3::new(10.0, 2.0, 0.0), Vec3::new(5.0, 2.0, 8.0)),
+//!     fade_distance: 1.0,
+//!     gain: 0.5,
+//!     lowpass_hz: Some(800.0),
+//!     sources: vec![rain_id, wind_id],
+//! };
+//! let zone_id = world.add_attenuation_zone(building);
+//! ```
+//!
+//! Zones are evaluated on the render thread once per block from the current listener pose.
+//! Within `fade_distance` outside the zone the effect is faded in, so walking through a
+//! door does not switch it abruptly. When several zones affect a source, their gains
+//! multiply and the lowest cutoff wins.
+
+use crate::math::{CoordinateConvention, Pose, Quat, Vec3};
+use crate::world::SourceId;
+use std::collections::HashMap;
+
+/// Highest cutoff of the zone low-pass; an open filter is bypassed
+const OPEN_CUTOFF_HZ: f32 = 20_000.0;
+
+/// Identifier of an attenuation zone in a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZoneId(pub(crate) u64);
+
+/// Volume of an attenuation zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneShape {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// Box with the given half extents along its local axes, oriented by `rotation`
+    Box {
+        center: Vec3,
+        half_extents: Vec3,
+        rotation: Quat,
+    },
+}
+
+impl ZoneShape {
+    /// Axis-aligned box
+    pub fn cuboid(center: Vec3, half_extents: Vec3) -> Self {
+        Self::Box {
+            center,
+            half_extents,
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    /// Distance from `point` to the zone, zero inside it
+    pub fn distance(&self, point: Vec3) -> f32 {
+        match *self {
+            Self::Sphere { center, radius } => (point.distance(center) - radius).max(0.0),
+            Self::Box {
+                center,
+                half_extents,
+                rotation,
+            } => {
+                let local = rotation.inverse() * (point - center);
+                (local.abs() - half_extents).max(Vec3::ZERO).length()
+            }
+        }
+    }
+
+    /// Convert the shape from `convention` to the native one
+    pub(crate) fn converted_to_native(self, convention: CoordinateConvention) -> Self {
+        match self {
+            Self::Sphere { center, radius } => Self::Sphere {
+                center: convention.vec_to_native(center),
+                radius,
+            },
+            // The axes only change order and sign, so the extents are permuted
+            Self::Box {
+                center,
+                half_extents,
+                rotation,
+            } => Self::Box {
+                center: convention.vec_to_native(center),
+                half_extents: convention.vec_to_native(half_extents).abs(),
+                rotation: convention.quat_to_native(rotation),
+            },
+        }
+    }
+
+    /// Convert the shape from the native convention to `convention`
+    pub(crate) fn converted_from_native(self, convention: CoordinateConvention) -> Self {
+        match self {
+            Self::Sphere { center, radius } => Self::Sphere {
+                center: convention.vec_from_native(center),
+                radius,
+            },
+            Self::Box {
+                center,
+                half_extents,
+                rotation,
+            } => Self::Box {
+                center: convention.vec_from_native(center),
+                half_extents: convention.vec_from_native(half_extents).abs(),
+                rotation: convention.quat_from_native(rotation),
+            },
+        }
+    }
+}
+
+/// A zone that attenuates and filters a set of sources while the listener is inside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttenuationZone {
+    pub shape: ZoneShape,
+    /// Distance outside the zone over which its effect fades in (0 switches it on at the
+    /// boundary)
+    pub fade_distance: f32,
+    /// Linear gain applied to the sources while the listener is inside
+    pub gain: f32,
+    /// Low-pass cutoff in Hz applied while the listener is inside, `None` for no filtering
+    pub lowpass_hz: Option<f32>,
+    /// Sources affected by the zone
+    pub sources: Vec<SourceId>,
+}
+
+impl AttenuationZone {
+    /// How much of the zone's effect applies to a listener at `position` (0..=1)
+    pub fn weight(&self, position: Vec3) -> f32 {
+        let distance = self.shape.distance(position);
+        if distance <= 0.0 {
+            1.0
+        } else if self.fade_distance <= 0.0 {
+            0.0
+        } else {
+            (1.0 - distance / self.fade_distance).max(0.0)
+        }
+    }
+}
+
+/// Combined zone effect on one source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ZoneParams {
+    pub gain: f32,
+    pub cutoff_hz: f32,
+}
+
+impl Default for ZoneParams {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            cutoff_hz: OPEN_CUTOFF_HZ,
+        }
+    }
+}
+
+/// Render-side evaluation of the world's zones for the current listener pose, passed
+/// through the mixer
+#[derive(Debug, Default)]
+pub struct ZoneEvaluator {
+    params: HashMap<SourceId, ZoneParams>,
+}
+
+impl ZoneEvaluator {
+    /// Recompute the per-source parameters of all zones for `listener`
+    pub(crate) fn update<'a>(
+        &mut self,
+        zones: impl IntoIterator<Item = &'a AttenuationZone>,
+        listener: &Pose,
+    ) {
+        self.params.clear();
+        for zone in zones {
+            let weight = zone.weight(listener.position);
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let gain = 1.0 + (zone.gain - 1.0) * weight;
+            // Interpolate the cutoff on a log scale so the fade sounds even
+            let cutoff_hz = zone.lowpass_hz.map_or(OPEN_CUTOFF_HZ, |cutoff| {
+                let cutoff = cutoff.clamp(1.0, OPEN_CUTOFF_HZ);
+                OPEN_CUTOFF_HZ * (cutoff / OPEN_CUTOFF_HZ).powf(weight)
+            });
+
+            for source_id in &zone.sources {
+                let params = self.params.entry(*source_id).or_default();
+                params.gain *= gain;
+                params.cutoff_hz = params.cutoff_hz.min(cutoff_hz);
+            }
+        }
+    }
+
+    /// Zone parameters of a source (neutral if no zone affects it)
+    pub(crate) fn params(&self, source_id: SourceId) -> ZoneParams {
+        self.params.get(&source_id).copied().unwrap_or_default()
+    }
+}
+
+/// Per-source gain ramp and one-pole low-pass applying the zone parameters
+#[derive(Debug, Clone)]
+pub(crate) struct ZoneFilter {
+    target: ZoneParams,
+    gain: f32,
+    lowpass_state: f32,
+    sample_rate: f32,
+}
+
+impl ZoneFilter {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            target: ZoneParams::default(),
+            gain: 1.0,
+            lowpass_state: 0.0,
+            sample_rate: sample_rate as f32,
+        }
+    }
+
+    pub fn set_target(&mut self, target: ZoneParams) {
+        self.target = target;
+    }
+
+    /// Apply the filter to a block of mono samples; the gain is ramped over the block
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let filtering = self.target.cutoff_hz < OPEN_CUTOFF_HZ;
+        if !filtering && self.target.gain == 1.0 && self.gain == 1.0 {
+            self.lowpass_state = samples.last().copied().unwrap_or(self.lowpass_state);
+            return;
+        }
+
+        let coefficient =
+            1.0 - (-2.0 * std::f32::consts::PI * self.target.cutoff_hz / self.sample_rate).exp();
+        let gain_step = (self.target.gain - self.gain) / samples.len().max(1) as f32;
+        for sample in samples.iter_mut() {
+            self.gain += gain_step;
+            if filtering {
+                self.lowpass_state += (*sample - self.lowpass_state) * coefficient;
+                *sample = self.lowpass_state * self.gain;
+            } else {
+                self.lowpass_state = *sample;
+                *sample *= self.gain;
+            }
+        }
+        self.gain = self.target.gain;
+    }
+}
@@ -0,0 +1,36 @@
+// `audibility` falls off with distance the way the renderer attenuates sources.
+
+use petalsonic::math::Vec3;
+use petalsonic::{PetalSonicWorld, PetalSonicWorldDesc};
+
+#[test]
+fn audibility_falls_off_monotonically_with_distance() {
+    let world = PetalSonicWorld::new(PetalSonicWorldDesc::default()).unwrap();
+    let listener = Vec3::new(1.0, 0.5, -2.0);
+    let direction = Vec3::new(0.6, 0.0, 0.8);
+
+    // Up to a kilometer (100 world units), in 1 m steps
+    let levels: Vec<f32> = (0..=1000)
+        .map(|step| world.audibility(listener + direction * (step as f32 * 0.1), listener))
+        .collect();
+
+    assert!(
+        (levels[0] - 1.0).abs() < 1e-6,
+        "audibility at the source {}",
+        levels[0]
+    );
+    for (step, pair) in levels.windows(2).enumerate() {
+        assert!(
+            pair[1] <= pair[0],
+            "audibility rises from {} to {} at step {}",
+            pair[0],
+            pair[1],
+            step
+        );
+    }
+    assert!(levels[1000] < 0.01, "audibility at 1 km {}", levels[1000]);
+
+    // Only the distance counts, not the direction
+    let other_side = world.audibility(listener - direction * 5.0, listener);
+    assert!((other_side - levels[50]).abs() < 1e-6);
+}
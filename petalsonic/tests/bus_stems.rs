@@ -0,0 +1,50 @@
+// A bus stem holds the sum of the sources on the bus, and nothing else.
+
+mod common;
+
+use common::{constant_clip, one_second, world_and_engine};
+use petalsonic::SourceConfig;
+use petalsonic::playback::LoopMode;
+use petalsonic::stems::StemTarget;
+
+const BLOCK_SIZE: usize = 512;
+const BLOCKS: usize = 4;
+
+/// Samples of a 32-bit float WAV file written by the stem recorder
+fn read_stem(path: &std::path::Path) -> Vec<f32> {
+    let bytes = std::fs::read(path).unwrap();
+    bytes[44..]
+        .chunks_exact(4)
+        .map(|sample| f32::from_le_bytes(sample.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn bus_stem_sums_the_sources_on_the_bus() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    for (level, bus) in [(0.1, Some("music")), (0.2, Some("music")), (0.4, None)] {
+        let source_id = world
+            .register_audio(
+                constant_clip(level, one_second()),
+                SourceConfig::non_spatial(),
+            )
+            .unwrap();
+        world.set_source_bus(source_id, bus).unwrap();
+        world.play(source_id, LoopMode::Infinite).unwrap();
+    }
+
+    let dir = std::env::temp_dir().join(format!("petalsonic-bus-stems-{}", std::process::id()));
+    engine
+        .record_stems(&[StemTarget::Bus("music".to_string())], &dir)
+        .unwrap();
+    let mix = engine.render_offline(BLOCKS * BLOCK_SIZE).unwrap();
+    engine.stop_stem_recording().unwrap();
+
+    let bus = read_stem(&dir.join("bus_music.wav"));
+    let master = read_stem(&dir.join("master.wav"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(bus.len(), mix.len());
+    assert!(bus.iter().all(|sample| (sample - 0.3).abs() < 1e-4));
+    assert!(master.iter().all(|sample| (sample - 0.7).abs() < 1e-4));
+}
@@ -0,0 +1,42 @@
+// A full command queue rejects a call as a whole: the commands of a play are queued all or
+// none, and a rejected setter leaves the world's state as it was.
+
+mod common;
+
+use common::constant_clip;
+use petalsonic::playback::LoopMode;
+use petalsonic::{PetalSonicWorld, PetalSonicWorldDesc, SourceConfig};
+use std::time::Duration;
+
+#[test]
+fn rejected_calls_queue_nothing_and_keep_the_world_state() {
+    // No engine renders the world, so nothing drains the queue
+    let desc = PetalSonicWorldDesc {
+        command_queue_capacity: 3,
+        ..Default::default()
+    };
+    let world = PetalSonicWorld::new(desc).unwrap();
+    let source_id = world
+        .register_audio(constant_clip(0.5, 1024), SourceConfig::non_spatial())
+        .unwrap();
+
+    world.solo(source_id, true).unwrap();
+    assert_eq!(world.command_queue_stats().pending, 1);
+
+    // Play, re-solo and play limit need three slots, two are left
+    assert!(
+        world
+            .play_for(source_id, Duration::from_millis(10), LoopMode::Once)
+            .is_err()
+    );
+    let stats = world.command_queue_stats();
+    assert_eq!(stats.pending, 1);
+    assert_eq!(stats.rejected, 3);
+
+    // Play and re-solo fit
+    world.play(source_id, LoopMode::Once).unwrap();
+    assert_eq!(world.command_queue_stats().pending, 3);
+
+    assert!(world.solo(source_id, false).is_err());
+    assert!(world.is_soloed(source_id));
+}
@@ -0,0 +1,35 @@
+// Fixture shared by the integration tests: a world with an engine rendering it offline, and
+// constant clips to play in it. Each test crate uses only part of it.
+#![allow(dead_code)]
+
+use petalsonic::audio_data::PetalSonicAudioData;
+use petalsonic::{PetalSonicEngine, PetalSonicWorld, PetalSonicWorldDesc};
+use std::sync::Arc;
+
+/// A world of the default description with `block_size`, and an engine for it
+pub fn world_and_engine(block_size: usize) -> (Arc<PetalSonicWorld>, PetalSonicEngine) {
+    world_and_engine_with(PetalSonicWorldDesc {
+        block_size,
+        ..Default::default()
+    })
+}
+
+/// A world of `desc` and an engine for it
+pub fn world_and_engine_with(
+    desc: PetalSonicWorldDesc,
+) -> (Arc<PetalSonicWorld>, PetalSonicEngine) {
+    let world = Arc::new(PetalSonicWorld::new(desc.clone()).unwrap());
+    let engine = PetalSonicEngine::new(desc, world.clone()).unwrap();
+    (world, engine)
+}
+
+/// A mono clip of `frames` frames at `level`, at the default world sample rate
+pub fn constant_clip(level: f32, frames: usize) -> Arc<PetalSonicAudioData> {
+    let sample_rate = PetalSonicWorldDesc::default().sample_rate;
+    PetalSonicAudioData::from_samples(vec![level; frames], sample_rate, 1).unwrap()
+}
+
+/// Frames of one second at the default world sample rate
+pub fn one_second() -> usize {
+    PetalSonicWorldDesc::default().sample_rate as usize
+}
@@ -0,0 +1,60 @@
+// Turning the listener around swaps which ear hears a source. Runs on the stereo panner
+// fallback when Steam Audio is not built in.
+
+mod common;
+
+use common::{constant_clip, one_second, world_and_engine};
+use petalsonic::SourceConfig;
+use petalsonic::math::{Pose, Quat, Vec3};
+use petalsonic::playback::LoopMode;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Left and right energy of a constant source one meter to the left of the origin (0.1
+/// world units), heard by a listener with the given rotation
+fn channel_energy(rotation: Quat) -> (f32, f32) {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    world.set_listener_pose(Pose::from_rotation(rotation));
+
+    let source_id = world
+        .register_audio(
+            constant_clip(0.5, one_second()),
+            SourceConfig::spatial(Vec3::new(-0.1, 0.0, 0.0)),
+        )
+        .unwrap();
+    world.play(source_id, LoopMode::Infinite).unwrap();
+
+    let samples = engine.render_offline(4 * BLOCK_SIZE).unwrap();
+    let energy = |channel: usize| {
+        samples
+            .iter()
+            .skip(channel)
+            .step_by(2)
+            .map(|sample| sample * sample)
+            .sum::<f32>()
+    };
+    (energy(0), energy(1))
+}
+
+#[test]
+fn turning_the_listener_around_swaps_left_and_right() {
+    let (facing_left, facing_right) = channel_energy(Quat::IDENTITY);
+    assert!(
+        facing_left > 10.0 * facing_right,
+        "facing forward: left {} right {}",
+        facing_left,
+        facing_right
+    );
+
+    let (turned_left, turned_right) = channel_energy(Quat::from_rotation_y(std::f32::consts::PI));
+    assert!(
+        turned_right > 10.0 * turned_left,
+        "turned around: left {} right {}",
+        turned_left,
+        turned_right
+    );
+
+    // Only the side changes, not the level
+    let total = facing_left + facing_right;
+    assert!((turned_left + turned_right - total).abs() < 0.05 * total);
+}
@@ -0,0 +1,61 @@
+// Calibration of the BS.1770 loudness meter: a 1 kHz sine at -23 dBFS (RMS) on the centre
+// channel reads -23 LUFS, and gating keeps silence out of the integrated loudness.
+
+use petalsonic::LoudnessMeter;
+use std::f64::consts::PI;
+
+const SAMPLE_RATE: u32 = 48000;
+/// 5.1 layout: L, R, C, LFE, Ls, Rs
+const CHANNELS: usize = 6;
+const CENTRE: usize = 2;
+
+/// Interleaved 5.1 audio with a 1 kHz sine of `rms_dbfs` on the centre channel, or silence
+fn centre_sine(seconds: usize, rms_dbfs: Option<f64>) -> Vec<f32> {
+    let frames = seconds * SAMPLE_RATE as usize;
+    let amplitude = rms_dbfs.map_or(0.0, |level| 2f64.sqrt() * 10f64.powf(level / 20.0));
+    let mut samples = vec![0.0f32; frames * CHANNELS];
+    for (i, frame) in samples.chunks_exact_mut(CHANNELS).enumerate() {
+        let phase = 2.0 * PI * 1000.0 * i as f64 / SAMPLE_RATE as f64;
+        frame[CENTRE] = (amplitude * phase.sin()) as f32;
+    }
+    samples
+}
+
+#[test]
+fn sine_at_minus_23_dbfs_reads_minus_23_lufs() {
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, CHANNELS as u16);
+    meter.process(&centre_sine(10, Some(-23.0)));
+
+    let reading = meter.reading();
+    for (name, lufs) in [
+        ("momentary", reading.momentary_lufs),
+        ("short-term", reading.short_term_lufs),
+        ("integrated", reading.integrated_lufs),
+    ] {
+        assert!(
+            (lufs + 23.0).abs() <= 0.1,
+            "{} loudness {} LUFS",
+            name,
+            lufs
+        );
+    }
+}
+
+#[test]
+fn gating_ignores_silence() {
+    // Long enough that the few blocks straddling the edges of the tone, which the gates
+    // rightly keep, do not move the result
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, CHANNELS as u16);
+    meter.process(&centre_sine(10, None));
+    meter.process(&centre_sine(60, Some(-23.0)));
+    meter.process(&centre_sine(30, None));
+
+    let reading = meter.reading();
+    assert!(
+        (reading.integrated_lufs + 23.0).abs() <= 0.1,
+        "integrated loudness {} LUFS",
+        reading.integrated_lufs
+    );
+    // The ungated short-term loudness does hear the trailing silence
+    assert_eq!(reading.short_term_lufs, f32::NEG_INFINITY);
+}
@@ -0,0 +1,49 @@
+// Evicting to stay within the memory budget skips sources that are still playing, however
+// long ago they were started.
+
+mod common;
+
+use common::{constant_clip, one_second, world_and_engine_with};
+use petalsonic::playback::LoopMode;
+use petalsonic::{MemoryBudget, PetalSonicWorldDesc, SourceConfig};
+
+const BLOCK_SIZE: usize = 512;
+
+#[test]
+fn eviction_skips_playing_sources() {
+    let clip = || constant_clip(0.5, one_second());
+    let clip_bytes = clip().memory_size();
+    let (world, mut engine) = world_and_engine_with(PetalSonicWorldDesc {
+        block_size: BLOCK_SIZE,
+        // Room for two clips
+        memory_budget: Some(MemoryBudget::evicting(2 * clip_bytes)),
+        ..Default::default()
+    });
+
+    // A music bed started first, then a one-shot registered after it
+    let music = world
+        .register_audio(clip(), SourceConfig::non_spatial())
+        .unwrap();
+    world.play(music, LoopMode::Infinite).unwrap();
+    engine.render_offline(BLOCK_SIZE).unwrap();
+    let one_shot = world
+        .register_audio(clip(), SourceConfig::non_spatial())
+        .unwrap();
+
+    // The music bed is the least recently used, but it is playing
+    let next = world
+        .register_audio(clip(), SourceConfig::non_spatial())
+        .unwrap();
+    assert_eq!(world.take_evicted_sources(), vec![one_shot]);
+    assert!(world.contains_audio(music));
+    assert_eq!(world.memory_stats().total_bytes, 2 * clip_bytes);
+
+    // Once stopped, it can be evicted
+    world.stop(music).unwrap();
+    engine.render_offline(BLOCK_SIZE).unwrap();
+    world
+        .register_audio(clip(), SourceConfig::non_spatial())
+        .unwrap();
+    assert_eq!(world.take_evicted_sources(), vec![music]);
+    assert!(world.contains_audio(next));
+}
@@ -0,0 +1,52 @@
+// A stereo world played on a mono device is down-mixed instead of rejected, so a source
+// panned hard to either side stays audible.
+
+mod common;
+
+use common::{constant_clip, one_second, world_and_engine_with};
+use petalsonic::math::{Pose, Vec3};
+use petalsonic::playback::LoopMode;
+use petalsonic::{ChannelMixMatrix, PetalSonicWorldDesc, SourceConfig};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Render a constant source at `position` through the output stage of a mono device,
+/// with the mix the engine picks when a stereo world is played on one channel
+fn render_mono(position: Vec3) -> Vec<f32> {
+    let (world, mut engine) = world_and_engine_with(PetalSonicWorldDesc {
+        block_size: BLOCK_SIZE,
+        output_mix: Some(ChannelMixMatrix::default_for(2, 1)),
+        ..Default::default()
+    });
+    world.set_listener_pose(Pose::default());
+
+    let source_id = world
+        .register_audio(
+            constant_clip(0.5, one_second()),
+            SourceConfig::spatial(position),
+        )
+        .unwrap();
+    world.play(source_id, LoopMode::Infinite).unwrap();
+
+    let mono = engine.render_offline(4 * BLOCK_SIZE).unwrap();
+    assert_eq!(mono.len(), 4 * BLOCK_SIZE);
+    mono
+}
+
+#[test]
+fn hard_panned_sources_are_heard_on_a_mono_device() {
+    // One meter to either side (0.1 world units), so the far channel is silent
+    for position in [Vec3::new(-0.1, 0.0, 0.0), Vec3::new(0.1, 0.0, 0.0)] {
+        let mono = render_mono(position);
+        let energy: f32 = mono[BLOCK_SIZE..]
+            .iter()
+            .map(|sample| sample * sample)
+            .sum();
+        assert!(
+            energy > 10.0,
+            "source at {:?} has energy {}",
+            position,
+            energy
+        );
+    }
+}
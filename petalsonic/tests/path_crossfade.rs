@@ -0,0 +1,78 @@
+// A source switching between the spatial and non-spatial path is crossfaded, so the
+// rendered output has no discontinuity at the switch.
+
+mod common;
+
+use common::world_and_engine;
+use petalsonic::SourceConfig;
+use petalsonic::audio_data::PetalSonicAudioData;
+use petalsonic::math::{Pose, Vec3};
+use petalsonic::playback::LoopMode;
+use std::f32::consts::PI;
+
+const BLOCK_SIZE: usize = 512;
+const FREQUENCY: f32 = 250.0;
+const AMPLITUDE: f32 = 0.5;
+
+/// Largest difference between consecutive samples of a channel of interleaved stereo
+fn max_sample_delta(samples: &[f32], channel: usize) -> f32 {
+    samples
+        .iter()
+        .skip(channel)
+        .step_by(2)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn switching_paths_renders_without_discontinuity() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    world.set_listener_pose(Pose::default());
+    let sample_rate = world.sample_rate();
+
+    // One second of a sine with a whole number of periods, so the loop has no seam
+    let sine: Vec<f32> = (0..sample_rate)
+        .map(|i| AMPLITUDE * (2.0 * PI * FREQUENCY * i as f32 / sample_rate as f32).sin())
+        .collect();
+    let clip = PetalSonicAudioData::from_samples(sine, sample_rate, 1).unwrap();
+    // One meter to the left, so the right channel is silent on the spatial path only
+    let spatial = SourceConfig::spatial(Vec3::new(-0.1, 0.0, 0.0));
+    let source_id = world.register_audio(clip, spatial.clone()).unwrap();
+    world.play(source_id, LoopMode::Infinite).unwrap();
+
+    let mut samples = engine.render_offline(2 * BLOCK_SIZE).unwrap();
+    world
+        .update_source_config(source_id, SourceConfig::non_spatial())
+        .unwrap();
+    samples.extend(engine.render_offline(3 * BLOCK_SIZE).unwrap());
+    world.update_source_config(source_id, spatial).unwrap();
+    samples.extend(engine.render_offline(3 * BLOCK_SIZE).unwrap());
+
+    // A sine changes by at most 2πf/fs times its amplitude per sample; allow for the
+    // level change spread over the crossfade, but not for a jump between the paths
+    let sine_slope = AMPLITUDE * 2.0 * PI * FREQUENCY / sample_rate as f32;
+    for channel in 0..2 {
+        let delta = max_sample_delta(&samples, channel);
+        assert!(
+            delta < 2.0 * sine_slope,
+            "channel {} jumps by {} (sine slope {})",
+            channel,
+            delta,
+            sine_slope
+        );
+    }
+
+    // The switch happened: hard left on the spatial path, on both channels off it
+    let right_level = |block: usize| {
+        samples[block * BLOCK_SIZE * 2..(block + 1) * BLOCK_SIZE * 2]
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .fold(0.0f32, |max, sample| max.max(sample.abs()))
+    };
+    assert!(right_level(1) < 0.01);
+    assert!(right_level(3) > 0.4);
+    assert!(right_level(6) < 0.01);
+}
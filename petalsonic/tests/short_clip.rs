@@ -0,0 +1,81 @@
+// Clips shorter than one render block must play completely once, then fall silent, on both
+// the non-spatial and the spatial path.
+
+mod common;
+
+use common::{constant_clip, world_and_engine};
+use petalsonic::math::{Pose, Vec3};
+use petalsonic::playback::LoopMode;
+use petalsonic::{PetalSonicEvent, SourceConfig};
+
+const BLOCK_SIZE: usize = 1024;
+const CLIP_FRAMES: usize = 100;
+
+/// Render three blocks of a short constant clip played once
+fn render_short_clip(config: SourceConfig) -> (Vec<f32>, Vec<PetalSonicEvent>) {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    world.set_listener_pose(Pose::default());
+
+    let source_id = world
+        .register_audio(constant_clip(0.5, CLIP_FRAMES), config)
+        .unwrap();
+    world.play(source_id, LoopMode::Once).unwrap();
+
+    let samples = engine.render_offline(3 * BLOCK_SIZE).unwrap();
+    (samples, engine.poll_events())
+}
+
+/// Index of the last frame carrying signal, if any
+fn last_audible_frame(samples: &[f32]) -> Option<usize> {
+    samples
+        .chunks_exact(2)
+        .rposition(|frame| frame.iter().any(|sample| sample.abs() > 1e-6))
+}
+
+fn completions(events: &[PetalSonicEvent]) -> usize {
+    events
+        .iter()
+        .filter(|event| matches!(event, PetalSonicEvent::SourceCompleted { .. }))
+        .count()
+}
+
+#[test]
+fn non_spatial_clip_shorter_than_a_block_plays_once() {
+    let (samples, events) = render_short_clip(SourceConfig::non_spatial());
+    assert_eq!(samples.len(), 3 * BLOCK_SIZE * 2);
+
+    // Every frame of the clip is heard; the declick fade only shapes its last frames
+    let audible = samples[..CLIP_FRAMES * 2]
+        .iter()
+        .filter(|sample| sample.abs() > 1e-6)
+        .count();
+    assert_eq!(audible, CLIP_FRAMES * 2);
+    assert!(
+        samples[..2]
+            .iter()
+            .all(|sample| (sample - 0.5).abs() < 0.05)
+    );
+
+    assert_eq!(last_audible_frame(&samples), Some(CLIP_FRAMES - 1));
+    assert_eq!(completions(&events), 1);
+}
+
+#[test]
+fn spatial_clip_shorter_than_a_block_plays_once() {
+    let (samples, events) = render_short_clip(SourceConfig::spatial(Vec3::new(0.1, 0.0, 0.0)));
+    assert_eq!(samples.len(), 3 * BLOCK_SIZE * 2);
+
+    // One meter to the right (0.1 world units), so the clip is not attenuated. It is heard,
+    // delayed at most by the interaural delay of the panner
+    let last = last_audible_frame(&samples).expect("the clip was not heard");
+    assert!(last >= CLIP_FRAMES - 1);
+    assert!(
+        last < CLIP_FRAMES + 64,
+        "the clip rang on until frame {}",
+        last
+    );
+    let energy: f32 = samples.iter().map(|sample| sample * sample).sum();
+    assert!(energy > 10.0, "energy {}", energy);
+
+    assert_eq!(completions(&events), 1);
+}
@@ -0,0 +1,37 @@
+// A source soloed before it is played mutes the other sources once playback starts.
+
+mod common;
+
+use common::{constant_clip, one_second, world_and_engine};
+use petalsonic::SourceConfig;
+use petalsonic::playback::LoopMode;
+
+const BLOCK_SIZE: usize = 512;
+
+#[test]
+fn solo_set_before_play_mutes_other_sources() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    let soloed = world
+        .register_audio(
+            constant_clip(0.5, one_second()),
+            SourceConfig::non_spatial(),
+        )
+        .unwrap();
+    let other = world
+        .register_audio(
+            constant_clip(0.25, one_second()),
+            SourceConfig::non_spatial(),
+        )
+        .unwrap();
+
+    world.solo(soloed, true).unwrap();
+    world.play(soloed, LoopMode::Infinite).unwrap();
+    world.play(other, LoopMode::Infinite).unwrap();
+
+    // Only the soloed source is heard, from the first block on
+    let samples = engine.render_offline(2 * BLOCK_SIZE).unwrap();
+    assert!(
+        samples.iter().all(|sample| (sample - 0.5).abs() < 1e-3),
+        "the unsoloed source was heard"
+    );
+}
@@ -0,0 +1,74 @@
+// `SourceReady` is delivered in order with the render thread's events, and `is_ready`
+// follows the data actually stored for the source.
+
+mod common;
+
+use common::{constant_clip, world_and_engine, world_and_engine_with};
+use petalsonic::audio_data::PetalSonicAudioData;
+use petalsonic::config::ResamplePolicy;
+use petalsonic::playback::LoopMode;
+use petalsonic::{PetalSonicEvent, PetalSonicWorldDesc, SourceConfig};
+
+const BLOCK_SIZE: usize = 512;
+
+#[test]
+fn source_ready_follows_earlier_events() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+
+    let first = world
+        .register_audio(constant_clip(0.5, 100), SourceConfig::non_spatial())
+        .unwrap();
+    world.play(first, LoopMode::Once).unwrap();
+    engine.render_offline(2 * BLOCK_SIZE).unwrap();
+    let second = world
+        .register_audio(constant_clip(0.5, 100), SourceConfig::non_spatial())
+        .unwrap();
+
+    let events = engine.poll_events();
+    let position = |wanted: fn(&PetalSonicEvent) -> bool| {
+        events.iter().position(wanted).expect("missing event")
+    };
+    let first_ready = position(|event| matches!(event, PetalSonicEvent::SourceReady { .. }));
+    let completed = position(|event| matches!(event, PetalSonicEvent::SourceCompleted { .. }));
+    let second_ready = events
+        .iter()
+        .rposition(|event| matches!(event, PetalSonicEvent::SourceReady { .. }))
+        .unwrap();
+    assert!(
+        matches!(events[first_ready], PetalSonicEvent::SourceReady { source_id } if source_id == first)
+    );
+    assert!(
+        matches!(events[second_ready], PetalSonicEvent::SourceReady { source_id } if source_id == second)
+    );
+    assert!(first_ready < completed && completed < second_ready);
+}
+
+#[test]
+fn deferred_source_is_ready_once_converted() {
+    let (world, engine) = world_and_engine_with(PetalSonicWorldDesc {
+        block_size: BLOCK_SIZE,
+        resample_policy: ResamplePolicy::OnFirstPlay,
+        ..Default::default()
+    });
+
+    let clip = PetalSonicAudioData::from_samples(vec![0.5; 4410], 44100, 1).unwrap();
+    let source_id = world
+        .register_audio(clip, SourceConfig::non_spatial())
+        .unwrap();
+    assert_ne!(world.sample_rate(), 44100);
+    let ready_sources = |events: Vec<PetalSonicEvent>| -> Vec<_> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                PetalSonicEvent::SourceReady { source_id } => Some(source_id),
+                _ => None,
+            })
+            .collect()
+    };
+    assert!(!world.is_ready(source_id));
+    assert!(ready_sources(engine.poll_events()).is_empty());
+
+    world.play(source_id, LoopMode::Once).unwrap();
+    assert!(world.is_ready(source_id));
+    assert_eq!(ready_sources(engine.poll_events()), vec![source_id]);
+}
@@ -0,0 +1,142 @@
+// MOD parsing rejects malformed modules without panicking, and a module source renders its
+// song block by block for as long as it plays.
+#![cfg(feature = "tracker")]
+
+mod common;
+
+use common::world_and_engine;
+use petalsonic::playback::LoopMode;
+use petalsonic::{PetalSonicEvent, SourceConfig, TrackerModule};
+
+const BLOCK_SIZE: usize = 512;
+/// Offset of the first pattern, after the header and signature
+const PATTERNS_OFFSET: usize = 1084;
+/// Bytes of a 4-channel pattern
+const PATTERN_SIZE: usize = 64 * 4 * 4;
+/// Period of C-2
+const C2: u16 = 428;
+
+/// A 4-channel module of one pattern whose first row plays a looping square wave
+fn module_bytes() -> Vec<u8> {
+    let sample: Vec<u8> = (0..64)
+        .map(|i| if i < 32 { 64i8 as u8 } else { -64i8 as u8 })
+        .collect();
+
+    let mut bytes = vec![0u8; PATTERNS_OFFSET];
+    bytes[..4].copy_from_slice(b"test");
+    // Sample 1: length, volume and loop length in words, looping over all of it
+    let header = 20;
+    let words = (sample.len() as u16 / 2).to_be_bytes();
+    bytes[header + 22..header + 24].copy_from_slice(&words);
+    bytes[header + 25] = 64;
+    bytes[header + 28..header + 30].copy_from_slice(&words);
+    // Song of one position playing pattern 0
+    bytes[950] = 1;
+    bytes[1080..1084].copy_from_slice(b"M.K.");
+
+    let mut pattern = vec![0u8; PATTERN_SIZE];
+    pattern[..4].copy_from_slice(&[(C2 >> 8) as u8, C2 as u8, 0x10, 0x00]);
+    bytes.extend(pattern);
+    bytes.extend(sample);
+    bytes
+}
+
+#[test]
+fn parses_a_valid_module() {
+    let mut bytes = module_bytes();
+    let module = TrackerModule::parse(&bytes).unwrap();
+    assert_eq!(module.title(), Some("test"));
+    assert_eq!(module.channels(), 4);
+
+    // Sample data cut short is played as far as it goes
+    bytes.truncate(bytes.len() - 10);
+    assert!(TrackerModule::parse(&bytes).is_ok());
+}
+
+#[test]
+fn rejects_malformed_modules() {
+    let bytes = module_bytes();
+
+    // Shorter than the header
+    assert!(TrackerModule::parse(&[]).is_err());
+    assert!(TrackerModule::parse(&bytes[..PATTERNS_OFFSET - 1]).is_err());
+
+    // Pattern data cut short
+    assert!(TrackerModule::parse(&bytes[..PATTERNS_OFFSET + PATTERN_SIZE - 1]).is_err());
+
+    // Orders referring to a pattern the file does not hold
+    let mut missing_pattern = bytes.clone();
+    missing_pattern[952] = 5;
+    assert!(TrackerModule::parse(&missing_pattern).is_err());
+
+    // Unknown signatures, including XM modules
+    let mut unknown = bytes.clone();
+    unknown[1080..1084].copy_from_slice(b"ABCD");
+    assert!(TrackerModule::parse(&unknown).is_err());
+    let mut xm = bytes.clone();
+    xm[..17].copy_from_slice(b"Extended Module: ");
+    xm[1080..1084].fill(0);
+    assert!(TrackerModule::parse(&xm).is_err());
+}
+
+#[test]
+fn garbage_modules_render_without_panicking() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+
+    // Pseudo-random headers, patterns and samples behind a valid signature
+    let mut state = 0x2545_f491_u32;
+    for _ in 0..8 {
+        let mut bytes: Vec<u8> = (0..PATTERNS_OFFSET + 130 * PATTERN_SIZE + 4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        bytes[1080..1084].copy_from_slice(b"M.K.");
+        // Orders within the patterns the file holds
+        bytes[952..1080].iter_mut().for_each(|order| *order &= 0x7F);
+        let module = TrackerModule::parse(&bytes).unwrap();
+
+        let source_id = world
+            .register_tracker_module(&module, SourceConfig::non_spatial())
+            .unwrap();
+        world.play(source_id, LoopMode::Once).unwrap();
+        let samples = engine.render_offline(8 * BLOCK_SIZE).unwrap();
+        assert!(samples.iter().all(|sample| sample.is_finite()));
+        world.stop(source_id).unwrap();
+    }
+}
+
+#[test]
+fn module_source_plays_on_past_the_end_of_its_song() {
+    let (world, mut engine) = world_and_engine(BLOCK_SIZE);
+    let sample_rate = world.sample_rate();
+
+    let module = TrackerModule::parse(&module_bytes()).unwrap();
+    let source_id = world
+        .register_tracker_module(&module, SourceConfig::non_spatial())
+        .unwrap();
+    world.play(source_id, LoopMode::Once).unwrap();
+
+    // One pattern of 64 rows at speed 6 and 125 BPM lasts 7.68 s; render past it
+    let frames = 8 * sample_rate as usize;
+    let samples = engine.render_offline(frames).unwrap();
+    let peak = |seconds: std::ops::Range<f32>| {
+        let frame = |time: f32| (time * sample_rate as f32) as usize * 2;
+        samples[frame(seconds.start)..frame(seconds.end)]
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+    };
+    // The square wave on one of four channels, at full volume
+    assert!((peak(0.0..1.0) - 0.125).abs() < 0.01, "{}", peak(0.0..1.0));
+    // The song starts over and the note is triggered again
+    assert!(peak(7.7..8.0) > 0.1);
+
+    let completed = engine
+        .poll_events()
+        .into_iter()
+        .any(|event| matches!(event, PetalSonicEvent::SourceCompleted { .. }));
+    assert!(!completed);
+}